@@ -0,0 +1,41 @@
+//! Timestamp parsing over a merged log whose lines alternate between layouts.
+//!
+//! Run with `cargo bench --bench timestamps`.
+
+use lumolog::timeindex::{TimestampParser, parse_timestamp};
+use std::hint::black_box;
+use std::time::Instant;
+
+const LINES: usize = 200_000;
+
+fn main() {
+    let layouts = [
+        "2024-01-15T08:30:01.123Z",
+        "1705307400",
+        "Jan 15 08:30:00",
+        "2024-01-15 08:30:01,123",
+        "0115 08:30:00.000000",
+    ];
+    let input: Vec<&str> = (0..LINES).map(|i| layouts[i % layouts.len()]).collect();
+
+    let start = Instant::now();
+    for raw in &input {
+        black_box(parse_timestamp(black_box(raw)));
+    }
+    report("no remembered layouts", start);
+
+    let start = Instant::now();
+    let mut parser = TimestampParser::default();
+    for raw in &input {
+        black_box(parser.parse(black_box(raw)));
+    }
+    report("per-source parser", start);
+}
+
+fn report(name: &str, start: Instant) {
+    let elapsed = start.elapsed();
+    println!(
+        "{name:<24} {LINES} lines in {elapsed:>10.2?} ({:.0} ns/line)",
+        elapsed.as_nanos() as f64 / LINES as f64
+    );
+}
@@ -0,0 +1,69 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+
+/// SGR parameters for a foreground (`base` 30) or background (`base` 40)
+/// color, or None for the terminal default.
+fn color_code(color: Color, base: u8) -> Option<String> {
+    let named = |n: u8| Some((base + n).to_string());
+    let bright = |n: u8| Some((base + 60 + n).to_string());
+    match color {
+        Color::Reset => None,
+        Color::Black => named(0),
+        Color::Red => named(1),
+        Color::Green => named(2),
+        Color::Yellow => named(3),
+        Color::Blue => named(4),
+        Color::Magenta => named(5),
+        Color::Cyan => named(6),
+        Color::Gray => named(7),
+        Color::DarkGray => bright(0),
+        Color::LightRed => bright(1),
+        Color::LightGreen => bright(2),
+        Color::LightYellow => bright(3),
+        Color::LightBlue => bright(4),
+        Color::LightMagenta => bright(5),
+        Color::LightCyan => bright(6),
+        Color::White => bright(7),
+        Color::Indexed(i) => Some(format!("{};5;{i}", base + 8)),
+        Color::Rgb(r, g, b) => Some(format!("{};2;{r};{g};{b}", base + 8)),
+    }
+}
+
+fn sgr(style: Style) -> String {
+    let mut codes = Vec::new();
+    for (modifier, code) in [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+    ] {
+        if style.add_modifier.contains(modifier) {
+            codes.push(code.to_string());
+        }
+    }
+    codes.extend(style.fg.and_then(|c| color_code(c, 30)));
+    codes.extend(style.bg.and_then(|c| color_code(c, 40)));
+    if codes.is_empty() {
+        String::new()
+    } else {
+        format!("\x1b[{}m", codes.join(";"))
+    }
+}
+
+/// A highlighted line as text with ANSI color escapes, for printing when
+/// there is no terminal to run the viewer in. Unstyled spans are left bare.
+pub fn line_to_ansi(line: &Line<'_>) -> String {
+    let mut out = String::new();
+    for span in &line.spans {
+        let start = sgr(line.style.patch(span.style));
+        if start.is_empty() {
+            out.push_str(&span.content);
+        } else {
+            out.push_str(&start);
+            out.push_str(&span.content);
+            out.push_str("\x1b[0m");
+        }
+    }
+    out
+}
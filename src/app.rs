@@ -1,12 +1,68 @@
+use crate::bundle::{RuleBundle, Rules};
 use crate::command;
-use crate::filter::filter_lines;
-use crate::highlighter::TokenKind;
-use crate::parser::{LogFormat, LogLevel, ParsedLine, detect_format, parse_line};
+use crate::config::{
+    BlankLines, EscAction, FuzzyConfig, PaneLayout, PivotUrl, ScrollConfig, SplitCommand,
+    YankConfig,
+};
+use crate::decode::{Decoded, decode_token};
+use crate::derived::{self, DerivedField};
+use crate::fields::{
+    FieldLookup, NumericSummary, TOP_VALUES, ValueDistribution, numeric_summary, sort_by_quantity,
+    value_distribution,
+};
+use crate::filter::{
+    CaseMode, FilterWarning, FuzzyMatcher, REGEX_PREFIX, TextMatch, filter_lines_matching,
+    is_context_free,
+};
+use crate::highlighter::{LevelBadge, TokenKind, expanded_line_count};
+use crate::linestats::{LengthStats, TOP_LONGEST, length_stats};
+use crate::markers::{MarkerRule, builtin_rules, match_rule};
+use crate::noise::NoisePreview;
+use crate::parser::{
+    LogFormat, LogLevel, ParsedLine, continues_entry, detect_format, is_blank, is_java_frame,
+    parse_error, parse_line,
+};
+use crate::provenance::{Provenance, is_control_line};
+use crate::savedfilters::{SavedFilter, SavedFilters};
+use crate::storyline::{StoryRow, build_storyline, group_thousands, render_storyline};
 use crate::timeindex::{
-    SparklineData, TimeIndex, TimeModeState, TimeRange, bucket_range_to_time_range,
-    build_time_index, compute_sparkline, filter_by_time_range,
+    SparklineData, TimeIndex, TimeModeState, TimeRange, bucket_level_counts,
+    bucket_range_to_time_range, build_time_index, build_time_index_as_of, compute_sparkline,
+    filter_by_time_range, format_sparkline_time, is_multi_day,
 };
-use std::collections::BTreeSet;
+use crate::traces::{TraceRow, build_trace_rows};
+use crate::units::parse_quantity;
+use crate::viewstate::{ExitState, ViewState};
+use ratatui::style::Color;
+use regex::{Regex, RegexSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Starting 1-in-N rate for sampling mode.
+const DEFAULT_SAMPLE_EVERY: usize = 10;
+
+/// Filtered sets bigger than this many bytes are yanked as a file path
+/// instead of as text: clipboards choke on hundreds of megabytes.
+pub const YANK_INLINE_LIMIT: usize = 1 << 20;
+
+/// Most lines the pinned pane holds; pinning another drops the oldest.
+pub const MAX_PINS: usize = 5;
+
+/// Context line counts `c` cycles through, as in `grep -C`.
+pub const CONTEXT_STEPS: [usize; 3] = [0, 2, 5];
+
+/// File written by "Export pinned lines" in the current directory.
+pub const PINS_FILE: &str = "lumolog-pins.txt";
+
+/// File written by "Export view as text" in the current directory.
+pub const VIEW_TEXT_FILE: &str = "lumolog-view.txt";
+
+/// A 1-based line number paired with the raw line text.
+pub type NumberedLine<'a> = (usize, &'a str);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppMode {
@@ -18,21 +74,48 @@ pub enum AppMode {
     TimeRange,
     Ask,
     Analyze,
+    /// Typing a note for the cursor line.
+    Annotate,
+    /// Typing the name to save the current filter under.
+    SaveFilter,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MenuAction {
     FilterByValue,
     OpenInBrowser,
     LookupAbuseIPDB,
+    /// Profile-defined URL template; `{}` is replaced by the token.
+    Pivot {
+        name: String,
+        url: String,
+    },
+    /// Configured shell command run in a new tmux / WezTerm pane.
+    Split {
+        name: String,
+        command: String,
+    },
+    /// Top values of a `key=` token's key across the filtered lines.
+    ValueDistribution,
+    /// Sort the filtered lines by a `key=` token's key, largest first.
+    SortByField,
+    /// Show what a JWT or base64 token holds.
+    Decode,
+    /// Hide every line with the same template as original line `usize`.
+    MuteTemplate(usize),
 }
 
 impl MenuAction {
-    pub fn label(self) -> &'static str {
+    pub fn label(&self) -> &str {
         match self {
             MenuAction::FilterByValue => "Filter by this value",
             MenuAction::OpenInBrowser => "Open in browser",
             MenuAction::LookupAbuseIPDB => "Lookup on AbuseIPDB",
+            MenuAction::ValueDistribution => "Show value distribution",
+            MenuAction::SortByField => "Sort by this field (largest first)",
+            MenuAction::Decode => "Decode",
+            MenuAction::MuteTemplate(_) => "Mute lines like this",
+            MenuAction::Pivot { name, .. } | MenuAction::Split { name, .. } => name,
         }
     }
 }
@@ -46,28 +129,232 @@ pub struct ContextMenuState {
     pub position: (u16, u16),
 }
 
+/// Order of the filtered lines other than their natural (file) order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Oldest first; lines without a timestamp last.
+    Timestamp,
+    /// Most severe first; lines without a level last.
+    Level,
+    /// Largest value of the field first, with durations and sizes compared
+    /// across units; lines without a numeric value last.
+    Field(String),
+}
+
+impl SortOrder {
+    /// Status bar text: `time ↑`, `level ↓`, `duration_ms ↓`.
+    pub fn label(&self) -> String {
+        match self {
+            SortOrder::Timestamp => "time ↑".to_string(),
+            SortOrder::Level => "level ↓".to_string(),
+            SortOrder::Field(key) => format!("{key} ↓"),
+        }
+    }
+}
+
+/// One constraint narrowing the view, shown as a removable chip in the
+/// breadcrumb row above the status bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breadcrumb {
+    /// A stacked text filter, by position in the stack.
+    Term(usize),
+    /// The filter in the filter bar.
+    Pattern,
+    Level,
+    /// Levels unchecked in the level picker.
+    HiddenLevels,
+    TimeRange,
+    Similar,
+    Noise,
+    Malformed,
+    Sort,
+    Sample,
+}
+
+/// Value distribution popup: the counts plus the highlighted row.
+pub struct ValueDistState {
+    pub dist: ValueDistribution,
+    pub selected: usize,
+    /// Percentiles when the key's values are numbers, durations or sizes.
+    pub summary: Option<NumericSummary>,
+    /// Values listed smallest first instead of most common first.
+    pub by_value: bool,
+}
+
+/// Decoded token popup.
+pub struct DecodedState {
+    pub decoded: Decoded,
+    /// Rows scrolled past the top.
+    pub scroll: usize,
+}
+
+/// One row of the malformed-lines panel: lines that failed to parse for
+/// the same reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedReason {
+    pub reason: String,
+    pub count: usize,
+    pub first_line: usize,
+}
+
 pub struct App {
     parsed_lines: Vec<ParsedLine>,
     format: LogFormat,
+    /// Fields from the last `#lumolog:` control line, for lines appended later.
+    provenance: Provenance,
     scroll_offset: usize,
     viewport_height: usize,
+    scroll_config: ScrollConfig,
+    esc_action: EscAction,
+    filter_fuzzy: FuzzyConfig,
+    /// Case and whole-word matching of plain-text filters, switched with
+    /// Alt-C and Alt-W in the filter bar.
+    text_match: TextMatch,
+    palette_fuzzy: FuzzyConfig,
     quit: bool,
+    confirm_quit: bool,
+    /// Waiting for the user to confirm a quit.
+    quit_pending: bool,
     mode: AppMode,
     filter_pattern: String,
+    /// Filters stacked with `/` before the one being edited; all must match.
+    filter_terms: Vec<String>,
+    /// Characters of the filter before the edit cursor.
+    filter_cursor: usize,
     filtered_indices: Vec<usize>,
     is_fuzzy: bool,
+    /// Why the `re:` filter pattern doesn't compile.
+    filter_error: Option<String>,
+    /// About the filters as last applied, e.g. that they keep every line.
+    filter_warning: Option<FilterWarning>,
+    /// Text filters only highlight their matches; lines that don't match
+    /// stay in view. Switched with Alt-H in the filter bar.
+    highlight_only: bool,
+    /// While `highlight_only`, how many lines in view the text filters match.
+    highlighted_matches: Option<usize>,
+    /// While `highlight_only`, original indices of the lines the text
+    /// filters match, in order.
+    highlight_hits: Vec<usize>,
+    /// Lines that arrived in follow mode but the filters hid, since they
+    /// last changed.
+    unmatched_arrivals: usize,
+    /// Lines shown around each match of the text filters.
+    context_lines: usize,
+    /// Original indices of the lines in view only as context, in order.
+    context_only: Vec<usize>,
+    /// View positions of the matches, in order, while highlighting only or
+    /// showing context; empty otherwise, when every line in view matches.
+    match_positions: Vec<usize>,
+    /// Original index of the line `n`/`N` last jumped to.
+    last_match: Option<usize>,
     json_pretty: bool,
+    /// Fold continuation lines (stack frames, wrapped messages) into the
+    /// entry above them.
+    join_lines: bool,
+    /// (first, last) original indices of every entry with continuation
+    /// lines, in order: `first` is the entry's own line.
+    entries: Vec<(usize, usize)>,
+    /// Lines (original indices) whose pretty state is flipped relative to the global toggle.
+    pretty_toggled: HashSet<usize>,
     source_name: String,
+    /// Absolute path of the file being viewed, recorded in exported view state.
+    source_path: Option<PathBuf>,
+    /// `[yank] location`: what "Yank location" copies.
+    location_template: String,
+    /// How many rotated files (`app.log.1`, ...) were read in ahead of it.
+    rotated_files: usize,
+    /// `R` was pressed; the event loop reads the rotated files.
+    rotated_requested: bool,
     follow_mode: bool,
     follow_paused: bool,
     min_level: Option<LogLevel>,
     available_levels: Vec<LogLevel>,
+    /// Levels unchecked in the level picker, on top of `min_level`; sorted.
+    hidden_levels: Vec<LogLevel>,
+    /// Selected row of the level picker, while it's open.
+    level_picker: Option<usize>,
     context_menu: Option<ContextMenuState>,
+    value_dist: Option<ValueDistState>,
+    decoded: Option<DecodedState>,
     wrap: bool,
+    /// Show tabs, carriage returns and trailing spaces.
+    show_whitespace: bool,
+    blank_lines: BlankLines,
+    /// Recolor the screen for high contrast.
+    high_contrast: bool,
+    /// Say in text what colors say: level tags, gutter symbols.
+    text_cues: bool,
+    /// How the compact view shows levels.
+    level_badge: LevelBadge,
+    /// Pinned pane and stats bar; the sparkline has `sparkline_visible`.
+    pane_layout: PaneLayout,
     h_scroll: usize,
     cursor_position: usize,
     yank_flash: u8,
+    background_jobs: usize,
+    macro_recording: Option<char>,
+    ignore_patterns: Option<RegexSet>,
+    highlight_rules: Vec<(Regex, Color)>,
+    pivots: Vec<PivotUrl>,
+    /// Where the ignore, highlight and pivot rules came from, for export.
+    rules: RuleBundle,
+    splits: Vec<SplitCommand>,
+    marker_rules: Vec<MarkerRule>,
+    derived_fields: Vec<DerivedField>,
+    /// (original index, rule index) for every line a marker rule matched, in line order.
+    markers: Vec<(usize, usize)>,
+    /// Selected row while the event list panel is open.
+    event_list_selected: Option<usize>,
+    /// Free-text notes keyed by original line index.
+    annotations: BTreeMap<usize, String>,
+    /// Line being annotated and the note typed so far, while in Annotate mode.
+    annotation_input: Option<(usize, String)>,
+    annotation_list_selected: Option<usize>,
+    /// Annotations changed since they were last exported.
+    annotations_dirty: bool,
+    /// Name typed so far while in SaveFilter mode.
+    save_filter_input: String,
+    /// Filters listed by the saved filters panel, as last read from disk.
+    saved_filters: SavedFilters,
+    saved_filters_selected: Option<usize>,
+    /// Trace → span → line rows, built when the trace view opens.
+    trace_rows: Vec<TraceRow>,
+    trace_view_selected: Option<usize>,
+    /// Level runs and error lines of the filtered view, built when the
+    /// storyline opens.
+    story_rows: Vec<StoryRow>,
+    storyline_selected: Option<usize>,
+    job_error: Option<String>,
+    /// Informational message for the status bar, e.g. where a yank went.
+    notice: Option<String>,
+    mark: Option<usize>,
+    /// Original indices of pinned lines, oldest first.
+    pins: Vec<usize>,
+    diff_pair: Option<(usize, usize)>,
+    /// Line shown in the hex dump popup, and rows scrolled past its top.
+    hex_dump: Option<(usize, usize)>,
     similar_template: Option<String>,
+    /// (line, reason) for every line the format's parser couldn't read, in
+    /// line order. Worked out on first use, then kept up to date.
+    malformed: Option<Vec<(usize, String)>>,
+    /// Show only lines in `malformed`.
+    malformed_only: bool,
+    /// Malformed-lines panel: reasons, most common first, and the selected row.
+    malformed_panel: Option<(Vec<MalformedReason>, usize)>,
+    /// Line length panel and the selected row of its longest lines.
+    line_stats: Option<(LengthStats, usize)>,
+    /// Templates hidden by "Collapse noise" or muted one at a time.
+    hidden_templates: HashSet<String>,
+    /// Lines with a template in `hidden_templates`.
+    muted_lines: usize,
+    /// What collapsing noise would hide, while the preview is open.
+    noise_preview: Option<NoisePreview>,
+    /// Sampling mode: show every Nth matching line, plus all warn+ lines.
+    sampling: bool,
+    sample_every: usize,
+    /// Lines the filters matched before sampling thinned them out.
+    unsampled_count: usize,
+    sort: Option<SortOrder>,
     palette_input: String,
     palette_selected: usize,
     palette_filtered: Vec<usize>,
@@ -87,11 +374,26 @@ pub struct App {
 }
 
 impl App {
+    #[allow(dead_code)]
     pub fn new(lines: Vec<String>) -> Self {
-        let format = detect_format(&lines);
-        let parsed_lines: Vec<ParsedLine> =
-            lines.iter().map(|line| parse_line(line, format)).collect();
-        let filtered_indices = (0..parsed_lines.len()).collect();
+        Self::with_format(lines, None)
+    }
+
+    /// Like [`App::new`], but parse as `format` instead of auto-detecting.
+    pub fn with_format(lines: Vec<String>, format: Option<LogFormat>) -> Self {
+        let format = format.unwrap_or_else(|| detect_format(&lines));
+        let mut provenance = Provenance::default();
+        let parsed_lines: Vec<ParsedLine> = lines
+            .iter()
+            .map(|line| {
+                let mut parsed = parse_line(line, format);
+                provenance.apply(&mut parsed);
+                parsed
+            })
+            .collect();
+        let filtered_indices = (0..parsed_lines.len())
+            .filter(|&i| !is_control_line(&parsed_lines[i].raw))
+            .collect();
 
         let available_levels: Vec<LogLevel> = {
             let set: BTreeSet<LogLevel> = parsed_lines.iter().filter_map(|l| l.level).collect();
@@ -108,31 +410,107 @@ impl App {
         };
         let sparkline_visible = time_index.is_some();
 
-        Self {
+        let mut app = Self {
             parsed_lines,
             format,
+            provenance,
             scroll_offset: 0,
             viewport_height: 24,
+            scroll_config: ScrollConfig::default(),
+            esc_action: EscAction::default(),
+            filter_fuzzy: FuzzyConfig::default(),
+            text_match: TextMatch::default(),
+            palette_fuzzy: FuzzyConfig::default(),
             quit: false,
+            confirm_quit: false,
+            quit_pending: false,
             mode: AppMode::Normal,
             filter_pattern: String::new(),
+            filter_terms: Vec::new(),
+            filter_cursor: 0,
             filtered_indices,
             is_fuzzy: false,
+            filter_error: None,
+            filter_warning: None,
+            highlight_only: false,
+            highlighted_matches: None,
+            highlight_hits: Vec::new(),
+            unmatched_arrivals: 0,
+            context_lines: 0,
+            context_only: Vec::new(),
+            match_positions: Vec::new(),
+            last_match: None,
             json_pretty: false,
+            join_lines: true,
+            entries: Vec::new(),
+            pretty_toggled: HashSet::new(),
             source_name: String::from("stdin"),
+            source_path: None,
+            location_template: YankConfig::default().location,
+            rotated_files: 0,
+            rotated_requested: false,
             follow_mode: false,
             follow_paused: false,
             min_level: None,
             available_levels,
+            hidden_levels: Vec::new(),
+            level_picker: None,
             context_menu: None,
+            value_dist: None,
+            decoded: None,
             wrap: false,
+            show_whitespace: false,
+            blank_lines: BlankLines::default(),
+            high_contrast: false,
+            text_cues: false,
+            level_badge: LevelBadge::default(),
+            pane_layout: PaneLayout::default(),
             h_scroll: 0,
             cursor_position: 0,
             yank_flash: 0,
+            background_jobs: 0,
+            macro_recording: None,
+            ignore_patterns: None,
+            highlight_rules: Vec::new(),
+            pivots: Vec::new(),
+            rules: RuleBundle::default(),
+            splits: Vec::new(),
+            marker_rules: builtin_rules(),
+            derived_fields: Vec::new(),
+            markers: Vec::new(),
+            event_list_selected: None,
+            annotations: BTreeMap::new(),
+            annotation_input: None,
+            annotation_list_selected: None,
+            annotations_dirty: false,
+            save_filter_input: String::new(),
+            saved_filters: SavedFilters::default(),
+            saved_filters_selected: None,
+            trace_rows: Vec::new(),
+            trace_view_selected: None,
+            story_rows: Vec::new(),
+            storyline_selected: None,
+            job_error: None,
+            notice: None,
+            mark: None,
+            pins: Vec::new(),
+            diff_pair: None,
+            hex_dump: None,
             similar_template: None,
+            malformed: None,
+            malformed_only: false,
+            malformed_panel: None,
+            line_stats: None,
+            hidden_templates: HashSet::new(),
+            muted_lines: 0,
+            noise_preview: None,
+            sampling: false,
+            sort: None,
+            sample_every: DEFAULT_SAMPLE_EVERY,
+            unsampled_count: 0,
             palette_input: String::new(),
             palette_selected: 0,
-            palette_filtered: (0..command::commands().len()).collect(),
+            palette_filtered: command::grouped_order(),
             time_index,
             sparkline_data: None,
             time_range: None,
@@ -146,7 +524,12 @@ impl App {
             analyze_input: String::new(),
             analyze_response: None,
             analyze_scroll: 0,
-        }
+        };
+        app.rescan_markers();
+        app.extend_entries(0);
+        let indices = std::mem::take(&mut app.filtered_indices);
+        app.filtered_indices = app.fold_continuations(indices);
+        app
     }
 
     pub fn total_lines(&self) -> usize {
@@ -189,6 +572,430 @@ impl App {
         self.scroll_up(step);
     }
 
+    pub fn set_scroll_config(&mut self, config: ScrollConfig) {
+        self.scroll_config = config;
+    }
+
+    /// Matcher settings for the filter's fuzzy fallback and the command palette.
+    pub fn set_fuzzy_config(&mut self, filter: FuzzyConfig, palette: FuzzyConfig) {
+        self.filter_fuzzy = filter;
+        self.palette_fuzzy = palette;
+    }
+
+    /// Hide lines matching any of these patterns (profile `ignore`).
+    pub fn set_ignore_patterns(&mut self, patterns: RegexSet) {
+        self.ignore_patterns = (!patterns.is_empty()).then_some(patterns);
+        self.recompute_filter();
+    }
+
+    /// Extra (pattern, color) highlights from the active profile.
+    pub fn set_highlight_rules(&mut self, rules: Vec<(Regex, Color)>) {
+        self.highlight_rules = rules;
+    }
+
+    pub fn highlight_rules(&self) -> &[(Regex, Color)] {
+        &self.highlight_rules
+    }
+
+    /// Profile URL templates offered in the token context menu.
+    pub fn set_pivots(&mut self, pivots: Vec<PivotUrl>) {
+        self.pivots = pivots;
+    }
+
+    /// Use these ignore, highlight and pivot rules in place of the current
+    /// ones. The bundle's filters aren't applied.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.set_highlight_rules(rules.highlights);
+        self.set_pivots(rules.bundle.pivot.clone());
+        self.rules = rules.bundle;
+        self.set_ignore_patterns(rules.ignore);
+    }
+
+    /// The rules in use and the text filters, for sharing as a bundle.
+    pub fn rule_bundle(&self) -> RuleBundle {
+        RuleBundle {
+            filters: self
+                .active_filter_terms()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            ..self.rules.clone()
+        }
+    }
+
+    /// Add a bundle's rules to the ones in use, and replace the text filters
+    /// with its filters when it has any.
+    pub fn import_rules(&mut self, bundle: &RuleBundle) -> anyhow::Result<()> {
+        let mut merged = self.rules.clone();
+        merged.merge(bundle);
+        self.set_rules(merged.compile()?);
+        if !bundle.filters.is_empty() {
+            self.set_filter_stack(&bundle.filters);
+        }
+        Ok(())
+    }
+
+    /// Commands offered as "open in a split" in the token context menu. Only
+    /// set these when a multiplexer is available to run them.
+    pub fn set_split_commands(&mut self, splits: Vec<SplitCommand>) {
+        self.splits = splits;
+    }
+
+    /// Compute the config's derived fields on every line, now and for lines
+    /// appended later.
+    pub fn set_derived_fields(&mut self, fields: Vec<DerivedField>) {
+        self.derived_fields = fields;
+        if self.derived_fields.is_empty() {
+            return;
+        }
+        for line in &mut self.parsed_lines {
+            derived::apply(&self.derived_fields, line);
+        }
+        self.recompute_filter();
+    }
+
+    /// Add user-defined marker rules. They take precedence over the built-in ones.
+    pub fn set_marker_rules(&mut self, rules: Vec<MarkerRule>) {
+        self.marker_rules = rules;
+        self.marker_rules.extend(builtin_rules());
+        self.rescan_markers();
+        self.event_list_selected = None;
+    }
+
+    fn rescan_markers(&mut self) {
+        self.markers = self
+            .parsed_lines
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, line)| match_rule(&self.marker_rules, line).map(|r| (idx, r)))
+            .collect();
+    }
+
+    /// Marker rule that matched original line `idx`, if any.
+    pub fn marker_at(&self, idx: usize) -> Option<&MarkerRule> {
+        let pos = self.markers.binary_search_by_key(&idx, |&(i, _)| i).ok()?;
+        Some(&self.marker_rules[self.markers[pos].1])
+    }
+
+    /// Every marked line as (original index, rule), in line order.
+    pub fn markers(&self) -> impl Iterator<Item = (usize, &MarkerRule)> {
+        self.markers
+            .iter()
+            .map(|&(idx, rule)| (idx, &self.marker_rules[rule]))
+    }
+
+    // Event list panel methods
+
+    pub fn open_event_list(&mut self) {
+        if self.markers.is_empty() {
+            return;
+        }
+        // Start at the first event at or below the current position
+        let here = self
+            .cursor_line_index()
+            .filter(|_| self.is_cursor_mode())
+            .or_else(|| self.filtered_indices.get(self.scroll_offset).copied())
+            .unwrap_or(0);
+        let pos = self.markers.partition_point(|&(idx, _)| idx < here);
+        self.event_list_selected = Some(pos.min(self.markers.len() - 1));
+    }
+
+    pub fn close_event_list(&mut self) {
+        self.event_list_selected = None;
+    }
+
+    pub fn event_list_selected(&self) -> Option<usize> {
+        self.event_list_selected
+    }
+
+    pub fn event_list_down(&mut self) {
+        if let Some(sel) = self.event_list_selected.as_mut()
+            && *sel + 1 < self.markers.len()
+        {
+            *sel += 1;
+        }
+    }
+
+    pub fn event_list_up(&mut self) {
+        if let Some(sel) = self.event_list_selected.as_mut() {
+            *sel = sel.saturating_sub(1);
+        }
+    }
+
+    /// Close the panel and jump to the selected event's line.
+    pub fn event_list_jump(&mut self) {
+        if let Some(sel) = self.event_list_selected.take()
+            && let Some(&(idx, _)) = self.markers.get(sel)
+        {
+            self.goto_line(idx + 1);
+        }
+    }
+
+    // Annotation methods
+
+    /// Start typing a note for the cursor line, pre-filled with its existing note.
+    pub fn enter_annotate_mode(&mut self) {
+        if !self.is_cursor_mode() {
+            return;
+        }
+        let Some(idx) = self.cursor_line_index() else {
+            return;
+        };
+        let text = self.annotations.get(&idx).cloned().unwrap_or_default();
+        self.annotation_input = Some((idx, text));
+        self.mode = AppMode::Annotate;
+    }
+
+    pub fn annotation_input(&self) -> &str {
+        self.annotation_input
+            .as_ref()
+            .map_or("", |(_, t)| t.as_str())
+    }
+
+    pub fn annotate_type(&mut self, c: char) {
+        if let Some((_, text)) = self.annotation_input.as_mut() {
+            text.push(c);
+        }
+    }
+
+    pub fn annotate_backspace(&mut self) {
+        if let Some((_, text)) = self.annotation_input.as_mut() {
+            text.pop();
+        }
+    }
+
+    /// Save the note typed so far. An empty note removes the annotation.
+    pub fn annotate_submit(&mut self) {
+        if let Some((idx, text)) = self.annotation_input.take() {
+            let text = text.trim();
+            let changed = if text.is_empty() {
+                self.annotations.remove(&idx).is_some()
+            } else {
+                self.annotations.insert(idx, text.to_string()).as_deref() != Some(text)
+            };
+            self.annotations_dirty |= changed;
+        }
+        self.mode = AppMode::Cursor;
+    }
+
+    pub fn exit_annotate_mode(&mut self) {
+        self.annotation_input = None;
+        self.mode = AppMode::Cursor;
+    }
+
+    /// Note attached to original line `idx`, if any.
+    pub fn annotation_at(&self, idx: usize) -> Option<&str> {
+        self.annotations.get(&idx).map(String::as_str)
+    }
+
+    /// Every annotation as (original index, note), in line order.
+    pub fn annotations(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.annotations.iter().map(|(&idx, n)| (idx, n.as_str()))
+    }
+
+    /// All annotations as plain text, one block per note: the line number,
+    /// the line's timestamp when it has one, the note, then the raw line.
+    pub fn export_annotations(&mut self) -> String {
+        self.annotations_dirty = false;
+        let mut out = String::new();
+        for (&idx, note) in &self.annotations {
+            let Some(line) = self.parsed_lines.get(idx) else {
+                continue;
+            };
+            match &line.timestamp {
+                Some(ts) => out.push_str(&format!("line {} [{ts}]: {note}\n", idx + 1)),
+                None => out.push_str(&format!("line {}: {note}\n", idx + 1)),
+            }
+            out.push_str(&format!("    {}\n", line.raw));
+        }
+        out
+    }
+
+    pub fn open_annotation_list(&mut self) {
+        if self.annotations.is_empty() {
+            return;
+        }
+        self.annotation_list_selected = Some(0);
+    }
+
+    pub fn close_annotation_list(&mut self) {
+        self.annotation_list_selected = None;
+    }
+
+    pub fn annotation_list_selected(&self) -> Option<usize> {
+        self.annotation_list_selected
+    }
+
+    pub fn annotation_list_down(&mut self) {
+        if let Some(sel) = self.annotation_list_selected.as_mut()
+            && *sel + 1 < self.annotations.len()
+        {
+            *sel += 1;
+        }
+    }
+
+    pub fn annotation_list_up(&mut self) {
+        if let Some(sel) = self.annotation_list_selected.as_mut() {
+            *sel = sel.saturating_sub(1);
+        }
+    }
+
+    /// Close the panel and jump to the selected annotation's line.
+    pub fn annotation_list_jump(&mut self) {
+        if let Some(sel) = self.annotation_list_selected.take()
+            && let Some(&idx) = self.annotations.keys().nth(sel)
+        {
+            self.goto_line(idx + 1);
+        }
+    }
+
+    // Trace view methods
+
+    /// Group lines with trace/span ids into a tree. Starts on the row of the
+    /// current line when it belongs to a trace.
+    pub fn open_trace_view(&mut self) {
+        self.trace_rows = build_trace_rows(&self.parsed_lines, self.time_index.as_ref());
+        if self.trace_rows.is_empty() {
+            return;
+        }
+        let here = self
+            .cursor_line_index()
+            .filter(|_| self.is_cursor_mode())
+            .or_else(|| self.filtered_indices.get(self.scroll_offset).copied());
+        let pos = here
+            .and_then(|idx| {
+                self.trace_rows
+                    .iter()
+                    .position(|row| matches!(row, TraceRow::Line { idx: i, .. } if *i == idx))
+            })
+            .unwrap_or(0);
+        self.trace_view_selected = Some(pos);
+    }
+
+    pub fn close_trace_view(&mut self) {
+        self.trace_view_selected = None;
+    }
+
+    pub fn trace_view_selected(&self) -> Option<usize> {
+        self.trace_view_selected
+    }
+
+    pub fn trace_rows(&self) -> &[TraceRow] {
+        &self.trace_rows
+    }
+
+    pub fn trace_view_down(&mut self) {
+        if let Some(sel) = self.trace_view_selected.as_mut()
+            && *sel + 1 < self.trace_rows.len()
+        {
+            *sel += 1;
+        }
+    }
+
+    pub fn trace_view_up(&mut self) {
+        if let Some(sel) = self.trace_view_selected.as_mut() {
+            *sel = sel.saturating_sub(1);
+        }
+    }
+
+    /// Close the panel and jump to the selected row's line (a trace or span
+    /// jumps to its first line).
+    pub fn trace_view_jump(&mut self) {
+        if let Some(sel) = self.trace_view_selected.take()
+            && let Some(row) = self.trace_rows.get(sel)
+        {
+            self.goto_line(row.target_line() + 1);
+        }
+    }
+
+    // Storyline methods
+
+    /// Compress the filtered lines into level runs and error/fatal lines.
+    pub fn open_storyline(&mut self) {
+        self.story_rows = build_storyline(&self.parsed_lines, &self.filtered_indices);
+        if self.story_rows.is_empty() {
+            return;
+        }
+        self.storyline_selected = Some(0);
+    }
+
+    pub fn close_storyline(&mut self) {
+        self.storyline_selected = None;
+    }
+
+    pub fn storyline_selected(&self) -> Option<usize> {
+        self.storyline_selected
+    }
+
+    pub fn story_rows(&self) -> &[StoryRow] {
+        &self.story_rows
+    }
+
+    pub fn storyline_down(&mut self) {
+        if let Some(sel) = self.storyline_selected.as_mut()
+            && *sel + 1 < self.story_rows.len()
+        {
+            *sel += 1;
+        }
+    }
+
+    pub fn storyline_up(&mut self) {
+        if let Some(sel) = self.storyline_selected.as_mut() {
+            *sel = sel.saturating_sub(1);
+        }
+    }
+
+    /// Close the panel and jump to the selected step's (first) line.
+    pub fn storyline_jump(&mut self) {
+        if let Some(sel) = self.storyline_selected.take()
+            && let Some(row) = self.story_rows.get(sel)
+        {
+            self.goto_line(row.target_line() + 1);
+        }
+    }
+
+    /// The open storyline as text (empty when it isn't open).
+    pub fn export_storyline(&self) -> String {
+        if self.storyline_selected.is_none() {
+            return String::new();
+        }
+        render_storyline(&self.story_rows, &self.parsed_lines)
+    }
+
+    /// Lines moved per mouse wheel notch.
+    pub fn wheel_step(&self) -> usize {
+        self.scroll_config.wheel_step.max(1)
+    }
+
+    fn half_page_step(&self) -> usize {
+        match self.scroll_config.half_page {
+            0 => (self.viewport_entries_from(self.scroll_offset) / 2).max(1),
+            n => n,
+        }
+    }
+
+    /// Ctrl-D: move the cursor (in cursor mode) or the viewport down half a page.
+    pub fn half_page_down(&mut self) {
+        let step = self.half_page_step();
+        if self.is_cursor_mode() {
+            self.scroll_down(step);
+            self.cursor_down(step);
+        } else {
+            self.scroll_down(step);
+        }
+    }
+
+    /// Ctrl-U: move the cursor (in cursor mode) or the viewport up half a page.
+    pub fn half_page_up(&mut self) {
+        let step = self.half_page_step();
+        if self.is_cursor_mode() {
+            self.scroll_up(step);
+            self.cursor_up(step);
+        } else {
+            self.scroll_up(step);
+        }
+    }
+
     pub fn scroll_to_top(&mut self) {
         self.scroll_offset = 0;
     }
@@ -205,6 +1012,53 @@ impl App {
         self.quit
     }
 
+    pub fn set_confirm_quit(&mut self, confirm: bool) {
+        self.confirm_quit = confirm;
+    }
+
+    /// Quit from a key press: asks first when `confirm_quit` is set and
+    /// there's something that would be lost.
+    pub fn request_quit(&mut self) {
+        if self.confirm_quit && self.unsaved_work().is_some() {
+            self.quit_pending = true;
+        } else {
+            self.quit();
+        }
+    }
+
+    /// While a quit is waiting for confirmation, what would be lost.
+    pub fn quit_confirmation(&self) -> Option<String> {
+        if self.quit_pending {
+            self.unsaved_work()
+        } else {
+            None
+        }
+    }
+
+    pub fn answer_quit(&mut self, confirmed: bool) {
+        self.quit_pending = false;
+        if confirmed {
+            self.quit();
+        }
+    }
+
+    fn unsaved_work(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.annotations_dirty {
+            parts.push("unexported notes");
+        }
+        if self.mark.is_some() {
+            parts.push("a marked line");
+        }
+        if !self.pins.is_empty() {
+            parts.push("pinned lines");
+        }
+        if self.follow_mode {
+            parts.push("follow mode on");
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
     fn clamp_scroll(&mut self) {
         let entries_from_end = self.viewport_entries_from_end();
         let max = self.filtered_indices.len().saturating_sub(entries_from_end);
@@ -215,12 +1069,13 @@ impl App {
 
     /// How many display lines does the entry at `parsed_lines[idx]` produce?
     fn display_line_count(&self, idx: usize) -> usize {
-        if self.json_pretty
-            && let Some(ref pj) = self.parsed_lines[idx].pretty_json
-        {
-            return pj.lines().count() + 1; // header + JSON body lines
-        }
-        1
+        let pretty = self.is_line_pretty(idx);
+        let folded = if pretty {
+            self.continuation_lines(idx).len()
+        } else {
+            0
+        };
+        expanded_line_count(&self.parsed_lines[idx], pretty) + folded
     }
 
     /// Starting at filtered entry `start`, count how many entries fit in the viewport.
@@ -264,6 +1119,21 @@ impl App {
             .collect()
     }
 
+    /// Original line numbers (1-indexed) of the first and last lines in the
+    /// viewport, folded lines included, and the time of the top one.
+    pub fn viewport_position(&self) -> Option<(usize, usize, Option<chrono::NaiveDateTime>)> {
+        let start = self.scroll_offset;
+        let end = (start + self.visible_entry_count()).min(self.filtered_indices.len());
+        let visible = self.filtered_indices.get(start..end)?;
+        let (&first, &last) = (visible.first()?, visible.last()?);
+        let last = self.continuation_lines(last).end;
+        let time = self
+            .time_index
+            .as_ref()
+            .and_then(|index| index.timestamp_at(first));
+        Some((first + 1, last, time))
+    }
+
     /// How many filtered entries are visible in the current viewport.
     pub fn visible_entry_count(&self) -> usize {
         self.viewport_entries_from(self.scroll_offset)
@@ -277,28 +1147,232 @@ impl App {
 
     pub fn toggle_pretty(&mut self) {
         self.json_pretty = !self.json_pretty;
+        self.pretty_toggled.clear();
+        self.clamp_scroll();
     }
 
     pub fn is_pretty(&self) -> bool {
         self.json_pretty
     }
 
-    pub fn toggle_wrap(&mut self) {
-        self.wrap = !self.wrap;
-        self.h_scroll = 0;
+    /// Whether the line at original index `idx` renders expanded: the global
+    /// toggle, flipped for lines toggled individually.
+    pub fn is_line_pretty(&self, idx: usize) -> bool {
+        self.json_pretty != self.pretty_toggled.contains(&idx)
     }
 
-    pub fn is_wrap(&self) -> bool {
-        self.wrap
+    /// True if any line renders expanded, globally or individually.
+    pub fn has_pretty_lines(&self) -> bool {
+        self.json_pretty || !self.pretty_toggled.is_empty()
     }
 
-    pub fn scroll_right(&mut self, n: usize) {
-        self.h_scroll = self.h_scroll.saturating_add(n);
+    /// Expand or collapse just the cursor line (or the top visible line
+    /// outside cursor mode), leaving every other line as it is.
+    pub fn toggle_line_pretty(&mut self) {
+        let pos = if self.mode == AppMode::Cursor {
+            self.cursor_position
+        } else {
+            self.scroll_offset
+        };
+        let Some(&idx) = self.filtered_indices.get(pos) else {
+            return;
+        };
+        if !self.pretty_toggled.remove(&idx) {
+            self.pretty_toggled.insert(idx);
+        }
+        if self.mode == AppMode::Cursor {
+            self.scroll_to_cursor();
+        }
     }
 
-    pub fn scroll_left(&mut self, n: usize) {
-        self.h_scroll = self.h_scroll.saturating_sub(n);
-    }
+    /// Fold continuation lines into the entry above them, or show them as
+    /// lines of their own again.
+    pub fn toggle_join_lines(&mut self) {
+        self.set_join_lines(!self.join_lines);
+        self.notice = Some(
+            if self.join_lines {
+                "Multi-line entries: joined"
+            } else {
+                "Multi-line entries: one line each"
+            }
+            .to_string(),
+        );
+    }
+
+    pub fn set_join_lines(&mut self, on: bool) {
+        if self.join_lines != on {
+            self.join_lines = on;
+            self.recompute_filter();
+        }
+    }
+
+    /// Record the entries that the continuation lines from `start` on carry
+    /// on, lengthening the last one when it runs on into them.
+    fn extend_entries(&mut self, start: usize) {
+        for idx in start.max(1)..self.parsed_lines.len() {
+            if !continues_entry(&self.parsed_lines, idx, self.format) {
+                continue;
+            }
+            match self.entries.last_mut() {
+                Some((_, last)) if *last == idx - 1 => *last = idx,
+                _ => self.entries.push((idx - 1, idx)),
+            }
+        }
+    }
+
+    /// Whether continuation lines are folded right now. The malformed-only
+    /// view is about lines, not entries.
+    fn is_joining(&self) -> bool {
+        self.join_lines && !self.malformed_only
+    }
+
+    /// The entry original line `idx` belongs to: the line a continuation
+    /// line carries on, or `idx` itself.
+    fn entry_head(&self, idx: usize) -> usize {
+        let pos = self.entries.partition_point(|&(first, _)| first <= idx);
+        match pos.checked_sub(1).map(|pos| self.entries[pos]) {
+            Some((first, last)) if idx <= last => first,
+            _ => idx,
+        }
+    }
+
+    /// Original indices of the continuation lines folded into the entry at
+    /// `idx`; empty when lines aren't joined or it has none.
+    pub fn continuation_lines(&self, idx: usize) -> Range<usize> {
+        let end = match self.entries.binary_search_by_key(&idx, |&(first, _)| first) {
+            Ok(pos) if self.is_joining() => self.entries[pos].1 + 1,
+            _ => idx + 1,
+        };
+        idx + 1..end
+    }
+
+    /// Java stack frames among the lines folded into the entry at `idx`.
+    pub fn folded_frames(&self, idx: usize) -> usize {
+        self.continuation_lines(idx)
+            .filter(|&i| is_java_frame(&self.parsed_lines[i].raw))
+            .count()
+    }
+
+    /// `indices` with continuation lines replaced by the entry they carry
+    /// on, so a match in a stack trace shows its error line, as long as the
+    /// level filter keeps that.
+    fn fold_continuations(&self, mut indices: Vec<usize>) -> Vec<usize> {
+        if !self.is_joining() || self.entries.is_empty() {
+            return indices;
+        }
+        for idx in indices.iter_mut() {
+            *idx = self.entry_head(*idx);
+        }
+        indices.dedup();
+        if let Some(min) = self.min_level {
+            indices.retain(|&i| self.parsed_lines[i].level.is_none_or(|level| level >= min));
+        }
+        indices
+    }
+
+    /// Original indices of the filtered lines with their continuation lines.
+    fn filtered_entry_lines(&self) -> impl Iterator<Item = usize> + '_ {
+        self.filtered_indices
+            .iter()
+            .flat_map(|&idx| idx..self.continuation_lines(idx).end)
+    }
+
+    pub fn toggle_wrap(&mut self) {
+        self.wrap = !self.wrap;
+        self.h_scroll = 0;
+    }
+
+    pub fn toggle_whitespace(&mut self) {
+        self.show_whitespace = !self.show_whitespace;
+    }
+
+    pub fn set_show_whitespace(&mut self, show: bool) {
+        self.show_whitespace = show;
+    }
+
+    pub fn is_show_whitespace(&self) -> bool {
+        self.show_whitespace
+    }
+
+    pub fn blank_lines(&self) -> BlankLines {
+        self.blank_lines
+    }
+
+    pub fn set_blank_lines(&mut self, blank_lines: BlankLines) {
+        self.blank_lines = blank_lines;
+        self.recompute_filter();
+    }
+
+    pub fn cycle_blank_lines(&mut self) {
+        self.set_blank_lines(self.blank_lines.next());
+        self.notice = Some(format!("Blank lines: {}", self.blank_lines.name()));
+    }
+
+    pub fn toggle_high_contrast(&mut self) {
+        self.high_contrast = !self.high_contrast;
+        self.notice = Some(
+            if self.high_contrast {
+                "High contrast: on"
+            } else {
+                "High contrast: off"
+            }
+            .to_string(),
+        );
+    }
+
+    pub fn set_high_contrast(&mut self, on: bool) {
+        self.high_contrast = on;
+    }
+
+    pub fn is_high_contrast(&self) -> bool {
+        self.high_contrast
+    }
+
+    pub fn toggle_text_cues(&mut self) {
+        self.text_cues = !self.text_cues;
+        self.notice = Some(
+            if self.text_cues {
+                "Text cues: on"
+            } else {
+                "Text cues: off"
+            }
+            .to_string(),
+        );
+    }
+
+    pub fn set_text_cues(&mut self, on: bool) {
+        self.text_cues = on;
+    }
+
+    pub fn is_text_cues(&self) -> bool {
+        self.text_cues
+    }
+
+    pub fn level_badge(&self) -> LevelBadge {
+        self.level_badge
+    }
+
+    pub fn set_level_badge(&mut self, badge: LevelBadge) {
+        self.level_badge = badge;
+    }
+
+    /// Badge, then code, letter, emoji, none, and back.
+    pub fn cycle_level_badge(&mut self) {
+        self.level_badge = self.level_badge.next();
+        self.notice = Some(format!("Level badges: {}", self.level_badge.name()));
+    }
+
+    pub fn is_wrap(&self) -> bool {
+        self.wrap
+    }
+
+    pub fn scroll_right(&mut self, n: usize) {
+        self.h_scroll = self.h_scroll.saturating_add(n);
+    }
+
+    pub fn scroll_left(&mut self, n: usize) {
+        self.h_scroll = self.h_scroll.saturating_sub(n);
+    }
 
     pub fn h_scroll(&self) -> usize {
         self.h_scroll
@@ -323,20 +1397,153 @@ impl App {
         self.cursor_position
     }
 
+    pub fn parsed_line(&self, idx: usize) -> Option<&ParsedLine> {
+        self.parsed_lines.get(idx)
+    }
+
+    /// Original index of the line under the cursor.
+    pub fn cursor_line_index(&self) -> Option<usize> {
+        self.filtered_indices.get(self.cursor_position).copied()
+    }
+
     pub fn cursor_line_raw(&self) -> Option<&str> {
         self.filtered_indices
             .get(self.cursor_position)
             .map(|&idx| self.parsed_lines[idx].raw.as_str())
     }
 
+    /// The cursor line with the continuation lines folded into it.
+    pub fn cursor_entry_raw(&self) -> Option<String> {
+        let mut text = self.cursor_line_raw()?.to_string();
+        let idx = self.filtered_indices[self.cursor_position];
+        for i in self.continuation_lines(idx) {
+            text.push('\n');
+            text.push_str(&self.parsed_lines[i].raw);
+        }
+        Some(text)
+    }
+
+    pub fn set_location_template(&mut self, template: String) {
+        self.location_template = template;
+    }
+
+    /// The cursor line's location for pasting into chat, `/var/log/app.log:12345`
+    /// by default, from the `[yank] location` template.
+    pub fn cursor_location(&self) -> Option<String> {
+        let &idx = self.filtered_indices.get(self.cursor_position)?;
+        let path = self
+            .source_path
+            .as_ref()
+            .map_or_else(|| self.source_name.clone(), |p| p.display().to_string());
+        let time = self
+            .time_index
+            .as_ref()
+            .and_then(|index| index.timestamp_at(idx))
+            .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+            .unwrap_or_default();
+        Some(
+            self.location_template
+                .replace("{path}", &path)
+                .replace("{file}", &self.source_name)
+                .replace("{line}", &(idx + 1).to_string())
+                .replace("{time}", &time),
+        )
+    }
+
     pub fn all_filtered_lines_raw(&self) -> String {
-        self.filtered_indices
-            .iter()
-            .map(|&idx| self.parsed_lines[idx].raw.as_str())
+        self.filtered_entry_lines()
+            .map(|idx| self.parsed_lines[idx].raw.as_str())
             .collect::<Vec<_>>()
             .join("\n")
     }
 
+    /// Filtered lines whose original index is `start` or later, in view order.
+    pub fn filtered_lines_since(&self, start: usize) -> impl Iterator<Item = &ParsedLine> {
+        self.filtered_indices
+            .iter()
+            .filter(move |&&idx| idx >= start)
+            .map(|&idx| &self.parsed_lines[idx])
+    }
+
+    /// Bytes `all_filtered_lines_raw` would produce.
+    pub fn filtered_raw_len(&self) -> usize {
+        self.raw_len(self.filtered_entry_lines())
+    }
+
+    /// Stream the filtered lines to `path`, one per line, without building
+    /// them into a single string first.
+    pub fn save_filtered_lines(&self, path: &Path) -> anyhow::Result<()> {
+        self.save_lines(path, self.filtered_entry_lines())
+    }
+
+    /// Original line numbers (1-indexed) of the first and last lines between
+    /// the mark and the cursor, either way round, folded lines included.
+    pub fn marked_range(&self) -> Option<(usize, usize)> {
+        let mark = self.mark?;
+        let &cursor = self.filtered_indices.get(self.cursor_position)?;
+        let (first, last) = (mark.min(cursor), mark.max(cursor));
+        Some((first + 1, self.continuation_lines(last).end))
+    }
+
+    /// Original indices of the filtered lines in [`marked_range`](Self::marked_range).
+    fn marked_range_lines(&self) -> impl Iterator<Item = usize> + '_ {
+        let (first, end) = self.marked_range().unwrap_or((1, 0));
+        self.filtered_entry_lines()
+            .filter(move |&idx| idx + 1 >= first && idx < end)
+    }
+
+    /// The lines in view from the mark to the cursor, for yanking.
+    pub fn marked_range_raw(&self) -> Option<String> {
+        self.marked_range()?;
+        Some(
+            self.marked_range_lines()
+                .map(|idx| self.parsed_lines[idx].raw.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+
+    /// Bytes `marked_range_raw` would produce.
+    pub fn marked_range_raw_len(&self) -> usize {
+        self.raw_len(self.marked_range_lines())
+    }
+
+    /// Number of lines in view from the mark to the cursor.
+    pub fn marked_range_count(&self) -> usize {
+        self.marked_range_lines().count()
+    }
+
+    /// Stream the lines in view from the mark to the cursor to `path`.
+    pub fn save_marked_range(&self, path: &Path) -> anyhow::Result<()> {
+        self.save_lines(path, self.marked_range_lines())
+    }
+
+    /// File name for a saved range, `lumolog-lines-182440-182472.log`.
+    pub fn marked_range_file_name(&self) -> Option<String> {
+        let (first, last) = self.marked_range()?;
+        Some(format!("lumolog-lines-{first}-{last}.log"))
+    }
+
+    /// Bytes of `lines` joined by newlines.
+    fn raw_len(&self, lines: impl Iterator<Item = usize>) -> usize {
+        let (text, lines) = lines.fold((0, 0usize), |(text, lines), idx| {
+            (text + self.parsed_lines[idx].raw.len(), lines + 1)
+        });
+        text + lines.saturating_sub(1)
+    }
+
+    fn save_lines(&self, path: &Path, lines: impl Iterator<Item = usize>) -> anyhow::Result<()> {
+        let write = || -> std::io::Result<()> {
+            let mut out = BufWriter::new(File::create(path)?);
+            for idx in lines {
+                out.write_all(self.parsed_lines[idx].raw.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+            out.flush()
+        };
+        write().map_err(|e| anyhow::anyhow!("cannot write {}: {e}", path.display()))
+    }
+
     pub fn set_yank_flash(&mut self) {
         self.yank_flash = 3;
     }
@@ -349,9 +1556,213 @@ impl App {
         self.yank_flash > 0
     }
 
+    /// Register being recorded into, for the status bar.
+    pub fn set_macro_recording(&mut self, register: Option<char>) {
+        self.macro_recording = register;
+    }
+
+    pub fn macro_recording(&self) -> Option<char> {
+        self.macro_recording
+    }
+
+    // Background job methods (clipboard / browser run on a worker thread)
+
+    pub fn begin_background_job(&mut self) {
+        self.background_jobs += 1;
+        self.job_error = None;
+        self.notice = None;
+    }
+
+    pub fn finish_background_job(&mut self, error: Option<String>) {
+        self.background_jobs = self.background_jobs.saturating_sub(1);
+        self.job_error = error;
+    }
+
+    pub fn is_job_pending(&self) -> bool {
+        self.background_jobs > 0
+    }
+
+    pub fn job_error(&self) -> Option<&str> {
+        self.job_error.as_deref()
+    }
+
+    pub fn notice(&self) -> Option<&str> {
+        self.notice.as_deref()
+    }
+
+    pub fn set_notice(&mut self, notice: String) {
+        self.notice = Some(notice);
+    }
+
+    /// Report a failed action that didn't go through the worker.
+    pub fn set_job_error(&mut self, error: String) {
+        self.job_error = Some(error);
+    }
+
+    /// Mark the cursor line (original index), or clear the mark if it's already there.
+    pub fn toggle_mark(&mut self) {
+        let Some(&idx) = self.filtered_indices.get(self.cursor_position) else {
+            return;
+        };
+        self.mark = if self.mark == Some(idx) {
+            None
+        } else {
+            Some(idx)
+        };
+    }
+
+    /// Original index of the marked line, if any.
+    pub fn mark(&self) -> Option<usize> {
+        self.mark
+    }
+
+    /// Pin the cursor line to the pinned pane, or unpin it if it's there.
+    pub fn toggle_pin(&mut self) {
+        let Some(&idx) = self.filtered_indices.get(self.cursor_position) else {
+            return;
+        };
+        if let Some(pos) = self.pins.iter().position(|&p| p == idx) {
+            self.pins.remove(pos);
+            return;
+        }
+        if self.pins.len() == MAX_PINS {
+            self.pins.remove(0);
+        }
+        self.pins.push(idx);
+    }
+
+    /// Original indices of the pinned lines, oldest first.
+    pub fn pins(&self) -> &[usize] {
+        &self.pins
+    }
+
+    pub fn clear_pins(&mut self) {
+        self.pins.clear();
+    }
+
+    /// Pinned lines as plain text in line order, each prefixed with its
+    /// line number.
+    pub fn export_pins(&self) -> String {
+        let mut pins = self.pins.clone();
+        pins.sort_unstable();
+        pins.iter()
+            .filter_map(|&idx| self.parsed_lines.get(idx).map(|l| (idx, l)))
+            .map(|(idx, line)| format!("line {}: {}\n", idx + 1, line.raw))
+            .collect()
+    }
+
+    /// The lines in view as plain text for a screen reader: a header with
+    /// the file, what's narrowing the view and how many lines are left, then
+    /// one line each, like `Line 12, ERROR, 2024-01-15T08:30:05Z: Failed to
+    /// connect. Fields: host=db-1.` with notes on lines of their own.
+    pub fn export_view_text(&self) -> String {
+        let mut text = format!("lumolog: {}\n", self.source_name);
+        let crumbs: Vec<String> = self
+            .breadcrumbs()
+            .into_iter()
+            .map(|(_, label)| label)
+            .collect();
+        if !crumbs.is_empty() {
+            text.push_str(&format!("Filters: {}\n", crumbs.join(", ")));
+        }
+        text.push_str(&format!(
+            "Showing {} of {} lines\n\n",
+            group_thousands(self.filtered_indices.len()),
+            group_thousands(self.parsed_lines.len())
+        ));
+        for &idx in &self.filtered_indices {
+            let line = &self.parsed_lines[idx];
+            let mut head = format!("Line {}", idx + 1);
+            if self.is_context_line(idx) {
+                head.push_str(", context");
+            }
+            if let Some(level) = line.level {
+                head.push_str(&format!(", {}", level.name()));
+            }
+            if crate::highlighter::shows_level_badge(line.format) {
+                if let Some(ts) = &line.timestamp {
+                    head.push_str(&format!(", {ts}"));
+                }
+                text.push_str(&format!("{head}: {}", line.message));
+            } else {
+                text.push_str(&format!("{head}: {}", line.raw));
+            }
+            if !line.extra_fields.is_empty() {
+                let fields = crate::highlighter::extra_fields_text(line);
+                text.push_str(&format!(". Fields: {fields}."));
+            }
+            text.push('\n');
+            for i in self.continuation_lines(idx) {
+                text.push_str(&format!("  Line {}: {}\n", i + 1, self.parsed_lines[i].raw));
+            }
+            if let Some(note) = self.annotation_at(idx) {
+                text.push_str(&format!("  Note: {note}\n"));
+            }
+        }
+        text
+    }
+
+    /// Open a word diff between the marked line and the cursor line.
+    pub fn open_diff(&mut self) {
+        if let Some(marked) = self.mark
+            && let Some(&idx) = self.filtered_indices.get(self.cursor_position)
+        {
+            self.diff_pair = Some((marked, idx));
+        }
+    }
+
+    pub fn close_diff(&mut self) {
+        self.diff_pair = None;
+    }
+
+    /// The (marked, cursor) pair being diffed.
+    pub fn diff_lines(&self) -> Option<(NumberedLine<'_>, NumberedLine<'_>)> {
+        let (a, b) = self.diff_pair?;
+        Some((
+            (a + 1, self.parsed_lines[a].raw.as_str()),
+            (b + 1, self.parsed_lines[b].raw.as_str()),
+        ))
+    }
+
+    /// Show the bytes of the cursor line as a hex dump.
+    pub fn open_hex_dump(&mut self) {
+        if let Some(&idx) = self.filtered_indices.get(self.cursor_position) {
+            self.hex_dump = Some((idx, 0));
+        }
+    }
+
+    pub fn close_hex_dump(&mut self) {
+        self.hex_dump = None;
+    }
+
+    /// The line in the hex dump popup.
+    pub fn hex_dump_line(&self) -> Option<NumberedLine<'_>> {
+        let (idx, _) = self.hex_dump?;
+        Some((idx + 1, self.parsed_lines[idx].raw.as_str()))
+    }
+
+    /// Dump rows scrolled past the top of the popup.
+    pub fn hex_dump_scroll(&self) -> usize {
+        self.hex_dump.map_or(0, |(_, scroll)| scroll)
+    }
+
+    /// Scroll the hex dump; the renderer stops at the last row.
+    pub fn scroll_hex_dump(&mut self, delta: isize) {
+        if let Some((_, scroll)) = self.hex_dump.as_mut() {
+            *scroll = scroll.saturating_add_signed(delta);
+        }
+    }
+
+    /// Keep the hex dump's scroll within its `rows` rows shown `height` at a time.
+    pub fn clamp_hex_dump_scroll(&mut self, rows: usize, height: usize) {
+        if let Some((_, scroll)) = self.hex_dump.as_mut() {
+            *scroll = (*scroll).min(rows.saturating_sub(height));
+        }
+    }
+
     pub fn cursor_down(&mut self, n: usize) {
         let max = self.filtered_indices.len().saturating_sub(1);
-        self.cursor_position = (self.cursor_position + n).min(max);
+        self.cursor_position = self.cursor_position.saturating_add(n).min(max);
         self.scroll_to_cursor();
     }
 
@@ -360,20 +1771,73 @@ impl App {
         self.scroll_to_cursor();
     }
 
+    /// Jump to original line `line` (1-based), or the first visible line after
+    /// it when it's filtered out. Moves the cursor in cursor mode, otherwise
+    /// the viewport.
+    pub fn goto_line(&mut self, line: usize) {
+        let target = line.saturating_sub(1);
+        let pos = if self.sort.is_some() {
+            self.nearest_filtered_position(target)
+        } else {
+            self.filtered_indices
+                .partition_point(|&idx| idx < target)
+                .min(self.filtered_indices.len().saturating_sub(1))
+        };
+        if self.is_cursor_mode() {
+            self.cursor_position = pos;
+            self.scroll_to_cursor();
+        } else {
+            self.scroll_offset = pos;
+            self.clamp_scroll();
+        }
+    }
+
+    /// Jump to the first line stamped at or after `time`, or to the last line
+    /// when the log ends before it.
+    pub fn goto_time(&mut self, time: chrono::NaiveDateTime) {
+        let Some(index) = &self.time_index else {
+            self.notice = Some("No timestamps to go to".to_string());
+            return;
+        };
+        let first = (0..self.parsed_lines.len())
+            .find(|&idx| index.timestamp_at(idx).is_some_and(|t| t >= time));
+        match first {
+            Some(idx) => self.goto_line(idx + 1),
+            None => {
+                self.goto_line(usize::MAX);
+                self.notice = Some(format!(
+                    "The log ends before {}",
+                    time.format("%Y-%m-%d %H:%M:%S")
+                ));
+            }
+        }
+    }
+
     fn scroll_to_cursor(&mut self) {
+        let Some(last) = self.filtered_indices.len().checked_sub(1) else {
+            return;
+        };
+        // Keep `scrolloff` entries of context around the cursor, but never more
+        // than half the viewport or the cursor could never reach the middle
+        let margin = self
+            .scroll_config
+            .scrolloff
+            .min(self.viewport_height.saturating_sub(1) / 2);
         // Cursor above viewport → scroll up
-        if self.cursor_position < self.scroll_offset {
-            self.scroll_offset = self.cursor_position;
+        let top = self.cursor_position.saturating_sub(margin);
+        if top < self.scroll_offset {
+            self.scroll_offset = top;
             return;
         }
         // Cursor below viewport → scroll down
+        let bottom = (self.cursor_position + margin).min(last);
         let visible_count = self.viewport_entries_from(self.scroll_offset);
         let last_visible = self.scroll_offset + visible_count.saturating_sub(1);
-        if self.cursor_position > last_visible {
-            // Walk backward from cursor to find scroll_offset that makes cursor the last visible entry
+        if bottom > last_visible {
+            // Walk backward from `bottom` to find scroll_offset that makes it the last visible entry
             let mut display_lines = 0;
-            let mut new_offset = self.cursor_position;
-            for i in (0..=self.cursor_position).rev() {
+            let mut new_offset = bottom;
+            for i in (0..=bottom).rev() {
                 let idx = self.filtered_indices[i];
                 let lines = self.display_line_count(idx);
                 if display_lines + lines > self.viewport_height {
@@ -396,289 +1860,1888 @@ impl App {
         &self.source_name
     }
 
-    // Filter mode methods
+    /// Terminal window title, e.g. `lumolog — app.log [FOLLOWING]`, so the
+    /// tab can be told apart from other terminals.
+    pub fn terminal_title(&self) -> String {
+        let state = match (self.follow_mode, self.follow_paused) {
+            (false, _) => "",
+            (true, false) => " [FOLLOWING]",
+            (true, true) => " [PAUSED]",
+        };
+        format!("lumolog — {}{state}", self.source_name)
+    }
 
-    pub fn is_filter_mode(&self) -> bool {
-        self.mode == AppMode::Filter
+    pub fn set_source_path(&mut self, path: PathBuf) {
+        // Yearless timestamps are dated back from when the file was last
+        // written rather than from today
+        if self
+            .time_index
+            .as_ref()
+            .is_some_and(TimeIndex::has_yearless)
+            && let Ok(modified) = std::fs::metadata(&path).and_then(|m| m.modified())
+        {
+            let reference = chrono::DateTime::<chrono::Local>::from(modified).naive_local();
+            let mut index = build_time_index_as_of(&self.parsed_lines, reference);
+            index.set_include_inherited(self.include_inherited_times());
+            if self.sparkline_width > 0 {
+                self.sparkline_data = compute_sparkline(&index, self.sparkline_width);
+            }
+            self.time_index = Some(index);
+            self.refresh_time_cursor_levels();
+        }
+        self.source_path = Some(path);
     }
 
-    pub fn filter_pattern(&self) -> &str {
-        &self.filter_pattern
+    pub fn rotated_files(&self) -> usize {
+        self.rotated_files
     }
 
-    pub fn is_fuzzy(&self) -> bool {
-        self.is_fuzzy
+    pub fn set_rotated_files(&mut self, count: usize) {
+        self.rotated_files = count;
     }
 
-    pub fn enter_filter_mode(&mut self) {
-        self.mode = AppMode::Filter;
+    /// Ask for the rotated files to be read in ahead of this one.
+    pub fn request_rotated_files(&mut self) {
+        self.rotated_requested = true;
     }
 
-    pub fn exit_filter_mode(&mut self) {
-        self.mode = AppMode::Normal;
+    pub fn take_rotated_request(&mut self) -> bool {
+        std::mem::take(&mut self.rotated_requested)
     }
 
-    pub fn clear_filter(&mut self) {
-        self.filter_pattern.clear();
-        self.recompute_filter();
-        self.mode = AppMode::Normal;
+    /// Keep `old`'s notes, pins and mark on the same lines after `offset`
+    /// lines were put in front of them.
+    pub fn carry_line_marks(&mut self, old: &App, offset: usize) {
+        let len = self.parsed_lines.len();
+        let moved = |idx: usize| Some(idx + offset).filter(|&i| i < len);
+        self.annotations = old
+            .annotations
+            .iter()
+            .filter_map(|(&idx, note)| Some((moved(idx)?, note.clone())))
+            .collect();
+        self.annotations_dirty = old.annotations_dirty;
+        self.pins = old.pins.iter().filter_map(|&idx| moved(idx)).collect();
+        self.mark = old.mark.and_then(moved);
+    }
+
+    /// Snapshot of the file and filters, for sharing with `--load`.
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            file: self.source_path.clone(),
+            filter: self.filter_pattern.clone(),
+            filters: self.filter_terms.clone(),
+            min_level: self.min_level,
+            hidden_levels: self.hidden_levels.clone(),
+            time_range: self.time_range.clone(),
+            layout: Some(self.pane_layout()),
+        }
     }
 
-    pub fn filter_input(&mut self, c: char) {
-        self.filter_pattern.push(c);
-        self.recompute_filter();
+    /// The view state plus the cursor line, mark and pins, for
+    /// `--dump-state-on-exit`.
+    pub fn exit_state(&self) -> ExitState {
+        let row = if self.is_cursor_mode() {
+            self.cursor_position
+        } else {
+            self.scroll_offset
+        };
+        ExitState {
+            view: self.view_state(),
+            cursor_line: self.filtered_indices.get(row).map(|&idx| idx + 1),
+            mark: self.mark.map(|idx| idx + 1),
+            pins: self.pins.iter().map(|&idx| idx + 1).collect(),
+            matches: self.filtered_indices.len(),
+        }
     }
 
-    pub fn filter_backspace(&mut self) {
-        self.filter_pattern.pop();
+    /// Restore the filters and pane layout from a saved view. Any other
+    /// active filters are cleared.
+    pub fn apply_view_state(&mut self, state: &ViewState) {
+        self.filter_pattern = state.filter.clone();
+        self.filter_terms = state.filters.clone();
+        self.min_level = state.min_level;
+        self.hidden_levels = state.hidden_levels.clone();
+        self.hidden_levels.sort_unstable();
+        self.hidden_levels.dedup();
+        self.time_range = state.time_range.clone();
+        if let Some(layout) = state.layout {
+            self.set_pane_layout(layout);
+        }
+        self.similar_template = None;
+        self.hidden_templates.clear();
         self.recompute_filter();
     }
 
-    fn recompute_filter(&mut self) {
-        let result = filter_lines(&self.parsed_lines, &self.filter_pattern, self.min_level);
-        let mut indices = result.indices;
-        // Time range filter
-        if let (Some(index), Some(range)) = (&self.time_index, &self.time_range) {
-            indices = filter_by_time_range(index, range, &indices);
+    // Saved filter methods
+
+    /// Whether there's a text, level or time filter worth saving.
+    fn has_savable_filter(&self) -> bool {
+        !self.active_filter_terms().is_empty()
+            || self.min_level.is_some()
+            || !self.hidden_levels.is_empty()
+            || self.time_range.is_some()
+    }
+
+    /// Start typing a name for the current filter.
+    pub fn enter_save_filter_mode(&mut self) {
+        if !self.has_savable_filter() {
+            self.notice = Some("No filter to save".to_string());
+            return;
         }
-        if let Some(ref tmpl) = self.similar_template {
-            indices.retain(|&i| self.parsed_lines[i].template == *tmpl);
+        self.save_filter_input.clear();
+        self.mode = AppMode::SaveFilter;
+    }
+
+    pub fn save_filter_input(&self) -> &str {
+        &self.save_filter_input
+    }
+
+    pub fn save_filter_type(&mut self, c: char) {
+        self.save_filter_input.push(c);
+    }
+
+    pub fn save_filter_backspace(&mut self) {
+        self.save_filter_input.pop();
+    }
+
+    /// The current filter under the name typed, for the caller to store.
+    /// None, staying in SaveFilter mode, while the name is blank.
+    pub fn save_filter_submit(&mut self) -> Option<SavedFilter> {
+        let name = self.save_filter_input.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let saved = SavedFilter::from_view_state(name, &self.view_state());
+        self.exit_save_filter_mode();
+        Some(saved)
+    }
+
+    pub fn exit_save_filter_mode(&mut self) {
+        self.save_filter_input.clear();
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn set_saved_filters(&mut self, saved: SavedFilters) {
+        self.saved_filters = saved;
+        if let Some(sel) = self.saved_filters_selected.as_mut() {
+            *sel = (*sel).min(self.saved_filters.len().saturating_sub(1));
+        }
+        if self.saved_filters.is_empty() {
+            self.saved_filters_selected = None;
+        }
+    }
+
+    pub fn saved_filters(&self) -> &SavedFilters {
+        &self.saved_filters
+    }
+
+    /// Replace the text, level and time filters with `saved`'s.
+    pub fn apply_saved_filter(&mut self, saved: &SavedFilter) {
+        self.apply_view_state(&saved.to_view_state());
+        self.notice = Some(format!("Applied filter \"{}\"", saved.name));
+    }
+
+    /// List `saved` to pick one from.
+    pub fn open_saved_filters(&mut self, saved: SavedFilters) {
+        self.set_saved_filters(saved);
+        if self.saved_filters.is_empty() {
+            self.notice = Some("No saved filters".to_string());
+            return;
+        }
+        self.saved_filters_selected = Some(0);
+    }
+
+    pub fn close_saved_filters(&mut self) {
+        self.saved_filters_selected = None;
+    }
+
+    pub fn saved_filters_selected(&self) -> Option<usize> {
+        self.saved_filters_selected
+    }
+
+    pub fn saved_filters_down(&mut self) {
+        if let Some(sel) = self.saved_filters_selected.as_mut()
+            && *sel + 1 < self.saved_filters.len()
+        {
+            *sel += 1;
+        }
+    }
+
+    pub fn saved_filters_up(&mut self) {
+        if let Some(sel) = self.saved_filters_selected.as_mut() {
+            *sel = sel.saturating_sub(1);
+        }
+    }
+
+    /// Close the panel and apply the selected filter.
+    pub fn saved_filters_apply(&mut self) {
+        if let Some(sel) = self.saved_filters_selected.take()
+            && let Some(saved) = self.saved_filters.filters().get(sel).cloned()
+        {
+            self.apply_saved_filter(&saved);
+        }
+    }
+
+    /// Name of the selected filter, for the caller to delete.
+    pub fn saved_filters_selected_name(&self) -> Option<&str> {
+        let sel = self.saved_filters_selected?;
+        self.saved_filters
+            .filters()
+            .get(sel)
+            .map(|f| f.name.as_str())
+    }
+
+    // Filter mode methods
+
+    pub fn is_filter_mode(&self) -> bool {
+        self.mode == AppMode::Filter
+    }
+
+    pub fn filter_pattern(&self) -> &str {
+        &self.filter_pattern
+    }
+
+    /// Filters stacked before the one being edited, oldest first.
+    pub fn filter_terms(&self) -> &[String] {
+        &self.filter_terms
+    }
+
+    /// The stacked filters and the one being edited, skipping empty ones.
+    fn active_filter_terms(&self) -> Vec<&str> {
+        self.filter_terms
+            .iter()
+            .map(String::as_str)
+            .chain([self.filter_pattern.as_str()])
+            .filter(|term| !term.is_empty())
+            .collect()
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        self.is_fuzzy
+    }
+
+    /// Why the `re:` filter doesn't compile, while it doesn't.
+    pub fn filter_error(&self) -> Option<&str> {
+        self.filter_error.as_deref()
+    }
+
+    pub fn filter_warning(&self) -> Option<FilterWarning> {
+        self.filter_warning
+    }
+
+    /// Open the filter bar. A filter already in place is stacked as a chip
+    /// and the bar starts empty, so each `/` adds a filter that must also
+    /// match.
+    pub fn enter_filter_mode(&mut self) {
+        self.mode = AppMode::Filter;
+        if !self.filter_pattern.is_empty() {
+            self.filter_terms
+                .push(std::mem::take(&mut self.filter_pattern));
+        }
+        self.filter_cursor = 0;
+    }
+
+    /// Edit cursor position in characters. The pattern can be replaced from
+    /// elsewhere (view state, AI, value distribution), so it's clamped.
+    pub fn filter_cursor(&self) -> usize {
+        self.filter_cursor.min(self.filter_pattern.chars().count())
+    }
+
+    fn filter_cursor_byte(&self) -> usize {
+        self.filter_pattern
+            .char_indices()
+            .nth(self.filter_cursor())
+            .map_or(self.filter_pattern.len(), |(i, _)| i)
+    }
+
+    pub fn exit_filter_mode(&mut self) {
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.filter_pattern.clear();
+        self.recompute_filter();
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn filter_input(&mut self, c: char) {
+        let at = self.filter_cursor_byte();
+        self.filter_pattern.insert(at, c);
+        self.filter_cursor = self.filter_cursor() + 1;
+        self.recompute_filter();
+    }
+
+    /// Insert pasted text into whichever input is active, as one edit: the
+    /// filter is recomputed once rather than per character. Line breaks
+    /// become spaces since every input is a single line.
+    pub fn paste(&mut self, text: &str) {
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        if text.is_empty() {
+            return;
+        }
+        match self.mode {
+            AppMode::Filter => {
+                let at = self.filter_cursor_byte();
+                self.filter_pattern.insert_str(at, &text);
+                self.filter_cursor = self.filter_cursor() + text.chars().count();
+                self.recompute_filter();
+            }
+            AppMode::CommandPalette => {
+                self.palette_input.push_str(&text);
+                self.recompute_palette();
+            }
+            AppMode::Ask => self.ask_input.push_str(&text),
+            AppMode::Analyze => self.analyze_input.push_str(&text),
+            AppMode::Annotate => {
+                if let Some((_, input)) = self.annotation_input.as_mut() {
+                    input.push_str(&text);
+                }
+            }
+            AppMode::SaveFilter => self.save_filter_input.push_str(&text),
+            _ => {}
+        }
+    }
+
+    /// Delete the character before the cursor. In an empty filter, the last
+    /// stacked filter comes back for editing instead.
+    pub fn filter_backspace(&mut self) {
+        if self.filter_pattern.is_empty()
+            && let Some(term) = self.filter_terms.pop()
+        {
+            self.filter_pattern = term;
+            self.filter_cursor = self.filter_pattern.chars().count();
+            self.recompute_filter();
+            return;
+        }
+        let end = self.filter_cursor_byte();
+        let Some(c) = self.filter_pattern[..end].chars().next_back() else {
+            return;
+        };
+        self.filter_cursor = self.filter_cursor() - 1;
+        self.filter_pattern.remove(end - c.len_utf8());
+        self.recompute_filter();
+    }
+
+    /// Delete the character under the cursor.
+    pub fn filter_delete(&mut self) {
+        let at = self.filter_cursor_byte();
+        if at < self.filter_pattern.len() {
+            self.filter_pattern.remove(at);
+            self.recompute_filter();
+        }
+    }
+
+    /// Ctrl-W: delete the word before the cursor and any spaces after it.
+    pub fn filter_delete_word(&mut self) {
+        let end = self.filter_cursor_byte();
+        let before = self.filter_pattern[..end].trim_end();
+        let start = before.rfind(char::is_whitespace).map_or(0, |i| {
+            i + before[i..].chars().next().map_or(1, char::len_utf8)
+        });
+        if start == end {
+            return;
+        }
+        self.filter_cursor = self.filter_pattern[..start].chars().count();
+        self.filter_pattern.replace_range(start..end, "");
+        self.recompute_filter();
+    }
+
+    /// Ctrl-U: delete everything before the cursor.
+    pub fn filter_delete_to_start(&mut self) {
+        let end = self.filter_cursor_byte();
+        if end == 0 {
+            return;
+        }
+        self.filter_pattern.replace_range(..end, "");
+        self.filter_cursor = 0;
+        self.recompute_filter();
+    }
+
+    pub fn filter_cursor_left(&mut self) {
+        self.filter_cursor = self.filter_cursor().saturating_sub(1);
+    }
+
+    pub fn filter_cursor_right(&mut self) {
+        self.filter_cursor = (self.filter_cursor() + 1).min(self.filter_pattern.chars().count());
+    }
+
+    pub fn filter_cursor_home(&mut self) {
+        self.filter_cursor = 0;
+    }
+
+    pub fn filter_cursor_end(&mut self) {
+        self.filter_cursor = self.filter_pattern.chars().count();
+    }
+
+    pub fn text_match(&self) -> TextMatch {
+        self.text_match
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.text_match.case
+    }
+
+    pub fn set_case_mode(&mut self, case: CaseMode) {
+        self.text_match.case = case;
+        self.recompute_filter();
+    }
+
+    /// Ignore case, then smart case, then match case exactly, then back.
+    pub fn cycle_case_mode(&mut self) {
+        self.set_case_mode(self.text_match.case.next());
+        let label = self.text_match.case.label().unwrap_or("ignore case");
+        self.notice = Some(format!("Filters: {label}"));
+    }
+
+    pub fn is_whole_word(&self) -> bool {
+        self.text_match.whole_word
+    }
+
+    /// Match filter text only as whole words, or anywhere again.
+    pub fn toggle_whole_word(&mut self) {
+        self.text_match.whole_word = !self.text_match.whole_word;
+        self.recompute_filter();
+        self.notice = Some(if self.text_match.whole_word {
+            "Filters: whole words".to_string()
+        } else {
+            "Filters: anywhere in a word".to_string()
+        });
+    }
+
+    pub fn is_highlight_only(&self) -> bool {
+        self.highlight_only
+    }
+
+    /// Lines in view the text filters match, while they only highlight.
+    pub fn highlighted_matches(&self) -> Option<usize> {
+        self.highlighted_matches
+    }
+
+    /// Make the text filters highlight their matches among all the lines,
+    /// or hide the lines that don't match again.
+    pub fn toggle_highlight_only(&mut self) {
+        self.highlight_only = !self.highlight_only;
+        self.recompute_filter();
+        self.notice = Some(if self.highlight_only {
+            "Filters: highlight matches, keep every line".to_string()
+        } else {
+            "Filters: hide lines that don't match".to_string()
+        });
+    }
+
+    /// Whether text filters are in place that `n`/`N` can jump between.
+    pub fn has_text_filter(&self) -> bool {
+        self.filter_error.is_none() && !self.active_filter_terms().is_empty()
+    }
+
+    /// Whether the line at original index `idx` is one the filters
+    /// highlight, while they only highlight.
+    pub fn is_highlight_hit(&self, idx: usize) -> bool {
+        self.highlight_only && self.highlight_hits.binary_search(&idx).is_ok()
+    }
+
+    /// Whether the line at view position `pos` matches the text filters.
+    /// Unless they only highlight or context lines are shown, every line in
+    /// view does.
+    fn is_match_at(&self, pos: usize) -> bool {
+        self.filtered_indices.get(pos).is_some_and(|idx| {
+            (!self.highlight_only || self.highlight_hits.binary_search(idx).is_ok())
+                && !self.is_context_line(*idx)
+        })
+    }
+
+    /// The view positions `match_positions` holds, worked out once per
+    /// filter change so the status bar's counter doesn't rescan each frame.
+    fn find_match_positions(&self) -> Vec<usize> {
+        if !self.highlight_only && self.context_only.is_empty() {
+            return Vec::new();
+        }
+        (0..self.filtered_indices.len())
+            .filter(|&p| self.is_match_at(p))
+            .collect()
+    }
+
+    /// View position of the current match: the cursor line in cursor mode,
+    /// otherwise the line last jumped to while it's still on screen.
+    fn current_match_position(&self) -> Option<usize> {
+        let pos = if self.is_cursor_mode() {
+            self.cursor_position
+        } else {
+            let idx = self.last_match?;
+            let pos = self.nearest_filtered_position(idx);
+            let on_screen = (self.scroll_offset..self.scroll_offset + self.viewport_height.max(1))
+                .contains(&pos);
+            if self.filtered_indices.get(pos) != Some(&idx) || !on_screen {
+                return None;
+            }
+            pos
+        };
+        self.is_match_at(pos).then_some(pos)
+    }
+
+    /// Which match the current one is and how many there are, as in
+    /// `match 12/87`, while on a match.
+    pub fn match_counter(&self) -> Option<(usize, usize)> {
+        if !self.has_text_filter() {
+            return None;
+        }
+        let pos = self.current_match_position()?;
+        let current = || self.match_positions.partition_point(|&p| p <= pos);
+        Some(match self.highlighted_matches {
+            Some(total) => (current(), total),
+            None if !self.context_only.is_empty() => (
+                current(),
+                self.filtered_indices.len() - self.context_only.len(),
+            ),
+            None => (pos + 1, self.filtered_indices.len()),
+        })
+    }
+
+    /// Jump to the `n`th matching line below the current one, wrapping
+    /// around at the bottom.
+    pub fn next_match(&mut self, n: usize) {
+        self.jump_to_match(n, true);
+    }
+
+    /// Jump to the `n`th matching line above the current one, wrapping
+    /// around at the top.
+    pub fn prev_match(&mut self, n: usize) {
+        self.jump_to_match(n, false);
+    }
+
+    fn jump_to_match(&mut self, n: usize, forward: bool) {
+        if !self.has_text_filter() {
+            self.notice = Some("No filter to jump between matches of".to_string());
+            return;
+        }
+        let len = self.filtered_indices.len();
+        // Without a current match, search from the top line on screen
+        let mut pos = self.current_match_position();
+        let mut wrapped = false;
+        for _ in 0..n.max(1) {
+            let found = if forward {
+                let start = pos.map_or(self.scroll_offset, |p| p + 1).min(len);
+                (start..len).find(|&p| self.is_match_at(p)).or_else(|| {
+                    wrapped = true;
+                    (0..start).find(|&p| self.is_match_at(p))
+                })
+            } else {
+                let end = pos.unwrap_or(self.scroll_offset).min(len);
+                (0..end).rev().find(|&p| self.is_match_at(p)).or_else(|| {
+                    wrapped = true;
+                    (end..len).rev().find(|&p| self.is_match_at(p))
+                })
+            };
+            let Some(found) = found else {
+                self.notice = Some("No matches".to_string());
+                return;
+            };
+            pos = Some(found);
+        }
+        let Some(pos) = pos else {
+            return;
+        };
+        self.last_match = Some(self.filtered_indices[pos]);
+        if self.is_cursor_mode() {
+            self.cursor_position = pos;
+            self.scroll_to_cursor();
+        } else {
+            self.scroll_offset = pos;
+            self.clamp_scroll();
+        }
+        if wrapped {
+            self.notice = Some(if forward {
+                "Search wrapped to the top".to_string()
+            } else {
+                "Search wrapped to the bottom".to_string()
+            });
+        }
+    }
+
+    /// Switch the filter between plain text and a regex by adding or
+    /// removing the `re:` prefix, keeping the edit cursor on the same character.
+    pub fn toggle_filter_regex(&mut self) {
+        let prefix_len = REGEX_PREFIX.chars().count();
+        if let Some(rest) = self.filter_pattern.strip_prefix(REGEX_PREFIX) {
+            self.filter_pattern = rest.to_string();
+            self.filter_cursor = self.filter_cursor.saturating_sub(prefix_len);
+        } else {
+            self.filter_pattern.insert_str(0, REGEX_PREFIX);
+            self.filter_cursor += prefix_len;
         }
+        self.recompute_filter();
+    }
+
+    fn recompute_filter(&mut self) {
+        let terms = self.active_filter_terms();
+        let started = Instant::now();
+        let result = filter_lines_matching(
+            &self.parsed_lines,
+            &terms,
+            self.min_level,
+            &self.filter_fuzzy,
+            self.text_match,
+        );
+        let mut warning = FilterWarning::check(&terms, &result, started.elapsed());
+        let (indices, matched) = if self.highlight_only {
+            // Keeping every line is the point here; only slowness is news
+            warning = warning.filter(|w| matches!(w, FilterWarning::Slow(_)));
+            (
+                self.level_filtered(),
+                Some(self.fold_continuations(result.indices)),
+            )
+        } else {
+            (result.indices, None)
+        };
+        let indices = self.narrow(indices);
+        let indices = self.add_context(indices);
+        let mut indices = self.sample(indices);
+        self.sort_indices(&mut indices);
+        let highlighted = matched.as_ref().map(|m| {
+            indices
+                .iter()
+                .filter(|i| m.binary_search(i).is_ok())
+                .count()
+        });
+        // Remember which original lines were on screen so the viewport can be
+        // re-anchored instead of jumping back to the top
+        let was_at_bottom = self.is_at_bottom();
+        let top_anchor = self.filtered_indices.get(self.scroll_offset).copied();
+        let cursor_anchor = self.filtered_indices.get(self.cursor_position).copied();
+
         self.filtered_indices = indices;
         self.is_fuzzy = result.is_fuzzy;
-        self.scroll_offset = 0;
+        self.filter_error = result.error;
+        self.filter_warning = warning;
+        self.highlighted_matches = highlighted;
+        self.highlight_hits = matched.unwrap_or_default();
+        self.match_positions = self.find_match_positions();
+        self.unmatched_arrivals = 0;
+        self.muted_lines = self
+            .parsed_lines
+            .iter()
+            .filter(|p| self.is_muted(p))
+            .count();
+
+        if was_at_bottom {
+            self.scroll_to_bottom();
+            self.clamp_scroll();
+        } else {
+            self.scroll_offset = top_anchor.map_or(0, |idx| self.nearest_filtered_position(idx));
+            self.clamp_scroll();
+        }
         if self.mode == AppMode::Cursor {
-            self.cursor_position = 0;
+            self.cursor_position =
+                cursor_anchor.map_or(0, |idx| self.nearest_filtered_position(idx));
+            self.scroll_to_cursor();
+        }
+    }
+
+    /// Lines the level filter keeps, ignoring the text filters.
+    fn level_filtered(&self) -> Vec<usize> {
+        filter_lines_matching(
+            &self.parsed_lines,
+            &[],
+            self.min_level,
+            &self.filter_fuzzy,
+            self.text_match,
+        )
+        .indices
+    }
+
+    /// Position in `filtered_indices` of the visible line closest to original
+    /// index `idx` (0 when nothing is visible).
+    fn nearest_filtered_position(&self, idx: usize) -> usize {
+        if self.sort.is_some() {
+            // Not in line order, so no binary search
+            return self
+                .filtered_indices
+                .iter()
+                .enumerate()
+                .min_by_key(|&(_, &i)| i.abs_diff(idx))
+                .map_or(0, |(pos, _)| pos);
+        }
+        let after = self.filtered_indices.partition_point(|&i| i < idx);
+        if after == self.filtered_indices.len() {
+            return after.saturating_sub(1);
+        }
+        if after > 0 && idx - self.filtered_indices[after - 1] < self.filtered_indices[after] - idx
+        {
+            after - 1
+        } else {
+            after
+        }
+    }
+
+    // Follow mode methods
+
+    /// Returns true if the scroll position is at or past the bottom of the content.
+    pub fn is_at_bottom(&self) -> bool {
+        let entries_from_end = self.viewport_entries_from_end();
+        let max = self.filtered_indices.len().saturating_sub(entries_from_end);
+        self.scroll_offset >= max
+    }
+
+    /// Parse and append new lines, preserving scroll position.
+    /// Auto-scrolls to bottom if the user was already at the bottom.
+    pub fn append_lines(&mut self, new_raw: Vec<String>) {
+        // Re-detect format if app started with no lines (stdin follow mode)
+        if self.parsed_lines.is_empty() && !new_raw.is_empty() {
+            self.format = detect_format(&new_raw);
+        }
+
+        let was_at_bottom = self.is_at_bottom();
+
+        let new_parsed: Vec<ParsedLine> = new_raw
+            .iter()
+            .map(|line| {
+                let mut parsed = parse_line(line, self.format);
+                self.provenance.apply(&mut parsed);
+                derived::apply(&self.derived_fields, &mut parsed);
+                parsed
+            })
+            .collect();
+
+        // Update time index incrementally
+        if let Some(ref mut idx) = self.time_index {
+            idx.append(&new_parsed);
+            // Recompute sparkline with current width
+            if self.sparkline_width > 0 {
+                self.sparkline_data = compute_sparkline(idx, self.sparkline_width);
+            }
+        }
+
+        let first_new = self.parsed_lines.len();
+        if let Some(ref mut bad) = self.malformed {
+            bad.extend(
+                new_raw
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, raw)| Some((first_new + i, parse_error(raw, self.format)?))),
+            );
+        }
+        self.parsed_lines.extend(new_parsed);
+        self.refresh_time_cursor_levels();
+        self.extend_entries(first_new);
+        for idx in first_new..self.parsed_lines.len() {
+            if let Some(rule) = match_rule(&self.marker_rules, &self.parsed_lines[idx]) {
+                self.markers.push((idx, rule));
+            }
+        }
+
+        if self.can_filter_appended() {
+            self.filter_appended(first_new);
+        } else {
+            self.refilter_all();
+        }
+
+        self.muted_lines += self.parsed_lines[first_new..]
+            .iter()
+            .filter(|p| self.is_muted(p))
+            .count();
+
+        // Lines the filters hide don't move the view, only get counted
+        let arrived = self.parsed_lines[first_new..]
+            .iter()
+            .filter(|line| !is_control_line(&line.raw))
+            .count();
+        let shown = self
+            .filtered_indices
+            .iter()
+            .filter(|&&idx| idx >= first_new)
+            .count();
+        self.unmatched_arrivals += arrived.saturating_sub(shown);
+        if was_at_bottom && shown > 0 {
+            self.scroll_to_bottom();
+        }
+    }
+
+    /// `matches` with the `context_lines` lines before and after each, taken
+    /// from the lines the view would show without the text filters. The
+    /// added lines are kept in `context_only`. Context is left out when
+    /// there's no text filter, and when sorting, sampling or highlighting
+    /// only, where neighbors aren't next to each other or all lines show.
+    fn add_context(&mut self, matches: Vec<usize>) -> Vec<usize> {
+        self.context_only.clear();
+        let n = self.context_lines;
+        if n == 0
+            || self.highlight_only
+            || self.sampling
+            || self.sort.is_some()
+            || self.active_filter_terms().is_empty()
+        {
+            return matches;
+        }
+        let base = self.narrow(self.level_filtered());
+        let mut shown = vec![false; base.len()];
+        for idx in &matches {
+            if let Ok(pos) = base.binary_search(idx) {
+                let end = (pos + n).min(base.len() - 1);
+                shown[pos.saturating_sub(n)..=end].fill(true);
+            }
+        }
+        let indices: Vec<usize> = base
+            .into_iter()
+            .zip(shown)
+            .filter_map(|(idx, shown)| shown.then_some(idx))
+            .collect();
+        self.context_only = indices
+            .iter()
+            .copied()
+            .filter(|idx| matches.binary_search(idx).is_err())
+            .collect();
+        indices
+    }
+
+    /// Whether original line `idx` is in view only as context for a match.
+    pub fn is_context_line(&self, idx: usize) -> bool {
+        self.context_only.binary_search(&idx).is_ok()
+    }
+
+    /// Lines shown around each match, as set by `c`.
+    pub fn context_lines(&self) -> usize {
+        self.context_lines
+    }
+
+    /// Step through [`CONTEXT_STEPS`] context lines around matches.
+    pub fn cycle_context_lines(&mut self) {
+        let next = CONTEXT_STEPS
+            .iter()
+            .position(|&n| n == self.context_lines)
+            .map_or(0, |i| (i + 1) % CONTEXT_STEPS.len());
+        self.context_lines = CONTEXT_STEPS[next];
+        self.recompute_filter();
+        self.notice = Some(match self.context_lines {
+            0 => "Context: off".to_string(),
+            n => format!("Context: {n} lines around each match"),
+        });
+    }
+
+    /// Whether appended lines can be filtered on their own and added to the
+    /// end of the view: nothing depends on the lines already there (sort
+    /// order, sampling, highlight counts), and the text filters matched
+    /// exactly, each deciding line by line.
+    fn can_filter_appended(&self) -> bool {
+        let terms = self.active_filter_terms();
+        self.sort.is_none()
+            && !self.sampling
+            && !self.highlight_only
+            && self.context_lines == 0
+            && !self.is_fuzzy
+            && self.filter_error.is_none()
+            && (terms.is_empty() || !self.filtered_indices.is_empty())
+            && terms.iter().all(|term| is_context_free(term))
+    }
+
+    /// Filter the lines from `first_new` on and add those kept to the view.
+    fn filter_appended(&mut self, first_new: usize) {
+        let result = filter_lines_matching(
+            &self.parsed_lines[first_new..],
+            &self.active_filter_terms(),
+            self.min_level,
+            &self.filter_fuzzy,
+            self.text_match,
+        );
+        // Exact matches exist among the earlier lines, so a fuzzy batch
+        // would have matched nothing over the whole log
+        if result.is_fuzzy {
+            return;
+        }
+        let indices = result.indices.into_iter().map(|i| i + first_new).collect();
+        let mut indices = self.narrow(indices);
+        // A continuation of the last entry in view is already shown with it
+        if let Some(&last) = self.filtered_indices.last() {
+            indices.retain(|&i| i > last);
+        }
+        self.unsampled_count += indices.len();
+        self.filtered_indices.extend(indices);
+    }
+
+    /// Filter every line again, as when the filters change but keeping the
+    /// view where it is.
+    fn refilter_all(&mut self) {
+        let result = filter_lines_matching(
+            &self.parsed_lines,
+            &self.active_filter_terms(),
+            self.min_level,
+            &self.filter_fuzzy,
+            self.text_match,
+        );
+        let (indices, matched) = if self.highlight_only {
+            (
+                self.level_filtered(),
+                Some(self.fold_continuations(result.indices)),
+            )
+        } else {
+            (result.indices, None)
+        };
+        let indices = self.narrow(indices);
+        let indices = self.add_context(indices);
+        let mut indices = self.sample(indices);
+        self.sort_indices(&mut indices);
+        self.highlighted_matches = matched.as_ref().map(|m| {
+            indices
+                .iter()
+                .filter(|i| m.binary_search(i).is_ok())
+                .count()
+        });
+        self.highlight_hits = matched.unwrap_or_default();
+        self.filtered_indices = indices;
+        self.match_positions = self.find_match_positions();
+        self.is_fuzzy = result.is_fuzzy;
+        self.filter_error = result.error;
+    }
+
+    /// How many lines arrived in follow mode that the filters hid, since
+    /// they last changed.
+    pub fn unmatched_arrivals(&self) -> usize {
+        self.unmatched_arrivals
+    }
+
+    /// `indices` less the lines hidden by anything but the text filters and
+    /// minimum level: unchecked levels, the time range, similar lines,
+    /// collapsed noise, malformed-only, ignore patterns, and control lines.
+    fn narrow(&self, indices: Vec<usize>) -> Vec<usize> {
+        let mut indices = self.fold_continuations(indices);
+        if !self.hidden_levels.is_empty() {
+            indices.retain(|&i| {
+                self.parsed_lines[i]
+                    .level
+                    .is_none_or(|level| !self.hidden_levels.contains(&level))
+            });
+        }
+        if let (Some(index), Some(range)) = (&self.time_index, &self.time_range) {
+            indices = filter_by_time_range(index, range, &indices);
+        }
+        if let Some(ref tmpl) = self.similar_template {
+            indices.retain(|&i| self.parsed_lines[i].template == *tmpl);
+        }
+        if !self.hidden_templates.is_empty() {
+            indices.retain(|&i| {
+                !self
+                    .hidden_templates
+                    .contains(&self.parsed_lines[i].template)
+            });
+        }
+        if self.malformed_only
+            && let Some(ref bad) = self.malformed
+        {
+            indices.retain(|&i| bad.binary_search_by_key(&i, |&(j, _)| j).is_ok());
+        }
+        if let Some(ref ignore) = self.ignore_patterns {
+            indices.retain(|&i| !ignore.is_match(&self.parsed_lines[i].raw));
+        }
+        if self.blank_lines == BlankLines::Hidden {
+            indices.retain(|&i| !is_blank(&self.parsed_lines[i].raw));
+        }
+        indices.retain(|&i| !is_control_line(&self.parsed_lines[i].raw));
+        indices
+    }
+
+    pub fn set_follow_mode(&mut self, enabled: bool) {
+        self.follow_mode = enabled;
+    }
+
+    pub fn is_follow_mode(&self) -> bool {
+        self.follow_mode
+    }
+
+    pub fn toggle_follow_pause(&mut self) {
+        self.follow_paused = !self.follow_paused;
+    }
+
+    pub fn is_follow_paused(&self) -> bool {
+        self.follow_paused
+    }
+
+    // Level filter methods
+
+    pub fn min_level(&self) -> Option<LogLevel> {
+        self.min_level
+    }
+
+    /// Returns counts of each log level present in the full (unfiltered) dataset.
+    /// Sorted by severity: Trace, Debug, Info, Warn, Error, Fatal. Only non-zero levels included.
+    pub fn level_counts(&self) -> Vec<(LogLevel, usize)> {
+        use std::collections::BTreeMap;
+        let mut counts: BTreeMap<LogLevel, usize> = BTreeMap::new();
+        for line in &self.parsed_lines {
+            if let Some(level) = line.level {
+                *counts.entry(level).or_insert(0) += 1;
+            }
+        }
+        counts.into_iter().collect()
+    }
+
+    /// Set the level filter outright (`set_min_level` toggles).
+    pub fn set_level_filter(&mut self, level: Option<LogLevel>) {
+        self.min_level = level;
+        self.recompute_filter();
+    }
+
+    /// Set min_level to the given level, or clear it if already set to that level.
+    pub fn set_min_level(&mut self, level: LogLevel) {
+        if self.min_level == Some(level) {
+            self.min_level = None;
+        } else {
+            self.min_level = Some(level);
+        }
+        self.recompute_filter();
+    }
+
+    /// Raise the minimum level (hide more). Cycles: None → second-lowest → … → highest → None.
+    pub fn cycle_level_up(&mut self) {
+        if self.available_levels.len() <= 1 {
+            return;
+        }
+        self.min_level = match self.min_level {
+            None => {
+                // Skip the lowest level (min=lowest ≡ no filter), go to second
+                self.available_levels.get(1).copied()
+            }
+            Some(current) => {
+                // Find the next higher level
+                match self.available_levels.iter().position(|&l| l == current) {
+                    Some(idx) if idx + 1 < self.available_levels.len() => {
+                        Some(self.available_levels[idx + 1])
+                    }
+                    _ => None, // wrap around to show all
+                }
+            }
+        };
+        self.recompute_filter();
+    }
+
+    /// Lower the minimum level (show more). Cycles: None → highest → … → second-lowest → None.
+    pub fn cycle_level_down(&mut self) {
+        if self.available_levels.len() <= 1 {
+            return;
+        }
+        self.min_level = match self.min_level {
+            None => {
+                // Start from the highest level
+                self.available_levels.last().copied()
+            }
+            Some(current) => {
+                match self.available_levels.iter().position(|&l| l == current) {
+                    Some(idx) if idx > 1 => Some(self.available_levels[idx - 1]),
+                    _ => None, // at second-lowest or lowest, wrap to show all
+                }
+            }
+        };
+        self.recompute_filter();
+    }
+
+    /// Levels unchecked in the level picker.
+    pub fn hidden_levels(&self) -> &[LogLevel] {
+        &self.hidden_levels
+    }
+
+    /// Hide lines at exactly these levels, leaving the minimum level alone.
+    pub fn set_hidden_levels(&mut self, mut levels: Vec<LogLevel>) {
+        levels.sort_unstable();
+        levels.dedup();
+        self.hidden_levels = levels;
+        self.recompute_filter();
+    }
+
+    /// Whether lines at `level` pass both the minimum level and the picker.
+    pub fn is_level_shown(&self, level: LogLevel) -> bool {
+        self.min_level.is_none_or(|min| level >= min) && !self.hidden_levels.contains(&level)
+    }
+
+    /// Levels present in the log, least severe first: the level picker's rows.
+    pub fn available_levels(&self) -> &[LogLevel] {
+        &self.available_levels
+    }
+
+    /// Open the picker for showing and hiding levels one by one.
+    pub fn open_level_picker(&mut self) {
+        if self.available_levels.is_empty() {
+            self.notice = Some("No log levels to pick from".to_string());
+            return;
+        }
+        self.level_picker = Some(0);
+    }
+
+    pub fn close_level_picker(&mut self) {
+        self.level_picker = None;
+    }
+
+    /// Selected row of the level picker, while it's open.
+    pub fn level_picker_selected(&self) -> Option<usize> {
+        self.level_picker
+    }
+
+    pub fn level_picker_down(&mut self) {
+        if let Some(sel) = self.level_picker.as_mut()
+            && *sel + 1 < self.available_levels.len()
+        {
+            *sel += 1;
+        }
+    }
+
+    pub fn level_picker_up(&mut self) {
+        if let Some(sel) = self.level_picker.as_mut() {
+            *sel = sel.saturating_sub(1);
+        }
+    }
+
+    /// Show or hide the selected level. Showing one below the minimum level
+    /// turns the minimum into unchecked levels first, so the others below
+    /// it stay hidden.
+    pub fn level_picker_toggle(&mut self) {
+        let Some(&level) = self
+            .level_picker
+            .and_then(|sel| self.available_levels.get(sel))
+        else {
+            return;
+        };
+        let mut hidden = self.hidden_levels.clone();
+        if self.is_level_shown(level) {
+            hidden.push(level);
+        } else {
+            if let Some(min) = self.min_level.take() {
+                hidden.extend(self.available_levels.iter().filter(|&&l| l < min));
+            }
+            hidden.retain(|&l| l != level);
+        }
+        self.set_hidden_levels(hidden);
+    }
+
+    // Context menu methods
+
+    pub fn mode(&self) -> AppMode {
+        self.mode
+    }
+
+    pub fn context_menu(&self) -> Option<&ContextMenuState> {
+        self.context_menu.as_ref()
+    }
+
+    pub fn open_context_menu(
+        &mut self,
+        token_value: String,
+        token_kind: TokenKind,
+        position: (u16, u16),
+    ) {
+        let mut items = vec![MenuAction::FilterByValue];
+        match token_kind {
+            TokenKind::Ip => items.push(MenuAction::LookupAbuseIPDB),
+            TokenKind::Url => items.push(MenuAction::OpenInBrowser),
+            TokenKind::Encoded => items.push(MenuAction::Decode),
+            _ => {}
+        }
+        items.extend(self.pivots.iter().map(|p| MenuAction::Pivot {
+            name: p.name.clone(),
+            url: p.url.clone(),
+        }));
+        items.extend(self.splits.iter().map(|c| MenuAction::Split {
+            name: c.name.clone(),
+            command: c.command.clone(),
+        }));
+        if token_kind == TokenKind::KeyValue {
+            items.push(MenuAction::SortByField);
+            items.push(MenuAction::ValueDistribution);
+        }
+        self.context_menu = Some(ContextMenuState {
+            token_value,
+            token_kind,
+            items,
+            selected: 0,
+            position,
+        });
+        self.mode = AppMode::ContextMenu;
+    }
+
+    /// [`open_context_menu`](Self::open_context_menu) for a token on
+    /// original line `line`, adding what can be done with the line itself.
+    pub fn open_line_context_menu(
+        &mut self,
+        token_value: String,
+        token_kind: TokenKind,
+        position: (u16, u16),
+        line: usize,
+    ) {
+        self.open_context_menu(token_value, token_kind, position);
+        if let Some(menu) = self.context_menu.as_mut()
+            && self
+                .parsed_lines
+                .get(line)
+                .is_some_and(|p| !p.template.is_empty())
+        {
+            menu.items.push(MenuAction::MuteTemplate(line));
+        }
+    }
+
+    pub fn close_context_menu(&mut self) {
+        self.context_menu = None;
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn menu_up(&mut self) {
+        if let Some(ref mut menu) = self.context_menu {
+            menu.selected = menu.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn menu_down(&mut self) {
+        if let Some(ref mut menu) = self.context_menu
+            && menu.selected + 1 < menu.items.len()
+        {
+            menu.selected += 1;
+        }
+    }
+
+    pub fn execute_menu_action(&mut self) -> Option<(MenuAction, String)> {
+        let mut menu = self.context_menu.take()?;
+        self.mode = AppMode::Normal;
+        let action = menu.items.swap_remove(menu.selected);
+        Some((action, menu.token_value))
+    }
+
+    pub fn execute_menu_item(&mut self, index: usize) -> Option<(MenuAction, String)> {
+        let mut menu = self.context_menu.take()?;
+        self.mode = AppMode::Normal;
+        if index >= menu.items.len() {
+            return None;
+        }
+        let action = menu.items.swap_remove(index);
+        Some((action, menu.token_value))
+    }
+
+    // Value distribution methods
+
+    /// Count the values of `key` over the filtered lines. Nothing opens when
+    /// no visible line has the key.
+    pub fn open_value_distribution(&mut self, key: &str) {
+        let dist = value_distribution(&self.parsed_lines, &self.filtered_indices, key, TOP_VALUES);
+        if dist.values.is_empty() {
+            return;
+        }
+        let summary = numeric_summary(&self.parsed_lines, &self.filtered_indices, key);
+        self.value_dist = Some(ValueDistState {
+            dist,
+            selected: 0,
+            summary,
+            by_value: false,
+        });
+    }
+
+    /// Switch the popup between most-common-first and smallest-value-first.
+    /// Only numeric keys can be sorted by value.
+    pub fn toggle_value_dist_sort(&mut self) {
+        let Some(state) = self.value_dist.as_mut() else {
+            return;
+        };
+        if state.summary.is_none() {
+            return;
+        }
+        state.by_value = !state.by_value;
+        state.selected = 0;
+        if state.by_value {
+            sort_by_quantity(&mut state.dist.values);
+        } else {
+            state.dist = value_distribution(
+                &self.parsed_lines,
+                &self.filtered_indices,
+                &state.dist.key,
+                TOP_VALUES,
+            );
+        }
+    }
+
+    pub fn value_distribution(&self) -> Option<&ValueDistState> {
+        self.value_dist.as_ref()
+    }
+
+    pub fn close_value_distribution(&mut self) {
+        self.value_dist = None;
+    }
+
+    pub fn value_dist_down(&mut self) {
+        if let Some(state) = self.value_dist.as_mut()
+            && state.selected + 1 < state.dist.values.len()
+        {
+            state.selected += 1;
+        }
+    }
+
+    pub fn value_dist_up(&mut self) {
+        if let Some(state) = self.value_dist.as_mut() {
+            state.selected = state.selected.saturating_sub(1);
+        }
+    }
+
+    /// Close the popup and filter by the value in row `index` (the
+    /// highlighted row when None).
+    pub fn apply_value_distribution(&mut self, index: Option<usize>) {
+        let Some(state) = self.value_dist.take() else {
+            return;
+        };
+        if let Some(value) = state.dist.values.get(index.unwrap_or(state.selected)) {
+            self.set_filter(value.filter.clone());
+        }
+    }
+
+    // Decoded token methods
+
+    /// Open the decoded popup for a JWT or base64 `token`.
+    pub fn open_decoded(&mut self, token: &str) {
+        match decode_token(token) {
+            Some(decoded) => self.decoded = Some(DecodedState { decoded, scroll: 0 }),
+            None => self.set_notice("Not a JWT or base64 text".to_string()),
+        }
+    }
+
+    pub fn decoded(&self) -> Option<&DecodedState> {
+        self.decoded.as_ref()
+    }
+
+    pub fn close_decoded(&mut self) {
+        self.decoded = None;
+    }
+
+    /// Scroll the decoded popup; the renderer stops at the last row.
+    pub fn decoded_scroll(&mut self, delta: isize) {
+        if let Some(state) = self.decoded.as_mut() {
+            state.scroll = state.scroll.saturating_add_signed(delta);
+        }
+    }
+
+    /// Keep the popup's scroll within its `rows` rows shown `height` at a time.
+    pub fn clamp_decoded_scroll(&mut self, rows: usize, height: usize) {
+        if let Some(state) = self.decoded.as_mut() {
+            state.scroll = state.scroll.min(rows.saturating_sub(height));
+        }
+    }
+
+    /// Replace the text filters: the last of `filters` is the one being
+    /// edited, the others are stacked before it.
+    pub fn set_filter_stack(&mut self, filters: &[String]) {
+        self.filter_terms = filters.to_vec();
+        self.filter_pattern = self.filter_terms.pop().unwrap_or_default();
+        self.recompute_filter();
+    }
+
+    /// Replace the text filter, including any stacked ones.
+    pub fn set_filter(&mut self, pattern: String) {
+        self.filter_pattern = pattern;
+        self.filter_terms.clear();
+        self.recompute_filter();
+    }
+
+    /// Filter to lines structurally similar to the current cursor line.
+    pub fn filter_by_similar(&mut self) {
+        if let Some(&idx) = self.filtered_indices.get(self.cursor_position) {
+            let tmpl = self.parsed_lines[idx].template.clone();
+            if tmpl.is_empty() {
+                self.notice = Some("Nothing to match on an empty line".to_string());
+                return;
+            }
+            self.similar_template = Some(tmpl);
+            self.mode = AppMode::Normal;
+            self.recompute_filter();
+        }
+    }
+
+    /// Drop the text, level, time range and similar filters in one go.
+    pub fn clear_all_filters(&mut self) {
+        self.filter_pattern.clear();
+        self.filter_terms.clear();
+        self.min_level = None;
+        self.hidden_levels.clear();
+        self.time_range = None;
+        self.similar_template = None;
+        self.hidden_templates.clear();
+        self.malformed_only = false;
+        self.sort = None;
+        self.recompute_filter();
+    }
+
+    pub fn set_esc_action(&mut self, action: EscAction) {
+        self.esc_action = action;
+    }
+
+    /// Esc in normal mode, as configured by `[keys] esc`.
+    pub fn escape(&mut self) {
+        match self.esc_action {
+            EscAction::Step => {
+                if self.is_similar_filter() {
+                    self.clear_similar();
+                } else if !self.hidden_templates.is_empty() {
+                    self.show_noise();
+                } else if self.malformed_only {
+                    self.toggle_malformed_only();
+                } else if self.time_range.is_some() {
+                    self.clear_time_range();
+                } else if !self.filter_pattern.is_empty() {
+                    self.clear_filter();
+                } else if self.filter_terms.pop().is_some() {
+                    self.recompute_filter();
+                } else if self.sort.is_some() {
+                    self.clear_sort();
+                }
+            }
+            EscAction::ClearAll => self.clear_all_filters(),
+            EscAction::None => {}
+        }
+    }
+
+    /// The constraints on the view with their chip labels, in the order the
+    /// breadcrumb row shows them: `/api`, `level≥WRN`, `08:10–08:25`,
+    /// `template`.
+    pub fn breadcrumbs(&self) -> Vec<(Breadcrumb, String)> {
+        let mut crumbs: Vec<(Breadcrumb, String)> = self
+            .filter_terms
+            .iter()
+            .enumerate()
+            .map(|(i, term)| (Breadcrumb::Term(i), format!("/{term}")))
+            .collect();
+        if !self.filter_pattern.is_empty() {
+            crumbs.push((Breadcrumb::Pattern, format!("/{}", self.filter_pattern)));
+        }
+        if let Some(level) = self.min_level {
+            crumbs.push((Breadcrumb::Level, format!("level≥{}", level.short_name())));
+        }
+        if !self.hidden_levels.is_empty() {
+            let names: Vec<&str> = self.hidden_levels.iter().map(|l| l.short_name()).collect();
+            crumbs.push((
+                Breadcrumb::HiddenLevels,
+                format!("level≠{}", names.join(",")),
+            ));
+        }
+        if let Some(range) = &self.time_range {
+            let multi_day = is_multi_day(range.start, range.end);
+            crumbs.push((
+                Breadcrumb::TimeRange,
+                format!(
+                    "{}–{}",
+                    format_sparkline_time(range.start, multi_day),
+                    format_sparkline_time(range.end, multi_day)
+                ),
+            ));
+        }
+        if self.similar_template.is_some() {
+            crumbs.push((Breadcrumb::Similar, "template".to_string()));
+        }
+        if !self.hidden_templates.is_empty() {
+            crumbs.push((
+                Breadcrumb::Noise,
+                format!("muted −{}", self.hidden_templates.len()),
+            ));
+        }
+        if self.malformed_only {
+            crumbs.push((Breadcrumb::Malformed, "malformed".to_string()));
+        }
+        if let Some(sort) = &self.sort {
+            crumbs.push((Breadcrumb::Sort, format!("sort {}", sort.label())));
+        }
+        if let Some(n) = self.sampling() {
+            crumbs.push((Breadcrumb::Sample, format!("1/{n}")));
+        }
+        crumbs
+    }
+
+    /// Lift the `n`th (1-based) breadcrumb's constraint, leaving the others.
+    pub fn remove_breadcrumb(&mut self, n: usize) {
+        let Some((crumb, label)) = n
+            .checked_sub(1)
+            .and_then(|i| self.breadcrumbs().into_iter().nth(i))
+        else {
+            return;
+        };
+        match crumb {
+            Breadcrumb::Term(i) => {
+                self.filter_terms.remove(i);
+                self.recompute_filter();
+            }
+            Breadcrumb::Pattern => {
+                self.filter_pattern.clear();
+                self.recompute_filter();
+            }
+            Breadcrumb::Level => self.set_level_filter(None),
+            Breadcrumb::HiddenLevels => self.set_hidden_levels(Vec::new()),
+            Breadcrumb::TimeRange => self.clear_time_range(),
+            Breadcrumb::Similar => self.clear_similar(),
+            Breadcrumb::Noise => self.show_noise(),
+            Breadcrumb::Malformed => self.toggle_malformed_only(),
+            Breadcrumb::Sort => self.clear_sort(),
+            Breadcrumb::Sample => self.toggle_sampling(),
+        }
+        self.notice = Some(format!("Removed {label}"));
+    }
+
+    pub fn clear_similar(&mut self) {
+        self.similar_template = None;
+        self.recompute_filter();
+    }
+
+    pub fn is_similar_filter(&self) -> bool {
+        self.similar_template.is_some()
+    }
+
+    // Malformed line methods
+
+    fn ensure_malformed(&mut self) {
+        if self.malformed.is_none() {
+            let bad = self
+                .parsed_lines
+                .iter()
+                .enumerate()
+                .filter_map(|(i, line)| Some((i, parse_error(&line.raw, self.format)?)))
+                .collect();
+            self.malformed = Some(bad);
         }
     }
 
-    // Follow mode methods
-
-    /// Returns true if the scroll position is at or past the bottom of the content.
-    pub fn is_at_bottom(&self) -> bool {
-        let entries_from_end = self.viewport_entries_from_end();
-        let max = self.filtered_indices.len().saturating_sub(entries_from_end);
-        self.scroll_offset >= max
+    /// Show only lines that failed to parse, or everything again.
+    pub fn toggle_malformed_only(&mut self) {
+        self.ensure_malformed();
+        self.malformed_only = !self.malformed_only;
+        self.recompute_filter();
     }
 
-    /// Parse and append new lines, preserving scroll position.
-    /// Auto-scrolls to bottom if the user was already at the bottom.
-    pub fn append_lines(&mut self, new_raw: Vec<String>) {
-        // Re-detect format if app started with no lines (stdin follow mode)
-        if self.parsed_lines.is_empty() && !new_raw.is_empty() {
-            self.format = detect_format(&new_raw);
+    pub fn is_malformed_only(&self) -> bool {
+        self.malformed_only
+    }
+
+    /// List why lines failed to parse, with counts. With none, just says so.
+    pub fn open_malformed_panel(&mut self) {
+        self.ensure_malformed();
+        let bad = self.malformed.as_deref().unwrap_or_default();
+        let mut rows: Vec<MalformedReason> = Vec::new();
+        let mut pos: HashMap<&str, usize> = HashMap::new();
+        for (idx, reason) in bad {
+            match pos.get(reason.as_str()) {
+                Some(&p) => rows[p].count += 1,
+                None => {
+                    pos.insert(reason, rows.len());
+                    rows.push(MalformedReason {
+                        reason: reason.clone(),
+                        count: 1,
+                        first_line: *idx,
+                    });
+                }
+            }
+        }
+        if rows.is_empty() {
+            self.notice = Some("No malformed lines".to_string());
+            return;
         }
+        // Stable, so equal counts stay in first-seen order
+        rows.sort_by_key(|r| std::cmp::Reverse(r.count));
+        self.malformed_panel = Some((rows, 0));
+    }
 
-        let was_at_bottom = self.is_at_bottom();
+    pub fn close_malformed_panel(&mut self) {
+        self.malformed_panel = None;
+    }
 
-        let new_parsed: Vec<ParsedLine> = new_raw
-            .iter()
-            .map(|line| parse_line(line, self.format))
-            .collect();
+    /// Panel rows and the selected one, while it's open.
+    pub fn malformed_panel(&self) -> Option<(&[MalformedReason], usize)> {
+        self.malformed_panel
+            .as_ref()
+            .map(|(rows, sel)| (rows.as_slice(), *sel))
+    }
 
-        // Update time index incrementally
-        if let Some(ref mut idx) = self.time_index {
-            idx.append(&new_parsed);
-            // Recompute sparkline with current width
-            if self.sparkline_width > 0 {
-                self.sparkline_data = compute_sparkline(idx, self.sparkline_width);
-            }
+    pub fn malformed_panel_down(&mut self) {
+        if let Some((rows, sel)) = self.malformed_panel.as_mut()
+            && *sel + 1 < rows.len()
+        {
+            *sel += 1;
         }
+    }
 
-        self.parsed_lines.extend(new_parsed);
+    pub fn malformed_panel_up(&mut self) {
+        if let Some((_, sel)) = self.malformed_panel.as_mut() {
+            *sel = sel.saturating_sub(1);
+        }
+    }
 
-        // Recompute filtered indices from scratch (filter or level filter may be active)
-        let result = filter_lines(&self.parsed_lines, &self.filter_pattern, self.min_level);
-        let mut indices = result.indices;
-        if let (Some(index), Some(range)) = (&self.time_index, &self.time_range) {
-            indices = filter_by_time_range(index, range, &indices);
+    /// Close the panel and jump to the first line with the selected reason.
+    pub fn malformed_panel_jump(&mut self) {
+        if let Some((rows, sel)) = self.malformed_panel.take()
+            && let Some(row) = rows.get(sel)
+        {
+            self.goto_line(row.first_line + 1);
         }
-        if let Some(ref tmpl) = self.similar_template {
-            indices.retain(|&i| self.parsed_lines[i].template == *tmpl);
+    }
+
+    // Line length methods
+
+    /// Show the length distribution of the filtered lines and the longest ones.
+    pub fn open_line_stats(&mut self) {
+        match length_stats(&self.parsed_lines, &self.filtered_indices, TOP_LONGEST) {
+            Some(stats) => self.line_stats = Some((stats, 0)),
+            None => self.notice = Some("No lines".to_string()),
         }
-        self.filtered_indices = indices;
-        self.is_fuzzy = result.is_fuzzy;
+    }
 
-        if was_at_bottom {
-            self.scroll_to_bottom();
+    pub fn close_line_stats(&mut self) {
+        self.line_stats = None;
+    }
+
+    /// Panel statistics and the selected longest line, while it's open.
+    pub fn line_stats(&self) -> Option<(&LengthStats, usize)> {
+        self.line_stats.as_ref().map(|(stats, sel)| (stats, *sel))
+    }
+
+    pub fn line_stats_down(&mut self) {
+        if let Some((stats, sel)) = self.line_stats.as_mut()
+            && *sel + 1 < stats.longest.len()
+        {
+            *sel += 1;
         }
     }
 
-    pub fn set_follow_mode(&mut self, enabled: bool) {
-        self.follow_mode = enabled;
+    pub fn line_stats_up(&mut self) {
+        if let Some((_, sel)) = self.line_stats.as_mut() {
+            *sel = sel.saturating_sub(1);
+        }
     }
 
-    pub fn is_follow_mode(&self) -> bool {
-        self.follow_mode
+    /// Close the panel and jump to the selected long line.
+    pub fn line_stats_jump(&mut self) {
+        if let Some((stats, sel)) = self.line_stats.take()
+            && let Some(&(idx, _)) = stats.longest.get(sel)
+        {
+            self.goto_line(idx + 1);
+        }
     }
 
-    pub fn toggle_follow_pause(&mut self) {
-        self.follow_paused = !self.follow_paused;
+    /// Jump to the longest of the filtered lines.
+    pub fn jump_to_longest_line(&mut self) {
+        let longest = self
+            .filtered_indices
+            .iter()
+            .copied()
+            .min_by_key(|&idx| (std::cmp::Reverse(self.parsed_lines[idx].raw.len()), idx));
+        if let Some(idx) = longest {
+            self.goto_line(idx + 1);
+        }
     }
 
-    pub fn is_follow_paused(&self) -> bool {
-        self.follow_paused
+    // Noise methods
+
+    /// Preview hiding the templates with the most lines in the view.
+    pub fn open_noise_preview(&mut self) {
+        match NoisePreview::new(&self.parsed_lines, &self.filtered_indices) {
+            Some(preview) => self.noise_preview = Some(preview),
+            None => self.notice = Some("No repeated lines to collapse".to_string()),
+        }
     }
 
-    // Level filter methods
+    pub fn close_noise_preview(&mut self) {
+        self.noise_preview = None;
+    }
 
-    pub fn min_level(&self) -> Option<LogLevel> {
-        self.min_level
+    pub fn noise_preview(&self) -> Option<&NoisePreview> {
+        self.noise_preview.as_ref()
     }
 
-    /// Returns counts of each log level present in the full (unfiltered) dataset.
-    /// Sorted by severity: Trace, Debug, Info, Warn, Error, Fatal. Only non-zero levels included.
-    pub fn level_counts(&self) -> Vec<(LogLevel, usize)> {
-        use std::collections::BTreeMap;
-        let mut counts: BTreeMap<LogLevel, usize> = BTreeMap::new();
-        for line in &self.parsed_lines {
-            if let Some(level) = line.level {
-                *counts.entry(level).or_insert(0) += 1;
-            }
+    pub fn noise_preview_more(&mut self) {
+        if let Some(preview) = self.noise_preview.as_mut() {
+            preview.more();
         }
-        counts.into_iter().collect()
     }
 
-    /// Set min_level to the given level, or clear it if already set to that level.
-    pub fn set_min_level(&mut self, level: LogLevel) {
-        if self.min_level == Some(level) {
-            self.min_level = None;
-        } else {
-            self.min_level = Some(level);
+    pub fn noise_preview_fewer(&mut self) {
+        if let Some(preview) = self.noise_preview.as_mut() {
+            preview.fewer();
         }
-        self.recompute_filter();
     }
 
-    /// Raise the minimum level (hide more). Cycles: None → second-lowest → … → highest → None.
-    pub fn cycle_level_up(&mut self) {
-        if self.available_levels.len() <= 1 {
+    /// Close the preview and hide the templates it lists. Templates already
+    /// hidden stay hidden, so collapsing again cuts deeper.
+    pub fn collapse_noise(&mut self) {
+        let Some(preview) = self.noise_preview.take() else {
             return;
-        }
-        self.min_level = match self.min_level {
-            None => {
-                // Skip the lowest level (min=lowest ≡ no filter), go to second
-                self.available_levels.get(1).copied()
-            }
-            Some(current) => {
-                // Find the next higher level
-                match self.available_levels.iter().position(|&l| l == current) {
-                    Some(idx) if idx + 1 < self.available_levels.len() => {
-                        Some(self.available_levels[idx + 1])
-                    }
-                    _ => None, // wrap around to show all
-                }
-            }
         };
+        self.hidden_templates
+            .extend(preview.hidden().iter().map(|t| t.template.clone()));
         self.recompute_filter();
+        self.notice = Some(format!(
+            "Hid {} lines of {} templates",
+            preview.hidden_lines(),
+            preview.hide
+        ));
     }
 
-    /// Lower the minimum level (show more). Cycles: None → highest → … → second-lowest → None.
-    pub fn cycle_level_down(&mut self) {
-        if self.available_levels.len() <= 1 {
-            return;
-        }
-        self.min_level = match self.min_level {
-            None => {
-                // Start from the highest level
-                self.available_levels.last().copied()
-            }
-            Some(current) => {
-                match self.available_levels.iter().position(|&l| l == current) {
-                    Some(idx) if idx > 1 => Some(self.available_levels[idx - 1]),
-                    _ => None, // at second-lowest or lowest, wrap to show all
-                }
-            }
-        };
+    /// Bring back the lines hidden by collapsing noise.
+    pub fn show_noise(&mut self) {
+        self.hidden_templates.clear();
         self.recompute_filter();
     }
 
-    // Context menu methods
+    /// Number of templates hidden by collapsing noise or muting.
+    pub fn hidden_template_count(&self) -> usize {
+        self.hidden_templates.len()
+    }
 
-    pub fn mode(&self) -> AppMode {
-        self.mode
+    /// Lines hidden by collapsing noise or muting, whatever else filters them.
+    pub fn muted_line_count(&self) -> usize {
+        self.muted_lines
     }
 
-    pub fn context_menu(&self) -> Option<&ContextMenuState> {
-        self.context_menu.as_ref()
+    fn is_muted(&self, line: &ParsedLine) -> bool {
+        !self.hidden_templates.is_empty() && self.hidden_templates.contains(&line.template)
     }
 
-    pub fn open_context_menu(
-        &mut self,
-        token_value: String,
-        token_kind: TokenKind,
-        position: (u16, u16),
-    ) {
-        let mut items = vec![MenuAction::FilterByValue];
-        match token_kind {
-            TokenKind::Ip => items.push(MenuAction::LookupAbuseIPDB),
-            TokenKind::Url => items.push(MenuAction::OpenInBrowser),
-            _ => {}
+    /// Hide every line shaped like original line `idx`: the same template,
+    /// whatever its numbers, ids and addresses.
+    pub fn mute_template(&mut self, idx: usize) {
+        let Some(template) = self.parsed_lines.get(idx).map(|p| p.template.clone()) else {
+            return;
+        };
+        if template.is_empty() {
+            self.notice = Some("Nothing to mute on an empty line".to_string());
+            return;
         }
-        self.context_menu = Some(ContextMenuState {
-            token_value,
-            token_kind,
-            items,
-            selected: 0,
-            position,
-        });
-        self.mode = AppMode::ContextMenu;
+        let count = self
+            .parsed_lines
+            .iter()
+            .filter(|p| p.template == template)
+            .count();
+        self.hidden_templates.insert(template);
+        self.recompute_filter();
+        self.notice = Some(format!("Muted {} lines like this", group_thousands(count)));
     }
 
-    pub fn close_context_menu(&mut self) {
-        self.context_menu = None;
-        self.mode = AppMode::Normal;
+    /// Mute the lines shaped like the cursor line.
+    pub fn mute_cursor_line(&mut self) {
+        if let Some(idx) = self.cursor_line_index() {
+            self.mute_template(idx);
+        }
     }
 
-    pub fn menu_up(&mut self) {
-        if let Some(ref mut menu) = self.context_menu {
-            menu.selected = menu.selected.saturating_sub(1);
+    // Sort methods
+
+    /// Reorder `indices` by the active sort. Stable, so ties keep line order.
+    fn sort_indices(&self, indices: &mut [usize]) {
+        match &self.sort {
+            None => {}
+            Some(SortOrder::Timestamp) => {
+                if let Some(index) = &self.time_index {
+                    indices.sort_by_key(|&i| {
+                        let ts = index.timestamp_at(i);
+                        (ts.is_none(), ts)
+                    });
+                }
+            }
+            Some(SortOrder::Level) => indices.sort_by_key(|&i| {
+                let level = self.parsed_lines[i].level;
+                (level.is_none(), std::cmp::Reverse(level))
+            }),
+            Some(SortOrder::Field(key)) => {
+                let lookup = FieldLookup::new(key);
+                let values: HashMap<usize, f64> = indices
+                    .iter()
+                    .filter_map(|&i| {
+                        let q = lookup.get(&self.parsed_lines[i]).and_then(parse_quantity)?;
+                        Some((i, q.value))
+                    })
+                    .collect();
+                indices.sort_by(|a, b| match (values.get(a), values.get(b)) {
+                    (Some(x), Some(y)) => y.total_cmp(x),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                });
+            }
         }
     }
 
-    pub fn menu_down(&mut self) {
-        if let Some(ref mut menu) = self.context_menu
-            && menu.selected + 1 < menu.items.len()
-        {
-            menu.selected += 1;
+    fn set_sort(&mut self, sort: Option<SortOrder>) {
+        self.sort = sort;
+        self.recompute_filter();
+        // A sorted view is read from the top
+        if self.sort.is_some() {
+            self.scroll_offset = 0;
+            self.cursor_position = 0;
         }
     }
 
-    pub fn execute_menu_action(&mut self) -> Option<(MenuAction, String)> {
-        let menu = self.context_menu.take()?;
-        self.mode = AppMode::Normal;
-        let action = menu.items[menu.selected];
-        Some((action, menu.token_value))
+    /// Natural order → time → level → natural order. A field sort goes back
+    /// to natural order.
+    pub fn cycle_sort(&mut self) {
+        let next = match self.sort {
+            None => Some(SortOrder::Timestamp),
+            Some(SortOrder::Timestamp) => Some(SortOrder::Level),
+            Some(SortOrder::Level) | Some(SortOrder::Field(_)) => None,
+        };
+        self.set_sort(next);
     }
 
-    pub fn execute_menu_item(&mut self, index: usize) -> Option<(MenuAction, String)> {
-        let menu = self.context_menu.take()?;
-        self.mode = AppMode::Normal;
-        let action = *menu.items.get(index)?;
-        Some((action, menu.token_value))
+    pub fn sort_by_field(&mut self, key: &str) {
+        self.set_sort(Some(SortOrder::Field(key.to_string())));
     }
 
-    pub fn set_filter(&mut self, pattern: String) {
-        self.filter_pattern = pattern;
-        self.recompute_filter();
+    /// Back to the lines' natural order.
+    pub fn clear_sort(&mut self) {
+        self.set_sort(None);
     }
 
-    /// Filter to lines structurally similar to the current cursor line.
-    pub fn filter_by_similar(&mut self) {
-        if let Some(&idx) = self.filtered_indices.get(self.cursor_position) {
-            let tmpl = self.parsed_lines[idx].template.clone();
-            self.similar_template = Some(tmpl);
-            self.mode = AppMode::Normal;
-            self.recompute_filter();
+    pub fn sort_order(&self) -> Option<&SortOrder> {
+        self.sort.as_ref()
+    }
+
+    // Sampling mode methods
+
+    /// Keep every Nth matching line and every warn+ line. Positions count
+    /// from the first match, so lines appended in follow mode don't reshuffle
+    /// which earlier lines are kept.
+    fn sample(&mut self, indices: Vec<usize>) -> Vec<usize> {
+        self.unsampled_count = indices.len();
+        if !self.sampling {
+            return indices;
         }
+        indices
+            .into_iter()
+            .enumerate()
+            .filter(|&(pos, idx)| {
+                pos % self.sample_every == 0
+                    || self.parsed_lines[idx]
+                        .level
+                        .is_some_and(|l| l >= LogLevel::Warn)
+            })
+            .map(|(_, idx)| idx)
+            .collect()
     }
 
-    pub fn clear_similar(&mut self) {
-        self.similar_template = None;
+    pub fn toggle_sampling(&mut self) {
+        self.sampling = !self.sampling;
         self.recompute_filter();
     }
 
-    pub fn is_similar_filter(&self) -> bool {
-        self.similar_template.is_some()
+    /// The 1-in-N rate while sampling mode is on.
+    pub fn sampling(&self) -> Option<usize> {
+        self.sampling.then_some(self.sample_every)
+    }
+
+    /// How many lines the filters matched, before sampling.
+    pub fn unsampled_count(&self) -> usize {
+        self.unsampled_count
+    }
+
+    /// Halve how many lines are shown (double N).
+    pub fn sample_sparser(&mut self) {
+        self.sample_every = self.sample_every.saturating_mul(2);
+        if self.sampling {
+            self.recompute_filter();
+        }
+    }
+
+    /// Double how many lines are shown (halve N, down to 1-in-2).
+    pub fn sample_denser(&mut self) {
+        self.sample_every = (self.sample_every / 2).max(2);
+        if self.sampling {
+            self.recompute_filter();
+        }
     }
 
     // Command palette methods
@@ -686,7 +3749,7 @@ impl App {
     pub fn open_palette(&mut self) {
         self.palette_input.clear();
         self.palette_selected = 0;
-        self.palette_filtered = (0..command::commands().len()).collect();
+        self.palette_filtered = command::grouped_order();
         self.mode = AppMode::CommandPalette;
     }
 
@@ -736,31 +3799,22 @@ impl App {
     fn recompute_palette(&mut self) {
         let cmds = command::commands();
         if self.palette_input.is_empty() {
-            self.palette_filtered = (0..cmds.len()).collect();
+            self.palette_filtered = command::grouped_order();
         } else {
-            use nucleo_matcher::pattern::{AtomKind, CaseMatching, Normalization, Pattern};
-            use nucleo_matcher::{Config, Matcher, Utf32Str};
-
-            let mut matcher = Matcher::new(Config::DEFAULT);
-            let pat = Pattern::new(
-                &self.palette_input,
-                CaseMatching::Ignore,
-                Normalization::Smart,
-                AtomKind::Fuzzy,
-            );
-            let mut buf = Vec::new();
+            let mut matcher = FuzzyMatcher::new(&self.palette_input, &self.palette_fuzzy, true);
 
-            // Collect (index, score) pairs, then sort by score descending
+            // Collect (index, score) pairs, then sort by score descending.
+            // A match in the description counts for half one in the name.
             let mut scored: Vec<(usize, u32)> = cmds
                 .iter()
                 .enumerate()
                 .filter_map(|(i, cmd)| {
-                    buf.clear();
-                    let haystack = Utf32Str::new(cmd.name, &mut buf);
-                    pat.score(haystack, &mut matcher).map(|s| (i, s))
+                    let name = matcher.score(cmd.name);
+                    let description = matcher.score(cmd.description).map(|s| s / 2);
+                    name.max(description).map(|s| (i, s))
                 })
                 .collect();
-            scored.sort_by(|a, b| b.1.cmp(&a.1));
+            scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
             self.palette_filtered = scored.into_iter().map(|(i, _)| i).collect();
         }
         self.palette_selected = 0;
@@ -776,6 +3830,46 @@ impl App {
         self.sparkline_visible = !self.sparkline_visible;
     }
 
+    pub fn pane_layout(&self) -> PaneLayout {
+        PaneLayout {
+            sparkline: self.sparkline_visible,
+            ..self.pane_layout
+        }
+    }
+
+    pub fn set_pane_layout(&mut self, layout: PaneLayout) {
+        self.sparkline_visible = layout.sparkline;
+        self.pane_layout = PaneLayout {
+            pinned_rows: layout.pinned_rows.clamp(1, MAX_PINS),
+            ..layout
+        };
+    }
+
+    pub fn toggle_pinned_pane(&mut self) {
+        self.pane_layout.pinned = !self.pane_layout.pinned;
+    }
+
+    pub fn toggle_stats_bar(&mut self) {
+        self.pane_layout.stats = !self.pane_layout.stats;
+    }
+
+    /// Show one more pinned line at a time, showing the pane if hidden.
+    pub fn grow_pinned_pane(&mut self) {
+        if self.pane_layout.pinned {
+            self.pane_layout.pinned_rows = (self.pane_layout.pinned_rows + 1).min(MAX_PINS);
+        }
+        self.pane_layout.pinned = true;
+    }
+
+    /// Show one fewer pinned line at a time; below one row the pane hides.
+    pub fn shrink_pinned_pane(&mut self) {
+        if self.pane_layout.pinned_rows > 1 {
+            self.pane_layout.pinned_rows -= 1;
+        } else {
+            self.pane_layout.pinned = false;
+        }
+    }
+
     pub fn sparkline_data(&self) -> Option<&SparklineData> {
         self.sparkline_data.as_ref()
     }
@@ -798,6 +3892,7 @@ impl App {
             if let Some(index) = &self.time_index {
                 self.sparkline_data = compute_sparkline(index, width);
             }
+            self.refresh_time_cursor_levels();
         }
     }
 
@@ -811,13 +3906,33 @@ impl App {
         }
         self.time_mode = Some(TimeModeState {
             cursor_bucket: num_buckets / 2,
+            cursor_levels: Vec::new(),
             range_start: None,
             dragging: false,
             drag_start: None,
         });
+        self.refresh_time_cursor_levels();
         self.mode = AppMode::TimeRange;
     }
 
+    /// Level breakdown of the bucket under the time mode cursor.
+    pub fn time_cursor_levels(&self) -> &[(Option<LogLevel>, usize)] {
+        self.time_mode
+            .as_ref()
+            .map_or(&[], |state| &state.cursor_levels)
+    }
+
+    /// Count the levels in the cursor's bucket again, after the cursor moved
+    /// or the sparkline was rebuilt. Scans every line, so not per frame.
+    fn refresh_time_cursor_levels(&mut self) {
+        if let (Some(state), Some(index), Some(sparkline)) =
+            (&mut self.time_mode, &self.time_index, &self.sparkline_data)
+        {
+            state.cursor_levels =
+                bucket_level_counts(index, sparkline, &self.parsed_lines, state.cursor_bucket);
+        }
+    }
+
     pub fn exit_time_mode(&mut self) {
         self.time_mode = None;
         self.mode = AppMode::Normal;
@@ -827,6 +3942,7 @@ impl App {
         if let Some(state) = &mut self.time_mode {
             state.cursor_bucket = state.cursor_bucket.saturating_sub(n);
         }
+        self.refresh_time_cursor_levels();
     }
 
     pub fn time_cursor_right(&mut self, n: usize) {
@@ -834,6 +3950,7 @@ impl App {
             let max = sparkline.num_buckets.saturating_sub(1);
             state.cursor_bucket = (state.cursor_bucket + n).min(max);
         }
+        self.refresh_time_cursor_levels();
     }
 
     pub fn time_mark_start(&mut self) {
@@ -880,6 +3997,42 @@ impl App {
         self.exit_time_mode();
     }
 
+    /// Whether lines that only inherit a timestamp from the line before
+    /// (stack trace continuations) pass time-range filters and count in the
+    /// sparkline.
+    pub fn include_inherited_times(&self) -> bool {
+        self.time_index
+            .as_ref()
+            .is_none_or(TimeIndex::include_inherited)
+    }
+
+    pub fn set_include_inherited_times(&mut self, include: bool) {
+        let Some(index) = &mut self.time_index else {
+            return;
+        };
+        index.set_include_inherited(include);
+        if self.sparkline_width > 0 {
+            self.sparkline_data = compute_sparkline(index, self.sparkline_width);
+        }
+        self.refresh_time_cursor_levels();
+        if self.time_range.is_some() {
+            self.recompute_filter();
+        }
+    }
+
+    pub fn toggle_inherited_times(&mut self) {
+        if self.time_index.is_none() {
+            return;
+        }
+        let include = !self.include_inherited_times();
+        self.set_include_inherited_times(include);
+        self.set_notice(if include {
+            "Time filters include lines without their own timestamp".to_string()
+        } else {
+            "Time filters skip lines without their own timestamp".to_string()
+        });
+    }
+
     pub fn clear_time_range(&mut self) {
         self.time_range = None;
         self.recompute_filter();
@@ -899,13 +4052,16 @@ impl App {
             state.drag_start = Some(bucket);
             state.range_start = Some(bucket);
         }
+        self.refresh_time_cursor_levels();
     }
 
     pub fn time_mouse_drag(&mut self, bucket: usize) {
         if let Some(state) = &mut self.time_mode
             && state.dragging
+            && state.cursor_bucket != bucket
         {
             state.cursor_bucket = bucket;
+            self.refresh_time_cursor_levels();
         }
     }
 
@@ -990,6 +4146,7 @@ impl App {
     /// Apply a parsed AI filter response by mapping it to existing filter primitives.
     pub fn apply_ai_filter(&mut self, response: &crate::ai::AiFilterResponse) {
         // Apply text filter
+        self.filter_terms.clear();
         if let Some(ref text) = response.text {
             self.filter_pattern = text.clone();
         } else {
@@ -997,19 +4154,7 @@ impl App {
         }
 
         // Apply level filter
-        if let Some(ref level_str) = response.min_level {
-            self.min_level = match level_str.to_uppercase().as_str() {
-                "TRACE" => Some(LogLevel::Trace),
-                "DEBUG" => Some(LogLevel::Debug),
-                "INFO" => Some(LogLevel::Info),
-                "WARN" | "WARNING" => Some(LogLevel::Warn),
-                "ERROR" => Some(LogLevel::Error),
-                "FATAL" => Some(LogLevel::Fatal),
-                _ => None,
-            };
-        } else {
-            self.min_level = None;
-        }
+        self.min_level = response.min_level.as_deref().and_then(LogLevel::from_name);
 
         // Apply time range filter
         if let Some(ref time_str) = response.time_range {
@@ -1020,6 +4165,7 @@ impl App {
 
         // Clear similar filter — AI query replaces all filters
         self.similar_template = None;
+        self.hidden_templates.clear();
 
         self.recompute_filter();
     }
@@ -0,0 +1,94 @@
+use crate::config::{HighlightRule, PivotUrl, Profile, compile_highlights};
+use ratatui::style::Color;
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File written by "Export rule bundle" and read by "Import rule bundle", in
+/// the current directory.
+pub const DEFAULT_FILE: &str = "lumolog-rules.toml";
+
+/// A team's triage setup in one TOML file: what to hide, what to color, where
+/// to pivot, and the filters to start from. Loaded with `--rules FILE` on top
+/// of the profile, or imported from the palette.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleBundle {
+    /// Text filters, stacked in this order.
+    pub filters: Vec<String>,
+    /// Regexes for lines to hide entirely, as in a profile's `ignore`.
+    pub ignore: Vec<String>,
+    pub highlight: Vec<HighlightRule>,
+    pub pivot: Vec<PivotUrl>,
+}
+
+/// A bundle with its patterns compiled, ready to hand to the viewer.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    pub bundle: RuleBundle,
+    pub ignore: RegexSet,
+    pub highlights: Vec<(Regex, Color)>,
+}
+
+impl RuleBundle {
+    /// The rules of a config profile, without filters.
+    pub fn from_profile(profile: &Profile) -> Self {
+        Self {
+            filters: Vec::new(),
+            ignore: profile.ignore.clone(),
+            highlight: profile.highlight.clone(),
+            pivot: profile.pivot.clone(),
+        }
+    }
+
+    /// Add `other`'s rules after these, skipping ones already here. Filters
+    /// are left alone.
+    pub fn merge(&mut self, other: &RuleBundle) {
+        fn extend<T: Clone + PartialEq>(into: &mut Vec<T>, from: &[T]) {
+            for item in from {
+                if !into.contains(item) {
+                    into.push(item.clone());
+                }
+            }
+        }
+        extend(&mut self.ignore, &other.ignore);
+        extend(&mut self.highlight, &other.highlight);
+        extend(&mut self.pivot, &other.pivot);
+    }
+
+    /// Number of ignore, highlight and pivot rules.
+    pub fn rule_count(&self) -> usize {
+        self.ignore.len() + self.highlight.len() + self.pivot.len()
+    }
+
+    pub fn compile(self) -> anyhow::Result<Rules> {
+        Ok(Rules {
+            ignore: RegexSet::new(&self.ignore)?,
+            highlights: compile_highlights(&self.highlight)?,
+            bundle: self,
+        })
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// Parse a bundle and check that its patterns and colors compile.
+    pub fn from_toml(text: &str) -> anyhow::Result<Self> {
+        let bundle: Self = toml::from_str(text)?;
+        bundle.clone().compile()?;
+        Ok(bundle)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("cannot read rule bundle {}: {e}", path.display()))?;
+        Self::from_toml(&text)
+            .map_err(|e| anyhow::anyhow!("invalid rule bundle {}: {e}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, self.to_toml()?)
+            .map_err(|e| anyhow::anyhow!("cannot write rule bundle {}: {e}", path.display()))
+    }
+}
@@ -8,35 +8,124 @@ pub enum Action {
     ScrollRight,
     PageDown,
     PageUp,
+    HalfPageDown,
+    HalfPageUp,
     ScrollToTop,
     ScrollToBottom,
     OpenFilter,
     CycleLevelUp,
     CycleLevelDown,
+    OpenLevelPicker,
     TogglePretty,
+    TogglePrettyLine,
     ToggleWrap,
+    ToggleWhitespace,
+    ToggleHighContrast,
+    ToggleTextCues,
+    CycleLevelBadge,
+    ToggleJoinLines,
+    CycleBlankLines,
+    ExportViewText,
     EnterCursorMode,
     ToggleFollowPause,
     OpenCommandPalette,
     YankLine,
+    YankLocation,
     YankAllFiltered,
+    ToggleMark,
+    YankMarkedRange,
+    SaveMarkedRange,
+    OpenEventList,
+    OpenMalformedPanel,
+    ToggleMalformedOnly,
+    CollapseNoise,
+    ShowNoise,
+    MuteTemplate,
+    OpenLineStats,
+    JumpToLongestLine,
+    NextMatch,
+    PrevMatch,
+    DiffWithMark,
+    ShowHexDump,
+    AnnotateLine,
+    OpenAnnotationList,
+    OpenTraceView,
+    OpenStoryline,
+    ExportStoryline,
+    ExportAnnotations,
+    TogglePin,
+    YankPins,
+    ExportPins,
+    ClearPins,
+    ExportViewState,
+    ExportRuleBundle,
+    ImportRuleBundle,
     EnterTimeMode,
     ClearTimeRange,
+    ClearAllFilters,
+    CycleCaseMode,
+    ToggleWholeWord,
+    ToggleHighlightOnly,
+    CycleContextLines,
+    SaveFilter,
+    OpenSavedFilters,
     ToggleSparkline,
+    TogglePinnedPane,
+    ToggleStatsBar,
+    GrowPinnedPane,
+    ShrinkPinnedPane,
+    ToggleSampling,
+    SampleSparser,
+    SampleDenser,
+    CycleSort,
+    NaturalOrder,
     TimeMarkStart,
     TimeMarkEndApply,
     TimePresetLast5m,
     TimePresetLast15m,
     TimePresetLast1h,
     TimePresetLast24h,
+    ToggleInheritedTimes,
+    LoadRotatedFiles,
     EnterAskMode,
     EnterAnalyzeMode,
 }
 
-/// A command in the palette. `keybinding` is a display string for the help column.
+/// Palette section headers, in the order they're listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Filtering,
+    Time,
+    Lines,
+    Views,
+    Ai,
+    Export,
+    Navigation,
+    General,
+}
+
+impl Category {
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Filtering => "Filtering",
+            Category::Time => "Time",
+            Category::Lines => "Lines",
+            Category::Views => "Views",
+            Category::Ai => "AI",
+            Category::Export => "Export",
+            Category::Navigation => "Navigation",
+            Category::General => "General",
+        }
+    }
+}
+
+/// A command in the palette. `keybinding` is a display string for the help
+/// column; `description` is shown for the selected command and searched too.
 pub struct Command {
     pub name: &'static str,
     pub keybinding: Option<&'static str>,
+    pub category: Category,
+    pub description: &'static str,
     pub action: Action,
 }
 
@@ -44,159 +133,634 @@ pub struct Command {
 /// and key dispatch derive from this list.
 pub fn commands() -> &'static [Command] {
     use Action::*;
+    use Category::*;
     static COMMANDS: &[Command] = &[
         // --- Actions (most useful at the top) ---
         Command {
             name: "Filter / search",
             keybinding: Some("/"),
+            category: Filtering,
+            description: "Show only lines matching text; falls back to fuzzy matching",
             action: OpenFilter,
         },
         Command {
             name: "AI query",
             keybinding: Some("a"),
+            category: Ai,
+            description: "Describe what you're looking for and let the AI write the filter",
             action: EnterAskMode,
         },
         Command {
             name: "AI analyze",
             keybinding: Some("A"),
+            category: Ai,
+            description: "Send the filtered lines to the AI and ask about them",
             action: EnterAnalyzeMode,
         },
         Command {
             name: "Level filter up",
             keybinding: Some("v"),
+            category: Filtering,
+            description: "Raise the minimum severity shown (info, warn, error, ...)",
             action: CycleLevelUp,
         },
         Command {
             name: "Level filter down",
             keybinding: Some("V"),
+            category: Filtering,
+            description: "Lower the minimum severity shown",
             action: CycleLevelDown,
         },
+        Command {
+            name: "Pick levels to show",
+            keybinding: Some("L"),
+            category: Filtering,
+            description: "Check and uncheck severities one by one, e.g. warn and fatal only",
+            action: OpenLevelPicker,
+        },
         Command {
             name: "Cursor mode",
             keybinding: Some("Enter"),
+            category: Lines,
+            description: "Move a cursor line by line to act on single lines",
             action: EnterCursorMode,
         },
         Command {
             name: "Yank line to clipboard",
             keybinding: Some("y (cursor)"),
+            category: Export,
+            description: "Copy the line under the cursor",
             action: YankLine,
         },
+        Command {
+            name: "Yank location (file:line)",
+            keybinding: Some("L (cursor)"),
+            category: Export,
+            description: "Copy the path and line number of the line under the cursor",
+            action: YankLocation,
+        },
         Command {
             name: "Yank all filtered lines",
             keybinding: Some("Y (cursor)"),
+            category: Export,
+            description: "Copy every line that passes the current filters",
             action: YankAllFiltered,
         },
         Command {
-            name: "Pretty-print JSON",
+            name: "Mark line",
+            keybinding: Some("m (cursor)"),
+            category: Lines,
+            description: "Remember a line to compare against later",
+            action: ToggleMark,
+        },
+        Command {
+            name: "Yank from mark to cursor",
+            keybinding: Some("M (cursor)"),
+            category: Export,
+            description: "Copy the lines in view from the marked line to the cursor line",
+            action: YankMarkedRange,
+        },
+        Command {
+            name: "Save mark-to-cursor range to file",
+            keybinding: Some("W (cursor)"),
+            category: Export,
+            description: "Write the lines from the mark to the cursor to lumolog-lines-FIRST-LAST.log",
+            action: SaveMarkedRange,
+        },
+        Command {
+            name: "Diff marked line with cursor",
+            keybinding: Some("d (cursor)"),
+            category: Lines,
+            description: "Word-diff the marked line against the cursor line",
+            action: DiffWithMark,
+        },
+        Command {
+            name: "Hex dump of line",
+            keybinding: Some("x (cursor)"),
+            category: Lines,
+            description: "Show the cursor line's bytes, to find control characters and bad encoding",
+            action: ShowHexDump,
+        },
+        Command {
+            name: "Pin / unpin line",
+            keybinding: Some("b (cursor)"),
+            category: Lines,
+            description: "Keep a line in view at the top while scrolling elsewhere",
+            action: TogglePin,
+        },
+        Command {
+            name: "Yank pinned lines",
+            keybinding: None,
+            category: Export,
+            description: "Copy the pinned lines as a group",
+            action: YankPins,
+        },
+        Command {
+            name: "Export pinned lines to file",
+            keybinding: None,
+            category: Export,
+            description: "Write the pinned lines to lumolog-pins.txt",
+            action: ExportPins,
+        },
+        Command {
+            name: "Clear pinned lines",
+            keybinding: None,
+            category: Lines,
+            description: "Unpin every pinned line",
+            action: ClearPins,
+        },
+        Command {
+            name: "Annotate line",
+            keybinding: Some("a (cursor)"),
+            category: Lines,
+            description: "Attach a note to the line under the cursor",
+            action: AnnotateLine,
+        },
+        Command {
+            name: "Annotation list",
+            keybinding: Some("E"),
+            category: Views,
+            description: "List annotated lines and jump to one",
+            action: OpenAnnotationList,
+        },
+        Command {
+            name: "Trace view (trace / span tree)",
+            keybinding: Some("T"),
+            category: Views,
+            description: "Group lines by trace ID and show their span tree",
+            action: OpenTraceView,
+        },
+        Command {
+            name: "Storyline (errors and level runs overview)",
+            keybinding: Some("O"),
+            category: Views,
+            description: "Errors in order with the quieter stretches between them collapsed",
+            action: OpenStoryline,
+        },
+        Command {
+            name: "Export storyline to clipboard",
+            keybinding: Some("y (storyline)"),
+            category: Export,
+            description: "Copy the storyline as plain text",
+            action: ExportStoryline,
+        },
+        Command {
+            name: "Export annotations to clipboard",
+            keybinding: Some("y (annotation list)"),
+            category: Export,
+            description: "Copy the annotated lines with their notes",
+            action: ExportAnnotations,
+        },
+        Command {
+            name: "Export view state (file, filters, time range)",
+            keybinding: None,
+            category: Export,
+            description: "Save file and filters to lumolog-view.json for --load",
+            action: ExportViewState,
+        },
+        Command {
+            name: "Export rule bundle (ignore, highlight, pivot, filters)",
+            keybinding: None,
+            category: Export,
+            description: "Save the triage rules and filters to lumolog-rules.toml for --rules",
+            action: ExportRuleBundle,
+        },
+        Command {
+            name: "Import rule bundle",
+            keybinding: None,
+            category: Export,
+            description: "Add the rules and filters in lumolog-rules.toml to this view",
+            action: ImportRuleBundle,
+        },
+        Command {
+            name: "Pretty-print structured lines",
             keybinding: Some("p"),
+            category: Views,
+            description: "Indent JSON and other structured lines",
             action: TogglePretty,
         },
+        Command {
+            name: "Expand / collapse line",
+            keybinding: Some("p / Enter (cursor)"),
+            category: Views,
+            description: "Pretty-print just the line under the cursor, or unfold its stack trace",
+            action: TogglePrettyLine,
+        },
         Command {
             name: "Toggle line wrap",
             keybinding: Some("w"),
+            category: Views,
+            description: "Wrap long lines instead of scrolling sideways",
             action: ToggleWrap,
         },
+        Command {
+            name: "Toggle whitespace (tabs, ^M, trailing spaces)",
+            keybinding: Some("W"),
+            category: Views,
+            description: "Show tabs, carriage returns and trailing spaces",
+            action: ToggleWhitespace,
+        },
+        Command {
+            name: "Toggle high contrast",
+            keybinding: None,
+            category: Views,
+            description: "Bright text, no dimming, black or white on colored backgrounds",
+            action: ToggleHighContrast,
+        },
+        Command {
+            name: "Toggle text cues (level tags, gutter symbols)",
+            keybinding: None,
+            category: Views,
+            description: "Say in text what colors say: level tags, cursor and context marks",
+            action: ToggleTextCues,
+        },
+        Command {
+            name: "Cycle level badge style (badge, code, letter, emoji, none)",
+            keybinding: None,
+            category: Views,
+            description: "Show levels as [ERR] badges, ERR, E, ❗ or not at all",
+            action: CycleLevelBadge,
+        },
+        Command {
+            name: "Toggle joining multi-line entries",
+            keybinding: None,
+            category: Views,
+            description: "Fold stack traces into the line above them, or show every line on its own",
+            action: ToggleJoinLines,
+        },
+        Command {
+            name: "Cycle blank lines",
+            keybinding: None,
+            category: Views,
+            description: "Show empty lines as separators, hide them, or show them as they are",
+            action: CycleBlankLines,
+        },
+        Command {
+            name: "Export view as text (for screen readers)",
+            keybinding: None,
+            category: Views,
+            description: "Write the lines in view as plain text to lumolog-view.txt",
+            action: ExportViewText,
+        },
+        Command {
+            name: "Event list (restarts, marker rules)",
+            keybinding: Some("e"),
+            category: Views,
+            description: "Restarts, deploys and marker rule matches, in order",
+            action: OpenEventList,
+        },
+        Command {
+            name: "Malformed lines (parse failures by reason)",
+            keybinding: Some("M"),
+            category: Views,
+            description: "Lines that failed to parse, grouped by reason",
+            action: OpenMalformedPanel,
+        },
+        Command {
+            name: "Line length stats",
+            keybinding: Some("I"),
+            category: Views,
+            description: "Distribution of line sizes and the longest lines, to spot ingestion bugs",
+            action: OpenLineStats,
+        },
+        Command {
+            name: "Jump to longest line",
+            keybinding: None,
+            category: Navigation,
+            description: "Go to the longest of the filtered lines",
+            action: JumpToLongestLine,
+        },
+        Command {
+            name: "Filter: only malformed lines",
+            keybinding: Some("f (malformed)"),
+            category: Filtering,
+            description: "Show only lines that failed to parse",
+            action: ToggleMalformedOnly,
+        },
+        Command {
+            name: "Collapse noise (hide noisiest templates)",
+            keybinding: Some("C"),
+            category: Filtering,
+            description: "Preview and hide the line shapes that repeat the most",
+            action: CollapseNoise,
+        },
+        Command {
+            name: "Mute lines like this",
+            keybinding: Some("z (cursor)"),
+            category: Filtering,
+            description: "Hide every line with the same shape as the cursor line",
+            action: MuteTemplate,
+        },
+        Command {
+            name: "Clear mutes (show collapsed noise again)",
+            keybinding: None,
+            category: Filtering,
+            description: "Bring back the lines hidden by muting or collapsing noise",
+            action: ShowNoise,
+        },
         Command {
             name: "Time range mode",
             keybinding: Some("t"),
+            category: Time,
+            description: "Pick a time window on the sparkline with the keyboard",
             action: EnterTimeMode,
         },
         Command {
             name: "Time: mark start",
             keybinding: Some("[ (time)"),
+            category: Time,
+            description: "Start a time window at the sparkline cursor",
             action: TimeMarkStart,
         },
         Command {
             name: "Time: mark end & apply",
             keybinding: Some("] (time)"),
+            category: Time,
+            description: "End the time window at the sparkline cursor and filter to it",
             action: TimeMarkEndApply,
         },
         Command {
             name: "Time: last 5 minutes",
             keybinding: Some("1 (time)"),
+            category: Time,
+            description: "Show the last 5 minutes of the log",
             action: TimePresetLast5m,
         },
         Command {
             name: "Time: last 15 minutes",
             keybinding: Some("2 (time)"),
+            category: Time,
+            description: "Show the last 15 minutes of the log",
             action: TimePresetLast15m,
         },
         Command {
             name: "Time: last 1 hour",
             keybinding: Some("3 (time)"),
+            category: Time,
+            description: "Show the last hour of the log",
             action: TimePresetLast1h,
         },
         Command {
             name: "Time: last 24 hours",
             keybinding: Some("4 (time)"),
+            category: Time,
+            description: "Show the last day of the log",
             action: TimePresetLast24h,
         },
+        Command {
+            name: "Time: include / skip lines without their own timestamp",
+            keybinding: None,
+            category: Time,
+            description: "Whether continuation lines count in time windows and the sparkline",
+            action: ToggleInheritedTimes,
+        },
         Command {
             name: "Clear time range",
             keybinding: Some("c (time)"),
+            category: Time,
+            description: "Drop the time window filter",
             action: ClearTimeRange,
         },
         Command {
-            name: "Toggle sparkline",
+            name: "Clear all filters (text, level, time, similar, noise, sort)",
+            keybinding: Some("X"),
+            category: Filtering,
+            description: "Reset text, level, time, similar-line and sort filters at once",
+            action: ClearAllFilters,
+        },
+        Command {
+            name: "Cycle filter case (ignore, smart, exact)",
+            keybinding: Some("Alt-C (filter)"),
+            category: Filtering,
+            description: "Match filter text ignoring case, by smart case, or exactly",
+            action: CycleCaseMode,
+        },
+        Command {
+            name: "Toggle whole-word filter",
+            keybinding: Some("Alt-W (filter)"),
+            category: Filtering,
+            description: "Match filter text only as a whole word, so err skips transferred",
+            action: ToggleWholeWord,
+        },
+        Command {
+            name: "Toggle highlight-only filter",
+            keybinding: Some("Alt-H (filter)"),
+            category: Filtering,
+            description: "Highlight filter matches but keep every line in view",
+            action: ToggleHighlightOnly,
+        },
+        Command {
+            name: "Cycle context lines around matches (0/2/5)",
+            keybinding: Some("c"),
+            category: Filtering,
+            description: "Show the lines before and after each filter match, dimmed, like grep -C",
+            action: CycleContextLines,
+        },
+        Command {
+            name: "Save filter as...",
+            keybinding: None,
+            category: Filtering,
+            description: "Keep the text, level and time filters under a name",
+            action: SaveFilter,
+        },
+        Command {
+            name: "Saved filters",
+            keybinding: Some("F"),
+            category: Filtering,
+            description: "Re-apply or delete a filter saved by name",
+            action: OpenSavedFilters,
+        },
+        Command {
+            name: "Toggle sampling (1-in-N, keeps warn+)",
+            keybinding: Some("S"),
+            category: Filtering,
+            description: "Show one line in N to thin out noise, always keeping warnings",
+            action: ToggleSampling,
+        },
+        Command {
+            name: "Sample fewer lines",
+            keybinding: Some("+"),
+            category: Filtering,
+            description: "Sample fewer lines",
+            action: SampleSparser,
+        },
+        Command {
+            name: "Sample more lines",
+            keybinding: Some("-"),
+            category: Filtering,
+            description: "Sample more lines",
+            action: SampleDenser,
+        },
+        Command {
+            name: "Cycle sort (time, level, natural order)",
+            keybinding: Some("o"),
+            category: Filtering,
+            description: "Order lines by time or by level instead of file order",
+            action: CycleSort,
+        },
+        Command {
+            name: "Back to natural order",
             keybinding: None,
+            category: Filtering,
+            description: "Show lines in file order again",
+            action: NaturalOrder,
+        },
+        Command {
+            name: "Toggle sparkline",
+            keybinding: Some("D"),
+            category: Views,
+            description: "Show or hide the time density bar",
             action: ToggleSparkline,
         },
+        Command {
+            name: "Toggle pinned pane",
+            keybinding: Some("Z"),
+            category: Views,
+            description: "Show or hide the pinned lines pane",
+            action: TogglePinnedPane,
+        },
+        Command {
+            name: "Pinned pane: show more lines",
+            keybinding: Some("}"),
+            category: Views,
+            description: "Give the pinned pane one more row",
+            action: GrowPinnedPane,
+        },
+        Command {
+            name: "Pinned pane: show fewer lines",
+            keybinding: Some("{"),
+            category: Views,
+            description: "Give the pinned pane one fewer row",
+            action: ShrinkPinnedPane,
+        },
+        Command {
+            name: "Toggle level stats bar",
+            keybinding: Some("B"),
+            category: Views,
+            description: "Show or hide the per-level line counts",
+            action: ToggleStatsBar,
+        },
+        Command {
+            name: "Load rotated files",
+            keybinding: Some("R"),
+            category: General,
+            description: "Read app.log.1, app.log.2.gz ... in ahead of app.log, oldest first",
+            action: LoadRotatedFiles,
+        },
         Command {
             name: "Pause / resume follow",
             keybinding: Some("Space"),
+            category: General,
+            description: "Stop or restart scrolling as new lines arrive",
             action: ToggleFollowPause,
         },
         Command {
             name: "Command palette",
             keybinding: Some("?"),
+            category: General,
+            description: "Search every command",
             action: OpenCommandPalette,
         },
         Command {
             name: "Quit",
             keybinding: Some("q"),
+            category: General,
+            description: "Exit lumolog",
             action: Quit,
         },
         // --- Navigation (less important, bottom) ---
         Command {
             name: "Scroll down",
             keybinding: Some("j / Down"),
+            category: Navigation,
+            description: "Move down one line",
             action: ScrollDown,
         },
         Command {
             name: "Scroll up",
             keybinding: Some("k / Up"),
+            category: Navigation,
+            description: "Move up one line",
             action: ScrollUp,
         },
         Command {
             name: "Scroll left",
             keybinding: Some("h / Left"),
+            category: Navigation,
+            description: "Scroll long lines left",
             action: ScrollLeft,
         },
         Command {
             name: "Scroll right",
             keybinding: Some("l / Right"),
+            category: Navigation,
+            description: "Scroll long lines right",
             action: ScrollRight,
         },
         Command {
             name: "Page down",
             keybinding: Some("Space / PgDn"),
+            category: Navigation,
+            description: "Move down one screen",
             action: PageDown,
         },
         Command {
             name: "Page up",
             keybinding: Some("PgUp"),
+            category: Navigation,
+            description: "Move up one screen",
             action: PageUp,
         },
+        Command {
+            name: "Half page down",
+            keybinding: Some("Ctrl-D"),
+            category: Navigation,
+            description: "Move down half a screen",
+            action: HalfPageDown,
+        },
+        Command {
+            name: "Half page up",
+            keybinding: Some("Ctrl-U"),
+            category: Navigation,
+            description: "Move up half a screen",
+            action: HalfPageUp,
+        },
         Command {
             name: "Go to top",
             keybinding: Some("g"),
+            category: Navigation,
+            description: "Jump to the first line",
             action: ScrollToTop,
         },
         Command {
             name: "Go to bottom",
             keybinding: Some("G"),
+            category: Navigation,
+            description: "Jump to the last line",
             action: ScrollToBottom,
         },
+        Command {
+            name: "Next match",
+            keybinding: Some("n"),
+            category: Navigation,
+            description: "Jump to the next line the text filters match",
+            action: NextMatch,
+        },
+        Command {
+            name: "Previous match",
+            keybinding: Some("N"),
+            category: Navigation,
+            description: "Jump to the previous line the text filters match",
+            action: PrevMatch,
+        },
     ];
     COMMANDS
 }
+
+/// Indices into [`commands`] grouped by category, keeping list order within
+/// each group. The palette shows this order when nothing is typed.
+pub fn grouped_order() -> Vec<usize> {
+    let mut order: Vec<usize> = (0..commands().len()).collect();
+    order.sort_by_key(|&i| commands()[i].category);
+    order
+}
@@ -0,0 +1,446 @@
+use crate::derived::DerivedField;
+use crate::highlighter::LevelBadge;
+use crate::markers::MarkerRule;
+use crate::parser::LogFormat;
+use ratatui::style::Color;
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// User settings loaded from `~/.config/lumolog/config.toml`. Every field has
+/// a default, so a missing file or a partial file is fine.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub scroll: ScrollConfig,
+    pub keys: KeysConfig,
+    pub display: DisplayConfig,
+    pub yank: YankConfig,
+    /// Which panes start shown, and how many rows the pinned pane gets.
+    pub layout: PaneLayout,
+    /// Fuzzy fallback used by the filter bar when the exact match finds nothing.
+    pub fuzzy: FuzzyConfig,
+    /// Fuzzy matching in the command palette.
+    pub palette: FuzzyConfig,
+    /// `[[marker]]` rules: lines matching `pattern` become named events on the
+    /// timeline and in the event list.
+    #[serde(rename = "marker")]
+    pub markers: Vec<MarkerRuleConfig>,
+    /// `[[split]]` commands offered in the token menu when running in tmux
+    /// or WezTerm.
+    pub split: Vec<SplitCommand>,
+    /// `[[derived]]` fields computed from each line's fields as it is parsed,
+    /// in order, so later ones can use earlier ones.
+    pub derived: Vec<DerivedFieldConfig>,
+    /// Named per-project settings, e.g. `[profile.api]`.
+    pub profile: BTreeMap<String, Profile>,
+}
+
+/// Settings for one team's or project's logs, chosen with `--profile NAME` or
+/// by matching the log file path against `paths`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Globs (`*`, `?`) matched against the file path or file name.
+    pub paths: Vec<String>,
+    /// Skip auto-detection and parse as this format ("json", "logfmt", ...).
+    pub format: Option<String>,
+    /// Regexes for lines to hide entirely (health checks, noisy pollers).
+    pub ignore: Vec<String>,
+    pub highlight: Vec<HighlightRule>,
+    pub pivot: Vec<PivotUrl>,
+    pub split: Vec<SplitCommand>,
+}
+
+impl Profile {
+    pub fn log_format(&self) -> anyhow::Result<Option<LogFormat>> {
+        match &self.format {
+            None => Ok(None),
+            Some(name) => LogFormat::from_name(name)
+                .map(Some)
+                .ok_or_else(|| anyhow::anyhow!("unknown format '{name}'")),
+        }
+    }
+
+    pub fn ignore_set(&self) -> anyhow::Result<RegexSet> {
+        Ok(RegexSet::new(&self.ignore)?)
+    }
+
+    pub fn highlight_rules(&self) -> anyhow::Result<Vec<(Regex, Color)>> {
+        compile_highlights(&self.highlight)
+    }
+}
+
+/// Compile `highlight` rules into (pattern, color) pairs.
+pub fn compile_highlights(rules: &[HighlightRule]) -> anyhow::Result<Vec<(Regex, Color)>> {
+    rules
+        .iter()
+        .map(|rule| Ok((Regex::new(&rule.pattern)?, parse_color(&rule.color)?)))
+        .collect()
+}
+
+/// Color every match of `pattern`. `color` is a name ("magenta") or "#rrggbb".
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HighlightRule {
+    pub pattern: String,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarkerRuleConfig {
+    pub label: String,
+    pub pattern: String,
+    #[serde(default = "default_marker_color")]
+    pub color: String,
+}
+
+/// `name = expr`, e.g. `latency_s` = `duration_ms / 1000`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DerivedFieldConfig {
+    pub name: String,
+    pub expr: String,
+}
+
+fn default_marker_color() -> String {
+    "yellow".to_string()
+}
+
+/// Extra context-menu entry that opens `url` with `{}` replaced by the
+/// clicked token.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct PivotUrl {
+    pub name: String,
+    pub url: String,
+}
+
+/// Fill `{}` in a pivot URL with the token, percent-encoded so spaces, `&`
+/// or `#` in it can't break the URL apart.
+pub fn expand_pivot_url(template: &str, token: &str) -> String {
+    let mut encoded = String::with_capacity(token.len());
+    for byte in token.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    template.replace("{}", &encoded)
+}
+
+/// Extra context-menu entry that runs `command` in a new terminal pane, with
+/// `{}` replaced by the clicked token (shell-quoted).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SplitCommand {
+    pub name: String,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScrollConfig {
+    /// Entries of context kept above and below the cursor (vim's `scrolloff`).
+    pub scrolloff: usize,
+    /// Lines moved per mouse wheel notch.
+    pub wheel_step: usize,
+    /// Entries moved by Ctrl-D / Ctrl-U. 0 means half the viewport.
+    pub half_page: usize,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            scrolloff: 0,
+            wheel_step: 3,
+            half_page: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysConfig {
+    pub esc: EscAction,
+    /// Ask before `q` quits while there are unexported notes, a marked line,
+    /// or follow mode is on.
+    pub confirm_quit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    /// Start with tabs, carriage returns and trailing spaces shown (`W`
+    /// toggles).
+    pub whitespace: bool,
+    /// Leave lines without a timestamp of their own (stack trace
+    /// continuations, which show the time of the line above) out of
+    /// time-range filters and the sparkline.
+    pub own_times_only: bool,
+    /// Start with the high-contrast theme.
+    pub high_contrast: bool,
+    /// Start with text cues: level tags and gutter symbols for what is
+    /// otherwise only shown by color.
+    pub text_cues: bool,
+    /// How the compact view shows levels.
+    pub level_badge: LevelBadge,
+    /// Fold stack traces and other continuation lines into the entry above
+    /// them.
+    pub join_lines: bool,
+    /// Show blank lines as separators, hide them, or show them as they are.
+    pub blank_lines: BlankLines,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            whitespace: false,
+            own_times_only: false,
+            high_contrast: false,
+            text_cues: false,
+            level_badge: LevelBadge::default(),
+            join_lines: true,
+            blank_lines: BlankLines::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct YankConfig {
+    /// What "Yank location" copies. `{path}` is the file's absolute path,
+    /// `{file}` its name, `{line}` the line number and `{time}` the line's
+    /// timestamp (empty when it has none).
+    pub location: String,
+}
+
+impl Default for YankConfig {
+    fn default() -> Self {
+        Self {
+            location: "{path}:{line}".to_string(),
+        }
+    }
+}
+
+/// Which optional panes are shown and how big they are. Set in `[layout]`,
+/// changed with keys while running, and saved with the view state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PaneLayout {
+    /// The time density bar at the top (`D` toggles).
+    pub sparkline: bool,
+    /// Pinned lines above the log view (`Z` toggles).
+    pub pinned: bool,
+    /// Most pinned lines shown at once (`{` / `}` resize).
+    pub pinned_rows: usize,
+    /// Per-level line counts above the status bar (`B` toggles).
+    pub stats: bool,
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        Self {
+            sparkline: true,
+            pinned: true,
+            pinned_rows: crate::app::MAX_PINS,
+            stats: true,
+        }
+    }
+}
+
+/// How blank and whitespace-only lines show.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlankLines {
+    /// A dim rule across the view, between the lines around them.
+    #[default]
+    Separator,
+    /// Left out of the view.
+    Hidden,
+    /// As they are, whitespace and all.
+    Literal,
+}
+
+impl BlankLines {
+    /// Separator → hidden → literal → separator.
+    pub fn next(self) -> Self {
+        match self {
+            BlankLines::Separator => BlankLines::Hidden,
+            BlankLines::Hidden => BlankLines::Literal,
+            BlankLines::Literal => BlankLines::Separator,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BlankLines::Separator => "separators",
+            BlankLines::Hidden => "hidden",
+            BlankLines::Literal => "shown as they are",
+        }
+    }
+}
+
+/// What Esc does in normal mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EscAction {
+    /// Clear one filter per press: similar, then time range, then text.
+    #[default]
+    Step,
+    /// Clear every filter at once, level included.
+    ClearAll,
+    /// Do nothing.
+    None,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FuzzyCase {
+    /// Always case-insensitive.
+    #[default]
+    Ignore,
+    /// Case-sensitive only when the query contains an uppercase letter.
+    Smart,
+    /// Always case-sensitive.
+    Respect,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FuzzyConfig {
+    pub case: FuzzyCase,
+    /// Boost matches that start near the beginning of the text. Unset means
+    /// on for the palette (short command names) and off for the filter.
+    pub prefer_prefix: Option<bool>,
+    /// Matches scoring below this are dropped.
+    pub min_score: u32,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Load from `path`, or the default location when `path` is None. A missing
+    /// file at the default location yields the defaults; an explicitly given
+    /// path must exist.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let (path, required) = match path {
+            Some(p) => (p.to_path_buf(), true),
+            None => match default_path() {
+                Some(p) => (p, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text)
+                .map_err(|e| anyhow::anyhow!("invalid config {}: {e}", path.display())),
+            Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow::anyhow!(
+                "cannot read config {}: {e}",
+                path.display()
+            )),
+        }
+    }
+
+    /// Compile the `[[marker]]` rules.
+    pub fn marker_rules(&self) -> anyhow::Result<Vec<MarkerRule>> {
+        self.markers
+            .iter()
+            .map(|m| {
+                Ok(MarkerRule {
+                    label: m.label.clone(),
+                    pattern: Regex::new(&m.pattern)
+                        .map_err(|e| anyhow::anyhow!("marker '{}': {e}", m.label))?,
+                    color: parse_color(&m.color)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Compile the `[[derived]]` field expressions.
+    pub fn derived_fields(&self) -> anyhow::Result<Vec<DerivedField>> {
+        self.derived
+            .iter()
+            .map(|d| DerivedField::parse(&d.name, &d.expr))
+            .collect()
+    }
+
+    /// The profile named `name`, or else the first one (by name) whose `paths`
+    /// match `file`. Naming a profile that doesn't exist is an error.
+    pub fn select_profile(
+        &self,
+        name: Option<&str>,
+        file: Option<&Path>,
+    ) -> anyhow::Result<Option<(&str, &Profile)>> {
+        if let Some(name) = name {
+            return match self.profile.get_key_value(name) {
+                Some((k, p)) => Ok(Some((k.as_str(), p))),
+                None => Err(anyhow::anyhow!("no profile named '{name}' in config")),
+            };
+        }
+        let Some(file) = file else {
+            return Ok(None);
+        };
+        let full = file.to_string_lossy();
+        let file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        Ok(self.profile.iter().find_map(|(k, p)| {
+            p.paths
+                .iter()
+                .any(|g| glob_matches(g, &full) || glob_matches(g, &file_name))
+                .then_some((k.as_str(), p))
+        }))
+    }
+}
+
+fn parse_color(name: &str) -> anyhow::Result<Color> {
+    name.parse::<Color>()
+        .map_err(|_| anyhow::anyhow!("unknown color '{name}'"))
+}
+
+/// Minimal glob: `*` matches any run of characters, `?` exactly one.
+fn glob_matches(glob: &str, text: &str) -> bool {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).is_ok_and(|re| re.is_match(text))
+}
+
+/// `$XDG_CONFIG_HOME/lumolog`, falling back to `~/.config/lumolog`.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(xdg) = std::env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(xdg).join("lumolog"));
+    }
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("lumolog"))
+}
+
+/// `$XDG_STATE_HOME/lumolog`, falling back to `~/.local/state/lumolog`, for
+/// what lumolog remembers between runs.
+pub fn state_dir() -> Option<PathBuf> {
+    if let Some(xdg) = std::env::var_os("XDG_STATE_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(xdg).join("lumolog"));
+    }
+    std::env::var_os("HOME").map(|home| {
+        PathBuf::from(home)
+            .join(".local")
+            .join("state")
+            .join("lumolog")
+    })
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
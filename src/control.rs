@@ -0,0 +1,205 @@
+use crate::parser::LogLevel;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// A command sent to a running viewer with `lumolog --ctl ...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    SetFilter(String),
+    ClearFilter,
+    /// None clears the level filter.
+    SetLevel(Option<LogLevel>),
+    ClearAll,
+    GotoTop,
+    GotoEnd,
+    /// 1-based line number.
+    Goto(usize),
+    Quit,
+}
+
+impl Command {
+    /// Parse `--ctl` words, e.g. `["set-filter", "timeout", "error"]`.
+    pub fn parse(words: &[String]) -> anyhow::Result<Self> {
+        let (name, args) = words
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("missing command"))?;
+        let arg = args.join(" ");
+        let no_args = |cmd: Command| {
+            if args.is_empty() {
+                Ok(cmd)
+            } else {
+                Err(anyhow::anyhow!("'{name}' takes no arguments"))
+            }
+        };
+        match name.as_str() {
+            "set-filter" if !arg.is_empty() => Ok(Command::SetFilter(arg)),
+            "set-filter" => Err(anyhow::anyhow!("set-filter needs the filter text")),
+            "clear-filter" => no_args(Command::ClearFilter),
+            "set-level" => match arg.as_str() {
+                "" | "all" | "none" => Ok(Command::SetLevel(None)),
+                level => LogLevel::from_name(level)
+                    .map(|l| Command::SetLevel(Some(l)))
+                    .ok_or_else(|| anyhow::anyhow!("unknown level '{level}'")),
+            },
+            "clear-all" => no_args(Command::ClearAll),
+            "goto-top" => no_args(Command::GotoTop),
+            "goto-end" => no_args(Command::GotoEnd),
+            "goto" => arg
+                .parse()
+                .map(Command::Goto)
+                .map_err(|_| anyhow::anyhow!("goto needs a line number")),
+            "quit" => no_args(Command::Quit),
+            other => Err(anyhow::anyhow!(
+                "unknown command '{other}' (set-filter, clear-filter, set-level, clear-all, \
+                 goto-top, goto-end, goto, quit)"
+            )),
+        }
+    }
+}
+
+/// Where viewers put their sockets: `$XDG_RUNTIME_DIR/lumolog`, else a
+/// per-user directory under the temp dir.
+pub fn socket_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("lumolog"),
+        #[cfg(unix)]
+        None => std::env::temp_dir().join(format!("lumolog-{}", unsafe { libc::getuid() })),
+        #[cfg(not(unix))]
+        None => std::env::temp_dir().join("lumolog"),
+    }
+}
+
+/// This process's socket path.
+pub fn default_socket_path() -> PathBuf {
+    socket_dir().join(format!("{}.sock", std::process::id()))
+}
+
+/// A listening control socket. Parsed commands arrive on [`Listener::try_recv`];
+/// the socket file is removed on drop.
+pub struct Listener {
+    path: PathBuf,
+    rx: mpsc::Receiver<Command>,
+}
+
+impl Listener {
+    pub fn try_recv(&self) -> Option<Command> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// How long either end of a `--ctl` connection waits on the other to send
+/// the command or the reply. Connections are handled one at a time, so a
+/// stalled client would otherwise hold up every later one, and a hung viewer
+/// would leave the client waiting forever.
+#[cfg(unix)]
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[cfg(unix)]
+pub fn listen(path: &Path) -> std::io::Result<Listener> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::DirBuilderExt;
+    use std::os::unix::net::UnixListener;
+
+    if let Some(dir) = path.parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)?;
+    }
+    // A leftover file from a crashed viewer would make bind fail
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path)?;
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            if stream.set_read_timeout(Some(CLIENT_TIMEOUT)).is_err()
+                || stream.set_write_timeout(Some(CLIENT_TIMEOUT)).is_err()
+            {
+                continue;
+            }
+            let mut line = String::new();
+            if BufReader::new(&stream).read_line(&mut line).is_err() {
+                continue;
+            }
+            let words: Vec<String> = line.split_whitespace().map(String::from).collect();
+            let reply = match Command::parse(&words) {
+                Ok(cmd) => {
+                    if tx.send(cmd).is_err() {
+                        break;
+                    }
+                    "ok".to_string()
+                }
+                Err(e) => format!("error: {e}"),
+            };
+            let _ = writeln!(stream, "{reply}");
+        }
+    });
+    Ok(Listener {
+        path: path.to_path_buf(),
+        rx,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn listen(_path: &Path) -> std::io::Result<Listener> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "control sockets need a Unix platform",
+    ))
+}
+
+/// Send `words` to the viewer at `socket`, or to the most recently started
+/// running viewer when `socket` is None. Returns the viewer's reply.
+#[cfg(unix)]
+pub fn send(socket: Option<&Path>, words: &[String]) -> anyhow::Result<String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    // Catch typos here rather than after connecting
+    Command::parse(words)?;
+    let mut stream = match socket {
+        Some(path) => UnixStream::connect(path)
+            .map_err(|e| anyhow::anyhow!("cannot connect to {}: {e}", path.display()))?,
+        None => newest_viewer().ok_or_else(|| {
+            anyhow::anyhow!("no running lumolog found in {}", socket_dir().display())
+        })?,
+    };
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    writeln!(stream, "{}", words.join(" "))?;
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply)?;
+    let reply = reply.trim_end().to_string();
+    match reply.strip_prefix("error: ") {
+        Some(e) => Err(anyhow::anyhow!("{e}")),
+        None => Ok(reply),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn send(_socket: Option<&Path>, _words: &[String]) -> anyhow::Result<String> {
+    Err(anyhow::anyhow!("--ctl needs a Unix platform"))
+}
+
+/// Connect to the newest socket in `socket_dir` that still has a viewer behind it.
+#[cfg(unix)]
+fn newest_viewer() -> Option<std::os::unix::net::UnixStream> {
+    let mut sockets: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(socket_dir())
+        .ok()?
+        .flatten()
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "sock"))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    sockets.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    sockets
+        .iter()
+        .find_map(|(_, path)| std::os::unix::net::UnixStream::connect(path).ok())
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// Shortest base64 run treated as an encoded token; shorter ones are too
+/// often ordinary words that happen to decode.
+pub const MIN_BASE64_LEN: usize = 16;
+
+/// Claims holding Unix times, shown as dates under the JWT's claims.
+const TIME_CLAIMS: &[(&str, &str)] =
+    &[("iat", "Issued"), ("nbf", "Not before"), ("exp", "Expires")];
+
+/// What an encoded token turned out to hold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decoded {
+    /// A JSON Web Token. The signature is not checked.
+    Jwt { header: Value, claims: Value },
+    /// Base64 (standard or URL-safe) that decodes to text.
+    Text(String),
+}
+
+impl Decoded {
+    /// Popup title: `JWT` or `Base64`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Decoded::Jwt { .. } => "JWT",
+            Decoded::Text(_) => "Base64",
+        }
+    }
+
+    /// What yanking copies: the pretty-printed claims of a JWT, or the text.
+    pub fn payload(&self) -> String {
+        match self {
+            Decoded::Jwt { claims, .. } => pretty(claims),
+            Decoded::Text(text) => text.clone(),
+        }
+    }
+
+    /// `iat`, `nbf` and `exp` claims as dates, in that order, with a label.
+    pub fn times(&self) -> Vec<(&'static str, DateTime<Utc>)> {
+        let Decoded::Jwt { claims, .. } = self else {
+            return Vec::new();
+        };
+        TIME_CLAIMS
+            .iter()
+            .filter_map(|&(claim, label)| {
+                let secs = claims.get(claim)?.as_i64()?;
+                Some((label, DateTime::from_timestamp(secs, 0)?))
+            })
+            .collect()
+    }
+}
+
+/// JSON indented two spaces, as in the pretty view.
+pub fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+}
+
+fn sextet(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a') as u32 + 26),
+        b'0'..=b'9' => Some((c - b'0') as u32 + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    }
+}
+
+/// Decode base64 in either the standard or the URL-safe alphabet, with or
+/// without `=` padding.
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let data = text.trim_end_matches('=').as_bytes();
+    if data.len() % 4 == 1 || text.len() - data.len() > 2 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut bits = 0u32;
+        for &c in chunk {
+            bits = bits << 6 | sextet(c)?;
+        }
+        bits <<= 6 * (4 - chunk.len() as u32);
+        let bytes = bits.to_be_bytes();
+        out.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(out)
+}
+
+/// Decoded bytes as text, when they are UTF-8 without control characters
+/// other than line breaks and tabs.
+fn printable(bytes: Vec<u8>) -> Option<String> {
+    let text = String::from_utf8(bytes).ok()?;
+    text.chars()
+        .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        .then_some(text)
+}
+
+fn json_object(part: &str) -> Option<Value> {
+    let value: Value = serde_json::from_slice(&decode_base64(part)?).ok()?;
+    value.is_object().then_some(value)
+}
+
+/// Decode `token` as a JWT (`header.claims.signature`, both JSON objects)
+/// or as base64 text. None when it's neither, so hashes and ids that only
+/// look like base64 are left alone.
+pub fn decode_token(token: &str) -> Option<Decoded> {
+    let token = token.trim_matches('"');
+    let parts: Vec<&str> = token.split('.').collect();
+    if let [header, claims, _signature] = parts[..] {
+        return Some(Decoded::Jwt {
+            header: json_object(header)?,
+            claims: json_object(claims)?,
+        });
+    }
+    if token.len() < MIN_BASE64_LEN {
+        return None;
+    }
+    decode_base64(token).and_then(printable).map(Decoded::Text)
+}
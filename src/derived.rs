@@ -0,0 +1,293 @@
+use crate::fields::FieldLookup;
+use crate::parser::ParsedLine;
+use crate::units::parse_quantity;
+use regex::Regex;
+
+/// A field computed from other fields when a line is parsed, e.g.
+/// `latency_s = duration_ms / 1000` or `endpoint = regex(path, "^/api/[^/]+")`.
+/// It is stored with the line's extracted fields, so display, filters and the
+/// value distribution treat it like any other field.
+pub struct DerivedField {
+    pub name: String,
+    expr: Expr,
+}
+
+enum Expr {
+    Number(f64),
+    Text(String),
+    Field(FieldLookup, String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, char, Box<Expr>),
+    Regex(Box<Expr>, Regex),
+    Lower(Box<Expr>),
+    Upper(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Ident(String),
+    Punct(char),
+}
+
+fn tokenize(source: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(pos, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit()
+            || (c == '.' && source[pos + 1..].starts_with(|d: char| d.is_ascii_digit()))
+        {
+            let mut end = pos;
+            while let Some(&(i, d)) = chars.peek() {
+                if !(d.is_ascii_digit() || d == '.') {
+                    break;
+                }
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            let text = &source[pos..end];
+            let n = text
+                .parse()
+                .map_err(|_| anyhow::anyhow!("bad number '{text}'"))?;
+            tokens.push(Token::Number(n));
+        } else if c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, e)) => {
+                            // Keep regex escapes like `\d` intact
+                            if e != '"' && e != '\\' {
+                                text.push('\\');
+                            }
+                            text.push(e);
+                        }
+                        None => anyhow::bail!("unterminated string"),
+                    },
+                    Some((_, '"')) => break,
+                    Some((_, ch)) => text.push(ch),
+                    None => anyhow::bail!("unterminated string"),
+                }
+            }
+            tokens.push(Token::Text(text));
+        } else if c.is_alphabetic() || c == '_' || c == '@' {
+            let mut end = pos;
+            while let Some(&(i, d)) = chars.peek() {
+                if !(d.is_alphanumeric() || matches!(d, '_' | '.' | '@')) {
+                    break;
+                }
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Ident(source[pos..end].to_string()));
+        } else if "+-*/(),".contains(c) {
+            tokens.push(Token::Punct(c));
+            chars.next();
+        } else {
+            anyhow::bail!("unexpected '{c}'");
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over `+ -` / `* /` / unary minus / atoms.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, c: char) -> anyhow::Result<()> {
+        match self.next() {
+            Some(Token::Punct(p)) if p == c => Ok(()),
+            _ => anyhow::bail!("expected '{c}'"),
+        }
+    }
+
+    fn sum(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.product()?;
+        while let Some(&Token::Punct(op @ ('+' | '-'))) = self.peek() {
+            self.pos += 1;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.product()?));
+        }
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(&Token::Punct(op @ ('*' | '/'))) = self.peek() {
+            self.pos += 1;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Expr> {
+        if self.peek() == Some(&Token::Punct('-')) {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Expr> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Text(s)) => Ok(Expr::Text(s)),
+            Some(Token::Punct('(')) => {
+                let inner = self.sum()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::Punct('(')) => {
+                self.pos += 1;
+                self.call(&name)
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Field(FieldLookup::new(&name), name)),
+            Some(token) => anyhow::bail!("unexpected {token:?}"),
+            None => anyhow::bail!("unexpected end of expression"),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> anyhow::Result<Expr> {
+        let arg = Box::new(self.sum()?);
+        let expr = match name {
+            "regex" => {
+                self.expect(',')?;
+                let Some(Token::Text(pattern)) = self.next() else {
+                    anyhow::bail!("regex() needs a string pattern");
+                };
+                Expr::Regex(arg, Regex::new(&pattern)?)
+            }
+            "lower" => Expr::Lower(arg),
+            "upper" => Expr::Upper(arg),
+            _ => anyhow::bail!("unknown function '{name}'"),
+        };
+        self.expect(')')?;
+        Ok(expr)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+}
+
+impl Value {
+    /// Numbers as-is; text through the unit parser, so `"2.3s"` is 2300.
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Text(s) => parse_quantity(s).map(|q| q.value),
+        }
+    }
+
+    fn into_text(self) -> String {
+        match self {
+            Value::Number(n) => format_number(n),
+            Value::Text(s) => s,
+        }
+    }
+}
+
+/// Whole numbers without a decimal point, others to at most 6 places.
+fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        return format!("{n:.0}");
+    }
+    let text = format!("{n:.6}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+impl Expr {
+    /// None when a referenced field is missing, a regex doesn't match, or
+    /// arithmetic gets a non-number.
+    fn eval(&self, line: &ParsedLine) -> Option<Value> {
+        match self {
+            Expr::Number(n) => Some(Value::Number(*n)),
+            Expr::Text(s) => Some(Value::Text(s.clone())),
+            Expr::Field(lookup, name) => lookup
+                .get(line)
+                .map(str::to_string)
+                .or_else(|| match name.as_str() {
+                    "message" => Some(line.message.clone()),
+                    "raw" => Some(line.raw.clone()),
+                    _ => None,
+                })
+                .map(Value::Text),
+            Expr::Neg(inner) => Some(Value::Number(-inner.eval(line)?.as_number()?)),
+            Expr::Binary(lhs, op, rhs) => {
+                let a = lhs.eval(line)?.as_number()?;
+                let b = rhs.eval(line)?.as_number()?;
+                let n = match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ if b == 0.0 => return None,
+                    _ => a / b,
+                };
+                Some(Value::Number(n))
+            }
+            Expr::Regex(inner, re) => {
+                let text = inner.eval(line)?.into_text();
+                let caps = re.captures(&text)?;
+                let m = caps.get(1).or_else(|| caps.get(0))?;
+                Some(Value::Text(m.as_str().to_string()))
+            }
+            Expr::Lower(inner) => Some(Value::Text(inner.eval(line)?.into_text().to_lowercase())),
+            Expr::Upper(inner) => Some(Value::Text(inner.eval(line)?.into_text().to_uppercase())),
+        }
+    }
+}
+
+impl DerivedField {
+    pub fn parse(name: &str, source: &str) -> anyhow::Result<Self> {
+        let wrap = |e: anyhow::Error| anyhow::anyhow!("derived field '{name}': {e}");
+        let mut parser = Parser {
+            tokens: tokenize(source).map_err(wrap)?,
+            pos: 0,
+        };
+        let expr = parser.sum().map_err(wrap)?;
+        if parser.pos < parser.tokens.len() {
+            return Err(wrap(anyhow::anyhow!("unexpected trailing input")));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            expr,
+        })
+    }
+
+    /// The field's value for `line`, if it can be computed.
+    pub fn eval(&self, line: &ParsedLine) -> Option<String> {
+        self.expr.eval(line).map(Value::into_text)
+    }
+}
+
+/// Compute each derived field in order and store it on the line. Later
+/// fields can use earlier ones.
+pub fn apply(fields: &[DerivedField], line: &mut ParsedLine) {
+    for field in fields {
+        let Some(value) = field.eval(line) else {
+            continue;
+        };
+        match line.extra_fields.iter_mut().find(|(k, _)| *k == field.name) {
+            Some((_, v)) => *v = value,
+            None => line.extra_fields.push((field.name.clone(), value)),
+        }
+    }
+}
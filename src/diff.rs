@@ -0,0 +1,98 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Splits a line into alternating word / whitespace tokens so the diff can be
+/// reassembled into the original text.
+static TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\S+|\s+").unwrap());
+
+/// Above this many LCS cells the middle of the lines is reported as one
+/// removed/added block instead of being diffed token by token.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffOp {
+    Equal(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Token-level diff between two lines. Tokens are runs of non-whitespace or
+/// whitespace, so concatenating the `Equal` + `Removed` ops gives back `old`
+/// and the `Equal` + `Added` ops give back `new`.
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffOp> {
+    let a: Vec<&str> = TOKEN_RE.find_iter(old).map(|m| m.as_str()).collect();
+    let b: Vec<&str> = TOKEN_RE.find_iter(new).map(|m| m.as_str()).collect();
+
+    // Common prefix and suffix are cheap and usually most of a log line
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let mut ops = Vec::new();
+    for t in &a[..prefix] {
+        push_op(&mut ops, DiffOp::Equal(t.to_string()));
+    }
+    if a_mid.len() * b_mid.len() > MAX_LCS_CELLS {
+        push_op(&mut ops, DiffOp::Removed(a_mid.concat()));
+        push_op(&mut ops, DiffOp::Added(b_mid.concat()));
+    } else {
+        for op in lcs_diff(a_mid, b_mid) {
+            push_op(&mut ops, op);
+        }
+    }
+    for t in &a[a.len() - suffix..] {
+        push_op(&mut ops, DiffOp::Equal(t.to_string()));
+    }
+    ops
+}
+
+/// Classic LCS table walk over the differing middle section.
+fn lcs_diff(a: &[&str], b: &[&str]) -> Vec<DiffOp> {
+    let (n, m) = (a.len(), b.len());
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            ops.push(DiffOp::Equal(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(DiffOp::Removed(a[i].to_string()));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Added(b[j].to_string()));
+            j += 1;
+        }
+    }
+    ops.extend(a[i..].iter().map(|t| DiffOp::Removed(t.to_string())));
+    ops.extend(b[j..].iter().map(|t| DiffOp::Added(t.to_string())));
+    ops
+}
+
+/// Append an op, merging it into the previous one when they are the same kind.
+fn push_op(ops: &mut Vec<DiffOp>, op: DiffOp) {
+    match (ops.last_mut(), &op) {
+        (Some(DiffOp::Equal(prev)), DiffOp::Equal(s))
+        | (Some(DiffOp::Removed(prev)), DiffOp::Removed(s))
+        | (Some(DiffOp::Added(prev)), DiffOp::Added(s)) => prev.push_str(s),
+        _ => ops.push(op),
+    }
+}
@@ -0,0 +1,309 @@
+use crate::parser::{LogLevel, ParsedLine, is_blank};
+use std::collections::BTreeMap;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often counts are pushed to the endpoint.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Most templates counted by name; lines of any others count as `other`, so
+/// a log with ids in odd places can't flood the backend with series.
+pub const MAX_TEMPLATES: usize = 200;
+
+/// Longest template name sent; longer ones are cut.
+const MAX_TEMPLATE_LEN: usize = 100;
+
+/// Longest an OTLP push may take, so a stalled collector can't hold the
+/// exporter's thread forever.
+const PUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Statsd datagrams are kept under this many bytes, to fit one packet.
+const MAX_DATAGRAM: usize = 1432;
+
+/// Lines read, by level (`none` for lines without one).
+pub const LINES: Counter = Counter {
+    name: "lumolog.lines",
+    key: "level",
+};
+
+/// Lines read, by structural template.
+pub const TEMPLATE_LINES: Counter = Counter {
+    name: "lumolog.template_lines",
+    key: "template",
+};
+
+/// Lines matching a marker rule, by rule label.
+pub const RULE_MATCHES: Counter = Counter {
+    name: "lumolog.rule_matches",
+    key: "rule",
+};
+
+/// A counter pushed with one attribute (a statsd tag) telling its series
+/// apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Counter {
+    pub name: &'static str,
+    pub key: &'static str,
+}
+
+/// Where `--export-metrics` pushes counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// `statsd://host:port`: DogStatsD counters over UDP.
+    Statsd(String),
+    /// `http(s)://collector:4318`: OTLP metrics as JSON over HTTP, posted to
+    /// `/v1/metrics` unless the URL already names that path.
+    Otlp(String),
+}
+
+impl Target {
+    pub fn parse(url: &str) -> anyhow::Result<Self> {
+        if let Some(addr) = url.strip_prefix("statsd://") {
+            let addr = addr.trim_end_matches('/');
+            if addr
+                .rsplit_once(':')
+                .is_none_or(|(host, port)| host.is_empty() || port.parse::<u16>().is_err())
+            {
+                anyhow::bail!("expected statsd://host:port, got {url}");
+            }
+            return Ok(Target::Statsd(addr.to_string()));
+        }
+        if url.starts_with("http://") || url.starts_with("https://") {
+            let url = url.trim_end_matches('/');
+            return Ok(Target::Otlp(if url.ends_with("/v1/metrics") {
+                url.to_string()
+            } else {
+                format!("{url}/v1/metrics")
+            }));
+        }
+        anyhow::bail!("unsupported metrics endpoint {url}: use statsd://host:port or http(s)://")
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    /// Running totals by counter and attribute value.
+    totals: BTreeMap<(Counter, String), u64>,
+    /// `totals` as of the last statsd send, which takes deltas.
+    sent: BTreeMap<(Counter, String), u64>,
+    /// Templates counted by name so far, at most [`MAX_TEMPLATES`].
+    templates: usize,
+}
+
+impl Counts {
+    fn add(&mut self, counter: Counter, value: &str) {
+        *self.totals.entry((counter, value.to_string())).or_insert(0) += 1;
+    }
+
+    fn add_template(&mut self, template: &str) {
+        let name: String = template.chars().take(MAX_TEMPLATE_LEN).collect();
+        let key = (TEMPLATE_LINES, name);
+        if !self.totals.contains_key(&key) {
+            if self.templates >= MAX_TEMPLATES {
+                self.add(TEMPLATE_LINES, "other");
+                return;
+            }
+            self.templates += 1;
+        }
+        *self.totals.entry(key).or_insert(0) += 1;
+    }
+}
+
+/// Counters for a long-running follow session, pushed to a statsd or OTLP
+/// endpoint every [`FLUSH_INTERVAL`] by [`start`].
+#[derive(Debug)]
+pub struct Exporter {
+    target: Target,
+    counts: Mutex<Counts>,
+    started: SystemTime,
+    agent: ureq::Agent,
+}
+
+fn level_name(level: Option<LogLevel>) -> &'static str {
+    match level {
+        Some(LogLevel::Trace) => "trace",
+        Some(LogLevel::Debug) => "debug",
+        Some(LogLevel::Info) => "info",
+        Some(LogLevel::Warn) => "warn",
+        Some(LogLevel::Error) => "error",
+        Some(LogLevel::Fatal) => "fatal",
+        None => "none",
+    }
+}
+
+/// `value` usable as a DogStatsD tag value: no separators or whitespace.
+fn tag_value(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| {
+            if matches!(c, '|' | ',' | '#') || c.is_whitespace() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string()
+}
+
+impl Exporter {
+    pub fn new(target: Target) -> Self {
+        Self {
+            target,
+            counts: Mutex::default(),
+            started: SystemTime::now(),
+            agent: ureq::Agent::config_builder()
+                .timeout_global(Some(PUSH_TIMEOUT))
+                .build()
+                .into(),
+        }
+    }
+
+    /// Count newly read lines, each with the label of the marker rule it
+    /// matched, if any. Blank lines don't count.
+    pub fn observe<'a>(&self, lines: impl IntoIterator<Item = (&'a ParsedLine, Option<&'a str>)>) {
+        let mut counts = self.counts.lock().unwrap();
+        for (line, rule) in lines {
+            if is_blank(&line.raw) {
+                continue;
+            }
+            counts.add(LINES, level_name(line.level));
+            if !line.template.is_empty() {
+                counts.add_template(&line.template);
+            }
+            if let Some(rule) = rule {
+                counts.add(RULE_MATCHES, rule);
+            }
+        }
+    }
+
+    /// Statsd lines for what was counted since the last call, like
+    /// `lumolog.lines:12|c|#level:error`.
+    pub fn take_statsd_lines(&self) -> Vec<String> {
+        let mut counts = self.counts.lock().unwrap();
+        let counts = &mut *counts;
+        let mut lines = Vec::new();
+        for (key @ (counter, value), &total) in &counts.totals {
+            let sent = counts.sent.get(key).copied().unwrap_or(0);
+            if total > sent {
+                lines.push(format!(
+                    "{}:{}|c|#{}:{}",
+                    counter.name,
+                    total - sent,
+                    counter.key,
+                    tag_value(value)
+                ));
+            }
+        }
+        counts.sent = counts.totals.clone();
+        lines
+    }
+
+    /// An OTLP `ExportMetricsServiceRequest` with the running totals as
+    /// cumulative sums since the exporter started.
+    pub fn otlp_body(&self) -> serde_json::Value {
+        let counts = self.counts.lock().unwrap();
+        let start = unix_nanos(self.started);
+        let now = unix_nanos(SystemTime::now());
+        let mut by_counter: BTreeMap<Counter, Vec<serde_json::Value>> = BTreeMap::new();
+        for ((counter, value), total) in &counts.totals {
+            by_counter
+                .entry(*counter)
+                .or_default()
+                .push(serde_json::json!({
+                    "attributes": [{"key": counter.key, "value": {"stringValue": value}}],
+                    "startTimeUnixNano": start,
+                    "timeUnixNano": now,
+                    "asInt": total.to_string(),
+                }));
+        }
+        let metrics: Vec<serde_json::Value> = by_counter
+            .into_iter()
+            .map(|(counter, points)| {
+                serde_json::json!({
+                    "name": counter.name,
+                    "sum": {
+                        // Cumulative
+                        "aggregationTemporality": 2,
+                        "isMonotonic": true,
+                        "dataPoints": points,
+                    },
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{"key": "service.name", "value": {"stringValue": "lumolog"}}],
+                },
+                "scopeMetrics": [{
+                    "scope": {"name": "lumolog", "version": env!("CARGO_PKG_VERSION")},
+                    "metrics": metrics,
+                }],
+            }],
+        })
+    }
+
+    /// Push the counts to the endpoint now.
+    pub fn flush(&self) -> anyhow::Result<()> {
+        match &self.target {
+            Target::Statsd(addr) => {
+                let lines = self.take_statsd_lines();
+                if lines.is_empty() {
+                    return Ok(());
+                }
+                let dest = addr
+                    .to_socket_addrs()?
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("cannot resolve {addr}"))?;
+                let bind = if dest.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                };
+                let socket = UdpSocket::bind(bind)?;
+                let mut datagram = String::new();
+                for line in lines {
+                    if !datagram.is_empty() && datagram.len() + 1 + line.len() > MAX_DATAGRAM {
+                        socket.send_to(datagram.as_bytes(), dest)?;
+                        datagram.clear();
+                    }
+                    if !datagram.is_empty() {
+                        datagram.push('\n');
+                    }
+                    datagram.push_str(&line);
+                }
+                socket.send_to(datagram.as_bytes(), dest)?;
+            }
+            Target::Otlp(url) => {
+                if self.counts.lock().unwrap().totals.is_empty() {
+                    return Ok(());
+                }
+                self.agent
+                    .post(url)
+                    .send_json(self.otlp_body())
+                    .map_err(|e| anyhow::anyhow!("cannot push metrics to {url}: {e}"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Flush `exporter` every [`FLUSH_INTERVAL`] from a background thread. A
+/// failed push is dropped; statsd counts sent into the void are lost, OTLP
+/// totals go out again with the next push.
+pub fn start(exporter: Arc<Exporter>) {
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(FLUSH_INTERVAL);
+            let _ = exporter.flush();
+        }
+    });
+}
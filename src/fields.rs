@@ -0,0 +1,201 @@
+use crate::parser::ParsedLine;
+use crate::units::{Dimension, Quantity, parse_quantity};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// How many distinct values the distribution popup lists.
+pub const TOP_VALUES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+    /// Filter text that selects lines with this value, as it appears in the
+    /// raw line (`status=500`, `"status":500`, ...).
+    pub filter: String,
+}
+
+/// Most common values of one key across a set of lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueDistribution {
+    pub key: String,
+    /// Most frequent first; ties in first-seen order.
+    pub values: Vec<ValueCount>,
+    /// Lines that had the key at all.
+    pub lines_with_key: usize,
+    /// Distinct values beyond the ones listed.
+    pub other_values: usize,
+}
+
+/// Looks up one key's value in parsed lines.
+pub struct FieldLookup {
+    key: String,
+    raw_re: Regex,
+}
+
+impl FieldLookup {
+    pub fn new(key: &str) -> Self {
+        let raw_re =
+            Regex::new(&format!(r#"(?:^|\s){}=("[^"]*"|\S+)"#, regex::escape(key))).unwrap();
+        Self {
+            key: key.to_string(),
+            raw_re,
+        }
+    }
+
+    /// Value of the key in `line`, without quotes: its extracted field when
+    /// the parser found one, otherwise a `key=value` pair in the raw text.
+    pub fn get<'a>(&self, line: &'a ParsedLine) -> Option<&'a str> {
+        if let Some((_, v)) = line.extra_fields.iter().find(|(k, _)| *k == self.key) {
+            return Some(v.trim_matches('"'));
+        }
+        self.raw_re
+            .captures(&line.raw)
+            .and_then(|caps| caps.get(1))
+            .map(|m| m.as_str().trim_matches('"'))
+    }
+}
+
+/// Order statistics of a key's numeric values, normalized to one unit (ms
+/// for durations, bytes for sizes) so `2.3s` and `850ms` compare correctly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumericSummary {
+    pub dimension: Dimension,
+    /// Values that parsed as numbers.
+    pub count: usize,
+    pub min: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+impl NumericSummary {
+    /// A statistic back in the key's dimension, for [`format_quantity`].
+    ///
+    /// [`format_quantity`]: crate::units::format_quantity
+    pub fn quantity(&self, value: f64) -> Quantity {
+        Quantity {
+            value,
+            dimension: self.dimension,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`.
+fn percentile(values: &[f64], p: f64) -> f64 {
+    let rank = (p * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+/// Summarize the numeric values of `key` over `indices`. Values that aren't
+/// numbers are skipped, bare numbers count in the key's unit, and None is
+/// returned when nothing parses or durations and sizes are mixed.
+pub fn numeric_summary(
+    lines: &[ParsedLine],
+    indices: &[usize],
+    key: &str,
+) -> Option<NumericSummary> {
+    let lookup = FieldLookup::new(key);
+    let mut dimension = Dimension::Scalar;
+    let mut values = Vec::new();
+    for &idx in indices {
+        let Some(q) = lookup.get(&lines[idx]).and_then(parse_quantity) else {
+            continue;
+        };
+        if q.dimension != Dimension::Scalar {
+            if dimension != Dimension::Scalar && dimension != q.dimension {
+                return None;
+            }
+            dimension = q.dimension;
+        }
+        values.push(q.value);
+    }
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    Some(NumericSummary {
+        dimension,
+        count: values.len(),
+        min: values[0],
+        p50: percentile(&values, 0.5),
+        p95: percentile(&values, 0.95),
+        max: values[values.len() - 1],
+    })
+}
+
+/// Order `values` by their normalized numeric value, smallest first, with
+/// values that aren't numbers last in their current order.
+pub fn sort_by_quantity(values: &mut [ValueCount]) {
+    values.sort_by(
+        |a, b| match (parse_quantity(&a.value), parse_quantity(&b.value)) {
+            (Some(x), Some(y)) => x.value.total_cmp(&y.value),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        },
+    );
+}
+
+/// The way `key` and `value` are spelled in `raw`, so a plain substring
+/// filter picks out the same lines. Falls back to the bare value.
+fn filter_text(raw: &str, key: &str, value: &str) -> String {
+    [
+        format!("{key}={value}"),
+        format!("{key}=\"{value}\""),
+        format!("\"{key}\":\"{value}\""),
+        format!("\"{key}\": \"{value}\""),
+        format!("\"{key}\":{value}"),
+        format!("\"{key}\": {value}"),
+    ]
+    .into_iter()
+    .find(|candidate| raw.contains(candidate.as_str()))
+    .unwrap_or_else(|| value.to_string())
+}
+
+/// Count the values of `key` over `indices` and keep the `limit` most common.
+pub fn value_distribution(
+    lines: &[ParsedLine],
+    indices: &[usize],
+    key: &str,
+    limit: usize,
+) -> ValueDistribution {
+    let lookup = FieldLookup::new(key);
+
+    // value -> (count, first line seen), kept in first-seen order
+    let mut counts: Vec<(String, usize, usize)> = Vec::new();
+    let mut pos: HashMap<String, usize> = HashMap::new();
+    let mut lines_with_key = 0;
+    for &idx in indices {
+        let Some(value) = lookup.get(&lines[idx]) else {
+            continue;
+        };
+        lines_with_key += 1;
+        match pos.get(value) {
+            Some(&p) => counts[p].1 += 1,
+            None => {
+                pos.insert(value.to_string(), counts.len());
+                counts.push((value.to_string(), 1, idx));
+            }
+        }
+    }
+
+    let distinct = counts.len();
+    // Stable sort keeps first-seen order among equal counts
+    counts.sort_by_key(|&(_, count, _)| std::cmp::Reverse(count));
+    let values = counts
+        .into_iter()
+        .take(limit)
+        .map(|(value, count, idx)| ValueCount {
+            filter: filter_text(&lines[idx].raw, key, &value),
+            value,
+            count,
+        })
+        .collect::<Vec<_>>();
+    ValueDistribution {
+        key: key.to_string(),
+        other_values: distinct - values.len(),
+        values,
+        lines_with_key,
+    }
+}
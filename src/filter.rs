@@ -1,21 +1,675 @@
+use crate::config::{FuzzyCase, FuzzyConfig};
+use crate::fields::FieldLookup;
 use crate::parser::{LogLevel, ParsedLine};
+use crate::units::{Dimension, Quantity, parse_quantity};
 use nucleo_matcher::pattern::{AtomKind, CaseMatching, Normalization, Pattern};
 use nucleo_matcher::{Config, Matcher, Utf32Str};
+use regex::Regex;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// `duration_ms > 500`, `size <= 10MB`, `status != 200`
+static FIELD_CMP_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*([\w.@-]+)\s*(>=|<=|==|!=|>|<)\s*(\S+)\s*$").unwrap());
+
+/// `status in 500..599` (both ends inclusive)
+static FIELD_RANGE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*([\w.@-]+)\s+in\s+(\S+?)\.\.(\S+)\s*$").unwrap());
+
+/// `service=auth`, `user="Jane Doe"`
+static FIELD_EQ_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^\s*([\w.@-]+)=("[^"]*"|[^\s"=]+)\s*$"#).unwrap());
+
+/// `has:trace_id`, `missing:user`
+static FIELD_PRESENCE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(has|missing):([\w.@-]+)\s*$").unwrap());
+
+/// Filter text starting with this is a regular expression.
+pub const REGEX_PREFIX: &str = "re:";
+
+/// The regex of a `re:` filter, or None for ordinary filter text. A bad
+/// pattern gives the last line of the regex error, e.g. `unclosed group`.
+pub fn regex_filter(pattern: &str) -> Option<Result<Regex, String>> {
+    let expr = pattern.strip_prefix(REGEX_PREFIX)?;
+    Some(Regex::new(expr).map_err(|e| {
+        let text = e.to_string();
+        let last = text.lines().last().unwrap_or_default();
+        last.trim_start_matches("error: ").to_string()
+    }))
+}
+
+/// A nucleo matcher + pattern built from a [`FuzzyConfig`], shared by the
+/// filter fallback and the command palette.
+pub struct FuzzyMatcher {
+    matcher: Matcher,
+    pattern: Pattern,
+    min_score: u32,
+    buf: Vec<char>,
+}
+
+impl FuzzyMatcher {
+    /// `prefer_prefix_default` applies when the config leaves it unset.
+    pub fn new(query: &str, config: &FuzzyConfig, prefer_prefix_default: bool) -> Self {
+        let mut matcher_config = Config::DEFAULT;
+        matcher_config.prefer_prefix = config.prefer_prefix.unwrap_or(prefer_prefix_default);
+        let case = match config.case {
+            FuzzyCase::Ignore => CaseMatching::Ignore,
+            FuzzyCase::Smart => CaseMatching::Smart,
+            FuzzyCase::Respect => CaseMatching::Respect,
+        };
+        Self {
+            matcher: Matcher::new(matcher_config),
+            pattern: Pattern::new(query, case, Normalization::Smart, AtomKind::Fuzzy),
+            min_score: config.min_score,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Score `haystack`, or None if it doesn't match or scores below the threshold.
+    pub fn score(&mut self, haystack: &str) -> Option<u32> {
+        self.buf.clear();
+        let haystack = Utf32Str::new(haystack, &mut self.buf);
+        self.pattern
+            .score(haystack, &mut self.matcher)
+            .filter(|&s| s >= self.min_score)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+enum Bound {
+    Cmp(CmpOp, Quantity),
+    Range(Quantity, Quantity),
+}
+
+/// A numeric condition on one field, typed into the filter bar instead of
+/// search text. Units on either side are normalized (`2.3s` is 2300ms), and a
+/// bare number is taken to already be in the other side's unit.
+pub struct FieldPredicate {
+    lookup: FieldLookup,
+    bound: Bound,
+}
+
+/// Whether two quantities can be compared: same dimension, or one is bare.
+fn comparable(a: Quantity, b: Quantity) -> bool {
+    a.dimension == b.dimension
+        || a.dimension == Dimension::Scalar
+        || b.dimension == Dimension::Scalar
+}
+
+impl FieldPredicate {
+    /// Parse `key OP number[unit]` or `key in low..high`; anything else is
+    /// ordinary search text.
+    pub fn parse(pattern: &str) -> Option<Self> {
+        if let Some(caps) = FIELD_RANGE_RE.captures(pattern) {
+            let low = parse_quantity(&caps[2])?;
+            let high = parse_quantity(&caps[3])?;
+            return Some(Self {
+                lookup: FieldLookup::new(&caps[1]),
+                bound: Bound::Range(low, high),
+            });
+        }
+        let caps = FIELD_CMP_RE.captures(pattern)?;
+        let op = match &caps[2] {
+            ">" => CmpOp::Gt,
+            ">=" => CmpOp::Ge,
+            "<" => CmpOp::Lt,
+            "<=" => CmpOp::Le,
+            "==" => CmpOp::Eq,
+            _ => CmpOp::Ne,
+        };
+        Some(Self {
+            lookup: FieldLookup::new(&caps[1]),
+            bound: Bound::Cmp(op, parse_quantity(&caps[3])?),
+        })
+    }
+
+    /// True when the line has the field, it parses as a number with a
+    /// compatible unit, and it satisfies the condition.
+    pub fn matches(&self, line: &ParsedLine) -> bool {
+        let Some(value) = self.lookup.get(line).and_then(parse_quantity) else {
+            return false;
+        };
+        match self.bound {
+            Bound::Cmp(op, target) => {
+                comparable(value, target)
+                    && match op {
+                        CmpOp::Gt => value.value > target.value,
+                        CmpOp::Ge => value.value >= target.value,
+                        CmpOp::Lt => value.value < target.value,
+                        CmpOp::Le => value.value <= target.value,
+                        CmpOp::Eq => (value.value - target.value).abs() < f64::EPSILON,
+                        CmpOp::Ne => (value.value - target.value).abs() >= f64::EPSILON,
+                    }
+            }
+            Bound::Range(low, high) => {
+                comparable(value, low)
+                    && comparable(value, high)
+                    && (low.value..=high.value).contains(&value.value)
+            }
+        }
+    }
+}
+
+/// A `key=value` filter: lines whose field has that value, ignoring case.
+/// The field is looked up like [`FieldPredicate`]'s, so it matches JSON and
+/// logfmt fields alike, not just the text `key=value`.
+pub struct FieldEquals {
+    lookup: FieldLookup,
+    value: String,
+}
+
+impl FieldEquals {
+    /// Parse `key=value`, with the value optionally in double quotes.
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let caps = FIELD_EQ_RE.captures(pattern)?;
+        Some(Self {
+            lookup: FieldLookup::new(&caps[1]),
+            value: caps[2].trim_matches('"').to_lowercase(),
+        })
+    }
+
+    pub fn matches(&self, line: &ParsedLine) -> bool {
+        self.lookup
+            .get(line)
+            .is_some_and(|v| v.to_lowercase() == self.value)
+    }
+}
+
+/// A `has:key` or `missing:key` filter: lines that have the field, or lack
+/// it, whatever its value. Looked up like [`FieldEquals`], so `missing:user`
+/// finds the unstructured stragglers in an otherwise structured log.
+pub struct FieldPresence {
+    lookup: FieldLookup,
+    present: bool,
+}
+
+impl FieldPresence {
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let caps = FIELD_PRESENCE_RE.captures(pattern)?;
+        Some(Self {
+            lookup: FieldLookup::new(&caps[2]),
+            present: &caps[1] == "has",
+        })
+    }
+
+    pub fn matches(&self, line: &ParsedLine) -> bool {
+        self.lookup.get(line).is_some() == self.present
+    }
+}
+
+/// One step into a JSON value: `.key`, `."odd key"`, `["key"]` or `[2]`.
+#[derive(Debug, Clone, PartialEq)]
+enum PathStep {
+    Key(String),
+    Index(usize),
+}
+
+/// A jq-style condition on JSON lines: `.user.id == 42`,
+/// `.duration_ms > 100`, `.tags[0] == "beta"`, or a bare path like
+/// `.error` for lines where it's there and not null or false. A missing
+/// value is null, as in jq, so `.user.id != 42` keeps lines without one.
+pub struct JsonFilter {
+    path: Vec<PathStep>,
+    cmp: Option<(CmpOp, serde_json::Value)>,
+}
+
+/// The steps of a path at the start of `text` and what follows it. None
+/// unless `text` starts with a path of at least one step.
+fn parse_json_path(text: &str) -> Option<(Vec<PathStep>, &str)> {
+    let mut steps = Vec::new();
+    let mut rest = text.strip_prefix('.')?;
+    loop {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            steps.push(PathStep::Key(quoted[..end].to_string()));
+            rest = &quoted[end + 1..];
+        } else if let Some(inner) = rest.strip_prefix('[') {
+            let end = inner.find(']')?;
+            let index = inner[..end].trim();
+            steps.push(
+                match index.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+                    Some(key) => PathStep::Key(key.to_string()),
+                    None => PathStep::Index(index.parse().ok()?),
+                },
+            );
+            rest = &inner[end + 1..];
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '@' | '$')))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return None;
+            }
+            steps.push(PathStep::Key(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        match rest.strip_prefix('.') {
+            Some(next) => rest = next,
+            None if rest.starts_with('[') => {}
+            None => break,
+        }
+    }
+    Some((steps, rest))
+}
+
+impl JsonFilter {
+    /// Parse `.path`, or `.path OP value` where the value is JSON (`42`,
+    /// `"a b"`, `true`, `null`) or a bare word taken as a string.
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let (path, rest) = parse_json_path(pattern.trim())?;
+        let rest = rest.trim();
+        if rest.is_empty() {
+            return Some(Self { path, cmp: None });
+        }
+        let (op, literal) = [
+            (">=", CmpOp::Ge),
+            ("<=", CmpOp::Le),
+            ("==", CmpOp::Eq),
+            ("!=", CmpOp::Ne),
+            (">", CmpOp::Gt),
+            ("<", CmpOp::Lt),
+        ]
+        .into_iter()
+        .find_map(|(text, op)| Some((op, rest.strip_prefix(text)?.trim())))?;
+        if literal.is_empty() {
+            return None;
+        }
+        let value = serde_json::from_str(literal)
+            .unwrap_or_else(|_| serde_json::Value::String(literal.to_string()));
+        Some(Self {
+            path,
+            cmp: Some((op, value)),
+        })
+    }
+
+    /// Whether this compares the value rather than just checking for it.
+    pub fn compares(&self) -> bool {
+        self.cmp.is_some()
+    }
+
+    /// True for a JSON line whose value at the path passes.
+    pub fn matches(&self, line: &ParsedLine) -> bool {
+        line.json
+            .as_ref()
+            .is_some_and(|value| self.matches_value(value))
+    }
+
+    /// Whether `line` is JSON with something at the path, even null.
+    pub fn has_path(&self, line: &ParsedLine) -> bool {
+        line.json
+            .as_ref()
+            .is_some_and(|value| self.lookup(value).is_some())
+    }
+
+    fn lookup<'a>(&self, value: &'a serde_json::Value) -> Option<&'a serde_json::Value> {
+        self.path.iter().try_fold(value, |v, step| match step {
+            PathStep::Key(key) => v.get(key),
+            PathStep::Index(i) => v.get(i),
+        })
+    }
+
+    pub fn matches_value(&self, value: &serde_json::Value) -> bool {
+        let actual = self.lookup(value).unwrap_or(&serde_json::Value::Null);
+        let Some((op, expected)) = &self.cmp else {
+            return !matches!(
+                actual,
+                serde_json::Value::Null | serde_json::Value::Bool(false)
+            );
+        };
+        use serde_json::Value::{Number, String};
+        let ordering = match (actual, expected) {
+            (Number(a), Number(b)) => a
+                .as_f64()
+                .zip(b.as_f64())
+                .and_then(|(a, b)| a.partial_cmp(&b)),
+            (String(a), String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        let equal = ordering.map_or(actual == expected, |o| o.is_eq());
+        match op {
+            CmpOp::Eq => equal,
+            CmpOp::Ne => !equal,
+            CmpOp::Gt => ordering.is_some_and(|o| o.is_gt()),
+            CmpOp::Ge => ordering.is_some_and(|o| o.is_ge()),
+            CmpOp::Lt => ordering.is_some_and(|o| o.is_lt()),
+            CmpOp::Le => ordering.is_some_and(|o| o.is_le()),
+        }
+    }
+}
 
 /// Result of filtering log lines — carries the matching indices and whether
 /// fuzzy matching was used (so the UI can indicate it).
 pub struct FilterResult {
     pub indices: Vec<usize>,
     pub is_fuzzy: bool,
+    /// Why a `re:` pattern didn't compile; the pattern is ignored until fixed.
+    pub error: Option<String>,
+    /// Lines at the minimum level, which the text filters chose from.
+    pub candidates: usize,
+}
+
+/// Filtering that takes longer than this is reported: it happens again on
+/// every keystroke.
+pub const SLOW_FILTER: Duration = Duration::from_millis(250);
+
+/// Something about a filter worth knowing before relying on it, shown in
+/// the filter bar while typing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterWarning {
+    /// Filtering took this long.
+    Slow(Duration),
+    /// Every line the filters were given is kept, e.g. `re:.*` or `!zzz`.
+    KeepsEverything,
+    /// No line is kept, not even by fuzzy matching.
+    KeepsNothing,
+}
+
+impl FilterWarning {
+    /// What to say about filtering with `terms`, which gave `result` and
+    /// took `elapsed`. A bad regex has its own message instead.
+    pub fn check(terms: &[&str], result: &FilterResult, elapsed: Duration) -> Option<Self> {
+        if terms.is_empty() || result.error.is_some() {
+            return None;
+        }
+        if elapsed >= SLOW_FILTER {
+            Some(FilterWarning::Slow(elapsed))
+        } else if result.candidates > 1 && result.indices.len() == result.candidates {
+            Some(FilterWarning::KeepsEverything)
+        } else if result.candidates > 0 && result.indices.is_empty() {
+            Some(FilterWarning::KeepsNothing)
+        } else {
+            None
+        }
+    }
+
+    pub fn message(self) -> String {
+        match self {
+            FilterWarning::Slow(elapsed) => {
+                format!("slow: {:.1}s per keystroke", elapsed.as_secs_f64())
+            }
+            FilterWarning::KeepsEverything => "keeps every line".to_string(),
+            FilterWarning::KeepsNothing => "keeps no lines".to_string(),
+        }
+    }
+}
+
+/// Filter text starting with this hides the lines the rest would show.
+pub const EXCLUDE_PREFIX: char = '!';
+
+/// Separates alternatives within one filter term, e.g. `timeout | refused`.
+/// The spaces are required: a bare `|` is a common field separator in logs.
+pub const OR_SEPARATOR: &str = " | ";
+
+/// How plain-text filters treat letter case. Regexes are always
+/// case-sensitive and field values never are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    #[default]
+    Ignore,
+    /// Case-sensitive only when the text has an uppercase letter.
+    Smart,
+    Sensitive,
+}
+
+impl CaseMode {
+    /// Whether `text` is matched with its case as typed.
+    pub fn is_sensitive(self, text: &str) -> bool {
+        match self {
+            CaseMode::Ignore => false,
+            CaseMode::Smart => text.chars().any(char::is_uppercase),
+            CaseMode::Sensitive => true,
+        }
+    }
+
+    /// Ignore → smart → sensitive → ignore.
+    pub fn next(self) -> Self {
+        match self {
+            CaseMode::Ignore => CaseMode::Smart,
+            CaseMode::Smart => CaseMode::Sensitive,
+            CaseMode::Sensitive => CaseMode::Ignore,
+        }
+    }
+
+    /// Shown in the filter bar; None for the default.
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            CaseMode::Ignore => None,
+            CaseMode::Smart => Some("smart case"),
+            CaseMode::Sensitive => Some("case-sensitive"),
+        }
+    }
+}
+
+/// How plain filter text is looked for in a line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextMatch {
+    pub case: CaseMode,
+    /// Only where the text isn't part of a longer word: `err` finds `err:`
+    /// and `[err]` but not `transferred`.
+    pub whole_word: bool,
+}
+
+impl TextMatch {
+    /// What's on besides the defaults, for the filter and status bars:
+    /// `smart case, whole word`. None when nothing is.
+    pub fn label(self) -> Option<String> {
+        let labels: Vec<&str> = self
+            .case
+            .label()
+            .into_iter()
+            .chain(self.whole_word.then_some("whole word"))
+            .collect();
+        (!labels.is_empty()).then(|| labels.join(", "))
+    }
 }
 
-/// Returns indices of lines matching the pattern (case-insensitive substring match)
-/// and at or above the minimum log level. Falls back to fuzzy matching when exact
-/// substring match returns zero results.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether `text[start..end]` stands alone, with no letter, digit or `_`
+/// right before or after it.
+pub fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    !text[..start].chars().next_back().is_some_and(is_word_char)
+        && !text[end..].chars().next().is_some_and(is_word_char)
+}
+
+/// Whether `needle` is somewhere in `haystack` as a whole word.
+pub fn contains_word(haystack: &str, needle: &str) -> bool {
+    haystack
+        .match_indices(needle)
+        .any(|(start, m)| is_whole_word(haystack, start, start + m.len()))
+}
+
+/// Returns indices of lines matching the pattern (case-insensitive substring match,
+/// a `re:` regex, or a [`FieldPredicate`]) and at or above the minimum log level.
+/// Falls back to fuzzy matching when exact substring match returns zero results.
+/// A leading `!` keeps the lines that don't match instead, without the fuzzy
+/// fallback.
+#[allow(dead_code)]
 pub fn filter_lines(
     lines: &[ParsedLine],
     pattern: &str,
     min_level: Option<LogLevel>,
+) -> FilterResult {
+    filter_lines_with(lines, pattern, min_level, &FuzzyConfig::default())
+}
+
+/// Lines among `candidates` that `pattern` matches exactly: as a `re:` regex,
+/// a [`JsonFilter`], a [`FieldPredicate`], a [`FieldPresence`], a
+/// [`FieldEquals`], or a substring looked for as `text` says. Errs when the regex doesn't compile.
+fn exact_matches(
+    lines: &[ParsedLine],
+    candidates: &[usize],
+    pattern: &str,
+    text: TextMatch,
+) -> Result<Vec<usize>, String> {
+    let keep = |pred: &dyn Fn(&ParsedLine) -> bool| -> Vec<usize> {
+        candidates
+            .iter()
+            .copied()
+            .filter(|&i| pred(&lines[i]))
+            .collect()
+    };
+
+    // Regular expression, e.g. `re:status=5\d\d`
+    if let Some(re) = regex_filter(pattern) {
+        let re = re?;
+        return Ok(keep(&|line| re.is_match(&line.raw)));
+    }
+
+    // jq-style path into JSON lines, e.g. `.user.id == 42`. A bare path no
+    // line has (`.env`, say) is searched for as text instead
+    if let Some(filter) = JsonFilter::parse(pattern) {
+        let matched = keep(&|line| filter.matches(line));
+        if !matched.is_empty()
+            || filter.compares()
+            || candidates.iter().any(|&i| filter.has_path(&lines[i]))
+        {
+            return Ok(matched);
+        }
+    }
+
+    // Numeric field condition, e.g. `duration_ms > 500`
+    if let Some(predicate) = FieldPredicate::parse(pattern) {
+        return Ok(keep(&|line| predicate.matches(line)));
+    }
+
+    // Field there or not, e.g. `has:trace_id`
+    if let Some(presence) = FieldPresence::parse(pattern) {
+        return Ok(keep(&|line| presence.matches(line)));
+    }
+
+    // Field value, e.g. `service=auth`. While nothing has it (a partly typed
+    // value, say), it's searched for as text like before
+    if let Some(field) = FieldEquals::parse(pattern) {
+        let matched = keep(&|line| field.matches(line));
+        if !matched.is_empty() {
+            return Ok(matched);
+        }
+    }
+
+    let found = |haystack: &str, needle: &str| {
+        if text.whole_word {
+            contains_word(haystack, needle)
+        } else {
+            haystack.contains(needle)
+        }
+    };
+    if text.case.is_sensitive(pattern) {
+        return Ok(keep(&|line| found(&line.raw, pattern)));
+    }
+    let pattern_lower = pattern.to_lowercase();
+    Ok(keep(&|line| {
+        found(&line.raw.to_lowercase(), &pattern_lower)
+    }))
+}
+
+/// Whether `term` keeps each line on its own account, so filtering part of
+/// a log keeps the same lines as filtering all of it would. Not so for a
+/// `key=value` or bare JSON path alternative, searched for as text when no
+/// line has it.
+pub fn is_context_free(term: &str) -> bool {
+    term.split(OR_SEPARATOR).all(|alternative| {
+        let inner = alternative
+            .strip_prefix(EXCLUDE_PREFIX)
+            .unwrap_or(alternative);
+        if regex_filter(inner).is_some() {
+            return true;
+        }
+        if let Some(filter) = JsonFilter::parse(inner) {
+            return filter.compares();
+        }
+        FieldPredicate::parse(inner).is_some() || FieldEquals::parse(inner).is_none()
+    })
+}
+
+/// Lines among `candidates` that one filter term keeps: those any of its
+/// `|` alternatives keeps, where a `!` alternative keeps what it doesn't
+/// match. None when no alternative is usable (empty, or a regex that doesn't
+/// compile), so the term filters nothing; the first regex error goes in
+/// `error`.
+fn term_matches(
+    lines: &[ParsedLine],
+    candidates: &[usize],
+    term: &str,
+    text: TextMatch,
+    error: &mut Option<String>,
+) -> Option<Vec<usize>> {
+    let mut kept: Option<Vec<usize>> = None;
+    for alternative in term.split(OR_SEPARATOR) {
+        let excluded = alternative.strip_prefix(EXCLUDE_PREFIX);
+        let inner = excluded.unwrap_or(alternative);
+        if inner.is_empty() {
+            continue;
+        }
+        let exact = match exact_matches(lines, candidates, inner, text) {
+            Ok(exact) => exact,
+            Err(e) => {
+                // Ignored until the regex compiles
+                error.get_or_insert(e);
+                continue;
+            }
+        };
+        let matched: Vec<usize> = if excluded.is_some() {
+            candidates
+                .iter()
+                .copied()
+                .filter(|i| exact.binary_search(i).is_err())
+                .collect()
+        } else {
+            exact
+        };
+        kept.get_or_insert_with(Vec::new).extend(matched);
+    }
+    kept.map(|mut kept| {
+        kept.sort_unstable();
+        kept.dedup();
+        kept
+    })
+}
+
+/// [`filter_lines`] with explicit settings for the fuzzy fallback.
+pub fn filter_lines_with(
+    lines: &[ParsedLine],
+    pattern: &str,
+    min_level: Option<LogLevel>,
+    fuzzy: &FuzzyConfig,
+) -> FilterResult {
+    filter_lines_stacked(lines, &[pattern], min_level, fuzzy)
+}
+
+/// Lines every term matches, as [`filter_lines_with`] does for each one.
+/// Within a term, alternatives separated by ` | ` are ORed, e.g.
+/// `timeout | refused`. The fuzzy fallback only applies to a lone plain
+/// term.
+pub fn filter_lines_stacked(
+    lines: &[ParsedLine],
+    terms: &[&str],
+    min_level: Option<LogLevel>,
+    fuzzy: &FuzzyConfig,
+) -> FilterResult {
+    filter_lines_matching(lines, terms, min_level, fuzzy, TextMatch::default())
+}
+
+/// [`filter_lines_stacked`] with plain text looked for as `text` says. A
+/// case-sensitive term is matched case-sensitively by the fuzzy fallback
+/// too, and whole words never fall back to fuzzy matching.
+pub fn filter_lines_matching(
+    lines: &[ParsedLine],
+    terms: &[&str],
+    min_level: Option<LogLevel>,
+    fuzzy: &FuzzyConfig,
+    text: TextMatch,
 ) -> FilterResult {
     // Level-filtered candidates (indices into `lines`)
     let level_ok: Vec<usize> = lines
@@ -32,51 +686,55 @@ pub fn filter_lines(
         .map(|(i, _)| i)
         .collect();
 
-    if pattern.is_empty() {
-        return FilterResult {
-            indices: level_ok,
-            is_fuzzy: false,
-        };
+    let mut indices = level_ok.clone();
+    let mut error = None;
+    for term in terms {
+        if let Some(kept) = term_matches(lines, &indices, term, text, &mut error) {
+            indices = kept;
+        }
     }
 
-    // Exact substring match (case-insensitive)
-    let pattern_lower = pattern.to_lowercase();
-    let exact: Vec<usize> = level_ok
-        .iter()
-        .copied()
-        .filter(|&i| lines[i].raw.to_lowercase().contains(&pattern_lower))
-        .collect();
-
-    if !exact.is_empty() {
+    // Regexes, field conditions, JSON paths, exclusions and combinations
+    // match exactly or not at all
+    let plain = match terms {
+        [term] => {
+            !text.whole_word
+                && !term.is_empty()
+                && !term.starts_with(EXCLUDE_PREFIX)
+                && !term.contains(OR_SEPARATOR)
+                && regex_filter(term).is_none()
+                && FieldPredicate::parse(term).is_none()
+                && FieldPresence::parse(term).is_none()
+                && JsonFilter::parse(term).is_none()
+        }
+        _ => false,
+    };
+    if !indices.is_empty() || !plain {
         return FilterResult {
-            indices: exact,
+            indices,
             is_fuzzy: false,
+            error,
+            candidates: level_ok.len(),
         };
     }
 
-    // Fuzzy fallback — only when exact match found nothing
-    let mut matcher = Matcher::new(Config::DEFAULT);
-    let pat = Pattern::new(
-        pattern,
-        CaseMatching::Ignore,
-        Normalization::Smart,
-        AtomKind::Fuzzy,
-    );
-    let mut buf = Vec::new();
-
+    // Fuzzy fallback — only when exact substring match found nothing
+    let mut config = *fuzzy;
+    if text.case.is_sensitive(terms[0]) {
+        config.case = FuzzyCase::Respect;
+    }
+    let mut matcher = FuzzyMatcher::new(terms[0], &config, false);
     let fuzzy: Vec<usize> = level_ok
         .iter()
         .copied()
-        .filter(|&i| {
-            buf.clear();
-            let haystack = Utf32Str::new(&lines[i].raw, &mut buf);
-            pat.score(haystack, &mut matcher).is_some()
-        })
+        .filter(|&i| matcher.score(&lines[i].raw).is_some())
         .collect();
 
     let is_fuzzy = !fuzzy.is_empty();
     FilterResult {
         indices: fuzzy,
         is_fuzzy,
+        error: None,
+        candidates: level_ok.len(),
     }
 }
@@ -0,0 +1,473 @@
+use crate::parser::{LogFormat, LogLevel};
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use std::io::{self, Write};
+
+/// Formats `lumolog generate` can write.
+pub const FORMATS: [LogFormat; 5] = [
+    LogFormat::Json,
+    LogFormat::Logfmt,
+    LogFormat::Plain,
+    LogFormat::Syslog,
+    LogFormat::AccessLog,
+];
+
+/// Chance, each second, that an incident starts: a few seconds of several
+/// times the traffic, mostly upstream timeouts.
+const BURST_CHANCE: f64 = 0.02;
+
+/// Traffic during a burst, as a multiple of the rate.
+const BURST_FACTOR: usize = 4;
+
+const SERVICES: [&str; 4] = ["api", "auth", "billing", "worker"];
+const HOSTS: [&str; 4] = ["web-1", "web-2", "web-3", "web-4"];
+const ENDPOINTS: [(&str, &str); 8] = [
+    ("GET", "/api/v1/users"),
+    ("GET", "/api/v1/users/{id}"),
+    ("POST", "/api/v1/users"),
+    ("POST", "/api/v1/auth/login"),
+    ("GET", "/api/v1/orders"),
+    ("POST", "/api/v1/orders"),
+    ("POST", "/api/v1/payments/charge"),
+    ("GET", "/healthz"),
+];
+const TABLES: [&str; 4] = ["users", "orders", "payments", "sessions"];
+const JOBS: [&str; 3] = ["send-digest", "expire-sessions", "reindex-search"];
+const PAYMENT_ERRORS: [&str; 3] = ["card declined", "insufficient funds", "fraud check failed"];
+const USER_AGENTS: [&str; 3] = ["curl/8.4.0", "Go-http-client/2.0", "python-requests/2.31.0"];
+
+/// A small deterministic PRNG (splitmix64), so a seed always gives the same
+/// log.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// True with probability `p`.
+    fn chance(&mut self, p: f64) -> bool {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 <= p
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+/// A field value: numbers and booleans stay bare in JSON.
+#[derive(Debug, Clone)]
+enum Value {
+    Str(String),
+    Num(u64),
+    Bool(bool),
+}
+
+impl Value {
+    fn text(&self) -> String {
+        match self {
+            Value::Str(s) => s.clone(),
+            Value::Num(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+        }
+    }
+}
+
+/// One made-up log event, before it's written in a format.
+#[derive(Debug, Clone)]
+struct Event {
+    time: NaiveDateTime,
+    level: LogLevel,
+    service: &'static str,
+    host: &'static str,
+    message: &'static str,
+    fields: Vec<(&'static str, Value)>,
+    /// Set on requests, which the access log format needs.
+    request: Option<Request>,
+}
+
+#[derive(Debug, Clone)]
+struct Request {
+    ip: String,
+    method: &'static str,
+    path: String,
+    status: u16,
+    bytes: u64,
+    agent: &'static str,
+}
+
+/// Writes synthetic logs one second at a time: mostly requests, with some
+/// debug chatter, slow queries, failed payments, and the odd burst of
+/// errors.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    format: LogFormat,
+    rng: Rng,
+    /// Seconds left in the current burst.
+    burst_left: u32,
+}
+
+impl Generator {
+    pub fn new(format: LogFormat, seed: u64) -> anyhow::Result<Self> {
+        if !FORMATS.contains(&format) {
+            let names: Vec<&str> = FORMATS.iter().map(|f| f.name()).collect();
+            anyhow::bail!(
+                "cannot generate {} logs; choose one of {}",
+                format.name(),
+                names.join(", ")
+            );
+        }
+        Ok(Self {
+            format,
+            rng: Rng(seed),
+            burst_left: 0,
+        })
+    }
+
+    /// The lines for the second starting at `start`: `rate` of them, spread
+    /// evenly, or several times that during a burst.
+    pub fn second(&mut self, start: NaiveDateTime, rate: usize) -> Vec<String> {
+        if self.burst_left == 0 && self.rng.chance(BURST_CHANCE) {
+            self.burst_left = 2 + self.rng.below(4) as u32;
+        }
+        let bursting = self.burst_left > 0;
+        self.burst_left = self.burst_left.saturating_sub(1);
+        let count = if bursting { rate * BURST_FACTOR } else { rate };
+        (0..count)
+            .map(|i| {
+                let offset = Duration::microseconds((i as i64 * 1_000_000) / count as i64);
+                let event = if bursting && self.rng.chance(0.6) {
+                    self.timeout(start + offset)
+                } else {
+                    self.event(start + offset)
+                };
+                self.render(&event)
+            })
+            .collect()
+    }
+
+    fn event(&mut self, time: NaiveDateTime) -> Event {
+        if self.format == LogFormat::AccessLog {
+            let status = self.status();
+            return self.request(time, status);
+        }
+        let roll = self.rng.below(1000);
+        match roll {
+            0..=649 => {
+                let status = self.status();
+                self.request(time, status)
+            }
+            650..=799 => {
+                let user = self.user_id();
+                let hit = self.rng.chance(0.8);
+                let mut event = self.base(time, LogLevel::Debug, "cache lookup");
+                event.fields = vec![
+                    ("key", Value::Str(format!("user:{user}"))),
+                    ("hit", Value::Bool(hit)),
+                ];
+                event
+            }
+            800..=879 => {
+                let user = self.user_id();
+                let mut event = self.base(time, LogLevel::Info, "user logged in");
+                event.service = "auth";
+                event.fields = vec![("user_id", Value::Num(user))];
+                event
+            }
+            880..=929 => {
+                let job = self.rng.pick(&JOBS);
+                let took = 200 + self.rng.below(5000) as u64;
+                let mut event = self.base(time, LogLevel::Info, "job finished");
+                event.service = "worker";
+                event.fields = vec![
+                    ("job", Value::Str(job.to_string())),
+                    ("duration_ms", Value::Num(took)),
+                ];
+                event
+            }
+            930..=969 => {
+                let table = self.rng.pick(&TABLES);
+                let took = 500 + self.rng.below(2500) as u64;
+                let mut event = self.base(time, LogLevel::Warn, "slow query");
+                event.fields = vec![
+                    ("table", Value::Str(table.to_string())),
+                    ("duration_ms", Value::Num(took)),
+                ];
+                event
+            }
+            970..=997 => {
+                let order = 10_000 + self.rng.below(90_000) as u64;
+                let error = self.rng.pick(&PAYMENT_ERRORS);
+                let mut event = self.base(time, LogLevel::Error, "payment failed");
+                event.service = "billing";
+                event.fields = vec![
+                    ("order_id", Value::Num(order)),
+                    ("error", Value::Str(error.to_string())),
+                ];
+                event
+            }
+            _ => {
+                let mut event = self.base(time, LogLevel::Fatal, "worker crashed");
+                event.service = "worker";
+                event.fields = vec![("error", Value::Str("out of memory".to_string()))];
+                event
+            }
+        }
+    }
+
+    /// A request failing with a timeout, what most of a burst is made of.
+    fn timeout(&mut self, time: NaiveDateTime) -> Event {
+        let mut event = self.request(time, 504);
+        if self.format != LogFormat::AccessLog {
+            event.message = "upstream timeout";
+            event
+                .fields
+                .push(("upstream", Value::Str("db-primary:5432".to_string())));
+        }
+        event
+    }
+
+    fn base(&mut self, time: NaiveDateTime, level: LogLevel, message: &'static str) -> Event {
+        Event {
+            time,
+            level,
+            service: self.rng.pick(&SERVICES),
+            host: self.rng.pick(&HOSTS),
+            message,
+            fields: Vec::new(),
+            request: None,
+        }
+    }
+
+    fn status(&mut self) -> u16 {
+        match self.rng.below(100) {
+            0..=84 => 200,
+            85..=89 => 201,
+            90..=93 => 304,
+            94..=96 => 404,
+            97..=98 => 401,
+            _ => 500,
+        }
+    }
+
+    fn user_id(&mut self) -> u64 {
+        1000 + self.rng.below(9000) as u64
+    }
+
+    fn request(&mut self, time: NaiveDateTime, status: u16) -> Event {
+        let (method, path) = self.rng.pick(&ENDPOINTS);
+        let path = path.replace("{id}", &self.user_id().to_string());
+        let ip = format!("10.0.{}.{}", self.rng.below(8), 2 + self.rng.below(250));
+        let took = match status {
+            504 => 30_000 + self.rng.below(500) as u64,
+            _ => 2 + self.rng.below(300) as u64,
+        };
+        let level = match status {
+            500.. => LogLevel::Error,
+            400.. => LogLevel::Warn,
+            _ => LogLevel::Info,
+        };
+        let request_id = format!("{:016x}", self.rng.next_u64());
+        let mut event = self.base(time, level, "request completed");
+        event.service = "api";
+        event.fields = vec![
+            ("method", Value::Str(method.to_string())),
+            ("path", Value::Str(path.clone())),
+            ("status", Value::Num(status as u64)),
+            ("duration_ms", Value::Num(took)),
+            ("ip", Value::Str(ip.clone())),
+            ("request_id", Value::Str(request_id)),
+        ];
+        event.request = Some(Request {
+            ip,
+            method,
+            path,
+            status,
+            bytes: if status == 304 {
+                0
+            } else {
+                100 + self.rng.below(8000) as u64
+            },
+            agent: self.rng.pick(&USER_AGENTS),
+        });
+        event
+    }
+
+    fn render(&self, event: &Event) -> String {
+        match self.format {
+            LogFormat::Json => {
+                let mut line = format!(
+                    "{{\"timestamp\":\"{}\",\"level\":\"{}\",\"service\":\"{}\",\"host\":\"{}\",\"message\":{}",
+                    event.time.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                    level_word(event.level),
+                    event.service,
+                    event.host,
+                    serde_json::Value::from(event.message)
+                );
+                for (key, value) in &event.fields {
+                    let json = match value {
+                        Value::Str(s) => serde_json::Value::from(s.as_str()).to_string(),
+                        other => other.text(),
+                    };
+                    line.push_str(&format!(",\"{key}\":{json}"));
+                }
+                line.push('}');
+                line
+            }
+            LogFormat::Logfmt => {
+                let mut line = format!(
+                    "ts={} level={} service={} host={} msg={}",
+                    event.time.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                    level_word(event.level),
+                    event.service,
+                    event.host,
+                    logfmt_value(event.message)
+                );
+                for (key, value) in &event.fields {
+                    line.push_str(&format!(" {key}={}", logfmt_value(&value.text())));
+                }
+                line
+            }
+            LogFormat::Plain => format!(
+                "{} {:<5} [{}] {}{}",
+                event.time.format("%Y-%m-%d %H:%M:%S%.3f"),
+                level_word(event.level).to_uppercase(),
+                event.service,
+                event.message,
+                field_suffix(event)
+            ),
+            LogFormat::Syslog => format!(
+                "{} {} {}[{}]: {} {}{}",
+                event.time.format("%b %e %H:%M:%S"),
+                event.host,
+                event.service,
+                1000 + SERVICES
+                    .iter()
+                    .position(|s| *s == event.service)
+                    .unwrap_or(0),
+                level_word(event.level).to_uppercase(),
+                event.message,
+                field_suffix(event)
+            ),
+            _ => {
+                // AccessLog: every event is a request
+                let request = event
+                    .request
+                    .as_ref()
+                    .expect("access log events are requests");
+                format!(
+                    "{} - - [{}] \"{} {} HTTP/1.1\" {} {} \"-\" \"{}\"",
+                    request.ip,
+                    event.time.format("%d/%b/%Y:%H:%M:%S +0000"),
+                    request.method,
+                    request.path,
+                    request.status,
+                    request.bytes,
+                    request.agent
+                )
+            }
+        }
+    }
+}
+
+fn level_word(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Trace => "trace",
+        LogLevel::Debug => "debug",
+        LogLevel::Info => "info",
+        LogLevel::Warn => "warn",
+        LogLevel::Error => "error",
+        LogLevel::Fatal => "fatal",
+    }
+}
+
+fn logfmt_value(text: &str) -> String {
+    if text.contains([' ', '"', '=']) {
+        format!("{text:?}")
+    } else {
+        text.to_string()
+    }
+}
+
+/// ` key=value ...` for formats without fields of their own.
+fn field_suffix(event: &Event) -> String {
+    event
+        .fields
+        .iter()
+        .map(|(key, value)| format!(" {key}={}", logfmt_value(&value.text())))
+        .collect()
+}
+
+/// Parse a `--duration` like `60s`, `5m` or `1h` (a bare number is seconds)
+/// into whole seconds, at least one.
+pub fn parse_duration(text: &str) -> anyhow::Result<u64> {
+    use crate::units::{Dimension, parse_quantity};
+    let seconds = match parse_quantity(text) {
+        Some(q) if q.dimension == Dimension::Scalar => q.value,
+        Some(q) if q.dimension == Dimension::Duration => q.value / 1000.0,
+        _ => anyhow::bail!("cannot read --duration '{text}', e.g. 60s, 5m or 1h"),
+    };
+    if seconds < 1.0 {
+        anyhow::bail!("--duration must be at least 1s, got '{text}'");
+    }
+    Ok(seconds.round() as u64)
+}
+
+/// Write `seconds` seconds of `rate` lines per second, starting at `start`.
+/// With `realtime`, each line waits for its timestamp to come round (from
+/// when this was called), for feeding a follow-mode viewer; otherwise lines
+/// go out as fast as they can be written. A closed pipe ends it quietly.
+pub fn run(
+    generator: &mut Generator,
+    start: NaiveDateTime,
+    rate: usize,
+    seconds: u64,
+    realtime: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let began = std::time::Instant::now();
+    let result = (|| {
+        for s in 0..seconds {
+            let second_start = start + Duration::seconds(s as i64);
+            let lines = generator.second(second_start, rate);
+            let count = lines.len() as u64;
+            for (i, line) in lines.into_iter().enumerate() {
+                if realtime {
+                    let due = std::time::Duration::from_secs(s)
+                        + std::time::Duration::from_micros(i as u64 * 1_000_000 / count);
+                    if let Some(wait) = due.checked_sub(began.elapsed()) {
+                        out.flush()?;
+                        std::thread::sleep(wait);
+                    }
+                }
+                writeln!(out, "{line}")?;
+            }
+        }
+        out.flush()
+    })();
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => other,
+    }
+}
+
+/// Where generated logs start when no `--start` is given: a fixed time, so a
+/// seed always gives the same log, or now when written in real time.
+pub fn default_start(realtime: bool) -> NaiveDateTime {
+    if realtime {
+        Utc::now().naive_utc()
+    } else {
+        DateTime::from_timestamp(1_705_305_600, 0)
+            .expect("valid timestamp")
+            .naive_utc()
+    }
+}
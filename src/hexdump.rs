@@ -0,0 +1,101 @@
+/// Bytes per row when the popup is wide enough, as in `hexdump -C`.
+pub const WIDE_ROW: usize = 16;
+
+/// Bytes per row in a narrow terminal.
+pub const NARROW_ROW: usize = 8;
+
+/// How a byte is shown in the dump, so the odd ones stand out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteClass {
+    /// Printable ASCII, including space.
+    Printable,
+    /// ASCII control characters: tabs, carriage returns, escapes, NUL, DEL.
+    Control,
+    /// Part of a multi-byte UTF-8 character (or of a replacement character
+    /// where the file wasn't valid UTF-8).
+    NonAscii,
+}
+
+pub fn classify(byte: u8) -> ByteClass {
+    match byte {
+        0x20..=0x7e => ByteClass::Printable,
+        0x80..=0xff => ByteClass::NonAscii,
+        _ => ByteClass::Control,
+    }
+}
+
+/// The byte in the ASCII column: itself when printable, `.` otherwise.
+pub fn ascii_char(byte: u8) -> char {
+    match classify(byte) {
+        ByteClass::Printable => byte as char,
+        _ => '.',
+    }
+}
+
+/// Row width that fits in `width` columns: offset, hex bytes (with an extra
+/// gap halfway) and the ASCII column.
+pub fn bytes_per_row(width: usize) -> usize {
+    if width >= row_width(WIDE_ROW) {
+        WIDE_ROW
+    } else {
+        NARROW_ROW
+    }
+}
+
+/// Columns taken by a row of `per_row` bytes.
+pub fn row_width(per_row: usize) -> usize {
+    // "00000000  ", "xx " per byte plus the middle gap, " |", the ASCII, "|"
+    10 + per_row * 3 + 1 + 2 + per_row + 1
+}
+
+/// The hex bytes of one row, padded to a full row so the ASCII column lines up.
+pub fn hex_column(chunk: &[u8], per_row: usize) -> String {
+    let mut out = String::new();
+    for i in 0..per_row {
+        if i == per_row / 2 {
+            out.push(' ');
+        }
+        match chunk.get(i) {
+            Some(b) => out.push_str(&format!("{b:02x} ")),
+            None => out.push_str("   "),
+        }
+    }
+    out
+}
+
+/// `bytes` as `hexdump -C` style rows:
+/// `00000000  48 65 6c 6c 6f 0d 1b 5b  ...  |Hello..[...|`.
+pub fn hex_dump(bytes: &[u8], per_row: usize) -> Vec<String> {
+    bytes
+        .chunks(per_row)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let ascii: String = chunk.iter().map(|&b| ascii_char(b)).collect();
+            format!(
+                "{:08x}  {} |{ascii}|",
+                row * per_row,
+                hex_column(chunk, per_row)
+            )
+        })
+        .collect()
+}
+
+/// One-line summary for the popup title: `142 bytes, 2 control, 3 non-ASCII`.
+pub fn summary(bytes: &[u8]) -> String {
+    let count = |class| bytes.iter().filter(|&&b| classify(b) == class).count();
+    let mut text = format!(
+        "{} byte{}",
+        bytes.len(),
+        if bytes.len() == 1 { "" } else { "s" }
+    );
+    for (class, name) in [
+        (ByteClass::Control, "control"),
+        (ByteClass::NonAscii, "non-ASCII"),
+    ] {
+        let n = count(class);
+        if n > 0 {
+            text.push_str(&format!(", {n} {name}"));
+        }
+    }
+    text
+}
@@ -1,9 +1,13 @@
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use regex::Regex;
+use std::ops::Range;
 use std::sync::LazyLock;
 
+use crate::decode::decode_token;
+use crate::filter::is_whole_word;
 use crate::parser::{LogFormat, LogLevel, ParsedLine};
+use serde::Deserialize;
 
 // ---------------------------------------------------------------------------
 // Inline pattern regexes (ordered by match priority)
@@ -12,68 +16,74 @@ use crate::parser::{LogFormat, LogLevel, ParsedLine};
 // 1. URLs
 static URL_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"https?://[^\s,\]>)"']+"#).unwrap());
 
-// 2. UUIDs
+// 2. Encoded tokens: JWTs, then runs of base64 (checked by decoding)
+static JWT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*").unwrap());
+static BASE64_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[A-Za-z0-9+/_-]{16,}={0,2}").unwrap());
+
+// 3. UUIDs
 static UUID_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b").unwrap()
 });
 
-// 3. IPv6 (common representations: full, compressed, loopback, link-local)
+// 4. IPv6 (common representations: full, compressed, loopback, link-local)
 static IPV6_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?i)\b(?:[0-9a-f]{1,4}:){7}[0-9a-f]{1,4}\b|(?i)\b(?:[0-9a-f]{1,4}:){1,7}:|(?i)\b(?:[0-9a-f]{1,4}:){1,6}:[0-9a-f]{1,4}\b|(?i)::(?:[0-9a-f]{1,4}:){0,5}[0-9a-f]{1,4}\b|\b::1\b|\b::\b").unwrap()
 });
 
-// 4. IPv4 (with octet capture groups for validation)
+// 5. IPv4 (with octet capture groups for validation)
 static IPV4_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"\b(\d{1,3})\.(\d{1,3})\.(\d{1,3})\.(\d{1,3})(?::\d{1,5})?\b").unwrap()
 });
 
-// 5. Pointer/memory addresses (0x hex)
+// 6. Pointer/memory addresses (0x hex)
 static POINTER_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"\b0x[0-9a-fA-F]{4,16}\b").unwrap());
 
-// 6. Unix file paths (at least 2 segments)
+// 7. Unix file paths (at least 2 segments)
 static PATH_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?:\./|~/|/)[\w.\-]+(?:/[\w.\-]+)+").unwrap());
 
-// 7. Unix processes: name[pid]
+// 8. Unix processes: name[pid]
 static UNIX_PROCESS_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"\b[a-zA-Z][\w.\-]*\[\d+\]").unwrap());
 
-// 8. HTTP methods
+// 9. HTTP methods
 static HTTP_METHOD_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"\b(?:GET|POST|PUT|DELETE|PATCH|HEAD|OPTIONS)\b").unwrap());
 
-// 9. Key=value pairs
+// 10. Key=value pairs
 static KEY_VALUE_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?:^|\s)(\w[\w.]*)=").unwrap());
 
-// 10. Quoted strings
+// 11. Quoted strings
 static QUOTED_STR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""[^"]{1,200}""#).unwrap());
 
-// 11. Keywords (boolean/null constants)
+// 12. Keywords (boolean/null constants)
 static KEYWORD_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?i)\b(?:true|false|null|nil|none|undefined|NaN)\b").unwrap());
 
-// 12. Version numbers (dotted: 2.4.1, 10.15.7 — exactly 3 segments)
+// 13. Version numbers (dotted: 2.4.1, 10.15.7 — exactly 3 segments)
 static VERSION_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bv?\d+\.\d+\.\d+\b").unwrap());
 
-// 13. Numbers (2+ digits, or decimal, or with unit suffix — skip tiny standalone digits)
+// 14. Numbers (2+ digits, or decimal, or with unit suffix — skip tiny standalone digits)
 static NUMBER_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"\b(?:\d+(?:\.\d+)?(?:ns|µs|us|ms|s|m|h|d|KB|MB|GB|TB|B)\b|\d+(?:\.\d+)?%|\d{2,}(?:\.\d+)?\b|\d+\.\d+\b)")
         .unwrap()
 });
 
-// 14. Protocol versions (HTTP/1.1, HTTP/2 — prevent number highlighting)
+// 15. Protocol versions (HTTP/1.1, HTTP/2 — prevent number highlighting)
 static PROTOCOL_VERSION_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"HTTP/\d+(?:\.\d+)?").unwrap());
 
-// 15. Inline dates (ISO-style dates not already captured as leading timestamps)
+// 16. Inline dates (ISO-style dates not already captured as leading timestamps)
 static DATE_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"\b\d{4}-\d{2}-\d{2}(?:[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+\-]\d{2}:?\d{2})?)?")
         .unwrap()
 });
 
-// 16. Level keywords (for plain format badge extraction)
+// 17. Level keywords (for plain format badge extraction)
 static HIGHLIGHT_LEVEL_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?i)\b(TRACE|DEBUG|INFO|NOTICE|WARN(?:ING)?|ERROR|FATAL|CRITICAL|SEVERE|EMERGENCY|EMERG|ALERT|PANIC)\b").unwrap()
 });
@@ -88,6 +98,10 @@ fn url_style() -> Style {
         .add_modifier(Modifier::UNDERLINED)
 }
 
+fn encoded_style() -> Style {
+    Style::default().fg(Color::Indexed(140))
+}
+
 fn uuid_style() -> Style {
     Style::default().fg(Color::Magenta)
 }
@@ -156,6 +170,8 @@ pub enum TokenKind {
     Process,
     KeyValue,
     QuotedString,
+    /// A JWT or base64 that decodes to text.
+    Encoded,
     Other,
 }
 
@@ -216,13 +232,28 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
     // 1. URLs (contain paths, IPs, etc.)
     collect_matches(&URL_RE, text, url_style(), TokenKind::Url, &mut regions);
 
-    // 2. UUIDs
+    // 2. Encoded tokens, kept only when they decode
+    for re in [&*JWT_RE, &*BASE64_RE] {
+        for m in re.find_iter(text) {
+            let region = MatchRegion {
+                start: m.start(),
+                end: m.end(),
+                style: encoded_style(),
+                kind: TokenKind::Encoded,
+            };
+            if !overlaps(&regions, &region) && decode_token(m.as_str()).is_some() {
+                regions.push(region);
+            }
+        }
+    }
+
+    // 3. UUIDs
     collect_matches(&UUID_RE, text, uuid_style(), TokenKind::Uuid, &mut regions);
 
-    // 3. IPv6 addresses
+    // 4. IPv6 addresses
     collect_matches(&IPV6_RE, text, ip_style(), TokenKind::Ip, &mut regions);
 
-    // 4. IPv4 addresses (validate octets)
+    // 5. IPv4 addresses (validate octets)
     for caps in IPV4_RE.captures_iter(text) {
         if let Some(m) = caps.get(0)
             && is_valid_ipv4(&caps)
@@ -239,7 +270,7 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         }
     }
 
-    // 5. Pointer addresses (0x...)
+    // 6. Pointer addresses (0x...)
     collect_matches(
         &POINTER_RE,
         text,
@@ -248,10 +279,10 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         &mut regions,
     );
 
-    // 6. Unix file paths
+    // 7. Unix file paths
     collect_matches(&PATH_RE, text, path_style(), TokenKind::Path, &mut regions);
 
-    // 7. Unix processes (sshd[1234])
+    // 8. Unix processes (sshd[1234])
     collect_matches(
         &UNIX_PROCESS_RE,
         text,
@@ -260,7 +291,7 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         &mut regions,
     );
 
-    // 8. HTTP methods
+    // 9. HTTP methods
     collect_matches(
         &HTTP_METHOD_RE,
         text,
@@ -269,7 +300,7 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         &mut regions,
     );
 
-    // 9. Key=value pairs (highlight key and '=' only)
+    // 10. Key=value pairs (highlight key and '=' only)
     for caps in KEY_VALUE_RE.captures_iter(text) {
         if let Some(key) = caps.get(1) {
             let region = MatchRegion {
@@ -284,7 +315,7 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         }
     }
 
-    // 10. Quoted strings
+    // 11. Quoted strings
     collect_matches(
         &QUOTED_STR_RE,
         text,
@@ -293,7 +324,7 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         &mut regions,
     );
 
-    // 11. Keywords (true, false, null, nil, none, undefined, NaN)
+    // 12. Keywords (true, false, null, nil, none, undefined, NaN)
     collect_matches(
         &KEYWORD_RE,
         text,
@@ -302,7 +333,7 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         &mut regions,
     );
 
-    // 12. Version numbers (2.4.1 etc. — higher priority than plain numbers)
+    // 13. Version numbers (2.4.1 etc. — higher priority than plain numbers)
     collect_matches(
         &VERSION_RE,
         text,
@@ -311,7 +342,7 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         &mut regions,
     );
 
-    // 13. Protocol versions (HTTP/1.1 — claim region to prevent number highlighting)
+    // 14. Protocol versions (HTTP/1.1 — claim region to prevent number highlighting)
     collect_matches(
         &PROTOCOL_VERSION_RE,
         text,
@@ -320,10 +351,10 @@ fn collect_all_regions(text: &str) -> Vec<MatchRegion> {
         &mut regions,
     );
 
-    // 14. Inline dates
+    // 15. Inline dates
     collect_matches(&DATE_RE, text, date_style(), TokenKind::Other, &mut regions);
 
-    // 15. Numbers (lowest priority — avoids coloring parts of IPs, UUIDs, etc.)
+    // 16. Numbers (lowest priority — avoids coloring parts of IPs, UUIDs, etc.)
     collect_matches(
         &NUMBER_RE,
         text,
@@ -370,6 +401,60 @@ fn tokenize_with_patterns(text: &str, base_style: Style) -> Vec<Span<'static>> {
     spans
 }
 
+/// Texts longer than this many bytes are only tokenized near the visible columns.
+const LONG_TEXT_BYTES: usize = 4096;
+
+/// Columns tokenized either side of the visible range, so a token cut by the
+/// edge of the window is still styled as it scrolls into view.
+const VISIBLE_MARGIN: usize = 512;
+
+/// `tokenize_with_patterns` for a piece of a line that starts at display
+/// column `col`. When the text is very long (minified JSON), only the part
+/// overlapping `visible` is tokenized and the rest is left in `base_style`.
+fn tokenize_visible(
+    text: &str,
+    base_style: Style,
+    col: usize,
+    visible: &Range<usize>,
+) -> Vec<Span<'static>> {
+    if text.len() <= LONG_TEXT_BYTES {
+        return tokenize_with_patterns(text, base_style);
+    }
+    let first = visible
+        .start
+        .saturating_sub(VISIBLE_MARGIN)
+        .saturating_sub(col);
+    let last = visible
+        .end
+        .saturating_add(VISIBLE_MARGIN)
+        .saturating_sub(col);
+    let start = byte_at_column(text, first);
+    let end = byte_at_column(text, last);
+
+    let mut spans = Vec::new();
+    if start > 0 {
+        spans.push(Span::styled(text[..start].to_string(), base_style));
+    }
+    if start < end {
+        spans.extend(tokenize_with_patterns(&text[start..end], base_style));
+    }
+    if end < text.len() {
+        spans.push(Span::styled(text[end..].to_string(), base_style));
+    }
+    spans
+}
+
+/// Byte offset of the `column`-th char of `text`, or its length if shorter.
+fn byte_at_column(text: &str, column: usize) -> usize {
+    text.char_indices()
+        .nth(column)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+fn spans_width(spans: &[Span]) -> usize {
+    spans.iter().map(Span::width).sum()
+}
+
 /// Returns spans with token metadata for click-to-action support.
 /// Each entry is (Span, Option<TokenKind>, raw_text).
 /// Non-token text has `None` for the kind.
@@ -417,16 +502,151 @@ pub fn tokenize_with_metadata(
 // ---------------------------------------------------------------------------
 
 pub fn highlight_line(parsed: &ParsedLine) -> Line<'_> {
+    highlight_line_visible(parsed, &(0..usize::MAX))
+}
+
+/// Like [`highlight_line`], but very long lines are only tokenized around the
+/// `visible` display columns (plus a margin); the rest keeps the base style.
+/// The text of the returned line is the same either way.
+pub fn highlight_line_visible<'a>(parsed: &'a ParsedLine, visible: &Range<usize>) -> Line<'a> {
+    highlight_line_visible_with(parsed, visible, LevelBadge::default())
+}
+
+/// [`highlight_line_visible`] with the level shown as `badge`.
+pub fn highlight_line_visible_with<'a>(
+    parsed: &'a ParsedLine,
+    visible: &Range<usize>,
+    badge: LevelBadge,
+) -> Line<'a> {
     match parsed.format {
-        LogFormat::Json => highlight_json_line(parsed),
-        LogFormat::Syslog => highlight_syslog_line(parsed),
+        LogFormat::Json => highlight_json_line(parsed, visible, badge),
+        LogFormat::Syslog => {
+            with_extra_fields(highlight_syslog_line(parsed, visible), parsed, visible)
+        }
+        LogFormat::AccessLog => highlight_access_line(parsed, visible, badge),
         LogFormat::Logfmt
         | LogFormat::Klog
         | LogFormat::Log4j
         | LogFormat::PythonLog
-        | LogFormat::AccessLog => highlight_json_line(parsed), // structured formats reuse compact view
-        LogFormat::Plain => highlight_plain_line(parsed),
+        | LogFormat::Console
+        | LogFormat::Cri => highlight_json_line(parsed, visible, badge), // structured formats reuse compact view
+        LogFormat::Plain => {
+            with_extra_fields(highlight_plain_line(parsed, visible), parsed, visible)
+        }
+    }
+}
+
+/// Whether lines of `format` start with a `[LVL]` badge. The others show
+/// their level by color only.
+pub fn shows_level_badge(format: LogFormat) -> bool {
+    !matches!(format, LogFormat::Plain | LogFormat::Syslog)
+}
+
+/// How the compact view shows a line's level before its timestamp.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LevelBadge {
+    /// `[ERR]` on a colored background.
+    #[default]
+    Badge,
+    /// `ERR` in the level's color.
+    Code,
+    /// `E` in the level's color.
+    Letter,
+    /// `❗`, `⚠️` and so on.
+    Emoji,
+    /// Nothing; the message color still tells.
+    None,
+}
+
+impl LevelBadge {
+    /// Badge → code → letter → emoji → none → badge.
+    pub fn next(self) -> Self {
+        match self {
+            LevelBadge::Badge => LevelBadge::Code,
+            LevelBadge::Code => LevelBadge::Letter,
+            LevelBadge::Letter => LevelBadge::Emoji,
+            LevelBadge::Emoji => LevelBadge::None,
+            LevelBadge::None => LevelBadge::Badge,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LevelBadge::Badge => "badge",
+            LevelBadge::Code => "code",
+            LevelBadge::Letter => "letter",
+            LevelBadge::Emoji => "emoji",
+            LevelBadge::None => "none",
+        }
+    }
+
+    /// `level` shown this way, with the space after it; nothing for
+    /// [`LevelBadge::None`].
+    pub fn spans(self, level: Option<LogLevel>) -> Vec<Span<'static>> {
+        let code = level.map_or("???", LogLevel::short_name);
+        let colored = level_style(level).add_modifier(Modifier::BOLD);
+        let label = match self {
+            LevelBadge::Badge => Span::styled(format!("[{code}]"), level_badge_style(level)),
+            LevelBadge::Code => Span::styled(code, colored),
+            LevelBadge::Letter => Span::styled(&code[..1], colored),
+            LevelBadge::Emoji => Span::raw(match level {
+                Some(LogLevel::Fatal) => "💀",
+                Some(LogLevel::Error) => "❗",
+                Some(LogLevel::Warn) => "⚠️",
+                Some(LogLevel::Info) => "ℹ️",
+                Some(LogLevel::Debug) => "🐛",
+                Some(LogLevel::Trace) => "🔍",
+                None => "  ",
+            }),
+            LevelBadge::None => return Vec::new(),
+        };
+        vec![label, Span::raw(" ")]
+    }
+
+    /// Display columns of [`spans`](Self::spans).
+    pub fn width(self, level: Option<LogLevel>) -> usize {
+        Line::from(self.spans(level)).width()
+    }
+}
+
+/// A line's extracted fields as shown after it: `key=value key=value`.
+pub fn extra_fields_text(parsed: &ParsedLine) -> String {
+    parsed
+        .extra_fields
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Append the line's extracted fields, dimmed, after two spaces.
+fn push_extra_fields(spans: &mut Vec<Span<'_>>, parsed: &ParsedLine, visible: &Range<usize>) {
+    push_dim_fields(spans, &extra_fields_text(parsed), visible);
+}
+
+/// Append `fields` text, dimmed, after two spaces; nothing when it's empty.
+fn push_dim_fields(spans: &mut Vec<Span<'_>>, fields: &str, visible: &Range<usize>) {
+    if fields.is_empty() {
+        return;
     }
+    let dim_style = Style::default()
+        .fg(Color::DarkGray)
+        .add_modifier(Modifier::DIM);
+    spans.push(Span::styled("  ", dim_style));
+    let col = spans_width(spans);
+    spans.extend(tokenize_visible(fields, dim_style, col, visible));
+}
+
+/// Plain and syslog lines show their text as it is; fields they only have
+/// from elsewhere (derived fields, `#lumolog:` provenance) follow it.
+fn with_extra_fields<'a>(
+    mut line: Line<'a>,
+    parsed: &ParsedLine,
+    visible: &Range<usize>,
+) -> Line<'a> {
+    push_extra_fields(&mut line.spans, parsed, visible);
+    line
 }
 
 fn level_style(level: Option<LogLevel>) -> Style {
@@ -475,7 +695,7 @@ fn timestamp_style() -> Style {
     Style::default().fg(Color::DarkGray)
 }
 
-fn highlight_plain_line(parsed: &ParsedLine) -> Line<'_> {
+fn highlight_plain_line<'a>(parsed: &'a ParsedLine, visible: &Range<usize>) -> Line<'a> {
     let style = level_style(parsed.level);
 
     if let Some(ref ts) = parsed.timestamp
@@ -500,61 +720,147 @@ fn highlight_plain_line(parsed: &ParsedLine) -> Line<'_> {
                 level_text.to_string(),
                 style.add_modifier(Modifier::BOLD),
             ));
-            spans.extend(tokenize_with_patterns(after_level, style));
+            let col = spans_width(&spans);
+            spans.extend(tokenize_visible(after_level, style, col, visible));
             return Line::from(spans);
         }
 
-        spans.extend(tokenize_with_patterns(rest, style));
+        let col = spans_width(&spans);
+        spans.extend(tokenize_visible(rest, style, col, visible));
         return Line::from(spans);
     }
-    Line::from(tokenize_with_patterns(&parsed.raw, style))
+    Line::from(tokenize_visible(&parsed.raw, style, 0, visible))
 }
 
-fn highlight_json_line(parsed: &ParsedLine) -> Line<'_> {
-    let style = level_style(parsed.level);
-
-    let level_str = match parsed.level {
-        Some(LogLevel::Fatal) => "FTL",
-        Some(LogLevel::Error) => "ERR",
-        Some(LogLevel::Warn) => "WRN",
-        Some(LogLevel::Info) => "INF",
-        Some(LogLevel::Debug) => "DBG",
-        Some(LogLevel::Trace) => "TRC",
-        None => "???",
-    };
-
-    let mut spans = Vec::new();
-
-    spans.push(Span::styled(
-        format!("[{}]", level_str),
-        level_badge_style(parsed.level),
-    ));
-    spans.push(Span::styled(" ".to_string(), Style::default()));
+/// The `[LVL] timestamp ` prefix of the compact structured view.
+fn compact_prefix(parsed: &ParsedLine, badge: LevelBadge) -> Vec<Span<'static>> {
+    let mut spans = badge.spans(parsed.level);
 
     if let Some(ref ts) = parsed.timestamp {
         spans.push(Span::styled(format!("{} ", ts), timestamp_style()));
     }
+    spans
+}
+
+fn highlight_json_line<'a>(
+    parsed: &'a ParsedLine,
+    visible: &Range<usize>,
+    badge: LevelBadge,
+) -> Line<'a> {
+    let style = level_style(parsed.level);
+    let mut spans = compact_prefix(parsed, badge);
+    let col = spans_width(&spans);
+    spans.extend(tokenize_visible(&parsed.message, style, col, visible));
+
+    push_extra_fields(&mut spans, parsed, visible);
+
+    Line::from(spans)
+}
 
-    spans.extend(tokenize_with_patterns(&parsed.message, style));
+/// Widths of the access log columns: the longest common method (`OPTIONS`),
+/// then bytes and latency, which are right-aligned.
+const ACCESS_METHOD_WIDTH: usize = 7;
+const ACCESS_BYTES_WIDTH: usize = 8;
+const ACCESS_LATENCY_WIDTH: usize = 7;
+
+/// Fields an access log line shows as columns rather than after the path.
+const ACCESS_COLUMN_FIELDS: [&str; 2] = ["bytes", "latency"];
+
+/// Style of an HTTP status by class: 2xx green, 4xx yellow, 5xx red, and
+/// others in the line's level color.
+fn status_style(status: &str, level: Option<LogLevel>) -> Style {
+    match status.as_bytes().first() {
+        Some(b'2') => Style::default().fg(Color::Green),
+        Some(b'4') => Style::default().fg(Color::Yellow),
+        Some(b'5') => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        _ => level_style(level),
+    }
+}
 
-    if !parsed.extra_fields.is_empty() {
-        let extras: String = parsed
+/// The method, status, bytes, latency and path of an access log line,
+/// padded into columns: `GET     200     2326   0.042  /api/users`. The
+/// latency column is only there when the line has one. None when the line
+/// didn't parse as an access log.
+fn access_cells(parsed: &ParsedLine) -> Option<Vec<Span<'static>>> {
+    let (method, rest) = parsed.message.split_once(' ')?;
+    let (path, status) = rest.rsplit_once(' ')?;
+    if status.len() != 3 || !status.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |key: &str| {
+        parsed
             .extra_fields
             .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect::<Vec<_>>()
-            .join(" ");
-        let dim_style = Style::default()
-            .fg(Color::DarkGray)
-            .add_modifier(Modifier::DIM);
-        spans.push(Span::styled("  ", dim_style));
-        spans.extend(tokenize_with_patterns(&extras, dim_style));
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+    let bytes = field("bytes")?;
+
+    let mut spans = vec![
+        Span::styled(
+            format!("{method:<ACCESS_METHOD_WIDTH$}"),
+            http_method_style(),
+        ),
+        Span::raw(" "),
+        Span::styled(status.to_string(), status_style(status, parsed.level)),
+        Span::styled(format!(" {bytes:>ACCESS_BYTES_WIDTH$}"), number_style()),
+    ];
+    if let Some(latency) = field("latency") {
+        spans.push(Span::styled(
+            format!(" {latency:>ACCESS_LATENCY_WIDTH$}"),
+            number_style(),
+        ));
+    }
+    spans.push(Span::raw("  "));
+    spans.push(Span::styled(path.to_string(), path_style()));
+    Some(spans)
+}
+
+/// The fields an access log line shows after its path: all but bytes and
+/// latency, which have columns.
+fn access_fields_text(parsed: &ParsedLine) -> String {
+    parsed
+        .extra_fields
+        .iter()
+        .filter(|(k, _)| !ACCESS_COLUMN_FIELDS.contains(&k.as_str()))
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The text of an access log line after its `[LVL] timestamp ` prefix, as
+/// [`highlight_line`] shows it, or None when it's shown in the compact view.
+pub fn access_line_text(parsed: &ParsedLine) -> Option<String> {
+    let mut text: String = access_cells(parsed)?
+        .iter()
+        .map(|span| span.content.as_ref())
+        .collect();
+    let fields = access_fields_text(parsed);
+    if !fields.is_empty() {
+        text.push_str("  ");
+        text.push_str(&fields);
     }
+    Some(text)
+}
 
+/// Access log lines in columns: method, status colored by class, bytes and
+/// latency right-aligned, then the path and the remaining fields. Lines
+/// that didn't parse fall back to the compact view.
+fn highlight_access_line<'a>(
+    parsed: &'a ParsedLine,
+    visible: &Range<usize>,
+    badge: LevelBadge,
+) -> Line<'a> {
+    let Some(cells) = access_cells(parsed) else {
+        return highlight_json_line(parsed, visible, badge);
+    };
+    let mut spans = compact_prefix(parsed, badge);
+    spans.extend(cells);
+    push_dim_fields(&mut spans, &access_fields_text(parsed), visible);
     Line::from(spans)
 }
 
-fn highlight_syslog_line(parsed: &ParsedLine) -> Line<'_> {
+fn highlight_syslog_line<'a>(parsed: &'a ParsedLine, visible: &Range<usize>) -> Line<'a> {
     let style = level_style(parsed.level);
 
     if let Some(ref ts) = parsed.timestamp
@@ -563,16 +869,29 @@ fn highlight_syslog_line(parsed: &ParsedLine) -> Line<'_> {
         let ts_end = pos + ts.len();
         let (ts_part, rest) = parsed.raw.split_at(ts_end);
         let mut spans = vec![Span::styled(ts_part.to_string(), timestamp_style())];
-        spans.extend(tokenize_with_patterns(rest, style));
+        let col = spans_width(&spans);
+        spans.extend(tokenize_visible(rest, style, col, visible));
         return Line::from(spans);
     }
-    Line::from(tokenize_with_patterns(&parsed.raw, style))
+    Line::from(tokenize_visible(&parsed.raw, style, 0, visible))
 }
 
 /// Overlay search-match highlighting onto an already-styled Line.
 /// Finds all case-insensitive occurrences of `pattern` in the concatenated
 /// span text, splits spans at match boundaries, and applies bg(Yellow)/fg(Black).
+#[allow(dead_code)]
 pub fn apply_search_highlight(line: Line<'_>, pattern: &str) -> Line<'static> {
+    apply_search_highlight_with(line, pattern, false, false)
+}
+
+/// [`apply_search_highlight`], matching `pattern`'s case exactly when
+/// `case_sensitive` and only as a whole word when `whole_word`.
+pub fn apply_search_highlight_with(
+    line: Line<'_>,
+    pattern: &str,
+    case_sensitive: bool,
+    whole_word: bool,
+) -> Line<'static> {
     if pattern.is_empty() {
         return Line::from(
             line.spans
@@ -583,11 +902,14 @@ pub fn apply_search_highlight(line: Line<'_>, pattern: &str) -> Line<'static> {
     }
 
     let full_text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
-    let text_lower = full_text.to_lowercase();
-    let pattern_lower = pattern.to_lowercase();
+    let (haystack, needle) = if case_sensitive {
+        (full_text.clone(), pattern.to_string())
+    } else {
+        (full_text.to_lowercase(), pattern.to_lowercase())
+    };
 
-    // Guard: if to_lowercase() changes byte length, skip highlighting
-    if text_lower.len() != full_text.len() {
+    // Guard: if lowercasing changes byte length, skip highlighting
+    if haystack.len() != full_text.len() {
         return Line::from(
             line.spans
                 .iter()
@@ -597,14 +919,11 @@ pub fn apply_search_highlight(line: Line<'_>, pattern: &str) -> Line<'static> {
     }
 
     // Find all match byte ranges
-    let mut matches: Vec<(usize, usize)> = Vec::new();
-    let mut search_start = 0;
-    while let Some(pos) = text_lower[search_start..].find(&pattern_lower) {
-        let abs_start = search_start + pos;
-        let abs_end = abs_start + pattern_lower.len();
-        matches.push((abs_start, abs_end));
-        search_start = abs_end;
-    }
+    let matches: Vec<(usize, usize)> = haystack
+        .match_indices(needle.as_str())
+        .map(|(start, m)| (start, start + m.len()))
+        .filter(|&(start, end)| !whole_word || is_whole_word(&haystack, start, end))
+        .collect();
 
     if matches.is_empty() {
         return Line::from(
@@ -616,6 +935,74 @@ pub fn apply_search_highlight(line: Line<'_>, pattern: &str) -> Line<'static> {
     }
 
     let highlight = Style::default().bg(Color::Yellow).fg(Color::Black);
+    restyle_ranges(&line, &full_text, &matches, |_| highlight)
+}
+
+/// [`apply_search_highlight`] for a `re:` filter: every non-empty match of
+/// `re` gets the search highlight.
+pub fn apply_regex_search_highlight(line: Line<'_>, re: &Regex) -> Line<'static> {
+    let full_text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    let matches: Vec<(usize, usize)> = re
+        .find_iter(&full_text)
+        .filter(|m| !m.is_empty())
+        .map(|m| (m.start(), m.end()))
+        .collect();
+    let highlight = Style::default().bg(Color::Yellow).fg(Color::Black);
+    restyle_ranges(&line, &full_text, &matches, |_| highlight)
+}
+
+/// Make invisible characters visible: tabs as `→`, carriage returns as `^M`
+/// and trailing spaces as `·`, dimmed. Tabs and spaces keep a width of one.
+pub fn visualize_whitespace(line: Line<'_>) -> Line<'static> {
+    let full_text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    let trailing_start = full_text.trim_end_matches([' ', '\t', '\r']).len();
+    let mut new_spans: Vec<Span<'static>> = Vec::new();
+    let mut text_pos = 0;
+    for span in &line.spans {
+        let mut plain = String::new();
+        for (i, c) in span.content.char_indices() {
+            let shown = match c {
+                '\t' => "→",
+                '\r' => "^M",
+                ' ' if text_pos + i >= trailing_start => "·",
+                _ => {
+                    plain.push(c);
+                    continue;
+                }
+            };
+            if !plain.is_empty() {
+                new_spans.push(Span::styled(std::mem::take(&mut plain), span.style));
+            }
+            new_spans.push(Span::styled(shown, span.style.fg(Color::DarkGray)));
+        }
+        if !plain.is_empty() {
+            new_spans.push(Span::styled(plain, span.style));
+        }
+        text_pos += span.content.len();
+    }
+    Line::from(new_spans)
+}
+
+/// Recolor every match of `re` in `line` with foreground `color`, keeping the
+/// rest of each span's style (used for profile highlight rules).
+pub fn apply_pattern_highlight(line: Line<'_>, re: &Regex, color: Color) -> Line<'static> {
+    let full_text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    let matches: Vec<(usize, usize)> = re
+        .find_iter(&full_text)
+        .filter(|m| !m.is_empty())
+        .map(|m| (m.start(), m.end()))
+        .collect();
+    restyle_ranges(&line, &full_text, &matches, |style| style.fg(color))
+}
+
+/// Split `line`'s spans at the (sorted, non-overlapping) byte ranges in
+/// `matches` and apply `restyle` to the matched pieces.
+fn restyle_ranges(
+    line: &Line<'_>,
+    full_text: &str,
+    matches: &[(usize, usize)],
+    restyle: impl Fn(Style) -> Style,
+) -> Line<'static> {
     let mut new_spans: Vec<Span<'static>> = Vec::new();
     let mut text_pos: usize = 0;
     let mut match_idx: usize = 0;
@@ -641,7 +1028,7 @@ pub fn apply_search_highlight(line: Line<'_>, pattern: &str) -> Line<'static> {
             if effective_start < effective_end {
                 new_spans.push(Span::styled(
                     full_text[effective_start..effective_end].to_string(),
-                    highlight,
+                    restyle(span.style),
                 ));
             }
 
@@ -667,37 +1054,168 @@ pub fn apply_search_highlight(line: Line<'_>, pattern: &str) -> Line<'static> {
     Line::from(new_spans)
 }
 
+/// Whether a line has a multi-line pretty form: JSON with a pretty body, or a
+/// structured (logfmt, klog, log4j, python, access, console) line with extracted fields.
+fn has_pretty_form(parsed: &ParsedLine) -> bool {
+    match parsed.format {
+        LogFormat::Json => parsed.pretty_json.is_some(),
+        LogFormat::Logfmt
+        | LogFormat::Klog
+        | LogFormat::Log4j
+        | LogFormat::PythonLog
+        | LogFormat::AccessLog
+        | LogFormat::Console
+        | LogFormat::Cri => !parsed.extra_fields.is_empty(),
+        LogFormat::Syslog | LogFormat::Plain => false,
+    }
+}
+
+/// Number of display rows `highlight_line_expanded` produces for this line.
+/// Kept in sync with the expansion so viewport math doesn't have to render.
+pub fn expanded_line_count(parsed: &ParsedLine, pretty: bool) -> usize {
+    if !pretty || !has_pretty_form(parsed) {
+        return 1;
+    }
+    match parsed.pretty_json {
+        Some(ref pj) if parsed.format == LogFormat::Json => pj.lines().count() + 1,
+        // header + message + one row per field
+        _ => parsed.extra_fields.len() + 2,
+    }
+}
+
+/// Header row shared by every pretty expansion: `--- [LVL] `.
+fn pretty_header(parsed: &ParsedLine, badge: LevelBadge) -> Line<'static> {
+    let style = level_style(parsed.level);
+    let mut spans = vec![Span::styled(
+        "--- ".to_string(),
+        style.add_modifier(Modifier::BOLD),
+    )];
+    spans.extend(badge.spans(parsed.level));
+    Line::from(spans)
+}
+
+fn json_punctuation_style() -> Style {
+    Style::default().fg(Color::DarkGray)
+}
+
+/// Syntax-highlight one line of `serde_json::to_string_pretty` output:
+/// keys, string values, numbers, booleans/null, and punctuation each get
+/// the matching inline-token style.
+pub fn highlight_pretty_json_line(line: &str) -> Vec<Span<'static>> {
+    let bytes = line.as_bytes();
+    let mut spans = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b'"' => {
+                // Scan to the closing quote, skipping escaped characters
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                let is_key = line[i..].trim_start().starts_with(':');
+                let style = if is_key {
+                    key_value_key_style()
+                } else {
+                    quoted_str_style()
+                };
+                spans.push(Span::styled(line[start..i].to_string(), style));
+            }
+            b'{' | b'}' | b'[' | b']' | b',' | b':' => {
+                i += 1;
+                spans.push(Span::styled(
+                    line[start..i].to_string(),
+                    json_punctuation_style(),
+                ));
+            }
+            b' ' | b'\t' => {
+                while i < bytes.len() && (bytes[i] == b' ' || bytes[i] == b'\t') {
+                    i += 1;
+                }
+                spans.push(Span::styled(line[start..i].to_string(), Style::default()));
+            }
+            _ => {
+                // Bare literal: number, true/false/null
+                while i < bytes.len() && !b" \t,:]}".contains(&bytes[i]) {
+                    i += 1;
+                }
+                let literal = &line[start..i];
+                let style = if matches!(literal, "true" | "false" | "null") {
+                    keyword_style()
+                } else {
+                    number_style()
+                };
+                spans.push(Span::styled(literal.to_string(), style));
+            }
+        }
+    }
+
+    spans
+}
+
 /// Returns one or more Lines for a parsed line.
-/// In pretty mode for JSON, returns the expanded multi-line JSON.
+/// In pretty mode, JSON expands to its indented body and other structured
+/// formats expand to one aligned `key: value` row per field.
 /// For everything else (or when pretty=false), returns a single line.
 pub fn highlight_line_expanded(parsed: &ParsedLine, pretty: bool) -> Vec<Line<'_>> {
-    if pretty
-        && parsed.format == LogFormat::Json
+    highlight_line_expanded_with(parsed, pretty, LevelBadge::default())
+}
+
+/// [`highlight_line_expanded`] with the level shown as `badge`.
+pub fn highlight_line_expanded_with(
+    parsed: &ParsedLine,
+    pretty: bool,
+    badge: LevelBadge,
+) -> Vec<Line<'_>> {
+    if !pretty || !has_pretty_form(parsed) {
+        return vec![highlight_line_visible_with(parsed, &(0..usize::MAX), badge)];
+    }
+
+    let style = level_style(parsed.level);
+    let mut lines = vec![pretty_header(parsed, badge)];
+
+    if parsed.format == LogFormat::Json
         && let Some(ref pretty_json) = parsed.pretty_json
     {
-        let style = level_style(parsed.level);
-        let level_str = match parsed.level {
-            Some(LogLevel::Fatal) => "FTL",
-            Some(LogLevel::Error) => "ERR",
-            Some(LogLevel::Warn) => "WRN",
-            Some(LogLevel::Info) => "INF",
-            Some(LogLevel::Debug) => "DBG",
-            Some(LogLevel::Trace) => "TRC",
-            None => "???",
-        };
-
-        let mut lines = Vec::new();
-        // First line: level badge + separator
-        lines.push(Line::from(vec![
-            Span::styled("--- ".to_string(), style.add_modifier(Modifier::BOLD)),
-            Span::styled(format!("[{}]", level_str), level_badge_style(parsed.level)),
-            Span::styled(" ".to_string(), Style::default()),
-        ]));
-        // Pretty-printed JSON lines
         for json_line in pretty_json.lines() {
-            lines.push(Line::from(Span::styled(format!("  {}", json_line), style)));
+            let mut spans = vec![Span::styled("  ".to_string(), Style::default())];
+            spans.extend(highlight_pretty_json_line(json_line));
+            lines.push(Line::from(spans));
         }
         return lines;
     }
-    vec![highlight_line(parsed)]
+
+    // Structured formats: timestamp joins the header, then one aligned row per field
+    if let Some(ref ts) = parsed.timestamp {
+        lines[0]
+            .spans
+            .push(Span::styled(ts.clone(), timestamp_style()));
+    }
+    let key_width = parsed
+        .extra_fields
+        .iter()
+        .map(|(k, _)| k.chars().count())
+        .chain(std::iter::once("message".len()))
+        .max()
+        .unwrap_or(0);
+    let mut field_row = |key: &str, value: &str, value_style: Style| {
+        let mut spans = vec![
+            Span::styled("  ".to_string(), Style::default()),
+            Span::styled(
+                format!("{:<width$}", format!("{}:", key), width = key_width + 1),
+                key_value_key_style(),
+            ),
+            Span::styled(" ".to_string(), Style::default()),
+        ];
+        spans.extend(tokenize_with_patterns(value, value_style));
+        lines.push(Line::from(spans));
+    };
+    field_row("message", &parsed.message, style);
+    for (k, v) in &parsed.extra_fields {
+        field_row(k, v, Style::default());
+    }
+    lines
 }
@@ -1,9 +1,38 @@
 pub mod ai;
+pub mod ansi;
 pub mod app;
+pub mod bundle;
 pub mod command;
+pub mod config;
+pub mod control;
+pub mod decode;
+pub mod derived;
+pub mod diff;
+pub mod exporter;
+pub mod fields;
 pub mod filter;
+pub mod generate;
+pub mod hexdump;
 pub mod highlighter;
+pub mod linestats;
+pub mod loader;
+pub mod macros;
+pub mod markers;
+pub mod metrics;
+pub mod noise;
 pub mod parser;
+pub mod picker;
+pub mod provenance;
+pub mod recent;
+pub mod replay;
+pub mod savedfilters;
+pub mod signals;
 pub mod source;
+pub mod split;
+pub mod storyline;
 pub mod timeindex;
+pub mod traces;
 pub mod ui;
+pub mod units;
+pub mod viewstate;
+pub mod worker;
@@ -0,0 +1,85 @@
+use crate::parser::ParsedLine;
+
+/// How many of the longest lines the panel lists.
+pub const TOP_LONGEST: usize = 10;
+
+/// Lines at least this many bytes long are flagged: a log line that size is
+/// usually a whole payload or several lines run together at ingestion.
+pub const OVERSIZED_BYTES: usize = 10_000;
+
+/// Upper bounds (exclusive) of the length buckets, in bytes; the last bucket
+/// holds everything from 1MB up.
+const BUCKET_BOUNDS: &[usize] = &[100, 1_000, 10_000, 100_000, 1_000_000];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthBucket {
+    /// Inclusive lower bound in bytes.
+    pub min: usize,
+    /// Exclusive upper bound in bytes; None for the last, open-ended bucket.
+    pub max: Option<usize>,
+    pub count: usize,
+}
+
+/// Byte lengths of a set of lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthStats {
+    pub lines: usize,
+    pub total_bytes: usize,
+    pub min: usize,
+    pub p50: usize,
+    pub p95: usize,
+    pub max: usize,
+    /// From shortest up to the bucket holding the longest line.
+    pub buckets: Vec<LengthBucket>,
+    /// (original index, length), longest first; ties in line order.
+    pub longest: Vec<(usize, usize)>,
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`.
+fn percentile(values: &[usize], p: f64) -> usize {
+    let rank = (p * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+/// Length statistics of the raw text of `indices`, or None when there are
+/// no lines. Lengths are in bytes as stored, without the line ending.
+pub fn length_stats(lines: &[ParsedLine], indices: &[usize], top: usize) -> Option<LengthStats> {
+    if indices.is_empty() {
+        return None;
+    }
+    let mut sized: Vec<(usize, usize)> = indices
+        .iter()
+        .map(|&idx| (idx, lines[idx].raw.len()))
+        .collect();
+    let mut lengths: Vec<usize> = sized.iter().map(|&(_, len)| len).collect();
+    lengths.sort_unstable();
+    let max = lengths[lengths.len() - 1];
+
+    let mut buckets: Vec<LengthBucket> = Vec::new();
+    let mut min = 0;
+    for max in BUCKET_BOUNDS.iter().map(|&b| Some(b)).chain([None]) {
+        buckets.push(LengthBucket { min, max, count: 0 });
+        min = max.unwrap_or(usize::MAX);
+    }
+    for &len in &lengths {
+        let pos = BUCKET_BOUNDS.partition_point(|&bound| bound <= len);
+        buckets[pos].count += 1;
+    }
+    let last = BUCKET_BOUNDS.partition_point(|&bound| bound <= max);
+    buckets.truncate(last + 1);
+
+    // Equal lengths stay in line order
+    sized.sort_by_key(|&(idx, len)| (std::cmp::Reverse(len), idx));
+    sized.truncate(top);
+
+    Some(LengthStats {
+        lines: lengths.len(),
+        total_bytes: lengths.iter().sum(),
+        min: lengths[0],
+        p50: percentile(&lengths, 0.5),
+        p95: percentile(&lengths, 0.95),
+        max,
+        buckets,
+        longest: sized,
+    })
+}
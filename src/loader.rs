@@ -0,0 +1,118 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
+
+/// How far a background load has got, shared with the thread doing it.
+#[derive(Debug, Default)]
+pub struct LoadProgress {
+    total_bytes: u64,
+    bytes: AtomicU64,
+    lines: AtomicUsize,
+    parsing: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+impl LoadProgress {
+    pub fn new(total_bytes: u64) -> Self {
+        Self {
+            total_bytes,
+            ..Self::default()
+        }
+    }
+
+    /// Size of the file, or 0 when unknown (pipes).
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn lines(&self) -> usize {
+        self.lines.load(Ordering::Relaxed)
+    }
+
+    pub fn set_read(&self, bytes: u64, lines: usize) {
+        self.bytes.store(bytes, Ordering::Relaxed);
+        self.lines.store(lines, Ordering::Relaxed);
+    }
+
+    /// Reading is done and the lines are being parsed.
+    pub fn start_parsing(&self) {
+        self.parsing.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_parsing(&self) -> bool {
+        self.parsing.load(Ordering::Relaxed)
+    }
+
+    /// Ask the loading thread to stop at its next check.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// A load running on its own thread.
+pub struct Loading<T> {
+    progress: Arc<LoadProgress>,
+    rx: mpsc::Receiver<anyhow::Result<T>>,
+    started: Instant,
+}
+
+/// Run `work` on a background thread, handing it the progress to report.
+pub fn spawn<T, F>(total_bytes: u64, work: F) -> Loading<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&LoadProgress) -> anyhow::Result<T> + Send + 'static,
+{
+    let progress = Arc::new(LoadProgress::new(total_bytes));
+    let (tx, rx) = mpsc::channel();
+    let shared = Arc::clone(&progress);
+    std::thread::spawn(move || {
+        let _ = tx.send(work(&shared));
+    });
+    Loading {
+        progress,
+        rx,
+        started: Instant::now(),
+    }
+}
+
+impl<T> Loading<T> {
+    pub fn progress(&self) -> &LoadProgress {
+        &self.progress
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// The result once the thread is done.
+    pub fn try_finish(&self) -> Option<anyhow::Result<T>> {
+        match self.rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Some(Err(anyhow::anyhow!("loading stopped unexpectedly")))
+            }
+        }
+    }
+
+    /// Block until the thread is done.
+    pub fn finish(self) -> anyhow::Result<T> {
+        self.rx
+            .recv()
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("loading stopped unexpectedly")))
+    }
+
+    /// Stop waiting. The thread stops reading at its next check; anything it
+    /// still returns is dropped.
+    pub fn cancel(self) {
+        self.progress.cancel();
+    }
+}
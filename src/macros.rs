@@ -0,0 +1,129 @@
+use crate::replay::{key_name, parse_key};
+use crossterm::event::KeyEvent;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Vim-style keystroke macros: `Q{a-z}` records into a register, `Q` stops,
+/// `@{a-z}` replays and `@@` repeats the last replayed register. Registers
+/// are kept in `macros.toml` under [`config_dir`](crate::config::config_dir),
+/// one `a = ["/", "t", "i", "m", "e", "enter"]` line each, so a macro made
+/// on one incident log can be replayed on the next.
+#[derive(Default)]
+pub struct MacroRecorder {
+    registers: HashMap<char, Vec<KeyEvent>>,
+    recording: Option<(char, Vec<KeyEvent>)>,
+    last_played: Option<char>,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    crate::config::config_dir().map(|dir| dir.join("macros.toml"))
+}
+
+/// Registers are single lowercase letters.
+pub fn is_register(c: char) -> bool {
+    c.is_ascii_lowercase()
+}
+
+impl MacroRecorder {
+    /// Start recording into `register`, discarding its previous contents.
+    pub fn start(&mut self, register: char) {
+        self.recording = Some((register, Vec::new()));
+    }
+
+    /// Stop recording and store the keys. Returns the register written to.
+    pub fn stop(&mut self) -> Option<char> {
+        let (register, keys) = self.recording.take()?;
+        self.registers.insert(register, keys);
+        Some(register)
+    }
+
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(r, _)| *r)
+    }
+
+    /// Append a key to the macro being recorded, if any.
+    pub fn record(&mut self, key: KeyEvent) {
+        if let Some((_, keys)) = self.recording.as_mut() {
+            keys.push(key);
+        }
+    }
+
+    /// Keys stored in `register` (`@` means the last replayed register).
+    pub fn keys(&mut self, register: char) -> Option<Vec<KeyEvent>> {
+        let register = if register == '@' {
+            self.last_played?
+        } else {
+            register
+        };
+        let keys = self.registers.get(&register)?.clone();
+        self.last_played = Some(register);
+        Some(keys)
+    }
+
+    /// Registers from a `macros.toml`, keys named as in recordings.
+    pub fn from_toml(text: &str) -> anyhow::Result<Self> {
+        let table: BTreeMap<String, Vec<String>> = toml::from_str(text)?;
+        let mut macros = Self::default();
+        for (name, names) in table {
+            let mut chars = name.chars();
+            let register = match (chars.next(), chars.next()) {
+                (Some(c), None) if is_register(c) => c,
+                _ => anyhow::bail!("macro register {name:?} is not a lowercase letter"),
+            };
+            let keys = names
+                .iter()
+                .map(|key| {
+                    parse_key(key)
+                        .ok_or_else(|| anyhow::anyhow!("unknown key {key:?} in macro {name}"))
+                })
+                .collect::<anyhow::Result<_>>()?;
+            macros.registers.insert(register, keys);
+        }
+        Ok(macros)
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        let table: BTreeMap<String, Vec<String>> = self
+            .registers
+            .iter()
+            .map(|(register, keys)| {
+                (
+                    register.to_string(),
+                    keys.iter().filter_map(key_name).collect(),
+                )
+            })
+            .collect();
+        Ok(toml::to_string(&table)?)
+    }
+
+    /// The macros at `path`; a missing file has none.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => anyhow::bail!("cannot read macros {}: {e}", path.display()),
+        };
+        Self::from_toml(&text)
+            .map_err(|e| anyhow::anyhow!("invalid macros {}: {e}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| anyhow::anyhow!("cannot create {}: {e}", dir.display()))?;
+        }
+        std::fs::write(path, self.to_toml()?)
+            .map_err(|e| anyhow::anyhow!("cannot write macros {}: {e}", path.display()))
+    }
+}
+
+/// Save `register` of `macros` into the file at `path`, re-reading it first
+/// so viewers running side by side keep each other's macros.
+pub fn record(path: &Path, macros: &MacroRecorder, register: char) -> anyhow::Result<()> {
+    let Some(keys) = macros.registers.get(&register) else {
+        return Ok(());
+    };
+    let mut saved = MacroRecorder::load(path)?;
+    saved.registers.insert(register, keys.clone());
+    saved.save(path)
+}
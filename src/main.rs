@@ -1,31 +1,87 @@
 mod ai;
+mod ansi;
 mod app;
+mod bundle;
 mod command;
+mod config;
+mod control;
+mod decode;
+mod derived;
+mod diff;
+mod exporter;
+mod fields;
 mod filter;
+mod generate;
+mod hexdump;
 mod highlighter;
+mod linestats;
+mod loader;
+mod macros;
+mod markers;
+mod metrics;
+mod noise;
 mod parser;
+mod picker;
+mod provenance;
+mod recent;
+mod replay;
+mod savedfilters;
+mod signals;
 mod source;
+mod split;
+mod storyline;
 mod timeindex;
+mod traces;
 mod ui;
+mod units;
+mod viewstate;
+mod worker;
 
-use app::{App, AppMode, MenuAction};
+use app::{App, AppMode, MenuAction, PINS_FILE, VIEW_TEXT_FILE, YANK_INLINE_LIMIT};
 use clap::Parser;
+use crossterm::cursor::SetCursorStyle;
 use crossterm::event::{
-    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, MouseButton,
-    MouseEventKind,
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
 };
 use crossterm::execute;
+use crossterm::terminal::SetTitle;
+use macros::MacroRecorder;
 use source::{FileSource, FollowableSource, FollowableStdinSource};
 use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::sync::mpsc;
 use std::time::Duration;
+use worker::{Job, JobOutcome, Worker};
 
 enum AiResult {
     Filter(Result<String, String>),
     Analyze(Result<String, String>),
 }
 
+/// Accumulates a vim-style numeric prefix for the next motion (`25j`, `100G`).
+#[derive(Default)]
+struct PendingCount(Option<usize>);
+
+impl PendingCount {
+    /// Consume `code` if it extends the count. A leading `0` is not a count.
+    fn push(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char(c @ '0'..='9') if c != '0' || self.0.is_some() => {
+                let digit = c as usize - '0' as usize;
+                let n = self.0.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+                self.0 = Some(n);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn take(&mut self) -> Option<usize> {
+        self.0.take()
+    }
+}
+
 enum FollowSource {
     File(FollowableSource),
     Stdin(FollowableStdinSource),
@@ -35,10 +91,15 @@ enum FollowSource {
 #[command(
     name = "lumolog",
     version,
-    about = "A terminal log viewer that makes logs readable"
+    about = "A terminal log viewer that makes logs readable",
+    args_conflicts_with_subcommands = true
 )]
 struct Cli {
-    /// Log file to view. Omit to read from stdin.
+    #[command(subcommand)]
+    subcommand: Option<Subcommand>,
+
+    /// Log file to view. Omit to read from stdin, or to pick one when run
+    /// bare in a terminal.
     file: Option<PathBuf>,
 
     /// Follow for new lines (like tail -f). Works with files and piped stdin.
@@ -56,56 +117,441 @@ struct Cli {
     /// AI model name (defaults per provider)
     #[arg(long)]
     ai_model: Option<String>,
+
+    /// Config file (defaults to ~/.config/lumolog/config.toml)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Config profile to use (defaults to the first whose paths match the file)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Open a view state saved with "Export view state" (file, filters, time range)
+    #[arg(long)]
+    load: Option<PathBuf>,
+
+    /// Open at this line number. `lumolog app.log +12345` works too.
+    #[arg(long, value_name = "LINE", conflicts_with = "goto_ts")]
+    goto: Option<usize>,
+
+    /// Open at the first line at or after this time, e.g. "2024-01-15T08:30"
+    #[arg(long, value_name = "TIME")]
+    goto_ts: Option<String>,
+
+    /// Rule bundle to use on top of the profile: ignore, highlight and pivot
+    /// rules, and filters to start with (see "Export rule bundle")
+    #[arg(long, value_name = "FILE", global = true)]
+    rules: Option<PathBuf>,
+
+    /// On quit, write the filters, time range, cursor line, mark and pins
+    /// to this file as JSON, for scripts that run lumolog to pick a slice.
+    #[arg(long, value_name = "FILE")]
+    dump_state_on_exit: Option<PathBuf>,
+
+    /// Write the keys, mouse actions and pastes handled to this file with
+    /// their timing, to replay with --replay-events.
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+
+    /// Play back events written by --record instead of reading the
+    /// keyboard, then hand over. Opens the recorded file unless one is
+    /// given. With output piped, prints the final screen instead.
+    #[arg(long, value_name = "FILE", conflicts_with = "recent")]
+    replay_events: Option<PathBuf>,
+
+    /// Send a command to a running lumolog and exit, e.g.
+    /// `--ctl set-filter error`, `--ctl goto-end`, `--ctl set-level warn`
+    #[arg(long, value_name = "COMMAND", num_args = 1.., allow_hyphen_values = true)]
+    ctl: Option<Vec<String>>,
+
+    /// Control socket to listen on (or to send to with --ctl). Defaults to one
+    /// per viewer under $XDG_RUNTIME_DIR/lumolog; --ctl then picks the newest.
+    #[arg(long, value_name = "PATH")]
+    socket: Option<PathBuf>,
+
+    /// Serve Prometheus metrics (lines read, errors, memory) on this address,
+    /// e.g. 127.0.0.1:9187. Requires --follow.
+    #[arg(long, value_name = "ADDR", requires = "follow")]
+    metrics: Option<String>,
+
+    /// Push line counts by level, template and marker rule to statsd
+    /// (statsd://host:8125) or an OTLP collector (http://host:4318) every
+    /// few seconds. Requires --follow.
+    #[arg(long, value_name = "URL", requires = "follow")]
+    export_metrics: Option<String>,
+
+    /// Smart case: filters ignore case unless they have an uppercase
+    /// letter. Alt-C in the filter bar switches while viewing.
+    #[arg(short, long)]
+    smart_case: bool,
+
+    /// Choose from recently opened files. Prints them, newest first, when
+    /// not run in a terminal.
+    #[arg(long, conflicts_with_all = ["file", "load"])]
+    recent: bool,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Subcommand {
+    /// Write synthetic logs to stdout, for demos, benchmarks and bug reports.
+    /// The same seed and start always give the same log.
+    Generate(GenerateArgs),
+    /// Print how each line parses (format, level, timestamp, message,
+    /// fields) instead of opening the viewer. Uses the --profile format and
+    /// the config's derived fields, to debug them outside the viewer.
+    TestFormat(TestFormatArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct TestFormatArgs {
+    /// Log file to parse. Omit to read from stdin.
+    file: Option<PathBuf>,
 }
 
-fn execute_action(action: MenuAction, value: String, app: &mut App) {
+#[derive(clap::Args, Debug)]
+struct GenerateArgs {
+    /// Log format: json, logfmt, plain, syslog or access
+    #[arg(long, default_value = "json")]
+    format: String,
+
+    /// Lines per second, before bursts
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    rate: u32,
+
+    /// How much log time to cover, e.g. 60s, 5m or 1h
+    #[arg(long, default_value = "60s")]
+    duration: String,
+
+    /// Seed for the random choices
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// Time of the first line (defaults to 2024-01-15 08:00 UTC, or now
+    /// with --realtime)
+    #[arg(long, value_name = "TIME")]
+    start: Option<String>,
+
+    /// Write each line when its timestamp comes round instead of all at
+    /// once, to feed a viewer in follow mode
+    #[arg(long)]
+    realtime: bool,
+}
+
+fn run_generate(args: &GenerateArgs) -> anyhow::Result<()> {
+    let format = parser::LogFormat::from_name(&args.format)
+        .ok_or_else(|| anyhow::anyhow!("unknown format '{}'", args.format))?;
+    let mut generator = generate::Generator::new(format, args.seed)?;
+    let seconds = generate::parse_duration(&args.duration)?;
+    let start = match args.start.as_deref() {
+        Some(text) => timeindex::parse_user_time(text).ok_or_else(|| {
+            anyhow::anyhow!("cannot read --start '{text}' as a time, e.g. 2024-01-15T08:30")
+        })?,
+        None => generate::default_start(args.realtime),
+    };
+    generate::run(
+        &mut generator,
+        start,
+        args.rate as usize,
+        seconds,
+        args.realtime,
+        &mut std::io::BufWriter::new(std::io::stdout().lock()),
+    )?;
+    Ok(())
+}
+
+fn execute_action(action: MenuAction, value: String, app: &mut App, worker: &Worker) {
     match action {
         MenuAction::FilterByValue => {
             app.set_filter(value);
         }
+        MenuAction::ValueDistribution => {
+            app.open_value_distribution(value.trim_end_matches('='));
+        }
+        MenuAction::SortByField => {
+            app.sort_by_field(value.trim_end_matches('='));
+        }
+        MenuAction::Decode => app.open_decoded(&value),
+        MenuAction::MuteTemplate(line) => app.mute_template(line),
         MenuAction::LookupAbuseIPDB => {
             let url = format!("https://www.abuseipdb.com/check/{}", value);
-            let _ = open::that(url);
+            app.begin_background_job();
+            worker.submit(Job::Open(url));
         }
         MenuAction::OpenInBrowser => {
-            let _ = open::that(&value);
+            app.begin_background_job();
+            worker.submit(Job::Open(value));
         }
+        MenuAction::Pivot { url, .. } => {
+            app.begin_background_job();
+            worker.submit(Job::Open(config::expand_pivot_url(&url, &value)));
+        }
+        MenuAction::Split { command, .. } => {
+            if let Some(mux) = split::Multiplexer::detect() {
+                app.begin_background_job();
+                worker.submit(Job::Split(mux, split::expand(&command, &value)));
+            }
+        }
+    }
+}
+
+fn apply_control(cmd: control::Command, app: &mut App) {
+    use control::Command::*;
+    match cmd {
+        SetFilter(text) => app.set_filter(text),
+        ClearFilter => app.set_filter(String::new()),
+        SetLevel(level) => app.set_level_filter(level),
+        ClearAll => app.clear_all_filters(),
+        GotoTop if app.is_cursor_mode() => app.cursor_up(usize::MAX),
+        GotoTop => app.scroll_to_top(),
+        GotoEnd if app.is_cursor_mode() => app.cursor_down(usize::MAX),
+        GotoEnd => app.scroll_to_bottom(),
+        Goto(line) => app.goto_line(line),
+        Quit => app.quit(),
     }
 }
 
-fn dispatch_action(action: command::Action, app: &mut App) {
+/// Where named filters are kept; an error when there's no home directory.
+fn saved_filters_path() -> anyhow::Result<std::path::PathBuf> {
+    savedfilters::default_path()
+        .ok_or_else(|| anyhow::anyhow!("no config directory to keep saved filters in"))
+}
+
+fn dispatch_action(action: command::Action, app: &mut App, worker: &Worker) {
     use command::Action::*;
     match action {
-        Quit => app.quit(),
+        Quit => app.request_quit(),
         ScrollDown => app.scroll_down(1),
         ScrollUp => app.scroll_up(1),
         ScrollLeft => app.scroll_left(1),
         ScrollRight => app.scroll_right(1),
         PageDown => app.page_down(),
         PageUp => app.page_up(),
+        HalfPageDown => app.half_page_down(),
+        HalfPageUp => app.half_page_up(),
         ScrollToTop => app.scroll_to_top(),
         ScrollToBottom => app.scroll_to_bottom(),
         OpenFilter => app.enter_filter_mode(),
         CycleLevelUp => app.cycle_level_up(),
         CycleLevelDown => app.cycle_level_down(),
+        OpenLevelPicker => app.open_level_picker(),
         TogglePretty => app.toggle_pretty(),
+        TogglePrettyLine => app.toggle_line_pretty(),
         ToggleWrap => app.toggle_wrap(),
+        ToggleWhitespace => app.toggle_whitespace(),
+        ToggleHighContrast => app.toggle_high_contrast(),
+        ToggleTextCues => app.toggle_text_cues(),
+        CycleLevelBadge => app.cycle_level_badge(),
+        ToggleJoinLines => app.toggle_join_lines(),
+        CycleBlankLines => app.cycle_blank_lines(),
+        ExportViewText => {
+            let path = std::env::current_dir()
+                .unwrap_or_default()
+                .join(VIEW_TEXT_FILE);
+            match std::fs::write(&path, app.export_view_text()) {
+                Ok(()) => {
+                    app.begin_background_job();
+                    worker.submit(Job::Copy(path.display().to_string()));
+                }
+                Err(e) => app.set_job_error(format!("cannot write {}: {e}", path.display())),
+            }
+        }
         EnterCursorMode => app.enter_cursor_mode(),
         ToggleFollowPause => app.toggle_follow_pause(),
         OpenCommandPalette => app.open_palette(),
         YankLine => {
-            if let Some(text) = app.cursor_line_raw()
-                && let Ok(mut clipboard) = arboard::Clipboard::new()
-            {
-                let _ = clipboard.set_text(text.to_string());
-                app.set_yank_flash();
+            if let Some(text) = app.cursor_entry_raw() {
+                app.begin_background_job();
+                worker.submit(Job::Copy(text));
+            }
+        }
+        YankLocation => {
+            if let Some(text) = app.cursor_location() {
+                app.begin_background_job();
+                worker.submit(Job::Copy(text));
+            }
+        }
+        YankAllFiltered if app.filtered_raw_len() > YANK_INLINE_LIMIT => {
+            // Too big for a clipboard: save to a file and copy its path instead
+            let path = std::env::temp_dir().join(format!(
+                "lumolog-yank-{}-{}.log",
+                std::process::id(),
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            ));
+            match app.save_filtered_lines(&path) {
+                Ok(()) => {
+                    app.begin_background_job();
+                    app.set_notice(format!(
+                        "{} lines saved to {}, path copied",
+                        storyline::group_thousands(app.total_lines()),
+                        path.display()
+                    ));
+                    worker.submit(Job::Copy(path.display().to_string()));
+                }
+                Err(e) => app.set_job_error(e.to_string()),
             }
         }
         YankAllFiltered => {
             let text = app.all_filtered_lines_raw();
-            if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                let _ = clipboard.set_text(text);
-                app.set_yank_flash();
+            app.begin_background_job();
+            worker.submit(Job::Copy(text));
+        }
+        ToggleMark => {
+            if app.is_cursor_mode() {
+                app.toggle_mark();
+            }
+        }
+        YankMarkedRange | SaveMarkedRange if !app.is_cursor_mode() => {}
+        YankMarkedRange | SaveMarkedRange if app.marked_range().is_none() => {
+            app.set_notice("Mark a line with m first".to_string());
+        }
+        YankMarkedRange if app.marked_range_raw_len() > YANK_INLINE_LIMIT => {
+            // Too big for a clipboard, as with yanking every filtered line
+            dispatch_action(SaveMarkedRange, app, worker);
+        }
+        YankMarkedRange => {
+            if let Some(text) = app.marked_range_raw() {
+                app.begin_background_job();
+                worker.submit(Job::Copy(text));
+            }
+        }
+        SaveMarkedRange => {
+            let name = app.marked_range_file_name().unwrap_or_default();
+            let path = std::env::current_dir().unwrap_or_default().join(name);
+            match app.save_marked_range(&path) {
+                Ok(()) => {
+                    app.begin_background_job();
+                    app.set_notice(format!(
+                        "{} lines saved to {}, path copied",
+                        storyline::group_thousands(app.marked_range_count()),
+                        path.display()
+                    ));
+                    worker.submit(Job::Copy(path.display().to_string()));
+                }
+                Err(e) => app.set_job_error(e.to_string()),
+            }
+        }
+        DiffWithMark => {
+            if app.is_cursor_mode() {
+                app.open_diff();
+            }
+        }
+        ShowHexDump => {
+            if app.is_cursor_mode() {
+                app.open_hex_dump();
+            }
+        }
+        OpenEventList => app.open_event_list(),
+        OpenMalformedPanel => app.open_malformed_panel(),
+        ToggleMalformedOnly => app.toggle_malformed_only(),
+        CollapseNoise => app.open_noise_preview(),
+        ShowNoise => app.show_noise(),
+        MuteTemplate => {
+            if app.is_cursor_mode() {
+                app.mute_cursor_line();
+            }
+        }
+        OpenLineStats => app.open_line_stats(),
+        JumpToLongestLine => app.jump_to_longest_line(),
+        NextMatch => app.next_match(1),
+        PrevMatch => app.prev_match(1),
+        AnnotateLine => app.enter_annotate_mode(),
+        OpenAnnotationList => app.open_annotation_list(),
+        OpenTraceView => app.open_trace_view(),
+        OpenStoryline => app.open_storyline(),
+        ExportStoryline => {
+            let text = app.export_storyline();
+            if !text.is_empty() {
+                app.begin_background_job();
+                worker.submit(Job::Copy(text));
+            }
+        }
+        ExportRuleBundle => {
+            let path = std::env::current_dir()
+                .unwrap_or_default()
+                .join(bundle::DEFAULT_FILE);
+            let rules = app.rule_bundle();
+            match rules.save(&path) {
+                Ok(()) => app.set_notice(format!(
+                    "Saved {} rules and {} filters to {}",
+                    rules.rule_count(),
+                    rules.filters.len(),
+                    bundle::DEFAULT_FILE
+                )),
+                Err(e) => app.set_job_error(e.to_string()),
+            }
+        }
+        CycleCaseMode => app.cycle_case_mode(),
+        ToggleWholeWord => app.toggle_whole_word(),
+        ToggleHighlightOnly => app.toggle_highlight_only(),
+        CycleContextLines => app.cycle_context_lines(),
+        SaveFilter => app.enter_save_filter_mode(),
+        OpenSavedFilters => {
+            match saved_filters_path().and_then(|path| savedfilters::SavedFilters::load(&path)) {
+                Ok(saved) => app.open_saved_filters(saved),
+                Err(e) => app.set_job_error(e.to_string()),
+            }
+        }
+        ImportRuleBundle => {
+            let path = std::env::current_dir()
+                .unwrap_or_default()
+                .join(bundle::DEFAULT_FILE);
+            match bundle::RuleBundle::load(&path).and_then(|rules| {
+                app.import_rules(&rules)?;
+                Ok(rules)
+            }) {
+                Ok(rules) => app.set_notice(format!(
+                    "Imported {} rules and {} filters from {}",
+                    rules.rule_count(),
+                    rules.filters.len(),
+                    bundle::DEFAULT_FILE
+                )),
+                Err(e) => app.set_job_error(e.to_string()),
+            }
+        }
+        ExportViewState => {
+            let path = std::env::current_dir()
+                .unwrap_or_default()
+                .join(viewstate::DEFAULT_FILE);
+            match app.view_state().save(&path) {
+                Ok(()) => {
+                    // Copy the command that reopens this view, ready to paste to a teammate
+                    app.begin_background_job();
+                    worker.submit(Job::Copy(format!("lumolog --load {}", path.display())));
+                }
+                Err(e) => app.set_job_error(e.to_string()),
+            }
+        }
+        TogglePin => {
+            if app.is_cursor_mode() {
+                app.toggle_pin();
+            }
+        }
+        YankPins => {
+            let text = app.export_pins();
+            if !text.is_empty() {
+                app.begin_background_job();
+                worker.submit(Job::Copy(text));
+            }
+        }
+        ExportPins => {
+            let text = app.export_pins();
+            if !text.is_empty() {
+                let path = std::env::current_dir().unwrap_or_default().join(PINS_FILE);
+                match std::fs::write(&path, text) {
+                    Ok(()) => {
+                        app.begin_background_job();
+                        worker.submit(Job::Copy(path.display().to_string()));
+                    }
+                    Err(e) => app.set_job_error(format!("cannot write {}: {e}", path.display())),
+                }
+            }
+        }
+        ClearPins => app.clear_pins(),
+        ExportAnnotations => {
+            let text = app.export_annotations();
+            if !text.is_empty() {
+                app.begin_background_job();
+                worker.submit(Job::Copy(text));
             }
         }
         EnterTimeMode => app.enter_time_mode(),
@@ -115,7 +561,17 @@ fn dispatch_action(action: command::Action, app: &mut App) {
                 app.exit_time_mode();
             }
         }
+        ClearAllFilters => app.clear_all_filters(),
         ToggleSparkline => app.toggle_sparkline(),
+        TogglePinnedPane => app.toggle_pinned_pane(),
+        ToggleStatsBar => app.toggle_stats_bar(),
+        GrowPinnedPane => app.grow_pinned_pane(),
+        ShrinkPinnedPane => app.shrink_pinned_pane(),
+        ToggleSampling => app.toggle_sampling(),
+        CycleSort => app.cycle_sort(),
+        NaturalOrder => app.clear_sort(),
+        SampleSparser => app.sample_sparser(),
+        SampleDenser => app.sample_denser(),
         EnterAskMode => app.enter_ask_mode(),
         EnterAnalyzeMode => app.enter_analyze_mode(),
         TimeMarkStart => {
@@ -153,11 +609,148 @@ fn dispatch_action(action: command::Action, app: &mut App) {
             }
             app.time_preset(1440);
         }
+        ToggleInheritedTimes => app.toggle_inherited_times(),
+        LoadRotatedFiles => app.request_rotated_files(),
     }
 }
 
+/// The arguments with a vi-style `+N` turned into `--goto N`, so
+/// `lumolog app.log +12345` opens at line 12345. Only positionals count:
+/// option values, anything after `--` and subcommand arguments are left as
+/// they are, so `--ctl set-filter +0200` and `-- +5` still work.
+fn plus_line_args(args: impl Iterator<Item = std::ffi::OsString>) -> Vec<std::ffi::OsString> {
+    let mut cmd = <Cli as clap::CommandFactory>::command();
+    cmd.build();
+    let mut args = args.fuse();
+    let mut out: Vec<std::ffi::OsString> = args.next().into_iter().collect();
+    while let Some(arg) = args.next() {
+        let Some(text) = arg.to_str() else {
+            out.push(arg);
+            continue;
+        };
+        if text == "--" || cmd.find_subcommand(text).is_some() {
+            out.push(arg);
+            out.extend(args.by_ref());
+            break;
+        }
+        let option = if let Some(long) = text.strip_prefix("--") {
+            (!long.contains('=')).then(|| cmd.get_arguments().find(|a| a.get_long() == Some(long)))
+        } else if let Some(short) = text.strip_prefix('-')
+            && let [c] = short.chars().collect::<Vec<_>>()[..]
+        {
+            Some(cmd.get_arguments().find(|a| a.get_short() == Some(c)))
+        } else {
+            None
+        };
+        if let Some(option) = option {
+            out.push(arg);
+            let takes = option
+                .and_then(|a| a.get_num_args())
+                .filter(|n| n.takes_values());
+            match takes {
+                // `--ctl` swallows the rest of the line
+                Some(n) if n.max_values() > 1 => out.extend(args.by_ref()),
+                Some(_) => out.extend(args.next()),
+                None => {}
+            }
+            continue;
+        }
+        match text.strip_prefix('+') {
+            Some(n) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) => {
+                out.push("--goto".into());
+                out.push(n.into());
+            }
+            _ => out.push(arg),
+        }
+    }
+    out
+}
+
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse_from(plus_line_args(std::env::args_os()));
+
+    if let Some(Subcommand::Generate(ref args)) = cli.subcommand {
+        if let Err(e) = run_generate(args) {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Subcommand::TestFormat(ref args)) = cli.subcommand {
+        // Profiles are chosen by path, so the file has to be in place first
+        cli.file = args.file.clone();
+        let settings = Settings::load(&cli).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        return print_parse_results(&cli, settings);
+    }
+
+    if let Some(ref words) = cli.ctl {
+        match control::send(cli.socket.as_deref(), words) {
+            Ok(reply) => {
+                println!("{reply}");
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let goto_time = cli.goto_ts.as_deref().map(|text| {
+        timeindex::parse_user_time(text).unwrap_or_else(|| {
+            eprintln!("Error: cannot read --goto-ts '{text}' as a time, e.g. 2024-01-15T08:30");
+            std::process::exit(1);
+        })
+    });
+
+    let view_state = cli.load.as_deref().map(|path| {
+        viewstate::ViewState::load(path).unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        })
+    });
+    let recording = cli.replay_events.as_deref().map(|path| {
+        replay::Recording::load(path).unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        })
+    });
+    // A file given on the command line wins over the one in the saved view,
+    // which wins over the recorded one
+    if cli.file.is_none() {
+        cli.file = view_state.as_ref().and_then(|s| s.file.clone());
+    }
+    if cli.file.is_none() {
+        cli.file = recording.as_ref().and_then(|r| r.header.file.clone());
+    }
+    // Replaying into a pipe: draw off-screen and print where it ends up
+    let headless = recording.is_some() && !std::io::stdout().is_terminal();
+    if headless && cli.file.is_none() {
+        eprintln!("Error: replaying without a terminal needs a log file");
+        std::process::exit(1);
+    }
+
+    let mut settings = Settings::load(&cli).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+
+    let recent_path = recent::default_path();
+    let recent_files = recent_path
+        .as_deref()
+        .map(recent::RecentFiles::load)
+        .unwrap_or_default()
+        .existing();
+    if cli.recent && !(std::io::stdin().is_terminal() && std::io::stdout().is_terminal()) {
+        for file in &recent_files {
+            println!("{}", file.display());
+        }
+        return Ok(());
+    }
 
     let ai_config: Option<ai::AiConfig> = {
         let provider_str = cli
@@ -195,58 +788,120 @@ fn main() -> anyhow::Result<()> {
         }
     };
 
-    let (lines, mut follow_source) = match &cli.file {
+    // Bind before taking over the terminal so an address in use is reported plainly
+    let metrics = cli.metrics.as_deref().map(|addr| {
+        let metrics = std::sync::Arc::new(metrics::Metrics::default());
+        if let Err(e) = metrics::serve(addr, std::sync::Arc::clone(&metrics)) {
+            eprintln!("Error: cannot serve metrics on {addr}: {e}");
+            std::process::exit(1);
+        }
+        metrics
+    });
+    let exporter = cli.export_metrics.as_deref().map(|url| {
+        let target = exporter::Target::parse(url).unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        });
+        let exporter = std::sync::Arc::new(exporter::Exporter::new(target));
+        exporter::start(std::sync::Arc::clone(&exporter));
+        exporter
+    });
+    let recorder = cli.record.as_deref().map(|path| {
+        replay::Recorder::create(path).unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        })
+    });
+
+    // False when there's no terminal to take keyboard input from
+    let mut interactive = true;
+    // Taken over early when a file is loaded with a progress screen
+    let mut tui = None;
+    let (mut app, mut follow_source) = match cli.file.clone() {
         Some(path) => {
             if !path.exists() {
                 eprintln!("Error: file not found: {}", path.display());
                 std::process::exit(1);
             }
-            let source = FileSource::open(path)?;
-            let follow = if cli.follow {
-                let initial_offset = std::fs::metadata(path)?.len();
-                Some(FollowSource::File(FollowableSource::new(
-                    path,
-                    initial_offset,
-                )))
+            if cli.follow && source::is_stream(&path) {
+                // e.g. `lumolog -f <(journalctl -fu svc)`: stream it like piped stdin
+                let file = std::fs::File::open(&path)?;
+                let mut stream = FollowableStdinSource::from_reader(file);
+                let initial = stream.recv_initial(Duration::from_millis(500));
+                let app = App::with_format(initial, settings.format);
+                (app, Some(FollowSource::Stdin(stream)))
+            } else if headless {
+                let (source, offset) = FileSource::open_with_progress(
+                    &path,
+                    cli.follow,
+                    &loader::LoadProgress::default(),
+                )?;
+                let app = App::with_format(source.into_lines(), settings.format);
+                let follow = cli
+                    .follow
+                    .then(|| FollowSource::File(FollowableSource::new(&path, offset)));
+                (app, follow)
+            } else {
+                let (term, signals) = tui.insert(init_terminal()?);
+                match load_file(term, signals, &path, cli.follow, settings.format) {
+                    Ok(Some(opened)) => opened,
+                    Ok(None) => {
+                        restore_terminal()?;
+                        eprintln!("Cancelled opening {}", path.display());
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        restore_terminal()?;
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        None if std::io::stdin().is_terminal() => {
+            // Launched bare: choose a file, coming back here if its load is
+            // cancelled or fails
+            let (term, signals) = tui.insert(init_terminal()?);
+            let root = std::env::current_dir()?;
+            let fuzzy = settings.config.palette;
+            let mut picker = if cli.recent {
+                picker::FilePicker::recent(root, &recent_files, fuzzy)
             } else {
-                None
+                picker::FilePicker::scan(root, &recent_files, fuzzy)
             };
-            (source.lines().to_vec(), follow)
+            loop {
+                let Some(path) = pick_file(term, signals, &mut picker)? else {
+                    restore_terminal()?;
+                    return Ok(());
+                };
+                // Profiles are chosen by path, so settle them now there is one
+                cli.file = Some(path.clone());
+                settings = match Settings::load(&cli) {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        picker.set_error(e);
+                        continue;
+                    }
+                };
+                match load_file(term, signals, &path, cli.follow, settings.format) {
+                    Ok(Some(opened)) => break opened,
+                    Ok(None) => {}
+                    Err(e) => picker.set_error(format!("Cannot open {}: {e}", display_name(&path))),
+                }
+            }
         }
         None => {
-            if std::io::stdin().is_terminal() {
-                eprintln!("Usage: lumolog <file> or pipe input via stdin");
-                eprintln!("Example: cat app.log | lumolog");
-                std::process::exit(1);
-            }
-
             if cli.follow {
-                // Stdin follow mode: spawn background reader before dup2
+                // Stdin follow mode: spawn background reader before redirecting stdin
                 let mut stdin_source = FollowableStdinSource::spawn_stdin();
                 let initial = stdin_source.recv_initial(Duration::from_millis(500));
 
-                // Redirect stdin to /dev/tty so crossterm can read keyboard events
-                #[cfg(unix)]
-                {
-                    use std::os::unix::io::AsRawFd;
-                    match std::fs::OpenOptions::new()
-                        .read(true)
-                        .write(true)
-                        .open("/dev/tty")
-                    {
-                        Ok(tty) => {
-                            let tty_fd = tty.as_raw_fd();
-                            unsafe { libc::dup2(tty_fd, libc::STDIN_FILENO) };
-                            std::mem::forget(tty);
-                        }
-                        Err(e) => {
-                            eprintln!("Cannot open /dev/tty for interactive mode: {e}");
-                            std::process::exit(1);
-                        }
-                    }
+                if let Err(e) = attach_tty() {
+                    eprintln!("No terminal for interactive mode ({e}), printing instead");
+                    interactive = false;
                 }
 
-                (initial, Some(FollowSource::Stdin(stdin_source)))
+                let app = App::with_format(initial, settings.format);
+                (app, Some(FollowSource::Stdin(stdin_source)))
             } else {
                 let lines = source::StdinSource::read_all()?.lines().to_vec();
                 if lines.is_empty() {
@@ -255,306 +910,1120 @@ fn main() -> anyhow::Result<()> {
                     std::process::exit(1);
                 }
 
-                // Redirect stdin to /dev/tty so crossterm can read keyboard events
-                #[cfg(unix)]
-                {
-                    use std::os::unix::io::AsRawFd;
-                    match std::fs::OpenOptions::new()
-                        .read(true)
-                        .write(true)
-                        .open("/dev/tty")
-                    {
-                        Ok(tty) => {
-                            let tty_fd = tty.as_raw_fd();
-                            unsafe { libc::dup2(tty_fd, libc::STDIN_FILENO) };
-                            std::mem::forget(tty);
-                        }
-                        Err(e) => {
-                            eprintln!("Cannot open /dev/tty for interactive mode: {e}");
-                            std::process::exit(1);
-                        }
-                    }
+                if let Err(e) = attach_tty() {
+                    eprintln!("No terminal for interactive mode ({e}), printing instead");
+                    interactive = false;
                 }
 
-                (lines, None)
+                (App::with_format(lines, settings.format), None)
             }
         }
     };
 
-    let mut terminal = ratatui::init();
-    execute!(std::io::stdout(), EnableMouseCapture)?;
+    app.scroll_to_bottom();
+    app.set_follow_mode(cli.follow);
+    let bundle_filters = settings.rules.bundle.filters.clone();
+    settings.apply(&mut app);
+    let counters = LineCounters { metrics, exporter };
+    // After the settings, so the config's marker rules are counted
+    counters.observe(&app, 0);
+    if cli.smart_case {
+        app.set_case_mode(filter::CaseMode::Smart);
+    }
+    if !bundle_filters.is_empty() {
+        app.set_filter_stack(&bundle_filters);
+    }
+
+    if let Some(ref path) = cli.file {
+        app.set_source_path(std::path::absolute(path).unwrap_or_else(|_| path.clone()));
+        app.set_source_name(display_name(path));
+    } else {
+        app.set_source_name("stdin".to_string());
+    }
+
+    if let Some(ref state) = view_state {
+        app.apply_view_state(state);
+    }
+
+    // Land on the requested line with the cursor on it
+    if let Some(line) = cli.goto {
+        app.enter_cursor_mode();
+        app.goto_line(line);
+    } else if let Some(time) = goto_time {
+        app.enter_cursor_mode();
+        app.goto_time(time);
+    }
+
+    if let Some(ref path) = cli.file
+        && !source::is_stream(path)
+    {
+        let rotated = source::rotated_siblings(path).len();
+        if rotated > 0 {
+            app.set_notice(format!(
+                "{rotated} rotated {} beside {}: R reads them in too",
+                if rotated == 1 { "file" } else { "files" },
+                display_name(path)
+            ));
+        }
+    }
+
+    if !interactive {
+        return print_lines(&mut app, &mut follow_source);
+    }
+
+    // Streams like `<(journalctl -fu api)` can't be opened again
+    if let (Some(path), Some(recent_path)) = (&cli.file, &recent_path)
+        && !source::is_stream(path)
+        && !headless
+    {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.clone());
+        // Not worth interrupting the viewer over a read-only state directory
+        let _ = recent::record(recent_path, &path);
+    }
+
+    if let Some(recording) = recording.as_ref().filter(|_| headless) {
+        let header = &recording.header;
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(
+            header.width,
+            header.height,
+        ))?;
+        let inputs = Inputs {
+            control: None,
+            replay: Some(replay::Player::new(recording.events.clone())),
+            recorder,
+            signals: signals::Signals::install()?,
+            headless,
+        };
+        run_event_loop(
+            &mut terminal,
+            &mut app,
+            &mut follow_source,
+            ai_config,
+            &counters,
+            inputs,
+            &cli,
+        )?;
+        print!("{}", replay::screen_text(terminal.backend().buffer()));
+        if let Some(ref path) = cli.dump_state_on_exit {
+            app.exit_state().save(path)?;
+        }
+        return Ok(());
+    }
+
+    let (mut terminal, signals) = match tui {
+        Some(tui) => tui,
+        None => init_terminal()?,
+    };
+
+    if let Some(ref recording) = recording {
+        let size = terminal.size()?;
+        let header = &recording.header;
+        if (size.width, size.height) != (header.width, header.height) {
+            app.set_notice(format!(
+                "Recorded in a {}x{} terminal, this one is {}x{}: mouse events may land elsewhere",
+                header.width, header.height, size.width, size.height
+            ));
+        }
+    }
+
+    let inputs = Inputs {
+        // Scripting is a nice-to-have: without a socket the viewer works as usual
+        control: control::listen(
+            &cli.socket
+                .clone()
+                .unwrap_or_else(control::default_socket_path),
+        )
+        .ok(),
+        replay: recording.map(|r| replay::Player::new(r.events)),
+        recorder,
+        signals,
+        headless,
+    };
+
+    let result = run_event_loop(
+        &mut terminal,
+        &mut app,
+        &mut follow_source,
+        ai_config,
+        &counters,
+        inputs,
+        &cli,
+    );
+    if let Some(ref exporter) = counters.exporter {
+        // Don't lose the counts since the last push
+        let _ = exporter.flush();
+    }
+
+    restore_terminal()?;
+    result?;
+    if let Some(ref path) = cli.dump_state_on_exit {
+        app.exit_state().save(path)?;
+    }
+    Ok(())
+}
+
+/// Take over the terminal for the viewer, restoring it if we panic. The
+/// signal handlers go in at the same time, so Ctrl-C in the picker or on the
+/// loading screen still gives the terminal back.
+fn init_terminal() -> anyhow::Result<(ratatui::DefaultTerminal, signals::Signals)> {
+    let signals = signals::Signals::install()?;
+    let terminal = ratatui::init();
+    execute!(std::io::stdout(), EnableMouseCapture, EnableBracketedPaste)?;
 
     // Ensure terminal is restored even on panic
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
-        let _ = execute!(std::io::stdout(), DisableMouseCapture);
+        let _ = execute!(
+            std::io::stdout(),
+            DisableMouseCapture,
+            DisableBracketedPaste,
+            SetCursorStyle::DefaultUserShape
+        );
         ratatui::restore();
         original_hook(panic_info);
     }));
+    Ok((terminal, signals))
+}
 
-    let mut app = App::new(lines);
-    app.scroll_to_bottom();
-    app.set_follow_mode(cli.follow);
+fn restore_terminal() -> anyhow::Result<()> {
+    execute!(
+        std::io::stdout(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        SetCursorStyle::DefaultUserShape
+    )?;
+    ratatui::restore();
+    Ok(())
+}
 
-    if let Some(ref path) = cli.file {
-        app.set_source_name(
-            path.file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| path.display().to_string()),
-        );
+/// File name shown in the title and status bar.
+fn display_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Read `path` in the background behind the loading screen and build the
+/// viewer for it. None when the user backs out.
+fn load_file(
+    terminal: &mut ratatui::DefaultTerminal,
+    signals: &signals::Signals,
+    path: &std::path::Path,
+    follow: bool,
+    format: Option<parser::LogFormat>,
+) -> anyhow::Result<Option<(App, Option<FollowSource>)>> {
+    // A compressed file's size on disk isn't how much there is to read
+    let total_bytes = if source::is_compressed(path) {
+        0
     } else {
-        app.set_source_name("stdin".to_string());
+        std::fs::metadata(path)?.len()
+    };
+    let load_path = path.to_path_buf();
+    let loading = loader::spawn(total_bytes, move |progress| {
+        let (source, offset) = FileSource::open_with_progress(&load_path, follow, progress)?;
+        progress.start_parsing();
+        Ok((App::with_format(source.into_lines(), format), offset))
+    });
+    let Some((app, offset)) = wait_for_load(terminal, signals, &display_name(path), loading)?
+    else {
+        return Ok(None);
+    };
+    let follow = follow.then(|| FollowSource::File(FollowableSource::new(path, offset)));
+    Ok(Some((app, follow)))
+}
+
+/// Run the file picker until a file is chosen, or None on Esc, Ctrl-C or a
+/// quit signal.
+fn pick_file(
+    terminal: &mut ratatui::DefaultTerminal,
+    signals: &signals::Signals,
+    picker: &mut picker::FilePicker,
+) -> anyhow::Result<Option<PathBuf>> {
+    loop {
+        terminal.draw(|frame| ui::render_picker(frame, picker))?;
+        if signals.take_quit() {
+            return Ok(None);
+        }
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Ok(None),
+            KeyCode::Char('c') if ctrl => return Ok(None),
+            KeyCode::Enter => {
+                if let Some(path) = picker.selected_path() {
+                    return Ok(Some(path));
+                }
+            }
+            KeyCode::Up => picker.move_selection(-1),
+            KeyCode::Char('p') if ctrl => picker.move_selection(-1),
+            KeyCode::Down => picker.move_selection(1),
+            KeyCode::Char('n') if ctrl => picker.move_selection(1),
+            KeyCode::PageUp => picker.move_selection(-10),
+            KeyCode::PageDown => picker.move_selection(10),
+            KeyCode::Tab => picker.toggle_show_all(),
+            KeyCode::Backspace => picker.pop_char(),
+            KeyCode::Char('u') if ctrl => picker.clear_query(),
+            KeyCode::Char(c) if !ctrl => picker.push_char(c),
+            _ => {}
+        }
     }
+}
 
-    let result = run_event_loop(&mut terminal, &mut app, &mut follow_source, ai_config);
+/// Files that open faster than this never show the loading screen.
+const LOADING_SCREEN_DELAY: Duration = Duration::from_millis(150);
 
-    execute!(std::io::stdout(), DisableMouseCapture)?;
-    ratatui::restore();
+/// Wait for a background load, drawing its progress. None when the user
+/// backs out with Esc, `q` or Ctrl-C, or on a quit signal, which is left
+/// pending so the picker quits too.
+fn wait_for_load<B: ratatui::backend::Backend, T>(
+    terminal: &mut ratatui::Terminal<B>,
+    signals: &signals::Signals,
+    name: &str,
+    loading: loader::Loading<T>,
+) -> anyhow::Result<Option<T>>
+where
+    B::Error: Send + Sync + 'static,
+{
+    let mut tick = 0;
+    loop {
+        if let Some(result) = loading.try_finish() {
+            return result.map(Some);
+        }
+        if loading.elapsed() >= LOADING_SCREEN_DELAY {
+            terminal.draw(|frame| ui::render_loading(frame, name, loading.progress(), tick))?;
+            tick += 1;
+        }
+        if signals.quit_pending() {
+            loading.cancel();
+            return Ok(None);
+        }
+        if event::poll(Duration::from_millis(80))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && (matches!(key.code, KeyCode::Esc | KeyCode::Char('q'))
+                || (key.code == KeyCode::Char('c')
+                    && key.modifiers.contains(KeyModifiers::CONTROL)))
+        {
+            loading.cancel();
+            return Ok(None);
+        }
+    }
+}
 
-    result
+/// State the key handler needs beyond `App`: background channels and
+/// partially typed input.
+struct KeyContext {
+    ai_config: Option<std::sync::Arc<ai::AiConfig>>,
+    ai_tx: mpsc::Sender<AiResult>,
+    worker: Worker,
+    pending_count: PendingCount,
+    macros: MacroRecorder,
+    /// Where recorded macros are saved; None when replaying headless, so
+    /// a recording plays the same wherever it runs.
+    macros_path: Option<std::path::PathBuf>,
+    /// Set after `Q` or `@` while waiting for the register key.
+    macro_prompt: Option<MacroPrompt>,
+    /// Nesting depth of macro replays, so a macro that calls itself stops.
+    replay_depth: usize,
+    /// Keys replayed since the outermost `@`, so `100@a` nested eight deep
+    /// can't freeze the viewer.
+    replayed_keys: usize,
 }
 
-fn run_event_loop(
-    terminal: &mut ratatui::DefaultTerminal,
+enum MacroPrompt {
+    Record,
+    /// Replay this many times.
+    Play(usize),
+}
+
+/// Deepest a macro may replay other macros (including itself).
+const MAX_MACRO_DEPTH: usize = 8;
+
+/// Most keys one `@` may replay, counts and nested macros included.
+const MAX_REPLAYED_KEYS: usize = 10_000;
+
+/// `Q` starts waiting for a register to record into, or stops the recording in progress.
+fn toggle_macro_recording(app: &mut App, ctx: &mut KeyContext) {
+    if ctx.macros.recording().is_some() {
+        let register = ctx.macros.stop();
+        app.set_macro_recording(None);
+        if let (Some(register), Some(path)) = (register, &ctx.macros_path)
+            && let Err(e) = macros::record(path, &ctx.macros, register)
+        {
+            app.set_job_error(e.to_string());
+        }
+    } else {
+        ctx.macro_prompt = Some(MacroPrompt::Record);
+    }
+}
+
+/// Feed the keys stored in `register` back through `handle_key`, `times` times.
+fn replay_macro(register: char, times: usize, app: &mut App, ctx: &mut KeyContext) {
+    if ctx.replay_depth >= MAX_MACRO_DEPTH {
+        return;
+    }
+    let Some(keys) = ctx.macros.keys(register) else {
+        return;
+    };
+    if ctx.replay_depth == 0 {
+        ctx.replayed_keys = 0;
+    }
+    ctx.replay_depth += 1;
+    'replay: for _ in 0..times {
+        for &key in &keys {
+            if ctx.replayed_keys >= MAX_REPLAYED_KEYS {
+                app.set_notice(format!(
+                    "Macro stopped after {} keys",
+                    storyline::group_thousands(MAX_REPLAYED_KEYS)
+                ));
+                break 'replay;
+            }
+            ctx.replayed_keys += 1;
+            handle_key(key, app, ctx);
+        }
+    }
+    ctx.replay_depth -= 1;
+}
+
+/// Handle one key press. Split out of the event loop so macros can replay keys
+/// through exactly the same path as live input.
+fn handle_key(key: KeyEvent, app: &mut App, ctx: &mut KeyContext) {
+    // A pending `Q` / `@` takes the next key as the register name
+    if let Some(prompt) = ctx.macro_prompt.take() {
+        if let KeyCode::Char(c) = key.code {
+            match prompt {
+                MacroPrompt::Record if macros::is_register(c) => {
+                    ctx.macros.start(c);
+                    app.set_macro_recording(Some(c));
+                }
+                MacroPrompt::Play(times) if macros::is_register(c) || c == '@' => {
+                    replay_macro(c, times, app, ctx);
+                }
+                _ => {}
+            }
+        }
+        return;
+    }
+
+    if app.quit_confirmation().is_some() {
+        app.answer_quit(matches!(key.code, KeyCode::Char('y' | 'q')));
+    } else if app.analyze_response().is_some() {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => app.clear_analyze_response(),
+            KeyCode::Down | KeyCode::Char('j') => app.analyze_scroll_down(1),
+            KeyCode::Up | KeyCode::Char('k') => app.analyze_scroll_up(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => app.analyze_scroll_down(10),
+            KeyCode::PageUp => app.analyze_scroll_up(10),
+            _ => {}
+        }
+    } else if app.event_list_selected().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.event_list_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.event_list_up(),
+            KeyCode::Enter => app.event_list_jump(),
+            KeyCode::Esc | KeyCode::Char('q' | 'e') => app.close_event_list(),
+            _ => {}
+        }
+    } else if app.level_picker_selected().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.level_picker_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.level_picker_up(),
+            KeyCode::Char(' ' | 'x') => app.level_picker_toggle(),
+            KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q' | 'L') => app.close_level_picker(),
+            _ => {}
+        }
+    } else if app.noise_preview().is_some() {
+        match key.code {
+            KeyCode::Right | KeyCode::Char('l' | '+' | '=') => app.noise_preview_more(),
+            KeyCode::Left | KeyCode::Char('h' | '-') => app.noise_preview_fewer(),
+            KeyCode::Enter => app.collapse_noise(),
+            KeyCode::Esc | KeyCode::Char('q' | 'C') => app.close_noise_preview(),
+            _ => {}
+        }
+    } else if app.line_stats().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.line_stats_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.line_stats_up(),
+            KeyCode::Enter => app.line_stats_jump(),
+            KeyCode::Esc | KeyCode::Char('q' | 'I') => app.close_line_stats(),
+            _ => {}
+        }
+    } else if app.malformed_panel().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.malformed_panel_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.malformed_panel_up(),
+            KeyCode::Enter => app.malformed_panel_jump(),
+            KeyCode::Char('f') => {
+                app.close_malformed_panel();
+                app.toggle_malformed_only();
+            }
+            KeyCode::Esc | KeyCode::Char('q' | 'M') => app.close_malformed_panel(),
+            _ => {}
+        }
+    } else if app.annotation_list_selected().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.annotation_list_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.annotation_list_up(),
+            KeyCode::Enter => app.annotation_list_jump(),
+            KeyCode::Char('y') => {
+                dispatch_action(command::Action::ExportAnnotations, app, &ctx.worker)
+            }
+            KeyCode::Esc | KeyCode::Char('q' | 'N') => app.close_annotation_list(),
+            _ => {}
+        }
+    } else if app.saved_filters_selected().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.saved_filters_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.saved_filters_up(),
+            KeyCode::Enter => app.saved_filters_apply(),
+            KeyCode::Char('d') => {
+                if let Some(name) = app.saved_filters_selected_name().map(str::to_string) {
+                    match saved_filters_path().and_then(|path| savedfilters::forget(&path, &name)) {
+                        Ok(saved) => {
+                            app.set_saved_filters(saved);
+                            app.set_notice(format!("Deleted filter \"{name}\""));
+                        }
+                        Err(e) => app.set_job_error(e.to_string()),
+                    }
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q' | 'F') => app.close_saved_filters(),
+            _ => {}
+        }
+    } else if app.trace_view_selected().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.trace_view_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.trace_view_up(),
+            KeyCode::Enter => app.trace_view_jump(),
+            KeyCode::Esc | KeyCode::Char('q' | 'T') => app.close_trace_view(),
+            _ => {}
+        }
+    } else if app.storyline_selected().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.storyline_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.storyline_up(),
+            KeyCode::Enter => app.storyline_jump(),
+            KeyCode::Char('y') => {
+                dispatch_action(command::Action::ExportStoryline, app, &ctx.worker)
+            }
+            KeyCode::Esc | KeyCode::Char('q' | 'O') => app.close_storyline(),
+            _ => {}
+        }
+    } else if let Some(state) = app.decoded() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.decoded_scroll(1),
+            KeyCode::Up | KeyCode::Char('k') => app.decoded_scroll(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => app.decoded_scroll(10),
+            KeyCode::PageUp => app.decoded_scroll(-10),
+            KeyCode::Char('y') => {
+                let text = state.decoded.payload();
+                app.begin_background_job();
+                ctx.worker.submit(Job::Copy(text));
+            }
+            KeyCode::Esc | KeyCode::Char('q') => app.close_decoded(),
+            _ => {}
+        }
+    } else if app.value_distribution().is_some() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.value_dist_down(),
+            KeyCode::Up | KeyCode::Char('k') => app.value_dist_up(),
+            KeyCode::Enter => app.apply_value_distribution(None),
+            KeyCode::Char('s') => app.toggle_value_dist_sort(),
+            KeyCode::Esc | KeyCode::Char('q') => app.close_value_distribution(),
+            _ => {}
+        }
+    } else if let Some((_, text)) = app.hex_dump_line() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => app.scroll_hex_dump(1),
+            KeyCode::Up | KeyCode::Char('k') => app.scroll_hex_dump(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => app.scroll_hex_dump(10),
+            KeyCode::PageUp => app.scroll_hex_dump(-10),
+            KeyCode::Char('y') => {
+                let dump = hexdump::hex_dump(text.as_bytes(), hexdump::WIDE_ROW).join("\n");
+                app.begin_background_job();
+                ctx.worker.submit(Job::Copy(dump));
+            }
+            KeyCode::Esc | KeyCode::Char('q' | 'x') => app.close_hex_dump(),
+            _ => {}
+        }
+    } else if app.diff_lines().is_some() {
+        if matches!(key.code, KeyCode::Esc | KeyCode::Char('q' | 'd')) {
+            app.close_diff();
+        }
+    } else if app.mode() == AppMode::CommandPalette {
+        match key.code {
+            KeyCode::Esc => app.close_palette(),
+            KeyCode::Up => app.palette_up(),
+            KeyCode::Down => app.palette_down(),
+            KeyCode::Enter => {
+                if let Some(action) = app.palette_execute() {
+                    dispatch_action(action, app, &ctx.worker);
+                }
+            }
+            KeyCode::Backspace => app.palette_backspace(),
+            KeyCode::Char(c) => app.palette_type(c),
+            _ => {}
+        }
+    } else if app.mode() == AppMode::TimeRange {
+        match key.code {
+            KeyCode::Left | KeyCode::Char('h') => app.time_cursor_left(1),
+            KeyCode::Right | KeyCode::Char('l') => app.time_cursor_right(1),
+            KeyCode::Char('H') => app.time_cursor_left(5),
+            KeyCode::Char('L') => app.time_cursor_right(5),
+            KeyCode::Char('[') => app.time_mark_start(),
+            KeyCode::Char(']') | KeyCode::Enter => app.time_mark_end_and_apply(),
+            KeyCode::Char('1') => app.time_preset(5),
+            KeyCode::Char('2') => app.time_preset(15),
+            KeyCode::Char('3') => app.time_preset(60),
+            KeyCode::Char('4') => app.time_preset(1440),
+            KeyCode::Char('Y') => {
+                dispatch_action(command::Action::YankAllFiltered, app, &ctx.worker)
+            }
+            KeyCode::Char('c') => {
+                app.clear_time_range();
+                app.exit_time_mode();
+            }
+            KeyCode::Esc => app.exit_time_mode(),
+            KeyCode::Char('q') => app.request_quit(),
+            _ => {}
+        }
+    } else if app.mode() == AppMode::ContextMenu {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => app.menu_up(),
+            KeyCode::Down | KeyCode::Char('j') => app.menu_down(),
+            KeyCode::Enter => {
+                if let Some((action, value)) = app.execute_menu_action() {
+                    execute_action(action, value, app, &ctx.worker);
+                }
+            }
+            KeyCode::Esc => app.close_context_menu(),
+            _ => app.close_context_menu(),
+        }
+    } else if app.mode() == AppMode::Ask {
+        match key.code {
+            KeyCode::Esc => app.exit_ask_mode(),
+            KeyCode::Backspace => app.ask_backspace(),
+            KeyCode::Char(c) => app.ask_type(c),
+            KeyCode::Enter => {
+                let query = app.ask_input().to_string();
+                if !query.is_empty()
+                    && let Some(ref config) = ctx.ai_config
+                {
+                    app.set_ai_thinking(true);
+                    app.exit_ask_mode();
+
+                    let format_name = match app.format() {
+                        parser::LogFormat::Json => "JSON",
+                        parser::LogFormat::Syslog => "Syslog",
+                        parser::LogFormat::Logfmt => "Logfmt",
+                        parser::LogFormat::Klog => "Klog",
+                        parser::LogFormat::Log4j => "Log4j",
+                        parser::LogFormat::PythonLog => "Python",
+                        parser::LogFormat::AccessLog => "Access",
+                        parser::LogFormat::Console => "Console",
+                        parser::LogFormat::Cri => "CRI",
+                        parser::LogFormat::Plain => "Plain",
+                    };
+
+                    let field_names: Vec<String> = app
+                        .visible_parsed_lines_numbered()
+                        .iter()
+                        .flat_map(|(_, pl)| pl.extra_fields.iter().map(|(k, _)| k.clone()))
+                        .collect::<std::collections::BTreeSet<_>>()
+                        .into_iter()
+                        .collect();
+
+                    let time_desc = app.time_index().and_then(|idx| {
+                        let min = idx.min_ts?;
+                        let max = idx.max_ts?;
+                        Some(format!("{} to {}", min, max))
+                    });
+
+                    let sample = app.sample_lines(30);
+
+                    let system_prompt = ai::build_system_prompt(
+                        format_name,
+                        &field_names,
+                        time_desc.as_deref(),
+                        &sample,
+                    );
+
+                    let config = config.clone();
+                    let tx = ctx.ai_tx.clone();
+                    std::thread::spawn(move || {
+                        let result = ai::query_ai(&config, &system_prompt, &query);
+                        let _ = tx.send(AiResult::Filter(result));
+                    });
+                }
+            }
+            _ => {}
+        }
+    } else if app.mode() == AppMode::Annotate {
+        match key.code {
+            KeyCode::Esc => app.exit_annotate_mode(),
+            KeyCode::Enter => app.annotate_submit(),
+            KeyCode::Backspace => app.annotate_backspace(),
+            KeyCode::Char(c) => app.annotate_type(c),
+            _ => {}
+        }
+    } else if app.mode() == AppMode::SaveFilter {
+        match key.code {
+            KeyCode::Esc => app.exit_save_filter_mode(),
+            KeyCode::Enter => {
+                if let Some(saved) = app.save_filter_submit() {
+                    let name = saved.name.clone();
+                    match saved_filters_path().and_then(|path| savedfilters::record(&path, saved)) {
+                        Ok(saved) => {
+                            app.set_saved_filters(saved);
+                            app.set_notice(format!("Saved filter \"{name}\""));
+                        }
+                        Err(e) => app.set_job_error(e.to_string()),
+                    }
+                }
+            }
+            KeyCode::Backspace => app.save_filter_backspace(),
+            KeyCode::Char(c) => app.save_filter_type(c),
+            _ => {}
+        }
+    } else if app.mode() == AppMode::Analyze {
+        match key.code {
+            KeyCode::Esc => app.exit_analyze_mode(),
+            KeyCode::Backspace => app.analyze_backspace(),
+            KeyCode::Char(c) => app.analyze_type(c),
+            KeyCode::Enter => {
+                let question = app.analyze_input().to_string();
+                if !question.is_empty()
+                    && let Some(ref config) = ctx.ai_config
+                {
+                    let log_lines: Vec<String> = app
+                        .all_filtered_lines_raw()
+                        .lines()
+                        .map(|s| s.to_string())
+                        .collect();
+
+                    let (system_prompt, user_msg) = ai::build_analyze_prompt(&question, &log_lines);
+
+                    app.set_ai_thinking(true);
+                    app.exit_analyze_mode();
+
+                    let config = config.clone();
+                    let tx = ctx.ai_tx.clone();
+                    std::thread::spawn(move || {
+                        let result = ai::query_ai(&config, &system_prompt, &user_msg);
+                        let _ = tx.send(AiResult::Analyze(result));
+                    });
+                }
+            }
+            _ => {}
+        }
+    } else if matches!(app.mode(), AppMode::Normal | AppMode::Cursor)
+        && key.modifiers == KeyModifiers::ALT
+        && let KeyCode::Char(c @ '1'..='9') = key.code
+    {
+        // Alt-digit lifts that breadcrumb; plain digits are counts
+        app.remove_breadcrumb(c as usize - '0' as usize);
+    } else if matches!(app.mode(), AppMode::Normal | AppMode::Cursor)
+        && ctx.pending_count.push(key.code)
+    {
+        // Digit consumed as a count prefix for the next motion
+    } else if app.is_cursor_mode() {
+        let count = ctx.pending_count.take();
+        let n = count.unwrap_or(1);
+        match key.code {
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.half_page_down()
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.half_page_up()
+            }
+            KeyCode::Down | KeyCode::Char('j') => app.cursor_down(n),
+            KeyCode::Up | KeyCode::Char('k') => app.cursor_up(n),
+            KeyCode::Char('g' | 'G') if count.is_some() => app.goto_line(n),
+            KeyCode::Char('g') => app.cursor_up(usize::MAX),
+            KeyCode::Char('G') => app.cursor_down(usize::MAX),
+            KeyCode::Char('y') => dispatch_action(command::Action::YankLine, app, &ctx.worker),
+            KeyCode::Char('Y') => {
+                dispatch_action(command::Action::YankAllFiltered, app, &ctx.worker)
+            }
+            KeyCode::Char('s') => app.filter_by_similar(),
+            KeyCode::Char('p') | KeyCode::Enter => app.toggle_line_pretty(),
+            KeyCode::Char('m') => app.toggle_mark(),
+            KeyCode::Char('M') => {
+                dispatch_action(command::Action::YankMarkedRange, app, &ctx.worker)
+            }
+            KeyCode::Char('W') => {
+                dispatch_action(command::Action::SaveMarkedRange, app, &ctx.worker)
+            }
+            KeyCode::Char('b') => app.toggle_pin(),
+            KeyCode::Char('e') => app.open_event_list(),
+            // Match navigation while filtered, as after a search in less or vim
+            KeyCode::Char('n') => app.next_match(n),
+            KeyCode::Char('N') => app.prev_match(n),
+            KeyCode::Char('a') => app.enter_annotate_mode(),
+            KeyCode::Char('E') => app.open_annotation_list(),
+            KeyCode::Char('T') => app.open_trace_view(),
+            KeyCode::Char('O') => app.open_storyline(),
+            KeyCode::Char('Q') => toggle_macro_recording(app, ctx),
+            KeyCode::Char('@') => ctx.macro_prompt = Some(MacroPrompt::Play(n)),
+            KeyCode::Char('d') => app.open_diff(),
+            KeyCode::Char('x') => app.open_hex_dump(),
+            KeyCode::Char('z') => app.mute_cursor_line(),
+            KeyCode::Char('L') => dispatch_action(command::Action::YankLocation, app, &ctx.worker),
+            KeyCode::Char('P') => app.toggle_pretty(),
+            KeyCode::Right | KeyCode::Char('l') => app.scroll_right(n),
+            KeyCode::Left | KeyCode::Char('h') => app.scroll_left(n),
+            KeyCode::Esc => app.exit_cursor_mode(),
+            KeyCode::Char('q') => app.request_quit(),
+            KeyCode::Char('?') => app.open_palette(),
+            _ => {}
+        }
+    } else {
+        let count = ctx.pending_count.take();
+        let n = count.unwrap_or(1);
+        match key.code {
+            _ if app.is_filter_mode() => match key.code {
+                KeyCode::Esc => {
+                    if app.filter_pattern().is_empty() {
+                        app.exit_filter_mode();
+                    } else {
+                        app.clear_filter();
+                    }
+                }
+                KeyCode::Enter => app.exit_filter_mode(),
+                KeyCode::Backspace => app.filter_backspace(),
+                KeyCode::Delete => app.filter_delete(),
+                KeyCode::Left => app.filter_cursor_left(),
+                KeyCode::Right => app.filter_cursor_right(),
+                KeyCode::Home => app.filter_cursor_home(),
+                KeyCode::End => app.filter_cursor_end(),
+                // AltGr arrives as Ctrl+Alt on some terminals; those are characters
+                KeyCode::Char(c)
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && !key.modifiers.contains(KeyModifiers::ALT) =>
+                {
+                    match c {
+                        'a' => app.filter_cursor_home(),
+                        'e' => app.filter_cursor_end(),
+                        'w' => app.filter_delete_word(),
+                        'u' => app.filter_delete_to_start(),
+                        'r' => app.toggle_filter_regex(),
+                        _ => {}
+                    }
+                }
+                KeyCode::Char('c') if key.modifiers == KeyModifiers::ALT => app.cycle_case_mode(),
+                KeyCode::Char('w') if key.modifiers == KeyModifiers::ALT => app.toggle_whole_word(),
+                KeyCode::Char('h') if key.modifiers == KeyModifiers::ALT => {
+                    app.toggle_highlight_only()
+                }
+                KeyCode::Char(c) => app.filter_input(c),
+                _ => {}
+            },
+            KeyCode::Char('q') => app.request_quit(),
+            // Configurable; never quits. Use 'q' to quit.
+            KeyCode::Esc => app.escape(),
+            KeyCode::Char('X') => app.clear_all_filters(),
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.half_page_down()
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.half_page_up()
+            }
+            KeyCode::Down | KeyCode::Char('j') => app.scroll_down(n),
+            KeyCode::Up | KeyCode::Char('k') => app.scroll_up(n),
+            KeyCode::Right | KeyCode::Char('l') => app.scroll_right(n),
+            KeyCode::Left | KeyCode::Char('h') => app.scroll_left(n),
+            KeyCode::Char(' ') if app.is_follow_mode() => app.toggle_follow_pause(),
+            KeyCode::PageDown | KeyCode::Char(' ') => app.page_down(),
+            KeyCode::PageUp => app.page_up(),
+            KeyCode::Char('g' | 'G') if count.is_some() => app.goto_line(n),
+            KeyCode::Char('g') => app.scroll_to_top(),
+            KeyCode::Char('G') => app.scroll_to_bottom(),
+            KeyCode::Char('/') => app.enter_filter_mode(),
+            KeyCode::Char('p') => app.toggle_pretty(),
+            KeyCode::Char('w') => app.toggle_wrap(),
+            KeyCode::Char('W') => app.toggle_whitespace(),
+            KeyCode::Char('D') => app.toggle_sparkline(),
+            KeyCode::Char('Z') => app.toggle_pinned_pane(),
+            KeyCode::Char('B') => app.toggle_stats_bar(),
+            KeyCode::Char('}') => app.grow_pinned_pane(),
+            KeyCode::Char('{') => app.shrink_pinned_pane(),
+            KeyCode::Char('R') => app.request_rotated_files(),
+            KeyCode::Char('v') => app.cycle_level_up(),
+            KeyCode::Char('V') => app.cycle_level_down(),
+            KeyCode::Char('L') => app.open_level_picker(),
+            KeyCode::Char('S') => app.toggle_sampling(),
+            KeyCode::Char('o') => app.cycle_sort(),
+            KeyCode::Char('+') => app.sample_sparser(),
+            KeyCode::Char('-') => app.sample_denser(),
+            KeyCode::Char('Y') => {
+                dispatch_action(command::Action::YankAllFiltered, app, &ctx.worker)
+            }
+            KeyCode::Char('t') => app.enter_time_mode(),
+            KeyCode::Char('e') => app.open_event_list(),
+            KeyCode::Char('M') => app.open_malformed_panel(),
+            KeyCode::Char('I') => app.open_line_stats(),
+            KeyCode::Char('F') => {
+                dispatch_action(command::Action::OpenSavedFilters, app, &ctx.worker)
+            }
+            KeyCode::Char('C') => app.open_noise_preview(),
+            KeyCode::Char('c') => app.cycle_context_lines(),
+            KeyCode::Char('n') => app.next_match(n),
+            KeyCode::Char('N') => app.prev_match(n),
+            KeyCode::Char('E') => app.open_annotation_list(),
+            KeyCode::Char('T') => app.open_trace_view(),
+            KeyCode::Char('O') => app.open_storyline(),
+            KeyCode::Char('Q') => toggle_macro_recording(app, ctx),
+            KeyCode::Char('@') => ctx.macro_prompt = Some(MacroPrompt::Play(n)),
+            KeyCode::Char('a') if app.is_ai_connected() => {
+                app.enter_ask_mode();
+            }
+            KeyCode::Char('A') if app.is_ai_connected() => {
+                app.enter_analyze_mode();
+            }
+            KeyCode::Char('?') => app.open_palette(),
+            KeyCode::Enter => app.enter_cursor_mode(),
+            _ => {}
+        }
+    }
+}
+
+/// `lumolog test-format`: print what the parser makes of each line of the input.
+fn print_parse_results(cli: &Cli, settings: Settings) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let lines = match &cli.file {
+        Some(path) => FileSource::open(path)?.lines().to_vec(),
+        None => source::StdinSource::read_all()?.lines().to_vec(),
+    };
+    let format = settings
+        .format
+        .unwrap_or_else(|| parser::detect_format(&lines));
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    for (i, raw) in lines.iter().enumerate() {
+        let mut parsed = parser::parse_line(raw, format);
+        derived::apply(&settings.derived_fields, &mut parsed);
+        write!(out, "{}", parser::describe(&parsed, i + 1))?;
+        if let Some(reason) = parser::parse_error(raw, format) {
+            writeln!(out, "  error:     {reason}")?;
+        }
+        if settings.rules.ignore.is_match(raw) {
+            writeln!(out, "  (hidden by the profile's ignore patterns)")?;
+        }
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Make keyboard input available after piped stdin has been read. On unix
+/// stdin is pointed at /dev/tty, where crossterm reads events from.
+fn attach_tty() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+        let tty = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")?;
+        unsafe { libc::dup2(tty.as_raw_fd(), libc::STDIN_FILENO) };
+        std::mem::forget(tty);
+    }
+    // crossterm reads the console through CONIN$ rather than stdin, so a
+    // piped stdin is fine as long as there is a console to open
+    #[cfg(windows)]
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("CONIN$")?;
+    Ok(())
+}
+
+/// Fallback when there's no terminal (e.g. a container without /dev/tty):
+/// print the highlighted lines, then keep printing new ones in follow mode.
+fn print_lines(app: &mut App, follow_source: &mut Option<FollowSource>) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let color = std::env::var_os("NO_COLOR").is_none();
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    let mut print_from = |app: &App, start: usize| -> std::io::Result<()> {
+        for parsed in app.filtered_lines_since(start) {
+            let line = highlighter::highlight_line(parsed);
+            if color {
+                writeln!(out, "{}", ansi::line_to_ansi(&line))?;
+            } else {
+                writeln!(out, "{line}")?;
+            }
+        }
+        out.flush()
+    };
+    let result = (|| -> anyhow::Result<()> {
+        print_from(app, 0)?;
+        let Some(source) = follow_source.as_mut() else {
+            return Ok(());
+        };
+        loop {
+            let new_lines = match source {
+                FollowSource::File(s) => s.read_new_lines()?,
+                FollowSource::Stdin(s) if s.is_closed() => return Ok(()),
+                FollowSource::Stdin(s) => s.read_new_lines(),
+            };
+            if new_lines.is_empty() {
+                std::thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+            let first_new = app.total_lines_unfiltered();
+            app.append_lines(new_lines);
+            print_from(app, first_new)?;
+        }
+    })();
+    match result {
+        // Piped into `head` and the like
+        Err(e)
+            if e.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        other => other,
+    }
+}
+
+/// Where the lines read are counted: the `--metrics` page and the
+/// `--export-metrics` pusher.
+struct LineCounters {
+    metrics: Option<std::sync::Arc<metrics::Metrics>>,
+    exporter: Option<std::sync::Arc<exporter::Exporter>>,
+}
+
+impl LineCounters {
+    /// Count the lines from original index `start` on.
+    fn observe(&self, app: &App, start: usize) {
+        let lines = start..app.total_lines_unfiltered();
+        if let Some(ref metrics) = self.metrics {
+            metrics.observe(lines.clone().filter_map(|i| app.parsed_line(i)));
+        }
+        if let Some(ref exporter) = self.exporter {
+            exporter.observe(lines.filter_map(|i| {
+                let line = app.parsed_line(i)?;
+                Some((line, app.marker_at(i).map(|rule| rule.label.as_str())))
+            }));
+        }
+    }
+}
+
+/// Where the viewer takes its input from, besides the terminal.
+struct Inputs {
+    /// Commands from `lumolog --ctl`.
+    control: Option<control::Listener>,
+    /// Events played back instead of the terminal's, for `--replay-events`.
+    replay: Option<replay::Player>,
+    /// Where `--record` writes the events handled.
+    recorder: Option<replay::Recorder>,
+    /// Quit and reload requests from outside.
+    signals: signals::Signals,
+    /// Drawing off-screen: nothing may be written to stdout.
+    headless: bool,
+}
+
+fn run_event_loop<B: ratatui::backend::Backend>(
+    terminal: &mut ratatui::Terminal<B>,
     app: &mut App,
     follow_source: &mut Option<FollowSource>,
     ai_config: Option<ai::AiConfig>,
-) -> anyhow::Result<()> {
+    counters: &LineCounters,
+    mut inputs: Inputs,
+    cli: &Cli,
+) -> anyhow::Result<()>
+where
+    B::Error: Send + Sync + 'static,
+{
+    if let Some(recorder) = inputs.recorder.as_mut() {
+        let size = terminal.size()?;
+        recorder
+            .start(&replay::Header {
+                file: cli.file.clone(),
+                width: size.width,
+                height: size.height,
+            })
+            .map_err(|e| anyhow::anyhow!("cannot start recording: {e}"))?;
+    }
     // Channel for receiving AI query results from background thread
     let (ai_tx, ai_rx) = mpsc::channel::<AiResult>();
     let ai_config = ai_config.map(std::sync::Arc::new);
 
     app.set_ai_connected(ai_config.is_some());
+    let macros_path = macros::default_path().filter(|_| !inputs.headless);
+    let macros = match &macros_path {
+        Some(path) => MacroRecorder::load(path).unwrap_or_else(|e| {
+            app.set_job_error(e.to_string());
+            MacroRecorder::default()
+        }),
+        None => MacroRecorder::default(),
+    };
+    let mut ctx = KeyContext {
+        ai_config,
+        ai_tx,
+        worker: Worker::spawn(),
+        pending_count: PendingCount::default(),
+        macros,
+        macros_path,
+        macro_prompt: None,
+        replay_depth: 0,
+        replayed_keys: 0,
+    };
+    // Only re-sent when they change
+    let mut shown_title = String::new();
+    let mut bar_cursor = false;
     loop {
         terminal.draw(|frame| ui::render(frame, app))?;
 
+        let title = app.terminal_title();
+        if title != shown_title && !inputs.headless {
+            execute!(std::io::stdout(), SetTitle(&title))?;
+            shown_title = title;
+        }
+        // The cursor itself is only shown where the filter bar places it
+        if app.is_filter_mode() != bar_cursor && !inputs.headless {
+            bar_cursor = app.is_filter_mode();
+            let style = if bar_cursor {
+                SetCursorStyle::SteadyBar
+            } else {
+                SetCursorStyle::DefaultUserShape
+            };
+            execute!(std::io::stdout(), style)?;
+        }
+
         let terminal_area: ratatui::layout::Rect = terminal.size()?.into();
 
-        if event::poll(Duration::from_millis(50))? {
-            match event::read()? {
+        let next = match inputs.replay.as_mut() {
+            Some(player) => player.next(Duration::from_millis(50)),
+            None if event::poll(Duration::from_millis(50))? => Some(event::read()?),
+            None => None,
+        };
+        if let Some(event) = next {
+            if let Some(recorder) = inputs.recorder.as_mut() {
+                recorder.record(&event)?;
+            }
+            match event {
                 Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    if app.analyze_response().is_some() {
-                        match key.code {
-                            KeyCode::Esc | KeyCode::Char('q') => app.clear_analyze_response(),
-                            KeyCode::Down | KeyCode::Char('j') => app.analyze_scroll_down(1),
-                            KeyCode::Up | KeyCode::Char('k') => app.analyze_scroll_up(1),
-                            KeyCode::PageDown | KeyCode::Char(' ') => app.analyze_scroll_down(10),
-                            KeyCode::PageUp => app.analyze_scroll_up(10),
-                            _ => {}
-                        }
-                    } else if app.mode() == AppMode::CommandPalette {
-                        match key.code {
-                            KeyCode::Esc => app.close_palette(),
-                            KeyCode::Up => app.palette_up(),
-                            KeyCode::Down => app.palette_down(),
-                            KeyCode::Enter => {
-                                if let Some(action) = app.palette_execute() {
-                                    dispatch_action(action, app);
-                                }
-                            }
-                            KeyCode::Backspace => app.palette_backspace(),
-                            KeyCode::Char(c) => app.palette_type(c),
-                            _ => {}
-                        }
-                    } else if app.mode() == AppMode::TimeRange {
-                        match key.code {
-                            KeyCode::Left | KeyCode::Char('h') => app.time_cursor_left(1),
-                            KeyCode::Right | KeyCode::Char('l') => app.time_cursor_right(1),
-                            KeyCode::Char('H') => app.time_cursor_left(5),
-                            KeyCode::Char('L') => app.time_cursor_right(5),
-                            KeyCode::Char('[') => app.time_mark_start(),
-                            KeyCode::Char(']') | KeyCode::Enter => app.time_mark_end_and_apply(),
-                            KeyCode::Char('1') => app.time_preset(5),
-                            KeyCode::Char('2') => app.time_preset(15),
-                            KeyCode::Char('3') => app.time_preset(60),
-                            KeyCode::Char('4') => app.time_preset(1440),
-                            KeyCode::Char('Y') => {
-                                dispatch_action(command::Action::YankAllFiltered, app)
-                            }
-                            KeyCode::Char('c') => {
-                                app.clear_time_range();
-                                app.exit_time_mode();
-                            }
-                            KeyCode::Esc => app.exit_time_mode(),
-                            KeyCode::Char('q') => app.quit(),
-                            _ => {}
-                        }
-                    } else if app.mode() == AppMode::ContextMenu {
-                        match key.code {
-                            KeyCode::Up | KeyCode::Char('k') => app.menu_up(),
-                            KeyCode::Down | KeyCode::Char('j') => app.menu_down(),
-                            KeyCode::Enter => {
-                                if let Some((action, value)) = app.execute_menu_action() {
-                                    execute_action(action, value, app);
-                                }
-                            }
-                            KeyCode::Esc => app.close_context_menu(),
-                            _ => app.close_context_menu(),
-                        }
-                    } else if app.mode() == AppMode::Ask {
-                        match key.code {
-                            KeyCode::Esc => app.exit_ask_mode(),
-                            KeyCode::Backspace => app.ask_backspace(),
-                            KeyCode::Char(c) => app.ask_type(c),
-                            KeyCode::Enter => {
-                                let query = app.ask_input().to_string();
-                                if !query.is_empty()
-                                    && let Some(ref config) = ai_config
-                                {
-                                    app.set_ai_thinking(true);
-                                    app.exit_ask_mode();
-
-                                    let format_name = match app.format() {
-                                        parser::LogFormat::Json => "JSON",
-                                        parser::LogFormat::Syslog => "Syslog",
-                                        parser::LogFormat::Logfmt => "Logfmt",
-                                        parser::LogFormat::Klog => "Klog",
-                                        parser::LogFormat::Log4j => "Log4j",
-                                        parser::LogFormat::PythonLog => "Python",
-                                        parser::LogFormat::AccessLog => "Access",
-                                        parser::LogFormat::Plain => "Plain",
-                                    };
-
-                                    let field_names: Vec<String> = app
-                                        .visible_parsed_lines_numbered()
-                                        .iter()
-                                        .flat_map(|(_, pl)| {
-                                            pl.extra_fields.iter().map(|(k, _)| k.clone())
-                                        })
-                                        .collect::<std::collections::BTreeSet<_>>()
-                                        .into_iter()
-                                        .collect();
-
-                                    let time_desc = app.time_index().and_then(|idx| {
-                                        let min = idx.min_ts?;
-                                        let max = idx.max_ts?;
-                                        Some(format!("{} to {}", min, max))
-                                    });
-
-                                    let sample = app.sample_lines(30);
-
-                                    let system_prompt = ai::build_system_prompt(
-                                        format_name,
-                                        &field_names,
-                                        time_desc.as_deref(),
-                                        &sample,
-                                    );
-
-                                    let config = config.clone();
-                                    let tx = ai_tx.clone();
-                                    std::thread::spawn(move || {
-                                        let result = ai::query_ai(&config, &system_prompt, &query);
-                                        let _ = tx.send(AiResult::Filter(result));
-                                    });
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else if app.mode() == AppMode::Analyze {
-                        match key.code {
-                            KeyCode::Esc => app.exit_analyze_mode(),
-                            KeyCode::Backspace => app.analyze_backspace(),
-                            KeyCode::Char(c) => app.analyze_type(c),
-                            KeyCode::Enter => {
-                                let question = app.analyze_input().to_string();
-                                if !question.is_empty()
-                                    && let Some(ref config) = ai_config
-                                {
-                                    let log_lines: Vec<String> = app
-                                        .all_filtered_lines_raw()
-                                        .lines()
-                                        .map(|s| s.to_string())
-                                        .collect();
-
-                                    let (system_prompt, user_msg) =
-                                        ai::build_analyze_prompt(&question, &log_lines);
-
-                                    app.set_ai_thinking(true);
-                                    app.exit_analyze_mode();
-
-                                    let config = config.clone();
-                                    let tx = ai_tx.clone();
-                                    std::thread::spawn(move || {
-                                        let result =
-                                            ai::query_ai(&config, &system_prompt, &user_msg);
-                                        let _ = tx.send(AiResult::Analyze(result));
-                                    });
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else if app.is_cursor_mode() {
-                        match key.code {
-                            KeyCode::Down | KeyCode::Char('j') => app.cursor_down(1),
-                            KeyCode::Up | KeyCode::Char('k') => app.cursor_up(1),
-                            KeyCode::Char('y') => dispatch_action(command::Action::YankLine, app),
-                            KeyCode::Char('Y') => {
-                                dispatch_action(command::Action::YankAllFiltered, app)
-                            }
-                            KeyCode::Char('s') => app.filter_by_similar(),
-                            KeyCode::Right | KeyCode::Char('l') => app.scroll_right(1),
-                            KeyCode::Left | KeyCode::Char('h') => app.scroll_left(1),
-                            KeyCode::Esc => app.exit_cursor_mode(),
-                            KeyCode::Char('q') => app.quit(),
-                            KeyCode::Char('?') => app.open_palette(),
-                            _ => {}
-                        }
-                    } else {
-                        match key.code {
-                            _ if app.is_filter_mode() => match key.code {
-                                KeyCode::Esc => {
-                                    if app.filter_pattern().is_empty() {
-                                        app.exit_filter_mode();
-                                    } else {
-                                        app.clear_filter();
-                                    }
-                                }
-                                KeyCode::Enter => app.exit_filter_mode(),
-                                KeyCode::Backspace => app.filter_backspace(),
-                                KeyCode::Char(c) => app.filter_input(c),
-                                _ => {}
-                            },
-                            KeyCode::Char('q') => app.quit(),
-                            KeyCode::Esc => {
-                                if app.is_similar_filter() {
-                                    app.clear_similar();
-                                } else if app.time_range().is_some() {
-                                    app.clear_time_range();
-                                } else if !app.filter_pattern().is_empty() {
-                                    app.clear_filter();
-                                }
-                                // No active filters: Esc does nothing. Use 'q' to quit.
-                            }
-                            KeyCode::Down | KeyCode::Char('j') => app.scroll_down(1),
-                            KeyCode::Up | KeyCode::Char('k') => app.scroll_up(1),
-                            KeyCode::Right | KeyCode::Char('l') => app.scroll_right(1),
-                            KeyCode::Left | KeyCode::Char('h') => app.scroll_left(1),
-                            KeyCode::Char(' ') if app.is_follow_mode() => app.toggle_follow_pause(),
-                            KeyCode::PageDown | KeyCode::Char(' ') => app.page_down(),
-                            KeyCode::PageUp => app.page_up(),
-                            KeyCode::Char('g') => app.scroll_to_top(),
-                            KeyCode::Char('G') => app.scroll_to_bottom(),
-                            KeyCode::Char('/') => app.enter_filter_mode(),
-                            KeyCode::Char('p') => app.toggle_pretty(),
-                            KeyCode::Char('w') => app.toggle_wrap(),
-                            KeyCode::Char('v') => app.cycle_level_up(),
-                            KeyCode::Char('V') => app.cycle_level_down(),
-                            KeyCode::Char('Y') => {
-                                dispatch_action(command::Action::YankAllFiltered, app)
-                            }
-                            KeyCode::Char('t') => app.enter_time_mode(),
-                            KeyCode::Char('a') if app.is_ai_connected() => {
-                                app.enter_ask_mode();
-                            }
-                            KeyCode::Char('A') if app.is_ai_connected() => {
-                                app.enter_analyze_mode();
-                            }
-                            KeyCode::Char('?') => app.open_palette(),
-                            KeyCode::Enter => app.enter_cursor_mode(),
-                            _ => {}
-                        }
+                    // Keys that start or stop a recording are not part of it
+                    let was_recording = ctx.macros.recording().is_some();
+                    handle_key(key, app, &mut ctx);
+                    if was_recording && ctx.macros.recording().is_some() {
+                        ctx.macros.record(key);
                     }
                 }
+                // Otherwise a paste arrives as keystrokes: a trailing newline
+                // would be Enter and the rest would run as normal-mode keys
+                Event::Paste(text) => app.paste(&text),
                 Event::Mouse(mouse) => {
                     // Check sparkline clicks first
                     if let Some(bucket) = ui::sparkline_bucket_at_position(
@@ -580,10 +2049,29 @@ fn run_event_loop(
                         && mouse.kind == MouseEventKind::Down(MouseButton::Left)
                     {
                         app.set_min_level(level);
+                    } else if let Some(n) =
+                        ui::breadcrumb_at_position(app, mouse.column, mouse.row, terminal_area)
+                        && mouse.kind == MouseEventKind::Down(MouseButton::Left)
+                    {
+                        app.remove_breadcrumb(n);
                     } else {
                         match mouse.kind {
                             MouseEventKind::Down(MouseButton::Left) => {
-                                if app.mode() == AppMode::ContextMenu {
+                                if app.decoded().is_some() {
+                                    app.close_decoded();
+                                } else if app.hex_dump_line().is_some() {
+                                    app.close_hex_dump();
+                                } else if app.value_distribution().is_some() {
+                                    match ui::value_dist_item_at_position(
+                                        app,
+                                        mouse.column,
+                                        mouse.row,
+                                        terminal_area,
+                                    ) {
+                                        Some(index) => app.apply_value_distribution(Some(index)),
+                                        None => app.close_value_distribution(),
+                                    }
+                                } else if app.mode() == AppMode::ContextMenu {
                                     if let Some(index) = ui::menu_item_at_position(
                                         app,
                                         mouse.column,
@@ -591,59 +2079,64 @@ fn run_event_loop(
                                         terminal_area,
                                     ) && let Some((action, value)) = app.execute_menu_item(index)
                                     {
-                                        execute_action(action, value, app);
+                                        execute_action(action, value, app, &ctx.worker);
                                     } else {
                                         app.close_context_menu();
-                                        if let Some((kind, value)) = ui::token_at_position(
+                                        if let Some((kind, value, line)) = ui::token_at_position(
                                             app,
                                             mouse.column,
                                             mouse.row,
                                             terminal_area,
                                         ) {
-                                            app.open_context_menu(
+                                            app.open_line_context_menu(
                                                 value,
                                                 kind,
                                                 (mouse.column, mouse.row),
+                                                line,
                                             );
                                         }
                                     }
                                 } else if app.mode() != AppMode::Cursor
-                                    && let Some((kind, value)) = ui::token_at_position(
+                                    && let Some((kind, value, line)) = ui::token_at_position(
                                         app,
                                         mouse.column,
                                         mouse.row,
                                         terminal_area,
                                     )
                                 {
-                                    app.open_context_menu(value, kind, (mouse.column, mouse.row));
+                                    app.open_line_context_menu(
+                                        value,
+                                        kind,
+                                        (mouse.column, mouse.row),
+                                        line,
+                                    );
                                 }
                             }
                             MouseEventKind::Drag(MouseButton::Left) => {
                                 // If we're in time mode with active drag, extend to wherever the mouse is
                                 if app.mode() == AppMode::TimeRange
-                                    && let Some(sparkline) = app.sparkline_data()
+                                    && let Some(bucket) = ui::nearest_sparkline_bucket(
+                                        app,
+                                        mouse.column,
+                                        terminal_area,
+                                    )
                                 {
-                                    // Try to map to nearest bucket even outside sparkline area
-                                    let col = mouse.column as usize;
-                                    let bucket = col
-                                        .saturating_sub(1)
-                                        .min(sparkline.num_buckets.saturating_sub(1));
                                     app.time_mouse_drag(bucket);
                                 }
                             }
                             MouseEventKind::Up(MouseButton::Left) => {
                                 if app.mode() == AppMode::TimeRange
-                                    && let Some(sparkline) = app.sparkline_data()
+                                    && let Some(bucket) = ui::nearest_sparkline_bucket(
+                                        app,
+                                        mouse.column,
+                                        terminal_area,
+                                    )
                                 {
-                                    let col = mouse.column as usize;
-                                    let bucket = col
-                                        .saturating_sub(1)
-                                        .min(sparkline.num_buckets.saturating_sub(1));
                                     app.time_mouse_up(bucket);
                                 }
                             }
-                            MouseEventKind::ScrollDown => app.scroll_down(3),
-                            MouseEventKind::ScrollUp => app.scroll_up(3),
+                            MouseEventKind::ScrollDown => app.scroll_down(app.wheel_step()),
+                            MouseEventKind::ScrollUp => app.scroll_up(app.wheel_step()),
                             MouseEventKind::ScrollLeft => app.scroll_left(3),
                             MouseEventKind::ScrollRight => app.scroll_right(3),
                             _ => {}
@@ -663,7 +2156,31 @@ fn run_event_loop(
                 FollowSource::Stdin(s) => s.read_new_lines(),
             };
             if !new_lines.is_empty() {
+                let first_new = app.total_lines_unfiltered();
                 app.append_lines(new_lines);
+                counters.observe(app, first_new);
+            }
+            if let Some(ref metrics) = counters.metrics
+                && let FollowSource::Stdin(s) = source
+            {
+                metrics.set_dropped(s.dropped_lines());
+            }
+        }
+
+        // Commands from `lumolog --ctl`
+        while let Some(cmd) = inputs.control.as_ref().and_then(|c| c.try_recv()) {
+            apply_control(cmd, app);
+        }
+
+        // Poll for finished clipboard / browser jobs
+        while let Some(outcome) = ctx.worker.try_recv() {
+            match outcome {
+                JobOutcome::Copied => {
+                    app.finish_background_job(None);
+                    app.set_yank_flash();
+                }
+                JobOutcome::Opened => app.finish_background_job(None),
+                JobOutcome::Failed(e) => app.finish_background_job(Some(e)),
             }
         }
 
@@ -693,10 +2210,270 @@ fn run_event_loop(
             }
         }
 
-        if app.should_quit() {
+        if inputs.signals.take_reload() {
+            reload(terminal, &inputs, cli, app, follow_source);
+        }
+        if app.take_rotated_request() {
+            load_rotated(terminal, &inputs, cli, app, follow_source);
+        }
+
+        if inputs.replay.as_ref().is_some_and(|p| p.is_finished()) {
+            inputs.replay = None;
+            if inputs.headless {
+                terminal.draw(|frame| ui::render(frame, app))?;
+                break;
+            }
+            app.set_notice("Replay finished: over to you".to_string());
+        }
+
+        if app.should_quit() || inputs.signals.take_quit() {
             break;
         }
     }
 
     Ok(())
 }
+
+/// What the config file sets up, compiled, so SIGHUP can re-read it.
+struct Settings {
+    config: config::Config,
+    profile: Option<config::Profile>,
+    format: Option<parser::LogFormat>,
+    /// The profile's rules with the `--rules` bundle's added.
+    rules: bundle::Rules,
+    marker_rules: Vec<markers::MarkerRule>,
+    derived_fields: Vec<derived::DerivedField>,
+}
+
+impl Settings {
+    /// Load the config and the profile for `cli`. Errors are ready to print.
+    fn load(cli: &Cli) -> Result<Self, String> {
+        let config =
+            config::Config::load(cli.config.as_deref()).map_err(|e| format!("Error: {e}"))?;
+        let profile = config
+            .select_profile(cli.profile.as_deref(), cli.file.as_deref())
+            .map_err(|e| format!("Error: {e}"))?;
+        let (format, mut rules) = match profile {
+            Some((name, p)) => p
+                .log_format()
+                .and_then(|format| {
+                    p.ignore_set()?;
+                    p.highlight_rules()?;
+                    Ok((format, bundle::RuleBundle::from_profile(p)))
+                })
+                .map_err(|e| format!("Error in profile '{name}': {e}"))?,
+            None => (None, bundle::RuleBundle::default()),
+        };
+        if let Some(ref path) = cli.rules {
+            let extra = bundle::RuleBundle::load(path).map_err(|e| format!("Error: {e}"))?;
+            rules.merge(&extra);
+            rules.filters = extra.filters;
+        }
+        let rules = rules.compile().map_err(|e| format!("Error: {e}"))?;
+        let marker_rules = config
+            .marker_rules()
+            .map_err(|e| format!("Error in config: {e}"))?;
+        let derived_fields = config
+            .derived_fields()
+            .map_err(|e| format!("Error in config: {e}"))?;
+        Ok(Self {
+            profile: profile.map(|(_, p)| p.clone()),
+            config,
+            format,
+            rules,
+            marker_rules,
+            derived_fields,
+        })
+    }
+
+    fn apply(self, app: &mut App) {
+        let config = self.config;
+        app.set_scroll_config(config.scroll);
+        app.set_esc_action(config.keys.esc);
+        app.set_confirm_quit(config.keys.confirm_quit);
+        app.set_show_whitespace(config.display.whitespace);
+        app.set_high_contrast(config.display.high_contrast);
+        app.set_text_cues(config.display.text_cues);
+        app.set_level_badge(config.display.level_badge);
+        app.set_join_lines(config.display.join_lines);
+        app.set_blank_lines(config.display.blank_lines);
+        app.set_location_template(config.yank.location);
+        app.set_pane_layout(config.layout);
+        app.set_include_inherited_times(!config.display.own_times_only);
+        app.set_fuzzy_config(config.fuzzy, config.palette);
+        app.set_derived_fields(self.derived_fields);
+        app.set_marker_rules(self.marker_rules);
+        app.set_rules(self.rules);
+        if split::Multiplexer::detect().is_some() {
+            // Profile commands first, then the global ones
+            let mut splits = self.profile.map(|p| p.split).unwrap_or_default();
+            splits.extend(config.split);
+            app.set_split_commands(splits);
+        }
+    }
+}
+
+/// SIGHUP: re-read the config and, when viewing a regular file, the file
+/// itself (e.g. after logrotate). Filters carry over; a config error leaves
+/// the old settings in place.
+fn reload<B: ratatui::backend::Backend>(
+    terminal: &mut ratatui::Terminal<B>,
+    inputs: &Inputs,
+    cli: &Cli,
+    app: &mut App,
+    follow_source: &mut Option<FollowSource>,
+) where
+    B::Error: Send + Sync + 'static,
+{
+    let settings = match Settings::load(cli) {
+        Ok(settings) => settings,
+        Err(e) => {
+            app.set_notice(format!("Reload failed: {e}"));
+            return;
+        }
+    };
+    let Some(path) = cli.file.as_deref().filter(|p| !source::is_stream(p)) else {
+        let layout = app.pane_layout();
+        settings.apply(app);
+        app.set_pane_layout(layout);
+        app.set_notice("Reloaded config".to_string());
+        return;
+    };
+    // Rotation has moved the older files along too, so look for them afresh
+    let with_rotated = app.rotated_files() > 0;
+    match reopen(
+        terminal,
+        inputs,
+        path,
+        with_rotated,
+        settings,
+        app,
+        follow_source,
+    ) {
+        Ok(Some((mut fresh, _))) => {
+            fresh.set_notice(format!("Reloaded config and {}", fresh.source_name()));
+            *app = fresh;
+        }
+        Ok(None) => app.set_notice("Reload cancelled".to_string()),
+        Err(e) => app.set_notice(format!("Reload failed: {e}")),
+    }
+}
+
+/// `R`: read the files logrotate left beside the one being viewed in ahead
+/// of it, keeping notes and pins on their lines.
+fn load_rotated<B: ratatui::backend::Backend>(
+    terminal: &mut ratatui::Terminal<B>,
+    inputs: &Inputs,
+    cli: &Cli,
+    app: &mut App,
+    follow_source: &mut Option<FollowSource>,
+) where
+    B::Error: Send + Sync + 'static,
+{
+    let Some(path) = cli.file.as_deref().filter(|p| !source::is_stream(p)) else {
+        app.set_notice("Rotated files are only looked for beside a log file".to_string());
+        return;
+    };
+    if app.rotated_files() > 0 {
+        app.set_notice("Rotated files are already loaded".to_string());
+        return;
+    }
+    if source::rotated_siblings(path).is_empty() {
+        app.set_notice(format!("No rotated files beside {}", display_name(path)));
+        return;
+    }
+    let settings = match Settings::load(cli) {
+        Ok(settings) => settings,
+        Err(e) => {
+            app.set_notice(format!("Loading rotated files failed: {e}"));
+            return;
+        }
+    };
+    match reopen(terminal, inputs, path, true, settings, app, follow_source) {
+        Ok(Some((mut fresh, offset))) => {
+            fresh.carry_line_marks(app, offset);
+            fresh.set_notice(format!(
+                "Read {} rotated {} ahead of {}",
+                fresh.rotated_files(),
+                if fresh.rotated_files() == 1 {
+                    "file"
+                } else {
+                    "files"
+                },
+                display_name(path)
+            ));
+            *app = fresh;
+        }
+        Ok(None) => app.set_notice("Loading rotated files cancelled".to_string()),
+        Err(e) => app.set_notice(format!("Loading rotated files failed: {e}")),
+    }
+}
+
+/// Read `path` again, after its rotated siblings when `with_rotated`, into a
+/// new viewer with `app`'s filters and layout. The files are read in the
+/// background behind the loading screen; None when the user backs out.
+/// Also returns how many lines came from the rotated files.
+fn reopen<B: ratatui::backend::Backend>(
+    terminal: &mut ratatui::Terminal<B>,
+    inputs: &Inputs,
+    path: &std::path::Path,
+    with_rotated: bool,
+    settings: Settings,
+    app: &App,
+    follow_source: &mut Option<FollowSource>,
+) -> anyhow::Result<Option<(App, usize)>>
+where
+    B::Error: Send + Sync + 'static,
+{
+    let rotated = if with_rotated {
+        source::rotated_siblings(path)
+    } else {
+        Vec::new()
+    };
+    let rotated_count = rotated.len();
+    let following = matches!(follow_source, Some(FollowSource::File(_)));
+    let load_path = path.to_path_buf();
+    // Compressed rotations make the total unknown up front
+    let loading = loader::spawn(0, move |progress| {
+        let mut lines = Vec::new();
+        for file in &rotated {
+            let (source, _) = FileSource::open_with_progress(file, false, progress)
+                .map_err(|e| anyhow::anyhow!("{}: {e}", display_name(file)))?;
+            lines.extend(source.into_lines());
+        }
+        let rotated_lines = lines.len();
+        let (source, offset) = FileSource::open_with_progress(&load_path, following, progress)?;
+        lines.extend(source.into_lines());
+        Ok((lines, rotated_lines, offset))
+    });
+    let loaded = if inputs.headless {
+        // Nothing to draw on or read keys from: just wait
+        loading.finish().map(Some)
+    } else {
+        wait_for_load(terminal, &inputs.signals, &display_name(path), loading)
+    };
+    let Some((lines, rotated_lines, offset)) = loaded? else {
+        return Ok(None);
+    };
+    if following {
+        *follow_source = Some(FollowSource::File(FollowableSource::new(path, offset)));
+    }
+
+    let state = app.view_state();
+    let mut fresh = App::with_format(lines, settings.format);
+    fresh.scroll_to_bottom();
+    fresh.set_follow_mode(app.is_follow_mode());
+    fresh.set_ai_connected(app.is_ai_connected());
+    let name = display_name(path);
+    fresh.set_source_name(match rotated_count {
+        0 => name,
+        n => format!("{name} +{n} rotated"),
+    });
+    fresh.set_rotated_files(rotated_count);
+    if let Some(ref file) = state.file {
+        fresh.set_source_path(file.clone());
+    }
+    settings.apply(&mut fresh);
+    fresh.apply_view_state(&state);
+    Ok(Some((fresh, rotated_lines)))
+}
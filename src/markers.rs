@@ -0,0 +1,37 @@
+use crate::parser::ParsedLine;
+use ratatui::style::Color;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Flags matching lines as named events: shown in the gutter and as vertical
+/// markers on the sparkline.
+#[derive(Debug, Clone)]
+pub struct MarkerRule {
+    pub label: String,
+    pub pattern: Regex,
+    pub color: Color,
+}
+
+/// Common deploy / restart announcements: app frameworks ("Started
+/// Application in 3.2 seconds", "Listening on :8080"), systemd unit
+/// start/stop lines, and shutdown signals.
+static RESTART_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\bstarted application\b|\bapplication (?:started|startup complete)\b|\blistening on\b|\bserver (?:started|listening)\b|\bstarted \S+ in [0-9.]+ ?s(?:econds)?\b|systemd\[\d+\]: (?:started|stopped|starting|stopping) |\bshutting down\b|\breceived sig(?:term|int)\b",
+    )
+    .unwrap()
+});
+
+/// Rules that are always on.
+pub fn builtin_rules() -> Vec<MarkerRule> {
+    vec![MarkerRule {
+        label: "restart".to_string(),
+        pattern: RESTART_RE.clone(),
+        color: Color::Cyan,
+    }]
+}
+
+/// Index of the first rule whose pattern matches the line.
+pub fn match_rule(rules: &[MarkerRule], line: &ParsedLine) -> Option<usize> {
+    rules.iter().position(|r| r.pattern.is_match(&line.raw))
+}
@@ -0,0 +1,123 @@
+use crate::parser::{LogLevel, ParsedLine};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Counters for a long-running follow session, served in the Prometheus text
+/// format by [`serve`].
+#[derive(Debug, Default)]
+pub struct Metrics {
+    lines_ingested: AtomicU64,
+    error_lines: AtomicU64,
+    dropped_lines: AtomicU64,
+}
+
+impl Metrics {
+    /// Count newly read lines.
+    pub fn observe<'a>(&self, lines: impl IntoIterator<Item = &'a ParsedLine>) {
+        let (mut total, mut errors) = (0, 0);
+        for line in lines {
+            total += 1;
+            if line.level.is_some_and(|l| l >= LogLevel::Error) {
+                errors += 1;
+            }
+        }
+        self.lines_ingested.fetch_add(total, Ordering::Relaxed);
+        self.error_lines.fetch_add(errors, Ordering::Relaxed);
+    }
+
+    /// Lines the source had to skip so far (it keeps the running total).
+    pub fn set_dropped(&self, dropped: u64) {
+        self.dropped_lines.store(dropped, Ordering::Relaxed);
+    }
+
+    /// The metrics page.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+            out.push_str(&format!(
+                "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+            ));
+        };
+        metric(
+            "lumolog_lines_ingested_total",
+            "counter",
+            "Log lines read since start.",
+            self.lines_ingested.load(Ordering::Relaxed),
+        );
+        metric(
+            "lumolog_error_lines_total",
+            "counter",
+            "Lines at ERROR level or above.",
+            self.error_lines.load(Ordering::Relaxed),
+        );
+        metric(
+            "lumolog_dropped_lines_total",
+            "counter",
+            "Lines skipped because they could not be read (invalid UTF-8).",
+            self.dropped_lines.load(Ordering::Relaxed),
+        );
+        if let Some(rss) = resident_memory_bytes() {
+            metric(
+                "lumolog_resident_memory_bytes",
+                "gauge",
+                "Resident memory of the lumolog process.",
+                rss,
+            );
+        }
+        out
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn resident_memory_bytes() -> Option<u64> {
+    // statm: size resident shared ... in pages
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(pages * u64::try_from(page_size).ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory_bytes() -> Option<u64> {
+    None
+}
+
+/// How long a scrape may take to send its request or read the response.
+/// Clients are served one at a time, so this bounds how long a stalled one
+/// holds up the rest.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Serve `GET /metrics` on `addr` from a background thread. Returns the bound
+/// address (useful with port 0).
+pub fn serve(addr: &str, metrics: Arc<Metrics>) -> std::io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // A client that errors or times out only loses its own response
+            let _ = respond(stream, &metrics);
+        }
+    });
+    Ok(local)
+}
+
+fn respond(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", metrics.render())
+    } else {
+        ("404 Not Found", "not found\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
@@ -0,0 +1,94 @@
+use crate::parser::ParsedLine;
+use std::collections::HashMap;
+
+/// How many templates "Collapse noise" offers to hide at first.
+pub const DEFAULT_HIDE: usize = 5;
+
+/// Most templates the preview lists.
+pub const MAX_HIDE: usize = 20;
+
+/// Lines sharing one structural template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateCount {
+    pub template: String,
+    pub count: usize,
+    /// Original index of the first line with the template, shown as an example.
+    pub first_line: usize,
+}
+
+/// Templates of `indices` covering more than one line, most lines first and
+/// ties in order of first appearance. Lines without a template are left out.
+pub fn template_counts(lines: &[ParsedLine], indices: &[usize]) -> Vec<TemplateCount> {
+    let mut by_template: HashMap<&str, (usize, usize)> = HashMap::new();
+    for &idx in indices {
+        let template = lines[idx].template.as_str();
+        if template.is_empty() {
+            continue;
+        }
+        by_template.entry(template).or_insert((0, idx)).0 += 1;
+    }
+    let mut counts: Vec<TemplateCount> = by_template
+        .into_iter()
+        .filter(|&(_, (count, _))| count > 1)
+        .map(|(template, (count, first_line))| TemplateCount {
+            template: template.to_string(),
+            count,
+            first_line,
+        })
+        .collect();
+    counts.sort_by_key(|t| (std::cmp::Reverse(t.count), t.first_line));
+    counts
+}
+
+/// What hiding the noisiest templates would take out of the view, before
+/// it's done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoisePreview {
+    /// The noisiest templates, at most [`MAX_HIDE`].
+    pub templates: Vec<TemplateCount>,
+    /// Lines in the view the templates were counted over.
+    pub lines: usize,
+    /// How many of `templates` would be hidden.
+    pub hide: usize,
+}
+
+impl NoisePreview {
+    /// Preview for the lines at `indices`, or None when no template repeats.
+    pub fn new(lines: &[ParsedLine], indices: &[usize]) -> Option<Self> {
+        let mut templates = template_counts(lines, indices);
+        if templates.is_empty() {
+            return None;
+        }
+        templates.truncate(MAX_HIDE);
+        let mut preview = Self {
+            hide: DEFAULT_HIDE.min(templates.len()),
+            templates,
+            lines: indices.len(),
+        };
+        // Don't suggest hiding everything when there are only a few shapes
+        while preview.hide > 1 && preview.hidden_lines() == preview.lines {
+            preview.hide -= 1;
+        }
+        Some(preview)
+    }
+
+    /// The templates that would be hidden.
+    pub fn hidden(&self) -> &[TemplateCount] {
+        &self.templates[..self.hide]
+    }
+
+    /// Lines that would disappear.
+    pub fn hidden_lines(&self) -> usize {
+        self.hidden().iter().map(|t| t.count).sum()
+    }
+
+    /// Hide one more template, up to all of those listed.
+    pub fn more(&mut self) {
+        self.hide = (self.hide + 1).min(self.templates.len());
+    }
+
+    /// Hide one fewer template, keeping at least one.
+    pub fn fewer(&mut self) {
+        self.hide = self.hide.saturating_sub(1).max(1);
+    }
+}
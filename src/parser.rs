@@ -1,4 +1,5 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::sync::LazyLock;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -10,10 +11,18 @@ pub enum LogFormat {
     Log4j,
     PythonLog,
     AccessLog,
+    /// Human-oriented console encoders: logrus text, zap console, pino-pretty
+    /// and zerolog's ConsoleWriter, possibly with ANSI colors.
+    Console,
+    /// Kubernetes container runtime (CRI) logs: each line carries a
+    /// timestamp, stream and tag in front of the container's own output,
+    /// which can be in any of the other formats.
+    Cri,
     Plain,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -23,7 +32,67 @@ pub enum LogLevel {
     Fatal,
 }
 
+impl LogFormat {
+    /// Parse a format name as written in config (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(LogFormat::Json),
+            "syslog" => Some(LogFormat::Syslog),
+            "logfmt" => Some(LogFormat::Logfmt),
+            "klog" => Some(LogFormat::Klog),
+            "log4j" => Some(LogFormat::Log4j),
+            "python" | "pythonlog" => Some(LogFormat::PythonLog),
+            "access" | "accesslog" => Some(LogFormat::AccessLog),
+            "console" => Some(LogFormat::Console),
+            "cri" => Some(LogFormat::Cri),
+            "plain" => Some(LogFormat::Plain),
+            _ => None,
+        }
+    }
+
+    /// The name [`LogFormat::from_name`] accepts for this format.
+    pub fn name(self) -> &'static str {
+        match self {
+            LogFormat::Json => "json",
+            LogFormat::Syslog => "syslog",
+            LogFormat::Logfmt => "logfmt",
+            LogFormat::Klog => "klog",
+            LogFormat::Log4j => "log4j",
+            LogFormat::PythonLog => "python",
+            LogFormat::AccessLog => "access",
+            LogFormat::Console => "console",
+            LogFormat::Cri => "cri",
+            LogFormat::Plain => "plain",
+        }
+    }
+}
+
 impl LogLevel {
+    /// Parse a level name ("error", "WARN", "warning", ...), case-insensitive.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "TRACE" => Some(LogLevel::Trace),
+            "DEBUG" => Some(LogLevel::Debug),
+            "INFO" => Some(LogLevel::Info),
+            "WARN" | "WARNING" => Some(LogLevel::Warn),
+            "ERROR" => Some(LogLevel::Error),
+            "FATAL" => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+
+    /// The level written out in capitals: `ERROR`, `WARN`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Fatal => "FATAL",
+        }
+    }
+
     pub fn short_name(self) -> &'static str {
         match self {
             LogLevel::Trace => "TRC",
@@ -44,6 +113,8 @@ pub struct ParsedLine {
     pub message: String,
     pub format: LogFormat,
     pub pretty_json: Option<String>,
+    /// The parsed object for JSON lines, kept for `.path` filters.
+    pub json: Option<serde_json::Value>,
     pub extra_fields: Vec<(String, String)>,
     pub template: String,
 }
@@ -80,17 +151,74 @@ static PYTHON_LOG_RE: LazyLock<Regex> = LazyLock::new(|| {
 });
 
 /// Apache/Nginx Combined Log Format:
-/// `IP - user [timestamp] "METHOD /path HTTP/ver" status bytes "referer" "user-agent"`
+/// `IP - user [timestamp] "METHOD /path HTTP/ver" status bytes "referer" "user-agent"`,
+/// optionally followed by the request time in seconds.
 static ACCESS_LOG_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r#"^(\S+) \S+ (\S+) \[([^\]]+)\] "(\S+) (\S+)[^"]*" (\d{3}) (\d+|-)(?:\s+"([^"]*)" "([^"]*)")?$"#).unwrap()
+    Regex::new(r#"^(\S+) \S+ (\S+) \[([^\]]+)\] "(\S+) (\S+)[^"]*" (\d{3}) (\d+|-)(?:\s+"([^"]*)" "([^"]*)")?(?:\s+(\d+(?:\.\d+)?))?$"#).unwrap()
+});
+
+/// ANSI CSI escape sequences (colors, bold, reset) left in by console encoders.
+static ANSI_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]").unwrap());
+
+/// logrus text formatter on a TTY: `INFO[0023] message    key=value`
+static LOGRUS_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(TRAC|DEBU|INFO|WARN|ERRO|FATA|PANI)\[([^\]]*)\]\s*(.*)$").unwrap()
+});
+
+/// zap console encoder: `2024-01-15T08:30:00.000Z\tINFO\tcaller.go:42\tmessage\t{"k":"v"}`
+static ZAP_CONSOLE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\S+)\t((?i:debug|info|warn|error|dpanic|panic|fatal))\t(.*)$").unwrap()
 });
 
+/// pino-pretty: `[12:03:04.123] INFO (app/1234 on host): message`
+static PINO_PRETTY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\[([^\]]+)\]\s+(TRACE|DEBUG|INFO|WARN|ERROR|FATAL)\s*(?:\(([^)]*)\))?:\s?(.*)$")
+        .unwrap()
+});
+
+/// The `(name/pid on hostname)` group of a pino-pretty line.
+static PINO_PROCESS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:(\S+)/)?(\d+)(?: on (\S+))?$").unwrap());
+
+/// zerolog ConsoleWriter: `12:03:04 INF message key=value` or `3:04PM INF ...`
+static ZEROLOG_CONSOLE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{1,2}:\d{2}(?::\d{2}(?:\.\d+)?)?(?:[AP]M)?|\d{4}-\d{2}-\d{2}T\S+)\s+(TRC|DBG|INF|WRN|ERR|FTL|PNC)\s+(.*)$").unwrap()
+});
+
+/// CRI container log: `2024-01-15T08:30:01.123456789Z stdout F payload`.
+/// The tag is `F` for a full line, `P` for part of one the runtime split.
+static CRI_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(?:\.\d+)?(?:Z|[+-]\d{2}:\d{2})) (stdout|stderr) ([FP])(?: (.*))?$").unwrap()
+});
+
+/// zap's `file.go:42` caller column.
+static CALLER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\S+:\d+$").unwrap());
+
+fn strip_ansi(s: &str) -> std::borrow::Cow<'_, str> {
+    ANSI_RE.replace_all(s, "")
+}
+
+fn is_console_line(line: &str) -> bool {
+    let line = strip_ansi(line);
+    LOGRUS_RE.is_match(&line)
+        || ZAP_CONSOLE_RE.is_match(&line)
+        || PINO_PRETTY_RE.is_match(&line)
+        || ZEROLOG_CONSOLE_RE.is_match(&line)
+}
+
 pub fn detect_format(lines: &[String]) -> LogFormat {
     let sample: Vec<&str> = lines.iter().take(10).map(|s| s.as_str()).collect();
     if sample.is_empty() {
         return LogFormat::Plain;
     }
 
+    // First: the payloads can be logfmt or JSON themselves
+    let cri_count = sample.iter().filter(|line| CRI_RE.is_match(line)).count();
+    if cri_count > sample.len() / 2 {
+        return LogFormat::Cri;
+    }
+
     let json_count = sample
         .iter()
         .filter(|line| {
@@ -110,6 +238,12 @@ pub fn detect_format(lines: &[String]) -> LogFormat {
         return LogFormat::Syslog;
     }
 
+    // Before logfmt: logrus and zerolog append key=value fields to the message
+    let console_count = sample.iter().filter(|line| is_console_line(line)).count();
+    if console_count > sample.len() / 2 {
+        return LogFormat::Console;
+    }
+
     let logfmt_count = sample
         .iter()
         .filter(|line| LOGFMT_LINE_RE.find_iter(line).count() >= 3)
@@ -156,12 +290,63 @@ pub fn parse_line(raw: &str, format: LogFormat) -> ParsedLine {
         LogFormat::Log4j => parse_log4j_line(raw),
         LogFormat::PythonLog => parse_python_log_line(raw),
         LogFormat::AccessLog => parse_access_log_line(raw),
+        LogFormat::Console => parse_console_line(raw),
+        LogFormat::Cri => parse_cri_line(raw),
         LogFormat::Plain => parse_plain_line(raw),
     };
-    parsed.template = compute_template(raw);
+    // Serilog lines arrive with their template already known. Blank lines
+    // are separators, not a shape of line
+    if parsed.template.is_empty() && !is_blank(raw) {
+        parsed.template = compute_template(raw);
+    }
     parsed
 }
 
+/// Why `raw` doesn't fit `format`'s parser, or None when it parses (or the
+/// format has no structure to miss, like plain text). Blank lines are fine.
+/// Reasons leave out positions so the same problem reads the same everywhere.
+pub fn parse_error(raw: &str, format: LogFormat) -> Option<String> {
+    if raw.trim().is_empty() {
+        return None;
+    }
+    let matches = |re: &Regex, what: &str| {
+        (!re.is_match(raw)).then(|| format!("doesn't match the {what} layout"))
+    };
+    match format {
+        LogFormat::Json => match serde_json::from_str::<serde_json::Value>(raw.trim()) {
+            Ok(serde_json::Value::Object(_)) => None,
+            Ok(_) => Some("JSON value is not an object".to_string()),
+            Err(e) => {
+                let text = e.to_string();
+                let reason = text.split(" at line ").next().unwrap_or(&text);
+                Some(format!("invalid JSON: {reason}"))
+            }
+        },
+        LogFormat::Syslog => matches(&SYSLOG_RE, "syslog"),
+        LogFormat::Logfmt => {
+            (!LOGFMT_PAIR_RE.is_match(raw)).then(|| "no key=value pairs".to_string())
+        }
+        LogFormat::Klog => matches(&KLOG_RE, "klog"),
+        LogFormat::Log4j => matches(&LOG4J_RE, "log4j"),
+        LogFormat::PythonLog => matches(&PYTHON_LOG_RE, "Python logging"),
+        LogFormat::AccessLog => matches(&ACCESS_LOG_RE, "access log"),
+        LogFormat::Console => {
+            let clean = strip_ansi(raw);
+            let known = [
+                &LOGRUS_RE,
+                &ZAP_CONSOLE_RE,
+                &PINO_PRETTY_RE,
+                &ZEROLOG_CONSOLE_RE,
+            ]
+            .iter()
+            .any(|re| re.is_match(&clean));
+            (!known).then(|| "no known console layout".to_string())
+        }
+        LogFormat::Cri => matches(&CRI_RE, "CRI"),
+        LogFormat::Plain => None,
+    }
+}
+
 /// Known JSON keys that are already extracted into dedicated ParsedLine fields.
 const KNOWN_JSON_KEYS: &[&str] = &[
     "level",
@@ -175,8 +360,29 @@ const KNOWN_JSON_KEYS: &[&str] = &[
     "msg",
     "log",
     "stream",
+    // Serilog JSON / CLEF / Logstash
+    "Timestamp",
+    "Level",
+    "MessageTemplate",
+    "messageTemplate",
+    "RenderedMessage",
+    "@t",
+    "@l",
+    "@mt",
+    "@m",
 ];
 
+/// Keys holding a Serilog message template (`User {UserId} logged in`).
+const TEMPLATE_KEYS: &[&str] = &["MessageTemplate", "messageTemplate", "@mt"];
+
+/// Keys holding a template's property bag. CLEF puts properties at the top level.
+const PROPERTIES_KEYS: &[&str] = &["Properties", "properties", "fields"];
+
+/// A `{Name}` hole in a message template, with optional `@`/`$` capturing
+/// hint and `,alignment` / `:format` suffix.
+static TEMPLATE_HOLE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{[@$]?(\w+)(?:[,:][^}]*)?\}").unwrap());
+
 /// Matches a single logfmt key=value pair.
 /// Captures: (1) key, (2) quoted value without quotes, or (3) unquoted value.
 static LOGFMT_PAIR_RE: LazyLock<Regex> =
@@ -230,129 +436,224 @@ fn format_json_value(v: &serde_json::Value) -> String {
     }
 }
 
+/// How many levels of JSON-inside-a-string `decode_nested_json` will unwrap.
+const MAX_NESTED_JSON_DEPTH: usize = 8;
+
+/// Recursively replace string values that themselves contain a JSON object or
+/// array (e.g. `"payload":"{\"a\":1}"`) with the decoded value, so pretty mode
+/// shows real structure instead of escaped quotes.
+pub fn decode_nested_json(value: serde_json::Value) -> serde_json::Value {
+    decode_nested_json_at(value, 0)
+}
+
+fn decode_nested_json_at(value: serde_json::Value, depth: usize) -> serde_json::Value {
+    use serde_json::Value;
+    if depth >= MAX_NESTED_JSON_DEPTH {
+        return value;
+    }
+    match value {
+        Value::String(s) => {
+            let trimmed = s.trim();
+            let looks_like_json = (trimmed.starts_with('{') && trimmed.ends_with('}'))
+                || (trimmed.starts_with('[') && trimmed.ends_with(']'));
+            if looks_like_json
+                && let Ok(inner @ (Value::Object(_) | Value::Array(_))) =
+                    serde_json::from_str::<Value>(trimmed)
+            {
+                return decode_nested_json_at(inner, depth + 1);
+            }
+            Value::String(s)
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|v| decode_nested_json_at(v, depth))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (k, decode_nested_json_at(v, depth)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Fill a message template's holes from `props`, leaving unknown holes as-is.
+fn render_template(template: &str, props: &serde_json::Map<String, serde_json::Value>) -> String {
+    TEMPLATE_HOLE_RE
+        .replace_all(template, |caps: &regex::Captures| {
+            match props.get(&caps[1]) {
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(v) => format_json_value(v),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
 fn parse_json_line(raw: &str) -> ParsedLine {
-    let trimmed = raw.trim();
-    match serde_json::from_str::<serde_json::Value>(trimmed) {
-        Ok(value) => {
-            let level = value
-                .get("level")
-                .or_else(|| value.get("severity"))
-                .or_else(|| value.get("log.level"))
-                .and_then(|v| {
-                    v.as_str()
-                        .and_then(parse_level_str)
-                        .or_else(|| v.as_u64().and_then(parse_numeric_level))
-                })
-                .or_else(|| {
-                    // Fallback: scan the message text for a level keyword (e.g. Docker logs
-                    // embed the level inside the "log" value, not as a separate key).
-                    let msg = value
-                        .get("message")
-                        .or_else(|| value.get("msg"))
-                        .or_else(|| value.get("log"))
-                        .and_then(|v| v.as_str())?;
-                    LEVEL_RE.find(msg).and_then(|m| parse_level_str(m.as_str()))
-                });
-
-            let timestamp = value
-                .get("timestamp")
-                .or_else(|| value.get("time"))
-                .or_else(|| value.get("@timestamp"))
-                .or_else(|| value.get("ts"))
-                .and_then(|v| {
-                    // String timestamps: use as-is
-                    if let Some(s) = v.as_str() {
-                        return Some(s.to_string());
-                    }
-                    // Numeric timestamps: epoch millis or secs → ISO string
-                    if let Some(n) = v.as_i64().or_else(|| v.as_f64().map(|f| f as i64)) {
-                        // Epoch millis (1e12..1e14 range)
-                        if (1_000_000_000_000..100_000_000_000_000).contains(&n) {
-                            let secs = n / 1000;
-                            let nsecs = ((n % 1000) * 1_000_000) as u32;
-                            if let Some(dt) = chrono::DateTime::from_timestamp(secs, nsecs) {
-                                return Some(dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string());
-                            }
-                        }
-                        // Epoch secs (1e9..1e10 range)
-                        if (1_000_000_000..10_000_000_000).contains(&n)
-                            && let Some(dt) = chrono::DateTime::from_timestamp(n, 0)
-                        {
-                            return Some(dt.format("%Y-%m-%dT%H:%M:%SZ").to_string());
-                        }
-                    }
-                    None
-                });
+    match serde_json::from_str::<serde_json::Value>(raw.trim()) {
+        Ok(value) if value.is_object() => json_object_line(raw, value),
+        _ => parse_unmatched_line(raw),
+    }
+}
+
+/// A line that doesn't fit the file's format, like a Go panic in a JSON log
+/// or a banner before logfmt, parsed on its own: as JSON or logfmt if it
+/// looks like either, as plain text otherwise.
+fn parse_unmatched_line(raw: &str) -> ParsedLine {
+    if raw.trim_start().starts_with('{')
+        && let Ok(value @ serde_json::Value::Object(_)) =
+            serde_json::from_str::<serde_json::Value>(raw.trim())
+    {
+        return json_object_line(raw, value);
+    }
+    if LOGFMT_LINE_RE.find_iter(raw).count() >= 3 {
+        return parse_logfmt_line(raw);
+    }
+    parse_plain_line(raw)
+}
 
-            // Docker JSON log detection: has "log" + "stream" keys, no "message"/"msg"
-            let is_docker_log = value.get("message").is_none()
-                && value.get("msg").is_none()
-                && value.get("log").is_some()
-                && value.get("stream").is_some();
+fn json_object_line(raw: &str, value: serde_json::Value) -> ParsedLine {
+    let message_template = TEMPLATE_KEYS
+        .iter()
+        .find_map(|k| value.get(*k))
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+    // Only Serilog-shaped lines get their property bag flattened
+    let properties = message_template.as_ref().and_then(|_| {
+        PROPERTIES_KEYS
+            .iter()
+            .find_map(|k| value.get(*k).and_then(|v| v.as_object()).map(|o| (*k, o)))
+    });
 
-            let mut message = value
+    let level = value
+        .get("level")
+        .or_else(|| value.get("severity"))
+        .or_else(|| value.get("log.level"))
+        .or_else(|| value.get("Level"))
+        .or_else(|| value.get("@l"))
+        .and_then(|v| {
+            v.as_str()
+                .and_then(parse_level_str)
+                .or_else(|| v.as_u64().and_then(parse_numeric_level))
+        })
+        .or_else(|| {
+            // Fallback: scan the message text for a level keyword (e.g. Docker logs
+            // embed the level inside the "log" value, not as a separate key).
+            let msg = value
                 .get("message")
                 .or_else(|| value.get("msg"))
                 .or_else(|| value.get("log"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.trim_end_matches('\n'))
-                .unwrap_or(trimmed)
-                .to_string();
-
-            // Docker logs: strip leading embedded timestamp from message
-            // when a wrapper timestamp already exists (avoids double timestamp display).
-            if is_docker_log
-                && timestamp.is_some()
-                && let Some(m) = PLAIN_TIMESTAMP_RE.find(&message)
-            {
-                message = message[m.end()..].trim_start().to_string();
-            }
+                .and_then(|v| v.as_str())?;
+            LEVEL_RE.find(msg).and_then(|m| parse_level_str(m.as_str()))
+        })
+        // CLEF omits `@l` for Information
+        .or_else(|| value.get("@mt").map(|_| LogLevel::Info));
 
-            let pretty = serde_json::to_string_pretty(&value).ok();
-
-            // Collect extra fields (keys not in KNOWN_JSON_KEYS).
-            // serde_json preserves insertion order with its default Map (backed by BTreeMap
-            // when the "preserve_order" feature is off), so keys come out alphabetically.
-            let extra_fields = value
-                .as_object()
-                .map(|obj| {
-                    obj.iter()
-                        .filter(|(k, _)| !KNOWN_JSON_KEYS.contains(&k.as_str()))
-                        .map(|(k, v)| (k.clone(), format_json_value(v)))
-                        .collect()
-                })
-                .unwrap_or_default();
-
-            ParsedLine {
-                raw: raw.to_string(),
-                level,
-                timestamp,
-                message,
-                format: LogFormat::Json,
-                pretty_json: pretty,
-                extra_fields,
-                template: String::new(),
+    let timestamp = value
+        .get("timestamp")
+        .or_else(|| value.get("time"))
+        .or_else(|| value.get("@timestamp"))
+        .or_else(|| value.get("ts"))
+        .or_else(|| value.get("Timestamp"))
+        .or_else(|| value.get("@t"))
+        .and_then(|v| {
+            // String timestamps: use as-is
+            if let Some(s) = v.as_str() {
+                return Some(s.to_string());
+            }
+            // Numeric timestamps: epoch millis or secs → ISO string
+            if let Some(n) = v.as_i64().or_else(|| v.as_f64().map(|f| f as i64)) {
+                // Epoch millis (1e12..1e14 range)
+                if (1_000_000_000_000..100_000_000_000_000).contains(&n) {
+                    let secs = n / 1000;
+                    let nsecs = ((n % 1000) * 1_000_000) as u32;
+                    if let Some(dt) = chrono::DateTime::from_timestamp(secs, nsecs) {
+                        return Some(dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string());
+                    }
+                }
+                // Epoch secs (1e9..1e10 range)
+                if (1_000_000_000..10_000_000_000).contains(&n)
+                    && let Some(dt) = chrono::DateTime::from_timestamp(n, 0)
+                {
+                    return Some(dt.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+                }
             }
+            None
+        });
+
+    // Docker JSON log detection: has "log" + "stream" keys, no "message"/"msg"
+    let is_docker_log = value.get("message").is_none()
+        && value.get("msg").is_none()
+        && value.get("log").is_some()
+        && value.get("stream").is_some();
+
+    let rendered = value
+        .get("RenderedMessage")
+        .or_else(|| value.get("@m"))
+        .or_else(|| value.get("message"))
+        .or_else(|| value.get("msg"))
+        .or_else(|| value.get("log"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim_end_matches('\n').to_string());
+    let mut message = match (rendered, &message_template) {
+        (Some(m), _) => m,
+        (None, Some(t)) => {
+            let props = properties.map(|(_, o)| o).or_else(|| value.as_object());
+            props.map_or_else(|| t.clone(), |p| render_template(t, p))
         }
-        Err(_) => ParsedLine {
-            raw: raw.to_string(),
-            level: None,
-            timestamp: None,
-            message: raw.to_string(),
-            format: LogFormat::Json,
-            pretty_json: None,
-            extra_fields: Vec::new(),
-            template: String::new(),
-        },
+        (None, None) => raw.trim().to_string(),
+    };
+
+    // Docker logs: strip leading embedded timestamp from message
+    // when a wrapper timestamp already exists (avoids double timestamp display).
+    if is_docker_log
+        && timestamp.is_some()
+        && let Some(m) = PLAIN_TIMESTAMP_RE.find(&message)
+    {
+        message = message[m.end()..].trim_start().to_string();
+    }
+
+    let pretty = serde_json::to_string_pretty(&decode_nested_json(value.clone())).ok();
+
+    // Collect extra fields (keys not in KNOWN_JSON_KEYS).
+    // serde_json preserves insertion order with its default Map (backed by BTreeMap
+    // when the "preserve_order" feature is off), so keys come out alphabetically.
+    let mut extra_fields: Vec<(String, String)> = value
+        .as_object()
+        .map(|obj| {
+            obj.iter()
+                .filter(|(k, _)| !KNOWN_JSON_KEYS.contains(&k.as_str()))
+                .filter(|(k, _)| properties.is_none_or(|(key, _)| key != k.as_str()))
+                .map(|(k, v)| (k.clone(), format_json_value(v)))
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some((_, props)) = properties {
+        extra_fields.extend(props.iter().map(|(k, v)| (k.clone(), format_json_value(v))));
+    }
+
+    ParsedLine {
+        raw: raw.to_string(),
+        level,
+        timestamp,
+        message,
+        format: LogFormat::Json,
+        pretty_json: pretty,
+        json: Some(value),
+        extra_fields,
+        template: message_template.unwrap_or_default(),
     }
 }
 
 fn parse_syslog_line(raw: &str) -> ParsedLine {
-    let (timestamp, message) = if let Some(caps) = SYSLOG_RE.captures(raw) {
-        (Some(caps[1].to_string()), caps[3].to_string())
-    } else {
-        (None, raw.to_string())
+    let Some(caps) = SYSLOG_RE.captures(raw) else {
+        return parse_unmatched_line(raw);
     };
+    let timestamp = Some(caps[1].to_string());
+    let message = caps[3].to_string();
 
     let level = LEVEL_RE.find(raw).and_then(|m| parse_level_str(m.as_str()));
 
@@ -363,15 +664,45 @@ fn parse_syslog_line(raw: &str) -> ParsedLine {
         message,
         format: LogFormat::Syslog,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     }
 }
 
+/// Strip the CRI prefix and parse what the container wrote in whatever
+/// format it looks like on its own. The runtime's timestamp replaces the
+/// payload's and the stream becomes a `stream` field. Plain payloads keep
+/// the timestamp in front so it still shows.
+fn parse_cri_line(raw: &str) -> ParsedLine {
+    let Some(caps) = CRI_RE.captures(raw) else {
+        return parse_unmatched_line(raw);
+    };
+    let timestamp = caps[1].to_string();
+    let payload = caps.get(4).map_or("", |m| m.as_str());
+    let format = detect_format(&[payload.to_string()]);
+    let mut parsed = match format {
+        LogFormat::Plain => parse_line(&format!("{timestamp} {payload}"), format),
+        _ => parse_line(payload, format),
+    };
+    if parsed.format == LogFormat::Plain {
+        parsed.message = payload.to_string();
+    }
+    parsed.timestamp = Some(timestamp);
+    parsed
+        .extra_fields
+        .push(("stream".to_string(), caps[2].to_string()));
+    parsed
+}
+
 fn parse_plain_line(raw: &str) -> ParsedLine {
     let timestamp = PLAIN_TIMESTAMP_RE.find(raw).map(|m| m.as_str().to_string());
 
-    let level = LEVEL_RE.find(raw).and_then(|m| parse_level_str(m.as_str()));
+    let level = if is_traceback_start(raw) {
+        Some(LogLevel::Error)
+    } else {
+        LEVEL_RE.find(raw).and_then(|m| parse_level_str(m.as_str()))
+    };
 
     ParsedLine {
         raw: raw.to_string(),
@@ -380,6 +711,7 @@ fn parse_plain_line(raw: &str) -> ParsedLine {
         message: raw.to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     }
@@ -409,6 +741,9 @@ fn parse_logfmt_line(raw: &str) -> ParsedLine {
             extra_fields.push((key.to_string(), value.to_string()));
         }
     }
+    if level.is_none() && timestamp.is_none() && message.is_none() && extra_fields.is_empty() {
+        return parse_unmatched_line(raw);
+    }
 
     ParsedLine {
         raw: raw.to_string(),
@@ -417,6 +752,7 @@ fn parse_logfmt_line(raw: &str) -> ParsedLine {
         message: message.unwrap_or_else(|| raw.to_string()),
         format: LogFormat::Logfmt,
         pretty_json: None,
+        json: None,
         extra_fields,
         template: String::new(),
     }
@@ -446,20 +782,12 @@ fn parse_klog_line(raw: &str) -> ParsedLine {
             message,
             format: LogFormat::Klog,
             pretty_json: None,
+            json: None,
             extra_fields,
             template: String::new(),
         }
     } else {
-        ParsedLine {
-            raw: raw.to_string(),
-            level: None,
-            timestamp: None,
-            message: raw.to_string(),
-            format: LogFormat::Klog,
-            pretty_json: None,
-            extra_fields: Vec::new(),
-            template: String::new(),
-        }
+        parse_unmatched_line(raw)
     }
 }
 
@@ -479,20 +807,12 @@ fn parse_log4j_line(raw: &str) -> ParsedLine {
             message,
             format: LogFormat::Log4j,
             pretty_json: None,
+            json: None,
             extra_fields,
             template: String::new(),
         }
     } else {
-        ParsedLine {
-            raw: raw.to_string(),
-            level: LEVEL_RE.find(raw).and_then(|m| parse_level_str(m.as_str())),
-            timestamp: PLAIN_TIMESTAMP_RE.find(raw).map(|m| m.as_str().to_string()),
-            message: raw.to_string(),
-            format: LogFormat::Log4j,
-            pretty_json: None,
-            extra_fields: Vec::new(),
-            template: String::new(),
-        }
+        parse_unmatched_line(raw)
     }
 }
 
@@ -511,20 +831,12 @@ fn parse_python_log_line(raw: &str) -> ParsedLine {
             message,
             format: LogFormat::PythonLog,
             pretty_json: None,
+            json: None,
             extra_fields,
             template: String::new(),
         }
     } else {
-        ParsedLine {
-            raw: raw.to_string(),
-            level: LEVEL_RE.find(raw).and_then(|m| parse_level_str(m.as_str())),
-            timestamp: PLAIN_TIMESTAMP_RE.find(raw).map(|m| m.as_str().to_string()),
-            message: raw.to_string(),
-            format: LogFormat::PythonLog,
-            pretty_json: None,
-            extra_fields: Vec::new(),
-            template: String::new(),
-        }
+        parse_unmatched_line(raw)
     }
 }
 
@@ -564,6 +876,10 @@ fn parse_access_log_line(raw: &str) -> ParsedLine {
         if let Some(ua) = caps.get(9) {
             extra_fields.push(("ua".to_string(), ua.as_str().to_string()));
         }
+        // nginx's $request_time, when the log_format appends it
+        if let Some(latency) = caps.get(10) {
+            extra_fields.push(("latency".to_string(), latency.as_str().to_string()));
+        }
 
         ParsedLine {
             raw: raw.to_string(),
@@ -572,28 +888,252 @@ fn parse_access_log_line(raw: &str) -> ParsedLine {
             message,
             format: LogFormat::AccessLog,
             pretty_json: None,
+            json: None,
             extra_fields,
             template: String::new(),
         }
     } else {
-        ParsedLine {
-            raw: raw.to_string(),
-            level: None,
-            timestamp: None,
-            message: raw.to_string(),
-            format: LogFormat::AccessLog,
+        parse_unmatched_line(raw)
+    }
+}
+
+/// Console lines keep their raw text with ANSI colors removed, since the
+/// viewer applies its own styling.
+fn parse_console_line(raw: &str) -> ParsedLine {
+    let clean = strip_ansi(raw).into_owned();
+    let mut extra_fields = Vec::new();
+
+    let parts = if let Some(caps) = LOGRUS_RE.captures(&clean) {
+        let (message, fields) = split_trailing_fields(&caps[3]);
+        extra_fields = fields;
+        Some((caps[1].to_string(), caps[2].to_string(), message))
+    } else if let Some(caps) = ZAP_CONSOLE_RE.captures(&clean) {
+        let mut columns: Vec<&str> = caps[3].split('\t').collect();
+        if columns.len() > 1
+            && let Some(last) = columns.last()
+            && let Ok(serde_json::Value::Object(obj)) = serde_json::from_str(last)
+        {
+            extra_fields.extend(obj.iter().map(|(k, v)| (k.clone(), format_json_value(v))));
+            columns.pop();
+        }
+        if columns.len() > 2 && CALLER_RE.is_match(columns[1]) {
+            extra_fields.insert(0, ("logger".to_string(), columns.remove(0).to_string()));
+        }
+        if columns.len() > 1 && CALLER_RE.is_match(columns[0]) {
+            let at = usize::from(extra_fields.first().is_some_and(|(k, _)| k == "logger"));
+            extra_fields.insert(at, ("caller".to_string(), columns.remove(0).to_string()));
+        }
+        Some((caps[2].to_string(), caps[1].to_string(), columns.join("\t")))
+    } else if let Some(caps) = PINO_PRETTY_RE.captures(&clean) {
+        if let Some(process) = caps.get(3)
+            && let Some(p) = PINO_PROCESS_RE.captures(process.as_str())
+        {
+            for (key, group) in [("name", 1), ("pid", 2), ("hostname", 3)] {
+                if let Some(m) = p.get(group) {
+                    extra_fields.push((key.to_string(), m.as_str().to_string()));
+                }
+            }
+        }
+        Some((
+            caps[2].to_string(),
+            caps[1].to_string(),
+            caps[4].to_string(),
+        ))
+    } else if let Some(caps) = ZEROLOG_CONSOLE_RE.captures(&clean) {
+        let (message, fields) = split_trailing_fields(&caps[3]);
+        extra_fields = fields;
+        Some((caps[2].to_string(), caps[1].to_string(), message))
+    } else {
+        None
+    };
+
+    match parts {
+        Some((level, timestamp, message)) => ParsedLine {
+            raw: clean,
+            level: parse_console_level(&level),
+            timestamp: Some(timestamp),
+            message,
+            format: LogFormat::Console,
             pretty_json: None,
-            extra_fields: Vec::new(),
+            json: None,
+            extra_fields,
             template: String::new(),
+        },
+        None => parse_unmatched_line(&clean),
+    }
+}
+
+/// Split `message  key=value key2="a b"` into the message and its trailing
+/// fields. Only a run of pairs reaching the end of the line counts, so an
+/// `x=1` in the middle of the message stays part of it.
+fn split_trailing_fields(text: &str) -> (String, Vec<(String, String)>) {
+    let pairs: Vec<_> = LOGFMT_PAIR_RE.captures_iter(text).collect();
+    let mut start = text.len();
+    for caps in pairs.iter().rev() {
+        let m = caps.get(0).unwrap();
+        let preceded_by_space = m.start() == 0 || text[..m.start()].ends_with(char::is_whitespace);
+        if !text[m.end()..start].trim().is_empty() || !preceded_by_space {
+            break;
         }
+        start = m.start();
+    }
+    let fields = pairs
+        .iter()
+        .filter(|caps| caps.get(0).unwrap().start() >= start)
+        .map(|caps| {
+            let value = caps
+                .get(2)
+                .or_else(|| caps.get(3))
+                .map_or("", |m| m.as_str());
+            (caps[1].to_string(), value.to_string())
+        })
+        .collect();
+    (text[..start].trim_end().to_string(), fields)
+}
+
+/// Level badges of console encoders: zerolog's three-letter `INF`, logrus's
+/// four-letter `ERRO`, zap's `DPANIC`, or a full name.
+fn parse_console_level(s: &str) -> Option<LogLevel> {
+    match s.to_uppercase().as_str() {
+        "TRC" | "TRAC" => Some(LogLevel::Trace),
+        "DBG" | "DEBU" => Some(LogLevel::Debug),
+        "INF" => Some(LogLevel::Info),
+        "WRN" => Some(LogLevel::Warn),
+        "ERR" | "ERRO" => Some(LogLevel::Error),
+        "FTL" | "FATA" | "PNC" | "PANI" | "DPANIC" => Some(LogLevel::Fatal),
+        other => parse_level_str(other),
+    }
+}
+
+/// A Java stack frame, indented or not: `at com.example.Foo.run(Foo.java:42)`,
+/// with logback's `~[app.jar:1.0]` packaging suffix allowed.
+static JAVA_FRAME_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*at [\w$.<>/-]+\([^)]*\)(?:\s*~?\[[^\]]*\])?\s*$").unwrap());
+
+/// The rest of a Java stack trace: `... 12 more`, `... 3 common frames
+/// omitted`, and the exception line itself, `java.io.IOException: reset`.
+static JAVA_TRACE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"^\s*\.\.\. \d+ (?:more|common frames omitted)\s*$",
+        r"|^(?:[a-z_$][\w$]*\.)+[A-Z][\w$]*(?:Exception|Error|Throwable)(?::.*)?$",
+    ))
+    .unwrap()
+});
+
+/// Whether `raw` is a Java stack frame (`at com.example.Foo(Foo.java:42)`).
+pub fn is_java_frame(raw: &str) -> bool {
+    JAVA_FRAME_RE.is_match(raw)
+}
+
+/// Whether `raw` is empty or only whitespace: a separator rather than a
+/// log line.
+pub fn is_blank(raw: &str) -> bool {
+    raw.trim().is_empty()
+}
+
+/// The line a Python traceback starts with.
+static PY_TRACEBACK_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Traceback \(most recent call last\):\s*$").unwrap());
+
+/// The exception a Python traceback ends with, `ValueError: bad value` or
+/// `requests.exceptions.ConnectionError: ...`, or bare as `KeyboardInterrupt`.
+static PY_EXCEPTION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:[A-Za-z_]\w*\.)*[A-Z]\w*(?:Error|Exception|Warning|Exit|Interrupt|Iteration)(?::.*)?$",
+    )
+    .unwrap()
+});
+
+/// What Python prints between chained tracebacks.
+const PY_CHAIN_LINES: [&str; 2] = [
+    "During handling of the above exception, another exception occurred:",
+    "The above exception was the direct cause of the following exception:",
+];
+
+/// Whether `raw` starts a Python traceback, `Traceback (most recent call last):`.
+pub fn is_traceback_start(raw: &str) -> bool {
+    PY_TRACEBACK_RE.is_match(raw)
+}
+
+fn is_traceback_chain(raw: &str) -> bool {
+    PY_CHAIN_LINES.contains(&raw.trim_end())
+}
+
+/// Whether line `idx` of `lines` carries on the entry above it: an
+/// [`is_continuation`] line, or part of a Python traceback. A traceback
+/// joins an ERROR or FATAL line right above it, as `logger.exception`
+/// writes them, and is an ERROR entry of its own otherwise. Its frames,
+/// final exception and any chained tracebacks, blank lines between them
+/// included, go with it.
+pub fn continues_entry(lines: &[ParsedLine], idx: usize, format: LogFormat) -> bool {
+    let Some(parsed) = lines.get(idx) else {
+        return false;
+    };
+    if parsed.format != LogFormat::Plain {
+        return false;
+    }
+    let raw = parsed.raw.as_str();
+    let above = |n: usize| idx.checked_sub(n).map(|i| lines[i].raw.as_str());
+    if is_traceback_start(raw) {
+        let after_error = idx
+            .checked_sub(1)
+            .is_some_and(|i| matches!(lines[i].level, Some(LogLevel::Error | LogLevel::Fatal)));
+        return after_error
+            || (above(1).is_some_and(is_blank) && above(2).is_some_and(is_traceback_chain));
+    }
+    if is_blank(raw) {
+        let below = lines.get(idx + 1).map(|p| p.raw.as_str());
+        return below.is_some_and(is_traceback_chain) || above(1).is_some_and(is_traceback_chain);
+    }
+    if is_traceback_chain(raw) {
+        return above(1).is_some_and(is_blank);
+    }
+    if PY_EXCEPTION_RE.is_match(raw) && above(1).is_some_and(|l| l.starts_with([' ', '\t'])) {
+        return true;
+    }
+    is_continuation(parsed, format)
+}
+
+/// Whether `parsed` carries on the entry above it, like a stack frame or
+/// the rest of a wrapped message: an indented line, a `Caused by:` line,
+/// any part of a Java stack trace, or in a structured `format`, a line that
+/// didn't fit it and has no level or timestamp of its own.
+pub fn is_continuation(parsed: &ParsedLine, format: LogFormat) -> bool {
+    let raw = parsed.raw.as_str();
+    if parsed.format != LogFormat::Plain || is_blank(raw) {
+        return false;
+    }
+    raw.starts_with([' ', '\t'])
+        || raw.starts_with("Caused by: ")
+        || is_java_frame(raw)
+        || JAVA_TRACE_RE.is_match(raw)
+        || (format != LogFormat::Plain && parsed.level.is_none() && parsed.timestamp.is_none())
+}
+
+/// What the parser made of a line, for `lumolog test-format`: the raw text, then
+/// one indented row per extracted part. `number` is 1-based.
+pub fn describe(parsed: &ParsedLine, number: usize) -> String {
+    let mut out = format!("line {number} ({}): {}\n", parsed.format.name(), parsed.raw);
+    let level = parsed
+        .level
+        .map_or("-".to_string(), |l| format!("{l:?}").to_uppercase());
+    out.push_str(&format!("  level:     {level}\n"));
+    out.push_str(&format!(
+        "  timestamp: {}\n",
+        parsed.timestamp.as_deref().unwrap_or("-")
+    ));
+    out.push_str(&format!("  message:   {}\n", parsed.message));
+    for (key, value) in &parsed.extra_fields {
+        out.push_str(&format!("  field:     {key} = {value}\n"));
     }
+    out
 }
 
 fn parse_level_str(s: &str) -> Option<LogLevel> {
     match s.to_uppercase().as_str() {
-        "TRACE" => Some(LogLevel::Trace),
+        "TRACE" | "VERBOSE" => Some(LogLevel::Trace),
         "DEBUG" => Some(LogLevel::Debug),
-        "INFO" | "NOTICE" => Some(LogLevel::Info),
+        "INFO" | "NOTICE" | "INFORMATION" => Some(LogLevel::Info),
         "WARN" | "WARNING" => Some(LogLevel::Warn),
         "ERROR" | "SEVERE" => Some(LogLevel::Error),
         "FATAL" | "CRITICAL" | "EMERGENCY" | "EMERG" | "ALERT" | "PANIC" => Some(LogLevel::Fatal),
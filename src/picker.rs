@@ -0,0 +1,293 @@
+use crate::config::FuzzyConfig;
+use crate::filter::FuzzyMatcher;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Extensions the picker lists by default. Rotated names like `app.log.1`
+/// and gzipped ones like `app.log.2.gz` count too; Tab shows every file.
+pub const LOG_EXTENSIONS: &[&str] = &["log", "txt"];
+
+/// Compression lumolog can't read, left out of the log list even when the
+/// name looks like one (`app.log.xz`).
+const UNREADABLE_EXTENSIONS: &[&str] = &["bz2", "xz", "zst", "zip"];
+
+/// How many directories below the starting one are searched.
+const MAX_DEPTH: usize = 4;
+
+/// Scanning stops after this many files, so a bare launch in `$HOME` stays quick.
+pub const MAX_FILES: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickerEntry {
+    /// Relative to the directory the picker was opened in.
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub is_log: bool,
+    /// From the recently opened list; the path is absolute.
+    pub recent: bool,
+}
+
+impl PickerEntry {
+    /// The path as listed, with the home directory shortened to `~`.
+    pub fn display(&self) -> String {
+        if let Some(home) = std::env::var_os("HOME").filter(|h| !h.is_empty())
+            && let Ok(rest) = self.path.strip_prefix(&home)
+        {
+            return Path::new("~").join(rest).display().to_string();
+        }
+        self.path.display().to_string()
+    }
+}
+
+/// Whether a file name looks like a log: `app.log`, `notes.txt`,
+/// `app.log.1`, `app.log.2026-10-01`, `app.log.2.gz`, but not `app.log.xz`.
+pub fn is_log_name(name: &str) -> bool {
+    let mut parts = name.split('.').skip(1).peekable();
+    if parts.peek().is_none() {
+        return false;
+    }
+    let parts: Vec<String> = parts.map(str::to_ascii_lowercase).collect();
+    if parts
+        .last()
+        .is_some_and(|ext| UNREADABLE_EXTENSIONS.contains(&ext.as_str()))
+    {
+        return false;
+    }
+    parts
+        .iter()
+        .any(|ext| LOG_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Files under `root`, newest first. Hidden files and directories are
+/// skipped, symlinked directories aren't followed, and unreadable ones are
+/// passed over. The flag is true when the scan stopped at [`MAX_FILES`].
+pub fn scan_dir(root: &Path) -> (Vec<PickerEntry>, bool) {
+    let mut entries = Vec::new();
+    let mut truncated = false;
+    let mut pending = vec![(root.to_path_buf(), 0)];
+    'scan: while let Some((dir, depth)) = pending.pop() {
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let path = entry.path();
+            if file_type.is_dir() {
+                if depth < MAX_DEPTH {
+                    pending.push((path, depth + 1));
+                }
+                continue;
+            }
+            // Follows symlinks to files; broken links and sockets drop out
+            let Ok(meta) = std::fs::metadata(&path) else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+            if entries.len() == MAX_FILES {
+                truncated = true;
+                break 'scan;
+            }
+            entries.push(PickerEntry {
+                path: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+                size: meta.len(),
+                modified: meta.modified().ok(),
+                is_log: is_log_name(&name),
+                recent: false,
+            });
+        }
+    }
+    entries.sort_by(|a, b| {
+        b.modified
+            .cmp(&a.modified)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    (entries, truncated)
+}
+
+/// Entries for recently opened files that still exist, in the given order.
+pub fn recent_entries(files: &[PathBuf]) -> Vec<PickerEntry> {
+    files
+        .iter()
+        .filter_map(|path| {
+            let meta = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+            Some(PickerEntry {
+                path: path.clone(),
+                size: meta.len(),
+                modified: meta.modified().ok(),
+                is_log: true,
+                recent: true,
+            })
+        })
+        .collect()
+}
+
+/// The file chooser shown when lumolog is started without a file in a
+/// terminal: a fuzzy search over the files below the current directory.
+pub struct FilePicker {
+    root: PathBuf,
+    entries: Vec<PickerEntry>,
+    truncated: bool,
+    /// Only recently opened files, for `--recent`.
+    recent_only: bool,
+    fuzzy: FuzzyConfig,
+    query: String,
+    show_all: bool,
+    /// Indices into `entries`, best match first.
+    matches: Vec<usize>,
+    selected: usize,
+    error: Option<String>,
+}
+
+impl FilePicker {
+    pub fn new(root: PathBuf, entries: Vec<PickerEntry>, fuzzy: FuzzyConfig) -> Self {
+        let mut picker = Self {
+            root,
+            entries,
+            truncated: false,
+            recent_only: false,
+            fuzzy,
+            query: String::new(),
+            show_all: false,
+            matches: Vec::new(),
+            selected: 0,
+            error: None,
+        };
+        picker.recompute();
+        picker
+    }
+
+    /// A picker over the `recent` files followed by the files below `root`.
+    pub fn scan(root: PathBuf, recent: &[PathBuf], fuzzy: FuzzyConfig) -> Self {
+        let (scanned, truncated) = scan_dir(&root);
+        let mut entries = recent_entries(recent);
+        entries.extend(
+            scanned
+                .into_iter()
+                .filter(|e| !recent.contains(&root.join(&e.path))),
+        );
+        let mut picker = Self::new(root, entries, fuzzy);
+        picker.truncated = truncated;
+        picker
+    }
+
+    /// A picker over just the `recent` files.
+    pub fn recent(root: PathBuf, recent: &[PathBuf], fuzzy: FuzzyConfig) -> Self {
+        let mut picker = Self::new(root, recent_entries(recent), fuzzy);
+        picker.recent_only = true;
+        picker
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// True when Tab has widened the list past log files.
+    pub fn shows_all(&self) -> bool {
+        self.show_all
+    }
+
+    pub fn is_recent_only(&self) -> bool {
+        self.recent_only
+    }
+
+    /// True when the directory had more files than were scanned.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Files the list is drawn from: logs only, or everything.
+    pub fn candidate_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| self.show_all || e.is_log)
+            .count()
+    }
+
+    pub fn matches(&self) -> impl Iterator<Item = &PickerEntry> {
+        self.matches.iter().map(|&i| &self.entries[i])
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Position of the highlighted row in [`matches`](Self::matches).
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Full path of the highlighted file.
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        let &idx = self.matches.get(self.selected)?;
+        Some(self.root.join(&self.entries[idx].path))
+    }
+
+    /// Why the last chosen file couldn't be opened, until the list changes.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.recompute();
+    }
+
+    pub fn pop_char(&mut self) {
+        self.query.pop();
+        self.recompute();
+    }
+
+    pub fn clear_query(&mut self) {
+        self.query.clear();
+        self.recompute();
+    }
+
+    pub fn toggle_show_all(&mut self) {
+        self.show_all = !self.show_all;
+        self.recompute();
+    }
+
+    /// Move the highlight by `delta` rows, stopping at either end.
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    fn recompute(&mut self) {
+        self.error = None;
+        self.selected = 0;
+        let candidates =
+            (0..self.entries.len()).filter(|&i| self.show_all || self.entries[i].is_log);
+        if self.query.is_empty() {
+            self.matches = candidates.collect();
+            return;
+        }
+        let mut matcher = FuzzyMatcher::new(&self.query, &self.fuzzy, false);
+        let mut scored: Vec<(usize, u32)> = candidates
+            .filter_map(|i| {
+                let path = self.entries[i].display();
+                matcher.score(&path).map(|score| (i, score))
+            })
+            .collect();
+        // Stable, so equal scores stay newest first
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.matches = scored.into_iter().map(|(i, _)| i).collect();
+    }
+}
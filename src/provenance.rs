@@ -0,0 +1,58 @@
+use crate::parser::ParsedLine;
+
+/// Lines starting with this carry provenance for the lines after them, e.g.
+/// `#lumolog:{"source":"api","host":"web-3"}` from a tool merging several
+/// logs into one stream.
+pub const CONTROL_PREFIX: &str = "#lumolog:";
+
+/// Whether `raw` is a well-formed control line, which the view leaves out.
+pub fn is_control_line(raw: &str) -> bool {
+    raw.starts_with(CONTROL_PREFIX) && parse_control_line(raw).is_some()
+}
+
+/// The fields of a control line's JSON object, by key. Strings are taken
+/// as they are, other values as JSON text; nulls are left out. None when the
+/// line isn't a control line or its JSON isn't an object.
+pub fn parse_control_line(raw: &str) -> Option<Vec<(String, String)>> {
+    let json = raw.strip_prefix(CONTROL_PREFIX)?;
+    let serde_json::Value::Object(map) = serde_json::from_str(json.trim()).ok()? else {
+        return None;
+    };
+    Some(
+        map.into_iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| {
+                let value = match v {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                (k, value)
+            })
+            .collect(),
+    )
+}
+
+/// Provenance fields in effect while reading a log top to bottom. Each
+/// control line replaces them; `#lumolog:{}` clears them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    fields: Vec<(String, String)>,
+}
+
+impl Provenance {
+    /// Take in the next line: a control line updates the fields, any other
+    /// line gets them as extra fields, after the ones it has of its own
+    /// (which win). A control line with bad JSON is left as an ordinary
+    /// line, so the mistake shows.
+    pub fn apply(&mut self, line: &mut ParsedLine) {
+        if let Some(fields) = parse_control_line(&line.raw) {
+            self.fields = fields;
+            return;
+        }
+        for (key, value) in &self.fields {
+            if !line.extra_fields.iter().any(|(k, _)| k == key) {
+                line.extra_fields.push((key.clone(), value.clone()));
+            }
+        }
+    }
+}
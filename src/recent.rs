@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+/// How many files the list remembers.
+pub const MAX_RECENT: usize = 50;
+
+/// Files opened in earlier runs, most recent first, kept one absolute path
+/// per line in `recent.txt` under [`state_dir`](crate::config::state_dir).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecentFiles {
+    files: Vec<PathBuf>,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    crate::config::state_dir().map(|dir| dir.join("recent.txt"))
+}
+
+impl RecentFiles {
+    pub fn parse(text: &str) -> Self {
+        let mut recent = Self::default();
+        for line in text.lines().rev() {
+            let line = line.trim();
+            if !line.is_empty() {
+                recent.add(Path::new(line));
+            }
+        }
+        recent
+    }
+
+    pub fn to_text(&self) -> String {
+        self.files
+            .iter()
+            .map(|f| format!("{}\n", f.display()))
+            .collect()
+    }
+
+    /// The list at `path`; a missing or unreadable file is an empty list.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| anyhow::anyhow!("cannot create {}: {e}", dir.display()))?;
+        }
+        std::fs::write(path, self.to_text())
+            .map_err(|e| anyhow::anyhow!("cannot write {}: {e}", path.display()))
+    }
+
+    #[allow(dead_code)]
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Files that are still there to open.
+    pub fn existing(&self) -> Vec<PathBuf> {
+        self.files.iter().filter(|f| f.is_file()).cloned().collect()
+    }
+
+    /// Move `file` to the front, dropping the oldest past [`MAX_RECENT`].
+    pub fn add(&mut self, file: &Path) {
+        self.files.retain(|f| f != file);
+        self.files.insert(0, file.to_path_buf());
+        self.files.truncate(MAX_RECENT);
+    }
+}
+
+/// Put `file` at the front of the list at `path`, re-reading it first so
+/// viewers running side by side don't drop each other's entries.
+pub fn record(path: &Path, file: &Path) -> anyhow::Result<()> {
+    let mut recent = RecentFiles::load(path);
+    recent.add(file);
+    recent.save(path)
+}
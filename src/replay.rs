@@ -0,0 +1,346 @@
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use ratatui::buffer::Buffer;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// First line of a recording: what the events were recorded against. Mouse
+/// positions only mean the same thing in a terminal of the same size.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Header {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// One event line: `t` is milliseconds since the recording started, with
+/// exactly one of `key`, `mouse` (with `x` and `y`) or `paste`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    t: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mouse: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    x: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    y: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    paste: Option<String>,
+}
+
+/// An event and when it happened, in milliseconds since the recording
+/// started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recorded {
+    pub at_ms: u64,
+    pub event: Event,
+}
+
+const MODIFIERS: [(&str, KeyModifiers); 3] = [
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT),
+];
+
+const KEY_NAMES: [(&str, KeyCode); 16] = [
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("space", KeyCode::Char(' ')),
+];
+
+fn modifier_prefix(modifiers: KeyModifiers) -> String {
+    MODIFIERS
+        .iter()
+        .filter(|(_, m)| modifiers.contains(*m))
+        .map(|(name, _)| format!("{name}+"))
+        .collect()
+}
+
+/// Split leading `ctrl+`, `alt+` and `shift+` off `text`.
+fn strip_modifiers(mut text: &str) -> (KeyModifiers, &str) {
+    let mut modifiers = KeyModifiers::NONE;
+    'outer: loop {
+        for (name, modifier) in MODIFIERS {
+            if let Some(rest) = text.strip_prefix(name).and_then(|t| t.strip_prefix('+'))
+                && !rest.is_empty()
+            {
+                modifiers |= modifier;
+                text = rest;
+                continue 'outer;
+            }
+        }
+        return (modifiers, text);
+    }
+}
+
+/// A key as written in a recording: `j`, `G`, `ctrl+c`, `shift+tab`, `f5`.
+/// None for keys lumolog doesn't use.
+pub fn key_name(key: &KeyEvent) -> Option<String> {
+    let name = match key.code {
+        KeyCode::F(n) => format!("f{n}"),
+        code => match KEY_NAMES.iter().find(|(_, c)| *c == code) {
+            Some((name, _)) => name.to_string(),
+            None => match code {
+                KeyCode::Char(c) => c.to_string(),
+                _ => return None,
+            },
+        },
+    };
+    Some(modifier_prefix(key.modifiers) + &name)
+}
+
+/// The key [`key_name`] wrote as `name`.
+pub fn parse_key(name: &str) -> Option<KeyEvent> {
+    let (modifiers, rest) = strip_modifiers(name);
+    let code = if let Some((_, code)) = KEY_NAMES.iter().find(|(n, _)| *n == rest) {
+        *code
+    } else if let Some(n) = rest.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        KeyCode::F(n)
+    } else {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => return None,
+        }
+    };
+    Some(KeyEvent::new(code, modifiers))
+}
+
+fn button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Right => "right",
+        MouseButton::Middle => "middle",
+    }
+}
+
+/// A mouse action as written in a recording: `down-left`, `drag-left`,
+/// `scroll-up`, `ctrl+down-left`.
+fn mouse_name(mouse: &MouseEvent) -> String {
+    let kind = match mouse.kind {
+        MouseEventKind::Down(b) => format!("down-{}", button_name(b)),
+        MouseEventKind::Up(b) => format!("up-{}", button_name(b)),
+        MouseEventKind::Drag(b) => format!("drag-{}", button_name(b)),
+        MouseEventKind::Moved => "moved".to_string(),
+        MouseEventKind::ScrollDown => "scroll-down".to_string(),
+        MouseEventKind::ScrollUp => "scroll-up".to_string(),
+        MouseEventKind::ScrollLeft => "scroll-left".to_string(),
+        MouseEventKind::ScrollRight => "scroll-right".to_string(),
+    };
+    modifier_prefix(mouse.modifiers) + &kind
+}
+
+fn parse_mouse(name: &str, column: u16, row: u16) -> Option<MouseEvent> {
+    let (modifiers, rest) = strip_modifiers(name);
+    let button = |b: &str| match b {
+        "left" => Some(MouseButton::Left),
+        "right" => Some(MouseButton::Right),
+        "middle" => Some(MouseButton::Middle),
+        _ => None,
+    };
+    let kind = match rest.split_once('-') {
+        Some(("down", b)) => MouseEventKind::Down(button(b)?),
+        Some(("up", b)) => MouseEventKind::Up(button(b)?),
+        Some(("drag", b)) => MouseEventKind::Drag(button(b)?),
+        Some(("scroll", "down")) => MouseEventKind::ScrollDown,
+        Some(("scroll", "up")) => MouseEventKind::ScrollUp,
+        Some(("scroll", "left")) => MouseEventKind::ScrollLeft,
+        Some(("scroll", "right")) => MouseEventKind::ScrollRight,
+        None if rest == "moved" => MouseEventKind::Moved,
+        _ => return None,
+    };
+    Some(MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers,
+    })
+}
+
+/// `event` as a recording line, or None for events the viewer ignores (key
+/// releases, mouse moves, focus changes, resizes).
+pub fn encode(at_ms: u64, event: &Event) -> Option<String> {
+    let mut entry = Entry {
+        t: at_ms,
+        ..Entry::default()
+    };
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => entry.key = Some(key_name(key)?),
+        Event::Mouse(mouse) if mouse.kind != MouseEventKind::Moved => {
+            entry.mouse = Some(mouse_name(mouse));
+            entry.x = Some(mouse.column);
+            entry.y = Some(mouse.row);
+        }
+        Event::Paste(text) => entry.paste = Some(text.clone()),
+        _ => return None,
+    }
+    serde_json::to_string(&entry).ok()
+}
+
+fn decode(line: &str) -> anyhow::Result<Recorded> {
+    let entry: Entry = serde_json::from_str(line)?;
+    let event = match (entry.key, entry.mouse, entry.paste) {
+        (Some(key), None, None) => {
+            Event::Key(parse_key(&key).ok_or_else(|| anyhow::anyhow!("unknown key '{key}'"))?)
+        }
+        (None, Some(mouse), None) => {
+            let (Some(x), Some(y)) = (entry.x, entry.y) else {
+                anyhow::bail!("mouse event without x and y");
+            };
+            Event::Mouse(
+                parse_mouse(&mouse, x, y)
+                    .ok_or_else(|| anyhow::anyhow!("unknown mouse action '{mouse}'"))?,
+            )
+        }
+        (None, None, Some(text)) => Event::Paste(text),
+        _ => anyhow::bail!("expected one of key, mouse or paste"),
+    };
+    Ok(Recorded {
+        at_ms: entry.t,
+        event,
+    })
+}
+
+/// Events recorded with `--record`, to play back with `--replay-events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub header: Header,
+    pub events: Vec<Recorded>,
+}
+
+impl Recording {
+    /// Parse a recording: a [`Header`] line, then one JSON object per event.
+    /// Blank lines are skipped.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let Some((_, first)) = lines.next() else {
+            anyhow::bail!("empty recording");
+        };
+        let header: Header =
+            serde_json::from_str(first).map_err(|e| anyhow::anyhow!("line 1: {e}"))?;
+        let events = lines
+            .map(|(n, line)| decode(line).map_err(|e| anyhow::anyhow!("line {}: {e}", n + 1)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self { header, events })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("cannot read recording {}: {e}", path.display()))?;
+        Self::parse(&text).map_err(|e| anyhow::anyhow!("invalid recording {}: {e}", path.display()))
+    }
+}
+
+/// Writes the events the viewer handles to a file as they happen, flushing
+/// each one so a crash still leaves the events that led to it.
+pub struct Recorder {
+    out: BufWriter<std::fs::File>,
+    started: Instant,
+}
+
+impl Recorder {
+    /// Open `path` for a recording. Nothing is written until [`Recorder::start`].
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let file = std::fs::File::create(path)
+            .map_err(|e| anyhow::anyhow!("cannot write recording {}: {e}", path.display()))?;
+        Ok(Self {
+            out: BufWriter::new(file),
+            started: Instant::now(),
+        })
+    }
+
+    /// Write the header and count event times from now.
+    pub fn start(&mut self, header: &Header) -> std::io::Result<()> {
+        self.started = Instant::now();
+        // A file name that isn't UTF-8 has no JSON form
+        let line = serde_json::to_string(header)?;
+        self.write_line(&line)
+    }
+
+    pub fn record(&mut self, event: &Event) -> std::io::Result<()> {
+        match encode(self.started.elapsed().as_millis() as u64, event) {
+            Some(line) => self.write_line(&line),
+            None => Ok(()),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        writeln!(self.out, "{line}")?;
+        self.out.flush()
+    }
+}
+
+/// Hands out a recording's events as they come due, keeping their timing.
+#[derive(Debug)]
+pub struct Player {
+    events: VecDeque<Recorded>,
+    started: Instant,
+}
+
+impl Player {
+    /// Start playing `events` now.
+    pub fn new(events: Vec<Recorded>) -> Self {
+        Self {
+            events: events.into(),
+            started: Instant::now(),
+        }
+    }
+
+    /// The next event once it's due, waiting up to `timeout` for it. None
+    /// when nothing comes due in time, or the recording is over.
+    pub fn next(&mut self, timeout: Duration) -> Option<Event> {
+        let due = Duration::from_millis(self.events.front()?.at_ms);
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            if wait > timeout {
+                std::thread::sleep(timeout);
+                return None;
+            }
+            std::thread::sleep(wait);
+        }
+        self.events.pop_front().map(|r| r.event)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// The text of a drawn screen, one line per row with trailing blanks cut,
+/// for comparing a replay's end state against a snapshot.
+pub fn screen_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    for y in 0..buffer.area.height {
+        let mut row = String::new();
+        for x in 0..buffer.area.width {
+            row.push_str(buffer[(x, y)].symbol());
+        }
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
@@ -0,0 +1,162 @@
+use crate::parser::LogLevel;
+use crate::timeindex::TimeRange;
+use crate::viewstate::ViewState;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A filter kept under a name to re-apply later: the text filters, level
+/// and time range, without the file they were made on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedFilter {
+    pub name: String,
+    #[serde(default)]
+    pub filter: String,
+    /// Filters stacked before `filter`.
+    #[serde(default)]
+    pub filters: Vec<String>,
+    pub min_level: Option<LogLevel>,
+    /// Levels hidden one by one, on top of `min_level`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hidden_levels: Vec<LogLevel>,
+    pub time_range: Option<TimeRange>,
+}
+
+impl SavedFilter {
+    /// The filters of `state` under `name`.
+    pub fn from_view_state(name: &str, state: &ViewState) -> Self {
+        Self {
+            name: name.to_string(),
+            filter: state.filter.clone(),
+            filters: state.filters.clone(),
+            min_level: state.min_level,
+            hidden_levels: state.hidden_levels.clone(),
+            time_range: state.time_range.clone(),
+        }
+    }
+
+    /// A view with these filters and nothing else, leaving the layout alone.
+    pub fn to_view_state(&self) -> ViewState {
+        ViewState {
+            filter: self.filter.clone(),
+            filters: self.filters.clone(),
+            min_level: self.min_level,
+            hidden_levels: self.hidden_levels.clone(),
+            time_range: self.time_range.clone(),
+            ..ViewState::default()
+        }
+    }
+
+    /// One-line description for the list:
+    /// `[a] "b" WRN+ -ERR 2024-01-15 08:00:00 - 2024-01-15 09:00:00`.
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self.filters.iter().map(|f| format!("[{f}]")).collect();
+        if !self.filter.is_empty() {
+            parts.push(format!("\"{}\"", self.filter));
+        }
+        if let Some(level) = self.min_level {
+            parts.push(format!("{}+", level.short_name()));
+        }
+        for level in &self.hidden_levels {
+            parts.push(format!("-{}", level.short_name()));
+        }
+        if let Some(range) = &self.time_range {
+            parts.push(format!(
+                "{} - {}",
+                range.start.format("%Y-%m-%d %H:%M:%S"),
+                range.end.format("%Y-%m-%d %H:%M:%S")
+            ));
+        }
+        parts.join(" ")
+    }
+}
+
+/// Named filters, in the order they were first saved, kept as
+/// `[[filter]]` tables in `filters.toml` under
+/// [`config_dir`](crate::config::config_dir) so they can be edited by hand.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SavedFilters {
+    filter: Vec<SavedFilter>,
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    crate::config::config_dir().map(|dir| dir.join("filters.toml"))
+}
+
+impl SavedFilters {
+    pub fn from_toml(text: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    /// The filters at `path`; a missing file is an empty list.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => anyhow::bail!("cannot read saved filters {}: {e}", path.display()),
+        };
+        Self::from_toml(&text)
+            .map_err(|e| anyhow::anyhow!("invalid saved filters {}: {e}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| anyhow::anyhow!("cannot create {}: {e}", dir.display()))?;
+        }
+        std::fs::write(path, self.to_toml()?)
+            .map_err(|e| anyhow::anyhow!("cannot write saved filters {}: {e}", path.display()))
+    }
+
+    pub fn filters(&self) -> &[SavedFilter] {
+        &self.filter
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filter.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.filter.len()
+    }
+
+    /// Add `filter`, replacing one with the same name in its place.
+    pub fn insert(&mut self, filter: SavedFilter) {
+        match self.filter.iter_mut().find(|f| f.name == filter.name) {
+            Some(existing) => *existing = filter,
+            None => self.filter.push(filter),
+        }
+    }
+
+    /// Drop the filter called `name`; false when there was none.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.filter.len();
+        self.filter.retain(|f| f.name != name);
+        self.filter.len() != before
+    }
+}
+
+/// Save `filter` into the file at `path`, re-reading it first so viewers
+/// running side by side don't drop each other's filters. Returns the list
+/// as written.
+pub fn record(path: &Path, filter: SavedFilter) -> anyhow::Result<SavedFilters> {
+    let mut saved = SavedFilters::load(path)?;
+    saved.insert(filter);
+    saved.save(path)?;
+    Ok(saved)
+}
+
+/// Delete the filter called `name` from the file at `path`. Returns the list
+/// as written.
+pub fn forget(path: &Path, name: &str) -> anyhow::Result<SavedFilters> {
+    let mut saved = SavedFilters::load(path)?;
+    if saved.remove(name) {
+        saved.save(path)?;
+    }
+    Ok(saved)
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Signals the viewer handles between frames instead of dying mid-draw:
+/// SIGINT and SIGTERM quit with the terminal restored, SIGHUP reloads.
+#[derive(Default)]
+pub struct Signals {
+    quit: Arc<AtomicBool>,
+    reload: Arc<AtomicBool>,
+}
+
+impl Signals {
+    /// Register the handlers. A second SIGINT or SIGTERM before the first
+    /// is handled exits at once, in case the viewer is stuck. Only unix has
+    /// these signals; elsewhere nothing is registered.
+    pub fn install() -> std::io::Result<Self> {
+        let signals = Self::default();
+        #[cfg(unix)]
+        {
+            use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+            use signal_hook::flag;
+            for signal in [SIGINT, SIGTERM] {
+                flag::register_conditional_shutdown(signal, 1, Arc::clone(&signals.quit))?;
+                flag::register(signal, Arc::clone(&signals.quit))?;
+            }
+            flag::register(SIGHUP, Arc::clone(&signals.reload))?;
+        }
+        Ok(signals)
+    }
+
+    /// Whether a quit signal arrived since the last call.
+    pub fn take_quit(&self) -> bool {
+        self.quit.swap(false, Ordering::Relaxed)
+    }
+
+    /// Whether a quit signal is waiting, leaving it for [`Signals::take_quit`].
+    pub fn quit_pending(&self) -> bool {
+        self.quit.load(Ordering::Relaxed)
+    }
+
+    /// Whether SIGHUP arrived since the last call.
+    pub fn take_reload(&self) -> bool {
+        self.reload.swap(false, Ordering::Relaxed)
+    }
+}
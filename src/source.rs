@@ -1,39 +1,187 @@
+use crate::loader::LoadProgress;
+use flate2::read::MultiGzDecoder;
 use memmap2::Mmap;
 use std::fs::File;
 use std::io::{self, BufRead, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, mpsc};
 use std::time::Duration;
 
+/// Lines between progress updates (and cancel checks) while reading.
+const PROGRESS_EVERY: usize = 64 * 1024;
+
 pub struct FileSource {
     lines: Vec<String>,
 }
 
 impl FileSource {
     pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
-        let file = File::open(path)?;
+        Ok(Self::read(path, false, &LoadProgress::default())?.0)
+    }
+
+    /// Like [`FileSource::open`], for following afterwards: leaves out a last
+    /// line whose newline hasn't been written yet, and returns the byte offset
+    /// where this read stopped, so following picks up with no gap and nothing
+    /// read twice.
+    #[allow(dead_code)] // used by integration tests
+    pub fn open_for_follow<P: AsRef<Path>>(path: P) -> anyhow::Result<(Self, u64)> {
+        Self::read(path, true, &LoadProgress::default())
+    }
+
+    /// [`FileSource::open`], or [`FileSource::open_for_follow`] when `follow`
+    /// is set, reporting to `progress` as it goes and stopping with an error
+    /// if the load is cancelled. The offset is only meaningful for following.
+    pub fn open_with_progress<P: AsRef<Path>>(
+        path: P,
+        follow: bool,
+        progress: &LoadProgress,
+    ) -> anyhow::Result<(Self, u64)> {
+        Self::read(path, follow, progress)
+    }
+
+    fn read<P: AsRef<Path>>(
+        path: P,
+        complete_only: bool,
+        progress: &LoadProgress,
+    ) -> anyhow::Result<(Self, u64)> {
+        let path = path.as_ref();
+        if complete_only && is_compressed(path) {
+            anyhow::bail!("can't follow compressed file {}", path.display());
+        }
+        let mut file = File::open(path)?;
         let metadata = file.metadata()?;
+        let split = |content: &str| {
+            let end = if complete_only {
+                content.rfind('\n').map_or(0, |pos| pos + 1)
+            } else {
+                content.len()
+            };
+            let mut lines = Vec::new();
+            for line in content[..end].lines() {
+                if lines.len() % PROGRESS_EVERY == 0 {
+                    if progress.is_cancelled() {
+                        anyhow::bail!("cancelled");
+                    }
+                    let offset = line.as_ptr() as usize - content.as_ptr() as usize;
+                    progress.set_read(offset as u64, lines.len());
+                }
+                lines.push(line.to_string());
+            }
+            progress.set_read(end as u64, lines.len());
+            Ok((Self { lines }, end as u64))
+        };
+
+        if is_compressed(path) {
+            let mut decoder = MultiGzDecoder::new(file);
+            let mut content = Vec::new();
+            let mut chunk = vec![0; 1 << 20];
+            loop {
+                if progress.is_cancelled() {
+                    anyhow::bail!("cancelled");
+                }
+                let read = decoder.read(&mut chunk)?;
+                if read == 0 {
+                    break;
+                }
+                content.extend_from_slice(&chunk[..read]);
+                progress.set_read(content.len() as u64, 0);
+            }
+            return split(&String::from_utf8(content)?);
+        }
+
+        // Pipes, FIFOs and process substitution (`<(cmd)`) report a length of
+        // 0 and can't be mapped; read them through to EOF instead.
+        if !metadata.is_file() {
+            let mut content = String::new();
+            file.read_to_string(&mut content)?;
+            return split(&content);
+        }
 
         if metadata.len() == 0 {
-            return Ok(Self { lines: Vec::new() });
+            return Ok((Self { lines: Vec::new() }, 0));
         }
 
+        // The map is fixed at the length seen here, even if the file grows
         let mmap = unsafe { Mmap::map(&file)? };
         let content = std::str::from_utf8(&mmap)?;
-        let lines: Vec<String> = content.lines().map(String::from).collect();
-        Ok(Self { lines })
+        split(content)
     }
 
     pub fn lines(&self) -> &[String] {
         &self.lines
     }
 
+    pub fn into_lines(self) -> Vec<String> {
+        self.lines
+    }
+
     #[allow(dead_code)] // used by integration tests
     pub fn line_count(&self) -> usize {
         self.lines.len()
     }
 }
 
+/// True when `path` exists but isn't a regular file (a FIFO, or a `/dev/fd/N`
+/// pipe from process substitution). Those can only be read once, front to
+/// back, so following them has to stream rather than poll the file length.
+pub fn is_stream<P: AsRef<Path>>(path: P) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| !m.is_file())
+}
+
+/// True for gzip files, which are decompressed as they're read. Their size
+/// on disk says little about how much text is inside.
+pub fn is_compressed<P: AsRef<Path>>(path: P) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// Older files logrotate left next to `path`, oldest first: `app.log.3.gz`,
+/// `app.log.2.gz`, `app.log.1` for `app.log`, or `app.log-20261014.gz`,
+/// `app.log-20261015` with date suffixes.
+pub fn rotated_siblings<P: AsRef<Path>>(path: P) -> Vec<PathBuf> {
+    let path = path.as_ref();
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let name = name.to_string_lossy();
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    // Date suffixes sort oldest first as text; numbers count up into the past
+    let is_digits = |s: &&str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let mut dated = Vec::new();
+    let mut numbered = Vec::new();
+    for entry in entries.flatten() {
+        if !entry.file_type().is_ok_and(|t| t.is_file()) {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(suffix) = file_name.strip_prefix(name.as_ref()) else {
+            continue;
+        };
+        let suffix = suffix.strip_suffix(".gz").unwrap_or(suffix);
+        if let Some(n) = suffix.strip_prefix('.').filter(is_digits) {
+            numbered.push((n.parse::<u64>().unwrap_or(u64::MAX), entry.path()));
+        } else if let Some(date) = suffix.strip_prefix('-').filter(is_digits) {
+            dated.push((date.to_string(), entry.path()));
+        }
+    }
+    dated.sort();
+    numbered.sort_by(|a, b| b.cmp(a));
+    dated
+        .into_iter()
+        .map(|(_, p)| p)
+        .chain(numbered.into_iter().map(|(_, p)| p))
+        .collect()
+}
+
 pub struct StdinSource {
     lines: Vec<String>,
 }
@@ -62,13 +210,19 @@ impl StdinSource {
 pub struct FollowableSource {
     path: PathBuf,
     offset: u64,
+    /// Bytes after the last newline read so far, held back until the writer
+    /// finishes the line so it isn't shown half-written and then again.
+    partial: Vec<u8>,
 }
 
 impl FollowableSource {
+    /// Follow `path` from `initial_offset`, which should be at the start of
+    /// a line (see [`FileSource::open_for_follow`]).
     pub fn new<P: AsRef<Path>>(path: P, initial_offset: u64) -> Self {
         Self {
             path: path.as_ref().to_path_buf(),
             offset: initial_offset,
+            partial: Vec::new(),
         }
     }
 
@@ -84,11 +238,19 @@ impl FollowableSource {
 
         file.seek(SeekFrom::Start(self.offset))?;
 
-        let mut buf = Vec::with_capacity((len - self.offset) as usize);
-        file.read_to_end(&mut buf)?;
+        let mut buf = std::mem::take(&mut self.partial);
+        buf.reserve((len - self.offset) as usize);
+        let read = file.read_to_end(&mut buf)?;
+
+        self.offset += read as u64;
 
-        self.offset = len;
+        let complete = buf
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |pos| pos + 1);
+        self.partial = buf.split_off(complete);
 
+        // Decoded only once whole, so a character split across reads survives
         let text = String::from_utf8_lossy(&buf);
         let lines: Vec<String> = text.lines().map(String::from).collect();
 
@@ -99,6 +261,8 @@ impl FollowableSource {
 pub struct FollowableStdinSource {
     receiver: mpsc::Receiver<String>,
     closed: bool,
+    /// Lines skipped by the reader thread because they weren't valid UTF-8.
+    dropped: Arc<AtomicU64>,
 }
 
 impl FollowableStdinSource {
@@ -114,10 +278,19 @@ impl FollowableStdinSource {
         Self::from_reader(file)
     }
 
+    /// Spawn a background reader on stdin. Keyboard input comes from the
+    /// console rather than stdin here, so stdin is never redirected.
+    #[cfg(not(unix))]
+    pub fn spawn_stdin() -> Self {
+        Self::from_reader(io::stdin())
+    }
+
     /// Create from any reader. Spawns a background thread that reads lines
     /// and sends them through an mpsc channel.
     pub fn from_reader<R: io::Read + Send + 'static>(reader: R) -> Self {
         let (tx, rx) = mpsc::channel();
+        let dropped = Arc::new(AtomicU64::new(0));
+        let reader_dropped = Arc::clone(&dropped);
         std::thread::spawn(move || {
             let buf_reader = io::BufReader::new(reader);
             for line in buf_reader.lines() {
@@ -127,6 +300,10 @@ impl FollowableStdinSource {
                             break;
                         }
                     }
+                    // The bad line has been consumed; carry on with the next one
+                    Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                        reader_dropped.fetch_add(1, Ordering::Relaxed);
+                    }
                     Err(_) => break,
                 }
             }
@@ -134,6 +311,7 @@ impl FollowableStdinSource {
         Self {
             receiver: rx,
             closed: false,
+            dropped,
         }
     }
 
@@ -176,6 +354,11 @@ impl FollowableStdinSource {
         lines
     }
 
+    /// Lines skipped so far because they weren't valid UTF-8.
+    pub fn dropped_lines(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
     /// Returns true if the stdin pipe has been closed (EOF / writer dropped).
     #[allow(dead_code)]
     pub fn is_closed(&self) -> bool {
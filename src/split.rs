@@ -0,0 +1,59 @@
+use std::process::Command;
+
+/// Terminal multiplexer lumolog is running inside, which can open a new pane
+/// next to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Wezterm,
+}
+
+impl Multiplexer {
+    /// Detect from the environment: `$TMUX` wins over `$WEZTERM_PANE` since
+    /// tmux can run inside WezTerm.
+    pub fn detect() -> Option<Self> {
+        let set = |var| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+        if set("TMUX") {
+            Some(Multiplexer::Tmux)
+        } else if set("WEZTERM_PANE") {
+            Some(Multiplexer::Wezterm)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Wezterm => "wezterm",
+        }
+    }
+
+    /// Process that runs `shell_command` in a new pane to the right.
+    pub fn split_command(self, shell_command: &str) -> Command {
+        let mut cmd = Command::new(self.name());
+        match self {
+            Multiplexer::Tmux => cmd.args(["split-window", "-h", shell_command]),
+            Multiplexer::Wezterm => cmd.args([
+                "cli",
+                "split-pane",
+                "--right",
+                "--",
+                "sh",
+                "-c",
+                shell_command,
+            ]),
+        };
+        cmd
+    }
+}
+
+/// Fill `{}` in a configured command with the token, quoted for `sh`.
+pub fn expand(template: &str, token: &str) -> String {
+    template.replace("{}", &shell_quote(token))
+}
+
+/// Single-quote `s` so the shell treats it as one literal word.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
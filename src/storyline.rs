@@ -0,0 +1,119 @@
+use crate::parser::{LogLevel, ParsedLine, is_blank};
+
+/// One step of the storyline: a run of quieter lines collapsed to a count,
+/// or an error/fatal line (with how many identical ones followed it).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoryRow {
+    Run {
+        level: Option<LogLevel>,
+        count: usize,
+        first_line: usize,
+    },
+    Event {
+        idx: usize,
+        repeats: usize,
+    },
+}
+
+impl StoryRow {
+    /// Original line index Enter jumps to.
+    pub fn target_line(&self) -> usize {
+        match self {
+            StoryRow::Run { first_line, .. } => *first_line,
+            StoryRow::Event { idx, .. } => *idx,
+        }
+    }
+}
+
+fn is_event(level: Option<LogLevel>) -> bool {
+    level.is_some_and(|l| l >= LogLevel::Error)
+}
+
+/// Compress `indices` (in line order) into level runs and error/fatal lines.
+/// A run ends whenever the level changes; back-to-back errors with the same
+/// template fold into one event. Blank lines are left out.
+pub fn build_storyline(lines: &[ParsedLine], indices: &[usize]) -> Vec<StoryRow> {
+    let mut rows: Vec<StoryRow> = Vec::new();
+    for &idx in indices {
+        let line = &lines[idx];
+        if is_blank(&line.raw) {
+            continue;
+        }
+        match rows.last_mut() {
+            Some(StoryRow::Event { idx: prev, repeats })
+                if is_event(line.level)
+                    && lines[*prev].level == line.level
+                    && lines[*prev].template == line.template =>
+            {
+                *repeats += 1;
+            }
+            Some(StoryRow::Run { level, count, .. })
+                if !is_event(line.level) && *level == line.level =>
+            {
+                *count += 1;
+            }
+            _ if is_event(line.level) => rows.push(StoryRow::Event { idx, repeats: 0 }),
+            _ => rows.push(StoryRow::Run {
+                level: line.level,
+                count: 1,
+                first_line: idx,
+            }),
+        }
+    }
+    rows
+}
+
+/// `4812` → `4,812`.
+pub fn group_thousands(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Label for a run: `… 4,812 info …`.
+pub fn run_label(level: Option<LogLevel>, count: usize) -> String {
+    let word = match level {
+        Some(LogLevel::Trace) => "trace",
+        Some(LogLevel::Debug) => "debug",
+        Some(LogLevel::Info) => "info",
+        Some(LogLevel::Warn) => "warn",
+        Some(LogLevel::Error) => "error",
+        Some(LogLevel::Fatal) => "fatal",
+        None => "other",
+    };
+    format!("… {} {word} …", group_thousands(count))
+}
+
+/// The storyline as plain text, one step per line, for pasting into a
+/// write-up.
+pub fn render_storyline(rows: &[StoryRow], lines: &[ParsedLine]) -> String {
+    let mut out = String::new();
+    for row in rows {
+        match row {
+            StoryRow::Run { level, count, .. } => out.push_str(&run_label(*level, *count)),
+            StoryRow::Event { idx, repeats } => {
+                let line = &lines[*idx];
+                out.push_str(&format!("line {}", idx + 1));
+                if let Some(ts) = &line.timestamp {
+                    out.push_str(&format!(" [{ts}]"));
+                }
+                out.push_str(&format!(
+                    " {} {}",
+                    line.level.map_or("", LogLevel::short_name),
+                    line.message
+                ));
+                if *repeats > 0 {
+                    out.push_str(&format!(" (×{})", repeats + 1));
+                }
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
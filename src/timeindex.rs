@@ -1,14 +1,21 @@
-use chrono::NaiveDateTime;
-use std::cell::Cell;
+use chrono::{Datelike, NaiveDateTime};
+use serde::{Deserialize, Serialize};
 
-use crate::parser::ParsedLine;
+use crate::parser::{LogLevel, ParsedLine};
 
 #[derive(Debug, Clone)]
 pub struct TimeIndex {
     /// Per-line resolved timestamp (forward-filled from nearest preceding timestamped line)
     timestamps: Vec<Option<NaiveDateTime>>,
+    /// Per line, whether its timestamp was forward-filled rather than its own.
+    inherited: Vec<bool>,
+    /// Whether lines with an inherited timestamp count in time-range filters
+    /// and sparkline buckets.
+    include_inherited: bool,
     pub min_ts: Option<NaiveDateTime>,
     pub max_ts: Option<NaiveDateTime>,
+    /// Remembers which layouts this source uses, for lines appended later.
+    parser: TimestampParser,
 }
 
 #[derive(Debug, Clone)]
@@ -19,7 +26,16 @@ pub struct SparklineData {
     pub num_buckets: usize,
 }
 
-#[derive(Debug, Clone)]
+impl SparklineData {
+    /// Bucket containing `ts`, clamped to the sparkline's range.
+    pub fn bucket_for(&self, ts: NaiveDateTime) -> Option<usize> {
+        let first_start = *self.bucket_starts.first()?;
+        let offset = (ts - first_start).num_seconds().max(0);
+        Some(((offset / self.bucket_duration_secs) as usize).min(self.num_buckets - 1))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TimeRange {
     pub start: NaiveDateTime,
     pub end: NaiveDateTime,
@@ -28,16 +44,14 @@ pub struct TimeRange {
 #[derive(Debug, Clone)]
 pub struct TimeModeState {
     pub cursor_bucket: usize,
+    /// [`bucket_level_counts`] for `cursor_bucket`, worked out when the
+    /// cursor or the sparkline changes rather than on every frame.
+    pub cursor_levels: Vec<(Option<LogLevel>, usize)>,
     pub range_start: Option<usize>,
     pub dragging: bool,
     pub drag_start: Option<usize>,
 }
 
-// "Winner sticks" cache: after first successful parse, try that format first.
-thread_local! {
-    static LAST_FORMAT_IDX: Cell<Option<usize>> = const { Cell::new(None) };
-}
-
 const FORMAT_STRINGS: &[&str] = &[
     "%Y-%m-%dT%H:%M:%S%.f%:z", // RFC 3339 with offset (colon)
     "%Y-%m-%dT%H:%M:%S%.f%#z", // RFC 3339 with offset (no colon)
@@ -58,31 +72,121 @@ const SYSLOG_MONTHS: &[&str] = &[
     "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
 ];
 
-pub fn parse_timestamp(raw: &str) -> Option<NaiveDateTime> {
-    let raw = raw.trim();
-    if raw.is_empty() {
-        return None;
+/// One way a timestamp can be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /// An entry of `FORMAT_STRINGS`.
+    Format(usize),
+    Epoch,
+    Syslog,
+    Klog,
+}
+
+/// Parses timestamps, trying the layouts already seen in a source before
+/// scanning all of them. Merged logs can mix several layouts, even line by
+/// line, so every layout that has matched is remembered, most recent first.
+#[derive(Debug, Clone)]
+pub struct TimestampParser {
+    recent: Vec<Layout>,
+    /// Year given to syslog and klog timestamps, which don't carry one.
+    year: i32,
+    /// The last of those, to notice the year turning over.
+    last_yearless: Option<NaiveDateTime>,
+}
+
+impl Default for TimestampParser {
+    fn default() -> Self {
+        Self::starting_in(chrono::Local::now().year())
     }
+}
 
-    // Try last successful format first ("winner sticks")
-    let last_idx = LAST_FORMAT_IDX.get();
-    if let Some(idx) = last_idx
-        && let Some(dt) = try_parse_with_format(raw, idx)
-    {
-        return Some(dt);
+impl TimestampParser {
+    /// A parser that puts yearless timestamps in `year` until their months
+    /// wrap around from December to January.
+    pub fn starting_in(year: i32) -> Self {
+        Self {
+            recent: Vec::new(),
+            year,
+            last_yearless: None,
+        }
     }
 
-    // Try all format strings
-    for (i, _fmt) in FORMAT_STRINGS.iter().enumerate() {
-        if Some(i) == last_idx {
-            continue; // Already tried
+    pub fn parse(&mut self, raw: &str) -> Option<NaiveDateTime> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return None;
+        }
+
+        // Only a full match is trusted here: a remembered layout that matches
+        // just a prefix could drop the fraction or offset a later one keeps.
+        for i in 0..self.recent.len() {
+            let layout = self.recent[i];
+            if let Some(dt) = self.try_layout(raw, layout, false) {
+                self.recent[..=i].rotate_right(1);
+                return Some(dt);
+            }
+        }
+
+        let (dt, layout, exact) = self.scan(raw)?;
+        if exact {
+            self.recent.insert(0, layout);
         }
-        if let Some(dt) = try_parse_with_format(raw, i) {
-            LAST_FORMAT_IDX.set(Some(i));
+        Some(dt)
+    }
+
+    /// Try every layout in order, returning the first match and whether it
+    /// covered the whole string.
+    fn scan(&mut self, raw: &str) -> Option<(NaiveDateTime, Layout, bool)> {
+        for i in 0..FORMAT_STRINGS.len() {
+            let layout = Layout::Format(i);
+            if let Some(dt) = self.try_layout(raw, layout, false) {
+                return Some((dt, layout, true));
+            }
+            if let Some(dt) = self.try_layout(raw, layout, true) {
+                return Some((dt, layout, false));
+            }
+        }
+        [Layout::Epoch, Layout::Syslog, Layout::Klog]
+            .into_iter()
+            .find_map(|layout| Some((self.try_layout(raw, layout, false)?, layout, true)))
+    }
+
+    /// `prefix` allows a format string to match the start of `raw` only; the
+    /// other layouts always read from the start and ignore what follows.
+    fn try_layout(&mut self, raw: &str, layout: Layout, prefix: bool) -> Option<NaiveDateTime> {
+        let parse = |year| match layout {
+            Layout::Format(idx) => try_parse_with_format(raw, idx, prefix),
+            Layout::Epoch => try_parse_epoch(raw),
+            // Syslog: "Jan 15 08:30:00"
+            Layout::Syslog => try_parse_syslog(raw, year),
+            // Klog: "0115 08:30:00.000000" (MMDD)
+            Layout::Klog => try_parse_klog(raw, year),
+        };
+        let dt = parse(self.year)?;
+        if !matches!(layout, Layout::Syslog | Layout::Klog) {
             return Some(dt);
         }
+        // Months running backwards by more than half a year mean the log
+        // crossed New Year
+        let dt = match self.last_yearless {
+            Some(prev) if prev.month() > dt.month() + 6 => {
+                self.year += 1;
+                parse(self.year)?
+            }
+            _ => dt,
+        };
+        self.last_yearless = Some(dt);
+        Some(dt)
     }
+}
+
+/// Parse a timestamp without reusing what earlier lines looked like.
+#[allow(dead_code)]
+pub fn parse_timestamp(raw: &str) -> Option<NaiveDateTime> {
+    TimestampParser::default().parse(raw)
+}
 
+fn try_parse_epoch(raw: &str) -> Option<NaiveDateTime> {
     // Try epoch millis (1e12..1e14 range)
     if let Ok(n) = raw.parse::<i64>() {
         if (1_000_000_000_000..100_000_000_000_000).contains(&n) {
@@ -110,26 +214,17 @@ pub fn parse_timestamp(raw: &str) -> Option<NaiveDateTime> {
             }
         }
     }
-
-    // Syslog: "Jan 15 08:30:00" (no year → current year)
-    if let Some(dt) = try_parse_syslog(raw) {
-        return Some(dt);
-    }
-
-    // Klog: "0115 08:30:00.000000" (MMDD, no year → current year)
-    if let Some(dt) = try_parse_klog(raw) {
-        return Some(dt);
-    }
-
     None
 }
 
-fn try_parse_with_format(raw: &str, idx: usize) -> Option<NaiveDateTime> {
+fn try_parse_with_format(raw: &str, idx: usize, prefix: bool) -> Option<NaiveDateTime> {
     let fmt = FORMAT_STRINGS[idx];
     // Some formats include %z/%:z which produce DateTime<FixedOffset>
     if fmt.contains("%z") || fmt.contains("%:z") || fmt.contains("%#z") {
-        if let Ok(dt) = chrono::DateTime::parse_from_str(raw, fmt) {
-            return Some(dt.naive_utc());
+        if !prefix {
+            return chrono::DateTime::parse_from_str(raw, fmt)
+                .ok()
+                .map(|dt| dt.naive_utc());
         }
         // Try prefix match: timestamp may be followed by other content
         // For fixed-offset formats, try increasingly longer prefixes
@@ -144,8 +239,8 @@ fn try_parse_with_format(raw: &str, idx: usize) -> Option<NaiveDateTime> {
         return None;
     }
     // NaiveDateTime formats
-    if let Ok(dt) = NaiveDateTime::parse_from_str(raw, fmt) {
-        return Some(dt);
+    if !prefix {
+        return NaiveDateTime::parse_from_str(raw, fmt).ok();
     }
     // Try prefix match for naive formats
     for end in (19..=raw.len().min(35)).rev() {
@@ -159,7 +254,7 @@ fn try_parse_with_format(raw: &str, idx: usize) -> Option<NaiveDateTime> {
     None
 }
 
-fn try_parse_syslog(raw: &str) -> Option<NaiveDateTime> {
+fn try_parse_syslog(raw: &str, year: i32) -> Option<NaiveDateTime> {
     // Format: "Jan 15 08:30:00" or "Jan  5 08:30:00"
     if raw.len() < 15 {
         return None;
@@ -179,14 +274,12 @@ fn try_parse_syslog(raw: &str) -> Option<NaiveDateTime> {
     let min: u32 = time_str[3..5].parse().ok()?;
     let sec: u32 = time_str[6..8].parse().ok()?;
 
-    use chrono::Datelike;
-    let year = chrono::Local::now().year();
     let date = chrono::NaiveDate::from_ymd_opt(year, month, day)?;
     let time = chrono::NaiveTime::from_hms_opt(hour, min, sec)?;
     Some(NaiveDateTime::new(date, time))
 }
 
-fn try_parse_klog(raw: &str) -> Option<NaiveDateTime> {
+fn try_parse_klog(raw: &str, year: i32) -> Option<NaiveDateTime> {
     // Format: "0115 08:30:00.000000" (MMDD HH:MM:SS.micros)
     if raw.len() < 15 {
         return None;
@@ -220,23 +313,49 @@ fn try_parse_klog(raw: &str) -> Option<NaiveDateTime> {
         0
     };
 
-    use chrono::Datelike;
-    let year = chrono::Local::now().year();
     let date = chrono::NaiveDate::from_ymd_opt(year, month, day)?;
     let time = chrono::NaiveTime::from_hms_micro_opt(hour, min, sec, micros)?;
     Some(NaiveDateTime::new(date, time))
 }
 
 pub fn build_time_index(lines: &[ParsedLine]) -> TimeIndex {
+    build_time_index_as_of(lines, chrono::Local::now().naive_local())
+}
+
+/// Like [`build_time_index`], for a log last written at `reference`.
+/// Syslog and klog timestamps carry no year, so they are counted back from
+/// it: the newest one lands in the reference's year, or the year before when
+/// that would put it in the future, and earlier ones go back a year at each
+/// December → January turn.
+pub fn build_time_index_as_of(lines: &[ParsedLine], reference: NaiveDateTime) -> TimeIndex {
+    let first_guess = reference.year();
+    let index = index_lines(lines, TimestampParser::starting_in(first_guess));
+    let Some(newest) = index.parser.last_yearless else {
+        return index;
+    };
+    let mut end_year = reference.year();
+    // A day of slack for clocks and time zones
+    if newest
+        .with_year(end_year)
+        .is_none_or(|t| t > reference + chrono::Duration::days(1))
+    {
+        end_year -= 1;
+    }
+    let start_year = end_year - (index.parser.year - first_guess);
+    if start_year == first_guess {
+        index
+    } else {
+        index_lines(lines, TimestampParser::starting_in(start_year))
+    }
+}
+
+fn index_lines(lines: &[ParsedLine], mut parser: TimestampParser) -> TimeIndex {
     let mut timestamps: Vec<Option<NaiveDateTime>> = Vec::with_capacity(lines.len());
     let mut min_ts: Option<NaiveDateTime> = None;
     let mut max_ts: Option<NaiveDateTime> = None;
 
-    // Reset the format cache at the start of building an index
-    LAST_FORMAT_IDX.set(None);
-
     for line in lines {
-        let ts = line.timestamp.as_ref().and_then(|s| parse_timestamp(s));
+        let ts = line.timestamp.as_ref().and_then(|s| parser.parse(s));
         if let Some(t) = ts {
             match min_ts {
                 None => min_ts = Some(t),
@@ -253,19 +372,24 @@ pub fn build_time_index(lines: &[ParsedLine]) -> TimeIndex {
     }
 
     // Forward-fill: lines without a timestamp inherit from the nearest preceding timestamped line
+    let mut inherited = vec![false; timestamps.len()];
     let mut last_ts: Option<NaiveDateTime> = None;
-    for ts in &mut timestamps {
+    for (ts, inherited) in timestamps.iter_mut().zip(&mut inherited) {
         if ts.is_some() {
             last_ts = *ts;
         } else if last_ts.is_some() {
             *ts = last_ts;
+            *inherited = true;
         }
     }
 
     TimeIndex {
         timestamps,
+        inherited,
+        include_inherited: true,
         min_ts,
         max_ts,
+        parser,
     }
 }
 
@@ -274,11 +398,43 @@ impl TimeIndex {
         self.min_ts.is_some() && self.max_ts.is_some()
     }
 
+    /// Whether any timestamp had its year guessed (syslog, klog).
+    pub fn has_yearless(&self) -> bool {
+        self.parser.last_yearless.is_some()
+    }
+
+    /// Whether the stamped lines span more than one calendar day.
+    pub fn is_multi_day(&self) -> bool {
+        matches!((self.min_ts, self.max_ts), (Some(min), Some(max)) if is_multi_day(min, max))
+    }
+
     pub fn timestamp_at(&self, idx: usize) -> Option<NaiveDateTime> {
         self.timestamps.get(idx).copied().flatten()
     }
 
-    #[allow(dead_code)]
+    /// Whether line `idx` has no timestamp of its own and shows the one
+    /// before it.
+    pub fn is_inherited(&self, idx: usize) -> bool {
+        self.inherited.get(idx).copied().unwrap_or(false)
+    }
+
+    pub fn include_inherited(&self) -> bool {
+        self.include_inherited
+    }
+
+    pub fn set_include_inherited(&mut self, include: bool) {
+        self.include_inherited = include;
+    }
+
+    /// The time line `idx` is filtered and bucketed by: its timestamp, unless
+    /// that is inherited and inherited ones are left out.
+    pub fn filter_time_at(&self, idx: usize) -> Option<NaiveDateTime> {
+        if !self.include_inherited && self.is_inherited(idx) {
+            return None;
+        }
+        self.timestamp_at(idx)
+    }
+
     pub fn len(&self) -> usize {
         self.timestamps.len()
     }
@@ -292,7 +448,7 @@ impl TimeIndex {
     pub fn append(&mut self, lines: &[ParsedLine]) {
         let mut last_ts = self.timestamps.last().copied().flatten();
         for line in lines {
-            let ts = line.timestamp.as_ref().and_then(|s| parse_timestamp(s));
+            let ts = line.timestamp.as_ref().and_then(|s| self.parser.parse(s));
             let resolved = ts.or(last_ts);
             if let Some(t) = ts {
                 match self.min_ts {
@@ -309,6 +465,7 @@ impl TimeIndex {
             if resolved.is_some() {
                 last_ts = resolved;
             }
+            self.inherited.push(ts.is_none() && resolved.is_some());
             self.timestamps.push(resolved);
         }
     }
@@ -326,27 +483,21 @@ pub fn compute_sparkline(index: &TimeIndex, num_buckets: usize) -> Option<Sparkl
     let bucket_duration_secs = (total_duration as f64 / num_buckets as f64).ceil() as i64;
     let bucket_duration_secs = bucket_duration_secs.max(1);
 
-    let mut buckets = vec![0u64; num_buckets];
-    let mut bucket_starts = Vec::with_capacity(num_buckets);
-
-    for i in 0..num_buckets {
-        let offset = chrono::Duration::seconds(bucket_duration_secs * i as i64);
-        bucket_starts.push(min_ts + offset);
-    }
-
-    for t in index.timestamps.iter().flatten() {
-        let offset_secs = (*t - min_ts).num_seconds().max(0);
-        let bucket_idx = (offset_secs / bucket_duration_secs) as usize;
-        let bucket_idx = bucket_idx.min(num_buckets - 1);
-        buckets[bucket_idx] += 1;
-    }
-
-    Some(SparklineData {
-        buckets,
+    let bucket_starts = (0..num_buckets)
+        .map(|i| min_ts + chrono::Duration::seconds(bucket_duration_secs * i as i64))
+        .collect();
+    let mut sparkline = SparklineData {
+        buckets: vec![0u64; num_buckets],
         bucket_starts,
         bucket_duration_secs,
         num_buckets,
-    })
+    };
+    for t in (0..index.len()).filter_map(|i| index.filter_time_at(i)) {
+        if let Some(bucket) = sparkline.bucket_for(t) {
+            sparkline.buckets[bucket] += 1;
+        }
+    }
+    Some(sparkline)
 }
 
 pub fn bucket_range_to_time_range(
@@ -367,12 +518,49 @@ pub fn bucket_range_to_time_range(
     })
 }
 
+/// Level breakdown of the lines counted in sparkline bucket `bucket`, most
+/// severe first, with unleveled lines last. Zero counts are left out.
+pub fn bucket_level_counts(
+    index: &TimeIndex,
+    sparkline: &SparklineData,
+    lines: &[ParsedLine],
+    bucket: usize,
+) -> Vec<(Option<LogLevel>, usize)> {
+    // Fatal..Trace, then no level
+    let mut counts = [0usize; 7];
+    for (i, line) in lines.iter().enumerate() {
+        let Some(ts) = index.filter_time_at(i) else {
+            continue;
+        };
+        if sparkline.bucket_for(ts) == Some(bucket) {
+            let slot = line
+                .level
+                .map_or(6, |l| LogLevel::Fatal as usize - l as usize);
+            counts[slot] += 1;
+        }
+    }
+    const ORDER: [Option<LogLevel>; 7] = [
+        Some(LogLevel::Fatal),
+        Some(LogLevel::Error),
+        Some(LogLevel::Warn),
+        Some(LogLevel::Info),
+        Some(LogLevel::Debug),
+        Some(LogLevel::Trace),
+        None,
+    ];
+    ORDER
+        .into_iter()
+        .zip(counts)
+        .filter(|&(_, n)| n > 0)
+        .collect()
+}
+
 pub fn filter_by_time_range(index: &TimeIndex, range: &TimeRange, indices: &[usize]) -> Vec<usize> {
     indices
         .iter()
         .copied()
         .filter(|&i| {
-            if let Some(ts) = index.timestamp_at(i) {
+            if let Some(ts) = index.filter_time_at(i) {
                 ts >= range.start && ts <= range.end
             } else {
                 false
@@ -381,6 +569,24 @@ pub fn filter_by_time_range(index: &TimeIndex, range: &TimeRange, indices: &[usi
         .collect()
 }
 
+/// A time typed on the command line (`--goto-ts`): anything a log timestamp
+/// can be, or a date and time to the minute (`2024-01-15T08:30`,
+/// `2024-01-15 08:30`), or a bare date for midnight.
+pub fn parse_user_time(text: &str) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    parse_timestamp(text)
+        .or_else(|| {
+            ["%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+                .iter()
+                .find_map(|fmt| NaiveDateTime::parse_from_str(text, fmt).ok())
+        })
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+}
+
 /// Format a NaiveDateTime for display in the sparkline.
 /// Uses "HH:MM" for same-day, "MM-DD HH:MM" for multi-day.
 pub fn format_sparkline_time(dt: NaiveDateTime, multi_day: bool) -> String {
@@ -0,0 +1,197 @@
+use crate::parser::ParsedLine;
+use crate::timeindex::TimeIndex;
+use std::collections::{HashMap, HashSet};
+
+/// Field names carrying OpenTelemetry / W3C trace context, as the various
+/// SDKs and log bridges spell them.
+const TRACE_KEYS: &[&str] = &["trace_id", "traceId", "trace.id", "TraceId", "traceid"];
+const SPAN_KEYS: &[&str] = &["span_id", "spanId", "span.id", "SpanId", "spanid"];
+const PARENT_KEYS: &[&str] = &[
+    "parent_span_id",
+    "parentSpanId",
+    "parent_id",
+    "parent.id",
+    "ParentSpanId",
+];
+
+/// One row of the trace tree: a trace, a span nested `depth` levels under it,
+/// or a log line belonging to the span above it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceRow {
+    Trace {
+        trace_id: String,
+        lines: usize,
+        first_line: usize,
+        duration: Option<chrono::Duration>,
+    },
+    Span {
+        span_id: String,
+        depth: usize,
+        lines: usize,
+        first_line: usize,
+        duration: Option<chrono::Duration>,
+    },
+    Line {
+        idx: usize,
+        depth: usize,
+    },
+}
+
+impl TraceRow {
+    /// Original line index Enter jumps to.
+    pub fn target_line(&self) -> usize {
+        match self {
+            TraceRow::Trace { first_line, .. } | TraceRow::Span { first_line, .. } => *first_line,
+            TraceRow::Line { idx, .. } => *idx,
+        }
+    }
+}
+
+/// Value of the first of `keys` present in the line's extracted fields, with
+/// JSON string quotes removed.
+fn field<'a>(line: &'a ParsedLine, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| {
+        line.extra_fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.trim_matches('"'))
+            .filter(|v| !v.is_empty())
+    })
+}
+
+#[derive(Default)]
+struct SpanAcc {
+    parent: Option<String>,
+    lines: Vec<usize>,
+}
+
+#[derive(Default)]
+struct TraceAcc {
+    /// Lines with a trace id but no span id.
+    loose: Vec<usize>,
+    spans: Vec<(String, SpanAcc)>,
+    span_pos: HashMap<String, usize>,
+}
+
+/// First-to-last timestamp span of `lines`, when at least two have timestamps.
+fn duration(lines: &[usize], index: Option<&TimeIndex>) -> Option<chrono::Duration> {
+    let index = index?;
+    let times: Vec<_> = lines
+        .iter()
+        .filter_map(|&i| index.timestamp_at(i))
+        .collect();
+    let first = times.iter().min()?;
+    let last = times.iter().max()?;
+    (times.len() > 1).then(|| *last - *first)
+}
+
+/// Group lines carrying a trace id into trace → span → line rows. Traces and
+/// sibling spans are ordered by their first line; a span whose parent never
+/// logs is shown at the top of its trace.
+pub fn build_trace_rows(lines: &[ParsedLine], index: Option<&TimeIndex>) -> Vec<TraceRow> {
+    let mut traces: Vec<(String, TraceAcc)> = Vec::new();
+    let mut trace_pos: HashMap<String, usize> = HashMap::new();
+
+    for (idx, line) in lines.iter().enumerate() {
+        let Some(trace_id) = field(line, TRACE_KEYS) else {
+            continue;
+        };
+        let t = *trace_pos.entry(trace_id.to_string()).or_insert_with(|| {
+            traces.push((trace_id.to_string(), TraceAcc::default()));
+            traces.len() - 1
+        });
+        let trace = &mut traces[t].1;
+        let Some(span_id) = field(line, SPAN_KEYS) else {
+            trace.loose.push(idx);
+            continue;
+        };
+        let s = *trace
+            .span_pos
+            .entry(span_id.to_string())
+            .or_insert_with(|| {
+                trace.spans.push((span_id.to_string(), SpanAcc::default()));
+                trace.spans.len() - 1
+            });
+        let span = &mut trace.spans[s].1;
+        if span.parent.is_none() {
+            span.parent = field(line, PARENT_KEYS).map(str::to_string);
+        }
+        span.lines.push(idx);
+    }
+
+    let mut rows = Vec::new();
+    for (trace_id, trace) in &traces {
+        let mut all: Vec<usize> = trace.loose.clone();
+        all.extend(
+            trace
+                .spans
+                .iter()
+                .flat_map(|(_, s)| s.lines.iter().copied()),
+        );
+        all.sort_unstable();
+        rows.push(TraceRow::Trace {
+            trace_id: trace_id.clone(),
+            lines: all.len(),
+            first_line: all[0],
+            duration: duration(&all, index),
+        });
+        rows.extend(
+            trace
+                .loose
+                .iter()
+                .map(|&idx| TraceRow::Line { idx, depth: 1 }),
+        );
+
+        // Spans are already in first-line order, so children come out sorted
+        let mut children: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut roots = Vec::new();
+        for (i, (span_id, span)) in trace.spans.iter().enumerate() {
+            match span.parent.as_deref() {
+                Some(p) if p != span_id && trace.span_pos.contains_key(p) => {
+                    children.entry(p).or_default().push(i)
+                }
+                _ => roots.push(i),
+            }
+        }
+
+        // Spans caught in a parent cycle have no root; they follow as roots
+        let mut visited = HashSet::new();
+        let mut stack = Vec::new();
+        for root in roots.into_iter().chain(0..trace.spans.len()) {
+            stack.push((root, 1));
+            while let Some((i, depth)) = stack.pop() {
+                if !visited.insert(i) {
+                    continue;
+                }
+                let (span_id, span) = &trace.spans[i];
+                rows.push(TraceRow::Span {
+                    span_id: span_id.clone(),
+                    depth,
+                    lines: span.lines.len(),
+                    first_line: span.lines[0],
+                    duration: duration(&span.lines, index),
+                });
+                rows.extend(span.lines.iter().map(|&idx| TraceRow::Line {
+                    idx,
+                    depth: depth + 1,
+                }));
+                if let Some(kids) = children.get(span_id.as_str()) {
+                    stack.extend(kids.iter().rev().map(|&k| (k, depth + 1)));
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Short human duration: `850ms`, `2.4s`, `3m12s`.
+pub fn format_duration(d: chrono::Duration) -> String {
+    let ms = d.num_milliseconds();
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m{:02}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
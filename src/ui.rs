@@ -1,54 +1,354 @@
 use ratatui::Frame;
-use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Layout, Position, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 
-use crate::app::{App, AppMode};
+use crate::app::{App, AppMode, MAX_PINS};
+use crate::config::BlankLines;
+use crate::decode::{Decoded, pretty};
+use crate::diff::{self, DiffOp};
+use crate::filter;
+use crate::hexdump::{self, ByteClass};
 use crate::highlighter::{
-    TokenKind, apply_search_highlight, highlight_line, highlight_line_expanded, level_badge_style,
-    tokenize_with_metadata,
+    LevelBadge, TokenKind, access_line_text, apply_pattern_highlight, apply_regex_search_highlight,
+    apply_search_highlight_with, extra_fields_text, highlight_line_expanded,
+    highlight_line_expanded_with, highlight_line_visible_with, level_badge_style,
+    shows_level_badge, tokenize_with_metadata, visualize_whitespace,
 };
-use crate::parser::LogFormat;
+use crate::linestats::OVERSIZED_BYTES;
+use crate::loader::LoadProgress;
 use crate::parser::LogLevel;
+use crate::parser::{LogFormat, is_blank};
+use crate::picker::{FilePicker, PickerEntry};
+use crate::storyline::{self, StoryRow};
 use crate::timeindex;
+use crate::traces::{self, TraceRow};
+use crate::units::{Dimension, Quantity, format_quantity};
 
 const SPARKLINE_CHARS: &[char] = &[' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
-pub fn render(frame: &mut Frame, app: &mut App) {
+const SPINNER: &[char] = &['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Shown while a file opens in the background: a spinner, how much has been
+/// read, and how to back out. `tick` advances the spinner.
+pub fn render_loading(frame: &mut Frame, name: &str, progress: &LoadProgress, tick: usize) {
     let area = frame.area();
-    app.tick_yank_flash();
+    let size = |bytes: u64| {
+        format_quantity(Quantity {
+            value: bytes as f64,
+            dimension: Dimension::Size,
+        })
+    };
+    let lines_read = storyline::group_thousands(progress.lines());
+    let status = if progress.is_parsing() {
+        format!("Parsing {lines_read} lines")
+    } else if progress.total_bytes() > 0 {
+        format!(
+            "Read {} of {}, {lines_read} lines",
+            size(progress.bytes()),
+            size(progress.total_bytes())
+        )
+    } else {
+        format!("Read {}, {lines_read} lines", size(progress.bytes()))
+    };
+    let rows = vec![
+        Line::from(vec![
+            Span::styled(
+                format!("{} ", SPINNER[tick % SPINNER.len()]),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(
+                format!("Opening {name}"),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(Span::styled(status, Style::default().fg(Color::Gray))),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Esc to cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    let height = (rows.len() as u16).min(area.height);
+    let message_area = Rect {
+        y: area.y + (area.height - height) / 2,
+        height,
+        ..area
+    };
+    frame.render_widget(
+        Paragraph::new(rows).alignment(ratatui::layout::Alignment::Center),
+        message_area,
+    );
+}
+
+/// The file chooser for a bare launch: the search query, matching files
+/// with their size and age, and a footer with counts or the last error.
+pub fn render_picker(frame: &mut Frame, picker: &FilePicker) {
+    let area = frame.area();
+    let [title_area, input_area, list_area, footer_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(area);
 
-    let filter_height =
-        if app.is_filter_mode() || app.mode() == AppMode::Ask || app.mode() == AppMode::Analyze {
-            1
+    let title = if picker.is_recent_only() {
+        " Recently opened "
+    } else {
+        " Open a log file "
+    };
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(title, Style::default().add_modifier(Modifier::BOLD)),
+            Span::styled(
+                picker.root().display().to_string(),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])),
+        title_area,
+    );
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(" > ", Style::default().fg(Color::Cyan)),
+            Span::raw(picker.query()),
+            Span::styled("_", Style::default().fg(Color::DarkGray)),
+        ])),
+        input_area,
+    );
+
+    // With nothing typed, recently opened files come first under their own
+    // header; search results are ranked together
+    enum Row<'a> {
+        Header(&'static str),
+        Entry(usize, &'a PickerEntry),
+    }
+    let grouped = picker.query().is_empty() && !picker.is_recent_only();
+    let mut entries: Vec<Row> = Vec::new();
+    let mut selected_row = 0;
+    let mut prev_recent = false;
+    for (pos, entry) in picker.matches().enumerate() {
+        if grouped && pos == 0 && entry.recent {
+            entries.push(Row::Header("Recent"));
+        } else if grouped && prev_recent && !entry.recent {
+            entries.push(Row::Header("In this directory"));
+        }
+        prev_recent = entry.recent;
+        if pos == picker.selected() {
+            selected_row = entries.len();
+        }
+        entries.push(Row::Entry(pos, entry));
+    }
+
+    let width = list_area.width as usize;
+    let height = list_area.height as usize;
+    let scroll = (selected_row + 1).saturating_sub(height);
+    let mut rows: Vec<Line> = Vec::new();
+    for row in entries.iter().skip(scroll).take(height) {
+        let (pos, entry) = match *row {
+            Row::Header(name) => {
+                rows.push(Line::from(Span::styled(
+                    format!(" {name}"),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                continue;
+            }
+            Row::Entry(pos, entry) => (pos, entry),
+        };
+        let size = format_quantity(Quantity {
+            value: entry.size as f64,
+            dimension: Dimension::Size,
+        });
+        let modified = entry.modified.map_or_else(String::new, |t| {
+            chrono::DateTime::<chrono::Local>::from(t)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        });
+        let details = format!("{size:>8}  {modified:<16} ");
+        let name = truncate_to_width(
+            &entry.display(),
+            width.saturating_sub(text_width(&details) + 3),
+        );
+        let pad = " ".repeat(width.saturating_sub(text_width(&name) + text_width(&details) + 1));
+        let (name_style, details_style) = if pos == picker.selected() {
+            (
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+                Style::default().fg(Color::Black).bg(Color::Cyan),
+            )
+        } else if entry.is_log {
+            (
+                Style::default().fg(Color::White),
+                Style::default().fg(Color::DarkGray),
+            )
         } else {
-            0
+            (
+                Style::default().fg(Color::Gray),
+                Style::default().fg(Color::DarkGray),
+            )
         };
-    let sparkline_height: u16 = if !app.is_sparkline_visible() {
-        0
-    } else if app.mode() == AppMode::TimeRange {
-        2
+        rows.push(Line::from(vec![
+            Span::styled(" ", details_style),
+            Span::styled(name, name_style),
+            Span::styled(pad, details_style),
+            Span::styled(details, details_style),
+        ]));
+    }
+    if rows.is_empty() {
+        let hint = if picker.candidate_count() > 0 {
+            " No files match"
+        } else if picker.is_recent_only() {
+            " No recently opened files"
+        } else if picker.shows_all() {
+            " No files here"
+        } else {
+            " No log files here, Tab to show all files"
+        };
+        rows.push(Line::from(Span::styled(
+            hint,
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    frame.render_widget(Paragraph::new(rows), list_area);
+
+    let footer = match picker.error() {
+        Some(error) => Line::from(Span::styled(
+            format!(" {error}"),
+            Style::default().fg(Color::Red),
+        )),
+        None if picker.is_recent_only() => Line::from(Span::styled(
+            format!(
+                " {} of {} recent files  Enter open  Esc quit",
+                storyline::group_thousands(picker.match_count()),
+                storyline::group_thousands(picker.candidate_count()),
+            ),
+            Style::default().fg(Color::DarkGray),
+        )),
+        None => {
+            let kind = if picker.shows_all() {
+                "files"
+            } else {
+                "log files"
+            };
+            let more = if picker.is_truncated() { "+" } else { "" };
+            Line::from(Span::styled(
+                format!(
+                    " {} of {}{more} {kind}  Enter open  Tab {}  Esc quit",
+                    storyline::group_thousands(picker.match_count()),
+                    storyline::group_thousands(picker.candidate_count()),
+                    if picker.shows_all() {
+                        "logs only"
+                    } else {
+                        "all files"
+                    },
+                ),
+                Style::default().fg(Color::DarkGray),
+            ))
+        }
+    };
+    frame.render_widget(Paragraph::new(footer), footer_area);
+}
+
+/// The screen split top to bottom: sparkline, pinned lines, log view, input
+/// bar, level stats, status bar. Hit-testing uses the same split as drawing.
+/// `has_stats` says whether any line has a level; it's passed in because
+/// counting them is a full pass over the lines.
+fn screen_areas(app: &App, area: Rect, has_stats: bool) -> [Rect; 7] {
+    let filter_height = if app.is_filter_mode()
+        || matches!(
+            app.mode(),
+            AppMode::Ask | AppMode::Analyze | AppMode::Annotate | AppMode::SaveFilter
+        ) {
+        1
     } else {
+        0
+    };
+    let stats_height: u16 = if has_stats && app.pane_layout().stats {
         1
+    } else {
+        0
     };
-    let level_counts = app.level_counts();
-    let stats_height: u16 = if level_counts.is_empty() { 0 } else { 1 };
+    let breadcrumbs_height = if app.breadcrumbs().is_empty() { 0 } else { 1 };
+    Layout::vertical([
+        Constraint::Length(sparkline_height(app)),
+        Constraint::Length(pinned_height(app)),
+        Constraint::Fill(1),
+        Constraint::Length(filter_height),
+        Constraint::Length(stats_height),
+        Constraint::Length(breadcrumbs_height),
+        Constraint::Length(1),
+    ])
+    .areas(area)
+}
+
+/// The breadcrumb chips, numbered for Alt-1 to Alt-9: ` 1 level≥WRN ✕ `.
+fn breadcrumb_chips(app: &App) -> Vec<String> {
+    app.breadcrumbs()
+        .iter()
+        .enumerate()
+        .map(|(i, (_, label))| format!(" {} {label} ✕ ", i + 1))
+        .collect()
+}
 
+fn render_breadcrumbs(frame: &mut Frame, app: &App, area: Rect) {
+    let mut spans: Vec<Span> = Vec::new();
+    for chip in breadcrumb_chips(app) {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            chip,
+            Style::default().fg(Color::Black).bg(Color::Cyan),
+        ));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// Which breadcrumb chip (1-based) a click landed on.
+pub fn breadcrumb_at_position(app: &App, column: u16, row: u16, area: Rect) -> Option<usize> {
+    let has_stats = !app.level_counts().is_empty();
+    let [_, _, _, _, _, crumbs_area, _] = screen_areas(app, area, has_stats);
+    if crumbs_area.height == 0 || row != crumbs_area.y {
+        return None;
+    }
+    let mut col = crumbs_area.x;
+    for (i, chip) in breadcrumb_chips(app).iter().enumerate() {
+        col += 1; // separator space
+        let width = text_width(chip) as u16;
+        if column >= col && column < col + width {
+            return Some(i + 1);
+        }
+        col += width;
+    }
+    None
+}
+
+pub fn render(frame: &mut Frame, app: &mut App) {
+    render_view(frame, app);
+    if app.is_high_contrast() {
+        high_contrast(frame.buffer_mut());
+    }
+}
+
+fn render_view(frame: &mut Frame, app: &mut App) {
+    let area = frame.area();
+    app.tick_yank_flash();
+
+    let sparkline_height = sparkline_height(app);
+    let level_counts = app.level_counts();
     let [
         sparkline_area,
+        pinned_area,
         main_area,
         filter_area,
         stats_area,
+        breadcrumbs_area,
         status_area,
-    ] = Layout::vertical([
-        Constraint::Length(sparkline_height),
-        Constraint::Fill(1),
-        Constraint::Length(filter_height),
-        Constraint::Length(stats_height),
-        Constraint::Length(1),
-    ])
-    .areas(area);
+    ] = screen_areas(app, area, !level_counts.is_empty());
 
     // Update sparkline width so App can recompute bucket data
     if sparkline_height > 0 {
@@ -69,48 +369,191 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     // Compute line number width from total line count
     let line_num_width = format!("{}", app.total_lines_unfiltered()).len().max(3);
 
-    let search_pattern: Option<&str> = if !app.filter_pattern().is_empty() && !app.is_fuzzy() {
+    if !app.pins().is_empty() {
+        render_pinned(frame, app, pinned_area, line_num_width);
+    }
+
+    // Only a single pattern is highlighted, not `a | b` alternatives
+    let single = !app.filter_pattern().contains(filter::OR_SEPARATOR);
+    let search_regex = filter::regex_filter(app.filter_pattern())
+        .and_then(Result::ok)
+        .filter(|_| single);
+    let search_pattern: Option<&str> = if !app.filter_pattern().is_empty()
+        && single
+        && !app.is_fuzzy()
+        && search_regex.is_none()
+        && app.filter_error().is_none()
+        && !app.filter_pattern().starts_with(filter::EXCLUDE_PREFIX)
+    {
         Some(app.filter_pattern())
     } else {
         None
     };
 
+    let case_sensitive = search_pattern.is_some_and(|p| app.case_mode().is_sensitive(p));
+    let whole_word = app.is_whole_word();
+
     let cursor_entry_index: Option<usize> = if app.is_cursor_mode() {
         Some(app.cursor_position().saturating_sub(app.scroll_offset()))
     } else {
         None
     };
     let cursor_bg = Color::DarkGray;
+    let mark = app.mark();
+    let gutter_style = |line_num: usize| {
+        if mark == Some(line_num - 1) {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        }
+    };
+    // Flag in the gutter separator column for annotated lines and lines a
+    // marker rule matched. Annotations win since the user put them there.
+    // With text cues, the cursor, mark, context lines and highlighted hits
+    // get a symbol there too, and rules their label's initial.
+    let text_cues = app.is_text_cues();
+    let gutter_flag = |line_num: usize, is_cursor: bool| {
+        let cue = |symbol: &str| {
+            Span::styled(
+                symbol.to_string(),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            )
+        };
+        if text_cues && is_cursor {
+            cue(">")
+        } else if app.annotation_at(line_num - 1).is_some() {
+            Span::styled(
+                "*",
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else if let Some(rule) = app.marker_at(line_num - 1) {
+            match rule.label.chars().next().filter(|_| text_cues) {
+                Some(initial) => Span::styled(
+                    initial.to_uppercase().to_string(),
+                    Style::default().fg(rule.color).add_modifier(Modifier::BOLD),
+                ),
+                None => Span::styled("▸", Style::default().fg(rule.color)),
+            }
+        } else if text_cues && mark == Some(line_num - 1) {
+            cue("'")
+        } else if text_cues && app.is_context_line(line_num - 1) {
+            cue("-")
+        } else if text_cues && app.is_highlight_hit(line_num - 1) {
+            cue(":")
+        } else {
+            Span::raw(" ")
+        }
+    };
+    // Formats without a `[LVL]` badge get a level tag after the gutter
+    let tag_width = level_tag_width(app);
+    let level_tag = |parsed: &crate::parser::ParsedLine| match parsed.level {
+        Some(level) => vec![
+            Span::styled(level.short_name(), level_badge_style(Some(level))),
+            Span::raw(" "),
+        ],
+        None => vec![Span::raw(" ".repeat(tag_width))],
+    };
 
-    let all_display_lines: Vec<Line> = if app.is_pretty() {
-        app.visible_parsed_lines_numbered()
-            .iter()
-            .enumerate()
-            .flat_map(|(entry_idx, (line_num, parsed))| {
-                let is_cursor = cursor_entry_index == Some(entry_idx);
-                let mut expanded = highlight_line_expanded(parsed, true);
+    // Display columns of the line text that can be on screen, so very long
+    // lines only get tokenized where the user is looking.
+    let content_width = main_area.width.saturating_sub(2) as usize;
+    let visible_cols = if app.is_wrap() {
+        0..content_width.saturating_mul(content_height)
+    } else {
+        let first = app
+            .h_scroll()
+            .saturating_sub(line_num_width + 1 + tag_width);
+        first..app.h_scroll() + content_width
+    };
+
+    let all_display_lines: Vec<Line> = app
+        .visible_parsed_lines_numbered()
+        .iter()
+        .enumerate()
+        .flat_map(|(entry_idx, (line_num, parsed))| {
+            let is_cursor = cursor_entry_index == Some(entry_idx);
+            if app.is_line_pretty(line_num - 1) {
+                let mut expanded = highlight_line_expanded_with(parsed, true, app.level_badge());
+                for (re, color) in app.highlight_rules() {
+                    expanded = expanded
+                        .into_iter()
+                        .map(|l| apply_pattern_highlight(l, re, *color))
+                        .collect();
+                }
                 if let Some(pattern) = search_pattern {
                     expanded = expanded
                         .into_iter()
-                        .map(|l| apply_search_highlight(l, pattern))
+                        .map(|l| {
+                            apply_search_highlight_with(l, pattern, case_sensitive, whole_word)
+                        })
+                        .collect();
+                }
+                if let Some(re) = &search_regex {
+                    expanded = expanded
+                        .into_iter()
+                        .map(|l| apply_regex_search_highlight(l, re))
                         .collect();
                 }
                 // Add line number prefix only to the first line of each expanded group
                 if let Some(first) = expanded.first_mut() {
                     let prefix = Span::styled(
-                        format!("{:>width$} ", line_num, width = line_num_width),
-                        Style::default().fg(Color::DarkGray),
+                        format!("{:>width$}", line_num, width = line_num_width),
+                        gutter_style(*line_num),
                     );
-                    first.spans.insert(0, prefix);
+                    if tag_width > 0 {
+                        first.spans.splice(0..0, level_tag(parsed));
+                    }
+                    first
+                        .spans
+                        .splice(0..0, [prefix, gutter_flag(*line_num, is_cursor)]);
                 }
                 // Add blank prefix to continuation lines for alignment
                 for line in expanded.iter_mut().skip(1) {
                     let blank_prefix = Span::styled(
-                        format!("{:>width$} ", "", width = line_num_width),
+                        format!("{:>width$} ", "", width = line_num_width + tag_width),
                         Style::default().fg(Color::DarkGray),
                     );
                     line.spans.insert(0, blank_prefix);
                 }
+                // Folded continuation lines, each under its own line number
+                for idx in app.continuation_lines(line_num - 1) {
+                    let Some(continued) = app.parsed_line(idx) else {
+                        break;
+                    };
+                    let mut line =
+                        highlight_line_visible_with(continued, &(0..usize::MAX), app.level_badge());
+                    for (re, color) in app.highlight_rules() {
+                        line = apply_pattern_highlight(line, re, *color);
+                    }
+                    if let Some(pattern) = search_pattern {
+                        line =
+                            apply_search_highlight_with(line, pattern, case_sensitive, whole_word);
+                    }
+                    if let Some(re) = &search_regex {
+                        line = apply_regex_search_highlight(line, re);
+                    }
+                    line.spans.splice(
+                        0..0,
+                        [
+                            Span::styled(
+                                format!("{:>width$}", idx + 1, width = line_num_width),
+                                gutter_style(idx + 1),
+                            ),
+                            gutter_flag(idx + 1, false),
+                            Span::raw(" ".repeat(tag_width)),
+                        ],
+                    );
+                    expanded.push(line);
+                }
+                if app.is_context_line(line_num - 1) {
+                    expanded = expanded.into_iter().map(dim_line).collect();
+                }
                 if is_cursor {
                     expanded = expanded
                         .into_iter()
@@ -118,30 +561,68 @@ pub fn render(frame: &mut Frame, app: &mut App) {
                         .collect();
                 }
                 expanded
-            })
-            .collect()
-    } else {
-        app.visible_parsed_lines_numbered()
-            .iter()
-            .enumerate()
-            .map(|(entry_idx, (line_num, parsed))| {
-                let is_cursor = cursor_entry_index == Some(entry_idx);
+            } else {
                 let prefix = Span::styled(
-                    format!("{:>width$} ", line_num, width = line_num_width),
-                    Style::default().fg(Color::DarkGray),
+                    format!("{:>width$}", line_num, width = line_num_width),
+                    gutter_style(*line_num),
                 );
-                let mut highlighted = highlight_line(parsed);
+                let mut highlighted =
+                    if app.blank_lines() == BlankLines::Separator && is_blank(&parsed.raw) {
+                        // A rule across the view rather than an empty row
+                        Line::from(Span::styled(
+                            "─".repeat(
+                                (app.h_scroll() + content_width)
+                                    .saturating_sub(line_num_width + 1 + tag_width),
+                            ),
+                            Style::default().fg(Color::DarkGray),
+                        ))
+                    } else {
+                        highlight_line_visible_with(parsed, &visible_cols, app.level_badge())
+                    };
+                for (re, color) in app.highlight_rules() {
+                    highlighted = apply_pattern_highlight(highlighted, re, *color);
+                }
                 if let Some(pattern) = search_pattern {
-                    highlighted = apply_search_highlight(highlighted, pattern);
+                    highlighted = apply_search_highlight_with(
+                        highlighted,
+                        pattern,
+                        case_sensitive,
+                        whole_word,
+                    );
+                }
+                if let Some(re) = &search_regex {
+                    highlighted = apply_regex_search_highlight(highlighted, re);
+                }
+                if app.is_show_whitespace() {
+                    highlighted = visualize_whitespace(highlighted);
+                }
+                if tag_width > 0 {
+                    highlighted.spans.splice(0..0, level_tag(parsed));
+                }
+                highlighted
+                    .spans
+                    .splice(0..0, [prefix, gutter_flag(*line_num, is_cursor)]);
+                let folded = app.continuation_lines(line_num - 1).len();
+                if folded > 0 {
+                    // Java traces count what they fold in frames
+                    let label = match app.folded_frames(line_num - 1) {
+                        0 => format!("  [+{folded} lines]"),
+                        frames => format!("  [+{frames} frames]"),
+                    };
+                    highlighted
+                        .spans
+                        .push(Span::styled(label, Style::default().fg(Color::DarkGray)));
+                }
+                if app.is_context_line(line_num - 1) {
+                    highlighted = dim_line(highlighted);
                 }
-                highlighted.spans.insert(0, prefix);
                 if is_cursor {
                     highlighted = apply_bg_to_line(highlighted, cursor_bg);
                 }
-                highlighted
-            })
-            .collect()
-    };
+                vec![highlighted]
+            }
+        })
+        .collect();
 
     let format_label = match app.format() {
         LogFormat::Json => "JSON",
@@ -151,14 +632,17 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         LogFormat::Log4j => "Log4j",
         LogFormat::PythonLog => "Python",
         LogFormat::AccessLog => "Access",
+        LogFormat::Console => "Console",
+        LogFormat::Cri => "CRI",
         LogFormat::Plain => "Plain",
     };
     let pretty_indicator = if app.is_pretty() { " pretty" } else { "" };
     let wrap_indicator = if app.is_wrap() { " wrap" } else { "" };
+    let whitespace_indicator = if app.is_show_whitespace() { " ws" } else { "" };
     let mut log_view = Paragraph::new(all_display_lines).block(
         Block::default().borders(Borders::ALL).title(format!(
-            "lumolog [{}{}{}]",
-            format_label, pretty_indicator, wrap_indicator
+            "lumolog [{}{}{}{}]",
+            format_label, pretty_indicator, wrap_indicator, whitespace_indicator
         )),
     );
     if app.is_wrap() {
@@ -171,21 +655,67 @@ pub fn render(frame: &mut Frame, app: &mut App) {
 
     // Render filter bar if in filter mode
     if app.is_filter_mode() {
-        let mut spans = vec![Span::styled(
+        // Stacked filters as chips before the one being edited
+        let mut spans: Vec<Span> = Vec::new();
+        for term in app.filter_terms() {
+            spans.push(Span::styled(
+                format!(" {term} "),
+                Style::default().fg(Color::Black).bg(Color::Cyan),
+            ));
+            spans.push(Span::raw(" "));
+        }
+        let chips_width = Line::from(spans.clone()).width() as u16;
+        spans.push(Span::styled(
             format!("/{}", app.filter_pattern()),
             Style::default().fg(Color::Cyan),
-        )];
-        if !app.filter_pattern().is_empty() {
+        ));
+        if let Some(error) = app.filter_error() {
+            spans.push(Span::styled(
+                format!("  (bad regex: {error})"),
+                Style::default().fg(Color::Red),
+            ));
+        } else if !app.filter_pattern().is_empty() || !app.filter_terms().is_empty() {
             let count = app.total_lines();
-            let label = if app.is_fuzzy() {
+            let label = if let Some(matched) = app.highlighted_matches() {
+                format!("  ({matched} of {count} highlighted)")
+            } else if app.is_fuzzy() {
                 format!("  (~{} fuzzy)", count)
+            } else if app.filter_pattern().starts_with(filter::EXCLUDE_PREFIX) {
+                format!("  ({} left)", count)
             } else {
                 format!("  ({} matches)", count)
             };
             spans.push(Span::styled(label, Style::default().fg(Color::DarkGray)));
+            if let Some(warning) = app.filter_warning() {
+                spans.push(Span::styled(
+                    format!("  {}", warning.message()),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+        }
+        if let Some(mode) = app.text_match().label() {
+            spans.push(Span::styled(
+                format!("  [{mode}]"),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        if app.is_highlight_only() {
+            spans.push(Span::styled(
+                "  [highlight only]",
+                Style::default().fg(Color::Yellow),
+            ));
         }
         let filter_bar = Paragraph::new(Line::from(spans));
         frame.render_widget(filter_bar, filter_area);
+        let before: String = app
+            .filter_pattern()
+            .chars()
+            .take(app.filter_cursor())
+            .collect();
+        let x = filter_area.x + chips_width + 1 + Line::from(before).width() as u16;
+        if x < filter_area.right() {
+            frame.set_cursor_position((x, filter_area.y));
+        }
     }
 
     // Render ask bar if in ask mode
@@ -203,6 +733,36 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         frame.render_widget(ask_bar, filter_area);
     }
 
+    // Render note bar if annotating a line
+    if app.mode() == AppMode::Annotate {
+        let spans = vec![
+            Span::styled(
+                "note: ",
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(app.annotation_input(), Style::default().fg(Color::White)),
+        ];
+        let note_bar = Paragraph::new(Line::from(spans));
+        frame.render_widget(note_bar, filter_area);
+    }
+
+    // Render name bar if saving the filter
+    if app.mode() == AppMode::SaveFilter {
+        let spans = vec![
+            Span::styled(
+                "save filter as: ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(app.save_filter_input(), Style::default().fg(Color::White)),
+        ];
+        let name_bar = Paragraph::new(Line::from(spans));
+        frame.render_widget(name_bar, filter_area);
+    }
+
     // Render analyze bar if in analyze mode
     if app.mode() == AppMode::Analyze {
         let spans = vec![
@@ -219,18 +779,20 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     }
 
     // Stats bar
-    if stats_height > 0 {
+    if stats_area.height > 0 {
         render_stats_bar(frame, app, stats_area, &level_counts);
     }
 
+    if breadcrumbs_area.height > 0 {
+        render_breadcrumbs(frame, app, breadcrumbs_area);
+    }
+
     // Status bar
     let total = app.total_lines();
     let offset = app.scroll_offset();
-    let pct = if total == 0 {
-        100
-    } else {
-        ((offset + app.visible_entry_count()).min(total) * 100) / total
-    };
+    let pct = ((offset + app.visible_entry_count()).min(total) * 100)
+        .checked_div(total)
+        .unwrap_or(100);
 
     let mut status_parts = vec![
         format!(" {}", app.source_name()),
@@ -240,9 +802,30 @@ pub fn render(frame: &mut Frame, app: &mut App) {
 
     if app.is_cursor_mode() {
         status_parts.push("CURSOR".to_string());
-        if app.show_yank_flash() {
-            status_parts.push("YANKED".to_string());
+        if let Some(idx) = app.cursor_line_index()
+            && let Some(rule) = app.marker_at(idx)
+        {
+            status_parts.push(format!("▸ {}", rule.label));
         }
+        if let Some(idx) = app.cursor_line_index()
+            && let Some(note) = app.annotation_at(idx)
+        {
+            status_parts.push(format!("* {}", note));
+        }
+    }
+    if let Some(register) = app.macro_recording() {
+        status_parts.push(format!("REC @{}", register));
+    }
+    if app.is_job_pending() {
+        status_parts.push("Working...".to_string());
+    } else if app.show_yank_flash() {
+        status_parts.push("YANKED".to_string());
+    }
+    if let Some(notice) = app.notice() {
+        status_parts.push(notice.to_string());
+    }
+    if let Some(err) = app.job_error() {
+        status_parts.push(format!("Err: {}", err));
     }
 
     if app.is_follow_mode() {
@@ -251,6 +834,9 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         } else {
             status_parts.push("FOLLOWING".to_string());
         }
+        if app.unmatched_arrivals() > 0 {
+            status_parts.push(unmatched_label(app.unmatched_arrivals()));
+        }
     }
 
     if let Some(min_level) = app.min_level() {
@@ -261,6 +847,25 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         status_parts.push(format!("Similar ({} matches)", total));
     }
 
+    if app.hidden_template_count() > 0 {
+        status_parts.push(format!(
+            "Muted: {} lines",
+            storyline::group_thousands(app.muted_line_count())
+        ));
+    }
+
+    if app.is_malformed_only() {
+        status_parts.push(format!("Malformed ({} lines)", total));
+    }
+
+    if let Some(sort) = app.sort_order() {
+        status_parts.push(format!("Sort: {}", sort.label()));
+    }
+
+    if let Some(n) = app.sampling() {
+        status_parts.push(format!("Sample 1/{} of {}", n, app.unsampled_count()));
+    }
+
     // Time range indicator in status bar
     if let Some(range) = app.time_range() {
         let multi_day = timeindex::is_multi_day(range.start, range.end);
@@ -273,14 +878,51 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         status_parts.push(format!("Col: {}", app.h_scroll()));
     }
 
+    // Stacked filters as `[chips]`, then the one being edited in quotes
+    let mut filters: Vec<String> = app
+        .filter_terms()
+        .iter()
+        .map(|t| format!("[{t}]"))
+        .collect();
     if !app.filter_pattern().is_empty() {
-        let mode = if app.is_fuzzy() { "~" } else { "" };
+        filters.push(format!("\"{}\"", app.filter_pattern()));
+    }
+    if let Some(error) = app.filter_error() {
         status_parts.push(format!(
-            "Filter{}: \"{}\" ({} matches)",
-            mode,
-            app.filter_pattern(),
-            total
+            "Filter: {} (bad regex: {error})",
+            filters.join(" ")
         ));
+    } else if !filters.is_empty() {
+        let mode = if app.is_fuzzy() { "~" } else { "" };
+        let text_mode = app
+            .text_match()
+            .label()
+            .map_or(String::new(), |mode| format!(" [{mode}]"));
+        if let Some(matched) = app.highlighted_matches() {
+            status_parts.push(format!(
+                "Highlight{}: {} ({} matches){}",
+                mode,
+                filters.join(" "),
+                matched,
+                text_mode
+            ));
+        } else {
+            status_parts.push(format!(
+                "Filter{}: {} ({} matches){}",
+                mode,
+                filters.join(" "),
+                total,
+                text_mode
+            ));
+        }
+    }
+
+    if app.context_lines() > 0 && app.has_text_filter() {
+        status_parts.push(format!("Context: ±{}", app.context_lines()));
+    }
+
+    if let Some((current, total)) = app.match_counter() {
+        status_parts.push(format!("match {current}/{total}"));
     }
 
     if app.is_ai_thinking() {
@@ -290,10 +932,13 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     }
 
     if let Some(err) = app.ai_error() {
-        let short_err = if err.len() > 80 { &err[..80] } else { err };
+        let short_err: String = err.chars().take(80).collect();
         status_parts.push(format!("AI err: {}", short_err));
     }
 
+    if let Some(position) = viewport_position_label(app) {
+        status_parts.push(position);
+    }
     status_parts.push(format!("{}%", pct));
 
     let status_text = status_parts.join(" | ");
@@ -305,6 +950,19 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         let mut remaining = status_text.as_str();
 
         // Colorize known labels within the status text
+        if app.is_follow_mode() && app.unmatched_arrivals() > 0 {
+            let label = unmatched_label(app.unmatched_arrivals());
+            if let Some(pos) = remaining.find(&label) {
+                if pos > 0 {
+                    spans.push(Span::styled(remaining[..pos].to_string(), base_style));
+                }
+                spans.push(Span::styled(
+                    label.clone(),
+                    Style::default().fg(Color::DarkGray).bg(Color::White),
+                ));
+                remaining = &remaining[pos + label.len()..];
+            }
+        }
         if let Some(min_level) = app.min_level() {
             let level_label = format!("Level: {}+", min_level.short_name());
             let level_color = match min_level {
@@ -358,6 +1016,20 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         }
     };
 
+    // A pending quit confirmation takes over the status bar until answered
+    let status = match app.quit_confirmation() {
+        Some(reason) => Paragraph::new(format!(
+            " Quit with {}? y to quit, any other key to stay",
+            reason
+        ))
+        .style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ),
+        None => status,
+    };
     frame.render_widget(status, status_area);
 
     // Context menu overlay
@@ -382,7 +1054,7 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         let menu_width = menu
             .items
             .iter()
-            .map(|a| a.label().len() as u16 + 2) // +2 for padding
+            .map(|a| text_width(a.label()) as u16 + 2) // +2 for padding
             .max()
             .unwrap_or(20)
             + 2; // +2 for border
@@ -413,9 +1085,30 @@ pub fn render(frame: &mut Frame, app: &mut App) {
 
         let palette_width = 50u16;
         let max_visible = 12usize;
-        let visible_count = filtered.len().min(max_visible);
-        // 1 for input line + visible items + 2 for border
-        let palette_height = (visible_count as u16 + 3).min(area.height);
+        let inner_width = palette_width as usize - 4; // 2 border + 2 padding
+
+        // With nothing typed the list is grouped, so each category gets a
+        // header row; search results are ranked and shown without them
+        enum Row {
+            Header(crate::command::Category),
+            Command(usize),
+        }
+        let grouped = input.is_empty();
+        let mut rows: Vec<Row> = Vec::new();
+        let mut selected_row = 0;
+        for (pos, &cmd_idx) in filtered.iter().enumerate() {
+            if grouped && (pos == 0 || cmds[filtered[pos - 1]].category != cmds[cmd_idx].category) {
+                rows.push(Row::Header(cmds[cmd_idx].category));
+            }
+            if pos == selected {
+                selected_row = rows.len();
+            }
+            rows.push(Row::Command(cmd_idx));
+        }
+
+        let visible_count = rows.len().min(max_visible);
+        // input line + visible rows + description line + 2 for border
+        let palette_height = (visible_count as u16 + 4).min(area.height);
 
         let x = (area.width.saturating_sub(palette_width)) / 2;
         let y = (area.height.saturating_sub(palette_height)) / 2;
@@ -432,22 +1125,35 @@ pub fn render(frame: &mut Frame, app: &mut App) {
         ]);
         lines_vec.push(input_line);
 
-        // Scroll the list so selected item is always visible
-        let scroll_offset = if selected >= max_visible {
-            selected - max_visible + 1
+        // Scroll the list so the selected item is visible, along with its
+        // header when it's the first of a group
+        let scroll_offset = if selected_row >= max_visible {
+            selected_row - max_visible + 1
         } else {
             0
         };
 
         // Command rows
-        for (display_idx, &cmd_idx) in filtered
+        for (row_idx, row) in rows
             .iter()
+            .enumerate()
             .skip(scroll_offset)
             .take(max_visible)
-            .enumerate()
         {
+            let cmd_idx = match *row {
+                Row::Header(category) => {
+                    lines_vec.push(Line::from(Span::styled(
+                        format!(" {}", category.name()),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    )));
+                    continue;
+                }
+                Row::Command(idx) => idx,
+            };
             let cmd = &cmds[cmd_idx];
-            let is_selected = scroll_offset + display_idx == selected;
+            let is_selected = row_idx == selected_row;
 
             let name_style = if is_selected {
                 Style::default()
@@ -459,9 +1165,9 @@ pub fn render(frame: &mut Frame, app: &mut App) {
             };
 
             let key_str = cmd.keybinding.unwrap_or("");
-            let name_display = &cmd.name[..cmd.name.len().min(30)];
-            let inner_width = palette_width as usize - 4; // 2 border + 2 padding
-            let padding = inner_width.saturating_sub(name_display.len() + key_str.len());
+            let name_display: String = cmd.name.chars().take(30).collect();
+            let padding =
+                inner_width.saturating_sub(text_width(&name_display) + text_width(key_str));
             let pad = " ".repeat(padding.max(1));
 
             let key_style = if is_selected {
@@ -485,69 +1191,1068 @@ pub fn render(frame: &mut Frame, app: &mut App) {
             ]));
         }
 
+        // What the selected command does
+        let description = filtered
+            .get(selected)
+            .map_or("", |&idx| cmds[idx].description);
+        lines_vec.push(Line::from(Span::styled(
+            format!(" {}", truncate_to_width(description, inner_width)),
+            Style::default().fg(Color::DarkGray),
+        )));
+
         let palette_block = Paragraph::new(lines_vec)
             .block(Block::default().borders(Borders::ALL).title("Commands"))
             .style(Style::default().fg(Color::White).bg(Color::Black));
 
-        frame.render_widget(Clear, palette_area);
-        frame.render_widget(palette_block, palette_area);
+        frame.render_widget(Clear, palette_area);
+        frame.render_widget(palette_block, palette_area);
+    }
+
+    // Analysis response overlay
+    if let Some(response) = app.analyze_response() {
+        let overlay_width = (area.width * 4 / 5).min(area.width.saturating_sub(4));
+        let overlay_height = (area.height * 7 / 10).min(area.height.saturating_sub(4));
+        let x = (area.width.saturating_sub(overlay_width)) / 2;
+        let y = (area.height.saturating_sub(overlay_height)) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+
+        let inner_width = overlay_width.saturating_sub(2) as usize;
+
+        // Word-wrap the response text into lines
+        let wrapped_lines: Vec<Line> = response
+            .lines()
+            .flat_map(|line| {
+                if line.is_empty() {
+                    return vec![Line::from("")];
+                }
+                let mut result = Vec::new();
+                let mut current = String::new();
+                for word in line.split_whitespace() {
+                    if current.is_empty() {
+                        current = word.to_string();
+                    } else if text_width(&current) + 1 + text_width(word) <= inner_width {
+                        current.push(' ');
+                        current.push_str(word);
+                    } else {
+                        result.push(Line::from(current.clone()));
+                        current = word.to_string();
+                    }
+                }
+                if !current.is_empty() {
+                    result.push(Line::from(current));
+                }
+                result
+            })
+            .collect();
+
+        let scroll = app.analyze_scroll() as u16;
+
+        let overlay = Paragraph::new(wrapped_lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" AI Analysis (Esc to close) ")
+                    .style(Style::default().fg(Color::White).bg(Color::Black)),
+            )
+            .scroll((scroll, 0));
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(overlay, overlay_area);
+    }
+
+    // Event list panel: every line a marker rule matched
+    if let Some(selected) = app.event_list_selected() {
+        let events: Vec<_> = app.markers().collect();
+        let label_width = events.iter().map(|(_, r)| r.label.len()).max().unwrap_or(0);
+        let num_width = format!("{}", app.total_lines_unfiltered()).len();
+        let title = format!(
+            " Events {}/{} (Enter to jump, Esc to close) ",
+            selected + 1,
+            events.len()
+        );
+        render_list_overlay(frame, area, title, events.len(), selected, |i| {
+            let (idx, rule) = events[i];
+            let raw = app.parsed_line(idx).map_or("", |p| p.raw.as_str());
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:>width$}  ", idx + 1, width = num_width),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("{:<width$}  ", rule.label, width = label_width),
+                    Style::default().fg(rule.color).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(raw.to_string()),
+            ])
+        });
+    }
+
+    // Malformed lines panel: parse failures grouped by reason
+    if let Some((rows, selected)) = app.malformed_panel() {
+        let max_count = rows.iter().map(|r| r.count).max().unwrap_or(0);
+        let count_width = storyline::group_thousands(max_count).len();
+        let total: usize = rows.iter().map(|r| r.count).sum();
+        let title = format!(
+            " Malformed lines: {} (Enter to jump, f to filter, Esc to close) ",
+            storyline::group_thousands(total)
+        );
+        render_list_overlay(frame, area, title, rows.len(), selected, |i| {
+            let row = &rows[i];
+            Line::from(vec![
+                Span::styled(
+                    format!(
+                        " {:>width$}  ",
+                        storyline::group_thousands(row.count),
+                        width = count_width
+                    ),
+                    Style::default().fg(Color::Red),
+                ),
+                Span::raw(row.reason.clone()),
+                Span::styled(
+                    format!("  (first: line {})", row.first_line + 1),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        });
+    }
+
+    // Line length panel: size distribution and the longest lines
+    if let Some((stats, selected)) = app.line_stats() {
+        let overlay_width = (area.width * 4 / 5).min(area.width.saturating_sub(4));
+        let size = |bytes: usize| {
+            format_quantity(Quantity {
+                value: bytes as f64,
+                dimension: Dimension::Size,
+            })
+        };
+        let dim = Style::default().fg(Color::DarkGray);
+        let oversized = Style::default().fg(Color::Red);
+        let heading = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+
+        let mut lines = vec![Line::from(vec![
+            Span::styled(" min ", dim),
+            Span::raw(size(stats.min)),
+            Span::styled("   p50 ", dim),
+            Span::raw(size(stats.p50)),
+            Span::styled("   p95 ", dim),
+            Span::raw(size(stats.p95)),
+            Span::styled("   max ", dim),
+            Span::styled(
+                size(stats.max),
+                if stats.max >= OVERSIZED_BYTES {
+                    oversized
+                } else {
+                    Style::default()
+                },
+            ),
+        ])];
+        lines.push(Line::raw(""));
+
+        let labels: Vec<String> = stats
+            .buckets
+            .iter()
+            .map(|b| match b.max {
+                Some(max) if b.min == 0 => format!("< {}", size(max)),
+                Some(max) => format!("{} – {}", size(b.min), size(max)),
+                None => format!("≥ {}", size(b.min)),
+            })
+            .collect();
+        let label_width = labels.iter().map(|l| text_width(l)).max().unwrap_or(0);
+        let max_count = stats.buckets.iter().map(|b| b.count).max().unwrap_or(0);
+        let count_width = storyline::group_thousands(max_count).len();
+        for (bucket, label) in stats.buckets.iter().zip(&labels) {
+            let bar = (bucket.count * LINE_STATS_BAR_WIDTH).div_ceil(max_count.max(1));
+            let style = if bucket.min >= OVERSIZED_BYTES {
+                oversized
+            } else {
+                Style::default().fg(Color::Cyan)
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!(
+                    " {label}{}  ",
+                    " ".repeat(label_width - text_width(label))
+                )),
+                Span::styled(
+                    format!("{:<width$}", "█".repeat(bar), width = LINE_STATS_BAR_WIDTH),
+                    style,
+                ),
+                Span::raw(format!(
+                    "  {:>width$}",
+                    storyline::group_thousands(bucket.count),
+                    width = count_width
+                )),
+            ]));
+        }
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(" Longest", heading));
+
+        let num_width = storyline::group_thousands(app.total_lines_unfiltered()).len();
+        let size_width = stats
+            .longest
+            .iter()
+            .map(|&(_, len)| size(len).len())
+            .max()
+            .unwrap_or(0);
+        let preview_width = (overlay_width as usize).saturating_sub(num_width + size_width + 14);
+        for (i, &(idx, len)) in stats.longest.iter().enumerate() {
+            let raw = app.parsed_line(idx).map_or("", |p| p.raw.as_str());
+            let mut line = Line::from(vec![
+                Span::styled(
+                    format!(
+                        " line {:>width$}  ",
+                        storyline::group_thousands(idx + 1),
+                        width = num_width
+                    ),
+                    dim,
+                ),
+                Span::styled(
+                    format!("{:>width$}  ", size(len), width = size_width),
+                    if len >= OVERSIZED_BYTES {
+                        oversized
+                    } else {
+                        Style::default()
+                    },
+                ),
+                Span::raw(truncate_to_width(
+                    &raw.chars().take(preview_width + 1).collect::<String>(),
+                    preview_width,
+                )),
+            ]);
+            if i == selected {
+                line = apply_bg_to_line(line, Color::DarkGray);
+            }
+            lines.push(line);
+        }
+
+        let overlay_height = (lines.len() as u16 + 2).min(area.height);
+        let x = (area.width.saturating_sub(overlay_width)) / 2;
+        let y = (area.height.saturating_sub(overlay_height)) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+        let overlay = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    " Line lengths: {} lines, {} (Enter to jump, Esc to close) ",
+                    storyline::group_thousands(stats.lines),
+                    size(stats.total_bytes)
+                ))
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        );
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(overlay, overlay_area);
+    }
+
+    // Collapse noise preview: the noisiest templates and what hiding them cuts
+    if let Some(preview) = app.noise_preview() {
+        let overlay_width = (area.width * 4 / 5).min(area.width.saturating_sub(4));
+        let dim = Style::default().fg(Color::DarkGray);
+        let hidden_lines = preview.hidden_lines();
+        let pct = |count: usize| count * 100 / preview.lines.max(1);
+
+        let mut lines = vec![Line::from(vec![
+            Span::raw(" Hides "),
+            Span::styled(
+                storyline::group_thousands(hidden_lines),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(format!(
+                " of {} lines ({}%), {} left",
+                storyline::group_thousands(preview.lines),
+                pct(hidden_lines),
+                storyline::group_thousands(preview.lines - hidden_lines)
+            )),
+        ])];
+        lines.push(Line::raw(""));
+
+        let count_width = preview
+            .templates
+            .first()
+            .map_or(0, |t| storyline::group_thousands(t.count).len());
+        let template_width = (overlay_width as usize).saturating_sub(count_width + 14);
+        for (i, template) in preview.templates.iter().enumerate() {
+            let hidden = i < preview.hide;
+            let style = if hidden { Style::default() } else { dim };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    if hidden { " ✕ " } else { "   " },
+                    Style::default().fg(Color::Red),
+                ),
+                Span::styled(
+                    format!(
+                        "{:>width$} {:>3}%  ",
+                        storyline::group_thousands(template.count),
+                        pct(template.count),
+                        width = count_width
+                    ),
+                    style,
+                ),
+                Span::styled(truncate_to_width(&template.template, template_width), style),
+            ]));
+        }
+
+        let overlay_height = (lines.len() as u16 + 2).min(area.height);
+        let x = (area.width.saturating_sub(overlay_width)) / 2;
+        let y = (area.height.saturating_sub(overlay_height)) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+        let overlay = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    " Collapse noise: {} templates (←/→ fewer/more, Enter to hide, Esc to close) ",
+                    preview.hide
+                ))
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        );
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(overlay, overlay_area);
+    }
+
+    // Level picker: a checkbox per level in the log
+    if let Some(selected) = app.level_picker_selected() {
+        let counts = app.level_counts();
+        let count_width = counts
+            .iter()
+            .map(|&(_, count)| storyline::group_thousands(count).len())
+            .max()
+            .unwrap_or(0);
+        let lines: Vec<Line> = app
+            .available_levels()
+            .iter()
+            .enumerate()
+            .map(|(i, &level)| {
+                let count = counts
+                    .iter()
+                    .find(|&&(l, _)| l == level)
+                    .map_or(0, |&(_, count)| count);
+                let shown = app.is_level_shown(level);
+                let mut line = Line::from(vec![
+                    Span::raw(if shown { " [x] " } else { " [ ] " }),
+                    Span::styled(
+                        format!(" {} ", level.short_name()),
+                        level_badge_style(Some(level)),
+                    ),
+                    Span::styled(
+                        format!(
+                            "  {:>width$} ",
+                            storyline::group_thousands(count),
+                            width = count_width
+                        ),
+                        if shown {
+                            Style::default()
+                        } else {
+                            Style::default().fg(Color::DarkGray)
+                        },
+                    ),
+                ]);
+                if i == selected {
+                    line = apply_bg_to_line(line, Color::DarkGray);
+                }
+                line
+            })
+            .collect();
+
+        let title = " Levels (Space to toggle, Esc to close) ";
+        let overlay_width = (text_width(title) as u16 + 2).min(area.width);
+        let overlay_height = (lines.len() as u16 + 2).min(area.height);
+        let x = (area.width.saturating_sub(overlay_width)) / 2;
+        let y = (area.height.saturating_sub(overlay_height)) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+        let overlay = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        );
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(overlay, overlay_area);
+    }
+
+    // Saved filters panel
+    if let Some(selected) = app.saved_filters_selected() {
+        let filters = app.saved_filters().filters();
+        let name_width = filters
+            .iter()
+            .map(|f| f.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let title = format!(
+            " Saved filters {}/{} (Enter to apply, d to delete, Esc to close) ",
+            selected + 1,
+            filters.len()
+        );
+        render_list_overlay(frame, area, title, filters.len(), selected, |i| {
+            let saved = &filters[i];
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:<width$}  ", saved.name, width = name_width),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(saved.summary(), Style::default().fg(Color::Gray)),
+            ])
+        });
+    }
+
+    // Annotation list panel
+    if let Some(selected) = app.annotation_list_selected() {
+        let notes: Vec<_> = app.annotations().collect();
+        let num_width = format!("{}", app.total_lines_unfiltered()).len();
+        let title = format!(
+            " Annotations {}/{} (Enter to jump, y to export, Esc to close) ",
+            selected + 1,
+            notes.len()
+        );
+        render_list_overlay(frame, area, title, notes.len(), selected, |i| {
+            let (idx, note) = notes[i];
+            let timestamp = app
+                .parsed_line(idx)
+                .and_then(|p| p.timestamp.as_deref())
+                .unwrap_or("");
+            Line::from(vec![
+                Span::styled(
+                    format!(" {:>width$}  ", idx + 1, width = num_width),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("{}  ", timestamp),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    note.to_string(),
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            ])
+        });
+    }
+
+    // Trace view panel: lines grouped by trace and span
+    if let Some(selected) = app.trace_view_selected() {
+        let rows_src = app.trace_rows();
+        let num_width = format!("{}", app.total_lines_unfiltered()).len();
+        let summary = |lines: usize, duration: Option<chrono::Duration>| {
+            let mut s = format!("  {lines} line{}", if lines == 1 { "" } else { "s" });
+            if let Some(d) = duration {
+                s.push_str(&format!("  {}", traces::format_duration(d)));
+            }
+            s
+        };
+        let title = format!(
+            " Traces {}/{} (Enter to jump, Esc to close) ",
+            selected + 1,
+            rows_src.len()
+        );
+        render_list_overlay(
+            frame,
+            area,
+            title,
+            rows_src.len(),
+            selected,
+            |i| match &rows_src[i] {
+                TraceRow::Trace {
+                    trace_id,
+                    lines,
+                    duration,
+                    ..
+                } => Line::from(vec![
+                    Span::styled(
+                        format!(" trace {trace_id}"),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        summary(*lines, *duration),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]),
+                TraceRow::Span {
+                    span_id,
+                    depth,
+                    lines,
+                    duration,
+                    ..
+                } => Line::from(vec![
+                    Span::styled(
+                        format!(" {}span {span_id}", "  ".repeat(*depth)),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::styled(
+                        summary(*lines, *duration),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]),
+                TraceRow::Line { idx, depth } => {
+                    let parsed = app.parsed_line(*idx);
+                    let level = parsed.and_then(|p| p.level);
+                    Line::from(vec![
+                        Span::styled(
+                            format!(
+                                " {}{:>width$}  ",
+                                "  ".repeat(*depth),
+                                idx + 1,
+                                width = num_width
+                            ),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                        Span::styled(
+                            level.map_or("   ", |l| l.short_name()),
+                            level_badge_style(level),
+                        ),
+                        Span::raw(format!(" {}", parsed.map_or("", |p| p.message.as_str()))),
+                    ])
+                }
+            },
+        );
+    }
+
+    // Storyline panel: level runs collapsed to counts, errors spelled out
+    if let Some(selected) = app.storyline_selected() {
+        let rows_src = app.story_rows();
+        let num_width = format!("{}", app.total_lines_unfiltered()).len();
+        let title = format!(
+            " Storyline {}/{} (Enter to jump, y to copy, Esc to close) ",
+            selected + 1,
+            rows_src.len()
+        );
+        render_list_overlay(
+            frame,
+            area,
+            title,
+            rows_src.len(),
+            selected,
+            |i| match &rows_src[i] {
+                StoryRow::Run { level, count, .. } => {
+                    let color = match level {
+                        Some(LogLevel::Warn) => Color::Yellow,
+                        _ => Color::DarkGray,
+                    };
+                    Line::from(Span::styled(
+                        format!(
+                            " {:>width$}  {}",
+                            "",
+                            storyline::run_label(*level, *count),
+                            width = num_width
+                        ),
+                        Style::default().fg(color),
+                    ))
+                }
+                StoryRow::Event { idx, repeats } => {
+                    let parsed = app.parsed_line(*idx);
+                    let level = parsed.and_then(|p| p.level);
+                    let mut spans = vec![
+                        Span::styled(
+                            format!(" {:>width$}  ", idx + 1, width = num_width),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                        Span::styled(
+                            level.map_or("   ", |l| l.short_name()),
+                            level_badge_style(level),
+                        ),
+                    ];
+                    if let Some(ts) = parsed.and_then(|p| p.timestamp.as_deref()) {
+                        spans.push(Span::styled(
+                            format!(" {ts}"),
+                            Style::default().fg(Color::DarkGray),
+                        ));
+                    }
+                    spans.push(Span::raw(format!(
+                        " {}",
+                        parsed.map_or("", |p| p.message.as_str())
+                    )));
+                    if *repeats > 0 {
+                        spans.push(Span::styled(
+                            format!(" ×{}", repeats + 1),
+                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                        ));
+                    }
+                    Line::from(spans)
+                }
+            },
+        );
+    }
+
+    // Value distribution popup for a key=value token
+    if let Some(state) = app.value_distribution()
+        && let Some(overlay_area) = value_dist_rect(app, area)
+    {
+        let dist = &state.dist;
+        let max_count = dist
+            .values
+            .iter()
+            .map(|v| v.count)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let count_width = storyline::group_thousands(max_count).len();
+        let mut rows: Vec<Line> = dist
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let pct = v.count * 100 / dist.lines_with_key.max(1);
+                let bar_len = (v.count * VALUE_DIST_BAR_WIDTH).div_ceil(max_count);
+                let mut line = Line::from(vec![
+                    Span::styled(
+                        format!(
+                            " {:>width$} {:>3}% ",
+                            storyline::group_thousands(v.count),
+                            pct,
+                            width = count_width
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(
+                        format!(
+                            "{:<width$} ",
+                            "█".repeat(bar_len),
+                            width = VALUE_DIST_BAR_WIDTH
+                        ),
+                        Style::default().fg(Color::Cyan),
+                    ),
+                    Span::raw(v.value.clone()),
+                ]);
+                if i == state.selected {
+                    line = apply_bg_to_line(line, Color::DarkGray);
+                }
+                line
+            })
+            .collect();
+        if let Some(summary) = &state.summary {
+            let stat = |label: &str, value: f64| {
+                vec![
+                    Span::styled(format!(" {label} "), Style::default().fg(Color::DarkGray)),
+                    Span::raw(format_quantity(summary.quantity(value))),
+                ]
+            };
+            rows.push(Line::from(
+                [
+                    stat("min", summary.min),
+                    stat(" p50", summary.p50),
+                    stat(" p95", summary.p95),
+                    stat(" max", summary.max),
+                ]
+                .concat(),
+            ));
+        }
+
+        let more = if dist.other_values > 0 {
+            format!(", +{} more", dist.other_values)
+        } else {
+            String::new()
+        };
+        let overlay = Paragraph::new(rows).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    " {} in {} lines{} (Enter to filter, {}Esc to close) ",
+                    dist.key,
+                    storyline::group_thousands(dist.lines_with_key),
+                    more,
+                    match (&state.summary, state.by_value) {
+                        (None, _) => "",
+                        (Some(_), false) => "s to sort by value, ",
+                        (Some(_), true) => "s to sort by count, ",
+                    }
+                ))
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        );
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(overlay, overlay_area);
     }
 
-    // Analysis response overlay
-    if let Some(response) = app.analyze_response() {
+    // Word diff overlay (marked line vs cursor line)
+    if let Some(((old_num, old), (new_num, new))) = app.diff_lines() {
         let overlay_width = (area.width * 4 / 5).min(area.width.saturating_sub(4));
-        let overlay_height = (area.height * 7 / 10).min(area.height.saturating_sub(4));
+        let overlay_height = (area.height / 2).min(area.height.saturating_sub(4));
         let x = (area.width.saturating_sub(overlay_width)) / 2;
         let y = (area.height.saturating_sub(overlay_height)) / 2;
         let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
 
-        let inner_width = overlay_width.saturating_sub(2) as usize;
-
-        // Word-wrap the response text into lines
-        let wrapped_lines: Vec<Line> = response
-            .lines()
-            .flat_map(|line| {
-                if line.is_empty() {
-                    return vec![Line::from("")];
+        let ops = diff::word_diff(old, new);
+        let mut old_spans = vec![Span::styled("- ", Style::default().fg(Color::Red))];
+        let mut new_spans = vec![Span::styled("+ ", Style::default().fg(Color::Green))];
+        for op in ops {
+            match op {
+                DiffOp::Equal(text) => {
+                    old_spans.push(Span::raw(text.clone()));
+                    new_spans.push(Span::raw(text));
                 }
-                let mut result = Vec::new();
-                let mut current = String::new();
-                for word in line.split_whitespace() {
-                    if current.is_empty() {
-                        current = word.to_string();
-                    } else if current.len() + 1 + word.len() <= inner_width {
-                        current.push(' ');
-                        current.push_str(word);
-                    } else {
-                        result.push(Line::from(current.clone()));
-                        current = word.to_string();
+                DiffOp::Removed(text) => old_spans.push(Span::styled(
+                    text,
+                    Style::default().fg(Color::White).bg(Color::Red),
+                )),
+                DiffOp::Added(text) => new_spans.push(Span::styled(
+                    text,
+                    Style::default().fg(Color::Black).bg(Color::Green),
+                )),
+            }
+        }
+
+        let overlay = Paragraph::new(vec![
+            Line::from(old_spans),
+            Line::from(""),
+            Line::from(new_spans),
+        ])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    " Diff: line {old_num} → line {new_num} (Esc to close) "
+                ))
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        )
+        .wrap(Wrap { trim: false });
+
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(overlay, overlay_area);
+    }
+
+    // Hex dump of one line's bytes, control and non-ASCII bytes colored
+    if let Some((line_num, text)) = app.hex_dump_line() {
+        let bytes = text.as_bytes();
+        let per_row = hexdump::bytes_per_row(area.width.saturating_sub(2) as usize);
+        let overlay_width = (hexdump::row_width(per_row) as u16 + 2).min(area.width);
+        let title = format!(
+            " Line {line_num}: {} (y to copy, Esc to close) ",
+            hexdump::summary(bytes)
+        );
+        let byte_style = |b: u8| match hexdump::classify(b) {
+            ByteClass::Printable => Style::default(),
+            ByteClass::Control => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ByteClass::NonAscii => Style::default().fg(Color::Yellow),
+        };
+        let rows: Vec<Line> = bytes
+            .chunks(per_row)
+            .enumerate()
+            .map(|(row, chunk)| {
+                let mut spans = vec![Span::styled(
+                    format!("{:08x}  ", row * per_row),
+                    Style::default().fg(Color::DarkGray),
+                )];
+                for i in 0..per_row {
+                    if i == per_row / 2 {
+                        spans.push(Span::raw(" "));
                     }
+                    spans.push(match chunk.get(i) {
+                        Some(&b) => Span::styled(format!("{b:02x} "), byte_style(b)),
+                        None => Span::raw("   "),
+                    });
                 }
-                if !current.is_empty() {
-                    result.push(Line::from(current));
-                }
-                result
+                spans.push(Span::raw(" |"));
+                spans.extend(
+                    chunk
+                        .iter()
+                        .map(|&b| Span::styled(hexdump::ascii_char(b).to_string(), byte_style(b))),
+                );
+                spans.push(Span::raw("|"));
+                Line::from(spans)
             })
             .collect();
+        let max_height = (area.height * 4 / 5).max(3);
+        let overlay_height = (rows.len() as u16 + 2).min(max_height).min(area.height);
+        app.clamp_hex_dump_scroll(rows.len(), overlay_height.saturating_sub(2) as usize);
+        let scroll = app.hex_dump_scroll();
 
-        let scroll = app.analyze_scroll() as u16;
+        let x = (area.width.saturating_sub(overlay_width)) / 2;
+        let y = (area.height.saturating_sub(overlay_height)) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+        let overlay = Paragraph::new(rows.into_iter().skip(scroll).collect::<Vec<_>>()).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        );
 
-        let overlay = Paragraph::new(wrapped_lines)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title(" AI Analysis (Esc to close) ")
-                    .style(Style::default().fg(Color::White).bg(Color::Black)),
-            )
-            .scroll((scroll, 0));
+        frame.render_widget(Clear, overlay_area);
+        frame.render_widget(overlay, overlay_area);
+    }
+
+    // Decoded JWT or base64 token
+    if let Some(state) = app.decoded() {
+        let overlay_width = (area.width * 4 / 5).min(area.width.saturating_sub(4));
+        let inner_width = overlay_width.saturating_sub(2).max(1) as usize;
+        let title = match state.decoded {
+            Decoded::Jwt { .. } => "y to copy claims",
+            Decoded::Text(_) => "y to copy",
+        };
+        let title = format!(
+            " {} (j/k to scroll, {title}, Esc to close) ",
+            state.decoded.label()
+        );
+        let rows: Vec<Line> = decoded_lines(&state.decoded, chrono::Utc::now())
+            .into_iter()
+            .flat_map(|line| wrap_line(line, inner_width))
+            .collect();
+        let max_height = (area.height * 4 / 5).max(3);
+        let overlay_height = (rows.len() as u16 + 2).min(max_height).min(area.height);
+        let visible = overlay_height.saturating_sub(2) as usize;
+        app.clamp_decoded_scroll(rows.len(), visible);
+        let scroll = app.decoded().map_or(0, |s| s.scroll);
+
+        let x = (area.width.saturating_sub(overlay_width)) / 2;
+        let y = (area.height.saturating_sub(overlay_height)) / 2;
+        let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+        let overlay = Paragraph::new(rows.into_iter().skip(scroll).collect::<Vec<_>>()).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .style(Style::default().fg(Color::White).bg(Color::Black)),
+        );
 
         frame.render_widget(Clear, overlay_area);
         frame.render_widget(overlay, overlay_area);
     }
 }
 
+/// Rows of the decoded popup: a JWT's header, claims and their dates, or
+/// the decoded text.
+fn decoded_lines(decoded: &Decoded, now: chrono::DateTime<chrono::Utc>) -> Vec<Line<'static>> {
+    let Decoded::Jwt { header, claims } = decoded else {
+        return decoded
+            .payload()
+            .lines()
+            .map(|l| Line::raw(l.to_string()))
+            .collect();
+    };
+    let heading = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let dim = Style::default().fg(Color::DarkGray);
+    let mut lines = vec![Line::styled("Header", heading)];
+    lines.extend(pretty(header).lines().map(|l| Line::raw(l.to_string())));
+    lines.push(Line::raw(""));
+    lines.push(Line::styled("Claims", heading));
+    lines.extend(pretty(claims).lines().map(|l| Line::raw(l.to_string())));
+    let times = decoded.times();
+    if !times.is_empty() {
+        lines.push(Line::raw(""));
+    }
+    for (label, time) in times {
+        let mut spans = vec![
+            Span::styled(format!("{label:<11}"), dim),
+            Span::raw(time.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+        ];
+        let note = match label {
+            "Expires" if time < now => Some("  expired"),
+            "Not before" if time > now => Some("  not yet valid"),
+            _ => None,
+        };
+        if let Some(note) = note {
+            spans.push(Span::styled(note, Style::default().fg(Color::Red)));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::raw(""));
+    lines.push(Line::styled("Signature not verified", dim));
+    lines
+}
+
+/// Split a line into rows of at most `width` columns, keeping span styles.
+fn wrap_line(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    let mut rows = vec![Line::default()];
+    let mut used = 0;
+    for span in line.spans {
+        let mut piece = String::new();
+        for c in span.content.chars() {
+            let w = text_width(c.encode_utf8(&mut [0; 4]));
+            if used + w > width && used > 0 {
+                let row = rows.last_mut().unwrap();
+                row.spans
+                    .push(Span::styled(std::mem::take(&mut piece), span.style));
+                rows.push(Line::default());
+                used = 0;
+            }
+            piece.push(c);
+            used += w;
+        }
+        if !piece.is_empty() {
+            rows.last_mut()
+                .unwrap()
+                .spans
+                .push(Span::styled(piece, span.style));
+        }
+    }
+    rows
+}
+
 // --- Sparkline rendering ---
 
+/// Terminal columns `text` takes up: wide CJK characters count as two,
+/// combining marks as none.
+fn text_width(text: &str) -> usize {
+    Span::raw(text).width()
+}
+
+/// `text` cut to fit in `width` columns, ending in `…` when shortened.
+fn truncate_to_width(text: &str, width: usize) -> String {
+    if text_width(text) <= width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = text_width(c.encode_utf8(&mut [0; 4]));
+        if used + w + 1 > width {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+    out.push('…');
+    out
+}
+
+/// Rows taken by the sparkline: the bars, plus in time mode the time axis
+/// and the level breakdown of the cursor bucket.
+fn sparkline_height(app: &App) -> u16 {
+    if !app.is_sparkline_visible() {
+        0
+    } else if app.mode() == AppMode::TimeRange {
+        3
+    } else {
+        1
+    }
+}
+
+/// Rows taken by the pinned pane: one per pin, up to the pane's size, plus
+/// its border.
+fn pinned_height(app: &App) -> u16 {
+    let layout = app.pane_layout();
+    match app.pins().len() {
+        0 => 0,
+        _ if !layout.pinned => 0,
+        n => n.min(layout.pinned_rows) as u16 + 2,
+    }
+}
+
+/// Pinned lines in the order they were pinned, with their line numbers.
+fn render_pinned(frame: &mut Frame, app: &App, area: Rect, line_num_width: usize) {
+    let width = area.width.saturating_sub(2) as usize;
+    let rows: Vec<Line> = app
+        .pins()
+        .iter()
+        .filter_map(|&idx| app.parsed_line(idx).map(|parsed| (idx, parsed)))
+        .map(|(idx, parsed)| {
+            let mut line = highlight_line_visible_with(parsed, &(0..width), app.level_badge());
+            for (re, color) in app.highlight_rules() {
+                line = apply_pattern_highlight(line, re, *color);
+            }
+            line.spans.insert(
+                0,
+                Span::styled(
+                    format!("{:>width$} ", idx + 1, width = line_num_width),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            );
+            line
+        })
+        .collect();
+    let pinned = Paragraph::new(rows).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Magenta))
+            .title(pinned_title(
+                app.pins().len(),
+                area.height.saturating_sub(2) as usize,
+            )),
+    );
+    frame.render_widget(pinned, area);
+}
+
+/// Where the viewport is in the file: `lines 182,440–182,472 · 08:31:02`,
+/// by original line number and the time of the top line.
+fn viewport_position_label(app: &App) -> Option<String> {
+    let (first, last, time) = app.viewport_position()?;
+    let mut label = format!(
+        "lines {}–{}",
+        storyline::group_thousands(first),
+        storyline::group_thousands(last)
+    );
+    if let Some(time) = time {
+        let multi_day = app
+            .time_index()
+            .is_some_and(timeindex::TimeIndex::is_multi_day);
+        let format = if multi_day {
+            "%m-%d %H:%M:%S"
+        } else {
+            "%H:%M:%S"
+        };
+        label.push_str(&format!(" · {}", time.format(format)));
+    }
+    Some(label)
+}
+
+/// Status bar note for lines follow mode received but the filters hid.
+fn unmatched_label(count: usize) -> String {
+    format!("+{count} unmatched")
+}
+
+/// ` Pinned 3/5 `, plus how many don't fit when the pane is made smaller.
+fn pinned_title(pins: usize, rows: usize) -> String {
+    match pins.saturating_sub(rows) {
+        0 => format!(" Pinned {pins}/{MAX_PINS} "),
+        hidden => format!(" Pinned {pins}/{MAX_PINS}, {hidden} hidden "),
+    }
+}
+
+/// Width of the proportional bar in the time mode level breakdown.
+const BUCKET_LEVEL_BAR_WIDTH: usize = 20;
+
+fn level_color(level: Option<LogLevel>) -> Color {
+    match level {
+        Some(LogLevel::Fatal | LogLevel::Error) => Color::Red,
+        Some(LogLevel::Warn) => Color::Yellow,
+        Some(LogLevel::Info) => Color::Green,
+        Some(LogLevel::Debug | LogLevel::Trace) => Color::DarkGray,
+        None => Color::Gray,
+    }
+}
+
+/// `255 lines ███▇▇▇  ERR 3  WRN 12  INF 240` for the bucket under the time
+/// mode cursor, starting below the cursor and shifted left to fit `width`.
+fn bucket_levels_line(app: &App, cursor_bucket: usize, width: usize) -> Line<'static> {
+    let levels = app.time_cursor_levels();
+    let total: usize = levels.iter().map(|&(_, n)| n).sum();
+    let mut spans = vec![Span::styled(
+        match total {
+            0 => "no lines ".to_string(),
+            1 => "1 line ".to_string(),
+            n => format!("{} lines ", storyline::group_thousands(n)),
+        },
+        Style::default().fg(Color::DarkGray),
+    )];
+    for &(level, count) in levels {
+        let cells = (count * BUCKET_LEVEL_BAR_WIDTH).div_ceil(total);
+        spans.push(Span::styled(
+            "█".repeat(cells),
+            Style::default().fg(level_color(level)),
+        ));
+    }
+    for &(level, count) in levels {
+        spans.push(Span::styled(
+            format!(
+                "  {} {}",
+                level.map_or("other", LogLevel::short_name),
+                count
+            ),
+            Style::default().fg(level_color(level)),
+        ));
+    }
+    let used: usize = spans.iter().map(|s| s.content.chars().count()).sum();
+    let start = (1 + cursor_bucket).min(width.saturating_sub(used));
+    spans.insert(0, Span::raw(" ".repeat(start)));
+    Line::from(spans)
+}
+
 fn render_sparkline(frame: &mut Frame, app: &App, area: Rect) {
     let sparkline = match app.sparkline_data() {
         Some(s) => s,
@@ -589,6 +2294,16 @@ fn render_sparkline(frame: &mut Frame, app: &App, area: Rect) {
 
     let max_val = sparkline.buckets.iter().copied().max().unwrap_or(1).max(1);
 
+    // Buckets holding a marked event (deploys, restarts), drawn as vertical markers
+    let mut marker_colors: Vec<Option<Color>> = vec![None; sparkline.num_buckets];
+    for (idx, rule) in app.markers() {
+        if let Some(ts) = index.timestamp_at(idx)
+            && let Some(bucket) = sparkline.bucket_for(ts)
+        {
+            marker_colors[bucket] = Some(rule.color);
+        }
+    }
+
     // Build sparkline line 1: bar characters
     let mut bar_spans: Vec<Span> = Vec::with_capacity(sparkline.num_buckets + 5);
     bar_spans.push(Span::styled(" ", Style::default()));
@@ -599,13 +2314,18 @@ fn render_sparkline(frame: &mut Frame, app: &App, area: Rect) {
         } else {
             ((count as f64 / max_val as f64) * 8.0).ceil() as usize
         };
-        let bar_char = SPARKLINE_CHARS[bar_idx.min(8)];
+        let mut bar_char = SPARKLINE_CHARS[bar_idx.min(8)];
 
         let is_selected = selected_range.is_some_and(|(s, e)| i >= s && i <= e);
         let is_cursor = is_time_mode && time_state.is_some_and(|s| s.cursor_bucket == i);
 
         let style = if is_cursor {
             Style::default().fg(Color::Yellow).bg(Color::Yellow)
+        } else if let Some(color) = marker_colors[i] {
+            if bar_idx == 0 {
+                bar_char = '│';
+            }
+            Style::default().fg(color)
         } else if is_selected {
             Style::default().fg(Color::Cyan)
         } else {
@@ -689,7 +2409,17 @@ fn render_sparkline(frame: &mut Frame, app: &App, area: Rect) {
         axis_spans.push(Span::styled(hints, Style::default().fg(Color::DarkGray)));
 
         let line2 = Line::from(axis_spans);
-        let sparkline_widget = Paragraph::new(vec![line1, line2]);
+        let mut rows = vec![line1, line2];
+        if area.height >= 3
+            && let Some(state) = time_state
+        {
+            rows.push(bucket_levels_line(
+                app,
+                state.cursor_bucket,
+                area.width as usize,
+            ));
+        }
+        let sparkline_widget = Paragraph::new(rows);
         frame.render_widget(sparkline_widget, area);
     } else {
         let sparkline_widget = Paragraph::new(line1);
@@ -713,6 +2443,9 @@ fn render_stats_bar(frame: &mut Frame, app: &App, area: Rect, level_counts: &[(L
         if active_min == Some(*level) {
             style = style.add_modifier(Modifier::UNDERLINED);
         }
+        if app.hidden_levels().contains(level) {
+            style = style.add_modifier(Modifier::CROSSED_OUT);
+        }
         spans.push(Span::styled(label, style));
     }
 
@@ -727,30 +2460,9 @@ pub fn stats_level_at_position(app: &App, column: u16, row: u16, area: Rect) ->
         return None;
     }
 
-    let filter_height =
-        if app.is_filter_mode() || app.mode() == AppMode::Ask || app.mode() == AppMode::Analyze {
-            1
-        } else {
-            0
-        };
-    let sparkline_height: u16 = if !app.is_sparkline_visible() {
-        0
-    } else if app.mode() == AppMode::TimeRange {
-        2
-    } else {
-        1
-    };
-
-    let [_, _, _, stats_area, _] = Layout::vertical([
-        Constraint::Length(sparkline_height),
-        Constraint::Fill(1),
-        Constraint::Length(filter_height),
-        Constraint::Length(1), // stats bar exists since level_counts is non-empty
-        Constraint::Length(1),
-    ])
-    .areas(area);
+    let [_, _, _, _, stats_area, _, _] = screen_areas(app, area, true);
 
-    if row != stats_area.y {
+    if stats_area.height == 0 || row != stats_area.y {
         return None;
     }
 
@@ -799,73 +2511,58 @@ fn bucket_indices_for_time_range(
     Some((start_bucket, end_bucket))
 }
 
-/// Given a click coordinate in the sparkline area, determine which bucket was clicked.
-pub fn sparkline_bucket_at_position(app: &App, column: u16, row: u16, area: Rect) -> Option<usize> {
+/// Where the sparkline bars are drawn: one column per bucket after a leading
+/// space, over every row of the sparkline (bars and, in time mode, the axis).
+fn sparkline_bars_area(app: &App, area: Rect) -> Option<Rect> {
     if !app.is_sparkline_visible() {
         return None;
     }
-
-    let sparkline_height: u16 = if app.mode() == AppMode::TimeRange {
-        2
-    } else {
-        1
-    };
-
-    let stats_height: u16 = if app.level_counts().is_empty() { 0 } else { 1 };
-
-    let [sparkline_area, _, _, _, _] = Layout::vertical([
-        Constraint::Length(sparkline_height),
-        Constraint::Fill(1),
-        Constraint::Length(if app.is_filter_mode() { 1 } else { 0 }),
-        Constraint::Length(stats_height),
-        Constraint::Length(1),
-    ])
-    .areas(area);
-
-    if row < sparkline_area.y || row >= sparkline_area.y + sparkline_area.height {
-        return None;
-    }
-
     let sparkline = app.sparkline_data()?;
-
-    // Bars start at column 1 (after leading space)
-    let bar_start = sparkline_area.x + 1;
-    let bar_end = bar_start + sparkline.num_buckets as u16;
-
-    if column >= bar_start && column < bar_end {
-        let bucket = (column - bar_start) as usize;
-        if bucket < sparkline.num_buckets {
-            return Some(bucket);
-        }
+    let has_stats = !app.level_counts().is_empty();
+    let [sparkline_area, _, _, _, _, _, _] = screen_areas(app, area, has_stats);
+    let bars = Rect {
+        x: sparkline_area.x + 1,
+        width: sparkline.num_buckets as u16,
+        ..sparkline_area
     }
+    .intersection(sparkline_area);
+    (!bars.is_empty()).then_some(bars)
+}
 
-    None
+/// Given a click coordinate in the sparkline area, determine which bucket was clicked.
+pub fn sparkline_bucket_at_position(app: &App, column: u16, row: u16, area: Rect) -> Option<usize> {
+    let bars = sparkline_bars_area(app, area)?;
+    bars.contains(Position::new(column, row))
+        .then(|| (column - bars.x) as usize)
+}
+
+/// The bucket nearest to `column`, for a drag that has left the sparkline:
+/// columns past either end map to the first or last bucket.
+pub fn nearest_sparkline_bucket(app: &App, column: u16, area: Rect) -> Option<usize> {
+    let bars = sparkline_bars_area(app, area)?;
+    Some((column.clamp(bars.x, bars.right() - 1) - bars.x) as usize)
 }
 
 // --- Existing helper functions ---
 
 /// Given a click coordinate, determine which token (if any) was clicked.
-/// Returns the `TokenKind` and the raw matched text.
+/// Returns the `TokenKind`, the raw matched text and the original index of
+/// the line it's on.
 pub fn token_at_position(
     app: &App,
     column: u16,
     row: u16,
     area: Rect,
-) -> Option<(TokenKind, String)> {
+) -> Option<(TokenKind, String, usize)> {
     // Recompute the layout the same way render() does
     let filter_height = if app.mode() == AppMode::Filter { 1 } else { 0 };
-    let sparkline_height: u16 = if !app.is_sparkline_visible() {
-        0
-    } else if app.mode() == AppMode::TimeRange {
-        2
-    } else {
-        1
-    };
+    let sparkline_height = sparkline_height(app);
 
     let stats_height: u16 = if app.level_counts().is_empty() { 0 } else { 1 };
 
-    let [_, main_area, _, _, _] = Layout::vertical([
+    let [_, _, main_area, _, _, _] = Layout::vertical([
         Constraint::Length(sparkline_height),
+        Constraint::Length(pinned_height(app)),
         Constraint::Fill(1),
         Constraint::Length(filter_height),
         Constraint::Length(stats_height),
@@ -892,12 +2589,13 @@ pub fn token_at_position(
     let click_col = (column - content_x) as usize;
 
     let line_num_width = format!("{}", app.total_lines_unfiltered()).len().max(3);
-    let prefix_width = line_num_width + 1; // +1 for the trailing space
+    // +1 for the trailing space
+    let prefix_width = line_num_width + 1 + level_tag_width(app);
 
     // Determine which parsed line corresponds to this row
     let visible = app.visible_parsed_lines_numbered();
 
-    if app.is_pretty() {
+    if app.has_pretty_lines() {
         // Pretty mode: all sub-lines have a same-width prefix (blank for continuations)
         if click_col < prefix_width {
             return None;
@@ -905,15 +2603,32 @@ pub fn token_at_position(
         let text_col = click_col - prefix_width;
 
         let mut display_row = 0;
-        for (_line_num, parsed) in &visible {
-            let expanded = highlight_line_expanded(parsed, true);
-            let row_count = expanded.len();
+        for (line_num, parsed) in &visible {
+            let pretty = app.is_line_pretty(line_num - 1);
+            let row_count = highlight_line_expanded(parsed, pretty).len();
             if click_row < display_row + row_count {
                 let base_style = Style::default();
-                let tokens = tokenize_with_metadata(&parsed.message, base_style);
-                return find_token_at_col(text_col, &tokens);
+                let on_line = |(kind, value)| (kind, value, line_num - 1);
+                if pretty {
+                    let tokens = tokenize_with_metadata(&parsed.message, base_style);
+                    return find_token_at_col(text_col, &tokens).map(on_line);
+                }
+                let tokens = tokenize_with_metadata(&get_clickable_text(parsed), base_style);
+                let skip = get_highlight_prefix_len(parsed, app.level_badge())
+                    + get_timestamp_prefix_len(parsed);
+                return text_col
+                    .checked_sub(skip)
+                    .and_then(|col| find_token_at_col(col, &tokens))
+                    .map(on_line);
             }
             display_row += row_count;
+            // Folded continuation lines have no clickable tokens
+            if pretty {
+                display_row += app.continuation_lines(line_num - 1).len();
+                if click_row < display_row {
+                    return None;
+                }
+            }
         }
     } else if app.is_wrap() {
         // Wrapped non-pretty: lines may span multiple display rows
@@ -923,12 +2638,14 @@ pub fn token_at_position(
         }
 
         let mut display_row = 0;
-        for (_line_num, parsed) in &visible {
-            let content_len: usize = highlight_line(parsed)
-                .spans
-                .iter()
-                .map(|s| s.content.len())
-                .sum();
+        for (line_num, parsed) in &visible {
+            // Only the length is needed, which doesn't depend on the styled window
+            let content_len: usize =
+                highlight_line_visible_with(parsed, &(0..0), app.level_badge())
+                    .spans
+                    .iter()
+                    .map(|s| s.content.len())
+                    .sum();
             let line_display_len = prefix_width + content_len;
             let rows = line_display_len.div_ceil(wrap_width).max(1);
 
@@ -946,14 +2663,15 @@ pub fn token_at_position(
                 let ts_prefix_len = get_timestamp_prefix_len(parsed);
                 let tokens = tokenize_with_metadata(&text_to_tokenize, base_style);
 
-                let extra_prefix = get_highlight_prefix_len(parsed);
+                let extra_prefix = get_highlight_prefix_len(parsed, app.level_badge());
                 let adjusted_col = if text_col >= extra_prefix + ts_prefix_len {
                     text_col - extra_prefix - ts_prefix_len
                 } else {
                     return None;
                 };
 
-                return find_token_at_col(adjusted_col, &tokens);
+                return find_token_at_col(adjusted_col, &tokens)
+                    .map(|(kind, value)| (kind, value, line_num - 1));
             }
             display_row += rows;
         }
@@ -965,20 +2683,21 @@ pub fn token_at_position(
         let text_col = click_col - prefix_width;
 
         if click_row < visible.len() {
-            let (_line_num, parsed) = &visible[click_row];
+            let (line_num, parsed) = &visible[click_row];
             let base_style = Style::default();
             let text_to_tokenize = get_clickable_text(parsed);
             let ts_prefix_len = get_timestamp_prefix_len(parsed);
             let tokens = tokenize_with_metadata(&text_to_tokenize, base_style);
 
-            let extra_prefix = get_highlight_prefix_len(parsed);
+            let extra_prefix = get_highlight_prefix_len(parsed, app.level_badge());
             let adjusted_col = if text_col >= extra_prefix + ts_prefix_len {
                 text_col - extra_prefix - ts_prefix_len
             } else {
                 return None;
             };
 
-            return find_token_at_col(adjusted_col, &tokens);
+            return find_token_at_col(adjusted_col, &tokens)
+                .map(|(kind, value)| (kind, value, line_num - 1));
         }
     }
 
@@ -992,7 +2711,7 @@ fn find_token_at_col(
 ) -> Option<(TokenKind, String)> {
     let mut pos = 0;
     for (_span, kind, raw) in tokens {
-        let end = pos + raw.len();
+        let end = pos + text_width(raw);
         if col >= pos && col < end {
             return kind.map(|k| (k, raw.clone()));
         }
@@ -1002,36 +2721,44 @@ fn find_token_at_col(
 }
 
 /// Get the full clickable text for a parsed line, including extra fields.
-/// For structured formats, this is message + "  " + extras (matching what the highlighter renders).
+/// For structured formats, this is message + "  " + extras (matching what the highlighter renders);
+/// access log lines use their column layout.
 fn get_clickable_text(parsed: &crate::parser::ParsedLine) -> String {
+    if parsed.format == LogFormat::AccessLog
+        && let Some(text) = access_line_text(parsed)
+    {
+        return text;
+    }
     match parsed.format {
         LogFormat::Json
         | LogFormat::Logfmt
         | LogFormat::Klog
         | LogFormat::Log4j
         | LogFormat::PythonLog
-        | LogFormat::AccessLog => {
+        | LogFormat::AccessLog
+        | LogFormat::Console
+        | LogFormat::Cri => {
             let mut text = parsed.message.clone();
             if !parsed.extra_fields.is_empty() {
                 text.push_str("  ");
-                let extras: String = parsed
-                    .extra_fields
-                    .iter()
-                    .map(|(k, v)| format!("{}={}", k, v))
-                    .collect::<Vec<_>>()
-                    .join(" ");
-                text.push_str(&extras);
+                text.push_str(&extra_fields_text(parsed));
             }
             text
         }
         LogFormat::Plain | LogFormat::Syslog => {
-            if let Some(ref ts) = parsed.timestamp
-                && let Some(pos) = parsed.raw.find(ts.as_str())
-            {
-                let ts_end = pos + ts.len();
-                return parsed.raw[ts_end..].to_string();
+            let mut text = parsed
+                .timestamp
+                .as_ref()
+                .and_then(|ts| Some(parsed.raw.find(ts.as_str())? + ts.len()))
+                .map_or_else(
+                    || parsed.raw.clone(),
+                    |ts_end| parsed.raw[ts_end..].to_string(),
+                );
+            if !parsed.extra_fields.is_empty() {
+                text.push_str("  ");
+                text.push_str(&extra_fields_text(parsed));
             }
-            parsed.raw.clone()
+            text
         }
     }
 }
@@ -1044,7 +2771,9 @@ fn get_timestamp_prefix_len(parsed: &crate::parser::ParsedLine) -> usize {
         | LogFormat::Klog
         | LogFormat::Log4j
         | LogFormat::PythonLog
-        | LogFormat::AccessLog => 0, // structured formats handle timestamp in extra prefix
+        | LogFormat::AccessLog
+        | LogFormat::Console
+        | LogFormat::Cri => 0, // structured formats handle timestamp in extra prefix
         LogFormat::Plain | LogFormat::Syslog => {
             if let Some(ref ts) = parsed.timestamp
                 && let Some(pos) = parsed.raw.find(ts.as_str())
@@ -1059,15 +2788,17 @@ fn get_timestamp_prefix_len(parsed: &crate::parser::ParsedLine) -> usize {
 /// Returns the character length of extra prefix spans added by highlight_*_line.
 /// For JSON: "[LVL] " (6) + timestamp + space if present.
 /// For plain/syslog: 0 (timestamp is part of raw text, handled by ts_prefix_len).
-fn get_highlight_prefix_len(parsed: &crate::parser::ParsedLine) -> usize {
+fn get_highlight_prefix_len(parsed: &crate::parser::ParsedLine, badge: LevelBadge) -> usize {
     match parsed.format {
         LogFormat::Json
         | LogFormat::Logfmt
         | LogFormat::Klog
         | LogFormat::Log4j
         | LogFormat::PythonLog
-        | LogFormat::AccessLog => {
-            let level_len = 6; // "[XXX] "
+        | LogFormat::AccessLog
+        | LogFormat::Console
+        | LogFormat::Cri => {
+            let level_len = badge.width(parsed.level);
             let ts_len = parsed
                 .timestamp
                 .as_ref()
@@ -1079,6 +2810,97 @@ fn get_highlight_prefix_len(parsed: &crate::parser::ParsedLine) -> usize {
     }
 }
 
+/// Columns of the level tag text cues put after the gutter, for formats
+/// whose lines don't start with a `[LVL]` badge or when badges are off.
+fn level_tag_width(app: &App) -> usize {
+    if app.is_text_cues()
+        && (!shows_level_badge(app.format()) || app.level_badge() == LevelBadge::None)
+    {
+        4
+    } else {
+        0
+    }
+}
+
+/// The high-contrast theme, applied over a drawn screen: no dimming, grays
+/// and dark colors brightened, and black or white text on backgrounds.
+fn high_contrast(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        cell.modifier.remove(Modifier::DIM);
+        cell.fg = match cell.fg {
+            Color::DarkGray | Color::Indexed(_) => Color::Gray,
+            Color::Red => Color::LightRed,
+            Color::Green => Color::LightGreen,
+            Color::Yellow => Color::LightYellow,
+            Color::Blue => Color::LightCyan,
+            Color::Magenta => Color::LightMagenta,
+            Color::Cyan => Color::LightCyan,
+            Color::Rgb(r, g, b) if u16::from(r) + u16::from(g) + u16::from(b) < 384 => Color::White,
+            fg => fg,
+        };
+        match cell.bg {
+            Color::Reset | Color::Black => {}
+            Color::DarkGray => {
+                cell.bg = Color::White;
+                cell.fg = Color::Black;
+            }
+            Color::Red | Color::Blue | Color::Magenta => cell.fg = Color::White,
+            _ => cell.fg = Color::Black,
+        }
+    }
+}
+
+/// `line` grayed out, as for context lines around a match.
+fn dim_line(line: Line<'_>) -> Line<'static> {
+    Line::from(
+        line.spans
+            .iter()
+            .map(|span| Span::styled(span.content.to_string(), span.style.fg(Color::DarkGray)))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Centered list popup of `len` rows built by `row`, scrolled to keep the
+/// `selected` one in view and highlighted.
+fn render_list_overlay(
+    frame: &mut Frame,
+    area: Rect,
+    title: String,
+    len: usize,
+    selected: usize,
+    row: impl Fn(usize) -> Line<'static>,
+) {
+    let overlay_width = (area.width * 4 / 5).min(area.width.saturating_sub(4));
+    let max_visible = (area.height * 3 / 5).saturating_sub(2).max(1) as usize;
+    let visible_count = len.min(max_visible);
+    let overlay_height = (visible_count as u16 + 2).min(area.height);
+    let x = (area.width.saturating_sub(overlay_width)) / 2;
+    let y = (area.height.saturating_sub(overlay_height)) / 2;
+    let overlay_area = Rect::new(x, y, overlay_width, overlay_height);
+
+    let scroll_offset = (selected + 1).saturating_sub(max_visible);
+    let rows: Vec<Line> = (scroll_offset..len.min(scroll_offset + max_visible))
+        .map(|i| {
+            let line = row(i);
+            if i == selected {
+                apply_bg_to_line(line, Color::DarkGray)
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    let overlay = Paragraph::new(rows).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(Color::White).bg(Color::Black)),
+    );
+
+    frame.render_widget(Clear, overlay_area);
+    frame.render_widget(overlay, overlay_area);
+}
+
 /// Overlay a background color on every span in a line, preserving existing fg/modifiers.
 fn apply_bg_to_line(line: Line<'_>, bg: Color) -> Line<'static> {
     Line::from(
@@ -1089,6 +2911,34 @@ fn apply_bg_to_line(line: Line<'_>, bg: Color) -> Line<'static> {
     )
 }
 
+/// Width of the bars in the line length panel.
+const LINE_STATS_BAR_WIDTH: usize = 24;
+
+/// Width of the frequency bars in the value distribution popup.
+const VALUE_DIST_BAR_WIDTH: usize = 12;
+
+/// Centered area of the value distribution popup, one row per value.
+fn value_dist_rect(app: &App, area: Rect) -> Option<Rect> {
+    let state = app.value_distribution()?;
+    let overlay_width = (area.width * 3 / 5).min(area.width.saturating_sub(4));
+    let summary_row = u16::from(state.summary.is_some());
+    let overlay_height = (state.dist.values.len() as u16 + summary_row + 2).min(area.height);
+    let x = (area.width.saturating_sub(overlay_width)) / 2;
+    let y = (area.height.saturating_sub(overlay_height)) / 2;
+    Some(Rect::new(x, y, overlay_width, overlay_height))
+}
+
+/// Row of the value distribution popup under a mouse click, if any.
+pub fn value_dist_item_at_position(app: &App, column: u16, row: u16, area: Rect) -> Option<usize> {
+    let rect = value_dist_rect(app, area)?;
+    let inside = column > rect.x
+        && column < rect.x + rect.width.saturating_sub(1)
+        && row > rect.y
+        && row < rect.y + rect.height.saturating_sub(1);
+    let index = (row.checked_sub(rect.y + 1)?) as usize;
+    (inside && index < app.value_distribution()?.dist.values.len()).then_some(index)
+}
+
 /// Check if a click position lands on a context menu item.
 /// Returns the 0-based item index if so.
 pub fn menu_item_at_position(app: &App, column: u16, row: u16, area: Rect) -> Option<usize> {
@@ -1097,7 +2947,7 @@ pub fn menu_item_at_position(app: &App, column: u16, row: u16, area: Rect) -> Op
     let menu_width = menu
         .items
         .iter()
-        .map(|a| a.label().len() as u16 + 2)
+        .map(|a| text_width(a.label()) as u16 + 2)
         .max()
         .unwrap_or(20)
         + 2;
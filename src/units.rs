@@ -0,0 +1,106 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// A number with an optional unit suffix: `500`, `2.3s`, `1.5MB`.
+static QUANTITY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([-+]?\d+(?:\.\d+)?(?:[eE][-+]?\d+)?)\s*([a-zA-Zµ]*)$").unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    /// A bare number.
+    Scalar,
+    /// Normalized to milliseconds.
+    Duration,
+    /// Normalized to bytes.
+    Size,
+}
+
+/// A parsed value, normalized to the base unit of its dimension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub dimension: Dimension,
+}
+
+fn unit_factor(unit: &str) -> Option<(f64, Dimension)> {
+    let duration = match unit {
+        "ns" => Some(1e-6),
+        "us" | "µs" => Some(1e-3),
+        "ms" => Some(1.0),
+        "s" | "sec" => Some(1000.0),
+        "m" | "min" => Some(60_000.0),
+        "h" => Some(3_600_000.0),
+        "d" => Some(86_400_000.0),
+        _ => None,
+    };
+    if let Some(f) = duration {
+        return Some((f, Dimension::Duration));
+    }
+    let size = match unit.to_ascii_lowercase().as_str() {
+        "b" => 1.0,
+        "kb" | "k" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        "tib" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((size, Dimension::Size))
+}
+
+/// Parse `text` (surrounding quotes allowed) as a number with an optional
+/// duration or size unit. Unknown suffixes don't parse.
+pub fn parse_quantity(text: &str) -> Option<Quantity> {
+    let caps = QUANTITY_RE.captures(text.trim().trim_matches('"'))?;
+    let number: f64 = caps[1].parse().ok()?;
+    if caps[2].is_empty() {
+        return Some(Quantity {
+            value: number,
+            dimension: Dimension::Scalar,
+        });
+    }
+    let (factor, dimension) = unit_factor(&caps[2])?;
+    Some(Quantity {
+        value: number * factor,
+        dimension,
+    })
+}
+
+/// Trim `{:.1}`-style trailing zeros: `2.0` → `2`, `2.5` → `2.5`.
+fn short_number(n: f64) -> String {
+    let text = format!("{n:.1}");
+    text.strip_suffix(".0").unwrap_or(&text).to_string()
+}
+
+/// Display a normalized quantity in the largest unit that keeps it at 1 or
+/// more: `850ms`, `2.3s`, `4.5min`, `1.5MB`.
+pub fn format_quantity(q: Quantity) -> String {
+    let units: &[(f64, &str)] = match q.dimension {
+        Dimension::Scalar => return short_number(q.value),
+        Dimension::Duration => &[
+            (86_400_000.0, "d"),
+            (3_600_000.0, "h"),
+            (60_000.0, "min"),
+            (1000.0, "s"),
+            (1.0, "ms"),
+            (1e-3, "µs"),
+            (1e-6, "ns"),
+        ],
+        Dimension::Size => &[
+            (1e12, "TB"),
+            (1e9, "GB"),
+            (1e6, "MB"),
+            (1e3, "KB"),
+            (1.0, "B"),
+        ],
+    };
+    let (factor, unit) = units
+        .iter()
+        .find(|(factor, _)| q.value.abs() >= *factor)
+        .unwrap_or(&units[units.len() - 1]);
+    format!("{}{unit}", short_number(q.value / factor))
+}
@@ -0,0 +1,85 @@
+use crate::config::PaneLayout;
+use crate::parser::LogLevel;
+use crate::timeindex::TimeRange;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File written by "Export view state" in the current directory.
+pub const DEFAULT_FILE: &str = "lumolog-view.json";
+
+/// The slice of a log someone is looking at: which file, and the filters that
+/// narrow it. Saved as JSON and reopened with `lumolog --load FILE`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewState {
+    /// Absolute path of the log file; None when reading stdin.
+    pub file: Option<PathBuf>,
+    pub filter: String,
+    /// Filters stacked before `filter`; a line must match all of them.
+    pub filters: Vec<String>,
+    pub min_level: Option<LogLevel>,
+    /// Levels hidden one by one, on top of `min_level`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hidden_levels: Vec<LogLevel>,
+    pub time_range: Option<TimeRange>,
+    /// Pane layout; None in files saved before it was recorded.
+    pub layout: Option<PaneLayout>,
+}
+
+impl ViewState {
+    /// Fails when `file` isn't valid UTF-8, which JSON can't hold.
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(text: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("cannot read view state {}: {e}", path.display()))?;
+        Self::from_json(&text)
+            .map_err(|e| anyhow::anyhow!("invalid view state {}: {e}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let json = self
+            .to_json()
+            .map_err(|e| anyhow::anyhow!("cannot save view state {}: {e}", path.display()))?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| anyhow::anyhow!("cannot write view state {}: {e}", path.display()))
+    }
+}
+
+/// Where the viewer was when it quit, written by `--dump-state-on-exit` for
+/// wrapper scripts: the view state fields plus the lines being looked at.
+/// Line numbers are 1-based, as shown in the gutter.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExitState {
+    #[serde(flatten)]
+    pub view: ViewState,
+    /// The cursor line, or the top line shown outside cursor mode; None
+    /// when the filters leave nothing to show.
+    pub cursor_line: Option<usize>,
+    /// The line marked with `m`.
+    pub mark: Option<usize>,
+    /// Pinned lines, oldest first.
+    pub pins: Vec<usize>,
+    /// Lines shown with the filters applied.
+    pub matches: usize,
+}
+
+impl ExitState {
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let json = self
+            .to_json()
+            .map_err(|e| anyhow::anyhow!("cannot save state {}: {e}", path.display()))?;
+        std::fs::write(path, json + "\n")
+            .map_err(|e| anyhow::anyhow!("cannot write state {}: {e}", path.display()))
+    }
+}
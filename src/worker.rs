@@ -0,0 +1,80 @@
+use crate::split::Multiplexer;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// Side effects that can block for hundreds of milliseconds (clipboard
+/// handshakes on Wayland/X11, spawning a browser) and so run off the UI thread.
+pub enum Job {
+    Copy(String),
+    Open(String),
+    /// Run a shell command in a new multiplexer pane.
+    Split(Multiplexer, String),
+}
+
+pub enum JobOutcome {
+    Copied,
+    Opened,
+    Failed(String),
+}
+
+/// A single background thread that runs jobs in submission order.
+pub struct Worker {
+    jobs: Sender<Job>,
+    outcomes: Receiver<JobOutcome>,
+}
+
+impl Worker {
+    pub fn spawn() -> Self {
+        let (job_tx, job_rx) = mpsc::channel::<Job>();
+        let (outcome_tx, outcome_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            // One clipboard handle for the worker's lifetime: on X11 the owner
+            // has to stay alive for other apps to paste what we copied
+            let mut clipboard: Option<arboard::Clipboard> = None;
+            for job in job_rx {
+                let outcome = match job {
+                    Job::Copy(text) => {
+                        if clipboard.is_none() {
+                            clipboard = arboard::Clipboard::new().ok();
+                        }
+                        match clipboard.as_mut() {
+                            Some(cb) => match cb.set_text(text) {
+                                Ok(()) => JobOutcome::Copied,
+                                Err(e) => JobOutcome::Failed(format!("clipboard: {e}")),
+                            },
+                            None => JobOutcome::Failed("clipboard unavailable".to_string()),
+                        }
+                    }
+                    Job::Open(target) => match open::that(&target) {
+                        Ok(()) => JobOutcome::Opened,
+                        Err(e) => JobOutcome::Failed(format!("open: {e}")),
+                    },
+                    Job::Split(mux, command) => match mux.split_command(&command).output() {
+                        Ok(out) if out.status.success() => JobOutcome::Opened,
+                        Ok(out) => JobOutcome::Failed(format!(
+                            "{}: {}",
+                            mux.name(),
+                            String::from_utf8_lossy(&out.stderr).trim()
+                        )),
+                        Err(e) => JobOutcome::Failed(format!("{}: {e}", mux.name())),
+                    },
+                };
+                if outcome_tx.send(outcome).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            jobs: job_tx,
+            outcomes: outcome_rx,
+        }
+    }
+
+    pub fn submit(&self, job: Job) {
+        let _ = self.jobs.send(job);
+    }
+
+    /// Next finished job, if any. Never blocks.
+    pub fn try_recv(&self) -> Option<JobOutcome> {
+        self.outcomes.try_recv().ok()
+    }
+}
@@ -0,0 +1,31 @@
+use lumolog::ansi::line_to_ansi;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+#[test]
+fn test_styled_spans_get_sgr_codes_and_reset() {
+    let line = Line::from(vec![
+        Span::styled(
+            "ERROR",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" boom "),
+        Span::styled(
+            "id",
+            Style::default()
+                .fg(Color::Indexed(249))
+                .bg(Color::Rgb(1, 2, 3)),
+        ),
+    ]);
+    assert_eq!(
+        line_to_ansi(&line),
+        "\x1b[1;31mERROR\x1b[0m boom \x1b[38;5;249;48;2;1;2;3mid\x1b[0m"
+    );
+}
+
+#[test]
+fn test_line_style_applies_to_its_spans() {
+    let line = Line::from("warn").style(Style::default().fg(Color::DarkGray));
+    assert_eq!(line_to_ansi(&line), "\x1b[90mwarn\x1b[0m");
+    assert_eq!(line_to_ansi(&Line::from("plain")), "plain");
+}
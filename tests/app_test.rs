@@ -1,5 +1,12 @@
-use lumolog::app::{App, AppMode};
+use lumolog::app::{App, AppMode, Breadcrumb, MAX_PINS, MenuAction, SortOrder};
+use lumolog::bundle::RuleBundle;
+use lumolog::config::{BlankLines, EscAction, FuzzyConfig, PivotUrl, ScrollConfig, SplitCommand};
+use lumolog::filter::{CaseMode, FilterWarning};
+use lumolog::highlighter::TokenKind;
+use lumolog::markers::MarkerRule;
 use lumolog::parser::{LogFormat, LogLevel};
+use lumolog::savedfilters::SavedFilters;
+use lumolog::timeindex::parse_user_time;
 
 #[test]
 fn test_scroll_down() {
@@ -222,3 +229,2098 @@ fn test_set_min_level_changes_level() {
     assert_eq!(app.min_level(), Some(LogLevel::Warn));
     assert_eq!(app.total_lines(), 2);
 }
+
+// Per-line pretty expansion tests
+
+#[test]
+fn test_toggle_line_pretty_expands_only_cursor_line() {
+    let lines: Vec<String> = (0..5)
+        .map(|i| format!(r#"{{"level":"info","msg":"event {}","n":{}}}"#, i, i))
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(50);
+    app.enter_cursor_mode();
+    app.cursor_down(2);
+    app.toggle_line_pretty();
+    assert!(app.is_line_pretty(2));
+    assert!(!app.is_line_pretty(1));
+    assert!(!app.is_pretty());
+    assert!(app.has_pretty_lines());
+
+    app.toggle_line_pretty();
+    assert!(!app.is_line_pretty(2));
+    assert!(!app.has_pretty_lines());
+}
+
+#[test]
+fn test_toggle_line_pretty_collapses_under_global_pretty() {
+    let lines: Vec<String> = (0..3)
+        .map(|i| format!(r#"{{"level":"info","msg":"event {}"}}"#, i))
+        .collect();
+    let mut app = App::new(lines);
+    app.toggle_pretty();
+    app.enter_cursor_mode();
+    app.toggle_line_pretty();
+    assert!(!app.is_line_pretty(0));
+    assert!(app.is_line_pretty(1));
+    // Flipping the global toggle resets individual overrides
+    app.toggle_pretty();
+    assert!(!app.is_line_pretty(0));
+    assert!(!app.has_pretty_lines());
+}
+
+#[test]
+fn test_diff_marked_line_with_cursor() {
+    let lines: Vec<String> = vec![
+        "GET /api/users status=200".into(),
+        "unrelated".into(),
+        "GET /api/users status=500".into(),
+    ];
+    let mut app = App::new(lines);
+    app.enter_cursor_mode();
+    // No mark yet: nothing to diff
+    app.open_diff();
+    assert!(app.diff_lines().is_none());
+
+    app.toggle_mark();
+    assert_eq!(app.mark(), Some(0));
+    app.cursor_down(2);
+    app.open_diff();
+    let ((old_num, old), (new_num, new)) = app.diff_lines().unwrap();
+    assert_eq!((old_num, new_num), (1, 3));
+    assert_eq!(old, "GET /api/users status=200");
+    assert_eq!(new, "GET /api/users status=500");
+
+    app.close_diff();
+    assert!(app.diff_lines().is_none());
+}
+
+#[test]
+fn test_toggle_mark_twice_clears() {
+    let lines: Vec<String> = (0..3).map(|i| format!("Line {}", i)).collect();
+    let mut app = App::new(lines);
+    app.enter_cursor_mode();
+    app.toggle_mark();
+    app.toggle_mark();
+    assert_eq!(app.mark(), None);
+}
+
+#[test]
+fn test_marked_range_yanks_and_saves_lines_in_view() {
+    let lines: Vec<String> = (0..10)
+        .map(|i| format!("{} line {i}", if i % 3 == 0 { "ERROR" } else { "INFO" }))
+        .collect();
+    let mut app = App::new(lines);
+    app.enter_cursor_mode();
+    assert_eq!(app.marked_range(), None);
+    assert_eq!(app.marked_range_raw(), None);
+
+    // Mark below the cursor works the same as above it
+    app.goto_line(8);
+    app.toggle_mark();
+    app.goto_line(3);
+    assert_eq!(app.marked_range(), Some((3, 8)));
+    assert_eq!(app.marked_range_count(), 6);
+    assert_eq!(
+        app.marked_range_file_name().as_deref(),
+        Some("lumolog-lines-3-8.log")
+    );
+
+    app.set_min_level(LogLevel::Error);
+    let text = app.marked_range_raw().unwrap();
+    assert_eq!(text, "ERROR line 3\nERROR line 6");
+    assert_eq!(app.marked_range_raw_len(), text.len());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("range.log");
+    app.save_marked_range(&path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "ERROR line 3\nERROR line 6\n"
+    );
+}
+
+#[test]
+fn test_scrolloff_keeps_context_below_cursor() {
+    let lines: Vec<String> = (0..100).map(|i| format!("Line {}", i)).collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    app.set_scroll_config(ScrollConfig {
+        scrolloff: 3,
+        ..ScrollConfig::default()
+    });
+    app.enter_cursor_mode();
+    app.cursor_down(6);
+    // Cursor at 6 with 3 lines of context: entries 0..=9 still fit
+    assert_eq!(app.scroll_offset(), 0);
+    app.cursor_down(1);
+    assert_eq!(app.scroll_offset(), 1);
+    app.cursor_up(4);
+    // Cursor at 3 needs entry 0 visible above it
+    assert_eq!(app.scroll_offset(), 0);
+}
+
+#[test]
+fn test_half_page_down_moves_cursor_and_viewport() {
+    let lines: Vec<String> = (0..100).map(|i| format!("Line {}", i)).collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    app.enter_cursor_mode();
+    app.half_page_down();
+    assert_eq!(app.cursor_position(), 5);
+    assert_eq!(app.scroll_offset(), 5);
+    app.half_page_up();
+    assert_eq!(app.cursor_position(), 0);
+    assert_eq!(app.scroll_offset(), 0);
+}
+
+#[test]
+fn test_half_page_uses_configured_step() {
+    let lines: Vec<String> = (0..100).map(|i| format!("Line {}", i)).collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    app.set_scroll_config(ScrollConfig {
+        half_page: 2,
+        ..ScrollConfig::default()
+    });
+    app.half_page_down();
+    assert_eq!(app.scroll_offset(), 2);
+}
+
+#[test]
+fn test_goto_line_moves_cursor() {
+    let lines: Vec<String> = (0..100).map(|i| format!("Line {}", i)).collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    app.enter_cursor_mode();
+    app.goto_line(50);
+    assert_eq!(app.cursor_position(), 49);
+    // Past the end clamps to the last line
+    app.goto_line(1000);
+    assert_eq!(app.cursor_position(), 99);
+}
+
+#[test]
+fn test_goto_line_skips_to_next_visible_when_filtered() {
+    let lines: Vec<String> = (0..20)
+        .map(|i| {
+            if i % 5 == 0 {
+                format!("ERROR event {}", i)
+            } else {
+                format!("INFO event {}", i)
+            }
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(2);
+    app.set_filter("ERROR".to_string());
+    // Lines 1, 6, 11, 16 (1-based) match; line 8 is filtered out
+    app.goto_line(8);
+    assert_eq!(app.scroll_offset(), 2);
+}
+
+#[test]
+fn test_filter_change_keeps_viewport_anchor() {
+    let lines: Vec<String> = (0..1000)
+        .map(|i| {
+            if i % 2 == 0 {
+                format!("even {}", i)
+            } else {
+                format!("odd {}", i)
+            }
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    app.scroll_down(500);
+    app.set_filter("even".to_string());
+    // Line 500 (original index) is still visible and stays at the top
+    assert_eq!(app.visible_parsed_lines_numbered()[0].0, 501);
+    app.set_filter("odd".to_string());
+    // Index 500 is gone; the nearest odd neighbour takes its place
+    let top = app.visible_parsed_lines_numbered()[0].0 - 1;
+    assert!(top == 499 || top == 501);
+}
+
+#[test]
+fn test_filter_change_keeps_cursor_line() {
+    let lines: Vec<String> = (0..100)
+        .map(|i| {
+            if i % 10 == 0 {
+                format!("ERROR {}", i)
+            } else {
+                format!("INFO {}", i)
+            }
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    app.enter_cursor_mode();
+    app.cursor_down(40);
+    app.set_filter("ERROR".to_string());
+    assert_eq!(app.cursor_position(), 4);
+    app.clear_filter();
+    assert_eq!(app.cursor_position(), 40);
+}
+
+#[test]
+fn test_filter_change_at_bottom_stays_at_bottom() {
+    let lines: Vec<String> = (0..100).map(|i| format!("Line {}", i)).collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    app.scroll_to_bottom();
+    app.set_filter("Line 1".to_string());
+    assert!(app.is_at_bottom());
+}
+
+#[test]
+fn test_background_job_tracking() {
+    let mut app = App::new(vec!["line".into()]);
+    assert!(!app.is_job_pending());
+    app.begin_background_job();
+    app.begin_background_job();
+    assert!(app.is_job_pending());
+    app.finish_background_job(None);
+    assert!(app.is_job_pending());
+    app.finish_background_job(Some("clipboard unavailable".into()));
+    assert!(!app.is_job_pending());
+    assert_eq!(app.job_error(), Some("clipboard unavailable"));
+    // Starting a new job clears the previous error
+    app.begin_background_job();
+    assert_eq!(app.job_error(), None);
+}
+
+#[test]
+fn test_palette_groups_by_category_until_searching() {
+    let mut app = App::new(vec!["line".into()]);
+    app.open_palette();
+    let cmds = lumolog::command::commands();
+    let categories: Vec<_> = app
+        .palette_filtered()
+        .iter()
+        .map(|&i| cmds[i].category)
+        .collect();
+    assert!(categories.is_sorted());
+    assert_eq!(categories.len(), cmds.len());
+}
+
+#[test]
+fn test_palette_searches_descriptions() {
+    let mut app = App::new(vec!["line".into()]);
+    app.open_palette();
+    for c in "severity".chars() {
+        app.palette_type(c);
+    }
+    let cmds = lumolog::command::commands();
+    let first = cmds[app.palette_filtered()[0]].action;
+    assert!(matches!(
+        first,
+        lumolog::command::Action::CycleLevelUp | lumolog::command::Action::CycleLevelDown
+    ));
+}
+
+#[test]
+fn test_palette_min_score_hides_weak_matches() {
+    let mut app = App::new(vec!["line".into()]);
+    app.open_palette();
+    app.palette_type('q');
+    assert!(!app.palette_filtered().is_empty());
+    app.set_fuzzy_config(
+        FuzzyConfig::default(),
+        FuzzyConfig {
+            min_score: u32::MAX,
+            ..FuzzyConfig::default()
+        },
+    );
+    app.palette_type('u');
+    assert!(app.palette_filtered().is_empty());
+}
+
+#[test]
+fn test_ignore_patterns_hide_lines() {
+    let lines: Vec<String> = vec![
+        "GET /healthz 200".into(),
+        "POST /orders 201".into(),
+        "GET /healthz 200".into(),
+    ];
+    let mut app = App::new(lines);
+    app.set_ignore_patterns(regex::RegexSet::new(["/healthz"]).unwrap());
+    assert_eq!(app.total_lines(), 1);
+    app.set_filter("POST".to_string());
+    assert_eq!(app.total_lines(), 1);
+    app.append_lines(vec!["GET /healthz 200".into(), "POST /users 201".into()]);
+    assert_eq!(app.total_lines(), 2);
+}
+
+#[test]
+fn test_pivots_appear_in_context_menu() {
+    let mut app = App::new(vec!["user=alice".into()]);
+    app.set_pivots(vec![PivotUrl {
+        name: "Search users".into(),
+        url: "https://admin/users?q={}".into(),
+    }]);
+    app.open_context_menu("alice".into(), TokenKind::KeyValue, (0, 0));
+    let (action, value) = app.execute_menu_item(1).unwrap();
+    assert_eq!(value, "alice");
+    assert_eq!(
+        action,
+        MenuAction::Pivot {
+            name: "Search users".into(),
+            url: "https://admin/users?q={}".into(),
+        }
+    );
+}
+
+#[test]
+fn test_goto_time_lands_on_first_line_at_or_after() {
+    let mut app = App::new(
+        (0..30)
+            .map(|i| format!("2024-01-15T08:{i:02}:00Z INFO tick {i}"))
+            .collect(),
+    );
+    app.enter_cursor_mode();
+    let at = |text: &str| parse_user_time(text).unwrap();
+    app.goto_time(at("2024-01-15T08:10"));
+    assert_eq!(app.cursor_position(), 10);
+    app.goto_time(at("2024-01-15 08:12:30"));
+    assert_eq!(app.cursor_position(), 13);
+    app.goto_time(at("2024-01-16"));
+    assert_eq!(app.cursor_position(), 29);
+    assert!(app.notice().is_some());
+
+    let mut plain = App::new(vec!["no time here".into()]);
+    plain.goto_time(at("2024-01-15"));
+    assert_eq!(plain.notice(), Some("No timestamps to go to"));
+}
+
+#[test]
+fn test_cursor_location_fills_template() {
+    let mut app = App::new(vec![
+        "2024-01-15T08:30:00Z INFO start".into(),
+        "2024-01-15T08:30:05Z ERROR boom".into(),
+        "    at handler".into(),
+    ]);
+    // The stack frame as a line of its own
+    app.set_join_lines(false);
+    app.set_source_path(std::path::PathBuf::from("/var/log/app.log"));
+    app.set_source_name("app.log".to_string());
+    app.enter_cursor_mode();
+    app.cursor_up(usize::MAX);
+    app.cursor_down(1);
+    assert_eq!(app.cursor_location().as_deref(), Some("/var/log/app.log:2"));
+
+    app.set_location_template("{file} +{line} @ {time}".to_string());
+    app.cursor_down(1);
+    assert_eq!(
+        app.cursor_location().as_deref(),
+        Some("app.log +3 @ 2024-01-15T08:30:05")
+    );
+}
+
+#[test]
+fn test_import_and_export_rule_bundle() {
+    let mut app = App::new(vec![
+        "GET /healthz 200".into(),
+        "POST /orders 500 timeout".into(),
+        "POST /orders 201".into(),
+        "GET /users 500 refused".into(),
+    ]);
+    app.set_ignore_patterns(regex::RegexSet::new(["/healthz"]).unwrap());
+    let bundle = RuleBundle::from_toml(
+        r#"
+filters = ["500", "orders | users"]
+ignore = ["/healthz", "201"]
+
+[[highlight]]
+pattern = "timeout"
+color = "red"
+"#,
+    )
+    .unwrap();
+    app.import_rules(&bundle).unwrap();
+    assert_eq!(app.filter_terms(), ["500"]);
+    assert_eq!(app.filter_pattern(), "orders | users");
+    assert_eq!(app.total_lines(), 2);
+    assert_eq!(app.highlight_rules().len(), 1);
+
+    let exported = app.rule_bundle();
+    assert_eq!(exported.filters, ["500", "orders | users"]);
+    assert_eq!(exported.ignore, ["/healthz", "201"]);
+    assert_eq!(exported.highlight, bundle.highlight);
+
+    // Importing again adds nothing twice
+    app.import_rules(&bundle).unwrap();
+    assert_eq!(app.rule_bundle(), exported);
+}
+
+#[test]
+fn test_restart_markers_found_on_load_and_append() {
+    let mut app = App::new(vec![
+        "INFO request ok".into(),
+        "INFO Listening on :8080".into(),
+    ]);
+    assert!(app.marker_at(0).is_none());
+    assert_eq!(app.marker_at(1).map(|r| r.label.as_str()), Some("restart"));
+    app.append_lines(vec!["WARN received SIGTERM".into()]);
+    let marked: Vec<usize> = app.markers().map(|(idx, _)| idx).collect();
+    assert_eq!(marked, [1, 2]);
+}
+
+#[test]
+fn test_event_list_jumps_to_marked_line() {
+    let lines: Vec<String> = (0..50)
+        .map(|i| {
+            if i == 10 || i == 30 {
+                format!("WARN circuit breaker opened {}", i)
+            } else {
+                format!("INFO ok {}", i)
+            }
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(5);
+    app.set_marker_rules(vec![MarkerRule {
+        label: "breaker".into(),
+        pattern: regex::Regex::new("circuit breaker opened").unwrap(),
+        color: ratatui::style::Color::Red,
+    }]);
+    app.open_event_list();
+    assert_eq!(app.event_list_selected(), Some(0));
+    app.event_list_down();
+    app.event_list_down();
+    assert_eq!(app.event_list_selected(), Some(1));
+    app.event_list_jump();
+    assert_eq!(app.event_list_selected(), None);
+    assert_eq!(app.scroll_offset(), 30);
+}
+
+#[test]
+fn test_trace_view_opens_on_current_line_and_jumps() {
+    let lines: Vec<String> = (0..30)
+        .map(|i| {
+            if i % 10 == 5 {
+                format!(r#"{{"level":"info","msg":"step {i}","trace_id":"t1","span_id":"s{i}"}}"#)
+            } else {
+                format!(r#"{{"level":"info","msg":"line {i}"}}"#)
+            }
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(5);
+    app.enter_cursor_mode();
+    app.cursor_down(15);
+    app.open_trace_view();
+    // trace t1, span s5, 5, span s15, 15, ...
+    assert_eq!(app.trace_view_selected(), Some(4));
+    assert_eq!(app.trace_rows().len(), 7);
+    app.trace_view_down();
+    app.trace_view_jump();
+    assert_eq!(app.trace_view_selected(), None);
+    assert_eq!(app.cursor_line_index(), Some(25));
+}
+
+#[test]
+fn test_trace_view_needs_trace_ids() {
+    let mut app = App::new(vec!["INFO no trace here".into()]);
+    app.open_trace_view();
+    assert_eq!(app.trace_view_selected(), None);
+}
+
+#[test]
+fn test_sampling_keeps_every_nth_and_all_warnings() {
+    let lines: Vec<String> = (0..100)
+        .map(|i| {
+            if i == 33 {
+                format!("ERROR boom {i}")
+            } else {
+                format!("INFO tick {i}")
+            }
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.toggle_sampling();
+    assert_eq!(app.sampling(), Some(10));
+    assert_eq!(app.unsampled_count(), 100);
+    // 0, 10, ..., 90 plus the error at 33
+    assert_eq!(app.total_lines(), 11);
+    assert!(
+        app.visible_parsed_lines_numbered()
+            .iter()
+            .any(|(_, l)| l.raw.contains("boom 33"))
+    );
+
+    app.sample_sparser();
+    assert_eq!(app.sampling(), Some(20));
+    assert_eq!(app.total_lines(), 6);
+    app.sample_denser();
+    app.sample_denser();
+    app.sample_denser();
+    assert_eq!(app.sampling(), Some(2));
+
+    app.toggle_sampling();
+    assert_eq!(app.sampling(), None);
+    assert_eq!(app.total_lines(), 100);
+}
+
+#[test]
+fn test_sampling_applies_after_filter() {
+    let lines: Vec<String> = (0..40).map(|i| format!("INFO req {i}")).collect();
+    let mut app = App::new(lines);
+    app.toggle_sampling();
+    app.enter_filter_mode();
+    for c in "req 1".chars() {
+        app.filter_input(c);
+    }
+    // "req 1" and "req 10".."req 19" match; every 10th of those is kept
+    assert_eq!(app.unsampled_count(), 11);
+    assert_eq!(app.total_lines(), 2);
+}
+
+#[test]
+fn test_storyline_follows_filter_and_jumps() {
+    let mut lines: Vec<String> = (0..20).map(|i| format!("INFO ok {i}")).collect();
+    lines[12] = "ERROR payment failed".into();
+    let mut app = App::new(lines);
+    app.set_viewport_height(5);
+    app.open_storyline();
+    assert_eq!(app.story_rows().len(), 3);
+    app.storyline_down();
+    assert!(
+        app.export_storyline()
+            .contains("line 13 ERR ERROR payment failed")
+    );
+    app.storyline_jump();
+    assert_eq!(app.storyline_selected(), None);
+    assert_eq!(app.scroll_offset(), 12);
+    assert_eq!(app.export_storyline(), "");
+}
+
+#[test]
+fn test_value_distribution_from_key_value_menu() {
+    let lines: Vec<String> = (0..10)
+        .map(|i| {
+            format!(
+                "level=info msg=req status={}",
+                if i % 3 == 0 { 500 } else { 200 }
+            )
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.open_context_menu("status=".into(), TokenKind::KeyValue, (0, 0));
+    let items = &app.context_menu().unwrap().items;
+    assert_eq!(items.last(), Some(&MenuAction::ValueDistribution));
+    let last = items.len() - 1;
+    let (action, value) = app.execute_menu_item(last).unwrap();
+    assert_eq!(action, MenuAction::ValueDistribution);
+
+    app.open_value_distribution(value.trim_end_matches('='));
+    let state = app.value_distribution().unwrap();
+    assert_eq!(state.dist.values[0].value, "200");
+    assert_eq!(state.dist.values[0].count, 6);
+    app.value_dist_down();
+    app.apply_value_distribution(None);
+    assert!(app.value_distribution().is_none());
+    assert_eq!(app.filter_pattern(), "status=500");
+    assert_eq!(app.total_lines(), 4);
+}
+
+#[test]
+fn test_value_distribution_needs_the_key() {
+    let mut app = App::new(vec!["INFO no pairs here".into()]);
+    app.open_value_distribution("status");
+    assert!(app.value_distribution().is_none());
+}
+
+#[test]
+fn test_event_list_needs_markers() {
+    let mut app = App::new(vec!["INFO nothing to see".into()]);
+    app.open_event_list();
+    assert_eq!(app.event_list_selected(), None);
+}
+
+#[test]
+fn test_annotate_cursor_line() {
+    let lines: Vec<String> = (0..10).map(|i| format!("INFO line {}", i)).collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(5);
+    app.enter_cursor_mode();
+    app.cursor_down(3);
+    app.enter_annotate_mode();
+    assert_eq!(app.mode(), AppMode::Annotate);
+    for c in "first retry".chars() {
+        app.annotate_type(c);
+    }
+    app.annotate_submit();
+    assert_eq!(app.mode(), AppMode::Cursor);
+    assert_eq!(app.annotation_at(3), Some("first retry"));
+
+    // Re-entering pre-fills the note; clearing it removes the annotation
+    app.enter_annotate_mode();
+    assert_eq!(app.annotation_input(), "first retry");
+    for _ in 0.."first retry".len() {
+        app.annotate_backspace();
+    }
+    app.annotate_submit();
+    assert_eq!(app.annotation_at(3), None);
+}
+
+#[test]
+fn test_annotate_escape_keeps_existing_note() {
+    let mut app = App::new(vec!["INFO a".into(), "INFO b".into()]);
+    app.enter_cursor_mode();
+    app.enter_annotate_mode();
+    app.annotate_type('x');
+    app.annotate_submit();
+    app.enter_annotate_mode();
+    app.annotate_type('y');
+    app.exit_annotate_mode();
+    assert_eq!(app.annotation_at(0), Some("x"));
+    assert_eq!(app.mode(), AppMode::Cursor);
+}
+
+#[test]
+fn test_annotation_list_jump_and_export() {
+    let lines: Vec<String> = (0..40)
+        .map(|i| format!("2024-01-15T10:00:{:02}Z INFO request {}", i, i))
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(5);
+    app.enter_cursor_mode();
+    for (line, note) in [(20, "slow"), (5, "start")] {
+        app.goto_line(line + 1);
+        app.enter_annotate_mode();
+        for c in note.chars() {
+            app.annotate_type(c);
+        }
+        app.annotate_submit();
+    }
+
+    let export = app.export_annotations();
+    let first = export.lines().next().unwrap();
+    assert!(first.starts_with("line 6 ["), "{first}");
+    assert!(first.ends_with("]: start"), "{first}");
+    assert!(export.contains("line 21 ["));
+    assert!(export.contains("    2024-01-15T10:00:20Z INFO request 20"));
+
+    app.open_annotation_list();
+    app.annotation_list_down();
+    assert_eq!(app.annotation_list_selected(), Some(1));
+    app.annotation_list_jump();
+    assert_eq!(app.annotation_list_selected(), None);
+    assert_eq!(app.cursor_line_index(), Some(20));
+}
+
+#[test]
+fn test_annotation_list_needs_annotations() {
+    let mut app = App::new(vec!["INFO nothing".into()]);
+    app.open_annotation_list();
+    assert_eq!(app.annotation_list_selected(), None);
+    assert_eq!(app.export_annotations(), "");
+}
+
+#[test]
+fn test_exit_state_reports_lines_being_looked_at() {
+    let mut app = App::new((0..10).map(|i| format!("INFO request {i}")).collect());
+    app.set_filter("request".to_string());
+    app.set_viewport_height(4);
+    app.scroll_to_top();
+    assert_eq!(app.exit_state().cursor_line, Some(1));
+
+    app.enter_cursor_mode();
+    app.goto_line(3);
+    app.toggle_mark();
+    app.goto_line(7);
+    app.toggle_pin();
+    let state = app.exit_state();
+    assert_eq!(state.view.filter, "request");
+    assert_eq!(state.cursor_line, Some(7));
+    assert_eq!(state.mark, Some(3));
+    assert_eq!(state.pins, [7]);
+    assert_eq!(state.matches, 10);
+
+    app.set_filter("nothing like this".to_string());
+    assert_eq!(app.exit_state().cursor_line, None);
+}
+
+#[test]
+fn test_view_state_restores_filters() {
+    let lines: Vec<String> = (0..60)
+        .map(|i| {
+            let level = if i % 3 == 0 { "ERROR" } else { "INFO" };
+            format!("2024-01-15T10:{:02}:00Z {} request {}", i, level, i)
+        })
+        .collect();
+    let mut source = App::new(lines.clone());
+    source.set_filter("request".to_string());
+    source.set_min_level(LogLevel::Error);
+    source.time_preset(15);
+    let state = source.view_state();
+    assert_eq!(state.min_level, Some(LogLevel::Error));
+    assert!(state.time_range.is_some());
+
+    let mut app = App::new(lines);
+    app.apply_view_state(&state);
+    assert_eq!(app.filter_pattern(), "request");
+    assert_eq!(app.min_level(), Some(LogLevel::Error));
+    assert_eq!(app.time_range(), state.time_range.as_ref());
+    assert_eq!(app.total_lines(), source.total_lines());
+}
+
+#[test]
+fn test_cycle_case_mode_refilters() {
+    let mut app = App::new(vec![
+        "ERROR Timeout talking to db".to_string(),
+        "INFO timeout raised to 5s".to_string(),
+    ]);
+    app.set_filter("Timeout".to_string());
+    assert_eq!(app.total_lines(), 2);
+    app.cycle_case_mode();
+    assert_eq!(app.case_mode(), CaseMode::Smart);
+    assert_eq!(app.total_lines(), 1);
+    assert_eq!(app.notice(), Some("Filters: smart case"));
+    app.set_filter("timeout".to_string());
+    assert_eq!(app.total_lines(), 2);
+    app.cycle_case_mode();
+    assert_eq!(app.total_lines(), 1);
+    app.cycle_case_mode();
+    assert_eq!(app.case_mode(), CaseMode::Ignore);
+    assert_eq!(app.total_lines(), 2);
+}
+
+#[test]
+fn test_filter_warning_follows_filter() {
+    let mut app = App::new(vec![
+        "ERROR disk full".to_string(),
+        "INFO disk ok".to_string(),
+    ]);
+    assert_eq!(app.filter_warning(), None);
+    app.set_filter("disk".to_string());
+    assert_eq!(app.filter_warning(), Some(FilterWarning::KeepsEverything));
+    app.set_filter("re:^WARN".to_string());
+    assert_eq!(app.filter_warning(), Some(FilterWarning::KeepsNothing));
+    app.set_filter("full".to_string());
+    assert_eq!(app.filter_warning(), None);
+}
+
+#[test]
+fn test_toggle_whole_word_refilters() {
+    let mut app = App::new(vec![
+        "ERROR err: disk full".to_string(),
+        "INFO transferred 5 files".to_string(),
+    ]);
+    app.set_filter("err".to_string());
+    assert_eq!(app.total_lines(), 2);
+    app.toggle_whole_word();
+    assert!(app.is_whole_word());
+    assert_eq!(app.total_lines(), 1);
+    assert_eq!(app.text_match().label().as_deref(), Some("whole word"));
+    app.toggle_whole_word();
+    assert_eq!(app.total_lines(), 2);
+}
+
+#[test]
+fn test_highlight_only_keeps_every_line() {
+    let mut app = App::new(vec![
+        "ERROR disk full".to_string(),
+        "INFO request done".to_string(),
+        "DEBUG cache hit".to_string(),
+        "ERROR disk slow".to_string(),
+    ]);
+    app.set_filter("disk".to_string());
+    assert_eq!(app.total_lines(), 2);
+    assert_eq!(app.highlighted_matches(), None);
+
+    app.toggle_highlight_only();
+    assert!(app.is_highlight_only());
+    assert_eq!(app.total_lines(), 4);
+    assert_eq!(app.highlighted_matches(), Some(2));
+    assert_eq!(app.filter_warning(), None);
+
+    // The level filter still hides lines
+    app.set_min_level(LogLevel::Info);
+    assert_eq!(app.total_lines(), 3);
+    assert_eq!(app.highlighted_matches(), Some(2));
+
+    app.append_lines(vec!["WARN disk almost full".to_string()]);
+    assert_eq!(app.total_lines(), 4);
+    assert_eq!(app.highlighted_matches(), Some(3));
+
+    app.toggle_highlight_only();
+    assert_eq!(app.total_lines(), 3);
+    assert_eq!(app.highlighted_matches(), None);
+}
+
+#[test]
+fn test_next_match_jumps_between_highlighted_lines() {
+    let lines: Vec<String> = (0..30)
+        .map(|i| {
+            if i % 10 == 3 {
+                format!("ERROR timeout {i}")
+            } else {
+                format!("INFO ok {i}")
+            }
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(5);
+    app.set_filter("timeout".to_string());
+    app.toggle_highlight_only();
+    assert!(app.has_text_filter());
+    assert_eq!(app.match_counter(), None);
+
+    app.next_match(1);
+    assert_eq!(app.scroll_offset(), 3);
+    assert_eq!(app.match_counter(), Some((1, 3)));
+    app.next_match(1);
+    assert_eq!(app.scroll_offset(), 13);
+    assert_eq!(app.match_counter(), Some((2, 3)));
+    app.prev_match(1);
+    assert_eq!(app.scroll_offset(), 3);
+
+    // Wraps around past the first match
+    app.prev_match(1);
+    assert_eq!(app.match_counter(), Some((3, 3)));
+    assert_eq!(app.notice(), Some("Search wrapped to the bottom"));
+
+    // A count skips matches, and scrolling away forgets the current one
+    app.scroll_to_top();
+    app.next_match(2);
+    assert_eq!(app.scroll_offset(), 13);
+    app.scroll_to_top();
+    assert_eq!(app.match_counter(), None);
+}
+
+#[test]
+fn test_next_match_moves_cursor_over_filtered_lines() {
+    let mut app = App::new(vec![
+        "ERROR disk full".to_string(),
+        "INFO request done".to_string(),
+        "ERROR disk slow".to_string(),
+        "ERROR disk gone".to_string(),
+    ]);
+    app.set_viewport_height(10);
+    app.set_filter("disk".to_string());
+    app.enter_cursor_mode();
+    assert_eq!(app.match_counter(), Some((1, 3)));
+    app.next_match(1);
+    assert_eq!(app.cursor_position(), 1);
+    assert_eq!(app.match_counter(), Some((2, 3)));
+    app.next_match(2);
+    assert_eq!(app.cursor_position(), 0);
+
+    app.clear_filter();
+    assert!(!app.has_text_filter());
+    app.next_match(1);
+    assert_eq!(app.cursor_position(), 0);
+    assert_eq!(app.match_counter(), None);
+}
+
+#[test]
+fn test_breadcrumbs_list_and_remove_constraints() {
+    let lines: Vec<String> = (0..20)
+        .map(|i| {
+            let level = if i % 2 == 0 { "ERROR" } else { "INFO" };
+            format!("2024-01-15T08:{:02}:00Z {level} api request {i}", i)
+        })
+        .collect();
+    let mut app = App::new(lines);
+    assert!(app.breadcrumbs().is_empty());
+
+    app.set_filter_stack(&["api".to_string(), "request".to_string()]);
+    app.set_level_filter(Some(LogLevel::Error));
+    app.time_preset(10);
+    app.cycle_sort();
+    let labels: Vec<String> = app.breadcrumbs().into_iter().map(|(_, l)| l).collect();
+    assert_eq!(
+        labels,
+        [
+            "/api",
+            "/request",
+            "level≥ERR",
+            "08:09–08:19",
+            "sort time ↑"
+        ]
+    );
+    assert_eq!(app.breadcrumbs()[0].0, Breadcrumb::Term(0));
+
+    // Lifting one leaves the rest in place
+    app.remove_breadcrumb(3);
+    assert_eq!(app.min_level(), None);
+    assert_eq!(app.notice(), Some("Removed level≥ERR"));
+    app.remove_breadcrumb(1);
+    assert!(app.filter_terms().is_empty());
+    assert_eq!(app.filter_pattern(), "request");
+    assert_eq!(app.breadcrumbs().len(), 3);
+
+    // Out of range does nothing
+    app.remove_breadcrumb(9);
+    app.remove_breadcrumb(0);
+    assert_eq!(app.breadcrumbs().len(), 3);
+}
+
+#[test]
+fn test_save_filter_and_apply_it_later() {
+    let lines: Vec<String> = (0..20)
+        .map(|i| {
+            let level = if i % 2 == 0 { "ERROR" } else { "INFO" };
+            format!("{level} request {i} path=/api/{}", i % 4)
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.enter_save_filter_mode();
+    assert_eq!(app.mode(), AppMode::Normal);
+    assert_eq!(app.notice(), Some("No filter to save"));
+
+    app.set_filter_stack(&["request".to_string(), "/api/2".to_string()]);
+    app.set_min_level(LogLevel::Error);
+    let narrowed = app.total_lines();
+    app.enter_save_filter_mode();
+    assert_eq!(app.mode(), AppMode::SaveFilter);
+    // A blank name isn't saved
+    app.save_filter_type(' ');
+    assert_eq!(app.save_filter_submit(), None);
+    app.save_filter_backspace();
+    for c in "api two".chars() {
+        app.save_filter_type(c);
+    }
+    let saved = app.save_filter_submit().unwrap();
+    assert_eq!(app.mode(), AppMode::Normal);
+    assert_eq!(saved.name, "api two");
+    assert_eq!(saved.filters, ["request"]);
+    assert_eq!(saved.filter, "/api/2");
+    assert_eq!(saved.min_level, Some(LogLevel::Error));
+
+    app.clear_all_filters();
+    let mut filters = SavedFilters::default();
+    filters.insert(saved);
+    app.open_saved_filters(filters);
+    assert_eq!(app.saved_filters_selected(), Some(0));
+    assert_eq!(app.saved_filters_selected_name(), Some("api two"));
+    app.saved_filters_apply();
+    assert_eq!(app.saved_filters_selected(), None);
+    assert_eq!(app.filter_terms(), ["request"]);
+    assert_eq!(app.filter_pattern(), "/api/2");
+    assert_eq!(app.total_lines(), narrowed);
+
+    app.open_saved_filters(SavedFilters::default());
+    assert_eq!(app.saved_filters_selected(), None);
+    assert_eq!(app.notice(), Some("No saved filters"));
+}
+
+#[test]
+fn test_pane_layout_resizes_and_travels_with_view_state() {
+    let mut app = App::new((0..10).map(|i| format!("INFO line {i}")).collect());
+    assert_eq!(app.pane_layout().pinned_rows, MAX_PINS);
+    app.grow_pinned_pane();
+    assert_eq!(app.pane_layout().pinned_rows, MAX_PINS);
+    for _ in 0..MAX_PINS - 1 {
+        app.shrink_pinned_pane();
+    }
+    assert_eq!(app.pane_layout().pinned_rows, 1);
+    assert!(app.pane_layout().pinned);
+    // Shrinking past one row hides the pane; growing brings it back
+    app.shrink_pinned_pane();
+    assert!(!app.pane_layout().pinned);
+    app.grow_pinned_pane();
+    assert!(app.pane_layout().pinned);
+    assert_eq!(app.pane_layout().pinned_rows, 1);
+    app.toggle_stats_bar();
+
+    let state = app.view_state();
+    let mut other = App::new(vec!["INFO x".to_string()]);
+    other.apply_view_state(&state);
+    assert_eq!(other.pane_layout(), app.pane_layout());
+    assert!(!other.pane_layout().stats);
+}
+
+fn filtered_app() -> App {
+    let lines: Vec<String> = (0..30)
+        .map(|i| {
+            let level = if i % 2 == 0 { "ERROR" } else { "INFO" };
+            format!("2024-01-15T10:{:02}:00Z {} job {}", i, level, i)
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_filter("job".to_string());
+    app.set_min_level(LogLevel::Error);
+    app.time_preset(15);
+    app
+}
+
+#[test]
+fn test_toggle_inherited_times_refilters_time_range() {
+    let lines = vec![
+        "2024-01-15 08:00:00 ERROR [main] com.app.Service - failed".to_string(),
+        "2024-01-15 10:00:00 ERROR [main] com.app.Service - failed again".to_string(),
+        "    at com.app.Service.run(Service.java:42)".to_string(),
+    ];
+    let mut app = App::new(lines);
+    app.set_join_lines(false);
+    app.time_preset(15);
+    assert_eq!(app.total_lines(), 2);
+    app.toggle_inherited_times();
+    assert!(!app.include_inherited_times());
+    assert_eq!(app.total_lines(), 1);
+    app.toggle_inherited_times();
+    assert_eq!(app.total_lines(), 2);
+}
+
+#[test]
+fn test_clear_all_filters() {
+    let mut app = filtered_app();
+    assert!(app.total_lines() < 30);
+    app.clear_all_filters();
+    assert_eq!(app.filter_pattern(), "");
+    assert_eq!(app.min_level(), None);
+    assert!(app.time_range().is_none());
+    assert_eq!(app.total_lines(), 30);
+}
+
+#[test]
+fn test_escape_steps_through_filters_by_default() {
+    let mut app = filtered_app();
+    app.escape();
+    assert!(app.time_range().is_none());
+    assert_eq!(app.filter_pattern(), "job");
+    app.escape();
+    assert_eq!(app.filter_pattern(), "");
+    // Level is left alone by the step-wise Esc
+    assert_eq!(app.min_level(), Some(LogLevel::Error));
+}
+
+#[test]
+fn test_escape_configurable() {
+    let mut app = filtered_app();
+    app.set_esc_action(EscAction::None);
+    app.escape();
+    assert!(app.time_range().is_some());
+
+    app.set_esc_action(EscAction::ClearAll);
+    app.escape();
+    assert!(app.time_range().is_none());
+    assert_eq!(app.min_level(), None);
+    assert_eq!(app.filter_pattern(), "");
+    assert!(!app.should_quit());
+}
+
+#[test]
+fn test_quit_without_confirmation_by_default() {
+    let mut app = App::new(vec!["INFO a".into()]);
+    app.enter_cursor_mode();
+    app.toggle_mark();
+    app.request_quit();
+    assert!(app.should_quit());
+}
+
+#[test]
+fn test_confirm_quit_with_unexported_notes() {
+    let mut app = App::new(vec!["INFO a".into(), "INFO b".into()]);
+    app.set_confirm_quit(true);
+    app.request_quit();
+    assert!(app.should_quit(), "nothing to lose, quits straight away");
+
+    let mut app = App::new(vec!["INFO a".into(), "INFO b".into()]);
+    app.set_confirm_quit(true);
+    app.enter_cursor_mode();
+    app.enter_annotate_mode();
+    app.annotate_type('x');
+    app.annotate_submit();
+    app.request_quit();
+    assert!(!app.should_quit());
+    assert_eq!(app.quit_confirmation().as_deref(), Some("unexported notes"));
+    app.answer_quit(false);
+    assert_eq!(app.quit_confirmation(), None);
+    assert!(!app.should_quit());
+
+    // Exporting the notes means there's nothing left to lose
+    app.export_annotations();
+    app.request_quit();
+    assert!(app.should_quit());
+}
+
+#[test]
+fn test_confirm_quit_while_following() {
+    let mut app = App::new(vec!["INFO a".into()]);
+    app.set_confirm_quit(true);
+    app.set_follow_mode(true);
+    app.request_quit();
+    assert_eq!(app.quit_confirmation().as_deref(), Some("follow mode on"));
+    app.answer_quit(true);
+    assert!(app.should_quit());
+}
+
+#[test]
+fn test_split_commands_follow_pivots_in_context_menu() {
+    let mut app = App::new(vec!["pod=api-7f9c".into()]);
+    app.set_pivots(vec![PivotUrl {
+        name: "Dashboard".into(),
+        url: "https://grafana/{}".into(),
+    }]);
+    app.set_split_commands(vec![SplitCommand {
+        name: "kubectl logs".into(),
+        command: "kubectl logs -f {}".into(),
+    }]);
+    app.open_context_menu("api-7f9c".into(), TokenKind::KeyValue, (0, 0));
+    let (action, _) = app.execute_menu_item(2).unwrap();
+    assert_eq!(action.label(), "kubectl logs");
+    assert_eq!(
+        action,
+        MenuAction::Split {
+            name: "kubectl logs".into(),
+            command: "kubectl logs -f {}".into(),
+        }
+    );
+}
+
+#[test]
+fn test_derived_fields_feed_filters_and_appended_lines() {
+    let lines = vec![
+        "level=info msg=req path=/api/users/1 duration_ms=2300".to_string(),
+        "level=info msg=req path=/api/orders/7 duration_ms=400".to_string(),
+    ];
+    let mut app = App::new(lines);
+    app.set_derived_fields(vec![
+        lumolog::derived::DerivedField::parse("latency_s", "duration_ms / 1000").unwrap(),
+        lumolog::derived::DerivedField::parse("endpoint", r#"regex(path, "^/api/[^/]+")"#).unwrap(),
+    ]);
+    app.set_filter("latency_s > 2".into());
+    assert_eq!(app.total_lines(), 1);
+
+    app.append_lines(vec![
+        "level=info msg=req path=/api/users/2 duration_ms=5000".into(),
+    ]);
+    assert_eq!(app.total_lines(), 2);
+    let appended = app.parsed_line(2).unwrap();
+    assert!(
+        appended
+            .extra_fields
+            .contains(&("endpoint".to_string(), "/api/users".to_string()))
+    );
+}
+
+#[test]
+fn test_provenance_lines_tag_later_lines_and_stay_hidden() {
+    let lines = vec![
+        r#"#lumolog:{"source":"api"}"#.to_string(),
+        "ERROR upstream timeout".to_string(),
+        r#"#lumolog:{"source":"worker"}"#.to_string(),
+        "ERROR job failed".to_string(),
+    ];
+    let mut app = App::new(lines);
+    assert_eq!(app.total_lines(), 2);
+    app.set_filter("source=worker".into());
+    assert_eq!(app.total_lines(), 1);
+    assert_eq!(app.cursor_line_raw(), Some("ERROR job failed"));
+
+    app.append_lines(vec![
+        "ERROR job retried".into(),
+        r#"#lumolog:{"source":"api"}"#.into(),
+        "ERROR upstream reset".into(),
+    ]);
+    assert_eq!(app.total_lines(), 2);
+    app.set_filter("source=api".into());
+    assert_eq!(app.total_lines(), 2);
+    // A line the parser can't read as a control line is shown as it is
+    app.append_lines(vec!["#lumolog:{oops".into()]);
+    app.clear_filter();
+    assert_eq!(app.total_lines(), 5);
+}
+
+#[test]
+fn test_value_distribution_sorts_numeric_values() {
+    let lines = vec![
+        "level=info took=2s".to_string(),
+        "level=info took=2s".to_string(),
+        "level=info took=900ms".to_string(),
+    ];
+    let mut app = App::new(lines);
+    app.open_value_distribution("took");
+    let state = app.value_distribution().unwrap();
+    assert!(state.summary.is_some());
+    assert_eq!(state.dist.values[0].value, "2s");
+
+    app.toggle_value_dist_sort();
+    let state = app.value_distribution().unwrap();
+    assert!(state.by_value);
+    assert_eq!(state.dist.values[0].value, "900ms");
+
+    app.toggle_value_dist_sort();
+    assert_eq!(app.value_distribution().unwrap().dist.values[0].value, "2s");
+}
+
+#[test]
+fn test_value_distribution_text_values_do_not_sort() {
+    let mut app = App::new(vec!["level=info user=bob".to_string()]);
+    app.open_value_distribution("user");
+    assert!(app.value_distribution().unwrap().summary.is_none());
+    app.toggle_value_dist_sort();
+    assert!(!app.value_distribution().unwrap().by_value);
+}
+
+#[test]
+fn test_sort_by_field_largest_first_across_units() {
+    let lines = vec![
+        "level=info path=/a took=850ms".to_string(),
+        "level=info path=/b took=2.3s".to_string(),
+        "level=info path=/c".to_string(),
+        "level=info path=/d took=1200".to_string(),
+    ];
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    app.sort_by_field("took");
+    assert_eq!(app.sort_order(), Some(&SortOrder::Field("took".into())));
+    let order: Vec<usize> = app
+        .visible_parsed_lines_numbered()
+        .iter()
+        .map(|(n, _)| *n)
+        .collect();
+    // Bare 1200 is read as ms; the line without the field goes last
+    assert_eq!(order, vec![2, 4, 1, 3]);
+
+    app.enter_cursor_mode();
+    app.goto_line(1);
+    assert_eq!(app.cursor_line_index(), Some(0));
+
+    app.escape();
+    assert_eq!(app.sort_order(), None);
+    let order: Vec<usize> = app
+        .visible_parsed_lines_numbered()
+        .iter()
+        .map(|(n, _)| *n)
+        .collect();
+    assert_eq!(order, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_cycle_sort_by_time_then_level() {
+    let lines = vec![
+        "2024-01-15T10:00:05Z INFO later".to_string(),
+        "2024-01-15T10:00:01Z ERROR first".to_string(),
+        "2024-01-15T10:00:03Z WARN middle".to_string(),
+    ];
+    let mut app = App::new(lines);
+    app.set_viewport_height(10);
+    let order = |app: &App| -> Vec<usize> {
+        app.visible_parsed_lines_numbered()
+            .iter()
+            .map(|(n, _)| *n)
+            .collect()
+    };
+    app.cycle_sort();
+    assert_eq!(app.sort_order(), Some(&SortOrder::Timestamp));
+    assert_eq!(order(&app), vec![2, 3, 1]);
+    app.cycle_sort();
+    assert_eq!(app.sort_order(), Some(&SortOrder::Level));
+    assert_eq!(order(&app), vec![2, 3, 1]);
+    app.set_filter("e".into());
+    assert_eq!(app.sort_order(), Some(&SortOrder::Level));
+    app.cycle_sort();
+    assert_eq!(app.sort_order(), None);
+    assert_eq!(order(&app), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_sort_by_field_from_key_value_menu() {
+    let mut app = App::new(vec!["level=info took=5ms".to_string()]);
+    app.open_context_menu("took=".into(), TokenKind::KeyValue, (0, 0));
+    let items = &app.context_menu().unwrap().items;
+    let pos = items.len() - 2;
+    assert_eq!(items[pos], MenuAction::SortByField);
+    let (action, value) = app.execute_menu_item(pos).unwrap();
+    assert_eq!(action, MenuAction::SortByField);
+    app.sort_by_field(value.trim_end_matches('='));
+    assert_eq!(app.sort_order(), Some(&SortOrder::Field("took".into())));
+}
+
+#[test]
+fn test_time_cursor_levels_follow_the_cursor_bucket() {
+    let lines: Vec<String> = (0..10)
+        .map(|i| {
+            let level = if i == 9 { "ERROR" } else { "INFO" };
+            format!("2024-01-15T10:{:02}:00Z {} job {}", i, level, i)
+        })
+        .collect();
+    let mut app = App::new(lines);
+    assert!(app.time_cursor_levels().is_empty());
+    app.set_sparkline_width(10);
+    app.enter_time_mode();
+    app.time_cursor_left(10);
+    assert_eq!(app.time_cursor_levels(), vec![(Some(LogLevel::Info), 1)]);
+    app.time_cursor_right(10);
+    assert_eq!(app.time_cursor_levels(), vec![(Some(LogLevel::Error), 1)]);
+    // Lines arriving in the open bucket are counted without moving the cursor
+    app.append_lines(vec!["2024-01-15T10:09:30Z WARN job 10".to_string()]);
+    assert_eq!(
+        app.time_cursor_levels(),
+        vec![(Some(LogLevel::Error), 1), (Some(LogLevel::Warn), 1)]
+    );
+    app.exit_time_mode();
+    assert!(app.time_cursor_levels().is_empty());
+}
+
+#[test]
+fn test_pin_lines_and_export() {
+    let lines: Vec<String> = (0..10).map(|i| format!("INFO line {i}")).collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(5);
+    app.enter_cursor_mode();
+    app.goto_line(7);
+    app.toggle_pin();
+    app.goto_line(3);
+    app.toggle_pin();
+    assert_eq!(app.pins(), &[6, 2]);
+    // Exported in line order
+    assert_eq!(
+        app.export_pins(),
+        "line 3: INFO line 2\nline 7: INFO line 6\n"
+    );
+
+    app.toggle_pin();
+    assert_eq!(app.pins(), &[6]);
+    app.clear_pins();
+    assert!(app.pins().is_empty());
+    assert_eq!(app.export_pins(), "");
+}
+
+#[test]
+fn test_pins_drop_oldest_past_max() {
+    let lines: Vec<String> = (0..10).map(|i| format!("INFO line {i}")).collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(5);
+    app.enter_cursor_mode();
+    for line in 1..=MAX_PINS + 1 {
+        app.goto_line(line);
+        app.toggle_pin();
+    }
+    assert_eq!(app.pins().len(), MAX_PINS);
+    assert_eq!(app.pins()[0], 1);
+    assert_eq!(app.pins()[MAX_PINS - 1], MAX_PINS);
+}
+
+#[test]
+fn test_save_filtered_lines_streams_to_file() {
+    let lines: Vec<String> = (0..6)
+        .map(|i| format!("{} line {i}", if i % 2 == 0 { "ERROR" } else { "INFO" }))
+        .collect();
+    let mut app = App::new(lines);
+    app.set_min_level(LogLevel::Error);
+    assert_eq!(app.filtered_raw_len(), app.all_filtered_lines_raw().len());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("yank.log");
+    app.save_filtered_lines(&path).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "ERROR line 0\nERROR line 2\nERROR line 4\n"
+    );
+
+    let missing = dir.path().join("no/such/dir/yank.log");
+    assert!(app.save_filtered_lines(&missing).is_err());
+}
+
+#[test]
+fn test_paste_goes_to_active_input_as_one_edit() {
+    let mut app = App::new(vec![
+        "INFO user=alice login".into(),
+        "WARN user=bob retry".into(),
+    ]);
+    // Nothing to paste into in normal mode
+    app.paste("qqq");
+    assert_eq!(app.filter_pattern(), "");
+
+    app.enter_filter_mode();
+    app.paste("user=bob\r\n");
+    assert_eq!(app.filter_pattern(), "user=bob");
+    assert_eq!(app.total_lines(), 1);
+    assert!(app.is_filter_mode());
+
+    app.exit_filter_mode();
+    app.open_palette();
+    app.paste("line\nwrap");
+    assert_eq!(app.palette_input(), "line wrap");
+}
+
+#[test]
+fn test_filter_input_edits_at_cursor() {
+    let mut app = App::new(vec!["INFO user=alice status=500".into()]);
+    app.enter_filter_mode();
+    app.paste("status=500");
+    app.filter_cursor_home();
+    app.paste("user=alice ");
+    assert_eq!(app.filter_pattern(), "user=alice status=500");
+    assert_eq!(app.filter_cursor(), 11);
+
+    app.filter_cursor_left();
+    app.filter_backspace();
+    assert_eq!(app.filter_pattern(), "user=alic status=500");
+    app.filter_input('e');
+    app.filter_cursor_right();
+    app.filter_delete();
+    assert_eq!(app.filter_pattern(), "user=alice tatus=500");
+
+    app.filter_cursor_end();
+    app.filter_delete_word();
+    assert_eq!(app.filter_pattern(), "user=alice ");
+    app.filter_delete_word();
+    assert_eq!(app.filter_pattern(), "");
+
+    app.paste("héllo wörld");
+    app.filter_cursor_left();
+    app.filter_cursor_left();
+    app.filter_delete_to_start();
+    assert_eq!(app.filter_pattern(), "ld");
+    assert_eq!(app.filter_cursor(), 0);
+}
+
+#[test]
+fn test_filter_input_handles_multibyte_characters() {
+    let mut app = App::new(vec![
+        "INFO service=決済サービス ok".into(),
+        "INFO service=認証 ok".into(),
+    ]);
+    app.enter_filter_mode();
+    for c in "決済x".chars() {
+        app.filter_input(c);
+    }
+    app.filter_backspace();
+    assert_eq!(app.filter_pattern(), "決済");
+    assert_eq!(app.total_lines(), 1);
+
+    app.filter_cursor_left();
+    app.filter_input('é');
+    assert_eq!(app.filter_pattern(), "決é済");
+    assert_eq!(app.filter_cursor(), 2);
+    app.filter_delete();
+    app.filter_backspace();
+    assert_eq!(app.filter_pattern(), "決");
+}
+
+#[test]
+fn test_terminal_title_shows_source_and_follow_state() {
+    let mut app = App::new(vec!["INFO a".into()]);
+    app.set_source_name("app.log".into());
+    assert_eq!(app.terminal_title(), "lumolog — app.log");
+    app.set_follow_mode(true);
+    assert_eq!(app.terminal_title(), "lumolog — app.log [FOLLOWING]");
+    app.toggle_follow_pause();
+    assert_eq!(app.terminal_title(), "lumolog — app.log [PAUSED]");
+}
+
+#[test]
+fn test_malformed_panel_groups_reasons_and_filters() {
+    let mut app = App::new(vec![
+        r#"{"level":"info","msg":"ok"}"#.into(),
+        r#"{"level":"error","msg":"cut"#.into(),
+        "    at com.example.Main".into(),
+        r#"{"level":"info","msg":"ok again"}"#.into(),
+        "    at com.example.Other".into(),
+        r#"{"level":"info","msg":"and again"}"#.into(),
+        r#"{"level":"info","msg":"still fine"}"#.into(),
+    ]);
+    app.set_join_lines(false);
+    app.open_malformed_panel();
+    let (rows, selected) = app.malformed_panel().unwrap();
+    assert_eq!(selected, 0);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].count, 2);
+    assert_eq!(rows[0].first_line, 2);
+    assert_eq!(rows[1].first_line, 1);
+    app.close_malformed_panel();
+
+    app.toggle_malformed_only();
+    assert_eq!(app.total_lines(), 3);
+    app.append_lines(vec!["not json".into(), r#"{"msg":"fine"}"#.into()]);
+    assert_eq!(app.total_lines(), 4);
+    app.escape();
+    assert!(!app.is_malformed_only());
+    assert_eq!(app.total_lines(), 9);
+}
+
+#[test]
+fn test_malformed_panel_stays_closed_without_failures() {
+    let mut app = App::new(vec!["plain text".into()]);
+    app.open_malformed_panel();
+    assert!(app.malformed_panel().is_none());
+    assert_eq!(app.notice(), Some("No malformed lines"));
+}
+
+#[test]
+fn test_carry_line_marks_shifts_notes_pins_and_mark() {
+    let lines: Vec<String> = (0..5).map(|i| format!("INFO line {i}")).collect();
+    let mut old = App::new(lines.clone());
+    old.set_viewport_height(5);
+    old.enter_cursor_mode();
+    old.cursor_down(2);
+    old.toggle_pin();
+    old.toggle_mark();
+    old.enter_annotate_mode();
+    for c in "here".chars() {
+        old.annotate_type(c);
+    }
+    old.annotate_submit();
+
+    let mut merged: Vec<String> = vec!["INFO rotated 0".into(), "INFO rotated 1".into()];
+    merged.extend(lines);
+    let mut fresh = App::new(merged);
+    fresh.carry_line_marks(&old, 2);
+    assert_eq!(fresh.pins(), &[4]);
+    assert_eq!(fresh.mark(), Some(4));
+    assert_eq!(fresh.annotation_at(4), Some("here"));
+    assert_eq!(fresh.annotation_at(2), None);
+}
+
+#[test]
+fn test_rotated_request_is_taken_once() {
+    let mut app = App::new(vec!["INFO a".into()]);
+    assert!(!app.take_rotated_request());
+    app.request_rotated_files();
+    assert!(app.take_rotated_request());
+    assert!(!app.take_rotated_request());
+}
+
+#[test]
+fn test_decode_menu_item_opens_popup() {
+    let mut app = App::new(vec!["token dXNlcjpjb3JyZWN0IGhvcnNl".to_string()]);
+    app.open_context_menu(
+        "dXNlcjpjb3JyZWN0IGhvcnNl".into(),
+        TokenKind::Encoded,
+        (0, 0),
+    );
+    let items = &app.context_menu().unwrap().items;
+    let pos = items.iter().position(|a| *a == MenuAction::Decode).unwrap();
+    let (action, value) = app.execute_menu_item(pos).unwrap();
+    assert_eq!(action, MenuAction::Decode);
+
+    app.open_decoded(&value);
+    let state = app.decoded().unwrap();
+    assert_eq!(state.decoded.payload(), "user:correct horse");
+    app.decoded_scroll(5);
+    app.clamp_decoded_scroll(1, 10);
+    assert_eq!(app.decoded().unwrap().scroll, 0);
+    app.close_decoded();
+    assert!(app.decoded().is_none());
+
+    app.open_decoded("not-encoded");
+    assert!(app.decoded().is_none());
+    assert_eq!(app.notice(), Some("Not a JWT or base64 text"));
+}
+
+#[test]
+fn test_hex_dump_shows_cursor_line() {
+    let mut app = App::new(vec!["first".to_string(), "second\tline".to_string()]);
+    app.enter_cursor_mode();
+    app.cursor_down(1);
+    app.open_hex_dump();
+    assert_eq!(app.hex_dump_line(), Some((2, "second\tline")));
+    app.scroll_hex_dump(3);
+    app.clamp_hex_dump_scroll(1, 10);
+    assert_eq!(app.hex_dump_scroll(), 0);
+    app.close_hex_dump();
+    assert_eq!(app.hex_dump_line(), None);
+}
+
+#[test]
+fn test_toggle_filter_regex() {
+    let mut app = App::new(vec!["job 12 done".to_string(), "job done".to_string()]);
+    app.enter_filter_mode();
+    for c in r"\d+".chars() {
+        app.filter_input(c);
+    }
+    assert_eq!(app.total_lines(), 0);
+
+    app.toggle_filter_regex();
+    assert_eq!(app.filter_pattern(), r"re:\d+");
+    assert_eq!(app.filter_cursor(), 6);
+    assert_eq!(app.total_lines(), 1);
+
+    app.filter_input('(');
+    assert_eq!(app.filter_error(), Some("unclosed group"));
+    assert_eq!(app.total_lines(), 2);
+
+    app.filter_backspace();
+    app.toggle_filter_regex();
+    assert_eq!(app.filter_pattern(), r"\d+");
+    assert_eq!(app.filter_cursor(), 3);
+    assert_eq!(app.filter_error(), None);
+}
+
+#[test]
+fn test_slash_stacks_filters_as_chips() {
+    let mut app = App::new(vec![
+        "INFO api ok".to_string(),
+        "ERROR api timeout".to_string(),
+        "ERROR worker timeout".to_string(),
+        "ERROR api refused".to_string(),
+    ]);
+    app.enter_filter_mode();
+    app.paste("api");
+    app.exit_filter_mode();
+    app.enter_filter_mode();
+    assert_eq!(app.filter_terms(), ["api"]);
+    assert_eq!(app.filter_pattern(), "");
+    assert_eq!(app.total_lines(), 3);
+    app.paste("timeout | refused");
+    assert_eq!(app.total_lines(), 2);
+    app.exit_filter_mode();
+
+    let state = app.view_state();
+    assert_eq!(state.filters, ["api"]);
+    let mut other = App::new(vec!["ERROR worker timeout".to_string()]);
+    other.apply_view_state(&state);
+    assert_eq!(other.total_lines(), 0);
+
+    // Backspace in an empty filter brings the last chip back for editing
+    app.enter_filter_mode();
+    assert_eq!(app.filter_terms().len(), 2);
+    app.filter_backspace();
+    assert_eq!(app.filter_pattern(), "timeout | refused");
+    assert_eq!(app.filter_cursor(), 17);
+    assert_eq!(app.filter_terms(), ["api"]);
+    app.exit_filter_mode();
+
+    // Esc drops the filter being edited, then the chips one by one
+    app.escape();
+    assert_eq!(app.filter_pattern(), "");
+    assert_eq!(app.total_lines(), 3);
+    app.escape();
+    assert!(app.filter_terms().is_empty());
+    assert_eq!(app.total_lines(), 4);
+
+    // Setting a filter from elsewhere replaces the whole stack
+    app.enter_filter_mode();
+    app.paste("ERROR");
+    app.enter_filter_mode();
+    app.set_filter("ok".to_string());
+    assert!(app.filter_terms().is_empty());
+    assert_eq!(app.total_lines(), 1);
+}
+
+#[test]
+fn test_collapse_noise_hides_noisiest_templates() {
+    let mut lines: Vec<String> = (0..20)
+        .map(|i| format!("INFO GET /healthz 200 in {i}ms"))
+        .collect();
+    lines.extend((0..5).map(|i| format!("DEBUG cache hit key={i}")));
+    lines.push("ERROR payment failed".to_string());
+    lines.push("WARN retrying payment".to_string());
+    let mut app = App::new(lines);
+
+    app.open_noise_preview();
+    let preview = app.noise_preview().unwrap();
+    assert_eq!(preview.hide, 2);
+    assert_eq!(preview.hidden_lines(), 25);
+    app.noise_preview_fewer();
+    app.collapse_noise();
+    assert!(app.noise_preview().is_none());
+    assert_eq!(app.hidden_template_count(), 1);
+    assert_eq!(app.total_lines(), 7);
+
+    // Collapsing again hides the next noisiest on top
+    app.open_noise_preview();
+    app.collapse_noise();
+    assert_eq!(app.hidden_template_count(), 2);
+    assert_eq!(app.total_lines(), 2);
+    app.open_noise_preview();
+    assert!(app.noise_preview().is_none());
+
+    app.escape();
+    assert_eq!(app.hidden_template_count(), 0);
+    assert_eq!(app.total_lines(), 27);
+}
+
+#[test]
+fn test_line_stats_panel_jumps_to_long_lines() {
+    let mut app = App::new(vec![
+        "short".to_string(),
+        format!("INFO {}", "x".repeat(500)),
+        "tiny".to_string(),
+        format!("WARN {}", "y".repeat(200)),
+    ]);
+    app.enter_cursor_mode();
+    app.jump_to_longest_line();
+    assert_eq!(app.cursor_position(), 1);
+
+    app.set_filter("!INFO".to_string());
+    app.open_line_stats();
+    let (stats, selected) = app.line_stats().unwrap();
+    assert_eq!(selected, 0);
+    assert_eq!(stats.lines, 3);
+    assert_eq!(stats.longest[0], (3, 205));
+    app.line_stats_down();
+    app.line_stats_jump();
+    assert!(app.line_stats().is_none());
+    // Line 1 is the second longest, first in the filtered view
+    assert_eq!(app.cursor_position(), 0);
+}
+
+#[test]
+fn test_follow_counts_arrivals_the_filter_hides() {
+    let mut app = App::new(vec!["INFO start".into(), "ERROR boom".into()]);
+    app.set_follow_mode(true);
+    app.set_filter("ERROR".to_string());
+    app.scroll_to_bottom();
+    assert_eq!(app.unmatched_arrivals(), 0);
+
+    app.append_lines(vec!["INFO tick".into(), "INFO tock".into()]);
+    assert_eq!(app.total_lines(), 1);
+    assert_eq!(app.unmatched_arrivals(), 2);
+
+    app.append_lines(vec!["INFO tick".into(), "ERROR again".into()]);
+    assert_eq!(app.total_lines(), 2);
+    assert_eq!(app.scroll_offset(), 2);
+    assert_eq!(app.unmatched_arrivals(), 3);
+
+    // Changing the filters starts the count over
+    app.set_filter("tick".to_string());
+    assert_eq!(app.total_lines(), 2);
+    assert_eq!(app.unmatched_arrivals(), 0);
+}
+
+#[test]
+fn test_follow_refilters_when_a_field_filter_falls_back_to_text() {
+    let mut app = App::new(vec![r#"level=info msg="service=auth is down""#.into()]);
+    app.set_follow_mode(true);
+    app.set_filter("service=auth".to_string());
+    assert_eq!(app.total_lines(), 1);
+
+    // Once a line has the field, the text match no longer counts
+    app.append_lines(vec!["level=info service=auth msg=ok".into()]);
+    assert_eq!(app.total_lines(), 1);
+    assert_eq!(app.unmatched_arrivals(), 0);
+}
+
+#[test]
+fn test_level_picker_hides_levels_one_by_one() {
+    let lines: Vec<String> = vec![
+        "INFO started".into(),
+        "WARN slow disk".into(),
+        "ERROR retrying".into(),
+        "FATAL out of memory".into(),
+    ];
+    let mut app = App::new(lines);
+    app.open_level_picker();
+    assert_eq!(app.level_picker_selected(), Some(0));
+    app.level_picker_toggle();
+    app.level_picker_down();
+    app.level_picker_down();
+    app.level_picker_toggle();
+    assert_eq!(app.hidden_levels(), [LogLevel::Info, LogLevel::Error]);
+    assert_eq!(app.total_lines(), 2);
+    assert!(
+        app.breadcrumbs()
+            .contains(&(Breadcrumb::HiddenLevels, "level≠INF,ERR".to_string()))
+    );
+
+    // Checking a level again brings it back
+    app.level_picker_toggle();
+    assert_eq!(app.total_lines(), 3);
+    app.close_level_picker();
+    assert_eq!(app.level_picker_selected(), None);
+}
+
+#[test]
+fn test_level_picker_turns_min_level_into_unchecked_levels() {
+    let lines: Vec<String> = vec![
+        "DEBUG cache miss".into(),
+        "INFO started".into(),
+        "WARN slow disk".into(),
+        "ERROR retrying".into(),
+    ];
+    let mut app = App::new(lines);
+    app.set_min_level(LogLevel::Warn);
+    assert_eq!(app.total_lines(), 2);
+
+    app.open_level_picker();
+    app.level_picker_down();
+    app.level_picker_toggle();
+    assert_eq!(app.min_level(), None);
+    assert_eq!(app.hidden_levels(), [LogLevel::Debug]);
+    assert_eq!(app.total_lines(), 3);
+
+    app.clear_all_filters();
+    assert!(app.hidden_levels().is_empty());
+    assert_eq!(app.total_lines(), 4);
+}
+
+#[test]
+fn test_mute_hides_lines_shaped_like_the_cursor_line() {
+    let lines: Vec<String> = vec![
+        "INFO healthcheck ok from 10.0.0.1".into(),
+        "ERROR payment 41 failed".into(),
+        "INFO healthcheck ok from 10.0.0.2".into(),
+        "INFO healthcheck ok from 10.0.0.3".into(),
+    ];
+    let mut app = App::new(lines);
+    app.enter_cursor_mode();
+    app.mute_cursor_line();
+    assert_eq!(app.total_lines(), 1);
+    assert_eq!(app.muted_line_count(), 3);
+    assert_eq!(app.notice(), Some("Muted 3 lines like this"));
+
+    // New lines of a muted shape stay hidden and are counted
+    app.append_lines(vec!["INFO healthcheck ok from 10.0.0.9".into()]);
+    assert_eq!(app.total_lines(), 1);
+    assert_eq!(app.muted_line_count(), 4);
+
+    app.show_noise();
+    assert_eq!(app.total_lines(), 5);
+    assert_eq!(app.muted_line_count(), 0);
+}
+
+#[test]
+fn test_line_context_menu_offers_mute() {
+    let mut app = App::new(vec!["GET /health user=alice".into(), "".into()]);
+    app.open_line_context_menu("user=".into(), TokenKind::KeyValue, (0, 0), 0);
+    let index = app
+        .context_menu()
+        .unwrap()
+        .items
+        .iter()
+        .position(|item| *item == MenuAction::MuteTemplate(0))
+        .unwrap();
+    let (action, _) = app.execute_menu_item(index).unwrap();
+    assert_eq!(action, MenuAction::MuteTemplate(0));
+
+    // A blank line has no shape to mute
+    app.open_line_context_menu("x".into(), TokenKind::KeyValue, (0, 0), 1);
+    assert!(
+        !app.context_menu()
+            .unwrap()
+            .items
+            .iter()
+            .any(|item| matches!(item, MenuAction::MuteTemplate(_)))
+    );
+}
+
+#[test]
+fn test_context_lines_surround_matches() {
+    let lines: Vec<String> = (0..20)
+        .map(|i| {
+            if i == 5 || i == 15 {
+                format!("ERROR step {i}")
+            } else {
+                format!("INFO step {i}")
+            }
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_filter("ERROR".to_string());
+    assert_eq!(app.total_lines(), 2);
+
+    app.cycle_context_lines();
+    assert_eq!(app.context_lines(), 2);
+    assert_eq!(app.total_lines(), 10);
+    assert!(app.is_context_line(3));
+    assert!(!app.is_context_line(5));
+    assert!(!app.is_context_line(8));
+
+    // n/N skip the context and count only matches
+    app.enter_cursor_mode();
+    app.next_match(1);
+    assert_eq!(app.cursor_line_index(), Some(5));
+    assert_eq!(app.match_counter(), Some((1, 2)));
+    app.next_match(1);
+    assert_eq!(app.cursor_line_index(), Some(15));
+
+    // Overlapping context shows once
+    app.cycle_context_lines();
+    assert_eq!(app.total_lines(), 20);
+    app.cycle_context_lines();
+    assert_eq!(app.context_lines(), 0);
+    assert_eq!(app.total_lines(), 2);
+}
+
+#[test]
+fn test_context_lines_respect_the_level_filter() {
+    let lines: Vec<String> = vec![
+        "DEBUG a".into(),
+        "INFO b".into(),
+        "ERROR boom".into(),
+        "DEBUG c".into(),
+        "INFO d".into(),
+    ];
+    let mut app = App::new(lines);
+    app.set_min_level(LogLevel::Info);
+    app.set_filter("boom".to_string());
+    app.cycle_context_lines();
+    assert_eq!(app.total_lines(), 3);
+    assert!(app.is_context_line(1));
+    assert!(app.is_context_line(4));
+    assert!(!app.is_context_line(3));
+}
+
+#[test]
+fn test_export_view_text_spells_out_levels() {
+    let lines = vec![
+        r#"{"timestamp":"2024-01-15T08:30:00Z","level":"info","message":"Started","port":8080}"#
+            .to_string(),
+        r#"{"timestamp":"2024-01-15T08:30:05Z","level":"error","message":"Failed to connect","host":"db-1"}"#
+            .to_string(),
+    ];
+    let mut app = App::new(lines);
+    app.set_filter("connect".to_string());
+    assert_eq!(
+        app.export_view_text(),
+        "lumolog: stdin\n\
+         Filters: /connect\n\
+         Showing 1 of 2 lines\n\
+         \n\
+         Line 2, ERROR, 2024-01-15T08:30:05Z: Failed to connect. Fields: host=\"db-1\".\n"
+    );
+
+    let mut app = App::new(vec![
+        "WARN disk almost full".to_string(),
+        "done".to_string(),
+    ]);
+    assert!(
+        app.export_view_text().ends_with(
+            "Showing 2 of 2 lines\n\nLine 1, WARN: WARN disk almost full\nLine 2: done\n"
+        )
+    );
+    app.set_text_cues(true);
+    assert!(app.is_text_cues());
+}
+
+fn stack_trace_app() -> App {
+    App::new(vec![
+        "2024-01-15 08:30:00.123 [main] INFO com.app.Service - starting".into(),
+        "2024-01-15 08:30:00.500 [main] INFO com.app.Service - listening".into(),
+        "2024-01-15 08:30:01.456 [main] ERROR com.app.Service - request failed".into(),
+        "java.lang.NullPointerException: user was null".into(),
+        "\tat com.app.Service.handle(Service.java:42)".into(),
+        "Caused by: java.io.IOException: reset".into(),
+        "2024-01-15 08:30:02.789 [main] INFO com.app.Service - recovered".into(),
+        "2024-01-15 08:30:02.900 [main] INFO com.app.Service - idle".into(),
+    ])
+}
+
+#[test]
+fn test_continuation_lines_join_the_entry_above() {
+    let mut app = stack_trace_app();
+    assert_eq!(app.total_lines(), 5);
+    assert_eq!(app.continuation_lines(2), 3..6);
+    assert!(app.continuation_lines(0).is_empty());
+
+    // A match in the trace shows the error line it belongs to
+    app.set_filter("NullPointer".to_string());
+    assert_eq!(app.total_lines(), 1);
+    app.enter_cursor_mode();
+    assert_eq!(
+        app.cursor_entry_raw().unwrap().lines().collect::<Vec<_>>(),
+        [
+            "2024-01-15 08:30:01.456 [main] ERROR com.app.Service - request failed",
+            "java.lang.NullPointerException: user was null",
+            "\tat com.app.Service.handle(Service.java:42)",
+            "Caused by: java.io.IOException: reset",
+        ]
+    );
+    assert_eq!(app.all_filtered_lines_raw().lines().count(), 4);
+    assert_eq!(app.filtered_raw_len(), app.all_filtered_lines_raw().len());
+
+    // Unless the level filter hides that line
+    app.set_filter("recovered | starting | Service.java".to_string());
+    app.set_min_level(LogLevel::Info);
+    assert_eq!(app.total_lines(), 3);
+    app.set_min_level(LogLevel::Warn);
+    assert_eq!(app.total_lines(), 1);
+
+    app.toggle_join_lines();
+    assert_eq!(app.notice(), Some("Multi-line entries: one line each"));
+    app.set_level_filter(None);
+    app.set_filter(String::new());
+    assert_eq!(app.total_lines(), 8);
+    assert!(app.continuation_lines(2).is_empty());
+}
+
+#[test]
+fn test_python_traceback_is_one_error_entry() {
+    let lines: Vec<String> = vec![
+        "2024-01-15 10:30:00,100 - worker - INFO - job started".into(),
+        "Traceback (most recent call last):".into(),
+        "  File \"/app/worker.py\", line 12, in run".into(),
+        "    data[\"id\"]".into(),
+        "KeyError: 'id'".into(),
+        "".into(),
+        "During handling of the above exception, another exception occurred:".into(),
+        "".into(),
+        "Traceback (most recent call last):".into(),
+        "  File \"/app/worker.py\", line 14, in run".into(),
+        "    raise ValueError(\"bad job\")".into(),
+        "ValueError: bad job".into(),
+        "2024-01-15 10:30:01,200 - worker - ERROR - job failed".into(),
+        "Traceback (most recent call last):".into(),
+        "  File \"/app/worker.py\", line 20, in main".into(),
+        "RuntimeError: gave up".into(),
+        "2024-01-15 10:30:02,300 - worker - INFO - job started".into(),
+    ];
+    // Grouped the same whether the file reads as Python logging or plain text
+    let mut plain = App::with_format(lines.clone(), Some(LogFormat::Plain));
+    assert_eq!(plain.continuation_lines(1), 2..12);
+    plain.set_min_level(LogLevel::Error);
+    assert_eq!(plain.total_lines(), 2);
+
+    let mut app = App::with_format(lines, Some(LogFormat::PythonLog));
+    assert_eq!(app.continuation_lines(1), 2..12);
+    assert_eq!(app.continuation_lines(12), 13..16);
+
+    // The whole traceback shows under the level filter, not just its fragments
+    app.set_min_level(LogLevel::Error);
+    assert_eq!(app.total_lines(), 2);
+    let raw = app.all_filtered_lines_raw();
+    assert!(
+        raw.starts_with("Traceback (most recent call last):"),
+        "{raw}"
+    );
+    assert!(
+        raw.contains("ValueError: bad job\n2024-01-15 10:30:01,200"),
+        "{raw}"
+    );
+    assert!(raw.ends_with("RuntimeError: gave up"), "{raw}");
+}
+
+#[test]
+fn test_appended_continuation_lines_join_the_last_entry() {
+    let mut app = stack_trace_app();
+    app.append_lines(vec![
+        "\tat com.app.Service.retry(Service.java:80)".into(),
+        "2024-01-15 08:30:03.000 [main] WARN com.app.Service - slow".into(),
+    ]);
+    assert_eq!(app.total_lines(), 6);
+    assert_eq!(app.continuation_lines(7), 8..9);
+
+    // With a filter in place, too
+    app.set_filter("Service".to_string());
+    app.append_lines(vec!["\tat com.app.Service.main(Service.java:9)".into()]);
+    assert_eq!(app.total_lines(), 6);
+    assert_eq!(app.continuation_lines(9), 10..11);
+}
+
+#[test]
+fn test_blank_lines_hide_and_stay_out_of_templates() {
+    let mut app = App::new(vec![
+        "INFO request 1 done".into(),
+        "".into(),
+        "   ".into(),
+        "INFO request 2 done".into(),
+    ]);
+    assert_eq!(app.total_lines(), 4);
+
+    // Similar lines never pull in the separators, nor do separators match
+    app.enter_cursor_mode();
+    app.filter_by_similar();
+    assert_eq!(app.total_lines(), 2);
+    app.clear_all_filters();
+    app.enter_cursor_mode();
+    app.cursor_down(1);
+    app.filter_by_similar();
+    assert_eq!(app.notice(), Some("Nothing to match on an empty line"));
+    assert_eq!(app.total_lines(), 4);
+
+    app.cycle_blank_lines();
+    assert_eq!(app.blank_lines(), BlankLines::Hidden);
+    assert_eq!(app.notice(), Some("Blank lines: hidden"));
+    assert_eq!(app.total_lines(), 2);
+    app.cycle_blank_lines();
+    assert_eq!(app.blank_lines(), BlankLines::Literal);
+    assert_eq!(app.total_lines(), 4);
+}
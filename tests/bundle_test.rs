@@ -0,0 +1,64 @@
+use lumolog::bundle::RuleBundle;
+use lumolog::config::{Config, HighlightRule, PivotUrl};
+
+fn sample_bundle() -> RuleBundle {
+    RuleBundle {
+        filters: vec!["api".to_string(), "timeout | refused".to_string()],
+        ignore: vec!["GET /healthz".to_string()],
+        highlight: vec![HighlightRule {
+            pattern: "req_[0-9a-f]+".to_string(),
+            color: "magenta".to_string(),
+        }],
+        pivot: vec![PivotUrl {
+            name: "Trace in Jaeger".to_string(),
+            url: "https://jaeger/trace/{}".to_string(),
+        }],
+    }
+}
+
+#[test]
+fn test_bundle_round_trips_through_toml() {
+    let bundle = sample_bundle();
+    let text = bundle.to_toml().unwrap();
+    assert!(text.contains("[[highlight]]"), "{text}");
+    assert_eq!(RuleBundle::from_toml(&text).unwrap(), bundle);
+
+    // Every section is optional
+    let only = RuleBundle::from_toml("ignore = [\"poll\"]\n").unwrap();
+    assert_eq!(only.ignore, ["poll"]);
+    assert!(only.filters.is_empty() && only.highlight.is_empty());
+}
+
+#[test]
+fn test_bundle_rejects_bad_patterns_and_keys() {
+    assert!(RuleBundle::from_toml("ignore = [\"(\"]\n").is_err());
+    assert!(
+        RuleBundle::from_toml("[[highlight]]\npattern = \"a\"\ncolor = \"notacolor\"\n").is_err()
+    );
+    assert!(RuleBundle::from_toml("colour = \"red\"\n").is_err());
+}
+
+#[test]
+fn test_bundle_merge_skips_duplicates() {
+    let config = Config::parse(
+        "[profile.api]\nignore = [\"GET /healthz\", \"poll\"]\npivot = [{ name = \"Trace in Jaeger\", url = \"https://jaeger/trace/{}\" }]\n",
+    )
+    .unwrap();
+    let mut rules = RuleBundle::from_profile(&config.profile["api"]);
+    rules.merge(&sample_bundle());
+    assert_eq!(rules.ignore, ["GET /healthz", "poll"]);
+    assert_eq!(rules.highlight.len(), 1);
+    assert_eq!(rules.pivot.len(), 1);
+    assert!(rules.filters.is_empty());
+    assert_eq!(rules.rule_count(), 4);
+}
+
+#[test]
+fn test_bundle_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("rules.toml");
+    let bundle = sample_bundle();
+    bundle.save(&path).unwrap();
+    assert_eq!(RuleBundle::load(&path).unwrap(), bundle);
+    assert!(RuleBundle::load(&dir.path().join("missing.toml")).is_err());
+}
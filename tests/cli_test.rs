@@ -22,3 +22,253 @@ fn test_help_flag() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("lumolog") || stdout.contains("USAGE") || stdout.contains("Usage"));
 }
+
+#[test]
+fn test_metrics_requires_follow() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--metrics",
+            "127.0.0.1:0",
+            "testdata/sample_plain.log",
+        ])
+        .output()
+        .expect("failed to execute");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_ne!(output.status.code(), Some(0));
+    assert!(stderr.contains("--follow"), "{stderr}");
+}
+
+#[test]
+fn test_goto_ts_rejects_unreadable_time() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "--goto-ts",
+            "yesterday",
+            "testdata/sample_plain.log",
+        ])
+        .output()
+        .expect("failed to execute");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr.contains("--goto-ts 'yesterday'"), "{stderr}");
+}
+
+#[test]
+fn test_plus_line_only_rewrites_positionals() {
+    let run = |args: &[&str]| {
+        let output = Command::new("cargo")
+            .args(["run", "--"])
+            .args(args)
+            .output()
+            .expect("failed to execute");
+        String::from_utf8_lossy(&output.stderr).into_owned()
+    };
+    // A positional +N becomes --goto, which clashes with --goto-ts
+    let stderr = run(&["testdata/sample_plain.log", "+5", "--goto-ts", "10:00"]);
+    assert!(stderr.contains("--goto"), "{stderr}");
+    // An option value is passed through untouched
+    let stderr = run(&["--load", "+5"]);
+    assert!(stderr.contains("cannot read view state +5"), "{stderr}");
+}
+
+#[test]
+fn test_test_format_prints_parsed_lines() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "test-format", "testdata/sample_logfmt.log"])
+        .output()
+        .expect("failed to execute");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout.starts_with("line 1 (logfmt): "), "{stdout}");
+    assert!(
+        stdout.contains("  message:   server starting\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_recent_prints_existing_files_when_piped() {
+    let state = tempfile::tempdir().unwrap();
+    let log = state.path().join("incident.log");
+    std::fs::write(&log, "line\n").unwrap();
+    std::fs::create_dir(state.path().join("lumolog")).unwrap();
+    std::fs::write(
+        state.path().join("lumolog").join("recent.txt"),
+        format!("{}\n/no/such/file.log\n", log.display()),
+    )
+    .unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "--recent"])
+        .env("XDG_STATE_HOME", state.path())
+        .output()
+        .expect("failed to execute");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}\n", log.display())
+    );
+}
+
+#[test]
+fn test_generate_writes_logs() {
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "generate",
+            "--format",
+            "logfmt",
+            "--rate",
+            "5",
+            "--duration",
+            "2s",
+        ])
+        .output()
+        .expect("failed to execute");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.lines().count() >= 10, "{stdout}");
+    assert!(
+        stdout.starts_with("ts=2024-01-15T08:00:00.000Z"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn test_replay_without_terminal_prints_final_screen() {
+    let events = std::env::temp_dir().join(format!("lumolog-replay-{}.jsonl", std::process::id()));
+    std::fs::write(
+        &events,
+        r#"{"file":"testdata/sample_json.log","width":80,"height":12}
+{"t":0,"key":"/"}
+{"t":5,"paste":"redis"}
+{"t":10,"key":"enter"}
+"#,
+    )
+    .unwrap();
+    let output = Command::new("cargo")
+        .args(["run", "--", "--replay-events"])
+        .arg(&events)
+        .output()
+        .expect("failed to execute");
+    let _ = std::fs::remove_file(&events);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(stdout.lines().count(), 12, "{stdout}");
+    assert!(stdout.contains("Failed to connect to redis"), "{stdout}");
+    assert!(stdout.contains("Filter: \"redis\" (1 matches)"), "{stdout}");
+}
+
+#[test]
+fn test_notes_and_match_keys_both_work_with_a_filter() {
+    let events =
+        std::env::temp_dir().join(format!("lumolog-replay-notes-{}.jsonl", std::process::id()));
+    std::fs::write(
+        &events,
+        r#"{"file":"testdata/sample_json.log","width":100,"height":16}
+{"t":0,"key":"/"}
+{"t":5,"paste":"redis"}
+{"t":10,"key":"enter"}
+{"t":15,"key":"enter"}
+{"t":20,"key":"n"}
+{"t":25,"key":"a"}
+{"t":30,"paste":"cache down"}
+{"t":35,"key":"enter"}
+{"t":40,"key":"E"}
+"#,
+    )
+    .unwrap();
+    let output = Command::new("cargo")
+        .args(["run", "--", "--replay-events"])
+        .arg(&events)
+        .output()
+        .expect("failed to execute");
+    let _ = std::fs::remove_file(&events);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Search wrapped to the top"), "{stdout}");
+    assert!(stdout.contains("Annotations 1/1"), "{stdout}");
+    assert!(stdout.contains("cache down"), "{stdout}");
+}
+
+#[test]
+fn test_rotated_files_load_in_rotation_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("app.log");
+    std::fs::write(dir.path().join("app.log.2"), "INFO oldest\n").unwrap();
+    std::fs::write(dir.path().join("app.log.1"), "INFO older\n").unwrap();
+    std::fs::write(&log, "INFO current\n").unwrap();
+    let events = dir.path().join("events.jsonl");
+    std::fs::write(
+        &events,
+        format!(
+            "{}\n{{\"t\":0,\"key\":\"R\"}}\n",
+            serde_json::json!({"file": log, "width": 100, "height": 12})
+        ),
+    )
+    .unwrap();
+    let output = Command::new("cargo")
+        .args(["run", "--", "--replay-events"])
+        .arg(&events)
+        .output()
+        .expect("failed to execute");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("Read 2 rotated files"), "{stdout}");
+    let at = |text: &str| stdout.find(text).unwrap();
+    assert!(at("INFO oldest") < at("INFO older") && at("INFO older") < at("INFO current"));
+}
+
+#[test]
+fn test_runaway_macro_stops() {
+    let events =
+        std::env::temp_dir().join(format!("lumolog-replay-macro-{}.jsonl", std::process::id()));
+    // Register a runs `99@a`, so replaying it would nest 99 times per level
+    std::fs::write(
+        &events,
+        r#"{"file":"testdata/sample_json.log","width":100,"height":16}
+{"t":0,"key":"Q"}
+{"t":1,"key":"a"}
+{"t":2,"key":"9"}
+{"t":3,"key":"9"}
+{"t":4,"key":"@"}
+{"t":5,"key":"a"}
+{"t":6,"key":"Q"}
+{"t":7,"key":"@"}
+{"t":8,"key":"a"}
+"#,
+    )
+    .unwrap();
+    let output = Command::new("cargo")
+        .args(["run", "--", "--replay-events"])
+        .arg(&events)
+        .output()
+        .expect("failed to execute");
+    let _ = std::fs::remove_file(&events);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stdout.contains("Macro stopped after 10,000 keys"),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,256 @@
+use lumolog::config::{
+    BlankLines, Config, EscAction, FuzzyCase, PaneLayout, ScrollConfig, expand_pivot_url,
+};
+use lumolog::highlighter::LevelBadge;
+use lumolog::parser::LogFormat;
+use std::path::Path;
+
+#[test]
+fn test_empty_config_uses_defaults() {
+    let config = Config::parse("").unwrap();
+    assert_eq!(config, Config::default());
+    assert_eq!(config.scroll.wheel_step, 3);
+    assert_eq!(config.scroll.scrolloff, 0);
+}
+
+#[test]
+fn test_partial_scroll_section() {
+    let config = Config::parse("[scroll]\nscrolloff = 5\n").unwrap();
+    assert_eq!(
+        config.scroll,
+        ScrollConfig {
+            scrolloff: 5,
+            ..ScrollConfig::default()
+        }
+    );
+}
+
+#[test]
+fn test_unknown_key_is_rejected() {
+    assert!(Config::parse("[scroll]\nscrollof = 5\n").is_err());
+}
+
+#[test]
+fn test_load_missing_explicit_path_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nope.toml");
+    assert!(Config::load(Some(&path)).is_err());
+}
+
+#[test]
+fn test_load_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "[scroll]\nwheel_step = 1\nhalf_page = 10\n").unwrap();
+    let config = Config::load(Some(&path)).unwrap();
+    assert_eq!(config.scroll.wheel_step, 1);
+    assert_eq!(config.scroll.half_page, 10);
+}
+
+#[test]
+fn test_yank_location_template() {
+    assert_eq!(Config::default().yank.location, "{path}:{line}");
+    let config = Config::parse("[yank]\nlocation = \"{file}:{line} ({time})\"\n").unwrap();
+    assert_eq!(config.yank.location, "{file}:{line} ({time})");
+}
+
+#[test]
+fn test_fuzzy_sections() {
+    let config = Config::parse(
+        "[fuzzy]\ncase = \"smart\"\n\n[palette]\nprefer_prefix = false\nmin_score = 20\n",
+    )
+    .unwrap();
+    assert_eq!(config.fuzzy.case, FuzzyCase::Smart);
+    assert_eq!(config.fuzzy.prefer_prefix, None);
+    assert_eq!(config.palette.prefer_prefix, Some(false));
+    assert_eq!(config.palette.min_score, 20);
+}
+
+const PROFILES: &str = r#"
+[profile.api]
+paths = ["*/api/*.log"]
+format = "logfmt"
+ignore = ["GET /healthz"]
+highlight = [{ pattern = "req_[0-9a-f]+", color = "magenta" }]
+pivot = [{ name = "Trace in Jaeger", url = "https://jaeger/trace/{}" }]
+
+[profile.worker]
+paths = ["worker-*.log"]
+"#;
+
+#[test]
+fn test_select_profile_by_name() {
+    let config = Config::parse(PROFILES).unwrap();
+    let (name, profile) = config.select_profile(Some("api"), None).unwrap().unwrap();
+    assert_eq!(name, "api");
+    assert_eq!(profile.log_format().unwrap(), Some(LogFormat::Logfmt));
+    assert_eq!(profile.pivot[0].name, "Trace in Jaeger");
+    assert!(profile.ignore_set().unwrap().is_match("GET /healthz 200"));
+    assert_eq!(profile.highlight_rules().unwrap().len(), 1);
+}
+
+#[test]
+fn test_select_unknown_profile_errors() {
+    let config = Config::parse(PROFILES).unwrap();
+    assert!(config.select_profile(Some("nope"), None).is_err());
+}
+
+#[test]
+fn test_select_profile_by_path() {
+    let config = Config::parse(PROFILES).unwrap();
+    let pick = |p: &str| {
+        config
+            .select_profile(None, Some(Path::new(p)))
+            .unwrap()
+            .map(|(name, _)| name.to_string())
+    };
+    assert_eq!(pick("/var/log/api/server.log").as_deref(), Some("api"));
+    // Globs also match the bare file name
+    assert_eq!(pick("/tmp/worker-3.log").as_deref(), Some("worker"));
+    assert_eq!(pick("/tmp/other.log"), None);
+}
+
+#[test]
+fn test_profile_bad_color_errors() {
+    let config =
+        Config::parse("[profile.x]\nhighlight = [{ pattern = \"a\", color = \"notacolor\" }]\n")
+            .unwrap();
+    let profile = &config.profile["x"];
+    assert!(profile.highlight_rules().is_err());
+}
+
+#[test]
+fn test_marker_rules() {
+    let config = Config::parse(
+        "[[marker]]\nlabel = \"breaker\"\npattern = \"circuit breaker opened\"\ncolor = \"red\"\n\n[[marker]]\nlabel = \"gc\"\npattern = \"Full GC\"\n",
+    )
+    .unwrap();
+    let rules = config.marker_rules().unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].label, "breaker");
+    assert!(
+        rules[0]
+            .pattern
+            .is_match("WARN circuit breaker opened for payments")
+    );
+    assert_eq!(rules[1].color, ratatui::style::Color::Yellow);
+}
+
+#[test]
+fn test_derived_fields() {
+    let config = Config::parse(
+        "[[derived]]\nname = \"latency_s\"\nexpr = \"duration_ms / 1000\"\n\n[[derived]]\nname = \"endpoint\"\nexpr = 'regex(path, \"^/api/[^/]+\")'\n",
+    )
+    .unwrap();
+    let fields = config.derived_fields().unwrap();
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[0].name, "latency_s");
+    assert_eq!(fields[1].name, "endpoint");
+
+    let bad = Config::parse("[[derived]]\nname = \"x\"\nexpr = \"a +\"\n").unwrap();
+    assert!(bad.derived_fields().is_err());
+}
+
+#[test]
+fn test_marker_rule_bad_regex_errors() {
+    let config = Config::parse("[[marker]]\nlabel = \"x\"\npattern = \"(\"\n").unwrap();
+    assert!(config.marker_rules().is_err());
+}
+
+#[test]
+fn test_esc_action() {
+    assert_eq!(Config::default().keys.esc, EscAction::Step);
+    let config = Config::parse("[keys]\nesc = \"clear-all\"\n").unwrap();
+    assert_eq!(config.keys.esc, EscAction::ClearAll);
+    assert!(Config::parse("[keys]\nesc = \"quit\"\n").is_err());
+}
+
+#[test]
+fn test_confirm_quit_defaults_off() {
+    assert!(!Config::default().keys.confirm_quit);
+    let config = Config::parse("[keys]\nconfirm_quit = true\n").unwrap();
+    assert!(config.keys.confirm_quit);
+}
+
+#[test]
+fn test_display_whitespace() {
+    assert!(!Config::default().display.whitespace);
+    let config = Config::parse("[display]\nwhitespace = true\n").unwrap();
+    assert!(config.display.whitespace);
+}
+
+#[test]
+fn test_layout_section() {
+    let layout = Config::default().layout;
+    assert!(layout.sparkline && layout.pinned && layout.stats);
+    assert_eq!(layout.pinned_rows, 5);
+    let config = Config::parse("[layout]\nstats = false\npinned_rows = 2\n").unwrap();
+    assert_eq!(
+        config.layout,
+        PaneLayout {
+            stats: false,
+            pinned_rows: 2,
+            ..PaneLayout::default()
+        }
+    );
+}
+
+#[test]
+fn test_display_own_times_only() {
+    assert!(!Config::default().display.own_times_only);
+    let config = Config::parse("[display]\nown_times_only = true\n").unwrap();
+    assert!(config.display.own_times_only);
+}
+
+#[test]
+fn test_split_commands_global_and_per_profile() {
+    let config = Config::parse(
+        "[[split]]\nname = \"less\"\ncommand = \"less +/{} app.log\"\n\n[profile.k8s]\nsplit = [{ name = \"kubectl logs\", command = \"kubectl logs -f {}\" }]\n",
+    )
+    .unwrap();
+    assert_eq!(config.split[0].name, "less");
+    assert_eq!(config.profile["k8s"].split[0].command, "kubectl logs -f {}");
+}
+
+#[test]
+fn test_display_accessibility() {
+    let display = Config::default().display;
+    assert!(!display.high_contrast && !display.text_cues);
+    let config = Config::parse("[display]\nhigh_contrast = true\ntext_cues = true\n").unwrap();
+    assert!(config.display.high_contrast && config.display.text_cues);
+}
+
+#[test]
+fn test_display_level_badge() {
+    assert_eq!(Config::default().display.level_badge, LevelBadge::Badge);
+    let config = Config::parse("[display]\nlevel_badge = \"letter\"\n").unwrap();
+    assert_eq!(config.display.level_badge, LevelBadge::Letter);
+    assert!(Config::parse("[display]\nlevel_badge = \"huge\"\n").is_err());
+}
+
+#[test]
+fn test_display_join_lines_defaults_on() {
+    assert!(Config::default().display.join_lines);
+    let config = Config::parse("[display]\njoin_lines = false\n").unwrap();
+    assert!(!config.display.join_lines);
+}
+
+#[test]
+fn test_display_blank_lines() {
+    assert_eq!(Config::default().display.blank_lines, BlankLines::Separator);
+    let config = Config::parse("[display]\nblank_lines = \"hidden\"\n").unwrap();
+    assert_eq!(config.display.blank_lines, BlankLines::Hidden);
+    assert!(Config::parse("[display]\nblank_lines = \"gone\"\n").is_err());
+}
+
+#[test]
+fn test_pivot_url_encodes_the_token() {
+    assert_eq!(
+        expand_pivot_url("https://grafana/explore?q={}", "req-42_a.b~c"),
+        "https://grafana/explore?q=req-42_a.b~c"
+    );
+    assert_eq!(
+        expand_pivot_url("https://search/?q={}&n=1", "user 7&admin#x/é"),
+        "https://search/?q=user%207%26admin%23x%2F%C3%A9&n=1"
+    );
+}
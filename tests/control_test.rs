@@ -0,0 +1,83 @@
+use lumolog::control::{self, Command};
+use lumolog::parser::LogLevel;
+
+fn words(s: &str) -> Vec<String> {
+    s.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!(
+        Command::parse(&words("set-filter connection refused")).unwrap(),
+        Command::SetFilter("connection refused".into())
+    );
+    assert_eq!(
+        Command::parse(&words("set-level warning")).unwrap(),
+        Command::SetLevel(Some(LogLevel::Warn))
+    );
+    assert_eq!(
+        Command::parse(&words("set-level all")).unwrap(),
+        Command::SetLevel(None)
+    );
+    assert_eq!(
+        Command::parse(&words("goto 120")).unwrap(),
+        Command::Goto(120)
+    );
+    assert_eq!(
+        Command::parse(&words("goto-end")).unwrap(),
+        Command::GotoEnd
+    );
+}
+
+#[test]
+fn test_parse_rejects_bad_commands() {
+    assert!(Command::parse(&[]).is_err());
+    assert!(Command::parse(&words("set-filter")).is_err());
+    assert!(Command::parse(&words("set-level loud")).is_err());
+    assert!(Command::parse(&words("goto end")).is_err());
+    assert!(Command::parse(&words("goto-end now")).is_err());
+    assert!(Command::parse(&words("explode")).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_send_reaches_listener() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("viewer.sock");
+    let listener = control::listen(&path).unwrap();
+
+    let reply = control::send(Some(&path), &words("set-filter timeout")).unwrap();
+    assert_eq!(reply, "ok");
+    assert_eq!(
+        listener.try_recv(),
+        Some(Command::SetFilter("timeout".into()))
+    );
+    assert_eq!(listener.try_recv(), None);
+
+    drop(listener);
+    assert!(!path.exists(), "socket file removed on drop");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_idle_client_does_not_block_later_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("viewer.sock");
+    let listener = control::listen(&path).unwrap();
+
+    // Connects but never sends a command
+    let _idle = std::os::unix::net::UnixStream::connect(&path).unwrap();
+    let reply = control::send(Some(&path), &words("goto-top")).unwrap();
+    assert_eq!(reply, "ok");
+    assert_eq!(listener.try_recv(), Some(Command::GotoTop));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_send_gives_up_on_a_silent_viewer() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("viewer.sock");
+    // Accepts connections but never reads or replies
+    let _silent = std::os::unix::net::UnixListener::bind(&path).unwrap();
+    assert!(control::send(Some(&path), &words("goto-top")).is_err());
+}
@@ -0,0 +1,57 @@
+use lumolog::decode::{Decoded, decode_base64, decode_token};
+
+const JWT: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+                   eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ.\
+                   SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c";
+
+#[test]
+fn test_decode_base64_alphabets_and_padding() {
+    assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+    assert_eq!(decode_base64("aGVsbG8").unwrap(), b"hello");
+    assert_eq!(
+        decode_base64("-_-_").unwrap(),
+        decode_base64("+/+/").unwrap()
+    );
+    assert_eq!(decode_base64("aGVsb"), None);
+    assert_eq!(decode_base64("aGVs bG8="), None);
+}
+
+#[test]
+fn test_jwt_header_and_claims() {
+    let decoded = decode_token(JWT).unwrap();
+    let Decoded::Jwt { header, claims } = &decoded else {
+        panic!("not a JWT: {decoded:?}");
+    };
+    assert_eq!(header["alg"], "HS256");
+    assert_eq!(claims["name"], "John Doe");
+    assert_eq!(decoded.label(), "JWT");
+    assert!(decoded.payload().contains("\n  \"sub\": \"1234567890\""));
+
+    let times = decoded.times();
+    assert_eq!(times.len(), 1);
+    assert_eq!(times[0].0, "Issued");
+    assert_eq!(times[0].1.to_rfc3339(), "2018-01-18T01:30:22+00:00");
+}
+
+#[test]
+fn test_base64_text() {
+    let decoded = decode_token("dXNlcjpjb3JyZWN0IGhvcnNl").unwrap();
+    assert_eq!(decoded, Decoded::Text("user:correct horse".to_string()));
+    assert_eq!(decoded.payload(), "user:correct horse");
+    assert!(decoded.times().is_empty());
+    assert_eq!(decode_token("\"dXNlcjpjb3JyZWN0IGhvcnNl\""), Some(decoded));
+}
+
+#[test]
+fn test_lookalikes_are_not_decoded() {
+    // Hex digests and identifiers decode to binary, not text
+    assert_eq!(
+        decode_token("da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+        None
+    );
+    assert_eq!(decode_token("RequestHandlerFactory"), None);
+    // Too short to tell
+    assert_eq!(decode_token("aGVsbG8="), None);
+    // Three dots but not JSON
+    assert_eq!(decode_token("aGVsbG8.d29ybGQ.c2ln"), None);
+}
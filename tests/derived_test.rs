@@ -0,0 +1,106 @@
+use lumolog::derived::{self, DerivedField};
+use lumolog::parser::{LogFormat, parse_line};
+
+fn eval(expr: &str, raw: &str, format: LogFormat) -> Option<String> {
+    DerivedField::parse("x", expr)
+        .unwrap()
+        .eval(&parse_line(raw, format))
+}
+
+#[test]
+fn test_arithmetic_on_fields() {
+    let line = "level=info msg=done duration_ms=2300";
+    assert_eq!(
+        eval("duration_ms / 1000", line, LogFormat::Logfmt),
+        Some("2.3".into())
+    );
+    assert_eq!(
+        eval("duration_ms * 2 + 1", line, LogFormat::Logfmt),
+        Some("4601".into())
+    );
+    assert_eq!(
+        eval("-(duration_ms - 300) / 2", line, LogFormat::Logfmt),
+        Some("-1000".into())
+    );
+}
+
+#[test]
+fn test_json_fields_and_units() {
+    let line = r#"{"level":"info","msg":"req","elapsed":"1.5s","bytes":2048}"#;
+    assert_eq!(eval("elapsed", line, LogFormat::Json), Some("1.5s".into()));
+    // Unit suffixes normalize to ms / bytes in arithmetic
+    assert_eq!(
+        eval("elapsed / 1000", line, LogFormat::Json),
+        Some("1.5".into())
+    );
+    assert_eq!(
+        eval("bytes / 1024", line, LogFormat::Json),
+        Some("2".into())
+    );
+}
+
+#[test]
+fn test_regex_extracts_group_or_match() {
+    let line = "level=info msg=req path=/api/users/42";
+    assert_eq!(
+        eval(r#"regex(path, "^/api/[^/]+")"#, line, LogFormat::Logfmt),
+        Some("/api/users".into())
+    );
+    assert_eq!(
+        eval(r#"regex(path, "/(\d+)$")"#, line, LogFormat::Logfmt),
+        Some("42".into())
+    );
+    assert_eq!(
+        eval(r#"regex(path, "^/internal")"#, line, LogFormat::Logfmt),
+        None
+    );
+    assert_eq!(
+        eval(r#"upper(regex(message, "^\w+"))"#, line, LogFormat::Logfmt),
+        Some("REQ".into())
+    );
+}
+
+#[test]
+fn test_missing_field_or_non_number_yields_nothing() {
+    let line = "level=info msg=req path=/x";
+    assert_eq!(eval("duration_ms / 1000", line, LogFormat::Logfmt), None);
+    assert_eq!(eval("path * 2", line, LogFormat::Logfmt), None);
+    assert_eq!(eval("1 / 0", line, LogFormat::Logfmt), None);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(DerivedField::parse("x", "a +").is_err());
+    assert!(DerivedField::parse("x", "(a").is_err());
+    assert!(DerivedField::parse("x", "a b").is_err());
+    assert!(DerivedField::parse("x", "nope(a)").is_err());
+    assert!(DerivedField::parse("x", r#"regex(a, "(")"#).is_err());
+    assert!(DerivedField::parse("x", "regex(a, b)").is_err());
+    let err = DerivedField::parse("latency_s", "a $ b").err().unwrap();
+    assert!(err.to_string().contains("latency_s"));
+}
+
+#[test]
+fn test_apply_in_order_and_replaces() {
+    let fields = vec![
+        DerivedField::parse("latency_s", "duration_ms / 1000").unwrap(),
+        DerivedField::parse("latency_min", "latency_s / 60").unwrap(),
+        DerivedField::parse("skipped", "missing + 1").unwrap(),
+    ];
+    let mut line = parse_line(
+        r#"{"level":"info","msg":"req","duration_ms":120000}"#,
+        LogFormat::Json,
+    );
+    derived::apply(&fields, &mut line);
+    derived::apply(&fields, &mut line);
+    let get = |k: &str| {
+        line.extra_fields
+            .iter()
+            .filter(|(key, _)| key == k)
+            .map(|(_, v)| v.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(get("latency_s"), vec!["120"]);
+    assert_eq!(get("latency_min"), vec!["2"]);
+    assert!(get("skipped").is_empty());
+}
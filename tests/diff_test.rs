@@ -0,0 +1,59 @@
+use lumolog::diff::{DiffOp, word_diff};
+
+fn reassemble(ops: &[DiffOp], keep_removed: bool) -> String {
+    ops.iter()
+        .filter_map(|op| match op {
+            DiffOp::Equal(s) => Some(s.as_str()),
+            DiffOp::Removed(s) if keep_removed => Some(s.as_str()),
+            DiffOp::Added(s) if !keep_removed => Some(s.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_identical_lines_are_one_equal_op() {
+    let ops = word_diff("user=alice action=login", "user=alice action=login");
+    assert_eq!(ops, [DiffOp::Equal("user=alice action=login".into())]);
+}
+
+#[test]
+fn test_single_changed_token() {
+    let ops = word_diff("GET /api status=200 ms=12", "GET /api status=500 ms=12");
+    assert_eq!(
+        ops,
+        [
+            DiffOp::Equal("GET /api ".into()),
+            DiffOp::Removed("status=200".into()),
+            DiffOp::Added("status=500".into()),
+            DiffOp::Equal(" ms=12".into()),
+        ]
+    );
+}
+
+#[test]
+fn test_inserted_tokens() {
+    let ops = word_diff("a c", "a b c");
+    assert_eq!(reassemble(&ops, true), "a c");
+    assert_eq!(reassemble(&ops, false), "a b c");
+    assert!(
+        ops.iter()
+            .any(|op| matches!(op, DiffOp::Added(s) if s.contains('b')))
+    );
+    assert!(!ops.iter().any(|op| matches!(op, DiffOp::Removed(_))));
+}
+
+#[test]
+fn test_diff_round_trips_both_sides() {
+    let old = "2024-01-15 10:00:00 INFO  request id=7 user=bob path=/a took 5ms";
+    let new = "2024-01-15 10:00:01 ERROR request id=8 path=/a user=bob failed";
+    let ops = word_diff(old, new);
+    assert_eq!(reassemble(&ops, true), old);
+    assert_eq!(reassemble(&ops, false), new);
+}
+
+#[test]
+fn test_empty_side() {
+    let ops = word_diff("", "hello world");
+    assert_eq!(ops, [DiffOp::Added("hello world".into())]);
+}
@@ -0,0 +1,136 @@
+use lumolog::exporter::{Exporter, MAX_TEMPLATES, Target};
+use lumolog::parser::{LogFormat, ParsedLine, parse_line};
+use std::net::{TcpListener, UdpSocket};
+use std::time::{Duration, Instant};
+
+fn plain(lines: &[&str]) -> Vec<ParsedLine> {
+    lines
+        .iter()
+        .map(|l| parse_line(l, LogFormat::Plain))
+        .collect()
+}
+
+#[test]
+fn test_target_parses_statsd_and_otlp_urls() {
+    assert_eq!(
+        Target::parse("statsd://127.0.0.1:8125").unwrap(),
+        Target::Statsd("127.0.0.1:8125".to_string())
+    );
+    assert_eq!(
+        Target::parse("http://otel:4318").unwrap(),
+        Target::Otlp("http://otel:4318/v1/metrics".to_string())
+    );
+    assert_eq!(
+        Target::parse("https://otel/v1/metrics").unwrap(),
+        Target::Otlp("https://otel/v1/metrics".to_string())
+    );
+    assert!(Target::parse("statsd://localhost").is_err());
+    assert!(Target::parse("udp://localhost:8125").is_err());
+}
+
+#[test]
+fn test_statsd_lines_send_only_what_is_new() {
+    let exporter = Exporter::new(Target::Statsd("127.0.0.1:8125".into()));
+    let lines = plain(&["ERROR disk full", "ERROR disk full", "INFO ok"]);
+    exporter.observe(lines.iter().map(|l| (l, None)));
+    exporter.observe([(&lines[2], Some("restart"))]);
+
+    let sent = exporter.take_statsd_lines();
+    assert!(
+        sent.contains(&"lumolog.lines:2|c|#level:error".to_string()),
+        "{sent:?}"
+    );
+    assert!(
+        sent.contains(&"lumolog.lines:2|c|#level:info".to_string()),
+        "{sent:?}"
+    );
+    assert!(sent.contains(&"lumolog.rule_matches:1|c|#rule:restart".to_string()));
+    assert!(
+        sent.iter()
+            .any(|l| l.starts_with("lumolog.template_lines:2|c|#template:ERROR_")),
+        "{sent:?}"
+    );
+
+    assert!(exporter.take_statsd_lines().is_empty());
+    exporter.observe(lines[..1].iter().map(|l| (l, None)));
+    assert!(
+        exporter
+            .take_statsd_lines()
+            .contains(&"lumolog.lines:1|c|#level:error".to_string())
+    );
+}
+
+#[test]
+fn test_templates_past_the_cap_count_as_other() {
+    let exporter = Exporter::new(Target::Statsd("127.0.0.1:8125".into()));
+    let lines: Vec<ParsedLine> = (0..MAX_TEMPLATES + 3)
+        .map(|i| {
+            let mut line = parse_line("INFO x", LogFormat::Plain);
+            line.template = format!("shape {i}");
+            line
+        })
+        .collect();
+    exporter.observe(lines.iter().map(|l| (l, None)));
+    let sent = exporter.take_statsd_lines();
+    let templates = sent
+        .iter()
+        .filter(|l| l.starts_with("lumolog.template_lines:"))
+        .count();
+    assert_eq!(templates, MAX_TEMPLATES + 1);
+    assert!(sent.contains(&"lumolog.template_lines:3|c|#template:other".to_string()));
+}
+
+#[test]
+fn test_otlp_body_has_cumulative_sums() {
+    let exporter = Exporter::new(Target::Otlp("http://otel:4318/v1/metrics".into()));
+    let lines = plain(&["WARN slow", "WARN slow"]);
+    exporter.observe(lines.iter().map(|l| (l, None)));
+
+    let body = exporter.otlp_body();
+    let metrics = &body["resourceMetrics"][0]["scopeMetrics"][0]["metrics"];
+    let lines_metric = metrics
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["name"] == "lumolog.lines")
+        .unwrap();
+    assert_eq!(lines_metric["sum"]["isMonotonic"], true);
+    assert_eq!(lines_metric["sum"]["aggregationTemporality"], 2);
+    let point = &lines_metric["sum"]["dataPoints"][0];
+    assert_eq!(point["asInt"], "2");
+    assert_eq!(point["attributes"][0]["key"], "level");
+    assert_eq!(point["attributes"][0]["value"]["stringValue"], "warn");
+}
+
+#[test]
+fn test_flush_sends_statsd_over_udp() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let addr = socket.local_addr().unwrap();
+    let exporter = Exporter::new(Target::Statsd(addr.to_string()));
+    exporter.observe(plain(&["FATAL gone"]).iter().map(|l| (l, None)));
+    exporter.flush().unwrap();
+
+    let mut buf = [0; 2048];
+    let n = socket.recv(&mut buf).unwrap();
+    let datagram = std::str::from_utf8(&buf[..n]).unwrap();
+    assert!(
+        datagram.contains("lumolog.lines:1|c|#level:fatal"),
+        "{datagram}"
+    );
+}
+
+#[test]
+fn test_flush_gives_up_on_a_stalled_collector() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let exporter = Exporter::new(Target::Otlp(format!("http://{addr}/v1/metrics")));
+    exporter.observe(plain(&["INFO up"]).iter().map(|l| (l, None)));
+
+    let start = Instant::now();
+    assert!(exporter.flush().is_err());
+    assert!(start.elapsed() < Duration::from_secs(30));
+    drop(listener);
+}
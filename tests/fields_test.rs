@@ -0,0 +1,124 @@
+use lumolog::fields::{ValueCount, numeric_summary, sort_by_quantity, value_distribution};
+use lumolog::parser::{LogFormat, parse_line};
+use lumolog::units::Dimension;
+
+#[test]
+fn test_logfmt_distribution_counts_and_filters() {
+    let lines: Vec<_> = [
+        "level=info msg=ok status=200",
+        "level=warn msg=slow status=200",
+        "level=error msg=boom status=500",
+        "level=info msg=ok status=200",
+        "level=info msg=nostatus",
+    ]
+    .iter()
+    .map(|l| parse_line(l, LogFormat::Logfmt))
+    .collect();
+    let dist = value_distribution(&lines, &[0, 1, 2, 3, 4], "status", 20);
+    assert_eq!(dist.lines_with_key, 4);
+    assert_eq!(dist.other_values, 0);
+    assert_eq!(
+        dist.values,
+        vec![
+            ValueCount {
+                value: "200".into(),
+                count: 3,
+                filter: "status=200".into()
+            },
+            ValueCount {
+                value: "500".into(),
+                count: 1,
+                filter: "status=500".into()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_json_string_values_are_unquoted() {
+    let lines: Vec<_> = [
+        r#"{"level":"info","msg":"a","region":"eu-west"}"#,
+        r#"{"level":"info","msg":"b", "region": "us-east"}"#,
+        r#"{"level":"info","msg":"c","region":"eu-west"}"#,
+    ]
+    .iter()
+    .map(|l| parse_line(l, LogFormat::Json))
+    .collect();
+    let dist = value_distribution(&lines, &[0, 1, 2], "region", 20);
+    assert_eq!(dist.values[0].value, "eu-west");
+    assert_eq!(dist.values[0].filter, r#""region":"eu-west""#);
+    assert_eq!(dist.values[1].filter, r#""region": "us-east""#);
+}
+
+#[test]
+fn test_plain_lines_fall_back_to_raw_pairs() {
+    let lines: Vec<_> = [
+        "2024-01-15 08:30:00 INFO request user=\"bob smith\" took 3ms",
+        "2024-01-15 08:30:01 INFO request user=alice took 5ms",
+        "2024-01-15 08:30:02 INFO request user=alice took 4ms",
+    ]
+    .iter()
+    .map(|l| parse_line(l, LogFormat::Plain))
+    .collect();
+    let dist = value_distribution(&lines, &[0, 1, 2], "user", 1);
+    assert_eq!(dist.values.len(), 1);
+    assert_eq!(dist.values[0].value, "alice");
+    assert_eq!(dist.other_values, 1);
+}
+
+#[test]
+fn test_only_given_indices_are_counted() {
+    let lines: Vec<_> = ["a=1", "a=2", "a=2"]
+        .iter()
+        .map(|l| parse_line(l, LogFormat::Plain))
+        .collect();
+    let dist = value_distribution(&lines, &[0], "a", 20);
+    assert_eq!(dist.lines_with_key, 1);
+    assert_eq!(dist.values[0].value, "1");
+}
+
+#[test]
+fn test_numeric_summary_normalizes_units() {
+    let lines: Vec<_> = [
+        "took=2.3s",
+        "took=850ms",
+        "took=120",
+        "took=1m",
+        "took=n/a",
+        "other=1",
+    ]
+    .iter()
+    .map(|l| parse_line(l, LogFormat::Logfmt))
+    .collect();
+    let summary = numeric_summary(&lines, &[0, 1, 2, 3, 4, 5], "took").unwrap();
+    assert_eq!(summary.dimension, Dimension::Duration);
+    assert_eq!(summary.count, 4);
+    assert_eq!(summary.min, 120.0);
+    assert_eq!(summary.p50, 850.0);
+    assert_eq!(summary.p95, 60_000.0);
+    assert_eq!(summary.max, 60_000.0);
+}
+
+#[test]
+fn test_numeric_summary_none_for_text_or_mixed_units() {
+    let lines: Vec<_> = ["k=abc size=1KB", "k=def size=2s"]
+        .iter()
+        .map(|l| parse_line(l, LogFormat::Logfmt))
+        .collect();
+    assert!(numeric_summary(&lines, &[0, 1], "k").is_none());
+    assert!(numeric_summary(&lines, &[0, 1], "size").is_none());
+}
+
+#[test]
+fn test_sort_by_quantity_orders_across_units() {
+    let lines: Vec<_> = [
+        "t=2s", "t=2s", "t=900ms", "t=-", "t=1.5s", "t=1.5s", "t=1.5s",
+    ]
+    .iter()
+    .map(|l| parse_line(l, LogFormat::Logfmt))
+    .collect();
+    let mut values = value_distribution(&lines, &[0, 1, 2, 3, 4, 5, 6], "t", 20).values;
+    sort_by_quantity(&mut values);
+    let order: Vec<_> = values.iter().map(|v| v.value.as_str()).collect();
+    assert_eq!(order, vec!["900ms", "1.5s", "2s", "-"]);
+}
@@ -1,4 +1,8 @@
-use lumolog::filter::filter_lines;
+use lumolog::config::{FuzzyCase, FuzzyConfig};
+use lumolog::filter::{
+    CaseMode, FilterWarning, JsonFilter, SLOW_FILTER, TextMatch, contains_word, filter_lines,
+    filter_lines_matching, filter_lines_stacked, filter_lines_with, is_context_free,
+};
 use lumolog::parser::{LogFormat, LogLevel, ParsedLine};
 
 fn make_line(raw: &str, level: Option<LogLevel>) -> ParsedLine {
@@ -9,6 +13,7 @@ fn make_line(raw: &str, level: Option<LogLevel>) -> ParsedLine {
         message: raw.to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     }
@@ -187,3 +192,458 @@ fn test_fuzzy_no_matches_returns_empty() {
     let result = filter_lines(&lines, "zzzzz", None);
     assert!(result.indices.is_empty());
 }
+
+#[test]
+fn test_fuzzy_min_score_drops_weak_matches() {
+    let lines = vec![make_line("connection refused", Some(LogLevel::Error))];
+    let loose = FuzzyConfig::default();
+    assert_eq!(
+        filter_lines_with(&lines, "conref", None, &loose).indices,
+        [0]
+    );
+    let strict = FuzzyConfig {
+        min_score: u32::MAX,
+        ..FuzzyConfig::default()
+    };
+    assert!(
+        filter_lines_with(&lines, "conref", None, &strict)
+            .indices
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_fuzzy_respect_case() {
+    let lines = vec![make_line("Connection Refused", Some(LogLevel::Error))];
+    let respect = FuzzyConfig {
+        case: FuzzyCase::Respect,
+        ..FuzzyConfig::default()
+    };
+    assert!(
+        filter_lines_with(&lines, "conref", None, &respect)
+            .indices
+            .is_empty()
+    );
+    assert_eq!(
+        filter_lines_with(&lines, "CoRe", None, &respect).indices,
+        [0]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Numeric field conditions
+// ---------------------------------------------------------------------------
+
+fn logfmt(lines: &[&str]) -> Vec<ParsedLine> {
+    lines
+        .iter()
+        .map(|l| lumolog::parser::parse_line(l, LogFormat::Logfmt))
+        .collect()
+}
+
+#[test]
+fn test_field_greater_than() {
+    let lines = logfmt(&[
+        "msg=a duration_ms=120 status=200",
+        "msg=b duration_ms=812 status=200",
+        "msg=c status=500",
+        "msg=d duration_ms=501 status=503",
+    ]);
+    let result = filter_lines(&lines, "duration_ms > 500", None);
+    assert_eq!(result.indices, vec![1, 3]);
+    assert!(!result.is_fuzzy);
+}
+
+#[test]
+fn test_field_range_is_inclusive() {
+    let lines = logfmt(&[
+        "msg=a status=200",
+        "msg=b status=500",
+        "msg=c status=599",
+        "msg=d status=404",
+    ]);
+    let result = filter_lines(&lines, "status in 500..599", None);
+    assert_eq!(result.indices, vec![1, 2]);
+}
+
+#[test]
+fn test_field_units_are_normalized() {
+    let lines = logfmt(&[
+        "msg=a took=850ms",
+        "msg=b took=2.3s",
+        "msg=c took=1s",
+        "msg=d took=12KB",
+    ]);
+    // 12KB is a size, so it never compares against a duration
+    assert_eq!(filter_lines(&lines, "took >= 1s", None).indices, vec![1, 2]);
+    // A bare number is read in the field's unit (milliseconds)
+    assert_eq!(filter_lines(&lines, "took < 900", None).indices, vec![0]);
+}
+
+#[test]
+fn test_field_condition_on_json_and_plain_lines() {
+    let json = vec![lumolog::parser::parse_line(
+        r#"{"level":"info","msg":"req","latency":"1.2s"}"#,
+        LogFormat::Json,
+    )];
+    assert_eq!(filter_lines(&json, "latency > 1s", None).indices, vec![0]);
+
+    let plain = vec![make_line("GET /api bytes=2048 done", None)];
+    assert_eq!(
+        filter_lines(&plain, "bytes != 2048", None).indices,
+        Vec::<usize>::new()
+    );
+    assert_eq!(filter_lines(&plain, "bytes == 2048", None).indices, vec![0]);
+}
+
+fn json_lines(raw: &[&str]) -> Vec<ParsedLine> {
+    raw.iter()
+        .map(|line| lumolog::parser::parse_line(line, LogFormat::Json))
+        .collect()
+}
+
+#[test]
+fn test_json_path_comparisons() {
+    let lines = json_lines(&[
+        r#"{"msg":"login","user":{"id":42,"name":"ann"},"duration_ms":120}"#,
+        r#"{"msg":"login","user":{"id":7,"name":"bob"},"duration_ms":80.5}"#,
+        r#"{"msg":"tick","tags":["beta","eu"]}"#,
+    ]);
+    let kept = |pattern: &str| filter_lines(&lines, pattern, None).indices;
+    assert_eq!(kept(".user.id == 42"), vec![0]);
+    assert_eq!(kept(".user.id == 42.0"), vec![0]);
+    assert_eq!(kept(".duration_ms > 100"), vec![0]);
+    assert_eq!(kept(".duration_ms <= 80.5"), vec![1]);
+    assert_eq!(kept(r#".user.name == "bob""#), vec![1]);
+    assert_eq!(kept(".user.name == bob"), vec![1]);
+    assert_eq!(kept(".tags[0] == beta"), vec![2]);
+    assert_eq!(kept(r#".user["name"] >= b"#), vec![1]);
+    // A missing value is null, so it's never equal but always unequal
+    assert_eq!(kept(".user.id != 42"), vec![1, 2]);
+    assert_eq!(kept(".duration_ms > abc"), Vec::<usize>::new());
+}
+
+#[test]
+fn test_json_bare_path_and_text_fallback() {
+    let lines = json_lines(&[
+        r#"{"msg":"failed","error":{"code":"E1"}}"#,
+        r#"{"msg":"ok","error":null}"#,
+        r#"{"msg":"see .env for settings","retry":false}"#,
+    ]);
+    let kept = |pattern: &str| filter_lines(&lines, pattern, None).indices;
+    assert_eq!(kept(".error"), vec![0]);
+    assert_eq!(kept(".error.code"), vec![0]);
+    assert_eq!(kept("!.error"), vec![1, 2]);
+    assert_eq!(kept(".retry"), Vec::<usize>::new());
+    // No line has an `env` key, so it's plain text
+    assert_eq!(kept(".env"), vec![2]);
+
+    // Only JSON lines are looked into
+    let plain = vec![make_line(r#"{"user":{"id":42}}"#, None)];
+    assert_eq!(
+        filter_lines(&plain, ".user.id == 42", None).indices,
+        Vec::<usize>::new()
+    );
+}
+
+#[test]
+fn test_json_filter_parse() {
+    assert!(JsonFilter::parse(".a.b").is_some());
+    assert!(JsonFilter::parse(r#"."@t" > "2024""#).is_some_and(|f| f.compares()));
+    assert!(JsonFilter::parse(".items[2].id == 1").is_some());
+    assert!(JsonFilter::parse(".").is_none());
+    assert!(JsonFilter::parse("user.id == 1").is_none());
+    assert!(JsonFilter::parse(".a ==").is_none());
+    assert!(JsonFilter::parse(".a ~ 1").is_none());
+    assert!(JsonFilter::parse(".items[x]").is_none());
+}
+
+#[test]
+fn test_field_equals_matches_field_values() {
+    let mut lines = logfmt(&[
+        "msg=a service=auth status=500",
+        "msg=b service=auth-proxy status=5001",
+        r#"msg=c service="Auth" status=200"#,
+    ]);
+    lines.push(lumolog::parser::parse_line(
+        r#"{"level":"error","msg":"d","service":"billing","status":500}"#,
+        LogFormat::Json,
+    ));
+    // The whole value, not a substring of the text, and JSON fields too
+    assert_eq!(filter_lines(&lines, "status=500", None).indices, vec![0, 3]);
+    assert_eq!(
+        filter_lines(&lines, "service=auth", None).indices,
+        vec![0, 2]
+    );
+    assert_eq!(
+        filter_lines(&lines, "service=billing", None).indices,
+        vec![3]
+    );
+    assert_eq!(
+        filter_lines(&lines, "!status=500", None).indices,
+        vec![1, 2]
+    );
+    // A value nothing has yet is searched for as text while typing
+    assert_eq!(
+        filter_lines(&lines, "service=auth-p", None).indices,
+        vec![1]
+    );
+}
+
+#[test]
+fn test_has_and_missing_filter_by_field_presence() {
+    let mut lines = logfmt(&[
+        "msg=a trace_id=abc user=1",
+        "msg=b user=2",
+        "msg=c trace_id=def",
+    ]);
+    lines.push(make_line("plain straggler without fields", None));
+    assert_eq!(
+        filter_lines(&lines, "has:trace_id", None).indices,
+        vec![0, 2]
+    );
+    assert_eq!(
+        filter_lines(&lines, "missing:trace_id", None).indices,
+        vec![1, 3]
+    );
+    assert_eq!(
+        filter_lines(&lines, "missing:user", None).indices,
+        vec![2, 3]
+    );
+    assert_eq!(filter_lines(&lines, "!has:user", None).indices, vec![2, 3]);
+    assert_eq!(
+        filter_lines(&lines, "has:trace_id | has:user", None).indices,
+        vec![0, 1, 2]
+    );
+    // Nothing has it: no text or fuzzy fallback
+    let result = filter_lines(&lines, "has:span", None);
+    assert!(result.indices.is_empty());
+    assert!(!result.is_fuzzy);
+    assert!(is_context_free("missing:user"));
+}
+
+#[test]
+fn test_field_condition_respects_level_filter() {
+    let mut lines = logfmt(&["msg=a ms=900", "msg=b ms=950"]);
+    lines[0].level = Some(LogLevel::Info);
+    lines[1].level = Some(LogLevel::Error);
+    let result = filter_lines(&lines, "ms > 500", Some(LogLevel::Error));
+    assert_eq!(result.indices, vec![1]);
+}
+
+#[test]
+fn test_non_numeric_comparison_is_plain_text() {
+    let lines = vec![make_line("if a > b then", None)];
+    assert_eq!(filter_lines(&lines, "a > b", None).indices, vec![0]);
+}
+
+#[test]
+fn test_regex_filter() {
+    let lines = vec![
+        make_line("GET /api status=503", None),
+        make_line("GET /api status=200", None),
+        make_line("POST /api status=500", Some(LogLevel::Error)),
+    ];
+    let result = filter_lines(&lines, r"re:status=5\d\d$", None);
+    assert_eq!(result.indices, vec![0, 2]);
+    assert!(!result.is_fuzzy);
+    assert_eq!(result.error, None);
+
+    // Case-sensitive unless asked otherwise, and never fuzzy
+    assert!(filter_lines(&lines, "re:get", None).indices.is_empty());
+    assert_eq!(filter_lines(&lines, "re:(?i)get", None).indices, vec![0, 1]);
+    assert_eq!(
+        filter_lines(&lines, "re:^POST", Some(LogLevel::Error)).indices,
+        vec![2]
+    );
+}
+
+#[test]
+fn test_invalid_regex_keeps_lines_and_reports() {
+    let lines = vec![make_line("a", None), make_line("b", None)];
+    let result = filter_lines(&lines, "re:status=(5", None);
+    assert_eq!(result.indices, vec![0, 1]);
+    assert_eq!(result.error.as_deref(), Some("unclosed group"));
+}
+
+#[test]
+fn test_exclude_filter_hides_matches() {
+    let lines = vec![
+        make_line("GET /healthz 200", None),
+        make_line("GET /api/users 200", Some(LogLevel::Info)),
+        make_line("GET /HEALTHZ 200", None),
+        make_line("POST /api/users 500 took=2s", Some(LogLevel::Error)),
+    ];
+    let result = filter_lines(&lines, "!healthz", None);
+    assert_eq!(result.indices, vec![1, 3]);
+    assert!(!result.is_fuzzy);
+
+    assert_eq!(filter_lines(&lines, "!re:^GET", None).indices, vec![3]);
+    assert_eq!(
+        filter_lines(&lines, "!took > 1s", None).indices,
+        vec![0, 1, 2]
+    );
+    assert_eq!(
+        filter_lines(&lines, "!healthz", Some(LogLevel::Error)).indices,
+        vec![3]
+    );
+    // A bare `!` while typing hides nothing, and nothing fuzzy is excluded
+    assert_eq!(filter_lines(&lines, "!", None).indices.len(), 4);
+    assert_eq!(filter_lines(&lines, "!hlthz", None).indices.len(), 4);
+    assert_eq!(
+        filter_lines(&lines, "!re:(", None).error.as_deref(),
+        Some("unclosed group")
+    );
+}
+
+#[test]
+fn test_stacked_filters_and_alternatives() {
+    let lines = vec![
+        make_line("GET /api/users 200", Some(LogLevel::Info)),
+        make_line("GET /api/users 500 timeout", Some(LogLevel::Error)),
+        make_line("POST /api/orders 500 refused", Some(LogLevel::Error)),
+        make_line("GET /healthz 200", None),
+        make_line("level=info|msg=refused", None),
+    ];
+    let fuzzy = FuzzyConfig::default();
+    let stacked = |terms: &[&str]| filter_lines_stacked(&lines, terms, None, &fuzzy);
+
+    assert_eq!(stacked(&["api", "500"]).indices, vec![1, 2]);
+    assert_eq!(stacked(&["timeout | refused"]).indices, vec![1, 2, 4]);
+    assert_eq!(stacked(&["api", "timeout | refused"]).indices, vec![1, 2]);
+    assert_eq!(stacked(&["GET", "!api | 500"]).indices, vec![1, 3]);
+    // A bare `|` is text, as in pipe-delimited logs
+    assert_eq!(stacked(&["info|msg"]).indices, vec![4]);
+    // Empty alternatives and terms filter nothing
+    assert_eq!(stacked(&["500 | "]).indices, vec![1, 2]);
+    assert_eq!(stacked(&[]).indices.len(), 5);
+
+    // A bad regex is skipped, the rest still apply
+    let result = stacked(&["re:(", "healthz | orders"]);
+    assert_eq!(result.indices, vec![2, 3]);
+    assert_eq!(result.error.as_deref(), Some("unclosed group"));
+
+    // No fuzzy fallback once filters are combined
+    assert!(stacked(&["hlthz"]).is_fuzzy);
+    assert!(stacked(&["GET", "hlthz"]).indices.is_empty());
+    assert!(stacked(&["hlthz | nothing"]).indices.is_empty());
+}
+
+#[test]
+fn test_case_modes() {
+    let lines = vec![
+        make_line("Error: disk full", Some(LogLevel::Error)),
+        make_line("error: retrying", Some(LogLevel::Error)),
+        make_line("ERROR code=E42", Some(LogLevel::Error)),
+    ];
+    let fuzzy = FuzzyConfig::default();
+    let cased = |term: &str, case| {
+        let text = TextMatch {
+            case,
+            whole_word: false,
+        };
+        filter_lines_matching(&lines, &[term], None, &fuzzy, text)
+    };
+
+    assert_eq!(cased("error", CaseMode::Ignore).indices, vec![0, 1, 2]);
+    assert_eq!(cased("Error", CaseMode::Ignore).indices, vec![0, 1, 2]);
+    // Smart case ignores case until there's an uppercase letter
+    assert_eq!(cased("error", CaseMode::Smart).indices, vec![0, 1, 2]);
+    assert_eq!(cased("Error", CaseMode::Smart).indices, vec![0]);
+    assert_eq!(cased("error", CaseMode::Sensitive).indices, vec![1]);
+    assert_eq!(cased("!ERROR", CaseMode::Sensitive).indices, vec![0, 1]);
+    // Field values still ignore case
+    assert_eq!(cased("code=e42", CaseMode::Sensitive).indices, vec![2]);
+    // The fuzzy fallback respects case too
+    assert!(cased("dskfull", CaseMode::Sensitive).is_fuzzy);
+    assert!(cased("DSKFULL", CaseMode::Sensitive).indices.is_empty());
+    assert!(cased("DSKFULL", CaseMode::Ignore).is_fuzzy);
+}
+
+#[test]
+fn test_case_mode_cycles() {
+    assert_eq!(CaseMode::default(), CaseMode::Ignore);
+    assert_eq!(CaseMode::Ignore.next(), CaseMode::Smart);
+    assert_eq!(CaseMode::Smart.next(), CaseMode::Sensitive);
+    assert_eq!(CaseMode::Sensitive.next(), CaseMode::Ignore);
+    assert_eq!(CaseMode::Ignore.label(), None);
+}
+
+#[test]
+fn test_whole_word_matching() {
+    let lines = vec![
+        make_line("err: disk full", Some(LogLevel::Error)),
+        make_line("transferred 5 files", Some(LogLevel::Info)),
+        make_line("[ERR] retrying", Some(LogLevel::Error)),
+        make_line("stderr closed", Some(LogLevel::Info)),
+        make_line("err_code=7", Some(LogLevel::Error)),
+    ];
+    let fuzzy = FuzzyConfig::default();
+    let words = TextMatch {
+        whole_word: true,
+        ..TextMatch::default()
+    };
+    let find = |term: &str, text| filter_lines_matching(&lines, &[term], None, &fuzzy, text);
+
+    assert_eq!(
+        find("err", TextMatch::default()).indices,
+        vec![0, 1, 2, 3, 4]
+    );
+    assert_eq!(find("err", words).indices, vec![0, 2]);
+    assert_eq!(find("!err", words).indices, vec![1, 3, 4]);
+    // Whole words don't fall back to fuzzy matching
+    let result = find("dsk", words);
+    assert!(result.indices.is_empty() && !result.is_fuzzy);
+    assert!(contains_word("a.err.b", "err"));
+    assert!(!contains_word("errors", "err"));
+    assert!(contains_word("errors err", "err"));
+    assert_eq!(words.label().as_deref(), Some("whole word"));
+    assert_eq!(TextMatch::default().label(), None);
+}
+
+#[test]
+fn test_filter_warnings() {
+    let lines = vec![
+        make_line("GET /api 200", Some(LogLevel::Info)),
+        make_line("GET /api 500", Some(LogLevel::Error)),
+        make_line("GET /healthz 200", Some(LogLevel::Info)),
+    ];
+    let fast = std::time::Duration::from_millis(3);
+    let check = |terms: &[&str], elapsed| {
+        let result = filter_lines_stacked(&lines, terms, None, &FuzzyConfig::default());
+        FilterWarning::check(terms, &result, elapsed)
+    };
+
+    assert_eq!(
+        check(&["re:.*"], fast),
+        Some(FilterWarning::KeepsEverything)
+    );
+    assert_eq!(check(&["!zzz"], fast), Some(FilterWarning::KeepsEverything));
+    assert_eq!(
+        check(&["re:^POST"], fast),
+        Some(FilterWarning::KeepsNothing)
+    );
+    assert_eq!(check(&["api"], fast), None);
+    assert_eq!(check(&[], fast), None);
+    // A bad regex says so itself
+    assert_eq!(check(&["re:("], fast), None);
+    assert_eq!(
+        check(&["api"], SLOW_FILTER),
+        Some(FilterWarning::Slow(SLOW_FILTER))
+    );
+    assert_eq!(
+        FilterWarning::Slow(std::time::Duration::from_millis(1250)).message(),
+        "slow: 1.2s per keystroke"
+    );
+}
+
+#[test]
+fn test_context_free_terms() {
+    assert!(is_context_free("error"));
+    assert!(is_context_free("re:5\\d\\d | !timeout"));
+    assert!(is_context_free("duration_ms > 500"));
+    assert!(is_context_free(".user.id == 42"));
+    // Searched for as text while no line has the field
+    assert!(!is_context_free("service=auth"));
+    assert!(!is_context_free("error | !.retry"));
+}
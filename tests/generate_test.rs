@@ -0,0 +1,72 @@
+use chrono::NaiveDateTime;
+use lumolog::generate::{FORMATS, Generator, default_start, parse_duration, run};
+use lumolog::parser::{LogFormat, detect_format, parse_line};
+
+fn start() -> NaiveDateTime {
+    default_start(false)
+}
+
+fn generate(format: LogFormat, seed: u64, rate: usize, seconds: u64) -> Vec<String> {
+    let mut generator = Generator::new(format, seed).unwrap();
+    let mut out = Vec::new();
+    run(&mut generator, start(), rate, seconds, false, &mut out).unwrap();
+    String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_same_seed_gives_same_log() {
+    let a = generate(LogFormat::Json, 7, 50, 30);
+    assert_eq!(a, generate(LogFormat::Json, 7, 50, 30));
+    assert_ne!(a, generate(LogFormat::Json, 8, 50, 30));
+}
+
+#[test]
+fn test_each_format_is_detected_with_levels() {
+    for format in FORMATS {
+        let lines = generate(format, 1, 20, 10);
+        assert_eq!(detect_format(&lines), format, "{}", lines[0]);
+        for line in &lines {
+            let parsed = parse_line(line, format);
+            assert!(parsed.level.is_some(), "{line}");
+            assert!(parsed.timestamp.is_some(), "{line}");
+        }
+    }
+}
+
+#[test]
+fn test_rate_and_duration_set_line_count_with_bursts_on_top() {
+    let lines = generate(LogFormat::Logfmt, 1, 10, 600);
+    let bursts = lines
+        .iter()
+        .filter(|l| l.contains("upstream timeout"))
+        .count();
+    assert!(bursts > 0, "ten minutes should see a burst");
+    // Burst seconds carry four times the rate, so the total only grows in
+    // steps of three seconds' worth
+    assert!(lines.len() >= 6000);
+    assert_eq!((lines.len() - 6000) % 30, 0);
+    assert!(lines.iter().any(|l| l.contains("level=debug")));
+    assert!(lines.iter().any(|l| l.contains("level=error")));
+    assert!(lines[0].starts_with("ts=2024-01-15T08:00:00.000Z "));
+    assert!(lines.last().unwrap().starts_with("ts=2024-01-15T08:09:59."));
+}
+
+#[test]
+fn test_unsupported_format_is_an_error() {
+    let err = Generator::new(LogFormat::Klog, 1).unwrap_err();
+    assert!(err.to_string().contains("klog"), "{err}");
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("60s").unwrap(), 60);
+    assert_eq!(parse_duration("5m").unwrap(), 300);
+    assert_eq!(parse_duration("1h").unwrap(), 3600);
+    assert_eq!(parse_duration("90").unwrap(), 90);
+    assert!(parse_duration("500ms").is_err());
+    assert!(parse_duration("soon").is_err());
+}
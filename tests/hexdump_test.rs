@@ -0,0 +1,49 @@
+use lumolog::hexdump::{
+    ByteClass, NARROW_ROW, WIDE_ROW, ascii_char, bytes_per_row, classify, hex_dump, row_width,
+    summary,
+};
+
+#[test]
+fn test_classify_bytes() {
+    assert_eq!(classify(b'a'), ByteClass::Printable);
+    assert_eq!(classify(b' '), ByteClass::Printable);
+    assert_eq!(classify(b'\t'), ByteClass::Control);
+    assert_eq!(classify(0x1b), ByteClass::Control);
+    assert_eq!(classify(0x7f), ByteClass::Control);
+    assert_eq!(classify(0xc3), ByteClass::NonAscii);
+    assert_eq!(ascii_char(b'A'), 'A');
+    assert_eq!(ascii_char(0x00), '.');
+}
+
+#[test]
+fn test_hex_dump_rows_line_up() {
+    let rows = hex_dump(b"Hello \x1b[31mred\x1b[0m caf\xc3\xa9", WIDE_ROW);
+    assert_eq!(
+        rows,
+        [
+            "00000000  48 65 6c 6c 6f 20 1b 5b  33 31 6d 72 65 64 1b 5b  |Hello .[31mred.[|",
+            "00000010  30 6d 20 63 61 66 c3 a9                           |0m caf..|",
+        ]
+    );
+}
+
+#[test]
+fn test_narrow_rows() {
+    assert_eq!(bytes_per_row(120), WIDE_ROW);
+    assert_eq!(bytes_per_row(60), NARROW_ROW);
+    assert_eq!(hex_dump(b"0123456789", NARROW_ROW).len(), 2);
+    assert_eq!(
+        row_width(WIDE_ROW),
+        hex_dump(&[b'x'; 16], WIDE_ROW)[0].len()
+    );
+}
+
+#[test]
+fn test_summary_counts_odd_bytes() {
+    assert_eq!(summary(b"a"), "1 byte");
+    assert_eq!(summary(b"ok\r"), "3 bytes, 1 control");
+    assert_eq!(
+        summary("ünï\t".as_bytes()),
+        "6 bytes, 1 control, 4 non-ASCII"
+    );
+}
@@ -1,5 +1,10 @@
-use lumolog::highlighter::{apply_search_highlight, highlight_line};
-use lumolog::parser::{LogFormat, LogLevel, ParsedLine};
+use lumolog::highlighter::{
+    LevelBadge, TokenKind, access_line_text, apply_pattern_highlight, apply_search_highlight,
+    apply_search_highlight_with, highlight_line, highlight_line_expanded_with,
+    highlight_line_visible, highlight_line_visible_with, tokenize_with_metadata,
+    visualize_whitespace,
+};
+use lumolog::parser::{LogFormat, LogLevel, ParsedLine, parse_line};
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 
@@ -12,6 +17,7 @@ fn test_error_line_has_red() {
         message: "something broke".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -23,6 +29,27 @@ fn test_error_line_has_red() {
     assert!(has_red, "Error lines should contain red spans");
 }
 
+#[test]
+fn test_plain_line_shows_extra_fields_after_text() {
+    let parsed = ParsedLine {
+        raw: "upstream timeout".to_string(),
+        level: None,
+        timestamp: None,
+        message: "upstream timeout".to_string(),
+        format: LogFormat::Plain,
+        pretty_json: None,
+        json: None,
+        extra_fields: vec![("source".to_string(), "api".to_string())],
+        template: String::new(),
+    };
+    let text: String = highlight_line(&parsed)
+        .spans
+        .iter()
+        .map(|s| s.content.as_ref())
+        .collect();
+    assert_eq!(text, "upstream timeout  source=api");
+}
+
 #[test]
 fn test_warn_line_has_yellow() {
     let parsed = ParsedLine {
@@ -32,6 +59,7 @@ fn test_warn_line_has_yellow() {
         message: "something iffy".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -52,6 +80,7 @@ fn test_info_line_is_dimmed() {
         message: "all good".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -83,6 +112,7 @@ fn test_ip_address_highlighted() {
         message: "Connected from 192.168.1.100 port 52413".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -103,6 +133,7 @@ fn test_invalid_ip_not_highlighted_as_single_span() {
         message: "Version 999.999.999.999 released".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -128,6 +159,7 @@ fn test_url_highlighted() {
         message: "Fetching https://api.example.com/data".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -148,6 +180,7 @@ fn test_uuid_highlighted() {
         message: "Request f47ac10b-58cc-4372-a567-0e02b2c3d479 processed".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -168,6 +201,7 @@ fn test_file_path_highlighted() {
         message: "Loading config from /etc/app/config.yaml".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -187,6 +221,7 @@ fn test_http_method_highlighted() {
         message: "Request: GET /api/users".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -210,6 +245,7 @@ fn test_quoted_string_highlighted() {
         message: r#"Cannot open file "config.yaml": permission denied"#.to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -229,6 +265,7 @@ fn test_key_value_highlighted() {
         message: "host=localhost:6379 status=connected".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -250,6 +287,7 @@ fn test_syslog_ip_highlighted() {
         message: "sshd[1234]: Accepted publickey from 192.168.1.100 port 52413".to_string(),
         format: LogFormat::Syslog,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -270,6 +308,7 @@ fn test_json_message_patterns_highlighted() {
         message: "Connected from 10.0.0.1".to_string(),
         format: LogFormat::Json,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -290,6 +329,7 @@ fn test_plain_line_without_patterns() {
         message: "Application starting up".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -316,6 +356,7 @@ fn test_url_takes_priority_over_path() {
         message: "Fetch https://example.com/api/data done".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -341,6 +382,7 @@ fn test_number_highlighted() {
         message: "Processed 150 records in 23ms".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -361,6 +403,7 @@ fn test_keyword_null_highlighted() {
         message: "Value was null for key".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -381,6 +424,7 @@ fn test_keyword_true_false_highlighted() {
         message: "verbose=true debug=false".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -406,6 +450,7 @@ fn test_pointer_address_highlighted() {
         message: "Segfault at address 0x7fff5fbff8c0 in thread 3".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -426,6 +471,7 @@ fn test_unix_process_highlighted() {
         message: "sshd[1234]: Accepted publickey".to_string(),
         format: LogFormat::Syslog,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -449,6 +495,7 @@ fn test_inline_date_highlighted() {
         message: "Backup completed for date 2024-06-15T10:30:00Z successfully".to_string(),
         format: LogFormat::Plain,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -473,6 +520,7 @@ fn test_json_extra_fields_rendered_dimmed() {
         message: "Failed".to_string(),
         format: LogFormat::Json,
         pretty_json: None,
+        json: None,
         extra_fields: vec![("error".to_string(), r#""Connection refused""#.to_string())],
         template: String::new(),
     };
@@ -499,6 +547,7 @@ fn test_json_no_extra_fields_no_trailing_space() {
         message: "Clean line".to_string(),
         format: LogFormat::Json,
         pretty_json: None,
+        json: None,
         extra_fields: Vec::new(),
         template: String::new(),
     };
@@ -544,6 +593,26 @@ fn test_search_highlight_cross_span_boundary() {
     assert_eq!(result.spans[1].style, highlight);
 }
 
+#[test]
+fn test_search_highlight_case_sensitive() {
+    let line = Line::from("error: Error".to_string());
+    let result = apply_search_highlight_with(line, "Error", true, false);
+    let highlight = Style::default().bg(Color::Yellow).fg(Color::Black);
+    assert_eq!(result.spans.len(), 2);
+    assert_eq!(result.spans[0].content.as_ref(), "error: ");
+    assert_eq!(result.spans[1].content.as_ref(), "Error");
+    assert_eq!(result.spans[1].style, highlight);
+}
+
+#[test]
+fn test_search_highlight_whole_word() {
+    let line = Line::from("transferred; err".to_string());
+    let result = apply_search_highlight_with(line, "err", false, true);
+    assert_eq!(result.spans.len(), 2);
+    assert_eq!(result.spans[0].content.as_ref(), "transferred; ");
+    assert_eq!(result.spans[1].content.as_ref(), "err");
+}
+
 #[test]
 fn test_search_highlight_no_match() {
     let line = Line::from(vec![Span::styled(
@@ -587,3 +656,251 @@ fn test_search_highlight_case_insensitive() {
     assert_eq!(result.spans[0].content.as_ref(), "ERROR");
     assert_eq!(result.spans[0].style, highlight);
 }
+
+#[test]
+fn test_pretty_json_line_syntax_colors() {
+    use lumolog::highlighter::highlight_pretty_json_line;
+    let spans = highlight_pretty_json_line(r#"  "user": "al\"ice", "n": 42, "ok": true,"#);
+    let style_of = |text: &str| {
+        spans
+            .iter()
+            .find(|s| s.content == text)
+            .and_then(|s| s.style.fg)
+    };
+    assert_eq!(style_of("\"user\""), Some(Color::Blue));
+    assert_eq!(style_of(r#""al\"ice""#), Some(Color::Indexed(222)));
+    assert_eq!(style_of("42"), Some(Color::Cyan));
+    assert_eq!(style_of("true"), Some(Color::LightRed));
+    assert_eq!(style_of(":"), Some(Color::DarkGray));
+    let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(text, r#"  "user": "al\"ice", "n": 42, "ok": true,"#);
+}
+
+#[test]
+fn test_search_highlight_applies_inside_pretty_json_body() {
+    use lumolog::highlighter::highlight_line_expanded;
+    use lumolog::parser::parse_line;
+    let parsed = parse_line(
+        r#"{"level":"error","message":"disk full","host":"db-1"}"#,
+        LogFormat::Json,
+    );
+    let expanded = highlight_line_expanded(&parsed, true);
+    let host_row = expanded
+        .into_iter()
+        .find(|l| l.spans.iter().any(|s| s.content.contains("db-1")))
+        .unwrap();
+    let result = apply_search_highlight(host_row, "db-1");
+    assert!(
+        result
+            .spans
+            .iter()
+            .any(|s| s.content == "db-1" && s.style.bg == Some(Color::Yellow))
+    );
+}
+
+#[test]
+fn test_pattern_highlight_colors_matches_only() {
+    let line = Line::from(vec![
+        Span::raw("request "),
+        Span::styled("req_ab12 done", Style::default().fg(Color::White)),
+    ]);
+    let re = regex::Regex::new(r"req_[0-9a-f]+").unwrap();
+    let out = apply_pattern_highlight(line, &re, Color::Magenta);
+    let text: String = out.spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(text, "request req_ab12 done");
+    let hit = out.spans.iter().find(|s| s.content == "req_ab12").unwrap();
+    assert_eq!(hit.style.fg, Some(Color::Magenta));
+    let rest = out.spans.iter().find(|s| s.content == " done").unwrap();
+    assert_eq!(rest.style.fg, Some(Color::White));
+}
+
+#[test]
+fn test_metadata_marks_encoded_tokens() {
+    let text = "auth=Bearer eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiJib2IifQ.c2ln basic=dXNlcjpjb3JyZWN0IGhvcnNl \
+                sha=da39a3ee5e6b4b0d3255bfef95601890afd80709";
+    let tokens: Vec<(TokenKind, String)> = tokenize_with_metadata(text, Style::default())
+        .into_iter()
+        .filter_map(|(_, kind, raw)| kind.map(|k| (k, raw)))
+        .filter(|(k, _)| *k == TokenKind::Encoded)
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            (
+                TokenKind::Encoded,
+                "eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiJib2IifQ.c2ln".to_string()
+            ),
+            (TokenKind::Encoded, "dXNlcjpjb3JyZWN0IGhvcnNl".to_string()),
+        ]
+    );
+}
+
+fn long_plain_line() -> ParsedLine {
+    let raw = format!(
+        "start https://a.example.com/x {} end https://b.example.com/y",
+        "filler ".repeat(2000)
+    );
+    ParsedLine {
+        message: raw.clone(),
+        raw,
+        level: None,
+        timestamp: None,
+        format: LogFormat::Plain,
+        pretty_json: None,
+        json: None,
+        extra_fields: Vec::new(),
+        template: String::new(),
+    }
+}
+
+fn url_styled(line: &Line, url: &str) -> bool {
+    line.spans
+        .iter()
+        .any(|span| span.style.fg == Some(Color::Blue) && span.content.contains(url))
+}
+
+#[test]
+fn test_long_line_only_tokenized_near_visible_columns() {
+    let parsed = long_plain_line();
+    let line = highlight_line_visible(&parsed, &(0..80));
+    let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(text, parsed.raw);
+    assert!(url_styled(&line, "a.example.com"));
+    assert!(!url_styled(&line, "b.example.com"));
+
+    let end = parsed.raw.len();
+    let line = highlight_line_visible(&parsed, &(end - 80..end));
+    assert!(!url_styled(&line, "a.example.com"));
+    assert!(url_styled(&line, "b.example.com"));
+}
+
+#[test]
+fn test_long_line_fully_tokenized_by_highlight_line() {
+    let parsed = long_plain_line();
+    let line = highlight_line(&parsed);
+    assert!(url_styled(&line, "a.example.com"));
+    assert!(url_styled(&line, "b.example.com"));
+}
+
+#[test]
+fn test_visualize_whitespace() {
+    let red = Style::default().fg(Color::Red);
+    let line = Line::from(vec![Span::styled("a\tb c", red), Span::raw("\r  ")]);
+    let shown = visualize_whitespace(line);
+    let text: String = shown.spans.iter().map(|s| s.content.as_ref()).collect();
+    // Inner spaces stay; the tab, CR and trailing spaces become visible
+    assert_eq!(text, "a→b c^M··");
+    let arrow = shown.spans.iter().find(|s| s.content == "→").unwrap();
+    assert_eq!(arrow.style.fg, Some(Color::DarkGray));
+    assert_eq!(shown.spans[0].style, red);
+
+    let blank = visualize_whitespace(Line::from("   "));
+    let text: String = blank.spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(text, "···");
+}
+
+fn line_text(line: &Line) -> String {
+    line.spans.iter().map(|s| s.content.as_ref()).collect()
+}
+
+#[test]
+fn test_access_line_in_columns() {
+    let parsed = parse_line(
+        r#"10.0.0.1 - - [10/Oct/2024:13:55:37 -0700] "POST /api/login HTTP/1.1" 401 512"#,
+        LogFormat::AccessLog,
+    );
+    let line = highlight_line(&parsed);
+    assert_eq!(
+        line_text(&line),
+        "[WRN] 10/Oct/2024:13:55:37 -0700 POST    401      512  /api/login  ip=10.0.0.1"
+    );
+    let span = |text: &str| {
+        line.spans
+            .iter()
+            .find(|s| s.content.trim() == text)
+            .unwrap()
+    };
+    assert_eq!(span("401").style.fg, Some(Color::Yellow));
+    assert_eq!(span("POST").style.fg, Some(Color::Magenta));
+    assert_eq!(span("512").style.fg, Some(Color::Cyan));
+    assert_eq!(span("/api/login").style.fg, Some(Color::Indexed(108)));
+    // Click targets line up with what's drawn after the prefix
+    assert!(line_text(&line).ends_with(&access_line_text(&parsed).unwrap()));
+}
+
+#[test]
+fn test_access_line_status_colors_and_latency() {
+    let ok = parse_line(
+        r#"10.0.0.1 - - [10/Oct/2024:13:55:42 -0700] "GET /api/health HTTP/1.1" 200 13 "-" "curl/8.1.2" 0.042"#,
+        LogFormat::AccessLog,
+    );
+    assert_eq!(
+        access_line_text(&ok).as_deref(),
+        Some("GET     200       13   0.042  /api/health  ip=10.0.0.1 ua=curl/8.1.2")
+    );
+    let line = highlight_line(&ok);
+    let status = line.spans.iter().find(|s| s.content == "200").unwrap();
+    assert_eq!(status.style.fg, Some(Color::Green));
+
+    let failed = parse_line(
+        r#"10.0.0.5 - - [10/Oct/2024:13:55:40 -0700] "PUT /api/config HTTP/1.1" 500 1024"#,
+        LogFormat::AccessLog,
+    );
+    let line = highlight_line(&failed);
+    let status = line.spans.iter().find(|s| s.content == "500").unwrap();
+    assert_eq!(status.style.fg, Some(Color::Red));
+}
+
+#[test]
+fn test_unparsed_access_line_shows_as_plain_text() {
+    let parsed = parse_line("garbage", LogFormat::AccessLog);
+    assert_eq!(parsed.format, LogFormat::Plain);
+    assert_eq!(access_line_text(&parsed), None);
+    assert_eq!(line_text(&highlight_line(&parsed)), "garbage");
+}
+
+#[test]
+fn test_level_badge_styles() {
+    let parsed = parse_line(
+        r#"{"level":"error","ts":"08:30:05","msg":"failed"}"#,
+        LogFormat::Json,
+    );
+    let text = |badge| {
+        line_text(&highlight_line_visible_with(
+            &parsed,
+            &(0..usize::MAX),
+            badge,
+        ))
+    };
+    assert_eq!(text(LevelBadge::Badge), "[ERR] 08:30:05 failed");
+    assert_eq!(text(LevelBadge::Code), "ERR 08:30:05 failed");
+    assert_eq!(text(LevelBadge::Letter), "E 08:30:05 failed");
+    assert_eq!(text(LevelBadge::Emoji), "❗ 08:30:05 failed");
+    assert_eq!(text(LevelBadge::None), "08:30:05 failed");
+
+    // Code and letter keep the level's color but drop the background
+    let code = highlight_line_visible_with(&parsed, &(0..usize::MAX), LevelBadge::Code);
+    assert_eq!(code.spans[0].style.fg, Some(Color::Red));
+    assert_eq!(code.spans[0].style.bg, None);
+
+    // Widths match what's drawn, emoji included
+    assert_eq!(LevelBadge::Badge.width(Some(LogLevel::Warn)), 6);
+    assert_eq!(LevelBadge::Emoji.width(Some(LogLevel::Warn)), 3);
+    assert_eq!(LevelBadge::Emoji.width(None), 3);
+    assert_eq!(LevelBadge::None.width(Some(LogLevel::Warn)), 0);
+
+    let pretty = highlight_line_expanded_with(&parsed, true, LevelBadge::Letter);
+    assert_eq!(line_text(&pretty[0]), "--- E ");
+}
+
+#[test]
+fn test_level_badge_cycles_through_every_style() {
+    let mut badge = LevelBadge::default();
+    assert_eq!(badge, LevelBadge::Badge);
+    let mut names = Vec::new();
+    for _ in 0..5 {
+        badge = badge.next();
+        names.push(badge.name());
+    }
+    assert_eq!(names, ["code", "letter", "emoji", "none", "badge"]);
+}
@@ -0,0 +1,64 @@
+use lumolog::linestats::{LengthBucket, length_stats};
+use lumolog::parser::{LogFormat, parse_line};
+
+fn lines(lengths: &[usize]) -> Vec<lumolog::parser::ParsedLine> {
+    lengths
+        .iter()
+        .map(|&n| parse_line(&"x".repeat(n), LogFormat::Plain))
+        .collect()
+}
+
+#[test]
+fn test_length_stats_summary_and_buckets() {
+    let lines = lines(&[10, 50, 150, 20, 12_000, 150]);
+    let all: Vec<usize> = (0..lines.len()).collect();
+    let stats = length_stats(&lines, &all, 3).unwrap();
+    assert_eq!(stats.lines, 6);
+    assert_eq!(stats.total_bytes, 12_380);
+    assert_eq!(
+        (stats.min, stats.p50, stats.p95, stats.max),
+        (10, 50, 12_000, 12_000)
+    );
+    assert_eq!(
+        stats.buckets,
+        [
+            LengthBucket {
+                min: 0,
+                max: Some(100),
+                count: 3
+            },
+            LengthBucket {
+                min: 100,
+                max: Some(1_000),
+                count: 2
+            },
+            LengthBucket {
+                min: 1_000,
+                max: Some(10_000),
+                count: 0
+            },
+            LengthBucket {
+                min: 10_000,
+                max: Some(100_000),
+                count: 1
+            },
+        ]
+    );
+    // Longest first, equal lengths in line order
+    assert_eq!(stats.longest, [(4, 12_000), (2, 150), (5, 150)]);
+}
+
+#[test]
+fn test_length_stats_over_filtered_lines() {
+    let lines = lines(&[10, 2_000_000, 30]);
+    let stats = length_stats(&lines, &[0, 2], 10).unwrap();
+    assert_eq!(stats.max, 30);
+    assert_eq!(stats.buckets.len(), 1);
+    assert_eq!(stats.longest, [(2, 30), (0, 10)]);
+
+    let all = length_stats(&lines, &[0, 1, 2], 10).unwrap();
+    assert_eq!(all.buckets.last().unwrap().max, None);
+    assert_eq!(all.buckets.last().unwrap().count, 1);
+
+    assert_eq!(length_stats(&lines, &[], 10), None);
+}
@@ -0,0 +1,69 @@
+use lumolog::loader::{self, LoadProgress};
+use lumolog::source::FileSource;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
+
+fn wait<T>(loading: &loader::Loading<T>) -> anyhow::Result<T> {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        if let Some(result) = loading.try_finish() {
+            return result;
+        }
+        assert!(Instant::now() < deadline, "load never finished");
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[test]
+fn test_open_with_progress_reports_bytes_and_lines() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "one\ntwo\nthree").unwrap();
+    file.flush().unwrap();
+
+    let progress = LoadProgress::new(13);
+    let (source, _) = FileSource::open_with_progress(file.path(), false, &progress).unwrap();
+    assert_eq!(source.lines(), &["one", "two", "three"]);
+    assert_eq!(progress.bytes(), 13);
+    assert_eq!(progress.lines(), 3);
+}
+
+#[test]
+fn test_open_with_progress_stops_when_cancelled() {
+    let mut file = NamedTempFile::new().unwrap();
+    writeln!(file, "line").unwrap();
+    file.flush().unwrap();
+
+    let progress = LoadProgress::new(5);
+    progress.cancel();
+    assert!(FileSource::open_with_progress(file.path(), false, &progress).is_err());
+}
+
+#[test]
+fn test_spawned_load_hands_back_result_and_progress() {
+    let loading = loader::spawn(100, |progress| {
+        progress.set_read(100, 7);
+        progress.start_parsing();
+        Ok("done")
+    });
+    assert_eq!(wait(&loading).unwrap(), "done");
+    assert_eq!(loading.progress().total_bytes(), 100);
+    assert_eq!(loading.progress().lines(), 7);
+    assert!(loading.progress().is_parsing());
+}
+
+#[test]
+fn test_spawned_load_passes_errors_through() {
+    let loading = loader::spawn::<(), _>(0, |_| anyhow::bail!("no such file"));
+    let err = wait(&loading).unwrap_err();
+    assert_eq!(err.to_string(), "no such file");
+}
+
+#[test]
+fn test_finish_waits_for_the_result() {
+    let loading = loader::spawn(0, |_| {
+        std::thread::sleep(Duration::from_millis(20));
+        Ok(42)
+    });
+    assert_eq!(loading.finish().unwrap(), 42);
+}
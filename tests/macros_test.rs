@@ -0,0 +1,91 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use lumolog::macros::{MacroRecorder, is_register, record};
+
+fn key(c: char) -> KeyEvent {
+    KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+}
+
+#[test]
+fn test_record_and_replay() {
+    let mut macros = MacroRecorder::default();
+    // Keys outside a recording are ignored
+    macros.record(key('x'));
+    macros.start('a');
+    assert_eq!(macros.recording(), Some('a'));
+    macros.record(key('v'));
+    macros.record(key('j'));
+    assert_eq!(macros.stop(), Some('a'));
+    assert_eq!(macros.recording(), None);
+    assert_eq!(macros.keys('a'), Some(vec![key('v'), key('j')]));
+}
+
+#[test]
+fn test_at_replays_last_register() {
+    let mut macros = MacroRecorder::default();
+    assert_eq!(macros.keys('@'), None);
+    macros.start('b');
+    macros.record(key('G'));
+    macros.stop();
+    macros.keys('b');
+    assert_eq!(macros.keys('@'), Some(vec![key('G')]));
+}
+
+#[test]
+fn test_rerecording_replaces_register() {
+    let mut macros = MacroRecorder::default();
+    macros.start('a');
+    macros.record(key('j'));
+    macros.stop();
+    macros.start('a');
+    macros.stop();
+    assert_eq!(macros.keys('a'), Some(vec![]));
+    assert_eq!(macros.keys('z'), None);
+}
+
+#[test]
+fn test_register_names() {
+    assert!(is_register('a'));
+    assert!(!is_register('A'));
+    assert!(!is_register('1'));
+}
+
+#[test]
+fn test_macros_save_and_load_by_register() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lumolog/macros.toml");
+    assert!(MacroRecorder::load(&path).unwrap().keys('a').is_none());
+
+    let mut macros = MacroRecorder::default();
+    macros.start('a');
+    macros.record(key('/'));
+    macros.record(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    macros.record(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL));
+    macros.stop();
+    record(&path, &macros, 'a').unwrap();
+
+    // Another viewer's macro in the file survives
+    let mut other = MacroRecorder::default();
+    other.start('b');
+    other.record(key('G'));
+    other.stop();
+    record(&path, &other, 'b').unwrap();
+
+    let mut loaded = MacroRecorder::load(&path).unwrap();
+    assert_eq!(
+        loaded.keys('a'),
+        Some(vec![
+            key('/'),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
+        ])
+    );
+    assert_eq!(loaded.keys('b'), Some(vec![key('G')]));
+    assert!(std::fs::read_to_string(&path).unwrap().contains("a = ["));
+}
+
+#[test]
+fn test_bad_macro_files_are_rejected() {
+    assert!(MacroRecorder::from_toml("A = [\"j\"]").is_err());
+    assert!(MacroRecorder::from_toml("a = [\"nosuchkey\"]").is_err());
+    assert!(MacroRecorder::from_toml("a = \"j\"").is_err());
+}
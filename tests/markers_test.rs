@@ -0,0 +1,29 @@
+use lumolog::markers::{builtin_rules, match_rule};
+use lumolog::parser::{LogFormat, parse_line};
+
+fn is_restart(raw: &str) -> bool {
+    match_rule(&builtin_rules(), &parse_line(raw, LogFormat::Plain)).is_some()
+}
+
+#[test]
+fn test_detects_framework_startup_lines() {
+    assert!(is_restart(
+        "2024-01-15 10:00:00 INFO Started Application in 3.21 seconds (JVM running for 4.1)"
+    ));
+    assert!(is_restart("Listening on http://0.0.0.0:8080"));
+    assert!(is_restart("INFO server started on port 3000"));
+}
+
+#[test]
+fn test_detects_systemd_and_signals() {
+    assert!(is_restart(
+        "Jan 15 10:00:00 web1 systemd[1]: Started nginx.service - A high performance web server."
+    ));
+    assert!(is_restart("WARN received SIGTERM, shutting down"));
+}
+
+#[test]
+fn test_ordinary_lines_are_not_markers() {
+    assert!(!is_restart("INFO GET /api/users 200 12ms"));
+    assert!(!is_restart("ERROR failed to start transaction"));
+}
@@ -0,0 +1,58 @@
+use lumolog::metrics::{self, Metrics};
+use lumolog::parser::{LogFormat, parse_line};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+
+fn metric_value(page: &str, name: &str) -> Option<u64> {
+    page.lines()
+        .find_map(|l| l.strip_prefix(name)?.strip_prefix(' ')?.parse().ok())
+}
+
+#[test]
+fn test_metrics_count_lines_and_errors() {
+    let metrics = Metrics::default();
+    let lines: Vec<_> = ["INFO ok", "ERROR broke", "FATAL gone", "no level"]
+        .iter()
+        .map(|l| parse_line(l, LogFormat::Plain))
+        .collect();
+    metrics.observe(&lines);
+    metrics.set_dropped(2);
+
+    let page = metrics.render();
+    assert_eq!(metric_value(&page, "lumolog_lines_ingested_total"), Some(4));
+    assert_eq!(metric_value(&page, "lumolog_error_lines_total"), Some(2));
+    assert_eq!(metric_value(&page, "lumolog_dropped_lines_total"), Some(2));
+    assert!(page.contains("# TYPE lumolog_lines_ingested_total counter"));
+}
+
+fn get(addr: std::net::SocketAddr, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn test_metrics_served_over_http() {
+    let metrics = Arc::new(Metrics::default());
+    metrics.observe(&[parse_line("ERROR x", LogFormat::Plain)]);
+    let addr = metrics::serve("127.0.0.1:0", Arc::clone(&metrics)).unwrap();
+
+    let response = get(addr, "/metrics");
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(response.contains("lumolog_error_lines_total 1"));
+
+    assert!(get(addr, "/").starts_with("HTTP/1.1 404"));
+}
+
+#[test]
+fn test_idle_client_does_not_block_the_next_scrape() {
+    let metrics = Arc::new(Metrics::default());
+    let addr = metrics::serve("127.0.0.1:0", metrics).unwrap();
+
+    // Connects but never sends a request
+    let _idle = TcpStream::connect(addr).unwrap();
+    assert!(get(addr, "/metrics").starts_with("HTTP/1.1 200 OK"));
+}
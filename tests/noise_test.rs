@@ -0,0 +1,67 @@
+use lumolog::noise::{DEFAULT_HIDE, MAX_HIDE, NoisePreview, template_counts};
+use lumolog::parser::{LogFormat, ParsedLine, parse_line};
+
+fn lines(raw: &[&str]) -> Vec<ParsedLine> {
+    raw.iter()
+        .map(|r| parse_line(r, LogFormat::Plain))
+        .collect()
+}
+
+#[test]
+fn test_template_counts_most_lines_first() {
+    let lines = lines(&[
+        "user 1 logged in",
+        "GET /healthz 200 in 3ms",
+        "GET /healthz 200 in 4ms",
+        "user 2 logged in",
+        "GET /healthz 200 in 2ms",
+        "disk full",
+    ]);
+    let all: Vec<usize> = (0..lines.len()).collect();
+    let counts = template_counts(&lines, &all);
+    // Templates seen once aren't noise
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts[0].template, "GET /healthz * in *");
+    assert_eq!((counts[0].count, counts[0].first_line), (3, 1));
+    assert_eq!((counts[1].count, counts[1].first_line), (2, 0));
+
+    // Only the given lines are counted
+    assert_eq!(template_counts(&lines, &[0, 1, 5]), []);
+}
+
+#[test]
+fn test_noise_preview_grows_and_shrinks() {
+    let raw: Vec<String> = (0..30)
+        .flat_map(|i: u8| {
+            let name = format!("{}{}", (b'a' + i / 26) as char, (b'a' + i % 26) as char);
+            (0..=i % 8).map(move |j| format!("event {name} step {j}"))
+        })
+        .collect();
+    let raw: Vec<&str> = raw.iter().map(String::as_str).collect();
+    let lines = lines(&raw);
+    let all: Vec<usize> = (0..lines.len()).collect();
+
+    let mut preview = NoisePreview::new(&lines, &all).unwrap();
+    assert_eq!(preview.lines, lines.len());
+    assert_eq!(preview.templates.len(), MAX_HIDE);
+    assert_eq!(preview.hide, DEFAULT_HIDE);
+    assert_eq!(preview.hidden_lines(), 8 * 3 + 7 * 2);
+
+    preview.fewer();
+    assert_eq!(preview.hidden_lines(), 8 * 3 + 7);
+    for _ in 0..MAX_HIDE {
+        preview.fewer();
+    }
+    assert_eq!(preview.hide, 1);
+    for _ in 0..2 * MAX_HIDE {
+        preview.more();
+    }
+    assert_eq!(preview.hide, MAX_HIDE);
+
+    // The suggestion leaves something to look at
+    let few = self::lines(&["a 1", "a 2", "b 1", "b 2"]);
+    assert_eq!(NoisePreview::new(&few, &[0, 1, 2, 3]).unwrap().hide, 1);
+
+    let unique = self::lines(&["a", "b"]);
+    assert_eq!(NoisePreview::new(&unique, &[0, 1]), None);
+}
@@ -1,4 +1,7 @@
-use lumolog::parser::{LogFormat, LogLevel, compute_template, detect_format, parse_line};
+use lumolog::parser::{
+    LogFormat, LogLevel, compute_template, continues_entry, describe, detect_format, is_blank,
+    is_continuation, is_java_frame, parse_error, parse_line,
+};
 
 // ---------------------------------------------------------------------------
 // LogLevel ordering tests
@@ -70,6 +73,19 @@ fn test_parse_json_line() {
     assert!(parsed.message.contains("Failed to connect"));
 }
 
+#[test]
+fn test_json_lines_keep_their_parsed_object() {
+    let parsed = parse_line(r#"{"level":"info","req":{"ms":42}}"#, LogFormat::Json);
+    assert_eq!(parsed.json.unwrap()["req"]["ms"], 42);
+    // A stray non-JSON line in a JSON log has nothing to keep
+    assert!(parse_line("panic: oops", LogFormat::Json).json.is_none());
+    assert!(
+        parse_line("level=info msg=hi", LogFormat::Logfmt)
+            .json
+            .is_none()
+    );
+}
+
 #[test]
 fn test_parse_plain_line_error() {
     let line = "2024-01-15 08:30:05 ERROR Failed to connect to redis";
@@ -909,6 +925,25 @@ fn test_parse_access_log_combined_dash_referer() {
     );
 }
 
+#[test]
+fn test_parse_access_log_trailing_request_time() {
+    let line = r#"10.0.0.1 - - [10/Oct/2024:13:55:42 -0700] "GET /api/health HTTP/1.1" 200 13 "-" "curl/8.1.2" 0.042"#;
+    let parsed = parse_line(line, LogFormat::AccessLog);
+    assert_eq!(parsed.message, "GET /api/health 200");
+    assert!(
+        parsed
+            .extra_fields
+            .iter()
+            .any(|(k, v)| k == "latency" && v == "0.042")
+    );
+    assert!(
+        parsed
+            .extra_fields
+            .iter()
+            .any(|(k, v)| k == "ua" && v == "curl/8.1.2")
+    );
+}
+
 #[test]
 fn test_access_log_sample_file() {
     let content = std::fs::read_to_string("testdata/sample_apache.log").unwrap();
@@ -932,3 +967,442 @@ fn test_access_log_sample_file() {
     assert!(p5.extra_fields.iter().any(|(k, _)| k == "referer"));
     assert!(p5.extra_fields.iter().any(|(k, _)| k == "ua"));
 }
+
+// ---------------------------------------------------------------------------
+// Nested JSON string decoding
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_json_pretty_decodes_nested_json_string() {
+    let raw =
+        r#"{"level":"info","msg":"req","payload":"{\"user\":{\"id\":42},\"tags\":\"[1,2]\"}"}"#;
+    let parsed = parse_line(raw, LogFormat::Json);
+    let pretty = parsed.pretty_json.unwrap();
+    assert!(!pretty.contains("\\\""), "escaped quotes left in: {pretty}");
+    assert!(pretty.contains("\"id\": 42"));
+    // Doubly-nested strings are unwrapped too
+    assert!(pretty.contains("\"tags\": ["));
+}
+
+#[test]
+fn test_decode_nested_json_leaves_plain_strings() {
+    use lumolog::parser::decode_nested_json;
+    let value = serde_json::json!({"msg": "{not json}", "n": "[1, 2"});
+    assert_eq!(decode_nested_json(value.clone()), value);
+}
+
+// ---------------------------------------------------------------------------
+// Console encoders (logrus, zap, pino-pretty, zerolog)
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_detect_console_zerolog_with_ansi() {
+    let lines = vec![
+        "\x1b[90m12:03:04\x1b[0m \x1b[32mINF\x1b[0m started \x1b[36mport=\x1b[0m8080".to_string(),
+        "\x1b[90m12:03:05\x1b[0m \x1b[31mERR\x1b[0m failed \x1b[36ma=\x1b[0m1 b=2 c=3".to_string(),
+    ];
+    assert_eq!(detect_format(&lines), LogFormat::Console);
+}
+
+#[test]
+fn test_parse_console_logrus() {
+    let line = "\x1b[31mERRO\x1b[0m[0023] Request failed     \x1b[31merror\x1b[0m=\"conn refused\" path=/api";
+    let parsed = parse_line(line, LogFormat::Console);
+    assert_eq!(parsed.level, Some(LogLevel::Error));
+    assert_eq!(parsed.timestamp.as_deref(), Some("0023"));
+    assert_eq!(parsed.message, "Request failed");
+    assert!(!parsed.raw.contains('\x1b'));
+    assert_eq!(
+        parsed.extra_fields,
+        vec![
+            ("error".to_string(), "conn refused".to_string()),
+            ("path".to_string(), "/api".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_console_zap() {
+    let line = "2024-01-15T08:30:00.000Z\tWARN\tserver/handler.go:42\tslow request\t{\"ms\":812}";
+    let parsed = parse_line(line, LogFormat::Console);
+    assert_eq!(parsed.level, Some(LogLevel::Warn));
+    assert_eq!(
+        parsed.timestamp.as_deref(),
+        Some("2024-01-15T08:30:00.000Z")
+    );
+    assert_eq!(parsed.message, "slow request");
+    assert_eq!(
+        parsed.extra_fields,
+        vec![
+            ("caller".to_string(), "server/handler.go:42".to_string()),
+            ("ms".to_string(), "812".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_console_pino_pretty() {
+    let line = "[12:03:04.123] \x1b[32mINFO\x1b[39m (api/4242 on web-1): \x1b[36mlistening\x1b[39m";
+    let parsed = parse_line(line, LogFormat::Console);
+    assert_eq!(parsed.level, Some(LogLevel::Info));
+    assert_eq!(parsed.timestamp.as_deref(), Some("12:03:04.123"));
+    assert_eq!(parsed.message, "listening");
+    assert_eq!(
+        parsed.extra_fields,
+        vec![
+            ("name".to_string(), "api".to_string()),
+            ("pid".to_string(), "4242".to_string()),
+            ("hostname".to_string(), "web-1".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_console_zerolog_keeps_inner_pairs_in_message() {
+    let parsed = parse_line("3:04PM DBG set x=1 then retried n=2", LogFormat::Console);
+    assert_eq!(parsed.level, Some(LogLevel::Debug));
+    assert_eq!(parsed.timestamp.as_deref(), Some("3:04PM"));
+    assert_eq!(parsed.message, "set x=1 then retried");
+    assert_eq!(
+        parsed.extra_fields,
+        vec![("n".to_string(), "2".to_string())]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Serilog / Logstash message templates
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_parse_serilog_json_prefers_rendered_message() {
+    let line = r#"{"Timestamp":"2024-01-15T08:30:00Z","Level":"Warning","MessageTemplate":"User {UserId} failed login","RenderedMessage":"User 42 failed login","Properties":{"UserId":42,"Source":"web"}}"#;
+    let parsed = parse_line(line, LogFormat::Json);
+    assert_eq!(parsed.level, Some(LogLevel::Warn));
+    assert_eq!(parsed.timestamp.as_deref(), Some("2024-01-15T08:30:00Z"));
+    assert_eq!(parsed.message, "User 42 failed login");
+    assert_eq!(parsed.template, "User {UserId} failed login");
+    assert_eq!(
+        parsed.extra_fields,
+        vec![
+            ("Source".to_string(), "\"web\"".to_string()),
+            ("UserId".to_string(), "42".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_clef_renders_template_from_top_level_properties() {
+    let line = r#"{"@t":"2024-01-15T08:30:00Z","@mt":"Processed {Count} items in {Elapsed:0.0} ms","Count":7,"Elapsed":3.5}"#;
+    let parsed = parse_line(line, LogFormat::Json);
+    // CLEF omits @l for Information
+    assert_eq!(parsed.level, Some(LogLevel::Info));
+    assert_eq!(parsed.message, "Processed 7 items in 3.5 ms");
+    assert_eq!(
+        parsed.template,
+        "Processed {Count} items in {Elapsed:0.0} ms"
+    );
+}
+
+#[test]
+fn test_serilog_lines_group_by_message_template() {
+    let a = parse_line(
+        r#"{"@t":"2024-01-15T08:30:00Z","@mt":"Cache {Name} evicted","Name":"users"}"#,
+        LogFormat::Json,
+    );
+    let b = parse_line(
+        r#"{"@t":"2024-01-15T08:30:01Z","@mt":"Cache {Name} evicted","Name":"sessions"}"#,
+        LogFormat::Json,
+    );
+    assert_eq!(a.template, b.template);
+}
+
+#[test]
+fn test_parse_logstash_template_flattens_fields() {
+    let line = r#"{"@timestamp":"2024-01-15T08:30:00Z","level":"Verbose","messageTemplate":"Tick {N}","message":"Tick 3","fields":{"N":3}}"#;
+    let parsed = parse_line(line, LogFormat::Json);
+    assert_eq!(parsed.level, Some(LogLevel::Trace));
+    assert_eq!(parsed.message, "Tick 3");
+    assert_eq!(
+        parsed.extra_fields,
+        vec![("N".to_string(), "3".to_string())]
+    );
+}
+
+#[test]
+fn test_format_names_round_trip() {
+    for format in [
+        LogFormat::Json,
+        LogFormat::Syslog,
+        LogFormat::Logfmt,
+        LogFormat::Klog,
+        LogFormat::Log4j,
+        LogFormat::PythonLog,
+        LogFormat::AccessLog,
+        LogFormat::Console,
+        LogFormat::Plain,
+    ] {
+        assert_eq!(LogFormat::from_name(format.name()), Some(format));
+    }
+}
+
+#[test]
+fn test_describe_lists_parsed_parts() {
+    let parsed = parse_line(
+        r#"{"level":"error","time":"2024-01-15T08:30:01Z","msg":"boom","user":"alice"}"#,
+        LogFormat::Json,
+    );
+    let text = describe(&parsed, 3);
+    assert!(text.starts_with("line 3 (json): {"), "{text}");
+    assert!(text.contains("  level:     ERROR\n"), "{text}");
+    assert!(
+        text.contains("  timestamp: 2024-01-15T08:30:01Z\n"),
+        "{text}"
+    );
+    assert!(text.contains("  message:   boom\n"), "{text}");
+    assert!(text.contains("  field:     user = "), "{text}");
+
+    let plain = describe(&parse_line("just text", LogFormat::Plain), 1);
+    assert!(plain.contains("  level:     -\n"), "{plain}");
+}
+
+#[test]
+fn test_parse_error_explains_lines_the_format_cannot_read() {
+    assert_eq!(parse_error(r#"{"level":"info"}"#, LogFormat::Json), None);
+    assert_eq!(
+        parse_error(r#"{"level":"info","msg":"cut"#, LogFormat::Json).as_deref(),
+        Some("invalid JSON: EOF while parsing a string")
+    );
+    assert_eq!(
+        parse_error("[1, 2]", LogFormat::Json).as_deref(),
+        Some("JSON value is not an object")
+    );
+    assert_eq!(parse_error("   ", LogFormat::Json), None);
+    assert_eq!(
+        parse_error("free text", LogFormat::Logfmt).as_deref(),
+        Some("no key=value pairs")
+    );
+    assert_eq!(
+        parse_error("garbage", LogFormat::AccessLog).as_deref(),
+        Some("doesn't match the access log layout")
+    );
+    assert_eq!(parse_error("anything", LogFormat::Plain), None);
+}
+
+// ---------------------------------------------------------------------------
+// CRI (Kubernetes container runtime) tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_parse_cri_json_payload() {
+    let raw = r#"2024-01-15T08:30:01.123456789Z stderr F {"level":"error","msg":"boom","code":7}"#;
+    let parsed = parse_line(raw, LogFormat::Cri);
+    assert_eq!(parsed.format, LogFormat::Json);
+    assert_eq!(parsed.level, Some(LogLevel::Error));
+    assert_eq!(parsed.message, "boom");
+    assert_eq!(
+        parsed.timestamp.as_deref(),
+        Some("2024-01-15T08:30:01.123456789Z")
+    );
+    assert!(parsed.raw.starts_with('{'));
+    assert!(
+        parsed
+            .extra_fields
+            .contains(&("stream".to_string(), "stderr".to_string()))
+    );
+    assert!(parsed.extra_fields.iter().any(|(k, _)| k == "code"));
+}
+
+#[test]
+fn test_parse_cri_plain_payload_keeps_timestamp_in_front() {
+    let raw = "2024-01-15T08:30:03.4Z stdout F WARN disk almost full";
+    let parsed = parse_line(raw, LogFormat::Cri);
+    assert_eq!(parsed.format, LogFormat::Plain);
+    assert_eq!(parsed.level, Some(LogLevel::Warn));
+    assert_eq!(parsed.raw, "2024-01-15T08:30:03.4Z WARN disk almost full");
+    assert_eq!(parsed.message, "WARN disk almost full");
+    assert_eq!(parsed.timestamp.as_deref(), Some("2024-01-15T08:30:03.4Z"));
+}
+
+#[test]
+fn test_parse_cri_empty_payload() {
+    let parsed = parse_line("2024-01-15T08:30:03Z stdout F", LogFormat::Cri);
+    assert_eq!(parsed.message, "");
+    assert_eq!(
+        parse_error("2024-01-15T08:30:03Z stdout F", LogFormat::Cri),
+        None
+    );
+    assert!(parse_error("not a container line", LogFormat::Cri).is_some());
+}
+
+#[test]
+fn test_detect_cri_before_logfmt() {
+    let lines: Vec<String> = (0..5)
+        .map(|i| format!("2024-01-15T08:30:0{i}Z stdout F level=info msg=tick n={i}"))
+        .collect();
+    assert_eq!(detect_format(&lines), LogFormat::Cri);
+    assert_eq!(LogFormat::from_name("cri"), Some(LogFormat::Cri));
+}
+
+// ---------------------------------------------------------------------------
+// Mixed-format files
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_lines_outside_the_detected_format_get_their_own_parse() {
+    // A panic in a JSON log is plain text, with its level still found
+    let panic = parse_line(
+        "2024-01-15T08:30:02Z panic: runtime error: index out of range",
+        LogFormat::Json,
+    );
+    assert_eq!(panic.format, LogFormat::Plain);
+    assert_eq!(panic.level, Some(LogLevel::Fatal));
+    assert_eq!(panic.timestamp.as_deref(), Some("2024-01-15T08:30:02Z"));
+
+    // logfmt from a sidecar in the same file
+    let logfmt = parse_line("level=warn msg=\"slow query\" took=2s", LogFormat::Json);
+    assert_eq!(logfmt.format, LogFormat::Logfmt);
+    assert_eq!(logfmt.level, Some(LogLevel::Warn));
+    assert_eq!(logfmt.message, "slow query");
+
+    // JSON in a syslog file
+    let json = parse_line(r#"{"level":"error","msg":"boom"}"#, LogFormat::Syslog);
+    assert_eq!(json.format, LogFormat::Json);
+    assert_eq!(json.message, "boom");
+
+    // A plain line in a logfmt file, and a bare JSON value in a JSON file
+    let banner = parse_line("Starting server v1.2 ERROR mode", LogFormat::Logfmt);
+    assert_eq!(banner.format, LogFormat::Plain);
+    assert_eq!(banner.level, Some(LogLevel::Error));
+    assert_eq!(
+        parse_line("[1, 2]", LogFormat::Json).format,
+        LogFormat::Plain
+    );
+    let traceback = parse_line(
+        "  File \"app.py\", line 3, in <module>",
+        LogFormat::PythonLog,
+    );
+    assert_eq!(traceback.format, LogFormat::Plain);
+}
+
+#[test]
+fn test_lines_in_the_detected_format_keep_it() {
+    let klog = parse_line(
+        "E0115 08:30:00.000000 12345 main.go:42] failed",
+        LogFormat::Klog,
+    );
+    assert_eq!(klog.format, LogFormat::Klog);
+    assert_eq!(klog.message, "failed");
+}
+
+#[test]
+fn test_continuation_lines() {
+    let continues = |raw: &str, format| is_continuation(&parse_line(raw, format), format);
+    // Indented frames and cause chains, in any format
+    assert!(continues(
+        "    at com.app.Main.run(Main.java:12)",
+        LogFormat::Plain
+    ));
+    assert!(continues(
+        "\tat com.app.Main.run(Main.java:12)",
+        LogFormat::Log4j
+    ));
+    assert!(continues(
+        "Caused by: java.io.IOException",
+        LogFormat::Plain
+    ));
+    // Lines outside a structured format with no level or time of their own
+    assert!(continues(
+        "the rest of a wrapped message",
+        LogFormat::PythonLog
+    ));
+    assert!(continues("ValueError: bad input", LogFormat::PythonLog));
+    // A traceback is levelled ERROR; whether it joins is up to continues_entry
+    assert!(!continues(
+        "Traceback (most recent call last):",
+        LogFormat::PythonLog
+    ));
+    assert!(!continues("ValueError: bad input", LogFormat::Plain));
+    // Lines of the format, or with their own level, start entries
+    assert!(!continues(
+        "2024-01-15 08:30:00,123 - app - ERROR - failed",
+        LogFormat::PythonLog
+    ));
+    assert!(!continues("panic: runtime error", LogFormat::Json));
+    assert!(!continues("level=info msg=ok took=1s", LogFormat::Json));
+    assert!(!continues("   ", LogFormat::Json));
+}
+
+#[test]
+fn test_java_stack_traces_continue_unindented() {
+    let continues =
+        |raw: &str| is_continuation(&parse_line(raw, LogFormat::Plain), LogFormat::Plain);
+    assert!(is_java_frame("at com.example.Foo.run(Foo.java:42)"));
+    assert!(is_java_frame(
+        "\tat java.base/java.lang.Thread.run(Thread.java:829) ~[na:na]"
+    ));
+    assert!(is_java_frame("at sun.reflect.Invoke.call(Native Method)"));
+    assert!(!is_java_frame("at noon the job ran"));
+    assert!(!is_java_frame(
+        "2024-01-15 at com.example.Foo.run(Foo.java:42)"
+    ));
+
+    assert!(continues("at com.example.Foo.run(Foo.java:42)"));
+    assert!(continues("... 12 more"));
+    assert!(continues("\t... 3 common frames omitted"));
+    assert!(continues("java.lang.IllegalStateException: closed"));
+    assert!(continues("com.example.OrderError"));
+    assert!(!continues("Exception handling is on"));
+    assert!(!continues(
+        "2024-01-15 08:30:00 ERROR java.lang.Exception: boom"
+    ));
+}
+
+#[test]
+fn test_python_traceback_continues_entry() {
+    let lines: Vec<_> = [
+        "started",
+        "Traceback (most recent call last):",
+        "  File \"app.py\", line 3, in <module>",
+        "ValueError: bad",
+        "",
+        "The above exception was the direct cause of the following exception:",
+        "",
+        "Traceback (most recent call last):",
+        "SystemExit: 1",
+        "",
+        "ValueError: not after a frame",
+    ]
+    .iter()
+    .map(|raw| parse_line(raw, LogFormat::Plain))
+    .collect();
+    assert_eq!(lines[1].level, Some(LogLevel::Error));
+    let continues: Vec<bool> = (0..lines.len())
+        .map(|idx| continues_entry(&lines, idx, LogFormat::Plain))
+        .collect();
+    assert_eq!(
+        continues,
+        [
+            false, false, true, true, true, true, true, true, false, false, false
+        ]
+    );
+
+    // After an ERROR line, as logger.exception writes it, the traceback joins it
+    let lines: Vec<_> = ["job failed: ERROR", "Traceback (most recent call last):"]
+        .iter()
+        .map(|raw| parse_line(raw, LogFormat::Plain))
+        .collect();
+    assert!(continues_entry(&lines, 1, LogFormat::Plain));
+}
+
+#[test]
+fn test_blank_lines_have_no_template() {
+    for raw in ["", "   ", "\t \r"] {
+        assert!(is_blank(raw));
+        for format in [LogFormat::Plain, LogFormat::Json, LogFormat::Log4j] {
+            let parsed = parse_line(raw, format);
+            assert_eq!(parsed.template, "", "{raw:?} as {format:?}");
+            assert_eq!(parsed.level, None);
+        }
+    }
+    assert!(!is_blank(" x "));
+}
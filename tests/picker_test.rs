@@ -0,0 +1,153 @@
+use lumolog::config::FuzzyConfig;
+use lumolog::picker::{FilePicker, PickerEntry, is_log_name, scan_dir};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn entry(path: &str, age_secs: u64) -> PickerEntry {
+    PickerEntry {
+        path: PathBuf::from(path),
+        size: 100,
+        modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 - age_secs)),
+        is_log: is_log_name(path.rsplit('/').next().unwrap()),
+        recent: false,
+    }
+}
+
+fn picker(entries: Vec<PickerEntry>) -> FilePicker {
+    FilePicker::new(PathBuf::from("/logs"), entries, FuzzyConfig::default())
+}
+
+fn listed(picker: &FilePicker) -> Vec<String> {
+    picker
+        .matches()
+        .map(|e| e.path.display().to_string())
+        .collect()
+}
+
+#[test]
+fn test_is_log_name() {
+    assert!(is_log_name("app.log"));
+    assert!(is_log_name("NOTES.TXT"));
+    assert!(is_log_name("app.log.1"));
+    assert!(is_log_name("app.log.2026-10-01"));
+    assert!(is_log_name("app.log.2.gz"));
+    assert!(!is_log_name("app.log.xz"));
+    assert!(!is_log_name("Cargo.toml"));
+    assert!(!is_log_name("log"));
+}
+
+#[test]
+fn test_lists_only_logs_until_tab() {
+    let mut p = picker(vec![
+        entry("app.log", 10),
+        entry("README.md", 20),
+        entry("old/app.log.1", 30),
+    ]);
+    assert_eq!(listed(&p), ["app.log", "old/app.log.1"]);
+    assert_eq!(p.candidate_count(), 2);
+
+    p.toggle_show_all();
+    assert!(p.shows_all());
+    assert_eq!(listed(&p), ["app.log", "README.md", "old/app.log.1"]);
+}
+
+#[test]
+fn test_query_filters_fuzzily() {
+    let mut p = picker(vec![
+        entry("api/server.log", 10),
+        entry("worker.log", 20),
+        entry("api/access.log", 30),
+    ]);
+    for c in "srvr".chars() {
+        p.push_char(c);
+    }
+    assert_eq!(listed(&p), ["api/server.log"]);
+
+    p.clear_query();
+    assert_eq!(p.match_count(), 3);
+}
+
+#[test]
+fn test_selection_stays_in_bounds() {
+    let mut p = picker(vec![entry("a.log", 10), entry("b.log", 20)]);
+    assert_eq!(p.selected_path(), Some(PathBuf::from("/logs/a.log")));
+    p.move_selection(5);
+    assert_eq!(p.selected_path(), Some(PathBuf::from("/logs/b.log")));
+    p.move_selection(-5);
+    assert_eq!(p.selected(), 0);
+
+    p.push_char('z');
+    assert_eq!(p.selected_path(), None);
+}
+
+#[test]
+fn test_typing_clears_error() {
+    let mut p = picker(vec![entry("a.log", 10)]);
+    p.set_error("Cannot open a.log: permission denied".to_string());
+    p.move_selection(1);
+    assert!(p.error().is_some());
+    p.push_char('a');
+    assert_eq!(p.error(), None);
+}
+
+#[test]
+fn test_scan_dir_skips_hidden_and_sorts_newest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("nested")).unwrap();
+    std::fs::create_dir(dir.path().join(".git")).unwrap();
+    std::fs::write(dir.path().join("old.log"), "a\n").unwrap();
+    std::fs::write(dir.path().join(".hidden.log"), "a\n").unwrap();
+    std::fs::write(dir.path().join(".git/HEAD.log"), "a\n").unwrap();
+    std::fs::write(dir.path().join("nested/new.log"), "abc\n").unwrap();
+    let old = std::fs::File::options()
+        .write(true)
+        .open(dir.path().join("old.log"))
+        .unwrap();
+    old.set_modified(SystemTime::now() - Duration::from_secs(3600))
+        .unwrap();
+
+    let (entries, truncated) = scan_dir(dir.path());
+    assert!(!truncated);
+    let paths: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+    assert_eq!(
+        paths,
+        [PathBuf::from("nested/new.log"), PathBuf::from("old.log")]
+    );
+    assert_eq!(entries[0].size, 4);
+    assert!(entries.iter().all(|e| e.is_log));
+}
+
+#[test]
+fn test_recent_files_come_first_without_duplicates() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.log"), "a\n").unwrap();
+    std::fs::write(dir.path().join("b.log"), "b\n").unwrap();
+    let elsewhere = tempfile::tempdir().unwrap();
+    let other = elsewhere.path().join("incident.txt");
+    std::fs::write(&other, "c\n").unwrap();
+    let gone = elsewhere.path().join("deleted.log");
+
+    let recent = [other.clone(), dir.path().join("b.log"), gone];
+    let p = FilePicker::scan(dir.path().to_path_buf(), &recent, FuzzyConfig::default());
+    let paths: Vec<PathBuf> = p.matches().map(|e| e.path.clone()).collect();
+    assert_eq!(
+        paths,
+        [other, dir.path().join("b.log"), PathBuf::from("a.log")]
+    );
+    assert!(p.matches().take(2).all(|e| e.recent));
+    assert_eq!(
+        p.selected_path(),
+        Some(elsewhere.path().join("incident.txt"))
+    );
+}
+
+#[test]
+fn test_recent_only_picker() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.log"), "a\n").unwrap();
+    let recent = [dir.path().join("a.log")];
+    let p = FilePicker::recent(PathBuf::from("/elsewhere"), &recent, FuzzyConfig::default());
+    assert!(p.is_recent_only());
+    assert_eq!(p.match_count(), 1);
+    assert_eq!(p.selected_path(), Some(dir.path().join("a.log")));
+}
@@ -56,6 +56,12 @@ fn test_pipeline_detect_apache() {
     assert_eq!(result.format, LogFormat::AccessLog);
 }
 
+#[test]
+fn test_pipeline_detect_console() {
+    let result = pipeline("testdata/sample_console.log");
+    assert_eq!(result.format, LogFormat::Console);
+}
+
 #[test]
 fn test_pipeline_detect_docker() {
     let result = pipeline("testdata/sample_docker.log");
@@ -81,7 +87,7 @@ fn test_pipeline_detect_mixed_as_plain() {
 #[test]
 fn test_json_pipeline_levels() {
     let result = pipeline("testdata/sample_json.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),
         Some(LogLevel::Debug),
         Some(LogLevel::Warn),
@@ -166,7 +172,7 @@ fn test_json_extra_fields_rendered() {
 #[test]
 fn test_plain_pipeline_levels() {
     let result = pipeline("testdata/sample_plain.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),  // INFO  Application starting up
         Some(LogLevel::Debug), // DEBUG Loading configuration
         Some(LogLevel::Info),  // INFO  Connected to database
@@ -274,7 +280,7 @@ fn test_syslog_pattern_highlighting() {
 #[test]
 fn test_logfmt_pipeline_levels() {
     let result = pipeline("testdata/sample_logfmt.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),
         Some(LogLevel::Debug),
         Some(LogLevel::Warn),
@@ -316,7 +322,7 @@ fn test_logfmt_pipeline_messages() {
 #[test]
 fn test_klog_pipeline_levels() {
     let result = pipeline("testdata/sample_klog.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),  // I
         Some(LogLevel::Info),  // I
         Some(LogLevel::Warn),  // W
@@ -346,6 +352,41 @@ fn test_klog_pipeline_colors() {
     }
 }
 
+// ===========================================================================
+// Console (logrus text with colors): full pipeline
+// ===========================================================================
+
+#[test]
+fn test_console_pipeline_levels() {
+    let result = pipeline("testdata/sample_console.log");
+    let expected = [
+        Some(LogLevel::Info),  // INFO
+        Some(LogLevel::Info),  // INFO
+        Some(LogLevel::Warn),  // WARN
+        Some(LogLevel::Debug), // DEBU
+        Some(LogLevel::Error), // ERRO
+        Some(LogLevel::Info),  // INFO
+        Some(LogLevel::Fatal), // FATA
+    ];
+    for (i, exp) in expected.iter().enumerate() {
+        assert_level(&result.parsed[i], *exp, i);
+    }
+    assert_eq!(result.parsed[2].message, "Slow query");
+}
+
+#[test]
+fn test_console_pipeline_colors() {
+    let result = pipeline("testdata/sample_console.log");
+    for (i, (parsed, line)) in result
+        .parsed
+        .iter()
+        .zip(result.highlighted.iter())
+        .enumerate()
+    {
+        assert_level_color(parsed, line, i);
+    }
+}
+
 // ===========================================================================
 // Log4j: full pipeline
 // ===========================================================================
@@ -353,7 +394,7 @@ fn test_klog_pipeline_colors() {
 #[test]
 fn test_log4j_pipeline_levels() {
     let result = pipeline("testdata/sample_log4j.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),  // INFO
         Some(LogLevel::Info),  // INFO
         Some(LogLevel::Debug), // DEBUG
@@ -408,7 +449,7 @@ fn test_log4j_extra_fields() {
 #[test]
 fn test_python_pipeline_levels() {
     let result = pipeline("testdata/sample_python.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),  // INFO
         Some(LogLevel::Debug), // DEBUG
         Some(LogLevel::Info),  // INFO
@@ -457,7 +498,7 @@ fn test_python_module_extra_fields() {
 #[test]
 fn test_apache_pipeline_levels() {
     let result = pipeline("testdata/sample_apache.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),  // 200
         Some(LogLevel::Warn),  // 401
         Some(LogLevel::Info),  // 204
@@ -567,7 +608,7 @@ fn test_docker_plain_message_not_stripped() {
 #[test]
 fn test_pino_pipeline_levels() {
     let result = pipeline("testdata/sample_pino.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),  // 30
         Some(LogLevel::Debug), // 20
         Some(LogLevel::Warn),  // 40
@@ -641,7 +682,7 @@ fn test_pino_timestamps_displayed_in_highlight() {
 #[test]
 fn test_mixed_pipeline_levels() {
     let result = pipeline("testdata/sample_mixed.log");
-    let expected = vec![
+    let expected = [
         Some(LogLevel::Info),  // INFO
         Some(LogLevel::Debug), // DEBUG
         Some(LogLevel::Info),  // INFO
@@ -950,7 +991,7 @@ fn test_stress_detect_plain() {
 #[test]
 fn test_stress_all_levels_detected() {
     let result = pipeline("testdata/sample_stress.log");
-    let expected = vec![
+    let expected = [
         (0, Some(LogLevel::Info)),   // INFO Application v3.12.1
         (1, Some(LogLevel::Debug)),  // DEBUG Loaded 42 config keys
         (2, Some(LogLevel::Info)),   // INFO Connected to postgres
@@ -1571,3 +1612,54 @@ fn test_debug_spans_produces_readable_output() {
         "debug output should mention a gray color for timestamp"
     );
 }
+
+// ===========================================================================
+// Pretty-print expansion for structured formats
+// ===========================================================================
+
+#[test]
+fn test_pretty_logfmt_expands_one_row_per_field() {
+    use lumolog::highlighter::{expanded_line_count, highlight_line_expanded};
+    let result = pipeline_from_lines(&[
+        r#"ts=2024-01-15T08:30:00Z level=error msg="connection refused" host=api-1 duration=3.2s"#,
+        r#"ts=2024-01-15T08:30:01Z level=info msg="retrying" host=api-1 attempt=2"#,
+    ]);
+    assert_eq!(result.format, LogFormat::Logfmt);
+    let parsed = &result.parsed[0];
+    let expanded = highlight_line_expanded(parsed, true);
+    // header + message + host + duration
+    assert_eq!(expanded.len(), 4);
+    assert_eq!(expanded_line_count(parsed, true), expanded.len());
+    assert!(line_text(&expanded[0]).contains("2024-01-15T08:30:00Z"));
+    let message_row = line_text(&expanded[1]);
+    assert!(message_row.contains("message:"));
+    assert!(message_row.contains("connection refused"));
+    // Keys are padded to a common width so values line up
+    let host_row = line_text(&expanded[2]);
+    let duration_row = line_text(&expanded[3]);
+    assert_eq!(host_row.find("api-1"), duration_row.find("3.2s"));
+}
+
+#[test]
+fn test_pretty_plain_stays_single_line() {
+    use lumolog::highlighter::{expanded_line_count, highlight_line_expanded};
+    let result = pipeline_from_lines(&["2024-01-01 00:00:00 ERROR disk full"]);
+    assert_eq!(highlight_line_expanded(&result.parsed[0], true).len(), 1);
+    assert_eq!(expanded_line_count(&result.parsed[0], true), 1);
+}
+
+#[test]
+fn test_pipeline_detect_cri() {
+    let result = pipeline("testdata/sample_cri.log");
+    assert_eq!(result.format, LogFormat::Cri);
+    // Each payload is parsed in its own format, behind the runtime's timestamp
+    assert_eq!(result.parsed[1].format, LogFormat::Json);
+    assert_eq!(result.parsed[2].format, LogFormat::Logfmt);
+    assert_eq!(result.parsed[3].format, LogFormat::Plain);
+    assert_level(&result.parsed[3], Some(LogLevel::Error), 4);
+    assert!(has_text(
+        &result.highlighted[3],
+        "2024-01-15T08:30:03.400000000Z"
+    ));
+    assert!(!has_text(&result.highlighted[3], "stderr F"));
+}
@@ -0,0 +1,61 @@
+use lumolog::parser::{LogFormat, parse_line};
+use lumolog::provenance::{Provenance, is_control_line, parse_control_line};
+
+fn pairs(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+    fields
+        .iter()
+        .map(|&(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_parse_control_line() {
+    assert_eq!(
+        parse_control_line(r#"#lumolog:{"source":"api","port":8080,"tls":true,"zone":null}"#),
+        Some(pairs(&[
+            ("port", "8080"),
+            ("source", "api"),
+            ("tls", "true")
+        ]))
+    );
+    assert_eq!(parse_control_line("#lumolog: {}"), Some(Vec::new()));
+    assert_eq!(parse_control_line(r#"#lumolog:["api"]"#), None);
+    assert_eq!(parse_control_line(r#"#lumolog:{"source":"#), None);
+    assert_eq!(parse_control_line(r#"{"source":"api"}"#), None);
+    assert!(is_control_line(r#"#lumolog:{"host":"web-3"}"#));
+    assert!(!is_control_line("#lumolog:oops"));
+}
+
+#[test]
+fn test_provenance_applies_until_replaced() {
+    let mut provenance = Provenance::default();
+    let mut read = |raw: &str, format| {
+        let mut line = parse_line(raw, format);
+        provenance.apply(&mut line);
+        line.extra_fields
+    };
+    assert!(read("starting up", LogFormat::Plain).is_empty());
+    assert!(
+        read(
+            r#"#lumolog:{"source":"api","host":"web-3"}"#,
+            LogFormat::Plain
+        )
+        .is_empty()
+    );
+    assert_eq!(
+        read("accepted", LogFormat::Plain),
+        pairs(&[("host", "web-3"), ("source", "api")])
+    );
+    // The line's own fields win
+    assert_eq!(
+        read("level=info msg=ok host=web-9", LogFormat::Logfmt),
+        pairs(&[("host", "web-9"), ("source", "api")])
+    );
+    read(r#"#lumolog:{"source":"worker"}"#, LogFormat::Plain);
+    assert_eq!(
+        read("job done", LogFormat::Plain),
+        pairs(&[("source", "worker")])
+    );
+    read("#lumolog:{}", LogFormat::Plain);
+    assert!(read("idle", LogFormat::Plain).is_empty());
+}
@@ -0,0 +1,65 @@
+use lumolog::recent::{MAX_RECENT, RecentFiles, record};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_add_moves_to_front_without_duplicates() {
+    let mut recent = RecentFiles::default();
+    recent.add(Path::new("/var/log/a.log"));
+    recent.add(Path::new("/var/log/b.log"));
+    recent.add(Path::new("/var/log/a.log"));
+    assert_eq!(
+        recent.files(),
+        [
+            PathBuf::from("/var/log/a.log"),
+            PathBuf::from("/var/log/b.log")
+        ]
+    );
+}
+
+#[test]
+fn test_add_drops_oldest_past_limit() {
+    let mut recent = RecentFiles::default();
+    for i in 0..MAX_RECENT + 5 {
+        recent.add(Path::new(&format!("/logs/{i}.log")));
+    }
+    assert_eq!(recent.files().len(), MAX_RECENT);
+    assert_eq!(
+        recent.files()[0],
+        PathBuf::from(format!("/logs/{}.log", MAX_RECENT + 4))
+    );
+}
+
+#[test]
+fn test_text_round_trip_keeps_order() {
+    let recent = RecentFiles::parse("/a.log\n\n/b.log\n/a.log\n");
+    assert_eq!(
+        recent.files(),
+        [PathBuf::from("/a.log"), PathBuf::from("/b.log")]
+    );
+    assert_eq!(RecentFiles::parse(&recent.to_text()), recent);
+}
+
+#[test]
+fn test_record_creates_file_and_keeps_other_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state").join("recent.txt");
+    assert!(RecentFiles::load(&path).files().is_empty());
+
+    record(&path, Path::new("/a.log")).unwrap();
+    record(&path, Path::new("/b.log")).unwrap();
+    assert_eq!(
+        RecentFiles::load(&path).files(),
+        [PathBuf::from("/b.log"), PathBuf::from("/a.log")]
+    );
+}
+
+#[test]
+fn test_existing_skips_deleted_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let kept = dir.path().join("kept.log");
+    std::fs::write(&kept, "x\n").unwrap();
+    let mut recent = RecentFiles::default();
+    recent.add(&kept);
+    recent.add(&dir.path().join("gone.log"));
+    assert_eq!(recent.existing(), [kept]);
+}
@@ -0,0 +1,159 @@
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use lumolog::replay::{
+    Header, Player, Recorder, Recording, encode, key_name, parse_key, screen_text,
+};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use std::time::Duration;
+
+#[test]
+fn test_key_names_round_trip() {
+    for (name, code, modifiers) in [
+        ("j", KeyCode::Char('j'), KeyModifiers::NONE),
+        ("shift+G", KeyCode::Char('G'), KeyModifiers::SHIFT),
+        ("ctrl+c", KeyCode::Char('c'), KeyModifiers::CONTROL),
+        ("+", KeyCode::Char('+'), KeyModifiers::NONE),
+        ("ctrl++", KeyCode::Char('+'), KeyModifiers::CONTROL),
+        ("space", KeyCode::Char(' '), KeyModifiers::NONE),
+        ("enter", KeyCode::Enter, KeyModifiers::NONE),
+        (
+            "ctrl+alt+pagedown",
+            KeyCode::PageDown,
+            KeyModifiers::CONTROL | KeyModifiers::ALT,
+        ),
+        ("f5", KeyCode::F(5), KeyModifiers::NONE),
+        ("f", KeyCode::Char('f'), KeyModifiers::NONE),
+    ] {
+        let key = KeyEvent::new(code, modifiers);
+        assert_eq!(key_name(&key).as_deref(), Some(name));
+        assert_eq!(parse_key(name), Some(key), "{name}");
+    }
+    assert_eq!(parse_key("hyper+x"), None);
+    assert_eq!(parse_key("enterr"), None);
+}
+
+#[test]
+fn test_recording_parses_events_in_order() {
+    let text = r#"{"file":"app.log","width":120,"height":40}
+{"t":0,"key":"/"}
+{"t":40,"paste":"timeout"}
+
+{"t":90,"mouse":"down-left","x":14,"y":6}
+{"t":120,"mouse":"ctrl+scroll-down","x":1,"y":2}
+"#;
+    let recording = Recording::parse(text).unwrap();
+    assert_eq!(
+        recording.header,
+        Header {
+            file: Some("app.log".into()),
+            width: 120,
+            height: 40
+        }
+    );
+    let times: Vec<u64> = recording.events.iter().map(|e| e.at_ms).collect();
+    assert_eq!(times, [0, 40, 90, 120]);
+    assert_eq!(
+        recording.events[1].event,
+        Event::Paste("timeout".to_string())
+    );
+    assert_eq!(
+        recording.events[3].event,
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::ScrollDown,
+            column: 1,
+            row: 2,
+            modifiers: KeyModifiers::CONTROL,
+        })
+    );
+}
+
+#[test]
+fn test_encoded_events_parse_back() {
+    let events = [
+        Event::Key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Drag(MouseButton::Left),
+            column: 30,
+            row: 1,
+            modifiers: KeyModifiers::NONE,
+        }),
+        Event::Paste("level=error \"quoted\"".to_string()),
+    ];
+    let mut text = String::from("{\"width\":80,\"height\":24}\n");
+    for (i, event) in events.iter().enumerate() {
+        text.push_str(&encode(i as u64 * 10, event).unwrap());
+        text.push('\n');
+    }
+    let recording = Recording::parse(&text).unwrap();
+    assert_eq!(recording.header.file, None);
+    let parsed: Vec<Event> = recording.events.into_iter().map(|e| e.event).collect();
+    assert_eq!(parsed, events);
+}
+
+#[test]
+fn test_ignored_events_are_not_recorded() {
+    assert_eq!(encode(0, &Event::FocusGained), None);
+    assert_eq!(encode(0, &Event::Resize(80, 24)), None);
+    let moved = MouseEvent {
+        kind: MouseEventKind::Moved,
+        column: 0,
+        row: 0,
+        modifiers: KeyModifiers::NONE,
+    };
+    assert_eq!(encode(0, &Event::Mouse(moved)), None);
+}
+
+#[test]
+fn test_bad_lines_report_their_number() {
+    let err = Recording::parse(
+        "{\"width\":80,\"height\":24}\n{\"t\":0,\"key\":\"j\"}\n{\"t\":5,\"key\":\"nope\"}\n",
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("line 3"), "{err}");
+    assert!(err.to_string().contains("nope"), "{err}");
+    let err = Recording::parse("{\"width\":80,\"height\":24}\n{\"t\":0}\n").unwrap_err();
+    assert!(err.to_string().contains("line 2"), "{err}");
+    assert!(Recording::parse("").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_recorder_rejects_non_utf8_file_name() {
+    use std::os::unix::ffi::OsStrExt;
+    let dir = tempfile::tempdir().unwrap();
+    let mut recorder = Recorder::create(&dir.path().join("session.jsonl")).unwrap();
+    let header = Header {
+        file: Some(std::ffi::OsStr::from_bytes(b"app-\xff.log").into()),
+        width: 80,
+        height: 24,
+    };
+    assert!(recorder.start(&header).is_err());
+}
+
+#[test]
+fn test_player_keeps_timing() {
+    let recording = Recording::parse(
+        "{\"width\":80,\"height\":24}\n{\"t\":0,\"key\":\"a\"}\n{\"t\":60,\"key\":\"b\"}\n",
+    )
+    .unwrap();
+    let mut player = Player::new(recording.events);
+    let key = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+    assert_eq!(player.next(Duration::from_millis(5)), Some(key('a')));
+    // Not due within a few milliseconds
+    assert_eq!(player.next(Duration::from_millis(5)), None);
+    assert!(!player.is_finished());
+    assert_eq!(player.next(Duration::from_millis(200)), Some(key('b')));
+    assert!(player.is_finished());
+    assert_eq!(player.next(Duration::from_millis(5)), None);
+}
+
+#[test]
+fn test_screen_text_trims_rows() {
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+    buffer.set_string(0, 0, "ab", Style::default());
+    buffer.set_string(2, 1, "cd", Style::default());
+    assert_eq!(screen_text(&buffer), "ab\n  cd\n");
+}
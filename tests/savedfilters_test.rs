@@ -0,0 +1,95 @@
+use chrono::NaiveDate;
+use lumolog::parser::LogLevel;
+use lumolog::savedfilters::{SavedFilter, SavedFilters, forget, record};
+use lumolog::timeindex::TimeRange;
+
+fn saved(name: &str, filter: &str) -> SavedFilter {
+    SavedFilter {
+        name: name.to_string(),
+        filter: filter.to_string(),
+        filters: Vec::new(),
+        min_level: None,
+        hidden_levels: Vec::new(),
+        time_range: None,
+    }
+}
+
+#[test]
+fn test_toml_round_trip() {
+    let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    let mut filters = SavedFilters::default();
+    filters.insert(SavedFilter {
+        name: "api errors".to_string(),
+        filter: "timeout".to_string(),
+        filters: vec!["api".to_string()],
+        min_level: Some(LogLevel::Error),
+        hidden_levels: vec![LogLevel::Fatal],
+        time_range: Some(TimeRange {
+            start: day.and_hms_opt(8, 0, 0).unwrap(),
+            end: day.and_hms_opt(9, 0, 0).unwrap(),
+        }),
+    });
+    filters.insert(saved("slow", "duration>500ms"));
+    let text = filters.to_toml().unwrap();
+    assert!(text.contains("[[filter]]"), "{text}");
+    assert_eq!(SavedFilters::from_toml(&text).unwrap(), filters);
+}
+
+#[test]
+fn test_hand_written_file_needs_only_a_name() {
+    let filters =
+        SavedFilters::from_toml("[[filter]]\nname = \"warnings\"\nmin_level = \"WARN\"\n").unwrap();
+    assert_eq!(filters.len(), 1);
+    assert_eq!(filters.filters()[0].min_level, Some(LogLevel::Warn));
+    assert_eq!(filters.filters()[0].filter, "");
+    assert!(SavedFilters::from_toml("[[filter]]\nname = \"x\"\ncolour = 1\n").is_err());
+}
+
+#[test]
+fn test_insert_replaces_same_name_in_place() {
+    let mut filters = SavedFilters::default();
+    filters.insert(saved("a", "one"));
+    filters.insert(saved("b", "two"));
+    filters.insert(saved("a", "three"));
+    let names: Vec<_> = filters.filters().iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["a", "b"]);
+    assert_eq!(filters.filters()[0].filter, "three");
+    assert!(filters.remove("a"));
+    assert!(!filters.remove("a"));
+    assert_eq!(filters.len(), 1);
+}
+
+#[test]
+fn test_summary_lists_each_part() {
+    let mut filter = saved("x", "timeout");
+    filter.filters = vec!["api".to_string()];
+    filter.min_level = Some(LogLevel::Warn);
+    assert_eq!(filter.summary(), "[api] \"timeout\" WRN+");
+    filter.hidden_levels = vec![LogLevel::Error];
+    assert_eq!(filter.summary(), "[api] \"timeout\" WRN+ -ERR");
+}
+
+#[test]
+fn test_record_and_forget_keep_other_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config").join("filters.toml");
+    assert!(SavedFilters::load(&path).unwrap().is_empty());
+
+    record(&path, saved("a", "one")).unwrap();
+    let written = record(&path, saved("b", "two")).unwrap();
+    assert_eq!(SavedFilters::load(&path).unwrap(), written);
+    assert_eq!(written.len(), 2);
+
+    let left = forget(&path, "a").unwrap();
+    assert_eq!(left.filters(), [saved("b", "two")]);
+    assert_eq!(SavedFilters::load(&path).unwrap(), left);
+}
+
+#[test]
+fn test_load_reports_bad_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("filters.toml");
+    std::fs::write(&path, "filter = 3\n").unwrap();
+    let err = SavedFilters::load(&path).unwrap_err().to_string();
+    assert!(err.contains("invalid saved filters"), "{err}");
+}
@@ -0,0 +1,19 @@
+#![cfg(unix)]
+
+use lumolog::signals::Signals;
+use signal_hook::consts::{SIGHUP, SIGTERM};
+use signal_hook::low_level::raise;
+
+#[test]
+fn test_signals_set_flags_once() {
+    let signals = Signals::install().unwrap();
+    assert!(!signals.take_reload());
+
+    raise(SIGHUP).unwrap();
+    assert!(signals.take_reload());
+    assert!(!signals.take_reload());
+    assert!(!signals.take_quit());
+
+    raise(SIGTERM).unwrap();
+    assert!(signals.take_quit());
+}
@@ -1,7 +1,7 @@
 use std::io::Write;
 use tempfile::NamedTempFile;
 
-use lumolog::source::FileSource;
+use lumolog::source::{FileSource, FollowableSource, is_compressed, rotated_siblings};
 
 #[test]
 fn test_file_source_reads_lines() {
@@ -113,3 +113,145 @@ fn test_followable_stdin_streams_from_pipe() {
     let _ = src.read_new_lines(); // drain + detect disconnect
     assert!(src.is_closed());
 }
+
+#[cfg(unix)]
+fn make_fifo(dir: &std::path::Path) -> std::path::PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    let path = dir.join("pipe");
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+    path
+}
+
+#[cfg(unix)]
+#[test]
+fn test_file_source_reads_fifo() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = make_fifo(dir.path());
+    let writer_path = path.clone();
+    let writer = std::thread::spawn(move || {
+        let mut fifo = std::fs::OpenOptions::new()
+            .write(true)
+            .open(writer_path)
+            .unwrap();
+        for i in 0..3 {
+            writeln!(fifo, "INFO from pipe {}", i).unwrap();
+        }
+    });
+    assert!(lumolog::source::is_stream(&path));
+    let source = FileSource::open(&path).unwrap();
+    writer.join().unwrap();
+    assert_eq!(source.line_count(), 3);
+    assert_eq!(source.lines()[2], "INFO from pipe 2");
+}
+
+#[test]
+fn test_regular_file_is_not_stream() {
+    assert!(!lumolog::source::is_stream("testdata/sample_plain.log"));
+    assert!(!lumolog::source::is_stream("nonexistent.log"));
+}
+
+#[test]
+fn test_followable_stdin_skips_invalid_utf8_lines() {
+    let input: &[u8] = b"good 1\nbad \xff\xfe\ngood 2\n";
+    let mut src = FollowableStdinSource::from_reader(Cursor::new(input));
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(src.read_new_lines(), vec!["good 1", "good 2"]);
+    assert_eq!(src.dropped_lines(), 1);
+}
+
+#[test]
+fn test_followable_source_holds_back_unfinished_line() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "first\nsecond half-").unwrap();
+    file.flush().unwrap();
+
+    let (initial, offset) = FileSource::open_for_follow(file.path()).unwrap();
+    assert_eq!(initial.lines(), &["first"]);
+    assert_eq!(offset, 6);
+
+    let mut source = FollowableSource::new(file.path(), offset);
+    assert!(source.read_new_lines().unwrap().is_empty());
+
+    // The rest of the line, and a multi-byte character split across writes
+    file.write_all(b"written\nthird \xc3").unwrap();
+    file.flush().unwrap();
+    assert_eq!(
+        source.read_new_lines().unwrap(),
+        vec!["second half-written"]
+    );
+    file.write_all(b"\xa9\n").unwrap();
+    file.flush().unwrap();
+    assert_eq!(source.read_new_lines().unwrap(), vec!["third é"]);
+}
+
+#[test]
+fn test_follow_continues_exactly_where_initial_read_stopped() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(file, "a\nb\n").unwrap();
+    file.flush().unwrap();
+    let (initial, offset) = FileSource::open_for_follow(file.path()).unwrap();
+    assert_eq!(offset, 4);
+
+    // Written after the initial read but before following starts
+    write!(file, "c\nd\n").unwrap();
+    file.flush().unwrap();
+    let mut source = FollowableSource::new(file.path(), offset);
+    let mut all = initial.lines().to_vec();
+    all.extend(source.read_new_lines().unwrap());
+    assert_eq!(all, vec!["a", "b", "c", "d"]);
+}
+
+fn write_gz(path: &std::path::Path, text: &str) {
+    let mut encoder = flate2::write::GzEncoder::new(
+        std::fs::File::create(path).unwrap(),
+        flate2::Compression::default(),
+    );
+    encoder.write_all(text.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+}
+
+#[test]
+fn test_gzip_file_is_decompressed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log.2.gz");
+    write_gz(&path, "INFO first\nERROR second\n");
+    assert!(is_compressed(&path));
+
+    let source = FileSource::open(&path).unwrap();
+    assert_eq!(source.lines(), &["INFO first", "ERROR second"]);
+    assert!(FileSource::open_for_follow(&path).is_err());
+}
+
+#[test]
+fn test_rotated_siblings_oldest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in [
+        "app.log",
+        "app.log.1",
+        "app.log.2.gz",
+        "app.log.10.gz",
+        "app.log-20261014.gz",
+        "app.log-20261015",
+        "app.log.bak",
+        "app.log.1.tmp",
+        "other.log.1",
+    ] {
+        std::fs::write(dir.path().join(name), "x\n").unwrap();
+    }
+    let names: Vec<String> = rotated_siblings(dir.path().join("app.log"))
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "app.log-20261014.gz",
+            "app.log-20261015",
+            "app.log.10.gz",
+            "app.log.2.gz",
+            "app.log.1",
+        ]
+    );
+    assert!(rotated_siblings(dir.path().join("other.log.1")).is_empty());
+}
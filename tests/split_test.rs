@@ -0,0 +1,29 @@
+use lumolog::split::{Multiplexer, expand, shell_quote};
+
+#[test]
+fn test_expand_quotes_token() {
+    assert_eq!(
+        expand("kubectl logs -f {}", "api-7f9c"),
+        "kubectl logs -f 'api-7f9c'"
+    );
+}
+
+#[test]
+fn test_shell_quote_escapes_single_quotes_and_metacharacters() {
+    assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    assert_eq!(shell_quote("a; rm -rf ~"), "'a; rm -rf ~'");
+}
+
+#[test]
+fn test_split_command_args() {
+    let cmd = Multiplexer::Tmux.split_command("top");
+    assert_eq!(cmd.get_program(), "tmux");
+    let args: Vec<_> = cmd.get_args().collect();
+    assert_eq!(args, ["split-window", "-h", "top"]);
+
+    let cmd = Multiplexer::Wezterm.split_command("top");
+    assert_eq!(cmd.get_program(), "wezterm");
+    let args: Vec<_> = cmd.get_args().collect();
+    assert_eq!(args.last().unwrap(), &"top");
+    assert!(args.contains(&std::ffi::OsStr::new("split-pane")));
+}
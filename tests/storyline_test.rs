@@ -0,0 +1,86 @@
+use lumolog::parser::{LogFormat, LogLevel, parse_line};
+use lumolog::storyline::{StoryRow, build_storyline, group_thousands, render_storyline};
+
+fn parse(lines: &[&str]) -> Vec<lumolog::parser::ParsedLine> {
+    lines
+        .iter()
+        .map(|l| parse_line(l, LogFormat::Plain))
+        .collect()
+}
+
+#[test]
+fn test_runs_collapse_and_errors_stay() {
+    let lines = parse(&[
+        "INFO a",
+        "INFO b",
+        "INFO c",
+        "ERROR db down",
+        "WARN retrying",
+        "INFO d",
+        "FATAL giving up",
+    ]);
+    let all: Vec<usize> = (0..lines.len()).collect();
+    let rows = build_storyline(&lines, &all);
+    assert_eq!(
+        rows,
+        vec![
+            StoryRow::Run {
+                level: Some(LogLevel::Info),
+                count: 3,
+                first_line: 0
+            },
+            StoryRow::Event { idx: 3, repeats: 0 },
+            StoryRow::Run {
+                level: Some(LogLevel::Warn),
+                count: 1,
+                first_line: 4
+            },
+            StoryRow::Run {
+                level: Some(LogLevel::Info),
+                count: 1,
+                first_line: 5
+            },
+            StoryRow::Event { idx: 6, repeats: 0 },
+        ]
+    );
+    assert_eq!(
+        render_storyline(&rows, &lines),
+        "… 3 info …\nline 4 ERR ERROR db down\n… 1 warn …\n… 1 info …\nline 7 FTL FATAL giving up\n"
+    );
+}
+
+#[test]
+fn test_repeated_errors_fold() {
+    let lines = parse(&[
+        "ERROR timeout after 30s",
+        "ERROR timeout after 31s",
+        "ERROR timeout after 29s",
+        "ERROR disk full",
+    ]);
+    let rows = build_storyline(&lines, &[0, 1, 2, 3]);
+    assert_eq!(
+        rows,
+        vec![
+            StoryRow::Event { idx: 0, repeats: 2 },
+            StoryRow::Event { idx: 3, repeats: 0 },
+        ]
+    );
+    assert!(
+        render_storyline(&rows, &lines).starts_with("line 1 ERR ERROR timeout after 30s (×3)\n")
+    );
+}
+
+#[test]
+fn test_only_given_indices_count() {
+    let lines = parse(&["INFO a", "INFO b", "ERROR x", "INFO c"]);
+    let rows = build_storyline(&lines, &[1, 2]);
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].target_line(), 1);
+}
+
+#[test]
+fn test_group_thousands() {
+    assert_eq!(group_thousands(7), "7");
+    assert_eq!(group_thousands(4812), "4,812");
+    assert_eq!(group_thousands(1234567), "1,234,567");
+}
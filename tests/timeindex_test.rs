@@ -1,7 +1,9 @@
+use lumolog::parser::LogLevel;
 use lumolog::parser::{detect_format, parse_line};
 use lumolog::timeindex::{
-    bucket_range_to_time_range, build_time_index, compute_sparkline, filter_by_time_range,
-    parse_timestamp,
+    TimestampParser, bucket_level_counts, bucket_range_to_time_range, build_time_index,
+    build_time_index_as_of, compute_sparkline, filter_by_time_range, parse_timestamp,
+    parse_user_time,
 };
 
 fn make_parsed_lines(raw: &[&str]) -> Vec<lumolog::parser::ParsedLine> {
@@ -93,6 +95,105 @@ fn test_parse_returns_none_for_garbage() {
     assert!(parse_timestamp("hello world 123").is_none());
 }
 
+#[test]
+fn test_parser_handles_layouts_alternating_line_by_line() {
+    let mut parser = TimestampParser::default();
+    for _ in 0..3 {
+        assert_eq!(
+            parser.parse("2024-01-15T08:30:01Z").unwrap().to_string(),
+            "2024-01-15 08:30:01"
+        );
+        assert_eq!(
+            parser.parse("1705307400").unwrap().to_string(),
+            "2024-01-15 08:30:00"
+        );
+        assert_eq!(
+            parser
+                .parse("Jan 15 08:30:02")
+                .unwrap()
+                .format("%m-%d %H:%M:%S")
+                .to_string(),
+            "01-15 08:30:02"
+        );
+    }
+}
+
+#[test]
+fn test_parser_does_not_let_a_remembered_layout_truncate_later_lines() {
+    let mut parser = TimestampParser::default();
+    parser.parse("2024-01-15T08:30:01").unwrap();
+    // The remembered layout matches only the start of these
+    let dt = parser.parse("2024-01-15T08:30:01+05:30").unwrap();
+    assert_eq!(dt.to_string(), "2024-01-15 03:00:01");
+    let dt = parser.parse("2024-01-15T08:30:01.250").unwrap();
+    assert_eq!(dt.format("%H:%M:%S%.3f").to_string(), "08:30:01.250");
+}
+
+#[test]
+fn test_time_index_append_keeps_mixed_layouts() {
+    let lines = make_parsed_lines(&[
+        r#"{"timestamp":"2024-01-15T08:30:00Z","level":"info","msg":"a"}"#,
+        r#"{"timestamp":"1705307460","level":"info","msg":"b"}"#,
+    ]);
+    let mut index = build_time_index(&lines);
+    let more = make_parsed_lines(&[
+        r#"{"timestamp":"2024-01-15T08:32:00Z","level":"info","msg":"c"}"#,
+        r#"{"timestamp":"1705307580","level":"info","msg":"d"}"#,
+    ]);
+    index.append(&more);
+    let times: Vec<String> = (0..4)
+        .map(|i| index.timestamp_at(i).unwrap().format("%H:%M").to_string())
+        .collect();
+    assert_eq!(times, vec!["08:30", "08:31", "08:32", "08:33"]);
+}
+
+fn date_at(index: &lumolog::timeindex::TimeIndex, idx: usize) -> String {
+    index
+        .timestamp_at(idx)
+        .unwrap()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+fn reference(text: &str) -> chrono::NaiveDateTime {
+    chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+#[test]
+fn test_syslog_years_across_new_year() {
+    let lines = make_parsed_lines(&[
+        "Dec 30 23:00:00 host app[1]: a",
+        "Dec 31 23:59:59 host app[1]: b",
+        "Jan  1 00:00:01 host app[1]: c",
+        "Jan  2 10:00:00 host app[1]: d",
+    ]);
+    let index = build_time_index_as_of(&lines, reference("2026-01-02 12:00:00"));
+    let dates: Vec<String> = (0..4).map(|i| date_at(&index, i)).collect();
+    assert_eq!(
+        dates,
+        vec!["2025-12-30", "2025-12-31", "2026-01-01", "2026-01-02"]
+    );
+    assert!(index.min_ts.unwrap() < index.max_ts.unwrap());
+}
+
+#[test]
+fn test_syslog_year_is_not_in_the_future() {
+    let lines = make_parsed_lines(&["Dec 31 23:59:59 host app[1]: a"]);
+    let index = build_time_index_as_of(&lines, reference("2026-01-05 09:00:00"));
+    assert_eq!(date_at(&index, 0), "2025-12-31");
+}
+
+#[test]
+fn test_klog_year_rolls_over_when_appending() {
+    let lines = make_parsed_lines(&["I1231 23:59:59.000000    1 main.go:1] a"]);
+    let mut index = build_time_index_as_of(&lines, reference("2025-12-31 23:59:59"));
+    index.append(&make_parsed_lines(&[
+        "I0101 00:00:01.000000    1 main.go:1] b",
+    ]));
+    assert_eq!(date_at(&index, 0), "2025-12-31");
+    assert_eq!(date_at(&index, 1), "2026-01-01");
+}
+
 // --- Time index building tests ---
 
 #[test]
@@ -157,6 +258,32 @@ fn test_sparkline_bucket_counts_sum_to_total() {
     assert_eq!(sparkline.num_buckets, 10);
 }
 
+#[test]
+fn test_bucket_level_counts_most_severe_first() {
+    let lines = make_parsed_lines(&[
+        r#"{"timestamp":"2024-01-15T08:30:01Z","level":"info","message":"a"}"#,
+        r#"{"timestamp":"2024-01-15T08:30:02Z","level":"error","message":"b"}"#,
+        r#"{"timestamp":"2024-01-15T08:30:03Z","level":"info","message":"c"}"#,
+        r#"{"timestamp":"2024-01-15T08:30:04Z","message":"d"}"#,
+        r#"{"timestamp":"2024-01-15T08:39:00Z","level":"warn","message":"e"}"#,
+    ]);
+    let index = build_time_index(&lines);
+    let sparkline = compute_sparkline(&index, 10).unwrap();
+    assert_eq!(
+        bucket_level_counts(&index, &sparkline, &lines, 0),
+        vec![
+            (Some(LogLevel::Error), 1),
+            (Some(LogLevel::Info), 2),
+            (None, 1)
+        ]
+    );
+    assert_eq!(
+        bucket_level_counts(&index, &sparkline, &lines, 9),
+        vec![(Some(LogLevel::Warn), 1)]
+    );
+    assert!(bucket_level_counts(&index, &sparkline, &lines, 5).is_empty());
+}
+
 #[test]
 fn test_sparkline_single_bucket() {
     let lines = make_parsed_lines(&[
@@ -197,6 +324,40 @@ fn test_filter_by_time_range() {
     assert_eq!(filtered, vec![1]);
 }
 
+#[test]
+fn test_inherited_timestamps_can_be_left_out_of_time_filters() {
+    let lines = make_parsed_lines(&[
+        "2024-01-15 08:00:00 ERROR [main] com.app.Service - failed",
+        "    at com.app.Service.run(Service.java:42)",
+        "2024-01-15 10:00:00 INFO [main] com.app.Service - recovered",
+    ]);
+    let mut index = build_time_index(&lines);
+    assert!(!index.is_inherited(0));
+    assert!(index.is_inherited(1));
+    assert_eq!(index.timestamp_at(1), index.timestamp_at(0));
+
+    let range = lumolog::timeindex::TimeRange {
+        start: index.timestamp_at(0).unwrap(),
+        end: index.timestamp_at(0).unwrap(),
+    };
+    let all: Vec<usize> = (0..3).collect();
+    assert_eq!(filter_by_time_range(&index, &range, &all), vec![0, 1]);
+    let total = |index: &lumolog::timeindex::TimeIndex| {
+        compute_sparkline(index, 4)
+            .unwrap()
+            .buckets
+            .iter()
+            .sum::<u64>()
+    };
+    assert_eq!(total(&index), 3);
+
+    index.set_include_inherited(false);
+    assert_eq!(filter_by_time_range(&index, &range, &all), vec![0]);
+    assert_eq!(total(&index), 2);
+    // The line still shows the inherited time
+    assert!(index.timestamp_at(1).is_some());
+}
+
 #[test]
 fn test_bucket_range_to_time_range() {
     let lines = make_parsed_lines(&[
@@ -248,3 +409,20 @@ fn test_build_from_sample_json_log() {
     let total: u64 = sparkline.buckets.iter().sum();
     assert_eq!(total as usize, parsed.len());
 }
+
+#[test]
+fn test_parse_user_time_accepts_minutes_and_dates() {
+    let expect = |text: &str, want: &str| {
+        assert_eq!(
+            parse_user_time(text).map(|t| t.to_string()).as_deref(),
+            Some(want),
+            "{text}"
+        );
+    };
+    expect("2024-01-15T08:30", "2024-01-15 08:30:00");
+    expect("2024-01-15 08:30", "2024-01-15 08:30:00");
+    expect("2024-01-15T08:30:05Z", "2024-01-15 08:30:05");
+    expect(" 2024-01-15 ", "2024-01-15 00:00:00");
+    assert_eq!(parse_user_time("08:30"), None);
+    assert_eq!(parse_user_time("soon"), None);
+}
@@ -0,0 +1,128 @@
+use lumolog::parser::{LogFormat, parse_line};
+use lumolog::timeindex::build_time_index;
+use lumolog::traces::{TraceRow, build_trace_rows, format_duration};
+
+fn parse(lines: &[&str]) -> Vec<lumolog::parser::ParsedLine> {
+    lines
+        .iter()
+        .map(|l| parse_line(l, LogFormat::Json))
+        .collect()
+}
+
+fn shape(rows: &[TraceRow]) -> Vec<String> {
+    rows.iter()
+        .map(|row| match row {
+            TraceRow::Trace {
+                trace_id, lines, ..
+            } => format!("trace {trace_id} {lines}"),
+            TraceRow::Span { span_id, depth, .. } => {
+                format!("{}span {span_id}", "  ".repeat(*depth))
+            }
+            TraceRow::Line { idx, depth } => format!("{}{idx}", "  ".repeat(*depth)),
+        })
+        .collect()
+}
+
+#[test]
+fn test_spans_nest_under_parents() {
+    let lines = parse(&[
+        r#"{"msg":"request in","trace_id":"t1","span_id":"a"}"#,
+        r#"{"msg":"unrelated"}"#,
+        r#"{"msg":"db query","trace_id":"t1","span_id":"b","parent_span_id":"a"}"#,
+        r#"{"msg":"other request","trace_id":"t2","span_id":"c"}"#,
+        r#"{"msg":"cache miss","trace_id":"t1","span_id":"d","parent_span_id":"b"}"#,
+        r#"{"msg":"request out","trace_id":"t1","span_id":"a"}"#,
+    ]);
+    let rows = build_trace_rows(&lines, None);
+    assert_eq!(
+        shape(&rows),
+        vec![
+            "trace t1 4",
+            "  span a",
+            "    0",
+            "    5",
+            "    span b",
+            "      2",
+            "      span d",
+            "        4",
+            "trace t2 1",
+            "  span c",
+            "    3",
+        ]
+    );
+    assert_eq!(rows[0].target_line(), 0);
+    assert_eq!(rows[6].target_line(), 4);
+}
+
+#[test]
+fn test_orphan_and_spanless_lines() {
+    // Parent "zz" never logs; a line with only a trace id sits under the trace
+    let lines = parse(&[
+        r#"{"msg":"x","traceId":"t1","spanId":"b","parentSpanId":"zz"}"#,
+        r#"{"msg":"y","traceId":"t1"}"#,
+    ]);
+    let rows = build_trace_rows(&lines, None);
+    assert_eq!(shape(&rows), vec!["trace t1 2", "  1", "  span b", "    0"]);
+}
+
+#[test]
+fn test_parent_cycle_still_shows_every_span() {
+    let lines = parse(&[
+        r#"{"msg":"x","trace_id":"t","span_id":"a","parent_span_id":"b"}"#,
+        r#"{"msg":"y","trace_id":"t","span_id":"b","parent_span_id":"a"}"#,
+    ]);
+    let rows = build_trace_rows(&lines, None);
+    let spans = rows
+        .iter()
+        .filter(|r| matches!(r, TraceRow::Span { .. }))
+        .count();
+    assert_eq!(spans, 2);
+}
+
+#[test]
+fn test_span_duration_first_to_last_log() {
+    let lines = parse(&[
+        r#"{"time":"2024-01-15T08:30:00.000Z","msg":"in","trace_id":"t","span_id":"a"}"#,
+        r#"{"time":"2024-01-15T08:30:00.250Z","msg":"mid","trace_id":"t","span_id":"b","parent_span_id":"a"}"#,
+        r#"{"time":"2024-01-15T08:30:01.500Z","msg":"out","trace_id":"t","span_id":"a"}"#,
+    ]);
+    let index = build_time_index(&lines);
+    let rows = build_trace_rows(&lines, Some(&index));
+    let durations: Vec<_> = rows
+        .iter()
+        .filter_map(|r| match r {
+            TraceRow::Trace { duration, .. } | TraceRow::Span { duration, .. } => {
+                Some(duration.map(format_duration))
+            }
+            TraceRow::Line { .. } => None,
+        })
+        .collect();
+    // A span with a single line has no duration
+    assert_eq!(
+        durations,
+        vec![Some("1.5s".to_string()), Some("1.5s".to_string()), None]
+    );
+}
+
+#[test]
+fn test_logfmt_trace_fields() {
+    let lines: Vec<_> = ["level=info msg=hi trace_id=abc span_id=s1"]
+        .iter()
+        .map(|l| parse_line(l, LogFormat::Logfmt))
+        .collect();
+    let rows = build_trace_rows(&lines, None);
+    assert_eq!(shape(&rows), vec!["trace abc 1", "  span s1", "    0"]);
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(
+        format_duration(chrono::Duration::milliseconds(850)),
+        "850ms"
+    );
+    assert_eq!(
+        format_duration(chrono::Duration::milliseconds(2400)),
+        "2.4s"
+    );
+    assert_eq!(format_duration(chrono::Duration::seconds(192)), "3m12s");
+}
@@ -0,0 +1,220 @@
+use lumolog::app::App;
+use lumolog::replay::screen_text;
+use lumolog::ui::{
+    breadcrumb_at_position, nearest_sparkline_bucket, render, sparkline_bucket_at_position,
+};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier};
+
+fn timed_app() -> App {
+    let lines: Vec<String> = (0..30)
+        .map(|i| format!("2024-01-15T10:{i:02}:00Z INFO job {i}"))
+        .collect();
+    let mut app = App::new(lines);
+    app.set_sparkline_width(10);
+    app
+}
+
+#[test]
+fn test_sparkline_click_maps_to_bucket_under_cursor() {
+    let app = timed_app();
+    let area = Rect::new(0, 0, 80, 24);
+    // Bars start after one column of padding
+    assert_eq!(sparkline_bucket_at_position(&app, 0, 0, area), None);
+    assert_eq!(sparkline_bucket_at_position(&app, 1, 0, area), Some(0));
+    assert_eq!(sparkline_bucket_at_position(&app, 10, 0, area), Some(9));
+    // Past the last bar, or below the sparkline
+    assert_eq!(sparkline_bucket_at_position(&app, 11, 0, area), None);
+    assert_eq!(sparkline_bucket_at_position(&app, 5, 3, area), None);
+}
+
+#[test]
+fn test_sparkline_follows_the_area_it_is_drawn_in() {
+    let app = timed_app();
+    let area = Rect::new(4, 2, 80, 24);
+    assert_eq!(sparkline_bucket_at_position(&app, 1, 0, area), None);
+    assert_eq!(sparkline_bucket_at_position(&app, 5, 2, area), Some(0));
+}
+
+#[test]
+fn test_drag_outside_sparkline_clamps_to_nearest_bucket() {
+    let app = timed_app();
+    let area = Rect::new(4, 2, 80, 24);
+    assert_eq!(nearest_sparkline_bucket(&app, 0, area), Some(0));
+    assert_eq!(nearest_sparkline_bucket(&app, 8, area), Some(3));
+    assert_eq!(nearest_sparkline_bucket(&app, 70, area), Some(9));
+}
+
+#[test]
+fn test_no_sparkline_bucket_when_hidden() {
+    let mut app = timed_app();
+    app.toggle_sparkline();
+    let area = Rect::new(0, 0, 80, 24);
+    assert_eq!(sparkline_bucket_at_position(&app, 1, 0, area), None);
+    assert_eq!(nearest_sparkline_bucket(&app, 1, area), None);
+}
+
+#[test]
+fn test_breadcrumb_click_maps_to_chip() {
+    let mut app = timed_app();
+    let area = Rect::new(0, 0, 80, 24);
+    // No constraints, no breadcrumb row
+    assert_eq!(breadcrumb_at_position(&app, 2, 21, area), None);
+
+    app.set_filter("job".to_string());
+    app.set_level_filter(Some(lumolog::parser::LogLevel::Info));
+    // Above the status bar and the level stats bar: ` 1 /job ✕ ` then ` 2 level≥INF ✕ `
+    let row = 22;
+    assert_eq!(breadcrumb_at_position(&app, 0, row, area), None);
+    assert_eq!(breadcrumb_at_position(&app, 1, row, area), Some(1));
+    assert_eq!(breadcrumb_at_position(&app, 10, row, area), Some(1));
+    assert_eq!(breadcrumb_at_position(&app, 11, row, area), None);
+    assert_eq!(breadcrumb_at_position(&app, 12, row, area), Some(2));
+    assert_eq!(breadcrumb_at_position(&app, 12, row - 1, area), None);
+}
+
+fn draw(app: &mut App) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    terminal.draw(|frame| render(frame, app)).unwrap();
+    terminal.backend().buffer().clone()
+}
+
+#[test]
+fn test_high_contrast_drops_grays_and_dimming() {
+    let mut app = App::new(vec![
+        "ERROR disk failed".to_string(),
+        "DEBUG retrying".to_string(),
+    ]);
+    let cells = |buffer: &Buffer, dark: &dyn Fn(&ratatui::buffer::Cell) -> bool| {
+        buffer.content.iter().filter(|c| dark(c)).count()
+    };
+    let dark =
+        |c: &ratatui::buffer::Cell| c.fg == Color::DarkGray || c.modifier.contains(Modifier::DIM);
+    assert!(cells(&draw(&mut app), &dark) > 0);
+    app.set_high_contrast(true);
+    assert_eq!(cells(&draw(&mut app), &dark), 0);
+}
+
+#[test]
+fn test_text_cues_tag_levels_and_mark_the_cursor() {
+    let mut app = App::new(vec![
+        "ERROR disk failed".to_string(),
+        "plain words".to_string(),
+    ]);
+    app.set_viewport_height(20);
+    let text = screen_text(&draw(&mut app));
+    assert!(text.contains("  1 ERROR disk failed"));
+
+    app.set_text_cues(true);
+    app.enter_cursor_mode();
+    let text = screen_text(&draw(&mut app));
+    assert!(text.contains("  1>ERR ERROR disk failed"), "{text}");
+    assert!(text.contains("  2     plain words"), "{text}");
+}
+
+#[test]
+fn test_joined_entry_shows_count_then_expands_in_place() {
+    let mut app = App::new(vec![
+        "ERROR request failed".to_string(),
+        "    at handler (app.js:10)".to_string(),
+        "    at router (app.js:20)".to_string(),
+        "INFO next".to_string(),
+    ]);
+    app.set_viewport_height(20);
+    let text = screen_text(&draw(&mut app));
+    assert!(
+        text.contains("  1 ERROR request failed  [+2 lines]"),
+        "{text}"
+    );
+    assert!(!text.contains("app.js"), "{text}");
+
+    app.enter_cursor_mode();
+    app.toggle_line_pretty();
+    let text = screen_text(&draw(&mut app));
+    assert!(text.contains("  2     at handler (app.js:10)"), "{text}");
+    assert!(text.contains("  3     at router (app.js:20)"), "{text}");
+    assert!(text.contains("  4 INFO next"), "{text}");
+}
+
+#[test]
+fn test_java_trace_folds_as_frames() {
+    let mut app = App::new(vec![
+        "2024-01-15 08:30:01 ERROR request failed".to_string(),
+        "java.lang.NullPointerException: user was null".to_string(),
+        "at com.app.Service.handle(Service.java:42)".to_string(),
+        "at com.app.Router.route(Router.java:7)".to_string(),
+        "... 12 more".to_string(),
+        "2024-01-15 08:30:02 INFO recovered".to_string(),
+    ]);
+    app.set_viewport_height(20);
+    assert_eq!(app.folded_frames(0), 2);
+    let text = screen_text(&draw(&mut app));
+    assert!(text.contains("ERROR request failed  [+2 frames]"), "{text}");
+    assert!(!text.contains("Service.java"), "{text}");
+}
+
+#[test]
+fn test_status_bar_shows_line_range_and_top_time() {
+    let lines: Vec<String> = (0..2000)
+        .map(|i| {
+            format!(
+                "2024-01-15T08:{:02}:{:02}Z INFO tick {i}",
+                i / 60 % 60,
+                i % 60
+            )
+        })
+        .collect();
+    let mut app = App::new(lines);
+    app.set_viewport_height(20);
+    app.goto_line(1501);
+    let text = screen_text(&draw(&mut app));
+    let (first, last, time) = app.viewport_position().unwrap();
+    assert!(first <= 1501 && 1501 <= last, "{first}..{last}");
+    let time = time.unwrap().format("%H:%M:%S").to_string();
+    let label = format!("lines 1,{:03}–1,{:03} · {time}", first - 1000, last - 1000);
+    assert!(text.contains(&label), "{label}\n{text}");
+}
+
+#[test]
+fn test_blank_line_shows_as_separator_or_literally() {
+    let mut app = App::new(vec![
+        "INFO first".to_string(),
+        "  ".to_string(),
+        "INFO second".to_string(),
+    ]);
+    app.set_viewport_height(20);
+    let text = screen_text(&draw(&mut app));
+    assert!(text.contains("2 ─────"), "{text}");
+
+    app.cycle_blank_lines();
+    let text = screen_text(&draw(&mut app));
+    assert!(text.contains("│  1 INFO first"), "{text}");
+    assert!(!text.contains("│  2"), "{text}");
+
+    app.cycle_blank_lines();
+    let text = screen_text(&draw(&mut app));
+    assert!(text.contains("│  2    "), "{text}");
+    assert!(!text.contains("2 ─────"), "{text}");
+}
+
+#[test]
+fn test_event_list_scrolls_to_keep_selection_in_view() {
+    let lines: Vec<String> = (1..=40)
+        .map(|i| format!("INFO server started worker{i:02}"))
+        .collect();
+    let mut app = App::new(lines);
+    app.open_event_list();
+    for _ in 0..39 {
+        app.event_list_down();
+    }
+    let text = screen_text(&draw(&mut app));
+    assert!(text.contains("Events 40/40"), "{text}");
+    assert!(
+        text.contains("│ 40  restart  INFO server started worker40"),
+        "{text}"
+    );
+    assert!(!text.contains("│  1  restart"), "{text}");
+}
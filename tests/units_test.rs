@@ -0,0 +1,52 @@
+use lumolog::units::{Dimension, Quantity, format_quantity, parse_quantity};
+
+#[test]
+fn test_durations_normalize_to_millis() {
+    let q = parse_quantity("2.3s").unwrap();
+    assert_eq!(q.dimension, Dimension::Duration);
+    assert!((q.value - 2300.0).abs() < 1e-9);
+    assert_eq!(parse_quantity("1500us").unwrap().value, 1.5);
+    assert_eq!(parse_quantity("2m").unwrap().value, 120_000.0);
+}
+
+#[test]
+fn test_sizes_normalize_to_bytes() {
+    let q = parse_quantity("1.5MB").unwrap();
+    assert_eq!(q.dimension, Dimension::Size);
+    assert_eq!(q.value, 1_500_000.0);
+    assert_eq!(parse_quantity("2KiB").unwrap().value, 2048.0);
+    assert_eq!(parse_quantity("512b").unwrap().value, 512.0);
+}
+
+#[test]
+fn test_bare_and_quoted_numbers() {
+    let q = parse_quantity("\"42\"").unwrap();
+    assert_eq!(q.dimension, Dimension::Scalar);
+    assert_eq!(q.value, 42.0);
+    assert_eq!(parse_quantity("-3.5").unwrap().value, -3.5);
+}
+
+#[test]
+fn test_unknown_suffix_does_not_parse() {
+    assert!(parse_quantity("500apples").is_none());
+    assert!(parse_quantity("fast").is_none());
+    assert!(parse_quantity("").is_none());
+}
+
+#[test]
+fn test_format_quantity_picks_readable_unit() {
+    let fmt = |text: &str| format_quantity(parse_quantity(text).unwrap());
+    assert_eq!(fmt("2300ms"), "2.3s");
+    assert_eq!(fmt("850ms"), "850ms");
+    assert_eq!(fmt("1500us"), "1.5ms");
+    assert_eq!(fmt("90s"), "1.5min");
+    assert_eq!(fmt("1536KB"), "1.5MB");
+    assert_eq!(fmt("512"), "512");
+    assert_eq!(
+        format_quantity(Quantity {
+            value: 0.0,
+            dimension: Dimension::Duration
+        }),
+        "0ns"
+    );
+}
@@ -0,0 +1,91 @@
+use chrono::NaiveDate;
+use lumolog::config::PaneLayout;
+use lumolog::parser::LogLevel;
+use lumolog::timeindex::TimeRange;
+use lumolog::viewstate::{ExitState, ViewState};
+use std::path::PathBuf;
+
+fn sample_state() -> ViewState {
+    let day = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+    ViewState {
+        file: Some(PathBuf::from("/var/log/api.log")),
+        filter: "timeout".to_string(),
+        filters: vec!["api | worker".to_string()],
+        min_level: Some(LogLevel::Warn),
+        hidden_levels: vec![LogLevel::Error],
+        time_range: Some(TimeRange {
+            start: day.and_hms_opt(10, 0, 0).unwrap(),
+            end: day.and_hms_opt(10, 15, 0).unwrap(),
+        }),
+        layout: Some(PaneLayout {
+            stats: false,
+            pinned_rows: 2,
+            ..PaneLayout::default()
+        }),
+    }
+}
+
+#[test]
+fn test_view_state_round_trips_through_json() {
+    let state = sample_state();
+    let json = state.to_json().unwrap();
+    assert!(json.contains("\"WARN\""), "{json}");
+    assert_eq!(ViewState::from_json(&json).unwrap(), state);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_view_state_with_non_utf8_path_is_an_error() {
+    use std::os::unix::ffi::OsStrExt;
+    let state = ViewState {
+        file: Some(std::ffi::OsStr::from_bytes(b"/tmp/app-\xff.log").into()),
+        ..ViewState::default()
+    };
+    assert!(state.to_json().is_err());
+    let dir = tempfile::tempdir().unwrap();
+    assert!(state.save(&dir.path().join("view.json")).is_err());
+}
+
+#[test]
+fn test_view_state_fields_are_optional() {
+    let state = ViewState::from_json(r#"{"filter": "oops"}"#).unwrap();
+    assert_eq!(state.filter, "oops");
+    assert_eq!(state.file, None);
+    assert!(state.filters.is_empty());
+    assert_eq!(state.min_level, None);
+    assert!(state.hidden_levels.is_empty());
+    assert_eq!(state.layout, None);
+}
+
+#[test]
+fn test_view_state_rejects_unknown_level() {
+    assert!(ViewState::from_json(r#"{"min_level": "LOUD"}"#).is_err());
+}
+
+#[test]
+fn test_view_state_save_and_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("view.json");
+    let state = sample_state();
+    state.save(&path).unwrap();
+    assert_eq!(ViewState::load(&path).unwrap(), state);
+    assert!(ViewState::load(&dir.path().join("missing.json")).is_err());
+}
+
+#[test]
+fn test_exit_state_json_is_flat() {
+    let state = ExitState {
+        view: sample_state(),
+        cursor_line: Some(120),
+        mark: None,
+        pins: vec![4, 9],
+        matches: 37,
+    };
+    let value: serde_json::Value = serde_json::from_str(&state.to_json().unwrap()).unwrap();
+    assert_eq!(value["filter"], "timeout");
+    assert_eq!(value["time_range"]["start"], "2024-01-15T10:00:00");
+    assert_eq!(value["cursor_line"], 120);
+    assert!(value["mark"].is_null());
+    assert_eq!(value["pins"], serde_json::json!([4, 9]));
+    assert_eq!(value["matches"], 37);
+}